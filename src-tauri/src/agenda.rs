@@ -0,0 +1,126 @@
+// 保管庫全体のタスクの予定表（期日のあるタスクを日付順に並べる）
+
+use std::fs;
+use std::path::Path;
+
+use chrono::{Duration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::dates;
+use crate::markdown;
+use crate::tr;
+use crate::vault::{path_string, Vault};
+
+/// 範囲を省略したときの日数（今日から）
+const DEFAULT_DAYS: i64 = 7;
+
+/// 予定表の範囲
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct AgendaRange {
+    /// 最初の日（`YYYY-MM-DD` か `next monday` などの言い方。省略時は今日）
+    pub from: Option<String>,
+    /// 最後の日（省略時は最初の日から 7 日後）
+    pub to: Option<String>,
+    /// 期日を過ぎた未完了のタスクも含める
+    pub include_overdue: bool,
+    /// 完了したタスクも含める
+    pub include_done: bool,
+}
+
+impl Default for AgendaRange {
+    fn default() -> Self {
+        Self {
+            from: None,
+            to: None,
+            include_overdue: true,
+            include_done: false,
+        }
+    }
+}
+
+/// 予定表の 1 項目
+#[derive(Debug, Serialize)]
+pub struct AgendaItem {
+    pub path: String,
+    /// 行番号（1 始まり）
+    pub line: usize,
+    pub text: String,
+    pub checked: bool,
+    /// 期日（`YYYY-MM-DD`）
+    pub due: String,
+    /// 今日からの日数（過ぎていれば負）
+    pub days: i64,
+    /// 期日を過ぎた未完了のタスク
+    pub overdue: bool,
+}
+
+/// `get_agenda` の結果
+#[derive(Debug, Serialize)]
+pub struct Agenda {
+    pub today: String,
+    pub from: String,
+    pub to: String,
+    /// 期日・ファイル・行の順
+    pub items: Vec<AgendaItem>,
+    pub overdue: usize,
+}
+
+fn range_date(text: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    dates::natural_date(text, today).ok_or_else(|| tr!("Could not understand date: {}", text))
+}
+
+/// 保管庫のノートから期日のあるタスク（`📅 2024-07-01` `(due: friday)` など）を集め、日付順に並べる
+///
+/// 範囲の中に期日があるタスクと、期日を過ぎた未完了のタスク（`include_overdue`）を返す。
+#[tauri::command(async)]
+pub fn get_agenda(root: String, range: Option<AgendaRange>) -> Result<Agenda, String> {
+    let range = range.unwrap_or_default();
+    let today = Local::now().date_naive();
+    let from = match range.from.as_deref() {
+        Some(from) => range_date(from, today)?,
+        None => today,
+    };
+    let to = match range.to.as_deref() {
+        Some(to) => range_date(to, today)?,
+        None => from + Duration::days(DEFAULT_DAYS),
+    };
+
+    let vault = Vault::scan(Path::new(&root));
+    let mut items = Vec::new();
+    for doc in vault.markdown_files() {
+        let content =
+            fs::read_to_string(doc).map_err(|e| tr!("Failed to read {}: {e}", doc.display()))?;
+        for task in markdown::tasks(&content) {
+            let Some(due) = task.due else {
+                continue;
+            };
+            let overdue = !task.checked && due < today;
+            let in_range = from <= due && due <= to;
+            if !(in_range || overdue && range.include_overdue)
+                || task.checked && !range.include_done
+            {
+                continue;
+            }
+            items.push(AgendaItem {
+                path: path_string(doc),
+                line: task.line,
+                text: task.text,
+                checked: task.checked,
+                due: due.format("%Y-%m-%d").to_string(),
+                days: (due - today).num_days(),
+                overdue,
+            });
+        }
+    }
+    // パスは走査順（パス順）なので、安定ソートで期日順にする
+    items.sort_by(|a, b| a.due.cmp(&b.due));
+    let format = |date: NaiveDate| date.format("%Y-%m-%d").to_string();
+    Ok(Agenda {
+        today: format(today),
+        from: format(from),
+        to: format(to),
+        overdue: items.iter().filter(|i| i.overdue).count(),
+        items,
+    })
+}
@@ -0,0 +1,285 @@
+// AI アシスタント（OpenAI 互換 API・Ollama のチャットを呼び、応答をイベントで逐次送る）
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_http::reqwest;
+
+use crate::keychain;
+use crate::semantic;
+use crate::state::AppState;
+use crate::tr;
+
+/// チャットの呼び出し先
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AiProvider {
+    OpenAi {
+        #[serde(default = "semantic::default_openai_url")]
+        url: String,
+        model: String,
+        /// キーチェーンに保存した API キーの名前（`set_api_key` で登録する）
+        #[serde(default)]
+        api_key_name: Option<String>,
+    },
+    Ollama {
+        #[serde(default = "semantic::default_ollama_url")]
+        url: String,
+        model: String,
+    },
+}
+
+/// `run_ai_action` の設定
+#[derive(Debug, Clone, Deserialize)]
+pub struct AiConfig {
+    pub provider: AiProvider,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// 翻訳先の言語（`translate` のみ。既定は英語）
+    #[serde(default)]
+    pub language: Option<String>,
+    /// 追加の指示
+    #[serde(default)]
+    pub instruction: Option<String>,
+}
+
+/// AI に頼む操作
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AiAction {
+    Summarize,
+    Rewrite,
+    Translate,
+}
+
+/// 実行中の要求（番号ごとの中断フラグ）
+#[derive(Default)]
+pub struct AiState {
+    next_id: AtomicU64,
+    running: Mutex<HashMap<u64, Arc<AtomicBool>>>,
+}
+
+/// `ai-chunk` イベントのペイロード（応答の断片）
+#[derive(Debug, Clone, Serialize)]
+pub struct AiChunk {
+    pub id: u64,
+    pub text: String,
+}
+
+/// `ai-finished` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct AiFinished {
+    pub id: u64,
+    /// 応答全体（中断・失敗時はそれまでに受け取った分）
+    pub text: String,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+fn system_prompt(action: AiAction, config: &AiConfig) -> String {
+    let mut prompt = match action {
+        AiAction::Summarize => "Summarize the following Markdown text concisely in the same language as the text. Reply with Markdown only.".to_string(),
+        AiAction::Rewrite => "Rewrite the following Markdown text to be clearer and easier to read, keeping its meaning, language and Markdown structure. Reply with the rewritten text only.".to_string(),
+        AiAction::Translate => format!(
+            "Translate the following Markdown text into {}. Keep the Markdown structure, code blocks, links and URLs unchanged. Reply with the translation only.",
+            config.language.as_deref().unwrap_or("English")
+        ),
+    };
+    if let Some(instruction) = config
+        .instruction
+        .as_deref()
+        .filter(|i| !i.trim().is_empty())
+    {
+        prompt.push(' ');
+        prompt.push_str(instruction.trim());
+    }
+    prompt
+}
+
+/// 応答の 1 行から本文の断片を取り出す（`None` は終了）
+fn parse_line(provider: &AiProvider, line: &str) -> Result<Option<String>, String> {
+    let line = line.trim();
+    let json = match provider {
+        AiProvider::OpenAi { .. } => match line.strip_prefix("data:").map(str::trim) {
+            Some("[DONE]") => return Ok(None),
+            Some(data) => data,
+            None => return Ok(Some(String::new())),
+        },
+        AiProvider::Ollama { .. } => line,
+    };
+    if json.is_empty() {
+        return Ok(Some(String::new()));
+    }
+    let value: Value =
+        serde_json::from_str(json).map_err(|e| tr!("Invalid response from AI server: {e}"))?;
+    if let Some(error) = value.get("error") {
+        let message = error["message"].as_str().or(error.as_str()).unwrap_or("");
+        return Err(tr!("AI server returned an error: {message}"));
+    }
+    let text = match provider {
+        AiProvider::OpenAi { .. } => value["choices"][0]["delta"]["content"].as_str(),
+        AiProvider::Ollama { .. } => value["message"]["content"].as_str(),
+    };
+    Ok(Some(text.unwrap_or_default().to_string()))
+}
+
+/// チャットを呼び、断片を受け取るたびに `on_chunk` を呼ぶ（戻り値は応答全体）
+async fn stream_chat(
+    config: &AiConfig,
+    api_key: Option<&str>,
+    messages: Value,
+    cancel: &AtomicBool,
+    mut on_chunk: impl FnMut(&str),
+) -> Result<String, String> {
+    let (url, body) = match &config.provider {
+        AiProvider::OpenAi { url, model, .. } => {
+            let mut body = json!({ "model": model, "messages": messages, "stream": true });
+            if let Some(t) = config.temperature {
+                body["temperature"] = json!(t);
+            }
+            (
+                format!("{}/chat/completions", url.trim_end_matches('/')),
+                body,
+            )
+        }
+        AiProvider::Ollama { url, model } => {
+            let mut body = json!({ "model": model, "messages": messages, "stream": true });
+            if let Some(t) = config.temperature {
+                body["options"] = json!({ "temperature": t });
+            }
+            (format!("{}/api/chat", url.trim_end_matches('/')), body)
+        }
+    };
+    let mut request = reqwest::Client::new()
+        .post(&url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string());
+    if let Some(key) = api_key {
+        request = request.bearer_auth(key);
+    }
+    let mut response = request
+        .send()
+        .await
+        .map_err(|e| tr!("Failed to connect to {url}: {e}"))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(tr!("AI request failed ({status}): {text}"));
+    }
+
+    let mut text = String::new();
+    let mut pending: Vec<u8> = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| tr!("Failed to read response from {url}: {e}"))?
+    {
+        if cancel.load(Ordering::SeqCst) {
+            break;
+        }
+        pending.extend_from_slice(&chunk);
+        // 行単位（SSE・NDJSON）で処理し、途中の行は次の断片まで残す
+        while let Some(newline) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=newline).collect();
+            match parse_line(&config.provider, &String::from_utf8_lossy(&line))? {
+                Some(piece) if !piece.is_empty() => {
+                    text.push_str(&piece);
+                    on_chunk(&piece);
+                }
+                Some(_) => {}
+                None => return Ok(text),
+            }
+        }
+    }
+    if let Some(Some(piece)) = parse_line(&config.provider, &String::from_utf8_lossy(&pending))
+        .ok()
+        .filter(|_| !cancel.load(Ordering::SeqCst))
+    {
+        text.push_str(&piece);
+        on_chunk(&piece);
+    }
+    Ok(text)
+}
+
+/// 設定の API キーをキーチェーンから読む
+fn api_key(provider: &AiProvider) -> Result<Option<String>, String> {
+    match provider {
+        AiProvider::OpenAi {
+            api_key_name: Some(name),
+            ..
+        } => keychain::get_secret(name)?
+            .map(Some)
+            .ok_or_else(|| tr!("API key \"{name}\" is not set")),
+        _ => Ok(None),
+    }
+}
+
+/// テキストの要約・書き直し・翻訳を開始し、要求の番号を返す
+/// （応答は `ai-chunk` で逐次、`ai-finished` でまとめて送る）
+#[tauri::command]
+pub fn run_ai_action(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    action: AiAction,
+    text: String,
+    config: AiConfig,
+) -> Result<u64, String> {
+    let api_key = api_key(&config.provider)?;
+    let id = state.ai.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+    let cancel = Arc::new(AtomicBool::new(false));
+    state.ai.running.lock().unwrap().insert(id, cancel.clone());
+
+    let messages = json!([
+        { "role": "system", "content": system_prompt(action, &config) },
+        { "role": "user", "content": text },
+    ]);
+    tauri::async_runtime::spawn(async move {
+        let mut received = String::new();
+        let result = stream_chat(&config, api_key.as_deref(), messages, &cancel, |piece| {
+            received.push_str(piece);
+            let _ = app.emit(
+                "ai-chunk",
+                AiChunk {
+                    id,
+                    text: piece.to_string(),
+                },
+            );
+        })
+        .await;
+        app.state::<AppState>()
+            .ai
+            .running
+            .lock()
+            .unwrap()
+            .remove(&id);
+        let cancelled = cancel.load(Ordering::SeqCst);
+        let payload = match result {
+            Ok(text) => AiFinished {
+                id,
+                text,
+                cancelled,
+                error: None,
+            },
+            Err(e) => AiFinished {
+                id,
+                text: received,
+                cancelled,
+                error: Some(e),
+            },
+        };
+        let _ = app.emit("ai-finished", payload);
+    });
+    Ok(id)
+}
+
+/// 実行中の要求を中断
+#[tauri::command]
+pub fn cancel_ai_action(state: State<'_, AppState>, id: u64) {
+    if let Some(cancel) = state.ai.running.lock().unwrap().get(&id) {
+        cancel.store(true, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,376 @@
+// 注釈（コメントと修正の提案）。本文は書き換えず、文書と同じフォルダの
+// `.<ファイル名>.mdvim-annotations.json` に保存する
+//
+// 注釈は範囲とその本文（`quote`）を持ち、文書が変わっても本文を探して位置を求め直す。
+// プレビューと書き出しでは、範囲の行末に番号を付け、末尾に注釈の一覧を入れる。
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::lsp::LspRange;
+use crate::markdown;
+use crate::storage;
+use crate::text::{self, escape_html, LineIndex};
+use crate::tr;
+use crate::vault;
+
+/// 注釈のファイル名の末尾
+const SIDECAR_SUFFIX: &str = ".mdvim-annotations.json";
+
+/// 本文に埋める番号の印（私用領域の文字なので見出しのアンカーには残らない）
+const MARK_START: char = '\u{E000}';
+const MARK_END: char = '\u{E001}';
+/// 番号の数字 0〜9 を表す文字の先頭
+const MARK_DIGIT: u32 = 0xE010;
+
+/// 書き出した HTML の注釈のスタイル
+pub const ANNOTATION_CSS: &str = r#"sup.annotation-ref a { text-decoration: none; font-weight: 600; }
+section.annotations { margin-top: 2em; border-top: 1px solid var(--border); }
+section.annotations li { margin: 0.8em 0; }
+.annotation-meta { color: var(--text-secondary); font-size: 0.9em; margin: 0; }
+.annotation del { background: rgba(248, 81, 73, 0.25); }
+.annotation ins { background: rgba(46, 160, 67, 0.25); text-decoration: none; }
+"#;
+
+/// 注釈の種類
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationKind {
+    #[default]
+    Comment,
+    /// 範囲を `replacement` に置き換える提案
+    Suggestion,
+}
+
+/// 注釈
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Annotation {
+    pub id: String,
+    #[serde(default)]
+    pub kind: AnnotationKind,
+    pub range: LspRange,
+    /// 範囲の本文（文書が変わったときに位置を求め直す）
+    #[serde(default)]
+    pub quote: String,
+    #[serde(default)]
+    pub author: String,
+    /// 作成日時（UNIX ミリ秒）
+    pub created: u64,
+    #[serde(default)]
+    pub text: String,
+    /// 提案する置き換え後の本文
+    #[serde(default)]
+    pub replacement: Option<String>,
+    #[serde(default)]
+    pub resolved: bool,
+    /// 解決した日時（UNIX ミリ秒）
+    #[serde(default)]
+    pub resolved_at: Option<u64>,
+    /// 範囲の本文が見つからない（一覧を取るときに確かめる）
+    #[serde(default)]
+    pub orphaned: bool,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct AnnotationFile {
+    annotations: Vec<Annotation>,
+}
+
+/// 文書の注釈のファイル
+pub fn sidecar(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}{SIDECAR_SUFFIX}"))
+}
+
+pub fn load(path: &Path) -> Vec<Annotation> {
+    storage::read_json::<AnnotationFile>(&sidecar(path))
+        .map(|file| file.annotations)
+        .unwrap_or_default()
+}
+
+/// 保存する（注釈がなくなればファイルを消す）
+fn store(path: &Path, annotations: Vec<Annotation>) -> Result<(), String> {
+    let file = sidecar(path);
+    if annotations.is_empty() {
+        if file.exists() {
+            fs::remove_file(&file).map_err(|e| tr!("Failed to write {}: {e}", file.display()))?;
+        }
+        return Ok(());
+    }
+    storage::write_json(&file, &AnnotationFile { annotations }, true)
+}
+
+/// 文書の移動に合わせて注釈のファイルも移す
+pub fn move_sidecar(old_path: &Path, new_path: &Path) -> Result<(), String> {
+    let (from, to) = (sidecar(old_path), sidecar(new_path));
+    if !from.is_file() || to.exists() {
+        return Ok(());
+    }
+    fs::rename(&from, &to)
+        .map_err(|e| tr!("Failed to move {} to {}: {e}", from.display(), to.display()))
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// 範囲の本文から今の位置を求め直す（元の位置に最も近いものを選ぶ）
+fn locate(content: &str, index: &LineIndex, annotation: &mut Annotation) {
+    let range = annotation.range.to_offsets(content);
+    if annotation.quote.is_empty() || content.get(range.clone()) == Some(&annotation.quote) {
+        annotation.orphaned = false;
+        return;
+    }
+    let found = content
+        .match_indices(annotation.quote.as_str())
+        .map(|(i, _)| i)
+        .min_by_key(|i| i.abs_diff(range.start));
+    match found {
+        Some(start) => {
+            let end = start + annotation.quote.len();
+            annotation.range = LspRange::from_offsets(index, content, start..end);
+            annotation.orphaned = false;
+        }
+        None => annotation.orphaned = true,
+    }
+}
+
+/// 内容に合わせて位置を求め直した注釈（作成した順）
+pub fn located(path: &Path, content: &str) -> Vec<Annotation> {
+    let index = LineIndex::new(content);
+    let mut annotations = load(path);
+    annotations
+        .iter_mut()
+        .for_each(|a| locate(content, &index, a));
+    annotations
+}
+
+fn mark(n: usize) -> String {
+    let digits: String = n
+        .to_string()
+        .chars()
+        .filter_map(|d| char::from_u32(MARK_DIGIT + d.to_digit(10)?))
+        .collect();
+    format!("{MARK_START}{digits}{MARK_END}")
+}
+
+fn format_time(millis: u64) -> (String, String) {
+    let time: DateTime<Local> =
+        (SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(millis)).into();
+    (time.to_rfc3339(), time.format("%Y-%m-%d %H:%M").to_string())
+}
+
+/// 描画に使う注釈（本文に番号の印を埋め、描画後に番号と一覧に置き換える）
+pub struct Rendering {
+    /// 番号の印を埋めた Markdown
+    pub content: String,
+    /// 印ごとの HTML（番号の順）
+    markers: Vec<String>,
+    /// 末尾に入れる一覧の HTML
+    pub list: String,
+}
+
+impl Rendering {
+    /// 解決していない注釈を描画の準備をする（注釈がなければ `None`）
+    pub fn prepare(content: &str, annotations: &[Annotation]) -> Option<Self> {
+        let open: Vec<&Annotation> = annotations
+            .iter()
+            .filter(|a| !a.resolved && !a.orphaned)
+            .collect();
+        if open.is_empty() {
+            return None;
+        }
+        let body_start = markdown::body_start(content);
+        let code = markdown::code_ranges(content);
+        let mut edits = Vec::new();
+        let mut markers = Vec::new();
+        let mut list = format!(
+            "<section class=\"annotations\">\n<h2>{}</h2>\n<ol>\n",
+            escape_html(&tr!("Annotations"))
+        );
+        for (i, annotation) in open.iter().enumerate() {
+            let n = i + 1;
+            let end = annotation.range.to_offsets(content).end;
+            // 範囲の終わりの行末（末尾の空白と表の `|` の前）に番号を付ける
+            let line_end = content[end..].find('\n').map_or(content.len(), |i| end + i);
+            let line_start = content[..line_end].rfind('\n').map_or(0, |i| i + 1);
+            let line = content[line_start..line_end].trim_end();
+            let line = line.strip_suffix('|').unwrap_or(line).trim_end();
+            let at = line_start + line.len();
+            // フロントマターとコードブロックの中には付けず、一覧にだけ載せる
+            if at >= body_start && !markdown::in_ranges(&code, at) {
+                edits.push((at..at, mark(n)));
+            }
+            let title = match &annotation.replacement {
+                Some(replacement) => format!("{} → {replacement}", annotation.quote),
+                None => annotation.text.clone(),
+            };
+            markers.push(format!(
+                "<sup class=\"annotation-ref\"><a href=\"#annotation-{n}\" title=\"{}\">{n}</a></sup>",
+                escape_html(&format!("{}: {title}", annotation.author))
+            ));
+
+            let (datetime, shown) = format_time(annotation.created);
+            let kind = match annotation.kind {
+                AnnotationKind::Comment => "comment",
+                AnnotationKind::Suggestion => "suggestion",
+            };
+            list.push_str(&format!(
+                "<li id=\"annotation-{n}\" class=\"annotation annotation-{kind}\" data-annotation-id=\"{}\">\n<p class=\"annotation-meta\"><strong>{}</strong> <time datetime=\"{datetime}\">{shown}</time></p>\n",
+                escape_html(&annotation.id),
+                escape_html(&annotation.author)
+            ));
+            match &annotation.replacement {
+                Some(replacement) => list.push_str(&format!(
+                    "<p><del>{}</del> → <ins>{}</ins></p>\n",
+                    escape_html(&annotation.quote),
+                    escape_html(replacement)
+                )),
+                None if !annotation.quote.is_empty() => list.push_str(&format!(
+                    "<blockquote>{}</blockquote>\n",
+                    escape_html(&annotation.quote)
+                )),
+                None => {}
+            }
+            if !annotation.text.trim().is_empty() {
+                list.push_str(&format!(
+                    "<p>{}</p>\n",
+                    escape_html(annotation.text.trim()).replace('\n', "<br>")
+                ));
+            }
+            list.push_str("</li>\n");
+        }
+        list.push_str("</ol>\n</section>\n");
+        Some(Self {
+            content: text::apply_edits(content, edits),
+            markers,
+            list,
+        })
+    }
+
+    /// 描画した HTML の印を番号に置き換える（一覧は含めない）
+    pub fn replace_markers(&self, html: &str) -> String {
+        let mut html = html.to_string();
+        for (i, marker) in self.markers.iter().enumerate() {
+            html = html.replace(&mark(i + 1), marker);
+        }
+        html
+    }
+}
+
+/// 注釈を追加する（`replacement` を指定すると修正の提案）
+///
+/// `content` は編集中の内容（省略時はファイルを読む）。`author` を省くと OS のユーザー名を使う。
+#[tauri::command]
+pub fn add_annotation(
+    path: String,
+    range: LspRange,
+    text: String,
+    content: Option<String>,
+    author: Option<String>,
+    replacement: Option<String>,
+) -> Result<Annotation, String> {
+    let path = vault::normalize(Path::new(&path));
+    let content = match content {
+        Some(content) => content,
+        None => {
+            fs::read_to_string(&path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?
+        }
+    };
+    let offsets = range.to_offsets(&content);
+    let author = author
+        .filter(|a| !a.trim().is_empty())
+        .or_else(|| env::var("USER").ok())
+        .or_else(|| env::var("USERNAME").ok())
+        .unwrap_or_default();
+    let mut annotations = load(&path);
+    let created = now_millis();
+    let mut id = created;
+    while annotations.iter().any(|a| a.id == format!("{id:x}")) {
+        id += 1;
+    }
+    let annotation = Annotation {
+        id: format!("{id:x}"),
+        kind: if replacement.is_some() {
+            AnnotationKind::Suggestion
+        } else {
+            AnnotationKind::Comment
+        },
+        range: LspRange::from_offsets(&LineIndex::new(&content), &content, offsets.clone()),
+        quote: content[offsets].to_string(),
+        author: author.trim().to_string(),
+        created,
+        text,
+        replacement,
+        resolved: false,
+        resolved_at: None,
+        orphaned: false,
+    };
+    annotations.push(annotation.clone());
+    store(&path, annotations)?;
+    Ok(annotation)
+}
+
+/// 注釈を解決済みにする（`resolved` が `false` なら未解決に戻す）
+#[tauri::command]
+pub fn resolve_annotation(
+    path: String,
+    id: String,
+    resolved: Option<bool>,
+) -> Result<Annotation, String> {
+    let path = vault::normalize(Path::new(&path));
+    let mut annotations = load(&path);
+    let annotation = annotations
+        .iter_mut()
+        .find(|a| a.id == id)
+        .ok_or_else(|| tr!("Annotation not found: {id}"))?;
+    annotation.resolved = resolved.unwrap_or(true);
+    annotation.resolved_at = annotation.resolved.then(now_millis);
+    let annotation = annotation.clone();
+    store(&path, annotations)?;
+    Ok(annotation)
+}
+
+/// 注釈を削除する
+#[tauri::command]
+pub fn remove_annotation(path: String, id: String) -> Result<(), String> {
+    let path = vault::normalize(Path::new(&path));
+    let mut annotations = load(&path);
+    let before = annotations.len();
+    annotations.retain(|a| a.id != id);
+    if annotations.len() == before {
+        return Err(tr!("Annotation not found: {id}"));
+    }
+    store(&path, annotations)
+}
+
+/// 文書の注釈の一覧（作成した順。位置は `content` か保存した内容に合わせて求め直す）
+#[tauri::command]
+pub fn list_annotations(
+    path: String,
+    content: Option<String>,
+    include_resolved: Option<bool>,
+) -> Result<Vec<Annotation>, String> {
+    let path = vault::normalize(Path::new(&path));
+    let content = match content {
+        Some(content) => content,
+        None => {
+            fs::read_to_string(&path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?
+        }
+    };
+    let mut annotations = located(&path, &content);
+    if !include_resolved.unwrap_or(false) {
+        annotations.retain(|a| !a.resolved);
+    }
+    Ok(annotations)
+}
@@ -0,0 +1,38 @@
+// アプリの設定フォルダ・データフォルダに置く JSON ファイルの読み書き（書き込みと復旧は `storage`）
+
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::storage;
+use crate::tr;
+
+/// 設定フォルダ内のファイル（利用者が選んだ設定）
+pub fn config_file(app: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| tr!("Failed to locate config directory: {e}"))?;
+    Ok(dir.join(name))
+}
+
+/// データフォルダ内のファイル（しおりや履歴など、使ううちに増えるもの）
+pub fn data_file(app: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| tr!("Failed to locate data directory: {e}"))?;
+    Ok(dir.join(name))
+}
+
+/// JSON ファイルを読む（なければ既定値。壊れていれば控えから戻す）
+pub fn read_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    Ok(storage::read_json(path).unwrap_or_default())
+}
+
+/// JSON ファイルに書く（フォルダがなければ作る。途中で止まっても前の内容が残る）
+pub fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    storage::write_json(path, value, true)
+}
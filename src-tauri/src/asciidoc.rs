@@ -0,0 +1,409 @@
+// AsciiDoc 文書を Markdown に変換（見出し・リスト・ブロック・表・リンクなど主要な構造のみ）
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+use crate::table;
+use crate::tr;
+
+static HEADING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(={1,6})\s+(.+?)\s*=*\s*$").unwrap());
+static ATTRIBUTE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^:(!?[\w-]+!?):\s*(.*)$").unwrap());
+static BLOCK_ATTRIBUTES: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\[([^\]]*)\]$").unwrap());
+static LIST_ITEM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\*{1,5}|-|\.{1,5}|\d+\.)\s+(?:\[([ xX*])\]\s+)?(.*)$").unwrap()
+});
+static DESCRIPTION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\S.*?)(:{2,4}|;;)(?:\s+(.*))?$").unwrap());
+static ADMONITION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(NOTE|TIP|IMPORTANT|WARNING|CAUTION):\s+(.*)$").unwrap());
+static BLOCK_IMAGE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^image::([^\[\s]+)\[([^\]]*)\]\s*$").unwrap());
+static INCLUDE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^include::([^\[\s]+)\[[^\]]*\]\s*$").unwrap());
+static CODE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"`\+?([^`]+?)\+?`").unwrap());
+static INLINE_IMAGE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"image:([^\[\s:][^\[\s]*)\[([^\]]*)\]").unwrap());
+static URL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:link:)?((?:https?|ftp|mailto):[^\s\[]+|link:[^\s\[]+)\[([^\]]*)\]").unwrap()
+});
+static XREF: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"<<([^,>]+)(?:,\s*([^>]+))?>>|xref:([^\[\s]+)\[([^\]]*)\]").unwrap()
+});
+static FOOTNOTE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"footnote:(?:[\w-]+)?\[([^\]]*)\]").unwrap());
+static STRONG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(^|[\s(\[{'"])\*([^\s*](?:[^*]*?[^\s*])?)\*($|[\s.,;:!?)\]}'"-])"#).unwrap()
+});
+static EMPHASIS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(^|[\s(\[{'"])_([^\s_](?:[^_]*?[^\s_])?)_($|[\s.,;:!?)\]}'"-])"#).unwrap()
+});
+static HIGHLIGHT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(^|[\s(\[{'"])#([^\s#](?:[^#]*?[^\s#])?)#($|[\s.,;:!?)\]}'"-])"#).unwrap()
+});
+static ATTRIBUTE_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{([\w-]+)\}").unwrap());
+
+/// 区切りブロックの種類（区切り行の文字）
+fn delimiter_kind(line: &str) -> Option<char> {
+    let c = line.chars().next()?;
+    (line.len() >= 4 && "-.=_*+/".contains(c) && line.chars().all(|ch| ch == c)).then_some(c)
+}
+
+/// `.adoc` へのリンクを `.md` にする
+fn to_markdown_target(target: &str) -> String {
+    let (path, anchor) = target
+        .split_once('#')
+        .map_or((target, None), |(p, a)| (p, Some(a)));
+    let path = match path.strip_suffix(".adoc") {
+        Some(stem) if !path.contains("://") => format!("{stem}.md"),
+        _ => path.to_string(),
+    };
+    match anchor {
+        Some(anchor) => format!("{path}#{anchor}"),
+        None => path,
+    }
+}
+
+struct Converter {
+    attributes: HashMap<String, String>,
+    footnotes: Vec<String>,
+}
+
+impl Converter {
+    fn substitute(&self, text: &str) -> String {
+        ATTRIBUTE_REFERENCE
+            .replace_all(text, |c: &Captures| {
+                self.attributes
+                    .get(&c[1])
+                    .cloned()
+                    .unwrap_or_else(|| c[0].to_string())
+            })
+            .into_owned()
+    }
+
+    /// 行内の記法を変換（コードの中は変換しない）
+    fn inline(&mut self, text: &str) -> String {
+        let text = self.substitute(text);
+        let mut out = String::new();
+        let mut last = 0;
+        for caps in CODE.captures_iter(&text) {
+            let whole = caps.get(0).unwrap();
+            out.push_str(&self.inline_segment(&text[last..whole.start()]));
+            out.push_str(&format!("`{}`", &caps[1]));
+            last = whole.end();
+        }
+        out.push_str(&self.inline_segment(&text[last..]));
+        out
+    }
+
+    fn inline_segment(&mut self, text: &str) -> String {
+        let text = FOOTNOTE.replace_all(text, |c: &Captures| {
+            self.footnotes.push(c[1].to_string());
+            format!("[^{}]", self.footnotes.len())
+        });
+        let text = INLINE_IMAGE.replace_all(&text, "![$2]($1)");
+        let text = URL.replace_all(&text, |c: &Captures| {
+            let target = c[1].strip_prefix("link:").unwrap_or(&c[1]);
+            let label = if c[2].is_empty() { target } else { &c[2] };
+            format!("[{label}]({})", to_markdown_target(target))
+        });
+        let text = XREF.replace_all(&text, |c: &Captures| match c.get(1) {
+            Some(id) => {
+                let label = c.get(2).map_or(id.as_str(), |m| m.as_str());
+                if id.as_str().contains('#') || id.as_str().ends_with(".adoc") {
+                    format!("[{label}]({})", to_markdown_target(id.as_str()))
+                } else {
+                    format!("[{label}](#{})", id.as_str())
+                }
+            }
+            None => {
+                let label = if c[4].is_empty() { &c[3] } else { &c[4] };
+                format!("[{label}]({})", to_markdown_target(&c[3]))
+            }
+        });
+        let mut text = text.into_owned();
+        for (pattern, replacement) in [
+            (&STRONG, "$1**$2**$3"),
+            (&EMPHASIS, "$1*$2*$3"),
+            (&HIGHLIGHT, "$1<mark>$2</mark>$3"),
+        ] {
+            // 区切りの文字を共有する隣の強調のために 2 回適用する
+            for _ in 0..2 {
+                text = pattern.replace_all(&text, replacement).into_owned();
+            }
+        }
+        // 行末の ` +` は改行
+        match text.strip_suffix(" +") {
+            Some(rest) => format!("{rest}\\"),
+            None => text,
+        }
+    }
+
+    /// `|===` の表（1 行目を見出しとする）
+    fn table(&mut self, body: &[&str]) -> String {
+        let mut columns = 0;
+        let mut cells: Vec<String> = Vec::new();
+        for line in body {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let row: Vec<String> = line
+                .split('|')
+                .skip(usize::from(line.starts_with('|')))
+                .map(|cell| table::escape_cell(&self.inline(cell.trim())))
+                .collect();
+            if columns == 0 {
+                columns = row.len();
+            }
+            cells.extend(row);
+        }
+        let columns = columns.max(1);
+        let rows: Vec<Vec<String>> = cells.chunks(columns).map(<[String]>::to_vec).collect();
+        if rows.is_empty() {
+            return String::new();
+        }
+        table::format_table(rows, false).trim_end().to_string()
+    }
+
+    /// `document` が偽ならブロックの中身（文書ヘッダーを持たない）
+    fn convert(&mut self, source: &str, document: bool) -> String {
+        let mut out: Vec<String> = Vec::new();
+        let mut front_matter: Vec<String> = Vec::new();
+        // 直前のブロック属性（`[source,rust]` など）とブロックタイトル
+        let mut block_attributes: Option<String> = None;
+        let mut block_title: Option<String> = None;
+        let mut in_header = document;
+        let mut lines = source.lines().peekable();
+        while let Some(line) = lines.next() {
+            let trimmed = line.trim_end();
+
+            if trimmed.starts_with("//") && !trimmed.starts_with("////") {
+                continue;
+            }
+            if let Some(caps) = ATTRIBUTE.captures(trimmed) {
+                let name = caps[1].trim_matches('!').to_string();
+                let value = caps[2].to_string();
+                if in_header && matches!(name.as_str(), "author" | "revdate" | "description") {
+                    let key = if name == "revdate" { "date" } else { &name };
+                    front_matter.push(format!("{key}: \"{}\"", value.replace('"', "\\\"")));
+                }
+                self.attributes.insert(name, value);
+                continue;
+            }
+            if trimmed.is_empty() {
+                in_header = false;
+                out.push(String::new());
+                continue;
+            }
+
+            if let Some(kind) = delimiter_kind(trimmed) {
+                let body: Vec<&str> = lines
+                    .by_ref()
+                    .take_while(|l| l.trim_end() != trimmed)
+                    .collect();
+                let attributes = block_attributes.take().unwrap_or_default();
+                if let Some(title) = block_title.take() {
+                    out.push(format!("**{}**", self.inline(&title)));
+                }
+                match kind {
+                    '-' | '.' => {
+                        let language = attributes
+                            .split(',')
+                            .nth(1)
+                            .filter(|_| attributes.starts_with("source") || kind == '-')
+                            .unwrap_or_default()
+                            .trim();
+                        let body = body.join("\n");
+                        let fence = if body.contains("```") { "````" } else { "```" };
+                        out.push(format!("{fence}{language}\n{body}\n{fence}"));
+                    }
+                    '_' | '=' if kind == '_' || !attributes.is_empty() => {
+                        // 引用と注記ブロック（`[NOTE]` の付いた `====`）
+                        if let Some(label) = attributes.split(',').next().filter(|a| {
+                            ["NOTE", "TIP", "IMPORTANT", "WARNING", "CAUTION"].contains(a)
+                        }) {
+                            out.push(format!(
+                                "> **{}{}:**",
+                                &label[..1],
+                                label[1..].to_lowercase()
+                            ));
+                        }
+                        let converted = self.convert(&body.join("\n"), false);
+                        for l in converted.trim_end().lines() {
+                            out.push(format!("> {l}").trim_end().to_string());
+                        }
+                    }
+                    '+' => out.extend(body.iter().map(|l| l.to_string())),
+                    '/' => {}
+                    _ => out.push(self.convert(&body.join("\n"), false).trim_end().to_string()),
+                }
+                continue;
+            }
+            if trimmed == "|===" {
+                let body: Vec<&str> = lines
+                    .by_ref()
+                    .take_while(|l| l.trim_end() != "|===")
+                    .collect();
+                block_attributes = None;
+                if let Some(title) = block_title.take() {
+                    out.push(format!("**{}**", self.inline(&title)));
+                }
+                out.push(self.table(&body));
+                continue;
+            }
+            if let Some(caps) = BLOCK_ATTRIBUTES.captures(trimmed) {
+                // `[[id]]` や `[#id]` のアンカーは捨てる
+                if !caps[1].starts_with('[') && !caps[1].starts_with('#') {
+                    block_attributes = Some(caps[1].to_string());
+                }
+                continue;
+            }
+            if let Some(title) = trimmed.strip_prefix('.') {
+                if !title.starts_with(['.', ' ']) && !title.is_empty() {
+                    block_title = Some(title.to_string());
+                    continue;
+                }
+            }
+
+            if let Some(caps) = HEADING.captures(trimmed) {
+                let level = caps[1].len();
+                let text = self.inline(&caps[2]);
+                if level == 1 && in_header {
+                    front_matter.insert(0, format!("title: \"{}\"", text.replace('"', "\\\"")));
+                }
+                out.push(format!("{} {text}", "#".repeat(level)));
+                continue;
+            }
+            if let Some(caps) = BLOCK_IMAGE.captures(trimmed) {
+                out.push(format!(
+                    "![{}]({})",
+                    caps[2].split(',').next().unwrap_or_default(),
+                    &caps[1]
+                ));
+                continue;
+            }
+            if let Some(caps) = INCLUDE.captures(trimmed) {
+                out.push(format!("{{{{include {}}}}}", to_markdown_target(&caps[1])));
+                continue;
+            }
+            if trimmed == "'''" || trimmed == "---" || trimmed == "***" {
+                out.push("---".to_string());
+                continue;
+            }
+            if trimmed == "<<<" {
+                continue;
+            }
+            if trimmed == "+" {
+                // リストの継続
+                out.push(String::new());
+                continue;
+            }
+            if let Some(caps) = ADMONITION.captures(trimmed) {
+                let label = &caps[1];
+                out.push(format!(
+                    "> **{}{}:** {}",
+                    &label[..1],
+                    label[1..].to_lowercase(),
+                    self.inline(&caps[2])
+                ));
+                continue;
+            }
+            if let Some(caps) = LIST_ITEM.captures(trimmed) {
+                let marker = &caps[1];
+                let depth = marker
+                    .chars()
+                    .filter(|&c| c == '*' || c == '.')
+                    .count()
+                    .max(1);
+                let indent = "  ".repeat(depth - 1);
+                let bullet =
+                    if marker.starts_with('.') || (marker.ends_with('.') && marker.len() > 1) {
+                        "1."
+                    } else {
+                        "-"
+                    };
+                let indent = if bullet == "1." {
+                    "   ".repeat(depth - 1)
+                } else {
+                    indent
+                };
+                let checkbox = match caps.get(2).map(|m| m.as_str()) {
+                    Some(" ") => "[ ] ",
+                    Some(_) => "[x] ",
+                    None => "",
+                };
+                out.push(format!(
+                    "{indent}{bullet} {checkbox}{}",
+                    self.inline(&caps[3])
+                ));
+                continue;
+            }
+            if let Some(caps) = DESCRIPTION.captures(trimmed) {
+                let term = self.inline(&caps[1]);
+                let description = caps
+                    .get(3)
+                    .map(|m| self.inline(m.as_str()))
+                    .unwrap_or_default();
+                out.push(
+                    format!("- **{term}**: {description}")
+                        .trim_end()
+                        .to_string(),
+                );
+                continue;
+            }
+            out.push(self.inline(trimmed));
+        }
+
+        let mut markdown = String::new();
+        if !front_matter.is_empty() {
+            markdown.push_str(&format!("---\n{}\n---\n\n", front_matter.join("\n")));
+        }
+        // 続く空行はまとめる
+        let mut blank = false;
+        for line in out {
+            if line.is_empty() {
+                if !blank && !markdown.is_empty() {
+                    markdown.push('\n');
+                }
+                blank = true;
+            } else {
+                markdown.push_str(&line);
+                markdown.push('\n');
+                blank = false;
+            }
+        }
+        markdown
+    }
+}
+
+/// AsciiDoc のテキストを Markdown に変換
+pub fn asciidoc_to_markdown(source: &str) -> String {
+    let mut converter = Converter {
+        attributes: HashMap::new(),
+        footnotes: Vec::new(),
+    };
+    let mut markdown = converter.convert(source, true);
+    if !converter.footnotes.is_empty() {
+        markdown.push('\n');
+        for (i, note) in converter.footnotes.iter().enumerate() {
+            markdown.push_str(&format!("[^{}]: {note}\n", i + 1));
+        }
+    }
+    markdown
+}
+
+/// AsciiDoc のファイルを Markdown に変換
+#[tauri::command]
+pub fn import_asciidoc(path: String) -> Result<String, String> {
+    let path = Path::new(&path);
+    let source =
+        fs::read_to_string(path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+    Ok(asciidoc_to_markdown(&source))
+}
@@ -0,0 +1,185 @@
+// 添付ファイル（画像など）の参照管理
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+use crate::bundle::ASSETS_DIR;
+use crate::links::{self, LinkKind};
+use crate::refactor::{self, read, UpdatedFile};
+use crate::state::AppState;
+use crate::tasks::Task;
+use crate::tr;
+use crate::vault::{self, path_string, Vault};
+
+/// 添付ファイルとみなす拡張子
+const ASSET_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "svg", "bmp", "ico", "avif", "tif", "tiff", "pdf", "mp3",
+    "wav", "ogg", "m4a", "flac", "mp4", "webm", "mov", "zip", "csv", "xlsx", "docx", "pptx",
+];
+
+/// 文書から参照されている添付ファイル
+#[derive(Debug, Serialize)]
+pub struct Attachment {
+    pub document: String,
+    /// 文書に書かれているリンク先
+    pub target: String,
+    /// 解決後の絶対パス
+    pub path: Option<String>,
+    pub exists: bool,
+    pub kind: LinkKind,
+    pub line: usize,
+}
+
+/// どの文書からも参照されていない添付ファイル
+#[derive(Debug, Serialize)]
+pub struct OrphanedAsset {
+    pub path: String,
+    pub size: u64,
+}
+
+/// 添付ファイルの付け替え結果
+#[derive(Debug, Serialize)]
+pub struct RelinkResult {
+    /// ファイル自体を移動したか
+    pub moved: bool,
+    pub updated_files: Vec<UpdatedFile>,
+}
+
+fn is_asset(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| ASSET_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// 文書の添付ファイルを置くフォルダ（文書と同じ場所の `assets/`）
+pub fn assets_dir(doc: &Path) -> PathBuf {
+    doc.parent().unwrap_or(Path::new(".")).join(ASSETS_DIR)
+}
+
+/// 文書から添付ファイルへのリンク先（相対パス）
+pub fn link_target(doc: &Path, file: &Path) -> String {
+    let dir = doc.parent().unwrap_or(Path::new("."));
+    links::percent_encode(&vault::relative_path(dir, file))
+}
+
+/// 添付ファイルを文書に埋め込む Markdown（`![alt](assets/...)`）
+pub fn embed_link(doc: &Path, file: &Path, alt: &str) -> String {
+    format!("![{alt}]({})", link_target(doc, file))
+}
+
+/// 文書が参照する添付ファイル（Markdown 以外のファイルへのリンク）を列挙
+fn document_attachments(vault: &Vault, doc: &Path, content: &str) -> Vec<Attachment> {
+    links::extract_links(content)
+        .into_iter()
+        .filter(|link| !link.target.is_empty() && !links::is_external(&link.target))
+        .filter_map(|link| {
+            let resolved = vault.resolve(doc, &link);
+            let is_note = match &resolved {
+                Some(p) => vault::is_markdown(p),
+                // 見つからないウィキリンクは拡張子のないものをノートとみなす
+                None => Path::new(&link.target).extension().is_none(),
+            };
+            (!is_note).then(|| Attachment {
+                document: path_string(doc),
+                target: link.target.clone(),
+                exists: resolved.as_ref().is_some_and(|p| p.is_file()),
+                path: resolved.map(|p| path_string(&p)),
+                kind: link.kind,
+                line: link.line,
+            })
+        })
+        .collect()
+}
+
+/// 文書（ファイル）またはワークスペース（フォルダ）内の添付ファイル参照を列挙
+#[tauri::command]
+pub fn list_attachments(
+    path: String,
+    vault_root: Option<String>,
+) -> Result<Vec<Attachment>, String> {
+    let path = vault::normalize(Path::new(&path));
+    let root = match vault_root {
+        Some(root) => PathBuf::from(root),
+        None if path.is_dir() => path.clone(),
+        None => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+    };
+    let vault = Vault::scan(&root);
+    let docs: Vec<PathBuf> = if path.is_dir() {
+        vault.markdown_files().cloned().collect()
+    } else {
+        vec![path]
+    };
+
+    let mut attachments = Vec::new();
+    for doc in docs {
+        let content = read(&doc)?;
+        attachments.extend(document_attachments(&vault, &doc, &content));
+    }
+    Ok(attachments)
+}
+
+/// ワークスペース内で、どの文書からも参照されていない添付ファイルを列挙
+#[tauri::command(async)]
+pub fn find_orphaned_assets(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    vault: String,
+    task_id: Option<String>,
+) -> Result<Vec<OrphanedAsset>, String> {
+    let task = Task::start(&app, &state, task_id)?;
+    let vault = Vault::scan(Path::new(&vault));
+    let mut referenced = HashSet::new();
+    let docs: Vec<&PathBuf> = vault.markdown_files().collect();
+    for (i, doc) in docs.iter().copied().enumerate() {
+        task.check()?;
+        task.progress(i + 1, docs.len(), || path_string(doc));
+        let content = read(doc)?;
+        for link in links::extract_links(&content) {
+            if let Some(path) = vault.resolve(doc, &link) {
+                referenced.insert(path);
+            }
+        }
+    }
+
+    Ok(vault
+        .files
+        .iter()
+        .filter(|p| is_asset(p) && !referenced.contains(*p))
+        .map(|p| OrphanedAsset {
+            path: path_string(p),
+            size: fs::metadata(p).map(|m| m.len()).unwrap_or(0),
+        })
+        .collect())
+}
+
+/// 添付ファイルを移動（移動済みならリンクのみ更新）し、参照している文書のリンクを書き換える
+#[tauri::command]
+pub fn relink_attachment(
+    vault: String,
+    old_path: String,
+    new_path: String,
+) -> Result<RelinkResult, String> {
+    let vault = Vault::scan(Path::new(&vault));
+    let old_path = vault::normalize(Path::new(&old_path));
+    let new_path = vault::normalize(Path::new(&new_path));
+
+    if old_path.exists() && !new_path.exists() {
+        let updated_files = refactor::move_with_link_update(&vault, &old_path, &new_path)?;
+        return Ok(RelinkResult {
+            moved: true,
+            updated_files,
+        });
+    }
+    if !new_path.exists() {
+        return Err(tr!("File not found: {}", new_path.display()));
+    }
+    let rewrites = refactor::rewrite_references(&vault, &old_path, &new_path)?;
+    Ok(RelinkResult {
+        moved: false,
+        updated_files: refactor::write_rewrites(rewrites)?,
+    })
+}
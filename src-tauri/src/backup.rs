@@ -0,0 +1,299 @@
+// ワークスペースの定期バックアップ（日時付きの ZIP スナップショット）
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::state::AppState;
+use crate::tr;
+use crate::vault::{self, path_string};
+use crate::zip::{self, ZipWriter};
+
+/// バックアップの設定
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BackupConfig {
+    pub vault: String,
+    pub backup_dir: String,
+    /// 実行間隔（分）
+    pub interval_minutes: u64,
+    /// 残すスナップショットの数
+    pub retention: usize,
+}
+
+/// 動作中のスケジューラ（`_stop` を破棄するとスレッドが終了する）
+struct Scheduler {
+    config: BackupConfig,
+    _stop: Sender<()>,
+}
+
+/// 定期バックアップの状態
+#[derive(Default)]
+pub struct BackupState {
+    scheduler: Mutex<Option<Scheduler>>,
+    /// 手動と定期のバックアップが同時に走らないようにする
+    running: Arc<Mutex<()>>,
+}
+
+/// スナップショットの情報
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    /// 作成日時（ローカル時刻）
+    pub created: String,
+}
+
+/// `backup-progress` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+/// `backup-finished` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupFinished {
+    pub backup: Option<BackupInfo>,
+    /// 前回から変更がなく作成しなかった
+    pub skipped: bool,
+    pub error: Option<String>,
+}
+
+fn modified(path: &Path) -> SystemTime {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+fn backup_info(path: &Path) -> BackupInfo {
+    let created: DateTime<Local> = modified(path).into();
+    BackupInfo {
+        path: path_string(path),
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        size: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        created: created.format("%Y-%m-%d %H:%M:%S").to_string(),
+    }
+}
+
+/// スナップショット名の接頭辞（ワークスペースのフォルダ名）
+fn snapshot_prefix(vault: &Path) -> String {
+    let name = vault
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "vault".to_string());
+    format!("{name}-")
+}
+
+/// バックアップフォルダ内のスナップショット（古い順）
+fn snapshots(backup_dir: &Path, prefix: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(backup_dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            let name = p.file_name().unwrap_or_default().to_string_lossy();
+            name.starts_with(prefix) && name.ends_with(".zip") && p.is_file()
+        })
+        .collect();
+    paths.sort_by_key(|p| (modified(p), p.clone()));
+    paths
+}
+
+/// スナップショットを作成し、保持数を超えた古いものを削除
+fn run_backup(
+    app: &AppHandle,
+    config: &BackupConfig,
+    running: &Mutex<()>,
+    skip_unchanged: bool,
+) -> Result<Option<BackupInfo>, String> {
+    let _guard = running.lock().unwrap();
+    let vault = vault::normalize(Path::new(&config.vault));
+    let backup_dir = vault::normalize(Path::new(&config.backup_dir));
+    if !vault.is_dir() {
+        return Err(tr!("Folder not found: {}", vault.display()));
+    }
+    let files: Vec<PathBuf> = vault::walk_files(&vault)
+        .into_iter()
+        .filter(|p| !p.starts_with(&backup_dir))
+        .collect();
+    let prefix = snapshot_prefix(&vault);
+
+    // 最新のスナップショット以降に更新されたファイルがなければ作らない
+    if skip_unchanged {
+        if let Some(latest) = snapshots(&backup_dir, &prefix).last() {
+            let latest = modified(latest);
+            if files.iter().all(|f| modified(f) <= latest) {
+                return Ok(None);
+            }
+        }
+    }
+
+    fs::create_dir_all(&backup_dir)
+        .map_err(|e| tr!("Failed to create {}: {e}", backup_dir.display()))?;
+    let stamp = Local::now().format("%Y%m%d-%H%M%S");
+    let path = (1..)
+        .map(|n| match n {
+            1 => backup_dir.join(format!("{prefix}{stamp}.zip")),
+            n => backup_dir.join(format!("{prefix}{stamp}-{n}.zip")),
+        })
+        .find(|p| !p.exists())
+        .unwrap_or_default();
+    // 書きかけのスナップショットが一覧に出ないよう、別名で書いてから改名する
+    let partial = path.with_extension("zip.partial");
+    let result = write_snapshot(app, &vault, &files, &partial).and_then(|_| {
+        fs::rename(&partial, &path).map_err(|e| {
+            tr!(
+                "Failed to move {} to {}: {e}",
+                partial.display(),
+                path.display()
+            )
+        })
+    });
+    if let Err(e) = result {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    let all = snapshots(&backup_dir, &prefix);
+    let excess = all.len().saturating_sub(config.retention.max(1));
+    for old in &all[..excess] {
+        let _ = fs::remove_file(old);
+    }
+    Ok(Some(backup_info(&path)))
+}
+
+fn write_snapshot(
+    app: &AppHandle,
+    vault: &Path,
+    files: &[PathBuf],
+    out: &Path,
+) -> Result<(), String> {
+    let file = File::create(out).map_err(|e| tr!("Failed to write {}: {e}", out.display()))?;
+    let mut writer = ZipWriter::new(BufWriter::new(file));
+    let total = files.len();
+    for (i, path) in files.iter().enumerate() {
+        // 走査後に削除されたファイルは飛ばす
+        if let Ok(data) = fs::read(path) {
+            let relative = path.strip_prefix(vault).unwrap_or(path);
+            writer.add(&vault::to_slash(relative), &data)?;
+        }
+        let done = i + 1;
+        if done % 20 == 0 || done == total {
+            let _ = app.emit("backup-progress", BackupProgress { done, total });
+        }
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+fn emit_finished(app: &AppHandle, result: &Result<Option<BackupInfo>, String>) {
+    let payload = match result {
+        Ok(backup) => BackupFinished {
+            backup: backup.clone(),
+            skipped: backup.is_none(),
+            error: None,
+        },
+        Err(e) => BackupFinished {
+            backup: None,
+            skipped: false,
+            error: Some(e.clone()),
+        },
+    };
+    let _ = app.emit("backup-finished", payload);
+}
+
+/// 定期バックアップを設定（`None` で停止）
+#[tauri::command]
+pub fn configure_backups(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    config: Option<BackupConfig>,
+) -> Result<(), String> {
+    let mut scheduler = state.backup.scheduler.lock().unwrap();
+    // 以前のスケジューラを止める
+    scheduler.take();
+    let Some(config) = config else {
+        return Ok(());
+    };
+    if config.interval_minutes == 0 {
+        return Err(tr!("Backup interval must be at least 1 minute"));
+    }
+
+    let (stop, stopped) = mpsc::channel::<()>();
+    let interval = Duration::from_secs(config.interval_minutes * 60);
+    let running = state.backup.running.clone();
+    let thread_config = config.clone();
+    thread::spawn(move || {
+        while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+            let result = run_backup(&app, &thread_config, &running, true);
+            emit_finished(&app, &result);
+        }
+    });
+    *scheduler = Some(Scheduler {
+        config,
+        _stop: stop,
+    });
+    Ok(())
+}
+
+/// 現在の定期バックアップの設定
+#[tauri::command]
+pub fn get_backup_config(state: State<'_, AppState>) -> Option<BackupConfig> {
+    state
+        .backup
+        .scheduler
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|s| s.config.clone())
+}
+
+/// 今すぐバックアップ（設定を省略すると定期バックアップの設定を使う）
+#[tauri::command(async)]
+pub fn backup_now(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    config: Option<BackupConfig>,
+) -> Result<BackupInfo, String> {
+    let configured = || {
+        let scheduler = state.backup.scheduler.lock().unwrap();
+        scheduler.as_ref().map(|s| s.config.clone())
+    };
+    let config = config
+        .or_else(configured)
+        .ok_or_else(|| tr!("Backups are not configured"))?;
+    let result = run_backup(&app, &config, &state.backup.running, false);
+    emit_finished(&app, &result);
+    result?.ok_or_else(|| tr!("Backup was not created"))
+}
+
+/// バックアップフォルダ内のスナップショット（新しい順）
+#[tauri::command]
+pub fn list_backups(backup_dir: String) -> Vec<BackupInfo> {
+    let mut backups = snapshots(Path::new(&backup_dir), "");
+    backups.reverse();
+    backups.iter().map(|p| backup_info(p)).collect()
+}
+
+/// スナップショットを展開して復元（同名のファイルは上書き）
+#[tauri::command(async)]
+pub fn restore_backup(backup: String, dest_dir: String) -> Result<Vec<String>, String> {
+    let data = fs::read(&backup).map_err(|e| tr!("Failed to read {backup}: {e}"))?;
+    let entries = zip::read_zip(&data)?;
+    let written = zip::extract(&entries, Path::new(&dest_dir), true)?;
+    Ok(written.iter().map(|p| path_string(p)).collect())
+}
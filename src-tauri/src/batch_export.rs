@@ -0,0 +1,224 @@
+// フォルダの一括エクスポート（glob に当てはまるノートを並列に書き出し、結果をまとめて返す）
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+use crate::editorconfig;
+use crate::export_profiles::{self, ExportFormat, ExportProfile};
+use crate::hooks::HookResult;
+use crate::output_template;
+use crate::state::AppState;
+use crate::tasks::{cancelled_error, Task};
+use crate::tr;
+use crate::vault::{self, path_string, to_slash, Vault};
+
+/// 同時に書き出すファイルの数の上限（PDF は変換のたびにブラウザを起動する）
+const MAX_WORKERS: usize = 4;
+
+/// ファイル 1 件の結果
+#[derive(Debug, Serialize)]
+pub struct BatchExportItem {
+    pub path: String,
+    /// 書き出したファイル（失敗したら `None`）
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    /// 書き出し後のコマンドの結果
+    pub hook: Option<HookResult>,
+}
+
+/// `batch_export` の結果
+#[derive(Debug, Serialize)]
+pub struct BatchExportSummary {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub duration_ms: u64,
+    /// 対象のファイルの順
+    pub items: Vec<BatchExportItem>,
+}
+
+/// 書き出し先（`out_dir` を指定し、テンプレートがなければフォルダの構成を保ってその下）
+fn output_path(
+    app: &AppHandle,
+    vault: &Vault,
+    profile: &ExportProfile,
+    out_dir: Option<&Path>,
+    source: &Path,
+    content: &str,
+) -> PathBuf {
+    let templated = profile
+        .output
+        .as_deref()
+        .is_some_and(|t| !t.trim().is_empty());
+    let base = out_dir.map(|out_dir| match source.parent() {
+        Some(dir) if !templated => {
+            out_dir.join(dir.strip_prefix(&vault.root).unwrap_or(Path::new("")))
+        }
+        _ => out_dir.to_path_buf(),
+    });
+    export_profiles::output_path(
+        app,
+        profile,
+        source,
+        content,
+        Some(&vault.root),
+        base.as_deref(),
+    )
+}
+
+/// 読み込みと書き出し先（失敗したらエラー）
+type Plan = Result<(String, PathBuf), String>;
+
+fn export_one(
+    app: &AppHandle,
+    state: State<'_, AppState>,
+    profile: &ExportProfile,
+    source: &Path,
+    plan: Plan,
+) -> Result<(PathBuf, Option<HookResult>), String> {
+    let (content, out) = plan?;
+    export_profiles::export_with(state.clone(), profile, source, content, &out)?;
+    let hook = export_profiles::run_post_export(app, &state, profile, source, &out)?;
+    Ok((out, hook))
+}
+
+/// `root` の中で `glob` に当てはまるノートをまとめて書き出す（`task_id` を指定すると進捗を送り、中断できる）
+///
+/// `glob` は EditorConfig と同じ書き方（省略時はすべてのノート）。`profile` はエクスポートの設定の名前で、
+/// `format` と `out_dir` を指定すればその項目だけ置き換える。1 件が失敗しても残りは続ける。
+#[allow(clippy::too_many_arguments)]
+#[tauri::command(async)]
+pub fn batch_export(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    root: String,
+    glob: Option<String>,
+    format: Option<ExportFormat>,
+    out_dir: Option<String>,
+    profile: Option<String>,
+    task_id: Option<String>,
+) -> Result<BatchExportSummary, String> {
+    let started = Instant::now();
+    let task = Task::start(&app, &state, task_id)?;
+    let mut profile = match profile.as_deref().filter(|p| !p.trim().is_empty()) {
+        Some(name) => export_profiles::find(&app, name)?,
+        None => ExportProfile::default(),
+    };
+    if let Some(format) = format {
+        profile.format = format;
+    }
+    let vault = Vault::scan(Path::new(&root));
+    if profile
+        .post_export
+        .as_deref()
+        .is_some_and(|c| !c.trim().is_empty())
+    {
+        state.trust.require(&app, &vault.root, "hooks")?;
+    }
+    let out_dir = out_dir
+        .filter(|d| !d.trim().is_empty())
+        .map(|d| vault::normalize(&vault.root.join(d)));
+    let pattern = match glob.as_deref().map(str::trim).filter(|g| !g.is_empty()) {
+        Some(glob) => {
+            Some(editorconfig::section_regex(glob).ok_or_else(|| tr!("Invalid glob: {}", glob))?)
+        }
+        None => None,
+    };
+    let sources: Vec<&PathBuf> = vault
+        .markdown_files()
+        .filter(|path| {
+            pattern.as_ref().is_none_or(|re| {
+                let relative = path.strip_prefix(&vault.root).unwrap_or(path);
+                re.is_match(&to_slash(relative))
+            })
+        })
+        .collect();
+
+    // 重なりの扱いが実行の順で変わらないよう、書き出し先は先にまとめて決める
+    let mut taken = HashSet::new();
+    let plans: Vec<Mutex<Option<Plan>>> = sources
+        .iter()
+        .map(|source| {
+            let plan = fs::read_to_string(source)
+                .map_err(|e| tr!("Failed to read {}: {e}", source.display()))
+                .and_then(|content| {
+                    let out =
+                        output_path(&app, &vault, &profile, out_dir.as_deref(), source, &content);
+                    let out = output_template::resolve(out, profile.collision, &mut taken)?;
+                    Ok((content, out))
+                });
+            Mutex::new(Some(plan))
+        })
+        .collect();
+
+    let total = sources.len();
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let items: Mutex<Vec<Option<BatchExportItem>>> = Mutex::new((0..total).map(|_| None).collect());
+    let workers = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .clamp(1, MAX_WORKERS)
+        .min(total.max(1));
+    let (done_tx, done_rx) = mpsc::channel::<String>();
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let done_tx = done_tx.clone();
+            let (app, profile) = (&app, &profile);
+            let (next, stop, items, sources, plans, state) =
+                (&next, &stop, &items, &sources, &plans, &state);
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= total || stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                let source = sources[i].as_path();
+                let item_started = Instant::now();
+                let plan = plans[i].lock().unwrap().take().unwrap();
+                let result = export_one(app, state.clone(), profile, source, plan);
+                let (output, error, hook) = match result {
+                    Ok((out, hook)) => (Some(path_string(&out)), None, hook),
+                    Err(e) => (None, Some(e), None),
+                };
+                items.lock().unwrap()[i] = Some(BatchExportItem {
+                    path: path_string(source),
+                    output,
+                    error,
+                    duration_ms: item_started.elapsed().as_millis() as u64,
+                    hook,
+                });
+                let _ = done_tx.send(path_string(source));
+            });
+        }
+        drop(done_tx);
+        // 進捗の通知と中断の確認はこのスレッドで行う
+        for (done, path) in done_rx.iter().enumerate() {
+            if task.check().is_err() {
+                stop.store(true, Ordering::SeqCst);
+            }
+            task.progress(done + 1, total, || path);
+        }
+    });
+    if stop.load(Ordering::SeqCst) {
+        return Err(cancelled_error());
+    }
+
+    let items: Vec<BatchExportItem> = items.into_inner().unwrap().into_iter().flatten().collect();
+    let succeeded = items.iter().filter(|item| item.error.is_none()).count();
+    Ok(BatchExportSummary {
+        total,
+        succeeded,
+        failed: items.len() - succeeded,
+        duration_ms: started.elapsed().as_millis() as u64,
+        items,
+    })
+}
@@ -0,0 +1,299 @@
+// プレビューの描画の計測（段階ごとの時間・遅いブロック・性能の予算）
+//
+// criterion はビルドの依存に含まれていないため、大きな文書での計測は `#[ignore]` のテストとして置く
+// （`cargo test --release -- --ignored`）。統計は繰り返しの中央値だけで、前回の結果との比較や外れ値の扱いはない。
+
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use pulldown_cmark::{Event, Parser};
+use serde::Serialize;
+use tauri::State;
+
+use crate::markdown;
+use crate::preprocess::preprocess;
+use crate::preview;
+use crate::prose;
+use crate::render::{self, RenderOptions};
+use crate::state::AppState;
+use crate::text::LineIndex;
+use crate::tr;
+use crate::vault::{self, path_string};
+
+/// 既定の繰り返し回数（中央値を使う）
+const DEFAULT_ITERATIONS: usize = 5;
+/// 報告する遅いブロックの数
+const SLOWEST_BLOCKS: usize = 5;
+
+/// 文書の大きさに対するプレビューの描画時間の予算（ミリ秒）
+///
+/// 小さな文書は 1 フレーム（16ms）、以降は 1KB あたり 0.2ms（1MB で約 220ms）。
+pub fn budget_millis(bytes: usize) -> f64 {
+    16.0 + bytes as f64 / 1024.0 * 0.2
+}
+
+/// 段階ごとの時間
+#[derive(Debug, Serialize)]
+pub struct StageTiming {
+    /// "profile" | "preprocess" | "render" | "outline" | "headings" | "word_count"
+    pub name: String,
+    /// 中央値（ミリ秒）
+    pub millis: f64,
+}
+
+/// 描画に時間のかかるブロック
+#[derive(Debug, Serialize)]
+pub struct BlockTiming {
+    /// 先頭の行番号（1 始まり）
+    pub line: usize,
+    /// ブロックの先頭の行
+    pub source: String,
+    pub millis: f64,
+}
+
+/// `parse_markdown` の計測結果
+#[derive(Debug, Serialize)]
+pub struct ParseBenchmark {
+    pub path: String,
+    pub bytes: usize,
+    pub lines: usize,
+    pub iterations: usize,
+    pub stages: Vec<StageTiming>,
+    /// プレビューの描画（profile・preprocess・render・outline）の合計
+    pub parse_millis: f64,
+    pub budget_millis: f64,
+    pub within_budget: bool,
+    pub slowest_blocks: Vec<BlockTiming>,
+}
+
+/// `iterations` 回実行した時間の中央値（ミリ秒）
+fn median_millis(iterations: usize, mut f: impl FnMut()) -> f64 {
+    let mut times: Vec<f64> = (0..iterations.max(1))
+        .map(|_| {
+            let started = Instant::now();
+            f();
+            started.elapsed().as_secs_f64() * 1000.0
+        })
+        .collect();
+    times.sort_by(f64::total_cmp);
+    times[times.len() / 2]
+}
+
+/// 段階ごとに計測する（`parse_markdown` と同じ順序と設定）
+pub fn measure(
+    state: &AppState,
+    content: &str,
+    path: Option<&Path>,
+    iterations: usize,
+) -> Result<ParseBenchmark, String> {
+    let mut stages = Vec::new();
+    let mut stage = |name: &str, millis: f64| {
+        stages.push(StageTiming {
+            name: name.to_string(),
+            millis,
+        })
+    };
+    stage(
+        "profile",
+        median_millis(iterations, || {
+            state.profiles.resolve(content, path);
+        }),
+    );
+    let profile = state.profiles.resolve(content, path);
+    let preprocessors = state.preprocess.get();
+    let trusted = path.is_some_and(|p| state.trust.is_trusted(p));
+    let mut failure = None;
+    stage(
+        "preprocess",
+        median_millis(iterations, || {
+            if let Err(e) = preprocess(content, path, None, &preprocessors, trusted) {
+                failure = Some(e);
+            }
+        }),
+    );
+    if let Some(e) = failure {
+        return Err(e);
+    }
+    let body = preprocess(content, path, None, &preprocessors, trusted)?;
+    let options = RenderOptions {
+        diagrams: trusted.then_some(&state.diagrams),
+        base_dir: path.and_then(Path::parent),
+        profile: Some(&profile),
+        ..Default::default()
+    };
+    stage(
+        "render",
+        median_millis(iterations, || {
+            render::render_html_with(&body, &options);
+        }),
+    );
+    stage(
+        "outline",
+        median_millis(iterations, || {
+            preview::outline(content, None);
+        }),
+    );
+    stage(
+        "headings",
+        median_millis(iterations, || {
+            markdown::headings(content);
+        }),
+    );
+    stage(
+        "word_count",
+        median_millis(iterations, || {
+            prose::analyze_prose(content.to_string(), None, None);
+        }),
+    );
+    let parse_millis = stages
+        .iter()
+        .filter(|s| {
+            matches!(
+                s.name.as_str(),
+                "profile" | "preprocess" | "render" | "outline"
+            )
+        })
+        .map(|s| s.millis)
+        .sum();
+
+    // トップレベルのブロックごとに描画して遅いものを探す
+    let index = LineIndex::new(&body);
+    let mut depth = 0usize;
+    let mut blocks = Vec::new();
+    for (event, range) in Parser::new_ext(&body, profile.parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(_) => {
+                if depth == 0 {
+                    blocks.push(range);
+                }
+                depth += 1;
+            }
+            Event::End(_) => depth -= 1,
+            _ if depth == 0 => blocks.push(range),
+            _ => {}
+        }
+    }
+    let mut slowest: Vec<BlockTiming> = blocks
+        .into_iter()
+        .map(|range| {
+            let source = &body[range.clone()];
+            BlockTiming {
+                line: index.line_of(range.start),
+                source: source.lines().next().unwrap_or_default().to_string(),
+                millis: median_millis(iterations, || {
+                    render::render_html_with(source, &options);
+                }),
+            }
+        })
+        .collect();
+    slowest.sort_by(|a, b| b.millis.total_cmp(&a.millis));
+    slowest.truncate(SLOWEST_BLOCKS);
+
+    let budget = budget_millis(content.len());
+    Ok(ParseBenchmark {
+        path: path.map(path_string).unwrap_or_default(),
+        bytes: content.len(),
+        lines: content.lines().count(),
+        iterations: iterations.max(1),
+        stages,
+        parse_millis,
+        budget_millis: budget,
+        within_budget: parse_millis <= budget,
+        slowest_blocks: slowest,
+    })
+}
+
+/// 文書のプレビューの描画時間を段階ごとに計測する（遅い文書の原因を調べるためのデバッグ用）
+#[tauri::command(async)]
+pub fn benchmark_parse(
+    state: State<'_, AppState>,
+    path: String,
+    iterations: Option<usize>,
+) -> Result<ParseBenchmark, String> {
+    let path = vault::normalize(Path::new(&path));
+    let content =
+        fs::read_to_string(&path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+    measure(
+        &state,
+        &content,
+        Some(&path),
+        iterations.unwrap_or(DEFAULT_ITERATIONS),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 計測用の大きな文書（見出し・段落・リスト・表・コード・リンク・タスク・日本語の本文を繰り返す）
+    fn corpus(bytes: usize) -> String {
+        let section = |n: usize| {
+            format!(
+                "## Section {n}\n\n\
+                 Lorem ipsum dolor sit amet, *consectetur* adipiscing elit, sed do **eiusmod** tempor \
+                 incididunt ut labore et dolore magna aliqua. See [[Note {n}]] and [the docs](docs/{n}.md).\n\
+                 日本語の段落です。吾輩は猫である。名前はまだ無い。どこで生れたかとんと見当がつかぬ。#tag{n}\n\n\
+                 - [ ] task {n}\n- [x] done {n}\n  - nested `code` item\n\n\
+                 | key | value |\n| --- | --- |\n| a{n} | {n} |\n| b{n} | $x^{n}$ |\n\n\
+                 ```rust\nfn f{n}() -> usize {{\n    {n}\n}}\n```\n\n\
+                 > [!note] Callout {n}\n> Quoted text with a footnote[^{n}].\n\n\
+                 [^{n}]: Footnote {n}.\n\n"
+            )
+        };
+        let mut content = String::from("---\ntitle: Benchmark corpus\n---\n\n# Corpus\n\n");
+        let mut n = 0;
+        while content.len() < bytes {
+            n += 1;
+            content.push_str(&section(n));
+        }
+        content
+    }
+
+    /// 1MB の文書が予算内で描画できること（最適化ビルドで `cargo test --release -- --ignored`）
+    #[test]
+    #[ignore]
+    fn parse_within_budget() {
+        let state = AppState::default();
+        let content = corpus(1024 * 1024);
+        let result = measure(&state, &content, None, 3).unwrap();
+        let stages: Vec<String> = result
+            .stages
+            .iter()
+            .map(|s| format!("{} {:.1} ms", s.name, s.millis))
+            .collect();
+        let names: Vec<&str> = result.stages.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "profile",
+                "preprocess",
+                "render",
+                "outline",
+                "headings",
+                "word_count"
+            ]
+        );
+        assert!(result
+            .stages
+            .iter()
+            .all(|s| s.millis.is_finite() && s.millis >= 0.0));
+        assert!(
+            result.within_budget,
+            "parse took {:.1} ms (budget {:.1} ms; {})",
+            result.parse_millis,
+            result.budget_millis,
+            stages.join(", ")
+        );
+    }
+
+    #[test]
+    fn corpus_covers_constructs() {
+        let state = AppState::default();
+        let content = corpus(8 * 1024);
+        assert!(content.len() >= 8 * 1024);
+        let result = measure(&state, &content, None, 1).unwrap();
+        assert_eq!(result.stages.len(), 6);
+        assert_eq!(result.slowest_blocks.len(), SLOWEST_BLOCKS);
+    }
+}
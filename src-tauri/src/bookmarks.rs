@@ -0,0 +1,136 @@
+// しおり（よく開くファイルと見出し。アプリのデータフォルダの `bookmarks.json` に保存する）
+
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::app_data;
+use crate::markdown;
+use crate::text::LineIndex;
+use crate::tr;
+use crate::vault::{self, path_string};
+
+/// データフォルダ内のファイル
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+/// しおり
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Bookmark {
+    pub id: String,
+    pub path: String,
+    /// 見出しの本文（ファイル全体なら `None`）
+    pub heading: Option<String>,
+    pub label: String,
+    /// 作成日時（UNIX ミリ秒）
+    pub created: u64,
+    /// 見出しの行番号（1 始まり。一覧を取るときに求め直す）
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// ファイルが存在する（一覧を取るときに確かめる）
+    #[serde(default)]
+    pub exists: bool,
+}
+
+fn load(app: &AppHandle) -> Result<Vec<Bookmark>, String> {
+    app_data::read_json(&app_data::data_file(app, BOOKMARKS_FILE)?)
+}
+
+fn store(app: &AppHandle, bookmarks: &[Bookmark]) -> Result<(), String> {
+    app_data::write_json(&app_data::data_file(app, BOOKMARKS_FILE)?, bookmarks)
+}
+
+/// 見出しの行番号（同じ本文の見出しが複数あれば最初のもの）
+fn heading_line(content: &str, heading: &str) -> Option<usize> {
+    let index = LineIndex::new(content);
+    markdown::headings(content)
+        .into_iter()
+        .find(|h| h.text.trim() == heading.trim())
+        .map(|h| index.line_of(h.range.start))
+}
+
+/// ファイルの有無と見出しの行番号を求め直す
+fn locate(bookmark: &mut Bookmark) {
+    let content = fs::read_to_string(&bookmark.path);
+    bookmark.exists = Path::new(&bookmark.path).is_file();
+    bookmark.line = match (&bookmark.heading, content) {
+        (Some(heading), Ok(content)) => heading_line(&content, heading),
+        _ => None,
+    };
+}
+
+/// しおりを追加する（同じファイルと見出しのしおりがあれば名前を変える）
+#[tauri::command]
+pub fn add_bookmark(
+    app: AppHandle,
+    path: String,
+    heading: Option<String>,
+    label: Option<String>,
+) -> Result<Bookmark, String> {
+    let path = path_string(&vault::normalize(Path::new(&path)));
+    let heading = heading.filter(|h| !h.trim().is_empty());
+    let label = label
+        .filter(|l| !l.trim().is_empty())
+        .or_else(|| heading.clone())
+        .unwrap_or_else(|| {
+            Path::new(&path)
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+    let mut bookmarks = load(&app)?;
+    let mut bookmark = match bookmarks
+        .iter_mut()
+        .find(|b| b.path == path && b.heading == heading)
+    {
+        Some(existing) => {
+            existing.label = label;
+            existing.clone()
+        }
+        None => {
+            let created = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64);
+            let mut id = created;
+            while bookmarks.iter().any(|b| b.id == format!("{id:x}")) {
+                id += 1;
+            }
+            let bookmark = Bookmark {
+                id: format!("{id:x}"),
+                path,
+                heading,
+                label,
+                created,
+                line: None,
+                exists: false,
+            };
+            bookmarks.push(bookmark.clone());
+            bookmark
+        }
+    };
+    store(&app, &bookmarks)?;
+    locate(&mut bookmark);
+    Ok(bookmark)
+}
+
+/// しおりの一覧（追加した順。見出しの行番号とファイルの有無を含む）
+#[tauri::command]
+pub fn list_bookmarks(app: AppHandle) -> Result<Vec<Bookmark>, String> {
+    let mut bookmarks = load(&app)?;
+    bookmarks.iter_mut().for_each(locate);
+    Ok(bookmarks)
+}
+
+/// しおりを削除する
+#[tauri::command]
+pub fn remove_bookmark(app: AppHandle, id: String) -> Result<(), String> {
+    let mut bookmarks = load(&app)?;
+    let before = bookmarks.len();
+    bookmarks.retain(|b| b.id != id);
+    if bookmarks.len() == before {
+        return Err(tr!("Bookmark not found: {id}"));
+    }
+    store(&app, &bookmarks)
+}
@@ -0,0 +1,180 @@
+// 文書と添付ファイルをまとめた ZIP バンドル（ノートの共有用）
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::export;
+use crate::links::{self, LinkKind};
+use crate::refactor::read;
+use crate::render;
+use crate::text;
+use crate::tr;
+use crate::vault::{self, path_string, Vault};
+use crate::zip::{self, ZipWriter};
+
+/// フォルダ外の添付ファイルを集めるフォルダ
+pub const ASSETS_DIR: &str = "assets";
+
+/// バンドル書き出しの結果
+#[derive(Debug, Serialize)]
+pub struct BundleExport {
+    pub path: String,
+    /// バンドル内の添付ファイルのパス
+    pub assets: Vec<String>,
+    /// 見つからなかったリンク先
+    pub missing: Vec<String>,
+}
+
+/// バンドル展開の結果
+#[derive(Debug, Serialize)]
+pub struct BundleImport {
+    /// 展開した Markdown 文書
+    pub document: Option<String>,
+    pub files: Vec<String>,
+}
+
+/// 添付ファイルのバンドル内でのパス（文書のフォルダ外のものは `assets/` に集める）
+fn bundle_name(dir: &Path, asset: &Path, used: &HashSet<String>) -> String {
+    if let Ok(relative) = asset.strip_prefix(dir) {
+        return vault::to_slash(relative);
+    }
+    let stem = asset
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ext = asset
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| match n {
+            1 => format!("{ASSETS_DIR}/{stem}{ext}"),
+            n => format!("{ASSETS_DIR}/{stem}-{n}{ext}"),
+        })
+        .find(|name| !used.contains(name))
+        .unwrap_or_default()
+}
+
+/// 文書・参照している添付ファイル・HTML 版を ZIP にまとめる
+#[tauri::command(async)]
+pub fn export_bundle(
+    path: String,
+    out_zip: String,
+    vault_root: Option<String>,
+) -> Result<BundleExport, String> {
+    let doc = vault::normalize(Path::new(&path));
+    let dir = doc.parent().map(Path::to_path_buf).unwrap_or_default();
+    let vault = Vault::scan(vault_root.as_deref().map(Path::new).unwrap_or(&dir));
+    let content = read(&doc)?;
+
+    let mut assets: Vec<(PathBuf, String)> = Vec::new();
+    let mut used = HashSet::new();
+    let mut wiki_targets = HashMap::new();
+    let mut missing = Vec::new();
+    let mut edits = Vec::new();
+    for link in links::extract_links(&content) {
+        if link.target.is_empty() || links::is_external(&link.target) {
+            continue;
+        }
+        let Some(resolved) = vault.resolve(&doc, &link) else {
+            if Path::new(&link.target).extension().is_some() && !missing.contains(&link.target) {
+                missing.push(link.target.clone());
+            }
+            continue;
+        };
+        if vault::is_markdown(&resolved) {
+            continue;
+        }
+        if !resolved.is_file() {
+            if !missing.contains(&link.target) {
+                missing.push(link.target.clone());
+            }
+            continue;
+        }
+
+        let name = match assets.iter().find(|(p, _)| *p == resolved) {
+            Some((_, name)) => name.clone(),
+            None => {
+                let name = bundle_name(&dir, &resolved, &used);
+                used.insert(name.clone());
+                assets.push((resolved.clone(), name.clone()));
+                name
+            }
+        };
+        // 展開後も参照できるよう、文書からの相対パスに書き換える
+        let wiki = link.is_wiki();
+        let mut target = link.target.clone();
+        if !resolved.starts_with(&dir) || link.target.starts_with('/') {
+            target = match link.kind {
+                LinkKind::Html => name.clone(),
+                _ if wiki => name.clone(),
+                _ => links::percent_encode(&name),
+            };
+            edits.push((link.target_range, target.clone()));
+        }
+        if wiki {
+            wiki_targets.insert(target, name);
+        }
+    }
+    let content = text::apply_edits(&content, edits);
+
+    let rewrite = |dest: &str, wiki: bool| {
+        let target = dest.split_once('#').map_or(dest, |(target, _)| target);
+        wiki.then(|| wiki_targets.get(target))
+            .flatten()
+            .map(|name| links::percent_encode(name))
+    };
+    let body = render::render_html(&content, Some(&rewrite));
+    let html = export::html_document(&export::document_title(&doc, &content), &body, "light");
+
+    let file_name = doc
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "note.md".to_string());
+    let stem = Path::new(&file_name)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let out_zip = PathBuf::from(out_zip);
+    if let Some(parent) = out_zip.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| tr!("Failed to create {}: {e}", parent.display()))?;
+    }
+    let file =
+        File::create(&out_zip).map_err(|e| tr!("Failed to write {}: {e}", out_zip.display()))?;
+    let mut writer = ZipWriter::new(BufWriter::new(file));
+    writer.add(&file_name, content.as_bytes())?;
+    writer.add(&format!("{stem}.html"), html.as_bytes())?;
+    for (path, name) in &assets {
+        let data = fs::read(path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+        writer.add(name, &data)?;
+    }
+    writer.finish()?;
+
+    Ok(BundleExport {
+        path: path_string(&out_zip),
+        assets: assets.into_iter().map(|(_, name)| name).collect(),
+        missing,
+    })
+}
+
+/// バンドルを展開（既存のファイルは上書きしない）
+#[tauri::command(async)]
+pub fn import_bundle(zip: String, dest_dir: String) -> Result<BundleImport, String> {
+    let data = fs::read(&zip).map_err(|e| tr!("Failed to read {zip}: {e}"))?;
+    let entries = zip::read_zip(&data)?;
+    let written = zip::extract(&entries, Path::new(&dest_dir), false)?;
+    let document = entries
+        .iter()
+        .zip(&written)
+        .find(|(entry, path)| !entry.name.contains('/') && vault::is_markdown(path))
+        .map(|(_, path)| path_string(path));
+    Ok(BundleImport {
+        document,
+        files: written.iter().map(|p| path_string(p)).collect(),
+    })
+}
@@ -0,0 +1,189 @@
+// スクリーンショットの取り込み（OS のツールで撮影し、文書の assets フォルダに保存する）
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::attachments;
+use crate::image_optimize;
+use crate::shell::{self, RunOptions};
+use crate::state::AppState;
+use crate::tr;
+use crate::vault;
+
+/// 撮影ツールを待つ時間（ウィンドウや範囲を選ぶ間も含む）
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 取り込むエラー出力の上限
+const MAX_STDERR: usize = 64 << 10;
+
+/// 撮影の範囲
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureMode {
+    /// 画面全体
+    Screen,
+    /// クリックしたウィンドウ
+    Window,
+    /// ドラッグで選んだ範囲
+    Region,
+}
+
+/// 文書に添付したファイル
+#[derive(Debug, Serialize)]
+pub struct CapturedAttachment {
+    pub path: String,
+    /// 文書に挿入する Markdown
+    pub markdown: String,
+}
+
+/// 撮影に使うコマンドの候補（見つかった最初のものを使う）
+fn commands(mode: CaptureMode, output: &Path) -> Vec<Command> {
+    let command = |program: &str, args: &[&str]| {
+        let mut command = Command::new(program);
+        command.args(args).arg(output);
+        command
+    };
+    if cfg!(target_os = "macos") {
+        let args: &[&str] = match mode {
+            CaptureMode::Screen => &["-x"],
+            CaptureMode::Window => &["-x", "-w"],
+            CaptureMode::Region => &["-x", "-i"],
+        };
+        return vec![command("screencapture", args)];
+    }
+    if cfg!(windows) {
+        let script = format!(
+            "Add-Type -AssemblyName System.Windows.Forms,System.Drawing; \
+             $b = [System.Windows.Forms.SystemInformation]::VirtualScreen; \
+             $bmp = New-Object System.Drawing.Bitmap $b.Width, $b.Height; \
+             [System.Drawing.Graphics]::FromImage($bmp).CopyFromScreen($b.Left, $b.Top, 0, 0, $bmp.Size); \
+             $bmp.Save('{}', [System.Drawing.Imaging.ImageFormat]::Png)",
+            output.display().to_string().replace('\'', "''")
+        );
+        let mut powershell = Command::new("powershell");
+        powershell.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+        return vec![powershell];
+    }
+    // Linux（GNOME・KDE・wlroots・X11 の順に試す）
+    let mut grim = Command::new("sh");
+    match mode {
+        CaptureMode::Screen => {
+            grim.args(["-c", "grim \"$1\"", "sh"]).arg(output);
+        }
+        _ => {
+            grim.args(["-c", "grim -g \"$(slurp)\" \"$1\"", "sh"])
+                .arg(output);
+        }
+    }
+    match mode {
+        CaptureMode::Screen => vec![
+            command("gnome-screenshot", &["-f"]),
+            command("spectacle", &["-b", "-n", "-f", "-o"]),
+            grim,
+            command("scrot", &["-o"]),
+            command("import", &["-window", "root"]),
+        ],
+        CaptureMode::Window => vec![
+            command("gnome-screenshot", &["-w", "-f"]),
+            command("spectacle", &["-b", "-n", "-a", "-o"]),
+            grim,
+            command("scrot", &["-s", "-o"]),
+            command("import", &[]),
+        ],
+        CaptureMode::Region => vec![
+            command("gnome-screenshot", &["-a", "-f"]),
+            command("spectacle", &["-b", "-n", "-r", "-o"]),
+            grim,
+            command("scrot", &["-s", "-o"]),
+            command("import", &[]),
+        ],
+    }
+}
+
+/// 撮影して PNG を返す（キャンセルされた場合はエラー）
+fn capture(mode: CaptureMode, output: &Path) -> Result<Vec<u8>, String> {
+    if cfg!(windows) && mode != CaptureMode::Screen {
+        return Err(tr!(
+            "Window and region capture are not supported on Windows"
+        ));
+    }
+    for command in commands(mode, output) {
+        let name = command.get_program().to_string_lossy().into_owned();
+        let options = RunOptions {
+            timeout: CAPTURE_TIMEOUT,
+            max_stdout: MAX_STDERR,
+            max_stderr: MAX_STDERR,
+            ..Default::default()
+        };
+        let Some(result) = shell::run_if_found(command, &name, options)? else {
+            continue;
+        };
+        let Some(status) = result.status else {
+            return Err(tr!(
+                "{} timed out after {} s",
+                name,
+                CAPTURE_TIMEOUT.as_secs()
+            ));
+        };
+        match fs::read(output) {
+            Ok(data) if !data.is_empty() => return Ok(data),
+            _ if status.success() => return Err(tr!("Screenshot was cancelled")),
+            // sh 経由の grim・slurp が見つからない場合は次を試す
+            _ if status.code() == Some(127) => continue,
+            _ => {
+                return Err(tr!(
+                    "Screenshot failed: {}",
+                    String::from_utf8_lossy(&result.stderr).trim()
+                ))
+            }
+        }
+    }
+    Err(tr!(
+        "No screenshot tool found (install gnome-screenshot, spectacle, grim, scrot or ImageMagick)"
+    ))
+}
+
+/// スクリーンショットを撮って文書の assets フォルダに保存し、埋め込み用の Markdown を返す
+///
+/// 撮影の間はエディタのウィンドウを隠す（メインスレッドを止めないように別スレッドで実行する）。
+/// 保存には画像の最適化の設定を使う。
+#[tauri::command(async)]
+pub fn capture_screenshot(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    document_path: String,
+    mode: CaptureMode,
+) -> Result<CapturedAttachment, String> {
+    let doc = vault::normalize(Path::new(&document_path));
+    let stamp = Local::now().format("%Y%m%d-%H%M%S");
+    let temp = std::env::temp_dir().join(format!(
+        "mdvim-screenshot-{}-{stamp}.png",
+        std::process::id()
+    ));
+    let window = app.get_webview_window("main");
+    if let Some(window) = &window {
+        let _ = window.hide();
+        // ウィンドウが消えるのを待つ
+        thread::sleep(Duration::from_millis(300));
+    }
+    let result = capture(mode, &temp);
+    if let Some(window) = &window {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    let _ = fs::remove_file(&temp);
+    let data = result?;
+    let target = attachments::assets_dir(&doc).join(format!("screenshot-{stamp}.png"));
+    let saved = image_optimize::write_image(&state.image_optimize.get(), &target, &data)?;
+    Ok(CapturedAttachment {
+        markdown: attachments::embed_link(&doc, Path::new(&saved.path), "screenshot"),
+        path: saved.path,
+    })
+}
@@ -0,0 +1,319 @@
+// ```chart のブロックの SVG（JavaScript を使えないエクスポート用の棒・折れ線・円グラフ）
+//
+// plotters はビルドの依存に含まれていないため、軸・目盛り・凡例を含めて SVG を直接組み立てる。
+// 対応するのは棒・折れ線・円の 3 種類だけで、対数軸・日付の軸・軸の範囲の指定はない。文字の幅は測らないので、
+// 長いラベルは重なることがある（`MAX_LABELS` より多いラベルは間引く）。
+
+use std::fmt::Write;
+
+use serde_json::Value;
+
+use crate::data_block::{DataBlockSpec, DataSet};
+use crate::text::escape_html;
+
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 360.0;
+/// 描画範囲の余白（上・右・下・左）
+const MARGIN: (f64, f64, f64, f64) = (40.0, 24.0, 56.0, 64.0);
+/// 横軸のラベルの数の上限（多ければ間引く）
+const MAX_LABELS: usize = 12;
+/// 目盛りの数の目安
+const TICKS: usize = 5;
+const PALETTE: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+];
+
+fn color(i: usize) -> &'static str {
+    PALETTE[i % PALETTE.len()]
+}
+
+/// 目盛りの数値（余分な 0 を付けない）
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        return format!("{}", n as i64);
+    }
+    let text = format!("{n:.2}");
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn label(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// 1・2・5 の倍数の目盛りの間隔
+fn nice_step(range: f64) -> f64 {
+    let raw = range / TICKS as f64;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .find(|m| m * magnitude >= raw)
+        .unwrap_or(10.0);
+    step * magnitude
+}
+
+/// 値の範囲から縦軸の範囲と間隔（0 を含める）
+fn axis(values: impl Iterator<Item = f64>) -> (f64, f64, f64) {
+    let (min, max) = values.fold((0.0f64, 0.0f64), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    let max = if max == min { min + 1.0 } else { max };
+    let step = nice_step(max - min);
+    (
+        (min / step).floor() * step,
+        (max / step).ceil() * step,
+        step,
+    )
+}
+
+/// 系列（列名とセルごとの値）
+struct Series<'a> {
+    name: &'a str,
+    values: Vec<Option<f64>>,
+}
+
+fn series<'a>(data: &'a DataSet, columns: &[usize]) -> Vec<Series<'a>> {
+    columns
+        .iter()
+        .map(|&i| Series {
+            name: &data.columns[i],
+            values: data.rows.iter().map(|row| row[i].as_f64()).collect(),
+        })
+        .collect()
+}
+
+fn open(svg: &mut String, title: Option<&str>) {
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {WIDTH} {HEIGHT}\" \
+         width=\"{WIDTH}\" height=\"{HEIGHT}\" font-family=\"sans-serif\" font-size=\"12\" role=\"img\">"
+    );
+    if let Some(title) = title {
+        let title = escape_html(title);
+        let _ = writeln!(svg, "<title>{title}</title>");
+        let _ = writeln!(
+            svg,
+            "<text x=\"{}\" y=\"22\" text-anchor=\"middle\" font-size=\"15\" font-weight=\"bold\">{title}</text>",
+            WIDTH / 2.0
+        );
+    }
+}
+
+/// 右上の凡例（系列が 2 つ以上のとき）
+fn legend(svg: &mut String, names: &[&str]) {
+    if names.len() < 2 {
+        return;
+    }
+    for (i, name) in names.iter().enumerate() {
+        let y = MARGIN.0 + i as f64 * 18.0;
+        let x = WIDTH - MARGIN.1 - 120.0;
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{x}\" y=\"{y}\" width=\"12\" height=\"12\" fill=\"{}\"/>\
+             <text x=\"{}\" y=\"{}\">{}</text>",
+            color(i),
+            x + 18.0,
+            y + 10.0,
+            escape_html(name)
+        );
+    }
+}
+
+/// 軸・目盛り・横軸のラベルを描き、値から y 座標への変換を返す
+fn axes(svg: &mut String, labels: &[String], all: &[Series], band: bool) -> impl Fn(f64) -> f64 {
+    let (top, right, bottom, left) = MARGIN;
+    let (plot_bottom, plot_height) = (HEIGHT - bottom, HEIGHT - top - bottom);
+    let (lo, hi, step) = axis(all.iter().flat_map(|s| s.values.iter().flatten().copied()));
+    let y = move |v: f64| plot_bottom - (v - lo) / (hi - lo) * plot_height;
+
+    let mut tick = lo;
+    while tick <= hi + step / 2.0 {
+        let ty = y(tick);
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{left}\" y1=\"{ty:.1}\" x2=\"{}\" y2=\"{ty:.1}\" stroke=\"#ddd\"/>\
+             <text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+            WIDTH - right,
+            left - 6.0,
+            ty + 4.0,
+            format_number(tick)
+        );
+        tick += step;
+    }
+    let _ = writeln!(
+        svg,
+        "<line x1=\"{left}\" y1=\"{:.1}\" x2=\"{}\" y2=\"{:.1}\" stroke=\"#333\"/>",
+        y(0.0f64.clamp(lo, hi)),
+        WIDTH - right,
+        y(0.0f64.clamp(lo, hi))
+    );
+
+    let every = labels.len().div_ceil(MAX_LABELS).max(1);
+    for (i, text) in labels.iter().enumerate().step_by(every) {
+        let x = x_position(i, labels.len(), band);
+        let _ = writeln!(
+            svg,
+            "<text x=\"{x:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+            plot_bottom + 18.0,
+            escape_html(text)
+        );
+    }
+    y
+}
+
+/// `i` 番目の項目の中心の x 座標（`band` なら棒の幅の中央）
+fn x_position(i: usize, count: usize, band: bool) -> f64 {
+    let (left, width) = (MARGIN.3, WIDTH - MARGIN.1 - MARGIN.3);
+    if band {
+        left + (i as f64 + 0.5) * width / count.max(1) as f64
+    } else if count <= 1 {
+        left + width / 2.0
+    } else {
+        left + i as f64 * width / (count - 1) as f64
+    }
+}
+
+fn bar(svg: &mut String, labels: &[String], all: &[Series]) {
+    let y = axes(svg, labels, all, true);
+    let band = (WIDTH - MARGIN.1 - MARGIN.3) / labels.len().max(1) as f64;
+    let width = band * 0.8 / all.len() as f64;
+    for (s, series) in all.iter().enumerate() {
+        for (i, value) in series.values.iter().enumerate() {
+            let Some(value) = value else {
+                continue;
+            };
+            let x = x_position(i, labels.len(), true) - band * 0.4 + s as f64 * width;
+            let (a, b) = (y(*value), y(0.0));
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{x:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\">\
+                 <title>{}: {}</title></rect>",
+                a.min(b),
+                width.max(1.0),
+                (a - b).abs(),
+                color(s),
+                escape_html(&labels[i]),
+                format_number(*value)
+            );
+        }
+    }
+}
+
+fn line(svg: &mut String, labels: &[String], all: &[Series]) {
+    let y = axes(svg, labels, all, false);
+    for (s, series) in all.iter().enumerate() {
+        // 値のないセルで線を切る
+        let mut segment: Vec<String> = Vec::new();
+        let flush = |segment: &mut Vec<String>, svg: &mut String| {
+            if segment.len() > 1 {
+                let _ = writeln!(
+                    svg,
+                    "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>",
+                    segment.join(" "),
+                    color(s)
+                );
+            }
+            segment.clear();
+        };
+        for (i, value) in series.values.iter().enumerate() {
+            let Some(value) = value else {
+                flush(&mut segment, svg);
+                continue;
+            };
+            let (px, py) = (x_position(i, labels.len(), false), y(*value));
+            segment.push(format!("{px:.1},{py:.1}"));
+            let _ = writeln!(
+                svg,
+                "<circle cx=\"{px:.1}\" cy=\"{py:.1}\" r=\"3\" fill=\"{}\">\
+                 <title>{}: {}</title></circle>",
+                color(s),
+                escape_html(&labels[i]),
+                format_number(*value)
+            );
+        }
+        flush(&mut segment, svg);
+    }
+}
+
+/// 最初の系列の円グラフ（正の値だけ）と、割合付きの凡例
+fn pie(svg: &mut String, labels: &[String], series: &Series) {
+    let slices: Vec<(usize, f64)> = series
+        .values
+        .iter()
+        .enumerate()
+        .filter_map(|(i, v)| v.filter(|v| *v > 0.0).map(|v| (i, v)))
+        .collect();
+    let total: f64 = slices.iter().map(|(_, v)| v).sum();
+    if total <= 0.0 {
+        return;
+    }
+    let radius = (HEIGHT - MARGIN.0 - 24.0) / 2.0;
+    let (cx, cy) = (MARGIN.3 + radius + 24.0, MARGIN.0 + radius);
+    let point = |angle: f64| (cx + radius * angle.sin(), cy - radius * angle.cos());
+    let mut angle = 0.0f64;
+    for (n, &(i, value)) in slices.iter().enumerate() {
+        let title = format!(
+            "{}: {} ({:.1}%)",
+            labels[i],
+            format_number(value),
+            value / total * 100.0
+        );
+        let sweep = value / total * std::f64::consts::TAU;
+        if slices.len() == 1 {
+            let _ = writeln!(
+                svg,
+                "<circle cx=\"{cx:.1}\" cy=\"{cy:.1}\" r=\"{radius:.1}\" fill=\"{}\"><title>{}</title></circle>",
+                color(n),
+                escape_html(&title)
+            );
+        } else {
+            let (x1, y1) = point(angle);
+            let (x2, y2) = point(angle + sweep);
+            let large = if sweep > std::f64::consts::PI { 1 } else { 0 };
+            let _ = writeln!(
+                svg,
+                "<path d=\"M{cx:.1},{cy:.1} L{x1:.1},{y1:.1} A{radius:.1},{radius:.1} 0 {large} 1 {x2:.1},{y2:.1} Z\" \
+                 fill=\"{}\" stroke=\"#fff\"><title>{}</title></path>",
+                color(n),
+                escape_html(&title)
+            );
+        }
+        let ly = MARGIN.0 + n as f64 * 18.0;
+        let lx = cx + radius + 40.0;
+        if ly < HEIGHT - 12.0 {
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{lx:.1}\" y=\"{ly:.1}\" width=\"12\" height=\"12\" fill=\"{}\"/>\
+                 <text x=\"{:.1}\" y=\"{:.1}\">{}</text>",
+                color(n),
+                lx + 18.0,
+                ly + 10.0,
+                escape_html(&title)
+            );
+        }
+        angle += sweep;
+    }
+}
+
+/// グラフの SVG（`x` は横軸の列、`y` は値の列）
+pub fn svg(spec: &DataBlockSpec, data: &DataSet, x: usize, y: &[usize]) -> String {
+    let labels: Vec<String> = data.rows.iter().map(|row| label(&row[x])).collect();
+    let all = series(data, y);
+    let mut svg = String::new();
+    open(&mut svg, spec.title.as_deref());
+    match spec.chart.as_str() {
+        "pie" => pie(&mut svg, &labels, &all[0]),
+        kind => {
+            if kind == "line" {
+                line(&mut svg, &labels, &all);
+            } else {
+                bar(&mut svg, &labels, &all);
+            }
+            let names: Vec<&str> = all.iter().map(|s| s.name).collect();
+            legend(&mut svg, &names);
+        }
+    }
+    svg.push_str("</svg>");
+    svg
+}
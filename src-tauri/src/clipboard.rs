@@ -0,0 +1,302 @@
+// OS のクリップボードの形式の判定（テキスト・HTML・画像・ファイルの一覧）
+
+use std::process::Command;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::html_markdown::html_to_markdown;
+use crate::images;
+use crate::links;
+use crate::shell::{self, RunOptions, RunOutput};
+use crate::tr;
+
+/// Windows でクリップボードの内容を JSON で出力する PowerShell
+const WINDOWS_SCRIPT: &str = r#"
+[Console]::OutputEncoding = [Text.Encoding]::UTF8
+Add-Type -AssemblyName System.Windows.Forms, System.Drawing
+$c = [Windows.Forms.Clipboard]
+$o = @{}
+if ($c::ContainsText()) { $o.text = $c::GetText() }
+if ($c::ContainsText([Windows.Forms.TextDataFormat]::Html)) { $o.html = $c::GetText([Windows.Forms.TextDataFormat]::Html) }
+if ($c::ContainsImage()) {
+  $m = New-Object IO.MemoryStream
+  $c::GetImage().Save($m, [Drawing.Imaging.ImageFormat]::Png)
+  $o.image = [Convert]::ToBase64String($m.ToArray())
+}
+if ($c::ContainsFileDropList()) { $o.files = @($c::GetFileDropList()) }
+$o | ConvertTo-Json -Compress
+"#;
+
+/// クリップボードの画像
+#[derive(Debug, Serialize)]
+pub struct ClipboardImage {
+    /// PNG の Base64（`save_image` にそのまま渡せる）
+    pub data: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// クリップボードの内容
+#[derive(Debug, Default, Serialize)]
+pub struct ClipboardContents {
+    /// 利用できる形式（"text" | "html" | "image" | "files"）
+    pub formats: Vec<String>,
+    pub text: Option<String>,
+    pub html: Option<String>,
+    /// HTML を Markdown に変換したもの
+    pub markdown: Option<String>,
+    pub image: Option<ClipboardImage>,
+    /// コピーされたファイルのパス
+    pub files: Vec<String>,
+}
+
+/// OS から読み取った内容
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct RawClipboard {
+    text: Option<String>,
+    html: Option<String>,
+    #[serde(skip)]
+    image: Option<Vec<u8>>,
+    #[serde(rename = "image")]
+    image_base64: Option<String>,
+    files: Vec<String>,
+}
+
+/// クリップボードを読むコマンドを待つ時間（持ち主のアプリが応答しない場合に備える）
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 読み込む内容の上限（画像を含む）
+const MAX_CONTENT: usize = 64 << 20;
+
+/// 取り込むエラー出力の上限
+const MAX_STDERR: usize = 64 << 10;
+
+/// コマンドを実行する（タイムアウトしたら `status` は `None`）
+fn output(program: &str, args: &[&str]) -> Result<RunOutput, String> {
+    let mut command = Command::new(program);
+    command.args(args);
+    shell::run(
+        command,
+        program,
+        RunOptions {
+            timeout: READ_TIMEOUT,
+            max_stdout: MAX_CONTENT,
+            max_stderr: MAX_STDERR,
+            ..Default::default()
+        },
+    )
+}
+
+/// コマンドを実行して標準出力を返す（失敗したら `None`）
+fn run(program: &str, args: &[&str]) -> Option<Vec<u8>> {
+    let output = output(program, args).ok()?;
+    (output.status.is_some_and(|s| s.success())
+        && !output.stdout_truncated
+        && !output.stdout.is_empty())
+    .then_some(output.stdout)
+}
+
+fn utf8(data: Vec<u8>) -> String {
+    String::from_utf8_lossy(&data).into_owned()
+}
+
+/// `file://` の URI の一覧（`text/uri-list`・GNOME の `copy\nfile://…`）
+fn uri_list(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| line.trim().strip_prefix("file://"))
+        .map(|path| links::percent_decode(path.strip_prefix("localhost").unwrap_or(path)))
+        .collect()
+}
+
+/// Linux（Wayland は wl-paste、X11 は xclip）
+fn read_linux() -> Result<RawClipboard, String> {
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    let (program, list, get): (&str, &[&str], &[&str]) = if wayland {
+        ("wl-paste", &["--list-types"], &["--no-newline", "--type"])
+    } else {
+        (
+            "xclip",
+            &["-selection", "clipboard", "-t", "TARGETS", "-o"],
+            &["-selection", "clipboard", "-o", "-t"],
+        )
+    };
+    let output = output(program, list)
+        .map_err(|e| tr!("{e}. Install wl-clipboard or xclip to read the clipboard."))?;
+    if output.status.is_none() {
+        return Err(tr!(
+            "{} timed out after {} s",
+            program,
+            READ_TIMEOUT.as_secs()
+        ));
+    }
+    let types = utf8(output.stdout);
+    // 空のクリップボード
+    if !output.status.is_some_and(|s| s.success()) || types.trim().is_empty() {
+        return Ok(RawClipboard::default());
+    }
+    let types: Vec<&str> = types.lines().map(str::trim).collect();
+    let read = |mime: &str| {
+        let mut args = get.to_vec();
+        args.push(mime);
+        run(program, &args)
+    };
+    let first = |candidates: &[&'static str]| -> Option<&'static str> {
+        candidates.iter().find(|c| types.contains(c)).copied()
+    };
+    let mut raw = RawClipboard::default();
+    if let Some(mime) = first(&[
+        "text/plain;charset=utf-8",
+        "UTF8_STRING",
+        "text/plain",
+        "STRING",
+    ]) {
+        raw.text = read(mime).map(utf8);
+    }
+    if types.contains(&"text/html") {
+        raw.html = read("text/html").map(utf8);
+    }
+    if types.contains(&"image/png") {
+        raw.image = read("image/png");
+    }
+    if let Some(mime) = first(&["x-special/gnome-copied-files", "text/uri-list"]) {
+        raw.files = read(mime).map(|d| uri_list(&utf8(d))).unwrap_or_default();
+    }
+    Ok(raw)
+}
+
+/// `«data HTML3C68…»` の 16 進のデータ
+fn applescript_data(output: &str) -> Option<Vec<u8>> {
+    let start = output.find("«data ")? + "«data ".len();
+    let hex = output[start..].get(4..)?;
+    let hex = &hex[..hex.find('»')?];
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// macOS（pbpaste と AppleScript）
+fn read_macos() -> Result<RawClipboard, String> {
+    let info = run("osascript", &["-e", "clipboard info"])
+        .map(utf8)
+        .unwrap_or_default();
+    let fetch = |class: &str| {
+        let script = format!("the clipboard as «class {class}»");
+        run("osascript", &["-e", &script]).and_then(|out| applescript_data(&utf8(out)))
+    };
+    let mut raw = RawClipboard::default();
+    if info.contains("«class utf8»") || info.contains("string") {
+        raw.text = run("pbpaste", &[]).map(utf8);
+    }
+    if info.contains("«class HTML»") {
+        raw.html = fetch("HTML").map(utf8);
+    }
+    if info.contains("«class PNGf»") {
+        raw.image = fetch("PNGf");
+    }
+    if info.contains("«class furl»") {
+        raw.files = run(
+            "osascript",
+            &["-e", "POSIX path of (the clipboard as «class furl»)"],
+        )
+        .map(|out| vec![utf8(out).trim().to_string()])
+        .unwrap_or_default();
+    }
+    Ok(raw)
+}
+
+/// CF_HTML のヘッダー（`StartHTML:` などのバイト位置）を除く
+fn strip_cf_html(html: &str) -> String {
+    let offset = |key: &str| {
+        let start = html.find(key)? + key.len();
+        let digits: String = html[start..]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect();
+        digits.parse::<usize>().ok()
+    };
+    match (offset("StartHTML:"), offset("EndHTML:")) {
+        (Some(start), Some(end)) if start < end && end <= html.len() => {
+            String::from_utf8_lossy(&html.as_bytes()[start..end]).into_owned()
+        }
+        _ => html[html.find('<').unwrap_or(0)..].to_string(),
+    }
+}
+
+/// Windows（PowerShell と System.Windows.Forms）
+fn read_windows() -> Result<RawClipboard, String> {
+    let output = output(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-NonInteractive",
+            "-STA",
+            "-Command",
+            WINDOWS_SCRIPT,
+        ],
+    )?;
+    if output.status.is_none() {
+        return Err(tr!(
+            "{} timed out after {} s",
+            "PowerShell",
+            READ_TIMEOUT.as_secs()
+        ));
+    }
+    let json = utf8(output.stdout);
+    if json.trim().is_empty() {
+        return Ok(RawClipboard::default());
+    }
+    let mut raw: RawClipboard =
+        serde_json::from_str(json.trim()).map_err(|e| tr!("Failed to read the clipboard: {e}"))?;
+    raw.html = raw.html.as_deref().map(strip_cf_html);
+    raw.image = raw
+        .image_base64
+        .take()
+        .and_then(|data| STANDARD.decode(data.trim()).ok());
+    Ok(raw)
+}
+
+/// クリップボードの形式とその内容（テキストの貼り付け・HTML の変換・画像の保存を選ぶため）
+#[tauri::command]
+pub fn get_clipboard_contents() -> Result<ClipboardContents, String> {
+    let raw = if cfg!(target_os = "macos") {
+        read_macos()?
+    } else if cfg!(windows) {
+        read_windows()?
+    } else {
+        read_linux()?
+    };
+    let mut contents = ClipboardContents {
+        text: raw.text.filter(|t| !t.is_empty()),
+        html: raw.html.filter(|h| !h.trim().is_empty()),
+        files: raw.files,
+        ..Default::default()
+    };
+    if contents.text.is_some() {
+        contents.formats.push("text".to_string());
+    }
+    if let Some(html) = &contents.html {
+        contents.formats.push("html".to_string());
+        contents.markdown = Some(html_to_markdown(html, None));
+    }
+    if let Some(data) = raw.image.filter(|d| !d.is_empty()) {
+        contents.formats.push("image".to_string());
+        let (width, height) = match images::probe_bytes(&data) {
+            Some((_, width, height)) => (width, height),
+            None => (None, None),
+        };
+        contents.image = Some(ClipboardImage {
+            data: STANDARD.encode(&data),
+            width,
+            height,
+        });
+    }
+    if !contents.files.is_empty() {
+        contents.formats.push("files".to_string());
+    }
+    Ok(contents)
+}
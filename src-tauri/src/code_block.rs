@@ -0,0 +1,138 @@
+// コードブロックの情報文字列（```rust:main.rs {3-5} のファイル名・強調行・行番号）
+
+use std::ops::RangeInclusive;
+
+use crate::text::escape_html;
+
+/// 情報文字列の内容
+#[derive(Debug, Default, PartialEq)]
+pub struct CodeInfo {
+    pub lang: String,
+    pub filename: Option<String>,
+    /// 強調する行（1 始まり）
+    pub highlight: Vec<RangeInclusive<usize>>,
+    pub line_numbers: bool,
+}
+
+impl CodeInfo {
+    /// 言語名以外の指定があるか（なければ通常のコードブロックとして描画する）
+    pub fn is_extended(&self) -> bool {
+        self.filename.is_some() || !self.highlight.is_empty() || self.line_numbers
+    }
+
+    fn highlighted(&self, line: usize) -> bool {
+        self.highlight.iter().any(|r| r.contains(&line))
+    }
+}
+
+/// `{1,3-5}` の行の指定
+fn parse_ranges(spec: &str) -> Vec<RangeInclusive<usize>> {
+    spec.split(',')
+        .filter_map(|part| {
+            let part = part.trim();
+            match part.split_once('-') {
+                Some((a, b)) => {
+                    let (a, b): (usize, usize) = (a.trim().parse().ok()?, b.trim().parse().ok()?);
+                    Some(a.min(b)..=a.max(b))
+                }
+                None => part.parse().ok().map(|n| n..=n),
+            }
+        })
+        .collect()
+}
+
+/// 情報文字列を解析する（`lang:filename`・`{行}`・`showLineNumbers`・`title="..."`）
+pub fn parse_info(info: &str) -> CodeInfo {
+    let mut code = CodeInfo::default();
+    let info = info.trim();
+    let mut rest = info;
+    // `{...}` は空白を含むことがあるので先に取り出す
+    let mut words = Vec::new();
+    while !rest.is_empty() {
+        rest = rest.trim_start();
+        if let Some(inner) = rest.strip_prefix('{') {
+            let end = inner.find('}').unwrap_or(inner.len());
+            code.highlight.extend(parse_ranges(&inner[..end]));
+            rest = inner.get(end + 1..).unwrap_or("");
+            continue;
+        }
+        if let Some(value) = rest.strip_prefix("title=\"") {
+            let end = value.find('"').unwrap_or(value.len());
+            code.filename = Some(value[..end].to_string());
+            rest = value.get(end + 1..).unwrap_or("");
+            continue;
+        }
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '{')
+            .unwrap_or(rest.len());
+        words.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    let mut words = words.into_iter().filter(|w| !w.is_empty());
+    if let Some(first) = words.next() {
+        match first.split_once(':') {
+            Some((lang, filename)) => {
+                code.lang = lang.to_string();
+                if !filename.is_empty() {
+                    code.filename = Some(filename.to_string());
+                }
+            }
+            None => code.lang = first.to_string(),
+        }
+    }
+    for word in words {
+        match word {
+            "showLineNumbers" | "linenos" | "line-numbers" => code.line_numbers = true,
+            _ => {
+                if let Some(value) = word.strip_prefix("title=") {
+                    code.filename = Some(value.trim_matches('"').to_string());
+                }
+            }
+        }
+    }
+    if !code.highlight.is_empty() {
+        code.line_numbers = true;
+    }
+    code
+}
+
+/// ファイル名・行番号・強調行の付いたコードブロックの HTML
+pub fn code_block_html(info: &CodeInfo, code: &str) -> String {
+    let lang = escape_html(&info.lang);
+    let mut out = format!("<figure class=\"code-block\" data-lang=\"{lang}\"");
+    if let Some(filename) = &info.filename {
+        out.push_str(&format!(" data-filename=\"{}\"", escape_html(filename)));
+    }
+    out.push_str(">\n");
+    if let Some(filename) = &info.filename {
+        out.push_str(&format!(
+            "<figcaption class=\"code-filename\">{}</figcaption>\n",
+            escape_html(filename)
+        ));
+    }
+    let class = if info.line_numbers {
+        " class=\"line-numbers\""
+    } else {
+        ""
+    };
+    out.push_str(&format!("<pre{class}><code"));
+    if !lang.is_empty() {
+        out.push_str(&format!(" class=\"language-{lang}\""));
+    }
+    out.push('>');
+    let code = code.strip_suffix('\n').unwrap_or(code);
+    for (i, line) in code.split('\n').enumerate() {
+        let number = i + 1;
+        let highlighted = if info.highlighted(number) {
+            " highlighted"
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            "<span class=\"line{highlighted}\" data-line=\"{number}\">{}</span>\n",
+            escape_html(line)
+        ));
+    }
+    out.push_str("</code></pre>\n</figure>\n");
+    out
+}
@@ -0,0 +1,604 @@
+// 共同編集セッション（LAN 内の TCP 接続、ホストが参加者間の操作を中継する）
+//
+// yrs（Yjs）や automerge はビルドの依存に含まれていないため、文書は `crdt` の RGA で同期する。
+// 同期するのはセッション中の文字単位の操作だけで、オフラインの編集の統合・履歴の保存・Yjs との互換はない。
+//
+// 参加コードは OS の乱数から作る 10 文字（50 ビット）で、コードを間違えたアドレスは数回で締め出す。
+// 接続の数には上限があり、送信は接続ごとのスレッドで時間を区切るので、止まった参加者がセッションを止めることはない。
+// ホストは参加者の ID と名前を接続から決め、参加者が送るメッセージの中の値は使わない。
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{IpAddr, Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::crdt::{Doc, Id, Item, Op, TextEdit};
+use crate::preview_server;
+use crate::state::AppState;
+use crate::tr;
+
+/// 既定の待ち受けポート
+const DEFAULT_PORT: u16 = 47_601;
+/// 参加コードの文字（紛らわしい `I` `O` `0` `1` を除く 32 文字）
+const CODE_ALPHABET: &[u8; 32] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+/// 参加コードの文字数
+const CODE_LEN: usize = 10;
+/// 同時に扱う接続の上限（参加の手続き中のものを含む）
+const MAX_CONNECTIONS: usize = 16;
+/// アドレスごとに参加コードを間違えられる回数（超えたアドレスからの接続は断る）
+const MAX_FAILED_ATTEMPTS: u32 = 5;
+/// 参加コードを間違えたときに応答を遅らせる時間
+const REJECT_DELAY: Duration = Duration::from_millis(500);
+/// 参加の手続きの待ち時間
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// 1 回の送信の待ち時間（超えた接続は切る）
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// 接続ごとの送信待ちのメッセージの上限（超えた接続は切る）
+const SEND_QUEUE: usize = 1024;
+
+/// セッションの参加者
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerInfo {
+    pub site: u64,
+    pub name: String,
+}
+
+/// 接続間でやり取りするメッセージ（1 行 1 JSON）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    Hello {
+        code: String,
+        site: u64,
+        name: String,
+    },
+    Welcome {
+        items: Vec<Item>,
+        peers: Vec<PeerInfo>,
+    },
+    Reject {
+        reason: String,
+    },
+    Ops {
+        ops: Vec<Op>,
+    },
+    Cursor {
+        site: u64,
+        name: String,
+        /// カーソル直前の文字
+        anchor: Option<Id>,
+    },
+    Peers {
+        peers: Vec<PeerInfo>,
+    },
+}
+
+/// 接続先 1 つ（送信は専用のスレッドが行う）
+struct Connection {
+    site: u64,
+    queue: SyncSender<String>,
+    stream: TcpStream,
+}
+
+impl Connection {
+    /// 送信用のスレッドを起動する（送れなくなったら接続を切り、受信側も終わらせる）
+    fn new(site: u64, stream: TcpStream) -> std::io::Result<Self> {
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        let mut writer = stream.try_clone()?;
+        let (queue, lines) = mpsc::sync_channel::<String>(SEND_QUEUE);
+        thread::spawn(move || {
+            for line in lines {
+                if writer.write_all(line.as_bytes()).is_err() {
+                    break;
+                }
+            }
+            let _ = writer.shutdown(Shutdown::Both);
+        });
+        Ok(Self {
+            site,
+            queue,
+            stream,
+        })
+    }
+
+    /// 送信待ちに加える（詰まっている接続は切る）
+    fn send(&self, line: &str) {
+        if self.queue.try_send(line.to_string()).is_err() {
+            let _ = self.stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+struct Session {
+    site: u64,
+    name: String,
+    host: bool,
+    doc: Mutex<Doc>,
+    /// ホストでは参加者への接続、参加者ではホストへの接続
+    connections: Mutex<Vec<Connection>>,
+    peers: Mutex<Vec<PeerInfo>>,
+    stopped: AtomicBool,
+    port: u16,
+    /// ホストで処理中の接続の数
+    active: AtomicUsize,
+    /// ホストで参加コードを間違えた回数（アドレスごと）
+    failures: Mutex<HashMap<IpAddr, u32>>,
+}
+
+/// 共同編集の状態
+#[derive(Default)]
+pub struct CollabState {
+    session: Mutex<Option<Arc<Session>>>,
+}
+
+/// ホストとして開始したセッションの情報
+#[derive(Debug, Serialize)]
+pub struct CollabHostInfo {
+    /// 参加者が入力する参加コード
+    pub code: String,
+    pub port: u16,
+    /// 参加者が接続するアドレス
+    pub address: Option<String>,
+}
+
+/// 参加したセッションの内容
+#[derive(Debug, Serialize)]
+pub struct CollabJoinInfo {
+    /// ホストの文書（エディタの内容をこれで置き換える）
+    pub content: String,
+    pub peers: Vec<PeerInfo>,
+}
+
+/// `collab-remote-edit` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteEdit {
+    /// 順に適用する変更
+    pub edits: Vec<TextEdit>,
+}
+
+/// `collab-cursor` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteCursor {
+    pub site: u64,
+    pub name: String,
+    /// UTF-16 単位の位置
+    pub offset: usize,
+}
+
+/// `collab-ended` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct CollabEnded {
+    pub reason: String,
+}
+
+/// OS の乱数
+pub fn random_u64() -> Result<u64, String> {
+    getrandom::u64().map_err(|e| tr!("Failed to generate random number: {e}"))
+}
+
+/// 参加コード（`XXXXX-XXXXX`）
+fn session_code() -> Result<String, String> {
+    let mut bytes = [0u8; CODE_LEN];
+    getrandom::fill(&mut bytes).map_err(|e| tr!("Failed to generate random number: {e}"))?;
+    let code: String = bytes
+        .iter()
+        .map(|b| CODE_ALPHABET[(b & 31) as usize] as char)
+        .collect();
+    Ok(format!(
+        "{}-{}",
+        &code[..CODE_LEN / 2],
+        &code[CODE_LEN / 2..]
+    ))
+}
+
+/// 照合用の参加コード（区切りと大文字小文字を無視）
+fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn encode(message: &Message) -> String {
+    let mut line = serde_json::to_string(message).unwrap_or_default();
+    line.push('\n');
+    line
+}
+
+/// 参加の手続きのメッセージを直接送る
+fn send(stream: &mut TcpStream, message: &Message) -> std::io::Result<()> {
+    stream.write_all(encode(message).as_bytes())
+}
+
+fn read_message(reader: &mut impl BufRead) -> Option<Message> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        // 解釈できない行は読み飛ばす
+        if let Ok(message) = serde_json::from_str(&line) {
+            return Some(message);
+        }
+    }
+}
+
+impl Session {
+    /// `except` 以外の接続先へ送る
+    fn broadcast(&self, message: &Message, except: Option<u64>) {
+        let line = encode(message);
+        for connection in self.connections.lock().unwrap().iter() {
+            if Some(connection.site) != except {
+                connection.send(&line);
+            }
+        }
+    }
+
+    /// 受け取ったメッセージを適用し、ホストなら他の参加者へ中継
+    fn handle(&self, app: &AppHandle, from: u64, message: Message) {
+        match message {
+            Message::Ops { mut ops } => {
+                if self.host {
+                    // 参加者が挿入できるのは自分の ID の文字だけ
+                    ops.retain(|op| match op {
+                        Op::Insert { id, .. } => id.site == from,
+                        Op::Delete { .. } => true,
+                    });
+                }
+                let edits = self.doc.lock().unwrap().apply_remote(ops.clone());
+                if !edits.is_empty() {
+                    let _ = app.emit("collab-remote-edit", RemoteEdit { edits });
+                }
+                if self.host {
+                    self.broadcast(&Message::Ops { ops }, Some(from));
+                }
+            }
+            Message::Cursor { site, name, anchor } => {
+                // ホストは送り手を接続から決める
+                let (site, name) = if self.host {
+                    let peers = self.peers.lock().unwrap();
+                    match peers.iter().find(|p| p.site == from) {
+                        Some(peer) => (peer.site, peer.name.clone()),
+                        None => return,
+                    }
+                } else {
+                    (site, name)
+                };
+                let offset = self.doc.lock().unwrap().offset_of_anchor(anchor);
+                let _ = app.emit(
+                    "collab-cursor",
+                    RemoteCursor {
+                        site,
+                        name: name.clone(),
+                        offset,
+                    },
+                );
+                if self.host {
+                    self.broadcast(&Message::Cursor { site, name, anchor }, Some(from));
+                }
+            }
+            // 参加者の一覧はホストからだけ受け取る
+            Message::Peers { peers } if !self.host => {
+                *self.peers.lock().unwrap() = peers.clone();
+                let _ = app.emit("collab-peers", peers);
+            }
+            _ => {}
+        }
+    }
+
+    /// 参加者の一覧が変わったことを全員に知らせる（ホストのみ）
+    fn announce_peers(&self, app: &AppHandle) {
+        let peers = self.peers.lock().unwrap().clone();
+        self.broadcast(
+            &Message::Peers {
+                peers: peers.clone(),
+            },
+            None,
+        );
+        let _ = app.emit("collab-peers", peers);
+    }
+
+    fn close(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        for connection in self.connections.lock().unwrap().drain(..) {
+            let _ = connection.stream.shutdown(Shutdown::Both);
+        }
+        if self.host {
+            // accept() の待機を解くため自分に接続する
+            let _ = TcpStream::connect_timeout(
+                &SocketAddr::from((Ipv4Addr::LOCALHOST, self.port)),
+                Duration::from_millis(200),
+            );
+        }
+    }
+}
+
+/// セッションが終わったら状態から外す（新しいセッションに置き換わっていれば何もしない）
+fn end_session(app: &AppHandle, session: &Arc<Session>, reason: &str) {
+    let state = app.state::<AppState>();
+    let mut current = state.collab.session.lock().unwrap();
+    if current.as_ref().is_some_and(|s| Arc::ptr_eq(s, session)) {
+        current.take();
+        drop(current);
+        session.close();
+        let _ = app.emit(
+            "collab-ended",
+            CollabEnded {
+                reason: reason.to_string(),
+            },
+        );
+    }
+}
+
+/// ホスト側で 1 人の参加者との接続を処理
+fn serve_guest(app: &AppHandle, session: &Session, code: &str, stream: TcpStream, ip: IpAddr) {
+    let Ok(read_half) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(read_half);
+    let mut stream = stream;
+    let _ = stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+    let reject = |stream: &mut TcpStream, reason: String| {
+        let _ = send(stream, &Message::Reject { reason });
+    };
+    let (site, name) = match read_message(&mut reader) {
+        Some(Message::Hello {
+            code: given,
+            site,
+            name,
+        }) if normalize_code(&given) == code => (site, name),
+        _ => {
+            *session.failures.lock().unwrap().entry(ip).or_default() += 1;
+            thread::sleep(REJECT_DELAY);
+            return reject(&mut stream, tr!("Invalid session code"));
+        }
+    };
+    let _ = stream.set_read_timeout(None);
+
+    {
+        // 接続の登録まで文書をロックし、その間の操作の取りこぼしを防ぐ
+        let doc = session.doc.lock().unwrap();
+        let mut peers = session.peers.lock().unwrap();
+        if peers.iter().any(|p| p.site == site) {
+            return reject(&mut stream, tr!("Participant ID is already in use"));
+        }
+        let Ok(connection) = Connection::new(site, stream) else {
+            return;
+        };
+        peers.push(PeerInfo {
+            site,
+            name: name.clone(),
+        });
+        connection.send(&encode(&Message::Welcome {
+            items: doc.items().to_vec(),
+            peers: peers.clone(),
+        }));
+        drop(peers);
+        session.connections.lock().unwrap().push(connection);
+    }
+    session.announce_peers(app);
+
+    while let Some(message) = read_message(&mut reader) {
+        session.handle(app, site, message);
+    }
+
+    session
+        .connections
+        .lock()
+        .unwrap()
+        .retain(|c| c.site != site);
+    session.peers.lock().unwrap().retain(|p| p.site != site);
+    if !session.stopped.load(Ordering::SeqCst) {
+        session.announce_peers(app);
+    }
+}
+
+fn leave(state: &CollabState) {
+    if let Some(session) = state.session.lock().unwrap().take() {
+        session.close();
+    }
+}
+
+/// 現在の文書で共同編集セッションを開始（同じネットワークの mdvim から参加できる）
+#[tauri::command]
+pub fn start_collab_session(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    content: String,
+    name: Option<String>,
+    port: Option<u16>,
+) -> Result<CollabHostInfo, String> {
+    leave(&state.collab);
+    let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, port.unwrap_or(DEFAULT_PORT)))
+        .map_err(|e| tr!("Failed to start collaboration session: {e}"))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| tr!("Failed to start collaboration session: {e}"))?
+        .port();
+
+    let site = random_u64()?;
+    let name = name.unwrap_or_else(|| "host".to_string());
+    let mut doc = Doc::new(site);
+    doc.local_edit(0, 0, &content);
+    let code = session_code()?;
+    let session = Arc::new(Session {
+        site,
+        name: name.clone(),
+        host: true,
+        doc: Mutex::new(doc),
+        connections: Mutex::new(Vec::new()),
+        peers: Mutex::new(vec![PeerInfo { site, name }]),
+        stopped: AtomicBool::new(false),
+        port,
+        active: AtomicUsize::new(0),
+        failures: Mutex::new(HashMap::new()),
+    });
+
+    let accept_session = session.clone();
+    let accept_code = normalize_code(&code);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            if accept_session.stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let Ok(peer) = stream.peer_addr() else {
+                continue;
+            };
+            let blocked = accept_session
+                .failures
+                .lock()
+                .unwrap()
+                .get(&peer.ip())
+                .is_some_and(|&n| n >= MAX_FAILED_ATTEMPTS);
+            if blocked || accept_session.active.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+                let _ = stream.shutdown(Shutdown::Both);
+                continue;
+            }
+            accept_session.active.fetch_add(1, Ordering::SeqCst);
+            let (app, session, code) = (app.clone(), accept_session.clone(), accept_code.clone());
+            thread::spawn(move || {
+                serve_guest(&app, &session, &code, stream, peer.ip());
+                session.active.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+
+    *state.collab.session.lock().unwrap() = Some(session);
+    Ok(CollabHostInfo {
+        code,
+        port,
+        address: preview_server::lan_address().map(|ip| format!("{ip}:{port}")),
+    })
+}
+
+/// 他の mdvim が開始したセッションに参加（`address` は `host:port`）
+#[tauri::command(async)]
+pub fn join_collab_session(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    address: String,
+    code: String,
+    name: Option<String>,
+) -> Result<CollabJoinInfo, String> {
+    leave(&state.collab);
+    let address = if address.contains(':') {
+        address
+    } else {
+        format!("{address}:{DEFAULT_PORT}")
+    };
+    let addr = address
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut a| a.next())
+        .ok_or_else(|| tr!("Invalid address: {address}"))?;
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(5))
+        .map_err(|e| tr!("Failed to connect to {address}: {e}"))?;
+
+    let site = random_u64()?;
+    let name = name.unwrap_or_else(|| "guest".to_string());
+    send(
+        &mut stream,
+        &Message::Hello {
+            code: code.trim().to_string(),
+            site,
+            name: name.clone(),
+        },
+    )
+    .map_err(|e| tr!("Failed to connect to {address}: {e}"))?;
+    let mut reader = BufReader::new(
+        stream
+            .try_clone()
+            .map_err(|e| tr!("Failed to connect to {address}: {e}"))?,
+    );
+    let _ = stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT));
+    let (items, peers) = match read_message(&mut reader) {
+        Some(Message::Welcome { items, peers }) => (items, peers),
+        Some(Message::Reject { reason }) => return Err(reason),
+        _ => return Err(tr!("No response from collaboration host")),
+    };
+    let _ = stream.set_read_timeout(None);
+    let connection =
+        Connection::new(0, stream).map_err(|e| tr!("Failed to connect to {address}: {e}"))?;
+
+    let doc = Doc::from_items(site, items);
+    let content = doc.text();
+    let session = Arc::new(Session {
+        site,
+        name,
+        host: false,
+        doc: Mutex::new(doc),
+        connections: Mutex::new(vec![connection]),
+        peers: Mutex::new(peers.clone()),
+        stopped: AtomicBool::new(false),
+        port: 0,
+        active: AtomicUsize::new(0),
+        failures: Mutex::new(HashMap::new()),
+    });
+
+    let reader_session = session.clone();
+    thread::spawn(move || {
+        while let Some(message) = read_message(&mut reader) {
+            reader_session.handle(&app, 0, message);
+        }
+        end_session(&app, &reader_session, &tr!("Disconnected from host"));
+    });
+
+    *state.collab.session.lock().unwrap() = Some(session);
+    Ok(CollabJoinInfo { content, peers })
+}
+
+/// エディタでの編集をセッションに送る（位置は UTF-16 単位）
+#[tauri::command]
+pub fn collab_edit(
+    state: State<'_, AppState>,
+    offset: usize,
+    delete_len: usize,
+    text: String,
+) -> Result<(), String> {
+    let session = state.collab.session.lock().unwrap().clone();
+    let session = session.ok_or_else(|| tr!("Not in a collaboration session"))?;
+    let ops = session
+        .doc
+        .lock()
+        .unwrap()
+        .local_edit(offset, delete_len, &text);
+    if !ops.is_empty() {
+        session.broadcast(&Message::Ops { ops }, None);
+    }
+    Ok(())
+}
+
+/// カーソル位置を他の参加者に知らせる
+#[tauri::command]
+pub fn collab_cursor(state: State<'_, AppState>, offset: usize) -> Result<(), String> {
+    let session = state.collab.session.lock().unwrap().clone();
+    let session = session.ok_or_else(|| tr!("Not in a collaboration session"))?;
+    let anchor = session.doc.lock().unwrap().anchor_at(offset);
+    session.broadcast(
+        &Message::Cursor {
+            site: session.site,
+            name: session.name.clone(),
+            anchor,
+        },
+        None,
+    );
+    Ok(())
+}
+
+/// セッションを終了（ホストが終了すると参加者も切断される）
+#[tauri::command]
+pub fn leave_collab_session(state: State<'_, AppState>) {
+    leave(&state.collab);
+}
@@ -0,0 +1,190 @@
+// コマンドの一覧（コマンドパレットとキー割り当ての編集画面が使う）
+//
+// 組み込みのコマンドに、有効なプラグインのコマンドと設定済みのフックを加える。
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::state::AppState;
+
+/// 組み込みのコマンド（`id`・名前・分類・既定のキー・Ex コマンド）
+type Builtin = (
+    &'static str,
+    &'static str,
+    &'static str,
+    Option<&'static str>,
+    Option<&'static str>,
+);
+
+const BUILTIN_COMMANDS: &[Builtin] = &[
+    ("file.new", "New File", "File", Some("Ctrl+N"), Some("new")),
+    (
+        "file.open",
+        "Open File",
+        "File",
+        Some("Ctrl+O"),
+        Some("edit"),
+    ),
+    ("file.save", "Save", "File", Some("Ctrl+S"), Some("write")),
+    (
+        "file.save_as",
+        "Save As",
+        "File",
+        Some("Ctrl+Shift+S"),
+        None,
+    ),
+    ("file.rename", "Rename File", "File", None, Some("rename")),
+    ("file.quit", "Quit", "File", None, Some("quit")),
+    ("file.quit_all", "Quit All", "File", None, Some("qall")),
+    (
+        "buffer.next",
+        "Next Buffer",
+        "Buffer",
+        Some("Ctrl+Tab"),
+        Some("bnext"),
+    ),
+    (
+        "buffer.previous",
+        "Previous Buffer",
+        "Buffer",
+        Some("Ctrl+Shift+Tab"),
+        Some("bprev"),
+    ),
+    ("buffer.list", "List Buffers", "Buffer", None, Some("ls")),
+    (
+        "buffer.delete",
+        "Close Buffer",
+        "Buffer",
+        None,
+        Some("bdelete"),
+    ),
+    (
+        "history.back",
+        "Go Back",
+        "Navigation",
+        Some("Alt+Left"),
+        None,
+    ),
+    (
+        "history.forward",
+        "Go Forward",
+        "Navigation",
+        Some("Alt+Right"),
+        None,
+    ),
+    (
+        "view.explorer",
+        "Toggle Explorer",
+        "View",
+        Some("Ctrl+E"),
+        Some("explorer"),
+    ),
+    (
+        "view.toc",
+        "Toggle Table of Contents",
+        "View",
+        None,
+        Some("toc"),
+    ),
+    ("view.layout", "Change Layout", "View", None, Some("layout")),
+    ("view.theme", "Change Theme", "View", None, Some("theme")),
+    (
+        "editor.toggle_vim",
+        "Toggle Vim Mode",
+        "Editor",
+        Some("Ctrl+`"),
+        None,
+    ),
+    (
+        "editor.paste_image",
+        "Paste Image",
+        "Editor",
+        None,
+        Some("image"),
+    ),
+    (
+        "search.project",
+        "Search in Project",
+        "Search",
+        Some("Ctrl+Shift+F"),
+        Some("search"),
+    ),
+    ("search.grep", "Grep", "Search", None, Some("grep")),
+    (
+        "project.open",
+        "Open Project",
+        "Project",
+        None,
+        Some("project"),
+    ),
+    (
+        "project.cd",
+        "Change Directory",
+        "Project",
+        None,
+        Some("cd"),
+    ),
+    ("export.html", "Export", "Export", None, Some("export")),
+    ("import.file", "Import", "Import", None, Some("import")),
+    ("help.show", "Help", "Help", Some("F1"), Some("help")),
+];
+
+/// コマンド
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandInfo {
+    pub id: String,
+    pub title: String,
+    pub category: String,
+    /// 既定のキー（`Ctrl+Shift+S` の形式）
+    pub keybinding: Option<String>,
+    /// 同じ動作の Ex コマンド（`:` なし）
+    pub ex: Option<String>,
+    /// "builtin" | "plugin" | "hook"
+    pub source: String,
+    /// コマンドを追加したプラグインの `id`
+    pub plugin: Option<String>,
+}
+
+/// 使えるコマンドすべて（組み込み・プラグイン・フックの順）
+pub fn all_commands(state: &AppState) -> Vec<CommandInfo> {
+    let builtin = BUILTIN_COMMANDS
+        .iter()
+        .map(|&(id, title, category, keybinding, ex)| CommandInfo {
+            id: id.to_string(),
+            title: title.to_string(),
+            category: category.to_string(),
+            keybinding: keybinding.map(str::to_string),
+            ex: ex.map(str::to_string),
+            source: "builtin".to_string(),
+            plugin: None,
+        });
+    let plugins = state
+        .plugins
+        .commands()
+        .into_iter()
+        .map(|(plugin, name, command)| CommandInfo {
+            id: format!("plugin.{plugin}.{}", command.id),
+            title: command.title,
+            category: name,
+            keybinding: None,
+            ex: None,
+            source: "plugin".to_string(),
+            plugin: Some(plugin),
+        });
+    let hooks = state.hooks.events().into_iter().map(|event| CommandInfo {
+        id: format!("hooks.{}", event.name()),
+        title: format!("Run {} Hooks", event.name()),
+        category: "Hooks".to_string(),
+        keybinding: None,
+        ex: None,
+        source: "hook".to_string(),
+        plugin: None,
+    });
+    builtin.chain(plugins).chain(hooks).collect()
+}
+
+/// コマンドの一覧
+#[tauri::command]
+pub fn list_commands(state: State<'_, AppState>) -> Vec<CommandInfo> {
+    all_commands(&state)
+}
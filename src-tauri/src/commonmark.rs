@@ -0,0 +1,239 @@
+// CommonMark への準拠（方言によって描画が変わる書き方の検出）
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use pulldown_cmark::{CodeBlockKind, Event, LinkType, Parser, Tag, TagEnd};
+use serde::Serialize;
+
+use crate::code_block;
+use crate::diagram::DiagramKind;
+use crate::gfm;
+use crate::obsidian;
+use crate::render::{render_html_with, RenderOptions};
+use crate::render_profile::RenderProfile;
+use crate::text::LineIndex;
+
+/// 方言によって描画が変わる書き方
+#[derive(Debug, Serialize)]
+pub struct DialectDifference {
+    /// 行番号（1 始まり）
+    pub line: usize,
+    /// "table" | "strikethrough" | "task_list" | "footnote" | "math" | "wikilink" | "front_matter"
+    /// | "alert" | "callout" | "autolink" | "tag" | "line_break" | "raw_html" | "code_block"
+    pub kind: String,
+    /// 該当する部分の最初の行
+    pub source: String,
+    /// 厳密な CommonMark と描画が異なるプロファイル
+    pub profiles: Vec<String>,
+}
+
+/// 見つかった書き方（種類・範囲・含まれるブロックの範囲）
+struct Construct {
+    kind: &'static str,
+    range: Range<usize>,
+    block: Range<usize>,
+}
+
+/// 拡張を含めたどれかの方言で特別に扱われる書き方を探す
+fn constructs(content: &str) -> Vec<Construct> {
+    let options = RenderProfile::default().parser_options() | RenderProfile::gfm().parser_options();
+    let mut found = Vec::new();
+    let mut depth = 0usize;
+    let mut block = 0..0;
+    let mut verbatim = 0usize;
+    let mut push = |kind, range: Range<usize>, block: &Range<usize>| {
+        found.push(Construct {
+            kind,
+            range,
+            block: block.clone(),
+        })
+    };
+    for (event, range) in Parser::new_ext(content, options).into_offset_iter() {
+        if let Event::Start(_) = &event {
+            if depth == 0 {
+                block = range.clone();
+            }
+            depth += 1;
+        }
+        match &event {
+            Event::Start(Tag::Table(_)) => push("table", range, &block),
+            Event::Start(Tag::Strikethrough) => push("strikethrough", range, &block),
+            Event::TaskListMarker(_) => push("task_list", range, &block),
+            Event::FootnoteReference(_) | Event::Start(Tag::FootnoteDefinition(_)) => {
+                push("footnote", range, &block)
+            }
+            Event::InlineMath(_) | Event::DisplayMath(_) => push("math", range, &block),
+            Event::Start(Tag::Link { link_type, .. } | Tag::Image { link_type, .. }) => {
+                if matches!(link_type, LinkType::WikiLink { .. }) {
+                    push("wikilink", range.clone(), &block);
+                }
+                verbatim += 1;
+            }
+            Event::Start(Tag::MetadataBlock(_)) => push("front_matter", range, &block),
+            Event::Start(Tag::BlockQuote(Some(_))) => push("alert", range, &block),
+            Event::Start(Tag::BlockQuote(None))
+                if content[range.clone()]
+                    .trim_start_matches(['>', ' '])
+                    .starts_with("[!") =>
+            {
+                push("callout", range, &block)
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                if let CodeBlockKind::Fenced(info) = kind {
+                    let info = code_block::parse_info(info);
+                    if info.is_extended() || DiagramKind::from_lang(&info.lang).is_some() {
+                        push("code_block", range.clone(), &block);
+                    }
+                }
+                verbatim += 1;
+            }
+            Event::End(TagEnd::Link | TagEnd::Image | TagEnd::CodeBlock) => {
+                verbatim = verbatim.saturating_sub(1)
+            }
+            Event::Text(text) if verbatim == 0 => {
+                if !gfm::autolinks(text).is_empty() {
+                    push("autolink", range.clone(), &block);
+                }
+                if !obsidian::inline_tags(text).is_empty() {
+                    push("tag", range, &block);
+                }
+            }
+            Event::SoftBreak => push("line_break", range, &block),
+            Event::Html(_) | Event::InlineHtml(_) => push("raw_html", range, &block),
+            _ => {}
+        }
+        if let Event::End(_) = &event {
+            depth -= 1;
+        }
+    }
+    found
+}
+
+/// 文書の中で方言（組み込みのプロファイル）によって描画が変わる書き方を報告する
+///
+/// 書き方を含むブロックを各プロファイルで描画し、厳密な CommonMark と比べる。見出しの ID の違いは無視する。
+pub fn dialect_differences(content: &str) -> Vec<DialectDifference> {
+    let found = constructs(content);
+    // 脚注の参照が描画されるように定義をブロックの後に付ける
+    let definitions: String = found
+        .iter()
+        .filter(|c| c.kind == "footnote" && c.range == c.block)
+        .map(|c| format!("\n\n{}", &content[c.block.clone()]))
+        .collect();
+    let strict = RenderProfile::strict();
+    let profiles: Vec<RenderProfile> = [
+        RenderProfile::default(),
+        RenderProfile::gfm(),
+        RenderProfile::commonmark(),
+    ]
+    .into_iter()
+    .map(|p| RenderProfile {
+        heading_ids: false,
+        ..p
+    })
+    .collect();
+    let render = |source: &str, profile: &RenderProfile| {
+        render_html_with(
+            source,
+            &RenderOptions {
+                profile: Some(profile),
+                ..Default::default()
+            },
+        )
+    };
+    let index = LineIndex::new(content);
+    let mut seen = HashSet::new();
+    let mut differences = Vec::new();
+    for construct in found {
+        if !seen.insert((construct.kind, construct.block.start)) {
+            continue;
+        }
+        let mut source = content[construct.block.clone()].to_string();
+        if construct.kind == "footnote" {
+            source.push_str(&definitions);
+        }
+        let expected = render(&source, &strict);
+        let differs: Vec<String> = profiles
+            .iter()
+            .filter(|p| render(&source, p) != expected)
+            .map(|p| p.name.clone())
+            .collect();
+        if differs.is_empty() {
+            continue;
+        }
+        let line = index.line_of(construct.range.start);
+        let first = content[construct.range.clone()]
+            .lines()
+            .next()
+            .unwrap_or_default();
+        differences.push(DialectDifference {
+            line,
+            kind: construct.kind.to_string(),
+            source: first.trim_end().to_string(),
+            profiles: differs,
+        });
+    }
+    differences
+}
+
+/// 厳密な CommonMark と描画が異なる書き方を報告する（README を別の環境で表示する前の確認用）
+#[tauri::command]
+pub fn validate_commonmark(content: String) -> Vec<DialectDifference> {
+    dialect_differences(&content)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::render::{render_html_with, RenderOptions};
+    use crate::render_profile::RenderProfile;
+    use crate::spec;
+
+    #[test]
+    fn commonmark_spec_examples() {
+        let profile = RenderProfile::strict();
+        let failures = spec::failures(include_str!("../tests/spec/commonmark.txt"), |markdown| {
+            render_html_with(
+                markdown,
+                &RenderOptions {
+                    profile: Some(&profile),
+                    ..Default::default()
+                },
+            )
+        });
+        assert!(
+            failures.is_empty(),
+            "{} examples failed\n\n{}",
+            failures.len(),
+            failures.join("\n\n")
+        );
+    }
+
+    #[test]
+    fn reports_extension_constructs() {
+        let content =
+            "# Title\n\n| a | b |\n| - | - |\n| 1 | 2 |\n\nplain ~~struck~~ text\n\njust text\n";
+        let kinds: Vec<(usize, String)> = super::dialect_differences(content)
+            .into_iter()
+            .map(|d| (d.line, d.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [(3, "table".to_string()), (7, "strikethrough".to_string())]
+        );
+    }
+
+    #[test]
+    fn reports_constructs_in_non_ascii_text() {
+        let content =
+            "# 見出し\n\n日本語の段落と café www.example.com\n\n連絡は foo@bar.baz まで\n";
+        let kinds: Vec<(usize, String)> = super::dialect_differences(content)
+            .into_iter()
+            .map(|d| (d.line, d.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [(3, "autolink".to_string()), (5, "autolink".to_string())]
+        );
+    }
+}
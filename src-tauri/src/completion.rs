@@ -0,0 +1,394 @@
+// エディタの入力補完（ノート名・見出し・タグ・絵文字）
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::markdown;
+use crate::obsidian;
+use crate::state::AppState;
+use crate::vault::{self, to_slash};
+
+/// 返す候補の数の既定値
+const DEFAULT_LIMIT: usize = 50;
+
+/// `:` で補完する絵文字（GitHub のショートコード）
+const EMOJI: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("alarm_clock", "⏰"),
+    ("angry", "😠"),
+    ("arrow_down", "⬇️"),
+    ("arrow_left", "⬅️"),
+    ("arrow_right", "➡️"),
+    ("arrow_up", "⬆️"),
+    ("art", "🎨"),
+    ("beer", "🍺"),
+    ("bell", "🔔"),
+    ("blush", "😊"),
+    ("book", "📖"),
+    ("bookmark", "🔖"),
+    ("books", "📚"),
+    ("boom", "💥"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("calendar", "📆"),
+    ("camera", "📷"),
+    ("cat", "🐱"),
+    ("chart_with_upwards_trend", "📈"),
+    ("clap", "👏"),
+    ("clipboard", "📋"),
+    ("clock3", "🕒"),
+    ("cloud", "☁️"),
+    ("coffee", "☕"),
+    ("computer", "💻"),
+    ("confused", "😕"),
+    ("construction", "🚧"),
+    ("cry", "😢"),
+    ("dog", "🐶"),
+    ("email", "📧"),
+    ("exclamation", "❗"),
+    ("eyes", "👀"),
+    ("file_folder", "📁"),
+    ("fire", "🔥"),
+    ("flag", "🚩"),
+    ("gift", "🎁"),
+    ("globe_with_meridians", "🌐"),
+    ("grin", "😁"),
+    ("grinning", "😀"),
+    ("hammer", "🔨"),
+    ("heart", "❤️"),
+    ("heavy_check_mark", "✔️"),
+    ("hourglass", "⌛"),
+    ("house", "🏠"),
+    ("information_source", "ℹ️"),
+    ("joy", "😂"),
+    ("key", "🔑"),
+    ("laughing", "😆"),
+    ("link", "🔗"),
+    ("lock", "🔒"),
+    ("mag", "🔍"),
+    ("memo", "📝"),
+    ("moon", "🌙"),
+    ("muscle", "💪"),
+    ("no_entry", "⛔"),
+    ("ok_hand", "👌"),
+    ("package", "📦"),
+    ("paperclip", "📎"),
+    ("pencil2", "✏️"),
+    ("pray", "🙏"),
+    ("pushpin", "📌"),
+    ("question", "❓"),
+    ("raised_hands", "🙌"),
+    ("recycle", "♻️"),
+    ("rocket", "🚀"),
+    ("rotating_light", "🚨"),
+    ("scissors", "✂️"),
+    ("see_no_evil", "🙈"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("snowflake", "❄️"),
+    ("sob", "😭"),
+    ("sparkles", "✨"),
+    ("star", "⭐"),
+    ("sunny", "☀️"),
+    ("sunglasses", "😎"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("trophy", "🏆"),
+    ("umbrella", "☔"),
+    ("unlock", "🔓"),
+    ("warning", "⚠️"),
+    ("wave", "👋"),
+    ("white_check_mark", "✅"),
+    ("wink", "😉"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("zap", "⚡"),
+    ("zzz", "💤"),
+];
+
+/// 補完の種類
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletionKind {
+    /// `[[` の後のノート名
+    Note,
+    /// リンクの `#` の後の見出し
+    Heading,
+    /// 本文の `#` の後のタグ
+    Tag,
+    /// `:` の後の絵文字
+    Emoji,
+}
+
+/// 補完の文脈
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CompletionContext {
+    pub vault_root: Option<String>,
+    /// 編集中の文書
+    pub path: Option<String>,
+    /// 編集中の文書の内容（未保存の変更を見出しの補完に使う）
+    pub content: Option<String>,
+    /// 見出しを補完するノート（`[[ノート#` のノート名。なければ編集中の文書）
+    pub note: Option<String>,
+    /// ウィキリンク内か（見出しを本文のまま挿入する。でなければアンカー）
+    pub wiki: bool,
+    pub limit: Option<usize>,
+}
+
+/// 補完の候補
+#[derive(Debug, Serialize)]
+pub struct CompletionItem {
+    pub label: String,
+    pub insert_text: String,
+    /// パス・見出しのレベル・タグの使用回数など
+    pub detail: Option<String>,
+}
+
+/// 索引に登録したノート
+struct IndexedNote {
+    modified: SystemTime,
+    headings: Vec<markdown::Heading>,
+    tags: Vec<String>,
+    aliases: Vec<String>,
+}
+
+/// ワークスペースごとのノートの索引（更新されたファイルだけ読み直す）
+#[derive(Default)]
+pub struct CompletionState {
+    vaults: Mutex<HashMap<PathBuf, HashMap<PathBuf, IndexedNote>>>,
+}
+
+fn modified(path: &Path) -> SystemTime {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+fn index_note(content: &str, modified: SystemTime) -> IndexedNote {
+    IndexedNote {
+        modified,
+        headings: markdown::headings(content),
+        tags: obsidian::note_tags(content),
+        aliases: obsidian::note_aliases(content),
+    }
+}
+
+/// 索引をファイルの更新に合わせる
+fn refresh(notes: &mut HashMap<PathBuf, IndexedNote>, root: &Path) {
+    let files: Vec<PathBuf> = vault::walk_files(root)
+        .into_iter()
+        .filter(|p| vault::is_markdown(p))
+        .collect();
+    notes.retain(|path, _| files.binary_search(path).is_ok());
+    for file in files {
+        let time = modified(&file);
+        if notes.get(&file).is_some_and(|n| n.modified == time) {
+            continue;
+        }
+        if let Ok(content) = fs::read_to_string(&file) {
+            notes.insert(file, index_note(&content, time));
+        }
+    }
+}
+
+/// 一致の順位（先頭一致・語の先頭の一致・部分一致。一致しなければ `None`）
+fn rank(label: &str, prefix: &str) -> Option<usize> {
+    if prefix.is_empty() {
+        return Some(0);
+    }
+    let label = label.to_lowercase();
+    if label.starts_with(prefix) {
+        return Some(0);
+    }
+    let index = label.find(prefix)?;
+    let word_start = label[..index].ends_with([' ', '-', '_', '/', '.']);
+    Some(if word_start { 1 } else { 2 })
+}
+
+fn stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn note_items(root: &Path, notes: &HashMap<PathBuf, IndexedNote>) -> Vec<CompletionItem> {
+    let mut names: HashMap<String, usize> = HashMap::new();
+    for path in notes.keys() {
+        *names.entry(stem(path).to_lowercase()).or_default() += 1;
+    }
+    let mut paths: Vec<&PathBuf> = notes.keys().collect();
+    paths.sort();
+    let mut items = Vec::new();
+    for path in paths {
+        let relative = to_slash(path.strip_prefix(root).unwrap_or(path));
+        let name = stem(path);
+        // 同じ名前のノートが複数あればパスで区別する
+        let target = if names[&name.to_lowercase()] > 1 {
+            relative
+                .strip_suffix(&format!(".{}", path.extension().unwrap().to_string_lossy()))
+                .unwrap_or(&relative)
+                .to_string()
+        } else {
+            name.clone()
+        };
+        for alias in &notes[path].aliases {
+            items.push(CompletionItem {
+                label: alias.clone(),
+                insert_text: format!("{target}|{alias}"),
+                detail: Some(relative.clone()),
+            });
+        }
+        items.push(CompletionItem {
+            label: name,
+            insert_text: target,
+            detail: Some(relative),
+        });
+    }
+    items
+}
+
+fn heading_items(headings: &[markdown::Heading], wiki: bool) -> Vec<CompletionItem> {
+    let slugs = markdown::heading_slugs(headings);
+    headings
+        .iter()
+        .zip(slugs)
+        .map(|(h, slug)| CompletionItem {
+            label: h.text.clone(),
+            insert_text: if wiki { h.text.clone() } else { slug },
+            detail: Some(format!("H{}", h.level)),
+        })
+        .collect()
+}
+
+fn tag_items(notes: &HashMap<PathBuf, IndexedNote>) -> Vec<CompletionItem> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for tag in notes.values().flat_map(|n| &n.tags) {
+        *counts.entry(tag).or_default() += 1;
+    }
+    let mut tags: Vec<(&str, usize)> = counts.into_iter().collect();
+    tags.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    tags.into_iter()
+        .map(|(tag, count)| CompletionItem {
+            label: tag.to_string(),
+            insert_text: tag.to_string(),
+            detail: Some(count.to_string()),
+        })
+        .collect()
+}
+
+/// 見出しを補完するノートを探す（ノート名・相対パス・別名の順）
+fn find_note<'a>(
+    root: &Path,
+    notes: &'a HashMap<PathBuf, IndexedNote>,
+    doc: Option<&Path>,
+    name: &str,
+) -> Option<&'a IndexedNote> {
+    let key = name.trim().trim_end_matches(".md").to_lowercase();
+    let mut candidates: Vec<(&PathBuf, &IndexedNote)> = notes
+        .iter()
+        .filter(|(path, note)| {
+            let relative = to_slash(&path.strip_prefix(root).unwrap_or(path).with_extension(""));
+            stem(path).to_lowercase() == key
+                || relative.to_lowercase() == key
+                || note.aliases.iter().any(|a| a.to_lowercase() == key)
+        })
+        .collect();
+    // 同じフォルダ、パスの短いものを優先する
+    candidates.sort_by_key(|(path, _)| {
+        (
+            doc.and_then(Path::parent) != path.parent(),
+            path.components().count(),
+        )
+    });
+    candidates.first().map(|(_, note)| *note)
+}
+
+/// 入力中の `prefix` に合う補完候補を返す
+#[tauri::command]
+pub fn get_completions(
+    state: State<'_, AppState>,
+    kind: CompletionKind,
+    prefix: String,
+    context: CompletionContext,
+) -> Vec<CompletionItem> {
+    let doc = context
+        .path
+        .as_deref()
+        .map(|p| vault::normalize(Path::new(p)));
+    let root = context
+        .vault_root
+        .as_deref()
+        .map(|r| vault::normalize(Path::new(r)))
+        .or_else(|| doc.as_deref().and_then(Path::parent).map(Path::to_path_buf));
+
+    let mut vaults = state.completion.vaults.lock().unwrap();
+    let notes = match (&root, kind) {
+        (_, CompletionKind::Emoji) | (None, _) => None,
+        (Some(root), _) => {
+            let notes = vaults.entry(root.clone()).or_default();
+            refresh(notes, root);
+            Some(&*notes)
+        }
+    };
+
+    let items = match kind {
+        CompletionKind::Emoji => EMOJI
+            .iter()
+            .map(|(code, emoji)| CompletionItem {
+                label: format!(":{code}:"),
+                insert_text: emoji.to_string(),
+                detail: Some(emoji.to_string()),
+            })
+            .collect(),
+        CompletionKind::Note => notes
+            .map(|n| note_items(root.as_deref().unwrap(), n))
+            .unwrap_or_default(),
+        CompletionKind::Tag => notes.map(tag_items).unwrap_or_default(),
+        CompletionKind::Heading => {
+            let note = context.note.as_deref().filter(|n| !n.trim().is_empty());
+            match (note, &context.content) {
+                (None, Some(content)) => heading_items(&markdown::headings(content), context.wiki),
+                (None, None) => doc
+                    .as_deref()
+                    .and_then(|d| fs::read_to_string(d).ok())
+                    .map(|c| heading_items(&markdown::headings(&c), context.wiki))
+                    .unwrap_or_default(),
+                (Some(name), _) => notes
+                    .and_then(|n| find_note(root.as_deref().unwrap(), n, doc.as_deref(), name))
+                    .map(|n| heading_items(&n.headings, context.wiki))
+                    .unwrap_or_default(),
+            }
+        }
+    };
+
+    let prefix = prefix
+        .trim_start_matches([':', '#'])
+        .trim_end_matches(':')
+        .to_lowercase();
+    let mut ranked: Vec<(usize, usize, CompletionItem)> = items
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, item)| {
+            let label = item.label.trim_matches(':');
+            rank(label, &prefix).map(|r| (r, i, item))
+        })
+        .collect();
+    // 順位が同じなら元の順（タグは使用回数順、見出しは文書順）
+    ranked.sort_by_key(|&(r, i, _)| (r, i));
+    ranked
+        .into_iter()
+        .take(context.limit.unwrap_or(DEFAULT_LIMIT))
+        .map(|(_, _, item)| item)
+        .collect()
+}
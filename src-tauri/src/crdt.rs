@@ -0,0 +1,346 @@
+// 共同編集用のテキスト CRDT（RGA: 文字ごとに ID を持ち、削除は墓標として残す）
+//
+// 位置はエディタと同じ UTF-16 コード単位で扱う。
+
+use serde::{Deserialize, Serialize};
+
+/// 文字の ID（Lamport 時刻と参加者 ID）。大きいほど新しい
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Id {
+    pub clock: u64,
+    pub site: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Item {
+    pub id: Id,
+    /// 挿入時に左隣にあった文字
+    pub origin: Option<Id>,
+    pub ch: char,
+    pub deleted: bool,
+}
+
+/// 他の参加者へ送る操作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Op {
+    Insert {
+        id: Id,
+        origin: Option<Id>,
+        ch: char,
+    },
+    Delete {
+        id: Id,
+    },
+}
+
+/// エディタに適用する変更（`offset` と `delete_len` は UTF-16 単位）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TextEdit {
+    pub offset: usize,
+    pub delete_len: usize,
+    pub text: String,
+}
+
+pub struct Doc {
+    site: u64,
+    clock: u64,
+    items: Vec<Item>,
+    /// 依存する文字がまだ届いていない操作
+    pending: Vec<Op>,
+}
+
+impl Doc {
+    pub fn new(site: u64) -> Self {
+        Self {
+            site,
+            clock: 0,
+            items: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// 他の参加者から受け取った全体の状態で置き換える
+    pub fn from_items(site: u64, items: Vec<Item>) -> Self {
+        let clock = items.iter().map(|i| i.id.clock).max().unwrap_or(0);
+        Self {
+            site,
+            clock,
+            items,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    pub fn text(&self) -> String {
+        self.items
+            .iter()
+            .filter(|i| !i.deleted)
+            .map(|i| i.ch)
+            .collect()
+    }
+
+    fn index_of(&self, id: Id) -> Option<usize> {
+        self.items.iter().position(|i| i.id == id)
+    }
+
+    /// 表示上の UTF-16 位置（`index` より前の削除されていない文字の長さ）
+    fn offset_of(&self, index: usize) -> usize {
+        self.items[..index]
+            .iter()
+            .filter(|i| !i.deleted)
+            .map(|i| i.ch.len_utf16())
+            .sum()
+    }
+
+    /// UTF-16 位置 `offset` の直前にある文字（`items` の添字）
+    fn visible_before(&self, offset: usize) -> Option<usize> {
+        let mut pos = 0;
+        let mut last = None;
+        for (index, item) in self.items.iter().enumerate() {
+            if item.deleted {
+                continue;
+            }
+            if pos >= offset {
+                break;
+            }
+            pos += item.ch.len_utf16();
+            last = Some(index);
+        }
+        last
+    }
+
+    /// カーソル位置を、その直前の文字の ID で表す（編集でずれないように）
+    pub fn anchor_at(&self, offset: usize) -> Option<Id> {
+        self.visible_before(offset).map(|i| self.items[i].id)
+    }
+
+    /// アンカーの現在の UTF-16 位置
+    pub fn offset_of_anchor(&self, anchor: Option<Id>) -> usize {
+        match anchor.and_then(|id| self.index_of(id)) {
+            Some(index) => self.offset_of(index + 1),
+            None => 0,
+        }
+    }
+
+    fn next_id(&mut self) -> Id {
+        self.clock += 1;
+        Id {
+            clock: self.clock,
+            site: self.site,
+        }
+    }
+
+    /// ローカルの編集を適用し、送信する操作を返す
+    pub fn local_edit(&mut self, offset: usize, delete_len: usize, text: &str) -> Vec<Op> {
+        let mut ops = Vec::new();
+        if delete_len > 0 {
+            let start = self.visible_before(offset).map_or(0, |i| i + 1);
+            let mut remaining = delete_len;
+            for item in &mut self.items[start..] {
+                if remaining == 0 {
+                    break;
+                }
+                if item.deleted {
+                    continue;
+                }
+                item.deleted = true;
+                remaining = remaining.saturating_sub(item.ch.len_utf16());
+                ops.push(Op::Delete { id: item.id });
+            }
+        }
+
+        let mut origin = self.anchor_at(offset);
+        let start = origin.and_then(|id| self.index_of(id)).map_or(0, |i| i + 1);
+        for (index, ch) in (start..).zip(text.chars()) {
+            let id = self.next_id();
+            // 自分の ID が最大なので、起点の直後に置けばよい
+            self.items.insert(
+                index,
+                Item {
+                    id,
+                    origin,
+                    ch,
+                    deleted: false,
+                },
+            );
+            ops.push(Op::Insert { id, origin, ch });
+            origin = Some(id);
+        }
+        ops
+    }
+
+    /// 1 つの操作を適用（依存する文字がなければ `Err` で返す）
+    fn integrate(&mut self, op: Op) -> Result<Option<TextEdit>, Op> {
+        match op {
+            Op::Insert { id, origin, ch } => {
+                if self.index_of(id).is_some() {
+                    return Ok(None);
+                }
+                let mut index = match origin {
+                    Some(origin) => match self.index_of(origin) {
+                        Some(i) => i + 1,
+                        None => return Err(op),
+                    },
+                    None => 0,
+                };
+                // 同じ起点に後から挿入された（ID の大きい）文字の後ろに置く
+                while index < self.items.len() && self.items[index].id > id {
+                    index += 1;
+                }
+                self.clock = self.clock.max(id.clock);
+                self.items.insert(
+                    index,
+                    Item {
+                        id,
+                        origin,
+                        ch,
+                        deleted: false,
+                    },
+                );
+                Ok(Some(TextEdit {
+                    offset: self.offset_of(index),
+                    delete_len: 0,
+                    text: ch.to_string(),
+                }))
+            }
+            Op::Delete { id } => {
+                let Some(index) = self.index_of(id) else {
+                    return Err(op);
+                };
+                if self.items[index].deleted {
+                    return Ok(None);
+                }
+                self.items[index].deleted = true;
+                Ok(Some(TextEdit {
+                    offset: self.offset_of(index),
+                    delete_len: self.items[index].ch.len_utf16(),
+                    text: String::new(),
+                }))
+            }
+        }
+    }
+
+    /// 受け取った操作を適用し、エディタに反映する変更を返す（連続する変更はまとめる）
+    pub fn apply_remote(&mut self, ops: Vec<Op>) -> Vec<TextEdit> {
+        let mut edits = Vec::new();
+        self.pending.extend(ops);
+        // 依存する文字が揃ったものから適用し、進まなくなるまで繰り返す
+        loop {
+            let before = self.pending.len();
+            for op in std::mem::take(&mut self.pending) {
+                match self.integrate(op) {
+                    Ok(Some(edit)) => push_edit(&mut edits, edit),
+                    Ok(None) => {}
+                    Err(op) => self.pending.push(op),
+                }
+            }
+            if self.pending.is_empty() || self.pending.len() == before {
+                break;
+            }
+        }
+        edits
+    }
+}
+
+/// 直前の変更と続いていればまとめる
+fn push_edit(edits: &mut Vec<TextEdit>, edit: TextEdit) {
+    if let Some(last) = edits.last_mut() {
+        if last.delete_len == 0
+            && edit.delete_len == 0
+            && last.offset + last.text.encode_utf16().count() == edit.offset
+        {
+            last.text.push_str(&edit.text);
+            return;
+        }
+        if last.text.is_empty() && edit.text.is_empty() && last.offset == edit.offset {
+            last.delete_len += edit.delete_len;
+            return;
+        }
+    }
+    edits.push(edit);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Doc, TextEdit};
+
+    /// エディタ側の内容に変更を当てる
+    fn apply(text: &mut Vec<u16>, edits: &[TextEdit]) {
+        for edit in edits {
+            let inserted: Vec<u16> = edit.text.encode_utf16().collect();
+            text.splice(edit.offset..edit.offset + edit.delete_len, inserted);
+        }
+    }
+
+    #[test]
+    fn concurrent_inserts_converge() {
+        let mut a = Doc::new(1);
+        a.local_edit(0, 0, "hello world");
+        let mut b = Doc::from_items(2, a.items().to_vec());
+        let mut c = Doc::from_items(3, a.items().to_vec());
+
+        // 同じ位置への挿入・削除を含む同時の編集
+        let from_a = a.local_edit(5, 0, ", dear");
+        let from_b = b.local_edit(5, 6, " 世界😀");
+        let from_c = c.local_edit(5, 0, "!");
+
+        let mut editors: Vec<Vec<u16>> = [&a, &b, &c]
+            .iter()
+            .map(|doc| doc.text().encode_utf16().collect())
+            .collect();
+        let mut reversed = from_c.clone();
+        reversed.reverse();
+        apply(&mut editors[0], &a.apply_remote(from_b.clone()));
+        apply(&mut editors[0], &a.apply_remote(reversed));
+        apply(&mut editors[1], &b.apply_remote(from_c.clone()));
+        apply(&mut editors[1], &b.apply_remote(from_a.clone()));
+        apply(&mut editors[2], &c.apply_remote(from_a));
+        apply(&mut editors[2], &c.apply_remote(from_b));
+
+        let text = a.text();
+        assert_eq!(b.text(), text);
+        assert_eq!(c.text(), text);
+        for editor in &editors {
+            assert_eq!(String::from_utf16(editor).unwrap(), text);
+        }
+        assert!(text.starts_with("hello"));
+        assert!(text.contains(", dear") && text.contains(" 世界😀") && text.contains('!'));
+        assert!(!text.contains("world"));
+    }
+
+    #[test]
+    fn out_of_order_ops_wait_for_their_origin() {
+        let mut a = Doc::new(1);
+        let ops = a.local_edit(0, 0, "abc");
+        let mut b = Doc::new(2);
+        let mut reversed = ops.clone();
+        reversed.reverse();
+        let edits = b.apply_remote(reversed);
+        assert_eq!(b.text(), "abc");
+        assert_eq!(
+            edits,
+            vec![TextEdit {
+                offset: 0,
+                delete_len: 0,
+                text: "abc".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn offsets_are_utf16() {
+        let mut a = Doc::new(1);
+        a.local_edit(0, 0, "😀x");
+        let mut b = Doc::from_items(2, a.items().to_vec());
+        let ops = a.local_edit(2, 1, "y");
+        assert_eq!(a.text(), "😀y");
+        let mut editor: Vec<u16> = b.text().encode_utf16().collect();
+        apply(&mut editor, &b.apply_remote(ops));
+        assert_eq!(b.text(), "😀y");
+        assert_eq!(String::from_utf16(&editor).unwrap(), "😀y");
+    }
+}
@@ -0,0 +1,113 @@
+// CriticMarkup（`{++追加++}` `{--削除--}` `{~~前~>後~~}` `{==強調==}` `{>>コメント<<}`）
+//
+// 描画では解析の前に HTML の `<ins>` `<del>` `<mark>` に置き換え、編集ではまとめて採用・却下する。
+// コードの中と、空行をまたぐものは対象にしない。
+
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+use crate::markdown;
+use crate::text;
+
+static CRITIC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?s)\{\+\+(?P<add>.*?)\+\+\}|\{--(?P<del>.*?)--\}|\{~~(?P<old>.*?)~>(?P<new>.*?)~~\}|\{==(?P<mark>.*?)==\}|\{>>(?P<comment>.*?)<<\}",
+    )
+    .unwrap()
+});
+
+/// 変更の記法 1 つ
+enum Markup<'a> {
+    Addition(&'a str),
+    Deletion(&'a str),
+    Substitution(&'a str, &'a str),
+    Highlight(&'a str),
+    Comment(&'a str),
+}
+
+fn markup<'a>(caps: &Captures<'a>) -> Markup<'a> {
+    let group = |name| caps.name(name).map(|m| m.as_str());
+    if let Some(text) = group("add") {
+        Markup::Addition(text)
+    } else if let Some(text) = group("del") {
+        Markup::Deletion(text)
+    } else if let (Some(old), Some(new)) = (group("old"), group("new")) {
+        Markup::Substitution(old, new)
+    } else if let Some(text) = group("mark") {
+        Markup::Highlight(text)
+    } else {
+        Markup::Comment(group("comment").unwrap_or_default())
+    }
+}
+
+/// 記法の範囲と内容（コードの中と空行をまたぐものを除く）
+fn markups(content: &str) -> Vec<(Range<usize>, Markup<'_>)> {
+    let code = markdown::code_ranges(content);
+    CRITIC
+        .captures_iter(content)
+        .filter_map(|caps| {
+            let whole = caps.get(0)?;
+            let blank_line = whole.as_str().contains("\n\n") || whole.as_str().contains("\n\r\n");
+            (!blank_line && !markdown::in_ranges(&code, whole.start()))
+                .then(|| (whole.range(), markup(&caps)))
+        })
+        .collect()
+}
+
+fn replace(content: &str, f: impl Fn(&Markup) -> String) -> String {
+    let edits = markups(content)
+        .into_iter()
+        .map(|(range, markup)| (range, f(&markup)))
+        .collect();
+    text::apply_edits(content, edits)
+}
+
+/// 描画の前に HTML のタグに置き換える（中の Markdown はそのまま解析される）
+pub fn to_html(content: &str) -> String {
+    replace(content, |markup| {
+        match markup {
+        Markup::Addition(text) => format!("<ins class=\"critic\">{text}</ins>"),
+        Markup::Deletion(text) => format!("<del class=\"critic\">{text}</del>"),
+        Markup::Substitution(old, new) => format!(
+            "<del class=\"critic critic-substitution\">{old}</del><ins class=\"critic critic-substitution\">{new}</ins>"
+        ),
+        Markup::Highlight(text) => format!("<mark class=\"critic\">{text}</mark>"),
+        Markup::Comment(text) => format!(
+            "<span class=\"critic-comment\">{}</span>",
+            text::escape_html(text.trim())
+        ),
+    }
+    })
+}
+
+/// すべての変更を採用する（追加と置き換え後を残し、削除・強調の記号・コメントを除く）
+pub fn accept(content: &str) -> String {
+    replace(content, |markup| match markup {
+        Markup::Addition(text) | Markup::Highlight(text) => text.to_string(),
+        Markup::Substitution(_, new) => new.to_string(),
+        Markup::Deletion(_) | Markup::Comment(_) => String::new(),
+    })
+}
+
+/// すべての変更を却下する（削除と置き換え前を残し、追加・強調の記号・コメントを除く）
+pub fn reject(content: &str) -> String {
+    replace(content, |markup| match markup {
+        Markup::Deletion(text) | Markup::Highlight(text) => text.to_string(),
+        Markup::Substitution(old, _) => old.to_string(),
+        Markup::Addition(_) | Markup::Comment(_) => String::new(),
+    })
+}
+
+/// CriticMarkup の変更をすべて採用した内容を返す
+#[tauri::command]
+pub fn accept_all_changes(content: String) -> String {
+    accept(&content)
+}
+
+/// CriticMarkup の変更をすべて却下した内容を返す
+#[tauri::command]
+pub fn reject_all_changes(content: String) -> String {
+    reject(&content)
+}
@@ -0,0 +1,481 @@
+// ローカルの CSV/JSON を参照する ```chart・```datatable のブロック
+//
+// ブロックの中身は `source: data/sales.csv` のような `キー: 値` の行（キーのない行はデータのパス）。
+// プレビューには読み込んだデータを返してフロントエンドで描き、エクスポートには静的な表か SVG のグラフを埋め込む。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+use crate::chart;
+use crate::links;
+use crate::table;
+use crate::text::escape_html;
+use crate::tr;
+
+/// 読み込むファイルの大きさの上限
+const MAX_FILE: u64 = 10 << 20;
+/// 返す行の上限（`limit` がなければ）
+const MAX_ROWS: usize = 10_000;
+/// エクスポートの表に入れる行の上限
+const MAX_TABLE_ROWS: usize = 500;
+
+/// ブロックの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataBlockKind {
+    Chart,
+    Datatable,
+}
+
+impl DataBlockKind {
+    /// コードブロックの言語名から
+    pub fn from_lang(lang: &str) -> Option<Self> {
+        match lang.to_ascii_lowercase().as_str() {
+            "chart" => Some(Self::Chart),
+            "datatable" => Some(Self::Datatable),
+            _ => None,
+        }
+    }
+}
+
+/// ブロックの指定
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DataBlockSpec {
+    /// データのパス（文書のフォルダからの相対パス）
+    pub source: String,
+    /// グラフの種類（`bar` `line` `pie`。省略時は `bar`）
+    pub chart: String,
+    pub title: Option<String>,
+    /// 横軸（円グラフではラベル）の列（省略時は最初の列）
+    pub x: Option<String>,
+    /// 値の列（省略時は横軸以外の数値の列）
+    pub y: Vec<String>,
+    /// 表に出す列（省略時はすべて）
+    pub columns: Vec<String>,
+    /// 行の上限
+    pub limit: Option<usize>,
+}
+
+/// 読み込んだデータ
+#[derive(Debug, Clone, Serialize)]
+pub struct DataSet {
+    pub columns: Vec<String>,
+    /// 数値は数値、空のセルは `null`
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// `load_data_block` の結果
+#[derive(Debug, Serialize)]
+pub struct DataBlock {
+    pub kind: DataBlockKind,
+    pub spec: DataBlockSpec,
+    /// 解決したデータのパス
+    pub path: String,
+    #[serde(flatten)]
+    pub data: DataSet,
+    /// 行の数（`limit` で切る前）
+    pub total_rows: usize,
+    pub truncated: bool,
+}
+
+fn list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+/// ブロックの中身を解析する
+pub fn parse_spec(kind: DataBlockKind, code: &str) -> Result<DataBlockSpec, String> {
+    let mut spec = DataBlockSpec {
+        chart: "bar".to_string(),
+        ..Default::default()
+    };
+    for line in code.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':').filter(|(k, _)| {
+            !k.is_empty() && k.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }) else {
+            spec.source = line.to_string();
+            continue;
+        };
+        let value = value.trim().trim_matches(['"', '\'']);
+        match key.to_ascii_lowercase().as_str() {
+            "source" | "src" | "file" | "data" => spec.source = value.to_string(),
+            "type" | "chart" => spec.chart = value.to_ascii_lowercase(),
+            "title" => spec.title = Some(value.to_string()),
+            "x" | "label" | "labels" => spec.x = Some(value.to_string()),
+            "y" | "value" | "values" | "series" => spec.y = list(value),
+            "columns" => spec.columns = list(value),
+            "limit" => {
+                spec.limit = Some(value.parse().map_err(|_| tr!("Invalid limit: {}", value))?)
+            }
+            _ => return Err(tr!("Unknown data block option: {}", key)),
+        }
+    }
+    if spec.source.is_empty() {
+        return Err(tr!("No data source in the block"));
+    }
+    if kind == DataBlockKind::Chart && !matches!(spec.chart.as_str(), "bar" | "line" | "pie") {
+        return Err(tr!("Unsupported chart type: {}", spec.chart));
+    }
+    Ok(spec)
+}
+
+/// CSV のセル（数値として読めれば数値）
+fn cell_value(cell: &str) -> Value {
+    let cell = cell.trim();
+    if cell.is_empty() {
+        return Value::Null;
+    }
+    let number = cell.replace(',', "");
+    match number.parse::<i64>() {
+        Ok(n) => Value::Number(n.into()),
+        Err(_) => number
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map_or_else(|| Value::String(cell.to_string()), Value::Number),
+    }
+}
+
+/// JSON の値（入れ子の値は文字列にする）
+fn json_value(value: &Value) -> Value {
+    match value {
+        Value::Array(_) | Value::Object(_) => Value::String(value.to_string()),
+        Value::String(s) if s.is_empty() => Value::Null,
+        value => value.clone(),
+    }
+}
+
+fn parse_csv_data(text: &str, delimiter: char) -> Result<DataSet, String> {
+    let mut rows = table::parse_csv(text, delimiter)?.into_iter();
+    let columns: Vec<String> = rows
+        .next()
+        .ok_or_else(|| tr!("No rows found"))?
+        .iter()
+        .map(|c| c.trim().to_string())
+        .collect();
+    let rows = rows
+        .enumerate()
+        .map(|(i, row)| {
+            if row.len() > columns.len() {
+                return Err(tr!(
+                    "Row {} has {} fields, expected {}",
+                    i + 2,
+                    row.len(),
+                    columns.len()
+                ));
+            }
+            let mut values: Vec<Value> = row.iter().map(|c| cell_value(c)).collect();
+            values.resize(columns.len(), Value::Null);
+            Ok(values)
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(DataSet { columns, rows })
+}
+
+/// JSON のデータ（オブジェクトの配列、先頭が見出しの配列の配列、`{"columns", "rows"}`）
+fn parse_json_data(text: &str, path: &Path) -> Result<DataSet, String> {
+    let value: Value =
+        serde_json::from_str(text).map_err(|e| tr!("Failed to parse {}: {e}", path.display()))?;
+    let array = |value: &Value| value.as_array().cloned();
+    let (columns, rows): (Vec<String>, Vec<Value>) = match &value {
+        Value::Object(object) => {
+            let columns = object.get("columns").and_then(array);
+            let rows = object.get("rows").or_else(|| object.get("data"));
+            match (columns, rows.and_then(array)) {
+                (Some(columns), Some(rows)) => (
+                    columns
+                        .iter()
+                        .map(|c| c.as_str().map_or_else(|| c.to_string(), str::to_string))
+                        .collect(),
+                    rows,
+                ),
+                (None, Some(rows)) => return records(&rows),
+                _ => return Err(tr!("Unsupported data layout in JSON")),
+            }
+        }
+        Value::Array(rows) => match rows.first() {
+            Some(Value::Array(header)) => (
+                header
+                    .iter()
+                    .map(|c| c.as_str().map_or_else(|| c.to_string(), str::to_string))
+                    .collect(),
+                rows[1..].to_vec(),
+            ),
+            _ => return records(rows),
+        },
+        _ => return Err(tr!("Unsupported data layout in JSON")),
+    };
+    let width = columns.len();
+    let rows = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let row = row
+                .as_array()
+                .ok_or_else(|| tr!("Unsupported data layout in JSON"))?;
+            if row.len() > width {
+                return Err(tr!(
+                    "Row {} has {} fields, expected {}",
+                    i + 1,
+                    row.len(),
+                    width
+                ));
+            }
+            let mut values: Vec<Value> = row.iter().map(json_value).collect();
+            values.resize(width, Value::Null);
+            Ok(values)
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(DataSet { columns, rows })
+}
+
+/// オブジェクトの配列（列は最初に現れた順）
+fn records(rows: &[Value]) -> Result<DataSet, String> {
+    let objects: Vec<&Map<String, Value>> = rows
+        .iter()
+        .map(|row| {
+            row.as_object()
+                .ok_or_else(|| tr!("Unsupported data layout in JSON"))
+        })
+        .collect::<Result<_, String>>()?;
+    let mut columns: Vec<String> = Vec::new();
+    for object in &objects {
+        for key in object.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+    let rows = objects
+        .iter()
+        .map(|object| {
+            columns
+                .iter()
+                .map(|c| object.get(c).map_or(Value::Null, json_value))
+                .collect()
+        })
+        .collect();
+    Ok(DataSet { columns, rows })
+}
+
+/// データのファイルを読み込む（拡張子で形式を決める）
+pub fn load_data(path: &Path) -> Result<DataSet, String> {
+    let size = fs::metadata(path)
+        .map_err(|e| tr!("Failed to read {}: {e}", path.display()))?
+        .len();
+    if size > MAX_FILE {
+        return Err(tr!(
+            "Data file is too large ({} bytes, limit {})",
+            size,
+            MAX_FILE
+        ));
+    }
+    let text =
+        fs::read_to_string(path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+    let text = text.trim_start_matches('\u{feff}');
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let data = match extension.as_str() {
+        "json" => parse_json_data(text, path)?,
+        "tsv" | "tab" => parse_csv_data(text, '\t')?,
+        "csv" => parse_csv_data(text, table::detect_delimiter(text))?,
+        _ => return Err(tr!("Unsupported data file: {}", path.display())),
+    };
+    if data.columns.is_empty() {
+        return Err(tr!("No rows found"));
+    }
+    Ok(data)
+}
+
+/// 列名の位置
+fn column(data: &DataSet, name: &str) -> Result<usize, String> {
+    data.columns
+        .iter()
+        .position(|c| c == name)
+        .ok_or_else(|| tr!("Unknown column: {}", name))
+}
+
+/// グラフの横軸と値の列を決める（値の列が数値でなければエラー）
+pub fn chart_columns(spec: &DataBlockSpec, data: &DataSet) -> Result<(usize, Vec<usize>), String> {
+    let x = match &spec.x {
+        Some(x) => column(data, x)?,
+        None => 0,
+    };
+    let numeric = |i: usize| {
+        data.rows
+            .iter()
+            .all(|row| matches!(row[i], Value::Number(_) | Value::Null))
+    };
+    let y = if spec.y.is_empty() {
+        (0..data.columns.len())
+            .filter(|&i| i != x && numeric(i))
+            .collect()
+    } else {
+        let y = spec
+            .y
+            .iter()
+            .map(|name| column(data, name))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(&i) = y.iter().find(|&&i| !numeric(i)) {
+            return Err(tr!("Column {} is not numeric", data.columns[i]));
+        }
+        y
+    };
+    if y.is_empty() {
+        return Err(tr!("No numeric columns to chart"));
+    }
+    Ok((x, y))
+}
+
+/// 文書のフォルダからデータのパスを解決する
+fn resolve(base_dir: &Path, source: &str) -> PathBuf {
+    base_dir.join(links::percent_decode(source))
+}
+
+/// ブロックが読むデータのファイル（指定が正しくなければ `None`）
+pub fn source_path(kind: DataBlockKind, code: &str, base_dir: &Path) -> Option<PathBuf> {
+    let spec = parse_spec(kind, code).ok()?;
+    Some(resolve(base_dir, &spec.source))
+}
+
+/// ブロックを解析してデータを読み込み、指定した列を検証する
+pub fn load(kind: DataBlockKind, code: &str, base_dir: &Path) -> Result<DataBlock, String> {
+    let spec = parse_spec(kind, code)?;
+    let path = resolve(base_dir, &spec.source);
+    let mut data = load_data(&path)?;
+    match kind {
+        DataBlockKind::Chart => {
+            chart_columns(&spec, &data)?;
+        }
+        DataBlockKind::Datatable if !spec.columns.is_empty() => {
+            let indexes = spec
+                .columns
+                .iter()
+                .map(|name| column(&data, name))
+                .collect::<Result<Vec<_>, _>>()?;
+            data = DataSet {
+                columns: spec.columns.clone(),
+                rows: data
+                    .rows
+                    .iter()
+                    .map(|row| indexes.iter().map(|&i| row[i].clone()).collect())
+                    .collect(),
+            };
+        }
+        DataBlockKind::Datatable => {}
+    }
+    let total_rows = data.rows.len();
+    let limit = spec.limit.unwrap_or(MAX_ROWS).min(MAX_ROWS);
+    data.rows.truncate(limit);
+    Ok(DataBlock {
+        kind,
+        path: path.to_string_lossy().into_owned(),
+        truncated: total_rows > data.rows.len(),
+        total_rows,
+        spec,
+        data,
+    })
+}
+
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// データの静的な表
+fn table_html(data: &DataSet, class: &str) -> String {
+    let numeric: Vec<bool> = (0..data.columns.len())
+        .map(|i| {
+            data.rows
+                .iter()
+                .all(|row| matches!(row[i], Value::Number(_) | Value::Null))
+        })
+        .collect();
+    let align = |i: usize| {
+        if numeric[i] {
+            " style=\"text-align: right\""
+        } else {
+            ""
+        }
+    };
+    let mut html = format!("<table class=\"{class}\">\n<thead>\n<tr>");
+    for (i, column) in data.columns.iter().enumerate() {
+        html.push_str(&format!("<th{}>{}</th>", align(i), escape_html(column)));
+    }
+    html.push_str("</tr>\n</thead>\n<tbody>\n");
+    for row in data.rows.iter().take(MAX_TABLE_ROWS) {
+        html.push_str("<tr>");
+        for (i, value) in row.iter().enumerate() {
+            html.push_str(&format!(
+                "<td{}>{}</td>",
+                align(i),
+                escape_html(&cell_text(value))
+            ));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</tbody>\n</table>\n");
+    html
+}
+
+/// エクスポートに埋め込む HTML（失敗した場合はエラーを表示する）
+pub fn html(kind: DataBlockKind, code: &str, base_dir: &Path) -> String {
+    let block = match load(kind, code, base_dir) {
+        Ok(block) => block,
+        Err(e) => {
+            return format!(
+                "<pre class=\"data-block-error\">{}</pre>\n",
+                escape_html(&e)
+            )
+        }
+    };
+    match kind {
+        DataBlockKind::Datatable => {
+            let caption = block
+                .spec
+                .title
+                .as_ref()
+                .map(|t| format!("<figcaption>{}</figcaption>\n", escape_html(t)))
+                .unwrap_or_default();
+            format!(
+                "<figure class=\"datatable\">\n{caption}{}</figure>\n",
+                table_html(&block.data, "datatable-table")
+            )
+        }
+        DataBlockKind::Chart => match chart_columns(&block.spec, &block.data) {
+            Ok((x, y)) => format!(
+                "<figure class=\"chart chart-{}\">\n{}\n</figure>\n",
+                block.spec.chart,
+                chart::svg(&block.spec, &block.data, x, &y)
+            ),
+            Err(e) => format!(
+                "<pre class=\"data-block-error\">{}</pre>\n",
+                escape_html(&e)
+            ),
+        },
+    }
+}
+
+/// ```chart・```datatable のブロックのデータを読み込み、検証してプレビュー用に返す
+///
+/// `path` は文書のパス（データのパスは文書のフォルダからの相対パス）。
+#[tauri::command]
+pub fn load_data_block(path: String, lang: String, code: String) -> Result<DataBlock, String> {
+    let kind = DataBlockKind::from_lang(&lang).ok_or_else(|| tr!("Not a data block: {}", lang))?;
+    let base_dir = Path::new(&path).parent().unwrap_or(Path::new("."));
+    load(kind, &code, base_dir)
+}
@@ -0,0 +1,601 @@
+// 日付の書式と計算（テンプレート・デイリーノート・スニペットの `{{date:YYYY年M月D日}}` など）
+//
+// 書式は Moment.js 形式（`YYYY-MM-DD` `MMMM Do` `[文字列]`）で、`%` を含むものは strftime 形式として扱う。
+// 月・曜日の名前と午前・午後は言語に合わせる（英語と日本語）。
+
+use std::fmt::Write as _;
+
+use chrono::{
+    Datelike, Duration, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, Timelike, Utc,
+};
+use serde::Serialize;
+
+use crate::i18n::{self, Locale};
+use crate::tr;
+
+const MONTHS_EN: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// 日曜日から
+const WEEKDAYS_EN: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+const WEEKDAYS_JA: [&str; 7] = ["日", "月", "火", "水", "木", "金", "土"];
+
+/// Moment.js の書式の記号（長いものから順に照合する）
+const TOKENS: &[&str] = &[
+    "YYYY", "YY", "Q", "MMMM", "MMM", "MM", "M", "DDDD", "DDD", "Do", "DD", "D", "dddd", "ddd",
+    "dd", "d", "E", "GGGG", "gggg", "WW", "W", "ww", "w", "HH", "H", "hh", "h", "mm", "m", "ss",
+    "s", "A", "a",
+];
+
+/// 英語の序数（`1st` `2nd` `11th`）
+fn ordinal(n: u32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+/// 記号 1 つを日時の値にする
+fn token(dt: &NaiveDateTime, token: &str, locale: Locale) -> String {
+    let month = dt.month0() as usize;
+    let weekday = dt.weekday().num_days_from_sunday() as usize;
+    let hour12 = match dt.hour() % 12 {
+        0 => 12,
+        h => h,
+    };
+    let pm = dt.hour() >= 12;
+    match token {
+        "YYYY" => format!("{:04}", dt.year()),
+        "YY" => format!("{:02}", dt.year().rem_euclid(100)),
+        "Q" => (month / 3 + 1).to_string(),
+        "MMMM" | "MMM" if locale == Locale::Ja => format!("{}月", month + 1),
+        "MMMM" => MONTHS_EN[month].to_string(),
+        "MMM" => MONTHS_EN[month][..3].to_string(),
+        "MM" => format!("{:02}", month + 1),
+        "M" => (month + 1).to_string(),
+        "DDDD" => format!("{:03}", dt.ordinal()),
+        "DDD" => dt.ordinal().to_string(),
+        "Do" if locale == Locale::Ja => format!("{}日", dt.day()),
+        "Do" => ordinal(dt.day()),
+        "DD" => format!("{:02}", dt.day()),
+        "D" => dt.day().to_string(),
+        "dddd" if locale == Locale::Ja => format!("{}曜日", WEEKDAYS_JA[weekday]),
+        "dddd" => WEEKDAYS_EN[weekday].to_string(),
+        "ddd" | "dd" if locale == Locale::Ja => WEEKDAYS_JA[weekday].to_string(),
+        "ddd" => WEEKDAYS_EN[weekday][..3].to_string(),
+        "dd" => WEEKDAYS_EN[weekday][..2].to_string(),
+        "d" => weekday.to_string(),
+        "E" => dt.weekday().number_from_monday().to_string(),
+        "GGGG" | "gggg" => format!("{:04}", dt.iso_week().year()),
+        "WW" | "ww" => format!("{:02}", dt.iso_week().week()),
+        "W" | "w" => dt.iso_week().week().to_string(),
+        "HH" => format!("{:02}", dt.hour()),
+        "H" => dt.hour().to_string(),
+        "hh" => format!("{hour12:02}"),
+        "h" => hour12.to_string(),
+        "mm" => format!("{:02}", dt.minute()),
+        "m" => dt.minute().to_string(),
+        "ss" => format!("{:02}", dt.second()),
+        "s" => dt.second().to_string(),
+        "A" | "a" if locale == Locale::Ja => if pm { "午後" } else { "午前" }.to_string(),
+        "A" => if pm { "PM" } else { "AM" }.to_string(),
+        "a" => if pm { "pm" } else { "am" }.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Moment.js 形式の書式で日時を文字列にする（`[...]` の中はそのまま出力する）
+pub fn format_moment(dt: &NaiveDateTime, pattern: &str, locale: Locale) -> String {
+    let mut out = String::new();
+    let mut rest = pattern;
+    while let Some(c) = rest.chars().next() {
+        if c == '[' {
+            let end = rest.find(']').unwrap_or(rest.len());
+            out.push_str(&rest[1..end]);
+            rest = rest.get(end + 1..).unwrap_or("");
+            continue;
+        }
+        if let Some(t) = TOKENS.iter().find(|t| rest.starts_with(*t)) {
+            out.push_str(&token(dt, t, locale));
+            rest = &rest[t.len()..];
+            continue;
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// 日時を文字列にする（`%` を含む書式は strftime 形式、それ以外は Moment.js 形式）
+pub fn format(dt: &NaiveDateTime, pattern: &str, locale: Locale) -> Result<String, String> {
+    if !pattern.contains('%') {
+        return Ok(format_moment(dt, pattern, locale));
+    }
+    let mut out = String::new();
+    // 不正な書式は Display がエラーを返すので、ここで捕まえる
+    write!(out, "{}", dt.format(pattern)).map_err(|_| tr!("Invalid date format: {pattern}"))?;
+    Ok(out)
+}
+
+/// 今の言語で現在の日時を文字列にする
+pub fn format_now(pattern: &str) -> Result<String, String> {
+    format(&Local::now().naive_local(), pattern, i18n::locale())
+}
+
+/// タイムゾーン（`local`・`UTC`・`Z`・`+09:00`・`-0530`）の時差（`local` は `None`）
+pub fn parse_timezone(zone: &str) -> Result<Option<FixedOffset>, String> {
+    let zone = zone.trim();
+    match zone.to_ascii_lowercase().as_str() {
+        "" | "local" => return Ok(None),
+        "utc" | "gmt" | "z" => return Ok(FixedOffset::east_opt(0)),
+        _ => {}
+    }
+    let invalid = || tr!("Invalid timezone: {zone}");
+    let (sign, digits) = match zone.as_bytes().first() {
+        Some(b'+') => (1, &zone[1..]),
+        Some(b'-') => (-1, &zone[1..]),
+        _ => return Err(invalid()),
+    };
+    let digits = digits.replace(':', "");
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.as_str(), "0"),
+        4 => digits.split_at(2),
+        _ => return Err(invalid()),
+    };
+    let hours: i32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+        .map(Some)
+        .ok_or_else(invalid)
+}
+
+/// タイムゾーンでの現在の日時
+pub fn now_in(zone: Option<FixedOffset>) -> NaiveDateTime {
+    match zone {
+        Some(zone) => Utc::now().with_timezone(&zone).naive_local(),
+        None => Local::now().naive_local(),
+    }
+}
+
+/// `YYYY-MM-DD` の日付
+pub fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").map_err(|_| tr!("Invalid date: {date}"))
+}
+
+/// 日付（省略時は今日）から `offset_days` 日後の日時を書式で文字列にする
+///
+/// `locale` を省くとバックエンドの文言の言語、`timezone` を省くとこの端末の時刻を使う。
+#[tauri::command]
+pub fn format_date(
+    pattern: Option<String>,
+    offset_days: Option<i64>,
+    locale: Option<String>,
+    date: Option<String>,
+    timezone: Option<String>,
+) -> Result<String, String> {
+    let locale = match locale.as_deref() {
+        Some(lang) => Locale::parse(lang).ok_or_else(|| tr!("Unsupported language: {lang}"))?,
+        None => i18n::locale(),
+    };
+    let now = now_in(parse_timezone(timezone.as_deref().unwrap_or_default())?);
+    let base = match date {
+        Some(date) => parse_date(&date)?.and_time(now.time()),
+        None => now,
+    };
+    let dt = base
+        .checked_add_signed(Duration::days(offset_days.unwrap_or(0)))
+        .ok_or_else(|| tr!("Date out of range"))?;
+    format(&dt, pattern.as_deref().unwrap_or("YYYY-MM-DD"), locale)
+}
+
+/// 曜日の名前（英語の名前・略称と日本語）から、日曜日を 0 とする番号
+fn weekday_number(word: &str) -> Option<u32> {
+    let word = word
+        .trim_end_matches('.')
+        .trim_end_matches("曜日")
+        .trim_end_matches('曜');
+    if let Some(i) = WEEKDAYS_JA.iter().position(|w| *w == word) {
+        return Some(i as u32);
+    }
+    (word.len() >= 3)
+        .then(|| {
+            WEEKDAYS_EN
+                .iter()
+                .position(|w| w.to_ascii_lowercase().starts_with(word))
+        })
+        .flatten()
+        .map(|i| i as u32)
+}
+
+/// 月の名前（英語の名前と略称）から 1 始まりの番号
+fn month_number(word: &str) -> Option<u32> {
+    let word = word.trim_end_matches('.');
+    (word.len() >= 3)
+        .then(|| {
+            MONTHS_EN
+                .iter()
+                .position(|m| m.to_ascii_lowercase().starts_with(word))
+        })
+        .flatten()
+        .map(|i| i as u32 + 1)
+}
+
+/// 期間の単位
+#[derive(Clone, Copy)]
+enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+fn unit(word: &str) -> Option<Unit> {
+    match word.trim_end_matches('s') {
+        "d" | "day" | "日" => Some(Unit::Day),
+        "w" | "wk" | "week" | "週" | "週間" => Some(Unit::Week),
+        "m" | "mo" | "month" | "か月" | "ヶ月" | "ヵ月" | "カ月" | "ケ月" | "月" => {
+            Some(Unit::Month)
+        }
+        "y" | "yr" | "year" | "年" => Some(Unit::Year),
+        _ => None,
+    }
+}
+
+/// 期間を足す（月と年は月末を超えないようにする）
+fn add(date: NaiveDate, amount: i64, unit: Unit) -> Option<NaiveDate> {
+    let months = |n: i64| {
+        let months = Months::new(u32::try_from(n.unsigned_abs()).ok()?);
+        if n >= 0 {
+            date.checked_add_months(months)
+        } else {
+            date.checked_sub_months(months)
+        }
+    };
+    match unit {
+        Unit::Day => date.checked_add_signed(Duration::days(amount)),
+        Unit::Week => date.checked_add_signed(Duration::weeks(amount)),
+        Unit::Month => months(amount),
+        Unit::Year => months(amount.checked_mul(12)?),
+    }
+}
+
+/// `today` から見た曜日の日付（`offset` が 0 なら今日以降、1 なら今日より後、-1 なら今日より前）
+fn weekday_from(today: NaiveDate, weekday: u32, offset: i64) -> NaiveDate {
+    let current = today.weekday().num_days_from_sunday() as i64;
+    let ahead = (weekday as i64 - current).rem_euclid(7);
+    let days = match offset {
+        0 => ahead,
+        o if o > 0 => {
+            if ahead == 0 {
+                7
+            } else {
+                ahead
+            }
+        }
+        _ => ahead - 7,
+    };
+    today + Duration::days(days)
+}
+
+/// 月曜日から始まる週の曜日（`weeks` 週後）
+fn weekday_of_week(today: NaiveDate, weekday: u32, weeks: i64) -> NaiveDate {
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let from_monday = (weekday as i64 + 6) % 7;
+    monday + Duration::days(weeks * 7 + from_monday)
+}
+
+/// 月と日（年を省けば今日以降で最も近い日）
+fn month_day(today: NaiveDate, year: Option<i32>, month: u32, day: u32) -> Option<NaiveDate> {
+    match year {
+        Some(year) => NaiveDate::from_ymd_opt(year, month, day),
+        None => {
+            let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+            if date < today {
+                NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+            } else {
+                Some(date)
+            }
+        }
+    }
+}
+
+fn number(word: &str) -> Option<i64> {
+    match word {
+        "a" | "an" | "one" => Some(1),
+        "two" => Some(2),
+        "three" => Some(3),
+        "four" => Some(4),
+        "five" => Some(5),
+        _ => word.parse().ok(),
+    }
+}
+
+/// 数字と単位の続き（`3日` `2週間`）に分ける
+fn split_number(word: &str) -> Option<(i64, &str)> {
+    let digits = word.len() - word.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    Some((word[..digits].parse().ok()?, &word[digits..]))
+}
+
+/// 日本語の言い方（`明日` `3日後` `2週間前` `来週の金曜日` `5月3日` `2025年5月3日` `月末`）
+fn natural_date_ja(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let text = text.as_str();
+    match text {
+        "今日" | "本日" => return Some(today),
+        "明日" => return Some(today + Duration::days(1)),
+        "明後日" | "あさって" => return Some(today + Duration::days(2)),
+        "昨日" => return Some(today - Duration::days(1)),
+        "一昨日" | "おととい" => return Some(today - Duration::days(2)),
+        "来週" => return Some(today + Duration::weeks(1)),
+        "先週" => return Some(today - Duration::weeks(1)),
+        "来月" => return add(today, 1, Unit::Month),
+        "先月" => return add(today, -1, Unit::Month),
+        "来年" => return add(today, 1, Unit::Year),
+        "去年" | "昨年" => return add(today, -1, Unit::Year),
+        "月末" | "今月末" => {
+            let next = add(today.with_day(1)?, 1, Unit::Month)?;
+            return Some(next - Duration::days(1));
+        }
+        _ => {}
+    }
+    // `3日後` `2週間前`
+    for (suffix, sign) in [("後", 1), ("先", 1), ("前", -1)] {
+        if let Some((n, rest)) = text.strip_suffix(suffix).and_then(split_number) {
+            return add(today, sign * n, unit(rest)?);
+        }
+    }
+    // `来週の金曜日` `今週金曜` `先週の月曜`
+    for (prefix, weeks) in [("来週", 1), ("今週", 0), ("先週", -1), ("再来週", 2)] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            let weekday = weekday_number(rest.trim_start_matches('の'))?;
+            return Some(weekday_of_week(today, weekday, weeks));
+        }
+    }
+    if let Some(weekday) = weekday_number(text) {
+        return Some(weekday_from(today, weekday, 0));
+    }
+    // `2025年5月3日` `5月3日`
+    let (year, rest) = match text.split_once('年') {
+        Some((year, rest)) => (Some(year.parse().ok()?), rest),
+        None => (None, text),
+    };
+    let (month, day) = rest.strip_suffix('日')?.split_once('月')?;
+    month_day(today, year, month.parse().ok()?, day.parse().ok()?)
+}
+
+/// 自然な言い方の日付を `today` から計算する（分からなければ `None`）
+///
+/// 英語: `today` `tomorrow` `yesterday` `in 3 days` `2 weeks ago` `next friday` `last monday`
+/// `friday`（今日以降）`next week` `next month` `end of month` `May 3` `3 May 2025` `+3d` `-1w`、
+/// 日付（`2025-05-03` `2025/5/3` `5/3`）。日本語の言い方も受け付ける。
+pub fn natural_date(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let text = text.trim().to_lowercase();
+    let text = text.trim_end_matches(['.', ',', '!']);
+    if !text.is_ascii() {
+        return natural_date_ja(text, today);
+    }
+    // `2025-05-03` `2025/5/3` `5/3`
+    let parts: Vec<&str> = text.split(['-', '/']).collect();
+    if parts.len() >= 2
+        && parts
+            .iter()
+            .all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()))
+    {
+        let numbers: Vec<u32> = parts.iter().filter_map(|p| p.parse().ok()).collect();
+        return match numbers[..] {
+            [year, month, day] if parts[0].len() == 4 => {
+                NaiveDate::from_ymd_opt(year as i32, month, day)
+            }
+            [month, day] => month_day(today, None, month, day),
+            _ => None,
+        };
+    }
+    // `+3d` `-2w`
+    if let Some(rest) = text.strip_prefix(['+', '-']) {
+        let (n, rest) = split_number(rest)?;
+        let sign = if text.starts_with('-') { -1 } else { 1 };
+        return add(today, sign * n, unit(rest)?);
+    }
+
+    let words: Vec<&str> = text.split_whitespace().filter(|w| *w != "on").collect();
+    match words[..] {
+        ["today"] | ["now"] => Some(today),
+        ["tomorrow"] => Some(today + Duration::days(1)),
+        ["yesterday"] => Some(today - Duration::days(1)),
+        ["day", "after", "tomorrow"] => Some(today + Duration::days(2)),
+        ["day", "before", "yesterday"] => Some(today - Duration::days(2)),
+        ["in", n, unit_word] | [n, unit_word, "from", "now"] | [n, unit_word, "later"] => {
+            add(today, number(n)?, unit(unit_word)?)
+        }
+        [n, unit_word, "ago"] => add(today, -number(n)?, unit(unit_word)?),
+        ["end", "of", "month"] | ["end", "of", "the", "month"] => {
+            let next = add(today.with_day(1)?, 1, Unit::Month)?;
+            Some(next - Duration::days(1))
+        }
+        ["end", "of", "week"] | ["end", "of", "the", "week"] => Some(weekday_of_week(today, 0, 0)),
+        [which @ ("next" | "last" | "this"), word] => {
+            let offset = match which {
+                "next" => 1,
+                "last" => -1,
+                _ => 0,
+            };
+            match weekday_number(word) {
+                Some(weekday) => Some(weekday_from(today, weekday, offset)),
+                None => add(today, offset, unit(word)?),
+            }
+        }
+        [word] => Some(weekday_from(today, weekday_number(word)?, 0)),
+        // `may 3` `may 3rd 2025` `3 may` `3 may 2025`
+        [a, b] | [a, b, _] => {
+            let year = match words.get(2) {
+                Some(year) => Some(year.parse().ok()?),
+                None => None,
+            };
+            let day = |w: &str| {
+                w.trim_end_matches(|c: char| c.is_ascii_alphabetic())
+                    .parse::<u32>()
+                    .ok()
+            };
+            let (month, day) = match month_number(a) {
+                Some(month) => (month, day(b)?),
+                None => (month_number(b)?, day(a)?),
+            };
+            month_day(today, year, month, day)
+        }
+        _ => None,
+    }
+}
+
+/// 文の先頭にある日付の言い方（長い言い方から順に試す）
+fn leading_date(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    // `(due: friday)` のように括弧で閉じていればそこまで
+    let text = text.split(')').next().unwrap_or_default();
+    let words: Vec<&str> = text.split_whitespace().take(5).collect();
+    (1..=words.len())
+        .rev()
+        .find_map(|n| natural_date(&words[..n].join(" "), today))
+}
+
+/// タスクのテキストの期日（`📅 2025-05-03` `@due(next friday)` `(due: tomorrow)` `期限: 明日`）
+pub fn due_date(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    if let Some(i) = text.find('📅') {
+        return leading_date(&text[i + '📅'.len_utf8()..], today);
+    }
+    if let Some(i) = text.find("@due(") {
+        let rest = &text[i + 5..];
+        return natural_date(&rest[..rest.find(')')?], today);
+    }
+    let lower = text.to_lowercase();
+    ["due:", "due ", "期限:", "期限：", "締切:", "締切："]
+        .iter()
+        .find_map(|marker| {
+            let i = lower.find(marker)?;
+            let rest = &lower[i + marker.len()..];
+            leading_date(rest, today)
+        })
+}
+
+/// 自然な言い方の日付の計算結果
+#[derive(Debug, Serialize)]
+pub struct NaturalDate {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    /// `pattern` の書式で書いた日付
+    pub formatted: String,
+    /// 今日からの日数
+    pub days_from_today: i64,
+}
+
+/// 自然な言い方の日付（`next friday` `in 3 weeks` `来週の金曜日` など）を計算し、書式で文字列にする
+///
+/// `date`（`YYYY-MM-DD`）を渡すとその日を今日として計算する。`pattern` の省略時は `YYYY-MM-DD`。
+#[tauri::command]
+pub fn parse_natural_date(
+    text: String,
+    pattern: Option<String>,
+    locale: Option<String>,
+    date: Option<String>,
+) -> Result<NaturalDate, String> {
+    let locale = match locale.as_deref() {
+        Some(lang) => Locale::parse(lang).ok_or_else(|| tr!("Unsupported language: {lang}"))?,
+        None => i18n::locale(),
+    };
+    let now = Local::now().naive_local();
+    let today = match date {
+        Some(date) => parse_date(&date)?,
+        None => now.date(),
+    };
+    let parsed =
+        natural_date(&text, today).ok_or_else(|| tr!("Could not understand date: {}", text))?;
+    Ok(NaturalDate {
+        date: parsed.format("%Y-%m-%d").to_string(),
+        formatted: format(
+            &parsed.and_time(now.time()),
+            pattern.as_deref().unwrap_or("YYYY-MM-DD"),
+            locale,
+        )?,
+        days_from_today: (parsed - today).num_days(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{due_date, natural_date};
+    use chrono::NaiveDate;
+
+    fn date(y: i32, m: u32, d: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(y, m, d)
+    }
+
+    #[test]
+    fn english_phrases() {
+        // 2025-05-07 は水曜日
+        let today = date(2025, 5, 7).unwrap();
+        assert_eq!(natural_date("next friday", today), date(2025, 5, 9));
+        assert_eq!(natural_date("Next Friday.", today), date(2025, 5, 9));
+        assert_eq!(natural_date("in 3 weeks", today), date(2025, 5, 28));
+        assert_eq!(natural_date("2 days ago", today), date(2025, 5, 5));
+        assert_eq!(natural_date("last monday", today), date(2025, 5, 5));
+        assert_eq!(natural_date("end of month", today), date(2025, 5, 31));
+        assert_eq!(natural_date("may 3", today), date(2026, 5, 3));
+        assert_eq!(natural_date("+1w", today), date(2025, 5, 14));
+        assert_eq!(natural_date("someday", today), None);
+        // 金曜日の `next friday` は翌週
+        let friday = date(2025, 5, 9).unwrap();
+        assert_eq!(natural_date("next friday", friday), date(2025, 5, 16));
+        assert_eq!(natural_date("friday", friday), date(2025, 5, 9));
+    }
+
+    #[test]
+    fn japanese_phrases() {
+        let today = date(2025, 5, 7).unwrap();
+        assert_eq!(natural_date("来週の金曜日", today), date(2025, 5, 16));
+        assert_eq!(natural_date("3日後", today), date(2025, 5, 10));
+        assert_eq!(natural_date("2週間前", today), date(2025, 4, 23));
+        assert_eq!(natural_date("5月3日", today), date(2026, 5, 3));
+    }
+
+    #[test]
+    fn due_dates_in_tasks() {
+        let today = date(2025, 5, 7).unwrap();
+        assert_eq!(
+            due_date("- [ ] ship @due(next friday)", today),
+            date(2025, 5, 9)
+        );
+        assert_eq!(
+            due_date("- [ ] ship (due: in 3 weeks)", today),
+            date(2025, 5, 28)
+        );
+        assert_eq!(
+            due_date("- [ ] ship 📅 2025-06-01", today),
+            date(2025, 6, 1)
+        );
+        assert_eq!(due_date("- [ ] 資料 期限: 明日", today), date(2025, 5, 8));
+        assert_eq!(due_date("- [ ] ship it", today), None);
+    }
+}
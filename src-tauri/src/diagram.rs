@@ -0,0 +1,286 @@
+// Graphviz・PlantUML の図（```dot・```plantuml のコードブロックを SVG にする）
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use tauri_plugin_http::reqwest;
+
+use crate::shell::{self, RunOptions};
+use crate::state::AppState;
+use crate::text::escape_html;
+use crate::tr;
+
+/// キャッシュする図の数
+const MAX_CACHE: usize = 256;
+
+/// `dot`・`java` の実行のタイムアウト
+const RENDER_TIMEOUT: Duration = Duration::from_secs(30);
+/// 取り込む SVG の上限
+const MAX_SVG: usize = 8 << 20;
+/// レンダラーのエラー出力の上限
+const MAX_STDERR: usize = 64 * 1024;
+
+/// PlantUML サーバーへの接続と応答の読み込みのタイムアウト
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const READ_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// PlantUML サーバーの URL に使う Base64 の文字
+const PLANTUML_ALPHABET: &[u8; 64] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz-_";
+
+/// 図の描画の設定
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DiagramConfig {
+    /// Graphviz の `dot` コマンド
+    pub graphviz_path: String,
+    /// PlantUML サーバー（例: `https://www.plantuml.com/plantuml`）。`plantuml_jar` より優先する
+    pub plantuml_server: Option<String>,
+    /// ローカルの `plantuml.jar`
+    pub plantuml_jar: Option<String>,
+    pub java_path: String,
+}
+
+impl Default for DiagramConfig {
+    fn default() -> Self {
+        Self {
+            graphviz_path: "dot".to_string(),
+            plantuml_server: None,
+            plantuml_jar: None,
+            java_path: "java".to_string(),
+        }
+    }
+}
+
+/// 図の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagramKind {
+    Graphviz,
+    PlantUml,
+}
+
+impl DiagramKind {
+    /// コードブロックの言語名から
+    pub fn from_lang(lang: &str) -> Option<Self> {
+        match lang.to_lowercase().as_str() {
+            "dot" | "graphviz" => Some(DiagramKind::Graphviz),
+            "plantuml" | "puml" => Some(DiagramKind::PlantUml),
+            _ => None,
+        }
+    }
+
+    fn class(self) -> &'static str {
+        match self {
+            DiagramKind::Graphviz => "graphviz",
+            DiagramKind::PlantUml => "plantuml",
+        }
+    }
+}
+
+/// 図の設定と描画結果のキャッシュ（ソースのハッシュをキーにする）
+#[derive(Default)]
+pub struct DiagramState {
+    config: Mutex<DiagramConfig>,
+    cache: Mutex<HashMap<u64, String>>,
+}
+
+impl DiagramState {
+    /// 図を SVG にする（同じソースはキャッシュを使う）
+    pub fn render(&self, kind: DiagramKind, source: &str) -> Result<String, String> {
+        let config = self.config.lock().unwrap().clone();
+        let mut hasher = DefaultHasher::new();
+        (kind, source).hash(&mut hasher);
+        let key = hasher.finish();
+        if let Some(svg) = self.cache.lock().unwrap().get(&key) {
+            return Ok(svg.clone());
+        }
+        let svg = match kind {
+            DiagramKind::Graphviz => graphviz(&config, source)?,
+            DiagramKind::PlantUml => plantuml(&config, source)?,
+        };
+        let svg = strip_prolog(&svg);
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= MAX_CACHE {
+            cache.clear();
+        }
+        cache.insert(key, svg.clone());
+        Ok(svg)
+    }
+
+    /// プレビューやエクスポートに埋め込む HTML（失敗した場合はエラーを表示する）
+    pub fn html(&self, kind: DiagramKind, source: &str) -> String {
+        match self.render(kind, source) {
+            Ok(svg) => format!(
+                "<div class=\"diagram diagram-{}\">\n{svg}\n</div>\n",
+                kind.class()
+            ),
+            Err(e) => format!("<pre class=\"diagram-error\">{}</pre>\n", escape_html(&e)),
+        }
+    }
+}
+
+/// XML 宣言や DOCTYPE を除き、`<svg` から始まるようにする
+fn strip_prolog(svg: &str) -> String {
+    match svg.find("<svg") {
+        Some(start) => svg[start..].trim_end().to_string(),
+        None => svg.trim().to_string(),
+    }
+}
+
+/// 標準入力にソースを渡してコマンドを実行する
+fn run(command: Command, name: &str, input: &str) -> Result<String, String> {
+    let output = shell::run(
+        command,
+        name,
+        RunOptions {
+            input: Some(input.as_bytes().to_vec()),
+            timeout: RENDER_TIMEOUT,
+            max_stdout: MAX_SVG,
+            max_stderr: MAX_STDERR,
+            ..Default::default()
+        },
+    )?;
+    let Some(status) = output.status else {
+        return Err(tr!("{name} timed out after {} s", RENDER_TIMEOUT.as_secs()));
+    };
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(tr!("{name} failed: {}", stderr.trim()));
+    }
+    if output.stdout_truncated {
+        return Err(tr!("{name} output exceeded {} bytes", MAX_SVG));
+    }
+    String::from_utf8(output.stdout).map_err(|_| tr!("{name} returned invalid UTF-8"))
+}
+
+fn graphviz(config: &DiagramConfig, source: &str) -> Result<String, String> {
+    let mut command = Command::new(&config.graphviz_path);
+    command.arg("-Tsvg");
+    run(command, "Graphviz", source)
+}
+
+/// `@startuml` がなければ補う
+fn plantuml_source(source: &str) -> String {
+    if source.trim_start().starts_with("@start") {
+        source.to_string()
+    } else {
+        format!("@startuml\n{}\n@enduml\n", source.trim_end())
+    }
+}
+
+/// PlantUML サーバーの URL 用の符号化（Deflate と独自の Base64）
+fn plantuml_encode(source: &str) -> Result<String, String> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder
+        .write_all(source.as_bytes())
+        .map_err(|e| tr!("Failed to compress data: {e}"))?;
+    let data = encoder
+        .finish()
+        .map_err(|e| tr!("Failed to compress data: {e}"))?;
+    let mut out = String::with_capacity(data.len() * 4 / 3 + 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            out.push(PLANTUML_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    Ok(out)
+}
+
+/// PlantUML サーバーから SVG を取得する
+///
+/// 描画は同期の処理から呼ばれ、非同期のコマンドの中にいることもあるので、要求は別のスレッドで待つ。
+fn fetch_plantuml(url: String, server: &str) -> Result<String, String> {
+    let server = server.to_string();
+    thread::spawn(move || {
+        tauri::async_runtime::block_on(async {
+            let client = reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .read_timeout(READ_TIMEOUT)
+                .build()
+                .map_err(|e| tr!("Failed to connect to {server}: {e}"))?;
+            let response = client
+                .get(&url)
+                .send()
+                .await
+                .map_err(|e| tr!("Failed to connect to {server}: {e}"))?;
+            let status = response.status();
+            let text = response
+                .text()
+                .await
+                .map_err(|e| tr!("Failed to connect to {server}: {e}"))?;
+            // 構文エラーでもエラー内容を描いた SVG が返る
+            if !status.is_success() && !text.contains("<svg") {
+                return Err(tr!("PlantUML server returned {status}"));
+            }
+            Ok(text)
+        })
+    })
+    .join()
+    .unwrap_or_else(|_| Err(tr!("PlantUML server request failed")))
+}
+
+fn plantuml(config: &DiagramConfig, source: &str) -> Result<String, String> {
+    let source = plantuml_source(source);
+    if let Some(server) = config.plantuml_server.as_deref().filter(|s| !s.is_empty()) {
+        let url = format!(
+            "{}/svg/{}",
+            server.trim_end_matches('/'),
+            plantuml_encode(&source)?
+        );
+        return fetch_plantuml(url, server);
+    }
+    let jar = config
+        .plantuml_jar
+        .as_deref()
+        .filter(|j| !j.is_empty())
+        .ok_or_else(|| tr!("PlantUML is not configured (set a server or plantuml.jar)"))?;
+    let mut command = Command::new(&config.java_path);
+    command.args(["-jar", jar, "-tsvg", "-pipe", "-charset", "UTF-8"]);
+    run(command, "PlantUML", &source)
+}
+
+/// 図の描画を設定する（キャッシュは破棄する）
+#[tauri::command]
+pub fn configure_diagrams(state: State<'_, AppState>, config: DiagramConfig) {
+    *state.diagrams.config.lock().unwrap() = config;
+    state.diagrams.cache.lock().unwrap().clear();
+    state.parse_cache.clear();
+}
+
+/// 図のソースを SVG にする（`lang` はコードブロックの言語名、`path` は文書のファイルかフォルダ）
+///
+/// 外部のレンダラーを使うので、`path` のワークスペースを信頼していなければエラーにする。
+#[tauri::command(async)]
+pub fn render_diagram(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    lang: String,
+    source: String,
+    path: Option<String>,
+) -> Result<String, String> {
+    let kind = DiagramKind::from_lang(&lang).ok_or_else(|| tr!("Unsupported diagram: {lang}"))?;
+    let path = path
+        .filter(|p| !p.trim().is_empty())
+        .ok_or_else(|| tr!("Diagrams require a trusted workspace"))?;
+    state
+        .trust
+        .require(&app, Path::new(&path), "diagram renderers")?;
+    state.diagrams.render(kind, &source)
+}
@@ -0,0 +1,344 @@
+// 2 つの版の差分を HTML にする（下書きの変更点を共有する）
+//
+// 行単位で差分を取り、変更した行の中はさらに単語単位（かな・漢字は 1 文字ずつ）で比べて強調する。
+// 並べ方は 1 列（削除と追加を上下に並べる）と左右の 2 列から選ぶ。
+
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::diff;
+use crate::export;
+use crate::text::{self, escape_html};
+use crate::tr;
+
+/// 既定で変更の前後に表示する変わらない行の数
+const DEFAULT_CONTEXT: usize = 3;
+
+/// 差分の並べ方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLayout {
+    #[default]
+    Inline,
+    SideBySide,
+}
+
+/// `export_diff_html` の設定
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct DiffHtmlOptions {
+    pub layout: DiffLayout,
+    pub theme: Option<String>,
+    /// 変更の前後に表示する変わらない行の数（`None` は 3 行）
+    pub context: Option<usize>,
+    /// 指定すると HTML をこのファイルにも書き込む
+    pub out_path: Option<String>,
+}
+
+const DIFF_CSS: &str = r#"table.diff { width: 100%; border-collapse: collapse; font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 0.9em; }
+table.diff td, table.diff th { border: none; padding: 0.1em 0.5em; vertical-align: top; }
+table.diff th { background: var(--bg-secondary); text-align: left; }
+.diff-no { width: 1%; color: var(--text-secondary); text-align: right; user-select: none; white-space: nowrap; }
+.diff-text { white-space: pre-wrap; word-break: break-word; }
+.diff-del .diff-text, td.diff-del { background: rgba(248, 81, 73, 0.15); }
+.diff-ins .diff-text, td.diff-ins { background: rgba(46, 160, 67, 0.15); }
+.diff-text del { background: rgba(248, 81, 73, 0.4); text-decoration: none; }
+.diff-text ins { background: rgba(46, 160, 67, 0.4); text-decoration: none; }
+.diff-skip td { color: var(--text-secondary); background: var(--bg-secondary); text-align: center; }
+.diff-summary { color: var(--text-secondary); }
+"#;
+
+/// 変更したかどうかを付けた行の断片
+type Line<'a> = Vec<(&'a str, bool)>;
+
+/// 表の 1 行
+enum Row<'a> {
+    Same {
+        old: usize,
+        new: usize,
+        text: &'a str,
+    },
+    Change {
+        old: Option<(usize, Line<'a>)>,
+        new: Option<(usize, Line<'a>)>,
+    },
+    Skip(usize),
+}
+
+/// 単語・空白・記号に分ける（かな・漢字は 1 文字ずつ、改行は 1 つの記号）
+fn tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut kind = 0;
+    for (i, c) in text.char_indices() {
+        let class = if c == '\n' || text::is_cjk(c) {
+            0
+        } else if c.is_alphanumeric() || c == '_' {
+            1
+        } else if c.is_whitespace() {
+            2
+        } else {
+            3
+        };
+        if let Some(s) = start {
+            if class != kind || class == 0 || class == 3 {
+                tokens.push(&text[s..i]);
+                start = Some(i);
+            }
+        } else {
+            start = Some(i);
+        }
+        kind = class;
+    }
+    if let Some(s) = start {
+        tokens.push(&text[s..]);
+    }
+    tokens
+}
+
+/// 単語単位の差分を行ごとに分ける
+fn split_lines<'a>(tokens: &[&'a str], changed: &[bool]) -> Vec<Line<'a>> {
+    let mut lines = vec![Vec::new()];
+    for (&token, &changed) in tokens.iter().zip(changed) {
+        if token == "\n" {
+            lines.push(Vec::new());
+        } else {
+            lines.last_mut().unwrap().push((token, changed));
+        }
+    }
+    lines
+}
+
+/// 変更した行の範囲どうしを単語単位で比べる
+fn word_diff<'a>(old: &[&'a str], new: &[&'a str]) -> (Vec<Line<'a>>, Vec<Line<'a>>) {
+    let old_text = old.join("\n");
+    let new_text = new.join("\n");
+    let a = tokens(&old_text);
+    let b = tokens(&new_text);
+    let mut old_changed = vec![false; a.len()];
+    let mut new_changed = vec![false; b.len()];
+    for hunk in diff::diff_lines(&a, &b) {
+        old_changed[hunk.old].fill(true);
+        new_changed[hunk.new].fill(true);
+    }
+    // 断片は元の行を指すように取り直す（結合した文字列は関数の中でしか生きない）
+    let relink = |lines: &[&'a str], split: Vec<Line<'_>>| -> Vec<Line<'a>> {
+        lines
+            .iter()
+            .zip(split)
+            .map(|(line, pieces)| {
+                let mut offset = 0;
+                pieces
+                    .into_iter()
+                    .map(|(piece, changed)| {
+                        let slice = &line[offset..offset + piece.len()];
+                        offset += piece.len();
+                        (slice, changed)
+                    })
+                    .collect()
+            })
+            .collect()
+    };
+    let old_lines = if old.is_empty() {
+        Vec::new()
+    } else {
+        relink(old, split_lines(&a, &old_changed))
+    };
+    let new_lines = if new.is_empty() {
+        Vec::new()
+    } else {
+        relink(new, split_lines(&b, &new_changed))
+    };
+    (old_lines, new_lines)
+}
+
+/// 差分の表の行（変わらない行は前後 `context` 行だけ残す）
+fn rows<'a>(old: &[&'a str], new: &[&'a str], context: usize) -> Vec<Row<'a>> {
+    let hunks = diff::diff_lines(old, new);
+    let mut rows = Vec::new();
+    let same = |rows: &mut Vec<Row<'a>>, i: usize, j: usize, n: usize, first: bool, last: bool| {
+        let head = if first { 0 } else { context.min(n) };
+        let tail = if last { 0 } else { context.min(n - head) };
+        let line = |k: usize| Row::Same {
+            old: i + k + 1,
+            new: j + k + 1,
+            text: old[i + k],
+        };
+        rows.extend((0..head).map(line));
+        if n > head + tail {
+            rows.push(Row::Skip(n - head - tail));
+        }
+        rows.extend((n - tail..n).map(line));
+    };
+
+    let (mut i, mut j) = (0, 0);
+    for (index, hunk) in hunks.iter().enumerate() {
+        same(&mut rows, i, j, hunk.old.start - i, index == 0, false);
+        let (old_lines, new_lines) = word_diff(&old[hunk.old.clone()], &new[hunk.new.clone()]);
+        let len = old_lines.len().max(new_lines.len());
+        let mut old_lines = old_lines.into_iter();
+        let mut new_lines = new_lines.into_iter();
+        for k in 0..len {
+            rows.push(Row::Change {
+                old: old_lines.next().map(|l| (hunk.old.start + k + 1, l)),
+                new: new_lines.next().map(|l| (hunk.new.start + k + 1, l)),
+            });
+        }
+        i = hunk.old.end;
+        j = hunk.new.end;
+    }
+    same(&mut rows, i, j, old.len() - i, hunks.is_empty(), true);
+    rows
+}
+
+/// 続けて変更した断片は 1 つの `<del>` / `<ins>` にまとめる
+fn line_html(line: &Line, tag: &str) -> String {
+    let mut html = String::new();
+    let mut run = String::new();
+    let flush = |html: &mut String, run: &mut String| {
+        if run.trim().is_empty() {
+            html.push_str(&escape_html(run));
+        } else {
+            html.push_str(&format!("<{tag}>{}</{tag}>", escape_html(run)));
+        }
+        run.clear();
+    };
+    for (piece, changed) in line {
+        if *changed {
+            run.push_str(piece);
+        } else {
+            flush(&mut html, &mut run);
+            html.push_str(&escape_html(piece));
+        }
+    }
+    flush(&mut html, &mut run);
+    html
+}
+
+fn inline_table(rows: &[Row]) -> String {
+    let mut html = String::from("<table class=\"diff diff-inline\">\n<tbody>\n");
+    let mut added = Vec::new();
+    let flush = |html: &mut String, added: &mut Vec<String>| html.extend(added.drain(..));
+    for row in rows {
+        match row {
+            Row::Same { old, new, text } => {
+                flush(&mut html, &mut added);
+                html.push_str(&format!(
+                    "<tr class=\"diff-same\"><td class=\"diff-no\">{old}</td><td class=\"diff-no\">{new}</td><td class=\"diff-text\">{}</td></tr>\n",
+                    escape_html(text)
+                ));
+            }
+            Row::Skip(n) => {
+                flush(&mut html, &mut added);
+                html.push_str(&format!(
+                    "<tr class=\"diff-skip\"><td colspan=\"3\">{}</td></tr>\n",
+                    escape_html(&tr!("{n} unchanged lines"))
+                ));
+            }
+            // 変更のまとまりごとに、削除した行を先に、追加した行を後に並べる
+            Row::Change { old, new } => {
+                if let Some((no, line)) = old {
+                    html.push_str(&format!(
+                        "<tr class=\"diff-del\"><td class=\"diff-no\">{no}</td><td class=\"diff-no\"></td><td class=\"diff-text\">{}</td></tr>\n",
+                        line_html(line, "del")
+                    ));
+                }
+                if let Some((no, line)) = new {
+                    added.push(format!(
+                        "<tr class=\"diff-ins\"><td class=\"diff-no\"></td><td class=\"diff-no\">{no}</td><td class=\"diff-text\">{}</td></tr>\n",
+                        line_html(line, "ins")
+                    ));
+                }
+            }
+        }
+    }
+    flush(&mut html, &mut added);
+    html.push_str("</tbody>\n</table>\n");
+    html
+}
+
+fn side_by_side_table(rows: &[Row]) -> String {
+    let mut html = format!(
+        "<table class=\"diff diff-side-by-side\">\n<thead><tr><th colspan=\"2\">{}</th><th colspan=\"2\">{}</th></tr></thead>\n<tbody>\n",
+        escape_html(&tr!("Before")),
+        escape_html(&tr!("After"))
+    );
+    let cells = |side: &Option<(usize, Line)>, class: &str, tag: &str| match side {
+        Some((no, line)) => format!(
+            "<td class=\"diff-no\">{no}</td><td class=\"diff-text {class}\">{}</td>",
+            line_html(line, tag)
+        ),
+        None => "<td class=\"diff-no\"></td><td class=\"diff-text\"></td>".to_string(),
+    };
+    for row in rows {
+        match row {
+            Row::Same { old, new, text } => {
+                let text = escape_html(text);
+                html.push_str(&format!(
+                    "<tr class=\"diff-same\"><td class=\"diff-no\">{old}</td><td class=\"diff-text\">{text}</td><td class=\"diff-no\">{new}</td><td class=\"diff-text\">{text}</td></tr>\n"
+                ));
+            }
+            Row::Skip(n) => html.push_str(&format!(
+                "<tr class=\"diff-skip\"><td colspan=\"4\">{}</td></tr>\n",
+                escape_html(&tr!("{n} unchanged lines"))
+            )),
+            Row::Change { old, new } => html.push_str(&format!(
+                "<tr>{}{}</tr>\n",
+                cells(old, "diff-del", "del"),
+                cells(new, "diff-ins", "ins")
+            )),
+        }
+    }
+    html.push_str("</tbody>\n</table>\n");
+    html
+}
+
+/// 2 つの Markdown 文書の差分を、単語単位で強調した HTML 文書にする
+#[tauri::command]
+pub fn export_diff_html(
+    old_content: String,
+    new_content: String,
+    title: Option<String>,
+    options: Option<DiffHtmlOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let old: Vec<&str> = old_content.lines().collect();
+    let new: Vec<&str> = new_content.lines().collect();
+    let rows = rows(&old, &new, options.context.unwrap_or(DEFAULT_CONTEXT));
+
+    let (mut removed, mut added) = (0, 0);
+    for row in &rows {
+        if let Row::Change { old, new } = row {
+            removed += usize::from(old.is_some());
+            added += usize::from(new.is_some());
+        }
+    }
+    let title = title
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| tr!("Changes"));
+    let mut body = format!("<h1>{}</h1>\n", escape_html(&title));
+    if added + removed == 0 {
+        body.push_str(&format!(
+            "<p class=\"diff-summary\">{}</p>\n",
+            escape_html(&tr!("No changes"))
+        ));
+    } else {
+        body.push_str(&format!(
+            "<p class=\"diff-summary\">{}</p>\n",
+            escape_html(&tr!("{added} lines added, {removed} lines removed"))
+        ));
+        body.push_str(&match options.layout {
+            DiffLayout::Inline => inline_table(&rows),
+            DiffLayout::SideBySide => side_by_side_table(&rows),
+        });
+    }
+    let css = export::theme_css(options.theme.as_deref().unwrap_or("light")) + DIFF_CSS;
+    let html = export::html_document_with_css(&title, &body, &css);
+    if let Some(out_path) = options.out_path {
+        let out = Path::new(&out_path);
+        fs::write(out, &html).map_err(|e| tr!("Failed to write {}: {e}", out.display()))?;
+    }
+    Ok(html)
+}
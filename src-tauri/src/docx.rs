@@ -0,0 +1,728 @@
+// Word 文書（.docx）への書き出し
+//
+// 脚注と引用（`[@key, p. 3]`）は角括弧の文字列ではなく Word の脚注か文末脚注にする。
+// 見出し・段落・強調・リンク・リスト・引用・コード・表・区切り線を Word のスタイルに対応させる。
+// 画像は埋め込まず代替テキストを入れる。
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use pulldown_cmark::{Alignment, Event, HeadingLevel, Parser, Tag, TagEnd};
+use regex::Regex;
+use serde::Deserialize;
+use tauri::State;
+
+use crate::export;
+use crate::links;
+use crate::markdown;
+use crate::preprocess::preprocess;
+use crate::state::AppState;
+use crate::text::escape_html;
+use crate::tr;
+use crate::vault::{self, path_string};
+use crate::zip::ZipWriter;
+
+/// リンクの関係 ID の開始番号（それより前は固定の部品）
+const FIRST_LINK_ID: usize = 10;
+/// リストの字下げ（1 段あたり、twip）
+const LIST_INDENT: usize = 720;
+
+/// 脚注の種類
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteKind {
+    /// ページの下の脚注
+    #[default]
+    Footnotes,
+    /// 文書の最後の文末脚注
+    Endnotes,
+}
+
+impl NoteKind {
+    /// 要素名（`footnote` か `endnote`）
+    fn element(self) -> &'static str {
+        match self {
+            Self::Footnotes => "footnote",
+            Self::Endnotes => "endnote",
+        }
+    }
+}
+
+/// `export_docx` のオプション
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct DocxOptions {
+    pub notes: NoteKind,
+    /// `[@key]` `[@key, p. 3; @other]` の引用も脚注にする
+    pub citations: bool,
+}
+
+impl Default for DocxOptions {
+    fn default() -> Self {
+        Self {
+            notes: NoteKind::Footnotes,
+            citations: true,
+        }
+    }
+}
+
+/// 書き出し中のブロックの入れ子
+enum Block {
+    Quote,
+    /// リスト（番号付けの ID と、番号付きか）
+    List(usize),
+    /// リストの項目（最初の段落に番号を付けたか）
+    Item(bool),
+    /// 脚注の定義（ラベルと、書き出し先を切り替える前の本文）
+    Note(String, String),
+}
+
+/// 段落のスタイルと書式
+#[derive(Default)]
+struct Paragraph {
+    style: Option<String>,
+    props: String,
+    runs: String,
+}
+
+struct Writer {
+    kind: NoteKind,
+    citation: Option<Regex>,
+    out: String,
+    blocks: Vec<Block>,
+    paragraph: Option<Paragraph>,
+    /// 強調・太字・取り消し線の入れ子の深さ
+    italic: usize,
+    bold: usize,
+    strike: usize,
+    in_link: bool,
+    /// 表の見出しの行か、列ごとの配置と今の列
+    table_head: bool,
+    alignments: Vec<Alignment>,
+    column: usize,
+    /// コードブロックの中身（`Some` ならコードブロックの中）
+    code: Option<String>,
+    /// 画像の代替テキスト（`Some` なら画像の中）
+    image: Option<String>,
+    /// フロントマターなど読み飛ばすブロックの深さ
+    skip: usize,
+    /// ラベルごとの脚注の定義の本文
+    definitions: HashMap<String, String>,
+    /// 脚注の番号順のラベル（引用は `None` と本文）
+    notes: Vec<(String, Option<String>)>,
+    /// リンク先（関係 ID の順）
+    links: Vec<String>,
+    /// 番号付けのインスタンス（番号付きか、開始番号）
+    numbering: Vec<(bool, u64)>,
+}
+
+fn run_text(text: &str) -> String {
+    format!("<w:t xml:space=\"preserve\">{}</w:t>", escape_html(text))
+}
+
+/// 引用の `[@key, p. 3; @other]` の中身から脚注の文（`key, p. 3; other`）
+fn citation_text(inner: &str) -> String {
+    inner
+        .split(';')
+        .map(|part| part.trim().replacen('@', "", 1))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+impl Writer {
+    fn new(kind: NoteKind, citations: bool) -> Self {
+        Self {
+            kind,
+            citation: citations
+                .then(|| Regex::new(r"\[(-?@[\w:.#$%&+?<>~/-]+[^\[\]]*)\]").unwrap()),
+            out: String::new(),
+            blocks: Vec::new(),
+            paragraph: None,
+            italic: 0,
+            bold: 0,
+            strike: 0,
+            in_link: false,
+            table_head: false,
+            alignments: Vec::new(),
+            column: 0,
+            code: None,
+            image: None,
+            skip: 0,
+            definitions: HashMap::new(),
+            notes: Vec::new(),
+            links: Vec::new(),
+            numbering: Vec::new(),
+        }
+    }
+
+    fn in_note(&self) -> bool {
+        self.blocks.iter().any(|b| matches!(b, Block::Note(..)))
+    }
+
+    /// 段落を始める（入れ子に応じたスタイルと番号を付ける）
+    fn open(&mut self, style: Option<&str>) {
+        if self.paragraph.is_some() {
+            return;
+        }
+        let mut paragraph = Paragraph {
+            style: style.map(str::to_string),
+            ..Default::default()
+        };
+        let depth = self
+            .blocks
+            .iter()
+            .filter(|b| matches!(b, Block::List(_)))
+            .count();
+        let list = self.blocks.iter().rev().find_map(|b| match b {
+            Block::List(num) => Some(*num),
+            _ => None,
+        });
+        let mut numbered = false;
+        if let Some(Block::Item(first)) = self.blocks.last_mut() {
+            numbered = !*first;
+            *first = true;
+        }
+        match list {
+            Some(num) if numbered => {
+                paragraph
+                    .style
+                    .get_or_insert_with(|| "ListParagraph".to_string());
+                paragraph.props = format!(
+                    "<w:numPr><w:ilvl w:val=\"{}\"/><w:numId w:val=\"{num}\"/></w:numPr>",
+                    depth.saturating_sub(1).min(8)
+                );
+            }
+            Some(_) => {
+                paragraph
+                    .style
+                    .get_or_insert_with(|| "ListParagraph".to_string());
+                paragraph.props = format!("<w:ind w:left=\"{}\"/>", depth * LIST_INDENT);
+            }
+            None => {}
+        }
+        if paragraph.style.is_none() {
+            if self.blocks.iter().any(|b| matches!(b, Block::Quote)) {
+                paragraph.style = Some("Quote".to_string());
+            } else if self.in_note() {
+                paragraph.style = Some("FootnoteText".to_string());
+            }
+        }
+        if !self.alignments.is_empty() {
+            let jc = match self.alignments.get(self.column) {
+                Some(Alignment::Center) => "center",
+                Some(Alignment::Right) => "right",
+                _ => "left",
+            };
+            paragraph.props.push_str(&format!("<w:jc w:val=\"{jc}\"/>"));
+        }
+        // 脚注の最初の段落は脚注の番号から始める
+        if let Some(Block::Note(_, _)) = self.blocks.last() {
+            if self.out.is_empty() {
+                paragraph.runs = self.note_mark();
+            }
+        }
+        self.paragraph = Some(paragraph);
+    }
+
+    fn close(&mut self) {
+        let Some(paragraph) = self.paragraph.take() else {
+            return;
+        };
+        let style = paragraph
+            .style
+            .map(|s| format!("<w:pStyle w:val=\"{s}\"/>"))
+            .unwrap_or_default();
+        self.out.push_str(&format!(
+            "<w:p><w:pPr>{style}{}</w:pPr>{}</w:p>\n",
+            paragraph.props, paragraph.runs
+        ));
+    }
+
+    /// 脚注の中の番号（`footnoteRef`）
+    fn note_mark(&self) -> String {
+        let element = self.kind.element();
+        format!(
+            "<w:r><w:rPr><w:rStyle w:val=\"FootnoteReference\"/></w:rPr><w:{element}Ref/></w:r>\
+             <w:r><w:t xml:space=\"preserve\"> </w:t></w:r>"
+        )
+    }
+
+    /// 書式を付けた文字列
+    fn push_run(&mut self, content: &str, char_style: Option<&str>) {
+        if self.paragraph.is_none() {
+            self.open(None);
+        }
+        let mut props = String::new();
+        if let Some(style) = char_style.or(self.in_link.then_some("Hyperlink")) {
+            props.push_str(&format!("<w:rStyle w:val=\"{style}\"/>"));
+        }
+        if self.bold > 0 || self.table_head {
+            props.push_str("<w:b/>");
+        }
+        if self.italic > 0 {
+            props.push_str("<w:i/>");
+        }
+        if self.strike > 0 {
+            props.push_str("<w:strike/>");
+        }
+        let run = format!("<w:r><w:rPr>{props}</w:rPr>{content}</w:r>");
+        if let Some(paragraph) = self.paragraph.as_mut() {
+            paragraph.runs.push_str(&run);
+        }
+    }
+
+    /// 脚注の参照（番号は出現順）
+    fn push_note_reference(&mut self, label: String, citation: Option<String>) {
+        let id = match self
+            .notes
+            .iter()
+            .position(|(l, c)| citation.is_none() && c.is_none() && *l == label)
+        {
+            Some(i) => i + 1,
+            None => {
+                self.notes.push((label, citation));
+                self.notes.len()
+            }
+        };
+        let element = self.kind.element();
+        self.push_run(
+            &format!("<w:{element}Reference w:id=\"{id}\"/>"),
+            Some("FootnoteReference"),
+        );
+    }
+
+    /// 本文の文字列（引用は脚注にする）
+    fn push_text(&mut self, text: &str) {
+        let Some(citation) = self.citation.clone() else {
+            self.push_run(&run_text(text), None);
+            return;
+        };
+        let mut last = 0;
+        for found in citation.captures_iter(text) {
+            let whole = found.get(0).unwrap();
+            let before = text[last..whole.start()].trim_end_matches(' ');
+            if !before.is_empty() {
+                self.push_run(&run_text(before), None);
+            }
+            let note = citation_text(&found[1]);
+            self.push_note_reference(format!("cite:{}", self.notes.len()), Some(note));
+            last = whole.end();
+        }
+        if last < text.len() {
+            self.push_run(&run_text(&text[last..]), None);
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.open(None),
+            Tag::Heading { level, .. } => {
+                let level = match level {
+                    HeadingLevel::H1 => 1,
+                    HeadingLevel::H2 => 2,
+                    HeadingLevel::H3 => 3,
+                    HeadingLevel::H4 => 4,
+                    HeadingLevel::H5 => 5,
+                    HeadingLevel::H6 => 6,
+                };
+                self.open(Some(&format!("Heading{level}")));
+            }
+            Tag::BlockQuote(_) => {
+                self.close();
+                self.blocks.push(Block::Quote);
+            }
+            Tag::CodeBlock(_) => {
+                self.close();
+                self.code = Some(String::new());
+            }
+            Tag::List(start) => {
+                self.close();
+                self.numbering.push((start.is_some(), start.unwrap_or(1)));
+                self.blocks.push(Block::List(self.numbering.len()));
+            }
+            Tag::Item => {
+                self.close();
+                self.blocks.push(Block::Item(false));
+            }
+            Tag::FootnoteDefinition(label) => {
+                self.close();
+                let body = std::mem::take(&mut self.out);
+                self.blocks.push(Block::Note(label.to_string(), body));
+            }
+            Tag::Table(alignments) => {
+                self.close();
+                self.alignments = alignments;
+                self.out.push_str(
+                    "<w:tbl><w:tblPr><w:tblStyle w:val=\"TableGrid\"/><w:tblW w:w=\"0\" w:type=\"auto\"/></w:tblPr>\n",
+                );
+            }
+            Tag::TableHead => {
+                self.table_head = true;
+                self.column = 0;
+                self.out.push_str("<w:tr><w:trPr><w:tblHeader/></w:trPr>\n");
+            }
+            Tag::TableRow => {
+                self.column = 0;
+                self.out.push_str("<w:tr>\n");
+            }
+            Tag::TableCell => {
+                self.out.push_str("<w:tc>");
+                self.open(None);
+            }
+            Tag::Emphasis => self.italic += 1,
+            Tag::Strong => self.bold += 1,
+            Tag::Strikethrough => self.strike += 1,
+            Tag::Link { dest_url, .. } if links::is_external(&dest_url) => {
+                if self.paragraph.is_none() {
+                    self.open(None);
+                }
+                self.links.push(dest_url.to_string());
+                let id = FIRST_LINK_ID + self.links.len();
+                if let Some(paragraph) = self.paragraph.as_mut() {
+                    paragraph
+                        .runs
+                        .push_str(&format!("<w:hyperlink r:id=\"rId{id}\">"));
+                }
+                self.in_link = true;
+            }
+            Tag::Image { .. } => self.image = Some(String::new()),
+            Tag::MetadataBlock(_) | Tag::HtmlBlock => self.skip += 1,
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::Heading(_) => self.close(),
+            TagEnd::BlockQuote(_) | TagEnd::List(_) | TagEnd::Item => {
+                self.close();
+                self.blocks.pop();
+            }
+            TagEnd::CodeBlock => {
+                let code = self.code.take().unwrap_or_default();
+                for line in code.trim_end_matches('\n').split('\n') {
+                    self.open(Some("Code"));
+                    if !line.is_empty() {
+                        self.push_run(&run_text(line), None);
+                    }
+                    self.close();
+                }
+            }
+            TagEnd::FootnoteDefinition => {
+                self.close();
+                if let Some(Block::Note(label, body)) = self.blocks.pop() {
+                    let note = std::mem::replace(&mut self.out, body);
+                    self.definitions.insert(label, note);
+                }
+            }
+            TagEnd::Table => {
+                self.alignments.clear();
+                self.out.push_str("</w:tbl>\n<w:p/>\n");
+            }
+            TagEnd::TableHead => {
+                self.table_head = false;
+                self.out.push_str("</w:tr>\n");
+            }
+            TagEnd::TableRow => self.out.push_str("</w:tr>\n"),
+            TagEnd::TableCell => {
+                self.open(None);
+                self.close();
+                self.out.push_str("</w:tc>\n");
+                self.column += 1;
+            }
+            TagEnd::Emphasis => self.italic = self.italic.saturating_sub(1),
+            TagEnd::Strong => self.bold = self.bold.saturating_sub(1),
+            TagEnd::Strikethrough => self.strike = self.strike.saturating_sub(1),
+            TagEnd::Link if self.in_link => {
+                self.in_link = false;
+                if let Some(paragraph) = self.paragraph.as_mut() {
+                    paragraph.runs.push_str("</w:hyperlink>");
+                }
+            }
+            TagEnd::Image => {
+                let alt = self.image.take().unwrap_or_default();
+                if !alt.is_empty() {
+                    self.italic += 1;
+                    self.push_run(&run_text(&format!("[{alt}]")), None);
+                    self.italic -= 1;
+                }
+            }
+            TagEnd::MetadataBlock(_) | TagEnd::HtmlBlock => self.skip = self.skip.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        if self.skip > 0 {
+            if let Event::End(tag @ (TagEnd::MetadataBlock(_) | TagEnd::HtmlBlock)) = event {
+                self.end(tag);
+            }
+            return;
+        }
+        if let Some(code) = self.code.as_mut() {
+            match event {
+                Event::Text(text) => code.push_str(&text),
+                Event::End(tag) => self.end(tag),
+                _ => {}
+            }
+            return;
+        }
+        if let Some(alt) = self.image.as_mut() {
+            match event {
+                Event::Text(text) | Event::Code(text) => alt.push_str(&text),
+                Event::End(tag) => self.end(tag),
+                _ => {}
+            }
+            return;
+        }
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.push_text(&text),
+            Event::Code(text) | Event::InlineMath(text) | Event::DisplayMath(text) => {
+                self.push_run(&run_text(&text), Some("CodeChar"))
+            }
+            Event::SoftBreak => self.push_run(&run_text(" "), None),
+            Event::HardBreak => self.push_run("<w:br/>", None),
+            Event::Rule => {
+                self.close();
+                self.out.push_str(
+                    "<w:p><w:pPr><w:pBdr><w:bottom w:val=\"single\" w:sz=\"6\" w:space=\"1\" w:color=\"auto\"/></w:pBdr></w:pPr></w:p>\n",
+                );
+            }
+            Event::TaskListMarker(checked) => {
+                self.push_run(&run_text(if checked { "☒ " } else { "☐ " }), None)
+            }
+            Event::FootnoteReference(label) => self.push_note_reference(label.to_string(), None),
+            _ => {}
+        }
+    }
+
+    /// 脚注の部品（区切り線の脚注と番号順の脚注）
+    fn notes_xml(&self) -> String {
+        let element = self.kind.element();
+        let mut xml = format!(
+            "{XML_HEADER}<w:{element}s {NAMESPACES}>\n\
+             <w:{element} w:type=\"separator\" w:id=\"-1\"><w:p><w:r><w:separator/></w:r></w:p></w:{element}>\n\
+             <w:{element} w:type=\"continuationSeparator\" w:id=\"0\"><w:p><w:r><w:continuationSeparator/></w:r></w:p></w:{element}>\n"
+        );
+        for (i, (label, citation)) in self.notes.iter().enumerate() {
+            let body = match citation {
+                Some(text) => format!(
+                    "<w:p><w:pPr><w:pStyle w:val=\"FootnoteText\"/></w:pPr>{}<w:r>{}</w:r></w:p>",
+                    self.note_mark(),
+                    run_text(text)
+                ),
+                None => match self.definitions.get(label) {
+                    Some(body) if !body.is_empty() => body.clone(),
+                    _ => format!(
+                        "<w:p><w:pPr><w:pStyle w:val=\"FootnoteText\"/></w:pPr>{}<w:r>{}</w:r></w:p>",
+                        self.note_mark(),
+                        run_text(label)
+                    ),
+                },
+            };
+            xml.push_str(&format!(
+                "<w:{element} w:id=\"{}\">{body}</w:{element}>\n",
+                i + 1
+            ));
+        }
+        xml.push_str(&format!("</w:{element}s>"));
+        xml
+    }
+
+    fn numbering_xml(&self) -> String {
+        let levels = |ordered: bool| {
+            (0..9)
+                .map(|level| {
+                    let (format, text) = if ordered {
+                        ("decimal", format!("%{}.", level + 1))
+                    } else {
+                        ("bullet", ["•", "◦", "▪"][level % 3].to_string())
+                    };
+                    format!(
+                        "<w:lvl w:ilvl=\"{level}\"><w:start w:val=\"1\"/><w:numFmt w:val=\"{format}\"/>\
+                         <w:lvlText w:val=\"{text}\"/><w:lvlJc w:val=\"left\"/>\
+                         <w:pPr><w:ind w:left=\"{}\" w:hanging=\"360\"/></w:pPr></w:lvl>",
+                        (level + 1) * LIST_INDENT
+                    )
+                })
+                .collect::<String>()
+        };
+        let mut xml = format!(
+            "{XML_HEADER}<w:numbering {NAMESPACES}>\n\
+             <w:abstractNum w:abstractNumId=\"0\">{}</w:abstractNum>\n\
+             <w:abstractNum w:abstractNumId=\"1\">{}</w:abstractNum>\n",
+            levels(false),
+            levels(true)
+        );
+        for (i, (ordered, start)) in self.numbering.iter().enumerate() {
+            let overrides = (0..9)
+                .map(|level| {
+                    format!(
+                        "<w:lvlOverride w:ilvl=\"{level}\"><w:startOverride w:val=\"{}\"/></w:lvlOverride>",
+                        if level == 0 { *start } else { 1 }
+                    )
+                })
+                .collect::<String>();
+            xml.push_str(&format!(
+                "<w:num w:numId=\"{}\"><w:abstractNumId w:val=\"{}\"/>{overrides}</w:num>\n",
+                i + 1,
+                u8::from(*ordered)
+            ));
+        }
+        xml.push_str("</w:numbering>");
+        xml
+    }
+
+    fn relationships_xml(&self) -> String {
+        let element = self.kind.element();
+        let mut xml = format!(
+            "{XML_HEADER}<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\n\
+             <Relationship Id=\"rId1\" Type=\"{REL}/styles\" Target=\"styles.xml\"/>\n\
+             <Relationship Id=\"rId2\" Type=\"{REL}/numbering\" Target=\"numbering.xml\"/>\n\
+             <Relationship Id=\"rId3\" Type=\"{REL}/settings\" Target=\"settings.xml\"/>\n\
+             <Relationship Id=\"rId4\" Type=\"{REL}/{element}s\" Target=\"{element}s.xml\"/>\n"
+        );
+        for (i, url) in self.links.iter().enumerate() {
+            xml.push_str(&format!(
+                "<Relationship Id=\"rId{}\" Type=\"{REL}/hyperlink\" Target=\"{}\" TargetMode=\"External\"/>\n",
+                FIRST_LINK_ID + i + 1,
+                escape_html(url)
+            ));
+        }
+        xml.push_str("</Relationships>");
+        xml
+    }
+}
+
+const XML_HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n";
+const NAMESPACES: &str =
+    "xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\" \
+     xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\"";
+const REL: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+const STYLES: &str = r#"<w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri" w:hAnsi="Calibri" w:eastAsia="Yu Mincho"/><w:sz w:val="22"/></w:rPr></w:rPrDefault><w:pPrDefault><w:pPr><w:spacing w:after="120" w:line="276" w:lineRule="auto"/></w:pPr></w:pPrDefault></w:docDefaults>
+<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/></w:style>
+<w:style w:type="paragraph" w:styleId="Title"><w:name w:val="Title"/><w:basedOn w:val="Normal"/><w:rPr><w:sz w:val="48"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:spacing w:before="360"/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:b/><w:sz w:val="36"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:spacing w:before="240"/><w:outlineLvl w:val="1"/></w:pPr><w:rPr><w:b/><w:sz w:val="30"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading3"><w:name w:val="heading 3"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:spacing w:before="240"/><w:outlineLvl w:val="2"/></w:pPr><w:rPr><w:b/><w:sz w:val="26"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading4"><w:name w:val="heading 4"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:outlineLvl w:val="3"/></w:pPr><w:rPr><w:b/><w:i/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading5"><w:name w:val="heading 5"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:outlineLvl w:val="4"/></w:pPr><w:rPr><w:b/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading6"><w:name w:val="heading 6"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:outlineLvl w:val="5"/></w:pPr><w:rPr><w:i/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Quote"><w:name w:val="Quote"/><w:basedOn w:val="Normal"/><w:pPr><w:ind w:left="720"/></w:pPr><w:rPr><w:i/><w:color w:val="555555"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="ListParagraph"><w:name w:val="List Paragraph"/><w:basedOn w:val="Normal"/><w:pPr><w:spacing w:after="60"/></w:pPr></w:style>
+<w:style w:type="paragraph" w:styleId="Code"><w:name w:val="Code"/><w:basedOn w:val="Normal"/><w:pPr><w:spacing w:after="0" w:line="240" w:lineRule="auto"/><w:shd w:val="clear" w:color="auto" w:fill="F3F3F3"/></w:pPr><w:rPr><w:rFonts w:ascii="Consolas" w:hAnsi="Consolas"/><w:sz w:val="20"/></w:rPr></w:style>
+<w:style w:type="character" w:styleId="CodeChar"><w:name w:val="Code Char"/><w:rPr><w:rFonts w:ascii="Consolas" w:hAnsi="Consolas"/><w:shd w:val="clear" w:color="auto" w:fill="F3F3F3"/></w:rPr></w:style>
+<w:style w:type="character" w:styleId="Hyperlink"><w:name w:val="Hyperlink"/><w:rPr><w:color w:val="0563C1"/><w:u w:val="single"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="FootnoteText"><w:name w:val="footnote text"/><w:basedOn w:val="Normal"/><w:pPr><w:spacing w:after="0"/></w:pPr><w:rPr><w:sz w:val="20"/></w:rPr></w:style>
+<w:style w:type="character" w:styleId="FootnoteReference"><w:name w:val="footnote reference"/><w:rPr><w:vertAlign w:val="superscript"/></w:rPr></w:style>
+<w:style w:type="table" w:styleId="TableGrid"><w:name w:val="Table Grid"/><w:tblPr><w:tblBorders><w:top w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:left w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:bottom w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:right w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:insideH w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:insideV w:val="single" w:sz="4" w:space="0" w:color="auto"/></w:tblBorders><w:tblCellMar><w:left w:w="108" w:type="dxa"/><w:right w:w="108" w:type="dxa"/></w:tblCellMar></w:tblPr></w:style>"#;
+
+/// Markdown を .docx のバイト列にする
+pub fn docx(content: &str, title: &str, options: &DocxOptions) -> Result<Vec<u8>, String> {
+    // 引用が複数のテキストに分かれないよう、続いたテキストをまとめる
+    let mut events: Vec<Event> = Vec::new();
+    for event in Parser::new_ext(content, markdown::parser_options()) {
+        match (events.last_mut(), event) {
+            (Some(Event::Text(previous)), Event::Text(text)) => {
+                *previous = format!("{previous}{text}").into();
+            }
+            (_, event) => events.push(event),
+        }
+    }
+    let mut writer = Writer::new(options.notes, options.citations);
+    for event in events {
+        writer.event(event);
+    }
+    writer.close();
+
+    let element = options.notes.element();
+    let body = std::mem::take(&mut writer.out);
+    let document = format!(
+        "{XML_HEADER}<w:document {NAMESPACES}><w:body>\n{body}\
+         <w:sectPr><w:pgSz w:w=\"11906\" w:h=\"16838\"/>\
+         <w:pgMar w:top=\"1440\" w:right=\"1440\" w:bottom=\"1440\" w:left=\"1440\" w:header=\"720\" w:footer=\"720\" w:gutter=\"0\"/></w:sectPr>\
+         </w:body></w:document>"
+    );
+    let content_types = format!(
+        "{XML_HEADER}<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+         <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+         <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+         <Override PartName=\"/word/document.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml\"/>\
+         <Override PartName=\"/word/styles.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml\"/>\
+         <Override PartName=\"/word/numbering.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml\"/>\
+         <Override PartName=\"/word/settings.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.settings+xml\"/>\
+         <Override PartName=\"/word/{element}s.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.{element}s+xml\"/>\
+         <Override PartName=\"/docProps/core.xml\" ContentType=\"application/vnd.openxmlformats-package.core-properties+xml\"/>\
+         </Types>"
+    );
+    let package_rels = format!(
+        "{XML_HEADER}<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+         <Relationship Id=\"rId1\" Type=\"{REL}/officeDocument\" Target=\"word/document.xml\"/>\
+         <Relationship Id=\"rId2\" Type=\"http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties\" Target=\"docProps/core.xml\"/>\
+         </Relationships>"
+    );
+    let core = format!(
+        "{XML_HEADER}<cp:coreProperties xmlns:cp=\"http://schemas.openxmlformats.org/package/2006/metadata/core-properties\" \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\"><dc:title>{}</dc:title></cp:coreProperties>",
+        escape_html(title)
+    );
+    let settings = format!(
+        "{XML_HEADER}<w:settings {NAMESPACES}><w:{element}Pr><w:{element} w:id=\"-1\"/><w:{element} w:id=\"0\"/></w:{element}Pr></w:settings>"
+    );
+    let styles = format!("{XML_HEADER}<w:styles {NAMESPACES}>\n{STYLES}\n</w:styles>");
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    zip.add("[Content_Types].xml", content_types.as_bytes())?;
+    zip.add("_rels/.rels", package_rels.as_bytes())?;
+    zip.add("docProps/core.xml", core.as_bytes())?;
+    zip.add("word/document.xml", document.as_bytes())?;
+    zip.add(
+        "word/_rels/document.xml.rels",
+        writer.relationships_xml().as_bytes(),
+    )?;
+    zip.add("word/styles.xml", styles.as_bytes())?;
+    zip.add("word/numbering.xml", writer.numbering_xml().as_bytes())?;
+    zip.add("word/settings.xml", settings.as_bytes())?;
+    zip.add(
+        &format!("word/{element}s.xml"),
+        writer.notes_xml().as_bytes(),
+    )?;
+    Ok(zip.finish()?.into_inner())
+}
+
+/// 開いている文書を Word 文書（.docx）に書き出す
+///
+/// 脚注と引用（`[@key]`）は Word の脚注（`notes` が `endnotes` なら文末脚注）になる。
+#[tauri::command(async)]
+pub fn export_docx(
+    state: State<'_, AppState>,
+    path: String,
+    content: String,
+    out_path: String,
+    options: Option<DocxOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let source = vault::normalize(Path::new(&path));
+    let out = vault::normalize(Path::new(&out_path));
+    let trusted = state.trust.is_trusted(&source);
+    let content = preprocess(
+        &content,
+        Some(&source),
+        None,
+        &state.preprocess.get(),
+        trusted,
+    )?;
+    let title = export::document_title(&source, &content);
+    let data = docx(&content, &title, &options)?;
+    if let Some(parent) = out.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| tr!("Failed to create {}: {e}", parent.display()))?;
+    }
+    fs::write(&out, data).map_err(|e| tr!("Failed to write {}: {e}", out.display()))?;
+    Ok(path_string(&out))
+}
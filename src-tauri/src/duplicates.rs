@@ -0,0 +1,248 @@
+// 重複したノートの検出（取り込んだノートの整理用）
+//
+// 本文（フロントマターを除き、行末の空白と空行の違いは無視）が同じものを完全な重複とし、
+// 語の並び（シングル）の Jaccard 係数が閾値以上のものをほぼ重複とする。
+// 候補の組は MinHash と LSH で絞り込んでから、シングルの集合で係数を求める。
+
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+use crate::blake3;
+use crate::markdown;
+use crate::refactor::read;
+use crate::similar;
+use crate::state::AppState;
+use crate::tasks::Task;
+use crate::vault::{path_string, Vault};
+
+/// シングル 1 つの語の数
+const SHINGLE: usize = 3;
+/// MinHash の値の数（`BANDS` × `ROWS`）
+const BANDS: usize = 16;
+const ROWS: usize = 4;
+/// 既定の閾値
+const DEFAULT_THRESHOLD: f32 = 0.8;
+
+/// 重複とみなしたノートの組
+#[derive(Debug, Serialize)]
+pub struct DuplicatePair {
+    pub a: String,
+    pub b: String,
+    /// Jaccard 係数（0〜1。完全な重複は 1）
+    pub similarity: f32,
+}
+
+/// 互いに重複したノートのまとまり
+#[derive(Debug, Serialize)]
+pub struct DuplicateCluster {
+    pub notes: Vec<String>,
+    /// すべてのノートの本文が同じ
+    pub exact: bool,
+    /// まとまりを作った組のうち最も低い類似度
+    pub similarity: f32,
+    pub pairs: Vec<DuplicatePair>,
+}
+
+/// 比較に使う本文（フロントマターを除き、行末の空白と空行をそろえる）
+fn normalized_body(content: &str) -> String {
+    content[markdown::body_start(content)..]
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 本文のシングル（連続する `SHINGLE` 語）のハッシュ値
+fn shingles(content: &str) -> HashSet<u64> {
+    let terms: Vec<String> = markdown::prose_blocks(content)
+        .iter()
+        .flat_map(|block| similar::terms(&block.text))
+        .collect();
+    if terms.len() < SHINGLE {
+        return (!terms.is_empty())
+            .then(|| hash_of(&terms))
+            .into_iter()
+            .collect();
+    }
+    terms.windows(SHINGLE).map(hash_of).collect()
+}
+
+/// 値を混ぜ合わせる（SplitMix64）
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+fn minhash(shingles: &HashSet<u64>) -> [u64; BANDS * ROWS] {
+    let mut signature = [u64::MAX; BANDS * ROWS];
+    for &shingle in shingles {
+        for (i, slot) in signature.iter_mut().enumerate() {
+            *slot = (*slot).min(mix(shingle ^ mix(i as u64)));
+        }
+    }
+    signature
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f32 {
+    let shared = a.intersection(b).count();
+    let union = a.len() + b.len() - shared;
+    if union == 0 {
+        0.0
+    } else {
+        shared as f32 / union as f32
+    }
+}
+
+/// 素集合（まとまりを作る）
+fn find(parent: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    let mut i = i;
+    while parent[i] != root {
+        let next = parent[i];
+        parent[i] = root;
+        i = next;
+    }
+    root
+}
+
+/// ワークスペース内の重複したノートのまとまりを、類似度の高い順に返す
+///
+/// `threshold` はほぼ重複とみなす Jaccard 係数の下限（既定は 0.8）。
+#[tauri::command(async)]
+pub fn find_duplicates(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    root: String,
+    threshold: Option<f32>,
+    task_id: Option<String>,
+) -> Result<Vec<DuplicateCluster>, String> {
+    let task = Task::start(&app, &state, task_id)?;
+    let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD).clamp(0.0, 1.0);
+    let vault = Vault::scan(Path::new(&root));
+    let files: Vec<&Path> = vault.markdown_files().map(|p| p.as_path()).collect();
+
+    // 本文が同じノートをまとめ、代表（先頭）だけをシングルで比べる
+    let mut by_hash: HashMap<[u8; 32], usize> = HashMap::new();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut sets: Vec<HashSet<u64>> = Vec::new();
+    for (i, doc) in files.iter().enumerate() {
+        task.check()?;
+        task.progress(i + 1, files.len(), || path_string(doc));
+        let content = read(doc)?;
+        let body = normalized_body(&content);
+        if body.is_empty() {
+            continue;
+        }
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(body.as_bytes());
+        match by_hash.entry(hasher.finalize()) {
+            Entry::Occupied(e) => groups[*e.get()].push(i),
+            Entry::Vacant(e) => {
+                e.insert(groups.len());
+                groups.push(vec![i]);
+                sets.push(shingles(&content));
+            }
+        }
+    }
+
+    // 同じ帯の値を持つ代表の組を候補にする
+    let signatures: Vec<_> = sets.iter().map(minhash).collect();
+    let mut candidates: HashSet<(usize, usize)> = HashSet::new();
+    for band in 0..BANDS {
+        task.check()?;
+        let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (g, signature) in signatures.iter().enumerate() {
+            if sets[g].is_empty() {
+                continue;
+            }
+            let key = hash_of(&signature[band * ROWS..(band + 1) * ROWS]);
+            buckets.entry(key).or_default().push(g);
+        }
+        for bucket in buckets.values().filter(|b| b.len() > 1) {
+            for (n, &a) in bucket.iter().enumerate() {
+                candidates.extend(bucket[n + 1..].iter().map(|&b| (a, b)));
+            }
+        }
+    }
+
+    let mut parent: Vec<usize> = (0..groups.len()).collect();
+    let mut near: Vec<(usize, usize, f32)> = Vec::new();
+    for (a, b) in candidates {
+        let similarity = jaccard(&sets[a], &sets[b]);
+        if similarity >= threshold && similarity > 0.0 {
+            near.push((a, b, similarity));
+            let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+            parent[ra] = rb;
+        }
+    }
+
+    // 以降は `parent` がそれぞれのまとまりの代表を直接指す
+    let mut clusters: HashMap<usize, Vec<usize>> = HashMap::new();
+    for g in 0..groups.len() {
+        let root = find(&mut parent, g);
+        clusters.entry(root).or_default().push(g);
+    }
+    let path = |i: usize| path_string(files[i]);
+    let mut result: Vec<DuplicateCluster> = clusters
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1 || groups[members[0]].len() > 1)
+        .map(|(root, members)| {
+            let mut pairs = Vec::new();
+            for &g in &members {
+                let first = groups[g][0];
+                pairs.extend(groups[g][1..].iter().map(|&other| DuplicatePair {
+                    a: path(first),
+                    b: path(other),
+                    similarity: 1.0,
+                }));
+            }
+            pairs.extend(near.iter().filter(|(a, _, _)| parent[*a] == root).map(
+                |&(a, b, similarity)| DuplicatePair {
+                    a: path(groups[a][0]),
+                    b: path(groups[b][0]),
+                    similarity,
+                },
+            ));
+            pairs.sort_by(|x, y| {
+                y.similarity
+                    .total_cmp(&x.similarity)
+                    .then_with(|| (&x.a, &x.b).cmp(&(&y.a, &y.b)))
+            });
+            let mut notes: Vec<String> = members
+                .iter()
+                .flat_map(|&g| groups[g].iter().map(|&i| path(i)))
+                .collect();
+            notes.sort();
+            DuplicateCluster {
+                exact: members.len() == 1,
+                similarity: pairs.iter().map(|p| p.similarity).fold(1.0, f32::min),
+                notes,
+                pairs,
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then_with(|| b.notes.len().cmp(&a.notes.len()))
+            .then_with(|| a.notes.cmp(&b.notes))
+    });
+    Ok(result)
+}
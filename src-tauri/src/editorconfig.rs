@@ -0,0 +1,232 @@
+// EditorConfig（`.editorconfig`）の読み込み
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::vault::{self, to_slash};
+
+/// エディタの設定に使うプロパティ（指定がなければ `None`）
+#[derive(Debug, Default, Serialize)]
+pub struct EditorConfig {
+    /// "space" | "tab"
+    pub indent_style: Option<String>,
+    pub indent_size: Option<usize>,
+    pub tab_width: Option<usize>,
+    /// "lf" | "crlf" | "cr"
+    pub end_of_line: Option<String>,
+    pub charset: Option<String>,
+    pub trim_trailing_whitespace: Option<bool>,
+    pub insert_final_newline: Option<bool>,
+    /// `off` は `None`
+    pub max_line_length: Option<usize>,
+    /// 読み込んだすべてのプロパティ（独自の拡張用）
+    pub properties: HashMap<String, String>,
+}
+
+/// セクション名（glob）とプロパティ
+type Section = (String, Vec<(String, String)>);
+
+/// `{a,b}` と `{1..3}` を正規表現にする
+fn braces(inner: &str) -> String {
+    if let Some((a, b)) = inner.split_once("..") {
+        if let (Ok(a), Ok(b)) = (a.parse::<i64>(), b.parse::<i64>()) {
+            let (lo, hi) = (a.min(b), a.max(b));
+            let numbers: Vec<String> = (lo..=hi.min(lo + 10_000)).map(|n| n.to_string()).collect();
+            return format!("(?:{})", numbers.join("|"));
+        }
+    }
+    let alternatives: Vec<String> = split_top_level(inner)
+        .iter()
+        .map(|a| glob_body(a))
+        .collect();
+    format!("(?:{})", alternatives.join("|"))
+}
+
+/// `,` で分ける（入れ子の `{}` の中は分けない）
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0usize, 0);
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+fn glob_body(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                out.push_str(".*");
+                i += 1;
+            }
+            '*' => out.push_str("[^/]*"),
+            '?' => out.push_str("[^/]"),
+            '[' => match chars[i + 1..].iter().position(|&c| c == ']') {
+                Some(len) => {
+                    let class: String = chars[i + 1..i + 1 + len].iter().collect();
+                    let class = match class.strip_prefix('!') {
+                        Some(rest) => format!("^{rest}"),
+                        None => class,
+                    };
+                    out.push_str(&format!("[{}]", class.replace('\\', "\\\\")));
+                    i += len + 1;
+                }
+                None => out.push_str("\\["),
+            },
+            '{' => {
+                let mut depth = 0;
+                let end = chars[i..].iter().position(|&c| {
+                    match c {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                    depth == 0
+                });
+                match end {
+                    Some(len) => {
+                        let inner: String = chars[i + 1..i + len].iter().collect();
+                        out.push_str(&braces(&inner));
+                        i += len;
+                    }
+                    None => out.push_str("\\{"),
+                }
+            }
+            '\\' if i + 1 < chars.len() => {
+                out.push_str(&regex::escape(&chars[i + 1].to_string()));
+                i += 1;
+            }
+            c => out.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// セクション名のパターン（`/` を含まなければどの階層のファイル名にも当てはまる）
+pub fn section_regex(glob: &str) -> Option<Regex> {
+    let pattern = if glob.contains('/') {
+        glob_body(glob.trim_start_matches('/'))
+    } else {
+        format!("(?:.*/)?{}", glob_body(glob))
+    };
+    Regex::new(&format!("^{pattern}$")).ok()
+}
+
+/// `.editorconfig` の内容（`root` の指定とセクションごとのプロパティ）
+fn parse(content: &str) -> (bool, Vec<Section>) {
+    let mut root = false;
+    let mut sections: Vec<Section> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push((name.to_string(), Vec::new()));
+            continue;
+        }
+        let Some((key, value)) = line.split_once(['=', ':']) else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim().to_string();
+        match sections.last_mut() {
+            Some((_, properties)) => properties.push((key, value)),
+            None if key == "root" => root = value.eq_ignore_ascii_case("true"),
+            None => {}
+        }
+    }
+    (root, sections)
+}
+
+/// ファイルに当てはまるプロパティ（キーは小文字。近い `.editorconfig` の指定を優先する）
+pub fn properties(path: &Path) -> HashMap<String, String> {
+    let path = vault::normalize(path);
+    let mut files = Vec::new();
+    let mut dir = path.parent();
+    while let Some(d) = dir {
+        if let Ok(content) = fs::read_to_string(d.join(".editorconfig")) {
+            let (root, sections) = parse(&content);
+            files.push((d.to_path_buf(), sections));
+            if root {
+                break;
+            }
+        }
+        dir = d.parent();
+    }
+
+    let mut properties = HashMap::new();
+    for (dir, sections) in files.iter().rev() {
+        let Ok(relative) = path.strip_prefix(dir) else {
+            continue;
+        };
+        let relative = to_slash(relative);
+        for (glob, values) in sections {
+            if section_regex(glob).is_some_and(|re| re.is_match(&relative)) {
+                for (key, value) in values {
+                    properties.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+    // `unset` は指定がないものとして扱う
+    properties.retain(|_, v| !v.eq_ignore_ascii_case("unset"));
+    properties
+}
+
+/// 真偽値のプロパティ
+pub fn flag(properties: &HashMap<String, String>, key: &str) -> Option<bool> {
+    match properties.get(key)?.to_lowercase().as_str() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn number(properties: &HashMap<String, String>, key: &str) -> Option<usize> {
+    properties.get(key)?.parse().ok()
+}
+
+fn keyword(properties: &HashMap<String, String>, key: &str) -> Option<String> {
+    properties.get(key).map(|v| v.to_lowercase())
+}
+
+/// 開いたファイルに当てはまる `.editorconfig` の設定
+#[tauri::command]
+pub fn get_editorconfig(path: String) -> EditorConfig {
+    let properties = properties(Path::new(&path));
+    let tab_width = number(&properties, "tab_width");
+    // `indent_size = tab` はタブ幅に従う
+    let indent_size = match properties.get("indent_size").map(|v| v.to_lowercase()) {
+        Some(v) if v == "tab" => tab_width,
+        _ => number(&properties, "indent_size"),
+    };
+    EditorConfig {
+        indent_style: keyword(&properties, "indent_style"),
+        indent_size,
+        tab_width: tab_width.or(indent_size),
+        end_of_line: keyword(&properties, "end_of_line"),
+        charset: keyword(&properties, "charset"),
+        trim_trailing_whitespace: flag(&properties, "trim_trailing_whitespace"),
+        insert_final_newline: flag(&properties, "insert_final_newline"),
+        max_line_length: number(&properties, "max_line_length"),
+        properties,
+    }
+}
@@ -0,0 +1,204 @@
+// 範囲の置き換えをまとめて適用する（Rust で求めた書き換えを Monaco で 1 回の取り消し単位にする）
+//
+// 置き換えは重なってはならず、重なっていれば何も適用せずにエラーを返す。
+// 同じ位置への挿入は渡した順に並べる。カーソルなどの位置は置き換え後の内容での位置に写す。
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::formatter::TextEdit;
+use crate::lsp::LspRange;
+use crate::text::{self, LineIndex};
+use crate::tr;
+
+/// エディタ上の位置（行・UTF-16 の桁。どちらも 1 始まり）
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct EditorPosition {
+    pub line: u64,
+    pub column: u64,
+}
+
+/// `apply_text_edits` の結果
+#[derive(Debug, Serialize)]
+pub struct ApplyEditsResult {
+    pub content: String,
+    pub changed: bool,
+    /// 置き換えた本文の、置き換え後の内容での範囲（渡した順）
+    pub applied: Vec<TextEdit>,
+    /// `positions` を置き換え後の内容に写した位置（渡した順）
+    pub positions: Vec<EditorPosition>,
+}
+
+/// 位置のバイトオフセット（行・桁が文書の外なら `None`）
+fn checked_offset(content: &str, index: &LineIndex, line: u64, column: u64) -> Option<usize> {
+    let (line, column) = (usize::try_from(line).ok()?, usize::try_from(column).ok()?);
+    if line == 0 || column == 0 || line > index.line_of(content.len()) {
+        return None;
+    }
+    let start = text::offset_at(content, line, 1);
+    let line_text = content[start..].split('\n').next().unwrap_or_default();
+    let line_text = line_text.strip_suffix('\r').unwrap_or(line_text);
+    (column <= line_text.encode_utf16().count() + 1).then(|| text::offset_at(content, line, column))
+}
+
+fn checked_range(content: &str, index: &LineIndex, range: &LspRange) -> Option<Range<usize>> {
+    let start = checked_offset(content, index, range.start_line, range.start_column)?;
+    let end = checked_offset(content, index, range.end_line, range.end_column)?;
+    (start <= end).then_some(start..end)
+}
+
+fn position(index: &LineIndex, content: &str, offset: usize) -> EditorPosition {
+    let (line, column) = index.position(content, offset);
+    EditorPosition {
+        line: line as u64,
+        column: column as u64,
+    }
+}
+
+/// 置き換えをまとめて適用し、新しい内容と写した位置を返す（1 つでも不正なら何も適用しない）
+///
+/// 置き換えた範囲の中の位置は置き換えた本文の末尾に、挿入した位置ちょうどの位置は挿入した本文の後ろに写す。
+#[tauri::command]
+pub fn apply_text_edits(
+    content: String,
+    edits: Vec<TextEdit>,
+    positions: Option<Vec<EditorPosition>>,
+) -> Result<ApplyEditsResult, String> {
+    let index = LineIndex::new(&content);
+    let mut ranges: Vec<(usize, Range<usize>)> = edits
+        .iter()
+        .enumerate()
+        .map(|(i, edit)| {
+            checked_range(&content, &index, &edit.range)
+                .map(|range| (i, range))
+                .ok_or_else(|| tr!("Invalid range in edit {}", i + 1))
+        })
+        .collect::<Result<_, _>>()?;
+    // 開始位置の順（同じ位置への挿入は渡した順）に並べ、重なりを確かめる
+    ranges.sort_by_key(|(i, range)| (range.start, range.end > range.start, *i));
+    for pair in ranges.windows(2) {
+        let ((a, first), (b, second)) = (&pair[0], &pair[1]);
+        if first.end > second.start {
+            return Err(tr!("Overlapping edits: {} and {}", a + 1, b + 1));
+        }
+    }
+
+    let positions: Vec<usize> = positions
+        .unwrap_or_default()
+        .iter()
+        .map(|p| {
+            checked_offset(&content, &index, p.line, p.column)
+                .ok_or_else(|| tr!("Invalid position: {}:{}", p.line, p.column))
+        })
+        .collect::<Result<_, _>>()?;
+
+    // 置き換え後の内容での各置き換えの位置と、位置の写し先
+    let mut new_ranges = vec![0..0; edits.len()];
+    let mut mapped = positions.clone();
+    let mut shift: isize = 0;
+    for (i, range) in &ranges {
+        let len = edits[*i].text.len();
+        let start = (range.start as isize + shift) as usize;
+        new_ranges[*i] = start..start + len;
+        for (offset, original) in mapped.iter_mut().zip(&positions) {
+            if *original >= range.end {
+                *offset = (*offset as isize + len as isize - range.len() as isize) as usize;
+            } else if *original > range.start {
+                *offset = start + len;
+            }
+        }
+        shift += len as isize - range.len() as isize;
+    }
+
+    let new_content = text::apply_edits(
+        &content,
+        ranges
+            .iter()
+            .map(|(i, range)| (range.clone(), edits[*i].text.clone()))
+            .collect(),
+    );
+    let new_index = LineIndex::new(&new_content);
+    Ok(ApplyEditsResult {
+        changed: new_content != content,
+        applied: edits
+            .iter()
+            .zip(new_ranges)
+            .map(|(edit, range)| TextEdit {
+                range: LspRange::from_offsets(&new_index, &new_content, range),
+                text: edit.text.clone(),
+            })
+            .collect(),
+        positions: mapped
+            .into_iter()
+            .map(|offset| position(&new_index, &new_content, offset))
+            .collect(),
+        content: new_content,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_text_edits, EditorPosition};
+    use crate::formatter::TextEdit;
+    use crate::lsp::LspRange;
+
+    fn edit(start: (u64, u64), end: (u64, u64), text: &str) -> TextEdit {
+        TextEdit {
+            range: LspRange {
+                start_line: start.0,
+                start_column: start.1,
+                end_line: end.0,
+                end_column: end.1,
+            },
+            text: text.to_string(),
+        }
+    }
+
+    fn at(line: u64, column: u64) -> EditorPosition {
+        EditorPosition { line, column }
+    }
+
+    #[test]
+    fn applies_edits_in_any_order_and_maps_positions() {
+        let content = "alpha beta\ngamma 日本\n".to_string();
+        let edits = vec![
+            edit((2, 7), (2, 9), "語"),
+            edit((1, 1), (1, 6), "A"),
+            edit((1, 11), (1, 11), "!"),
+        ];
+        let positions = vec![at(1, 7), at(2, 1), at(1, 3), at(1, 11)];
+        let result = apply_text_edits(content, edits, Some(positions)).unwrap();
+        assert_eq!(result.content, "A beta!\ngamma 語\n");
+        assert!(result.changed);
+        let mapped: Vec<(u64, u64)> = result
+            .positions
+            .iter()
+            .map(|p| (p.line, p.column))
+            .collect();
+        // 置き換えた範囲の中は置き換えた本文の末尾に、挿入した位置は挿入した本文の後ろに写す
+        assert_eq!(mapped, vec![(1, 3), (2, 1), (1, 2), (1, 8)]);
+        let applied = &result.applied[0].range;
+        assert_eq!(
+            (applied.start_line, applied.start_column, applied.end_column),
+            (2, 7, 8)
+        );
+    }
+
+    #[test]
+    fn inserts_at_the_same_position_keep_their_order() {
+        let edits = vec![edit((1, 2), (1, 2), "1"), edit((1, 2), (1, 2), "2")];
+        let result = apply_text_edits("ab".to_string(), edits, None).unwrap();
+        assert_eq!(result.content, "a12b");
+    }
+
+    #[test]
+    fn rejects_overlapping_and_out_of_range_edits() {
+        let overlapping = vec![edit((1, 1), (1, 4), "x"), edit((1, 3), (1, 5), "y")];
+        assert!(apply_text_edits("abcdef".to_string(), overlapping, None).is_err());
+        let outside = vec![edit((1, 1), (1, 9), "x")];
+        assert!(apply_text_edits("abc".to_string(), outside, None).is_err());
+        let bad_position = Some(vec![at(3, 1)]);
+        assert!(apply_text_edits("abc".to_string(), Vec::new(), bad_position).is_err());
+    }
+}
@@ -0,0 +1,1341 @@
+// Vim の Ex コマンドの解釈（`:g/pattern/d` `:'<,'>s/a/b/g` `:5,10m 0` `:%norm Aabc` など）
+//
+// 範囲: `%` `.` `$` 行番号 `'x`（マーク）`/pat/` `?pat?` と `+` `-` の増減、`,` と `;`（`;` は前の位置を現在行にする）。
+// コマンド: `d` `y` `pu` `s` `g` `g!` `v` `m` `t` `co` `j` `>` `<` `p` `nu` `#` `ma` `k` `norm`（`|` で続けられる）。
+// `:normal` は移動（h l 0 ^ $ w b e f t F T j k G gg）・編集（x X D C s S ~ r J p P dd cc yy と d c y + 移動）・
+// 挿入（i a I A o O。Esc か `<Esc>` まで）を扱う。`<CR>` `<Tab>` `<BS>` `<lt>` の記法も受け付ける。
+//
+// 行ごとに番号を振って `:g` の対象や移動した行の追跡に使い、結果は元の内容に対する置き換えとして返す。
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::edits::EditorPosition;
+use crate::formatter::{self, TextEdit};
+use crate::replace::{self, Substitution};
+use crate::text::{self, LineIndex};
+use crate::tr;
+
+/// レジスタの内容
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Register {
+    pub text: String,
+    /// 行単位（`dd` `yy` `:d` `:y`）か
+    pub linewise: bool,
+}
+
+/// コマンドを実行するバッファの状態
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ExBuffer {
+    pub content: String,
+    /// 現在行（1 始まり）
+    pub cursor_line: usize,
+    /// マーク（`<` `>` を含む）の行番号
+    pub marks: HashMap<String, usize>,
+    /// 無名レジスタ
+    pub register: Option<Register>,
+    pub shift_width: usize,
+    /// 前回の検索パターン（空のパターンで使う）
+    pub last_pattern: Option<String>,
+}
+
+impl Default for ExBuffer {
+    fn default() -> Self {
+        Self {
+            content: String::new(),
+            cursor_line: 1,
+            marks: HashMap::new(),
+            register: None,
+            shift_width: 4,
+            last_pattern: None,
+        }
+    }
+}
+
+/// `run_ex_command` の結果
+#[derive(Debug, Serialize)]
+pub struct ExResult {
+    pub content: String,
+    pub changed: bool,
+    /// 元の内容から実行後の内容への置き換え
+    pub edits: Vec<TextEdit>,
+    pub cursor: EditorPosition,
+    /// 実行後のマークの行番号（消えた行のマークは除く）
+    pub marks: HashMap<String, usize>,
+    pub register: Option<Register>,
+    pub last_pattern: Option<String>,
+    /// `:p` `:nu` などが表示する行とメッセージ
+    pub output: Vec<String>,
+}
+
+struct Line {
+    id: usize,
+    text: String,
+}
+
+/// `:normal` の移動先
+#[derive(Clone, Copy)]
+enum Motion {
+    /// 行の中の位置（`inclusive` なら位置の文字も含む）
+    Char { to: usize, inclusive: bool },
+    /// 行（1 始まり）
+    Line(usize),
+}
+
+/// コマンドの文字列を読み進める
+struct Input<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Input<'a> {
+    fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.rest = &self.rest[c.len_utf8()..];
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let eaten = self.peek() == Some(c);
+        if eaten {
+            self.bump();
+        }
+        eaten
+    }
+
+    fn skip_spaces(&mut self) {
+        self.rest = self.rest.trim_start_matches([' ', '\t']);
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let len = self.rest.len()
+            - self
+                .rest
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .len();
+        let (digits, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        digits.parse().ok()
+    }
+
+    fn name(&mut self) -> &'a str {
+        let len = self.rest.len()
+            - self
+                .rest
+                .trim_start_matches(|c: char| c.is_ascii_alphabetic())
+                .len();
+        let (name, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        name
+    }
+
+    /// 区切り文字までを読む（区切り文字は `\` で書ける。ほかの `\` はそのまま残す）
+    fn delimited(&mut self, delimiter: char) -> String {
+        let mut out = String::new();
+        while let Some(c) = self.bump() {
+            if c == delimiter {
+                break;
+            }
+            if c == '\\' {
+                match self.bump() {
+                    Some(next) if next == delimiter => out.push(next),
+                    Some(next) => {
+                        out.push('\\');
+                        out.push(next);
+                    }
+                    None => out.push('\\'),
+                }
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    /// 残りをすべて取る
+    fn take_rest(&mut self) -> &'a str {
+        std::mem::take(&mut self.rest)
+    }
+}
+
+/// コマンド名が `full` の省略形（`min` 文字以上）か
+fn is_abbreviation(name: &str, full: &str, min: usize) -> bool {
+    name.len() >= min && full.starts_with(name)
+}
+
+/// 文字の種類（空白・単語の文字・記号）
+fn class(c: char) -> u8 {
+    if c.is_whitespace() {
+        0
+    } else if c.is_alphanumeric() || c == '_' {
+        1
+    } else {
+        2
+    }
+}
+
+fn first_non_blank(chars: &[char]) -> usize {
+    chars.iter().position(|c| !c.is_whitespace()).unwrap_or(0)
+}
+
+/// 次の単語の始まり（なければ行の長さ）
+fn next_word_start(chars: &[char], col: usize) -> usize {
+    let mut pos = col;
+    if let Some(&c) = chars.get(pos) {
+        let start = class(c);
+        while pos < chars.len() && start != 0 && class(chars[pos]) == start {
+            pos += 1;
+        }
+    }
+    while pos < chars.len() && class(chars[pos]) == 0 {
+        pos += 1;
+    }
+    pos
+}
+
+/// 単語の終わり（次の文字から探す）
+fn word_end(chars: &[char], col: usize) -> usize {
+    let mut pos = col + 1;
+    while pos < chars.len() && class(chars[pos]) == 0 {
+        pos += 1;
+    }
+    if pos >= chars.len() {
+        return chars.len().saturating_sub(1);
+    }
+    let start = class(chars[pos]);
+    while pos + 1 < chars.len() && class(chars[pos + 1]) == start {
+        pos += 1;
+    }
+    pos
+}
+
+/// 前の単語の始まり
+fn previous_word_start(chars: &[char], col: usize) -> usize {
+    let mut pos = col;
+    while pos > 0 && class(chars[pos - 1]) == 0 {
+        pos -= 1;
+    }
+    if pos == 0 {
+        return 0;
+    }
+    let start = class(chars[pos - 1]);
+    while pos > 0 && class(chars[pos - 1]) == start {
+        pos -= 1;
+    }
+    pos
+}
+
+/// インデントの幅（タブは次の `shift_width` の倍数まで）と、インデントの文字数
+fn indent_width(text: &str, shift_width: usize) -> (usize, usize) {
+    let mut width = 0;
+    let mut len = 0;
+    for c in text.chars() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += shift_width - width % shift_width,
+            _ => break,
+        }
+        len += 1;
+    }
+    (width, len)
+}
+
+/// `:normal` の `<Esc>` などの記法を文字にする
+fn keys(notation: &str) -> Vec<char> {
+    let mut keys = Vec::new();
+    let mut rest = notation;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            if let Some(end) = rest.find('>') {
+                let key = match rest[1..end].to_ascii_lowercase().as_str() {
+                    "esc" => Some('\x1b'),
+                    "cr" | "enter" | "return" | "nl" => Some('\r'),
+                    "tab" => Some('\t'),
+                    "bs" => Some('\x08'),
+                    "lt" => Some('<'),
+                    "space" => Some(' '),
+                    "bar" => Some('|'),
+                    _ => None,
+                };
+                if let Some(key) = key {
+                    keys.push(key);
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        keys.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    keys
+}
+
+/// 挿入する文字（Esc まで）と、その次の位置
+fn insert_text(keys: &[char], start: usize) -> (String, usize) {
+    let end = keys[start..]
+        .iter()
+        .position(|&c| c == '\x1b')
+        .map_or(keys.len(), |i| start + i);
+    let mut text = String::new();
+    for &c in &keys[start..end] {
+        match c {
+            '\r' | '\n' => text.push('\n'),
+            '\x08' => {
+                if !text.ends_with('\n') {
+                    text.pop();
+                }
+            }
+            c => text.push(c),
+        }
+    }
+    (text, (end + 1).min(keys.len()))
+}
+
+struct Ex {
+    lines: Vec<Line>,
+    next_id: usize,
+    /// 現在行（1 始まり）と桁（文字の位置）
+    cursor: usize,
+    column: usize,
+    /// マークのある行の番号
+    marks: HashMap<String, usize>,
+    register: Option<Register>,
+    last_pattern: Option<String>,
+    last_substitute: Option<(String, String)>,
+    shift_width: usize,
+    output: Vec<String>,
+    in_global: bool,
+}
+
+impl Ex {
+    fn new(buffer: &ExBuffer) -> Self {
+        let text = &buffer.content;
+        let mut lines: Vec<Line> = text
+            .split('\n')
+            .enumerate()
+            .map(|(id, line)| Line {
+                id,
+                text: line.strip_suffix('\r').unwrap_or(line).to_string(),
+            })
+            .collect();
+        if lines.len() > 1 && text.ends_with('\n') {
+            lines.pop();
+        }
+        let len = lines.len();
+        let marks = buffer
+            .marks
+            .iter()
+            .filter(|(_, &line)| (1..=len).contains(&line))
+            .map(|(name, &line)| (name.clone(), lines[line - 1].id))
+            .collect();
+        let mut ex = Self {
+            next_id: len,
+            lines,
+            cursor: 1,
+            column: 0,
+            marks,
+            register: buffer.register.clone(),
+            last_pattern: buffer.last_pattern.clone(),
+            last_substitute: None,
+            shift_width: buffer.shift_width.max(1),
+            output: Vec::new(),
+            in_global: false,
+        };
+        ex.goto(buffer.cursor_line.clamp(1, len));
+        ex
+    }
+
+    fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    fn text(&self, line: usize) -> &str {
+        &self.lines[line - 1].text
+    }
+
+    fn chars(&self) -> Vec<char> {
+        self.text(self.cursor).chars().collect()
+    }
+
+    fn set_chars(&mut self, chars: &[char]) {
+        self.lines[self.cursor - 1].text = chars.iter().collect();
+    }
+
+    fn position(&self, id: usize) -> Option<usize> {
+        self.lines
+            .iter()
+            .position(|line| line.id == id)
+            .map(|i| i + 1)
+    }
+
+    fn new_line(&mut self, text: String) -> Line {
+        self.next_id += 1;
+        Line {
+            id: self.next_id,
+            text,
+        }
+    }
+
+    /// `after` 行の後ろ（0 なら先頭）に行を入れる
+    fn insert_lines(&mut self, after: usize, texts: Vec<String>) {
+        let lines: Vec<Line> = texts.into_iter().map(|text| self.new_line(text)).collect();
+        self.lines.splice(after..after, lines);
+    }
+
+    /// `start`〜`end` 行を取り除く（バッファには少なくとも 1 行を残す）
+    fn remove_lines(&mut self, start: usize, end: usize) -> Vec<String> {
+        let removed = self
+            .lines
+            .drain(start - 1..end)
+            .map(|line| line.text)
+            .collect();
+        if self.lines.is_empty() {
+            let line = self.new_line(String::new());
+            self.lines.push(line);
+        }
+        removed
+    }
+
+    /// `start`〜`end` 行を置き換える（行の番号は前から順に引き継ぐ）
+    fn replace_lines(&mut self, start: usize, end: usize, texts: Vec<String>) {
+        let ids: Vec<usize> = self.lines[start - 1..end].iter().map(|l| l.id).collect();
+        let lines: Vec<Line> = texts
+            .into_iter()
+            .enumerate()
+            .map(|(i, text)| match ids.get(i) {
+                Some(&id) => Line { id, text },
+                None => self.new_line(text),
+            })
+            .collect();
+        self.lines.splice(start - 1..end, lines);
+    }
+
+    /// 行に移り、桁を最初の空白でない文字にする
+    fn goto(&mut self, line: usize) {
+        self.cursor = line.clamp(1, self.len());
+        self.column = first_non_blank(&self.chars());
+    }
+
+    fn pattern(&mut self, pattern: String) -> Result<String, String> {
+        if pattern.is_empty() {
+            return self
+                .last_pattern
+                .clone()
+                .ok_or_else(|| tr!("No previous regular expression"));
+        }
+        self.last_pattern = Some(pattern.clone());
+        Ok(pattern)
+    }
+
+    // ---- 範囲 ----
+
+    /// 行の指定 1 つ（なければ `None`）。0 行目も受け付ける
+    fn address(&mut self, input: &mut Input) -> Result<Option<usize>, String> {
+        input.skip_spaces();
+        let base = match input.peek() {
+            Some('.') => {
+                input.bump();
+                Some(self.cursor)
+            }
+            Some('$') => {
+                input.bump();
+                Some(self.len())
+            }
+            Some(c) if c.is_ascii_digit() => input.number(),
+            Some('\'') => {
+                input.bump();
+                let name = input.bump().map(String::from).unwrap_or_default();
+                let line = self.marks.get(&name).and_then(|&id| self.position(id));
+                Some(line.ok_or_else(|| tr!("Mark not set: {}", name))?)
+            }
+            Some(delimiter @ ('/' | '?')) => {
+                input.bump();
+                let pattern = input.delimited(delimiter);
+                let pattern = self.pattern(pattern)?;
+                Some(self.search(&pattern, delimiter == '?')?)
+            }
+            _ => None,
+        };
+        let mut line = base.map(|line| line as isize);
+        loop {
+            input.skip_spaces();
+            let sign = match input.peek() {
+                Some('+') => 1,
+                Some('-') => -1,
+                _ => break,
+            };
+            input.bump();
+            let n = input.number().unwrap_or(1) as isize;
+            line = Some(line.unwrap_or(self.cursor as isize) + sign * n);
+        }
+        match line {
+            Some(line) if line < 0 || line as usize > self.len() => Err(tr!("Invalid range")),
+            line => Ok(line.map(|line| line as usize)),
+        }
+    }
+
+    /// 現在行の次から（`backward` なら前から）探し、端で折り返す
+    fn search(&self, pattern: &str, backward: bool) -> Result<usize, String> {
+        let re = replace::build_regex(pattern, false, false)?;
+        let len = self.len();
+        let found = (1..=len)
+            .map(|i| {
+                if backward {
+                    (self.cursor + len * 2 - i - 1) % len + 1
+                } else {
+                    (self.cursor + i - 1) % len + 1
+                }
+            })
+            .find(|&line| re.is_match(self.text(line)));
+        found.ok_or_else(|| tr!("Pattern not found: {}", pattern))
+    }
+
+    /// 範囲（指定した行の数と、始まりと終わり）
+    fn range(&mut self, input: &mut Input) -> Result<(usize, usize, usize), String> {
+        input.skip_spaces();
+        if input.eat('%') {
+            return Ok((2, 1, self.len()));
+        }
+        let mut addresses = Vec::new();
+        let mut after_separator = false;
+        loop {
+            let address = self.address(input)?;
+            input.skip_spaces();
+            match input.peek() {
+                Some(separator @ (',' | ';')) => {
+                    input.bump();
+                    let line = address.unwrap_or(self.cursor);
+                    if separator == ';' {
+                        self.cursor = line.clamp(1, self.len());
+                    }
+                    addresses.push(line);
+                    after_separator = true;
+                }
+                _ => {
+                    match address {
+                        Some(line) => addresses.push(line),
+                        None if after_separator => addresses.push(self.cursor),
+                        None => {}
+                    }
+                    break;
+                }
+            }
+        }
+        Ok(match addresses[..] {
+            [] => (0, self.cursor, self.cursor),
+            [line] => (1, line, line),
+            [.., a, b] => (2, a.min(b), a.max(b)),
+        })
+    }
+
+    // ---- コマンド ----
+
+    /// `|` で続くコマンドをすべて実行する
+    fn execute(&mut self, command: &str) -> Result<(), String> {
+        let mut input = Input { rest: command };
+        loop {
+            input.rest = input.rest.trim_start_matches([' ', '\t', ':']);
+            if input.rest.is_empty() {
+                return Ok(());
+            }
+            self.command(&mut input)?;
+            input.skip_spaces();
+            if !input.rest.is_empty() && !input.eat('|') {
+                return Err(tr!("Trailing characters: {}", input.rest));
+            }
+        }
+    }
+
+    fn command(&mut self, input: &mut Input) -> Result<(), String> {
+        let (addresses, start, end) = self.range(input)?;
+        input.skip_spaces();
+        let name = match input.peek() {
+            Some(c) if c.is_ascii_alphabetic() => input.name(),
+            Some(c @ ('>' | '<' | '#')) => &input.rest[..c.len_utf8()],
+            _ => "",
+        };
+        if matches!(name, ">" | "<" | "#") {
+            input.bump();
+        }
+        let bang = input.eat('!');
+        // 0 行目は `:m` `:t` `:pu` の行き先にだけ使える
+        let first = start.max(1);
+        match name {
+            "" if addresses > 0 => {
+                self.goto(end);
+                Ok(())
+            }
+            "" => Ok(()),
+            name if is_abbreviation(name, "delete", 1) => {
+                let (start, end) = self.count(input, first, end);
+                let removed = self.remove_lines(start, end);
+                self.register = Some(Register {
+                    text: removed.join("\n") + "\n",
+                    linewise: true,
+                });
+                self.goto(start);
+                Ok(())
+            }
+            name if is_abbreviation(name, "yank", 1) => {
+                let (start, end) = self.count(input, first, end);
+                let text: Vec<&str> = (start..=end).map(|line| self.text(line)).collect();
+                self.register = Some(Register {
+                    text: text.join("\n") + "\n",
+                    linewise: true,
+                });
+                Ok(())
+            }
+            name if is_abbreviation(name, "put", 2) => {
+                input.skip_spaces();
+                if input
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphabetic() || c == '"')
+                {
+                    input.bump();
+                }
+                let register = self
+                    .register
+                    .clone()
+                    .ok_or_else(|| tr!("Nothing in register"))?;
+                let text = register.text.strip_suffix('\n').unwrap_or(&register.text);
+                let texts: Vec<String> = text.split('\n').map(String::from).collect();
+                let count = texts.len();
+                let after = if bang { end.saturating_sub(1) } else { end };
+                self.insert_lines(after, texts);
+                self.goto(after + count);
+                Ok(())
+            }
+            name if is_abbreviation(name, "substitute", 1) => self.substitute(input, first, end),
+            name if is_abbreviation(name, "global", 1) || is_abbreviation(name, "vglobal", 1) => {
+                let (start, end) = if addresses == 0 {
+                    (1, self.len())
+                } else {
+                    (first, end)
+                };
+                self.global(input, start, end, bang || name.starts_with('v'))
+            }
+            name if is_abbreviation(name, "mark", 2) || name == "k" => {
+                input.skip_spaces();
+                let mark = input.bump().map(String::from).unwrap_or_default();
+                let id = self.lines[end.max(1) - 1].id;
+                self.marks.insert(mark, id);
+                Ok(())
+            }
+            name if is_abbreviation(name, "move", 1) => {
+                let target = self.address(input)?.ok_or_else(|| tr!("Invalid range"))?;
+                self.move_lines(first, end, target)
+            }
+            "t" => self.copy_lines(input, first, end),
+            name if is_abbreviation(name, "copy", 2) => self.copy_lines(input, first, end),
+            name if is_abbreviation(name, "join", 1) => {
+                input.skip_spaces();
+                let (start, end) = match input.number() {
+                    Some(count) => (end, end + count.max(2) - 1),
+                    None if addresses < 2 || first == end => (first, first + 1),
+                    None => (first, end),
+                };
+                self.join(start, end.min(self.len()), !bang);
+                self.goto(start);
+                Ok(())
+            }
+            ">" | "<" => {
+                let mut amount = 1;
+                while input.eat(name.chars().next().unwrap_or('>')) {
+                    amount += 1;
+                }
+                let (start, end) = self.count(input, first, end);
+                for line in start..=end {
+                    self.shift(line, amount, name == ">");
+                }
+                self.goto(end);
+                Ok(())
+            }
+            name if is_abbreviation(name, "print", 1) => {
+                self.print(first, end, false);
+                Ok(())
+            }
+            "#" => {
+                self.print(first, end, true);
+                Ok(())
+            }
+            name if is_abbreviation(name, "number", 2) => {
+                self.print(first, end, true);
+                Ok(())
+            }
+            name if is_abbreviation(name, "normal", 4) => {
+                input.skip_spaces();
+                let keys = keys(input.take_rest());
+                let ids: Vec<usize> = self.lines[first - 1..end].iter().map(|l| l.id).collect();
+                for id in ids {
+                    if let Some(line) = self.position(id) {
+                        self.cursor = line;
+                        self.column = 0;
+                        self.normal(&keys)?;
+                    }
+                }
+                Ok(())
+            }
+            name => Err(tr!("Not an editor command: {}", name)),
+        }
+    }
+
+    /// コマンドの後ろの個数（あれば範囲の終わりから数える）
+    fn count(&self, input: &mut Input, start: usize, end: usize) -> (usize, usize) {
+        input.skip_spaces();
+        // レジスタの指定（無名レジスタだけを使う）
+        if input
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '"')
+        {
+            input.bump();
+            input.skip_spaces();
+        }
+        match input.number() {
+            Some(count) => (end, (end + count.max(1) - 1).min(self.len())),
+            None => (start, end),
+        }
+    }
+
+    fn substitute(&mut self, input: &mut Input, start: usize, end: usize) -> Result<(), String> {
+        let (pattern, replacement) = match input.peek() {
+            Some(delimiter) if !delimiter.is_alphanumeric() && !" \t\"|".contains(delimiter) => {
+                input.bump();
+                let pattern = input.delimited(delimiter);
+                let replacement = input.delimited(delimiter);
+                let pattern = self.pattern(pattern)?;
+                (pattern, replacement)
+            }
+            // `:s` だけなら前回の置換を繰り返す
+            _ => self
+                .last_substitute
+                .clone()
+                .ok_or_else(|| tr!("No previous regular expression"))?,
+        };
+        self.last_substitute = Some((pattern.clone(), replacement.clone()));
+        let len = input.rest.len()
+            - input
+                .rest
+                .trim_start_matches(|c: char| c.is_ascii_alphabetic() || c == '&' || c == '#')
+                .len();
+        let flags = &input.rest[..len];
+        input.rest = &input.rest[len..];
+        let (start, end) = self.count(input, start, end);
+
+        let substitution = Substitution::new(&pattern, &replacement, flags)?;
+        let segment = self.lines[start - 1..end]
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let index = LineIndex::new(&segment);
+        let replacements = substitution.replacements(&segment, &index, 1, usize::MAX);
+        if replacements.is_empty() {
+            return if self.in_global {
+                Ok(())
+            } else {
+                Err(tr!("Pattern not found: {}", pattern))
+            };
+        }
+        if substitution.count_only() {
+            let mut lines: Vec<usize> = replacements.iter().map(|r| r.line).collect();
+            lines.dedup();
+            self.output.push(tr!(
+                "{} matches on {} lines",
+                replacements.len(),
+                lines.len()
+            ));
+            return Ok(());
+        }
+
+        // 最後に置き換えた位置の、置き換え後の内容でのオフセット
+        let last = replacements.last().map_or(0, |r| r.range.start);
+        let shift: isize = replacements[..replacements.len() - 1]
+            .iter()
+            .map(|r| r.text.len() as isize - r.range.len() as isize)
+            .sum();
+        let last = (last as isize + shift) as usize;
+        let new = text::apply_edits(
+            &segment,
+            replacements
+                .into_iter()
+                .map(|r| (r.range, r.text))
+                .collect(),
+        );
+        let cursor = start + new[..last].matches('\n').count();
+        self.replace_lines(start, end, new.split('\n').map(String::from).collect());
+        self.goto(cursor);
+        Ok(())
+    }
+
+    fn global(
+        &mut self,
+        input: &mut Input,
+        start: usize,
+        end: usize,
+        invert: bool,
+    ) -> Result<(), String> {
+        if self.in_global {
+            return Err(tr!("Cannot use :global recursively"));
+        }
+        let delimiter = input.bump().unwrap_or('/');
+        let pattern = input.delimited(delimiter);
+        let pattern = self.pattern(pattern)?;
+        let command = match input.take_rest().trim() {
+            "" => "p",
+            command => command,
+        };
+        let re = replace::build_regex(&pattern, false, false)?;
+        // 先に対象の行に印を付け、消えていない行ごとに実行する
+        let ids: Vec<usize> = self.lines[start - 1..end]
+            .iter()
+            .filter(|line| re.is_match(&line.text) != invert)
+            .map(|line| line.id)
+            .collect();
+        if ids.is_empty() {
+            self.output.push(tr!("Pattern not found: {}", pattern));
+            return Ok(());
+        }
+        self.in_global = true;
+        let result = ids.into_iter().try_for_each(|id| match self.position(id) {
+            Some(line) => {
+                self.goto(line);
+                self.execute(command)
+            }
+            None => Ok(()),
+        });
+        self.in_global = false;
+        result
+    }
+
+    fn move_lines(&mut self, start: usize, end: usize, target: usize) -> Result<(), String> {
+        if (start..end).contains(&target) {
+            return Err(tr!("Cannot move a range of lines into itself"));
+        }
+        let count = end - start + 1;
+        let moved: Vec<Line> = self.lines.drain(start - 1..end).collect();
+        let after = if target >= end {
+            target - count
+        } else {
+            target
+        };
+        self.lines.splice(after..after, moved);
+        self.goto(after + count);
+        Ok(())
+    }
+
+    fn copy_lines(&mut self, input: &mut Input, start: usize, end: usize) -> Result<(), String> {
+        let target = self.address(input)?.ok_or_else(|| tr!("Invalid range"))?;
+        let texts: Vec<String> = (start..=end)
+            .map(|line| self.text(line).to_string())
+            .collect();
+        let count = texts.len();
+        self.insert_lines(target, texts);
+        self.goto(target + count);
+        Ok(())
+    }
+
+    /// `start`〜`end` 行を 1 行にし、つないだ位置（文字の位置）を返す
+    ///
+    /// `spaces` なら後ろの行の先頭の空白を除いて空白 1 つでつなぐ（`)` の前と空の行には入れない）。
+    fn join(&mut self, start: usize, end: usize, spaces: bool) -> usize {
+        let mut joined = self.text(start).to_string();
+        let mut at = joined.chars().count();
+        for line in start + 1..=end {
+            let next = self.text(line);
+            at = joined.chars().count();
+            if !spaces {
+                joined.push_str(next);
+                continue;
+            }
+            let next = next.trim_start();
+            if next.is_empty() {
+                continue;
+            }
+            if !joined.is_empty() && !joined.ends_with([' ', '\t']) && !next.starts_with(')') {
+                joined.push(' ');
+            }
+            joined.push_str(next);
+        }
+        if end > start {
+            self.lines[start - 1].text = joined;
+            self.remove_lines(start + 1, end);
+        }
+        at
+    }
+
+    fn shift(&mut self, line: usize, amount: usize, right: bool) {
+        let text = self.text(line);
+        if text.is_empty() {
+            return;
+        }
+        let (width, len) = indent_width(text, self.shift_width);
+        let width = if right {
+            width + self.shift_width * amount
+        } else {
+            width.saturating_sub(self.shift_width * amount)
+        };
+        let indent_len: usize = text.chars().take(len).map(char::len_utf8).sum();
+        let indent = if text[..indent_len].contains('\t') {
+            "\t".repeat(width / self.shift_width) + &" ".repeat(width % self.shift_width)
+        } else {
+            " ".repeat(width)
+        };
+        self.lines[line - 1].text = indent + &text[indent_len..];
+    }
+
+    fn print(&mut self, start: usize, end: usize, numbers: bool) {
+        for line in start..=end {
+            let text = self.text(line);
+            self.output.push(if numbers {
+                format!("{line:>3} {text}")
+            } else {
+                text.to_string()
+            });
+        }
+        self.goto(end);
+    }
+
+    // ---- :normal ----
+
+    /// キーを実行する（移動に失敗したら残りを実行しない）
+    fn normal(&mut self, keys: &[char]) -> Result<(), String> {
+        let mut i = 0;
+        while i < keys.len() {
+            let mut count = None;
+            while let Some(d) = keys.get(i).and_then(|c| c.to_digit(10)) {
+                if d == 0 && count.is_none() {
+                    break;
+                }
+                count = Some(count.unwrap_or(0) * 10 + d as usize);
+                i += 1;
+            }
+            let Some(&key) = keys.get(i) else {
+                break;
+            };
+            i += 1;
+            let n = count.unwrap_or(1).max(1);
+            let chars = self.chars();
+            match key {
+                '\x1b' => {}
+                'i' | 'a' | 'I' | 'A' => {
+                    self.column = match key {
+                        'i' => self.column,
+                        'a' => (self.column + 1).min(chars.len()),
+                        'I' => first_non_blank(&chars),
+                        _ => chars.len(),
+                    };
+                    let (text, next) = insert_text(keys, i);
+                    i = next;
+                    self.insert(&text.repeat(n));
+                }
+                'o' | 'O' => {
+                    let after = if key == 'o' {
+                        self.cursor
+                    } else {
+                        self.cursor - 1
+                    };
+                    self.insert_lines(after, vec![String::new()]);
+                    self.cursor = after + 1;
+                    self.column = 0;
+                    let (text, next) = insert_text(keys, i);
+                    i = next;
+                    self.insert(&vec![text; n].join("\n"));
+                }
+                'x' | 's' => {
+                    let column = self.column;
+                    self.delete_chars(column, (column + n).min(chars.len()));
+                    if key == 's' {
+                        self.column = column;
+                        let (text, next) = insert_text(keys, i);
+                        i = next;
+                        self.insert(&text);
+                    }
+                }
+                'X' => {
+                    let start = self.column.saturating_sub(n);
+                    self.delete_chars(start, self.column);
+                }
+                'D' | 'C' => {
+                    let column = self.column;
+                    self.delete_chars(column, chars.len());
+                    if key == 'C' {
+                        self.column = column;
+                        let (text, next) = insert_text(keys, i);
+                        i = next;
+                        self.insert(&text);
+                    }
+                }
+                'S' => {
+                    self.change_lines(n);
+                    let (text, next) = insert_text(keys, i);
+                    i = next;
+                    self.insert(&text);
+                }
+                '~' => {
+                    let end = (self.column + n).min(chars.len());
+                    let mut chars = chars;
+                    for c in &mut chars[self.column.min(end)..end] {
+                        *c = if c.is_uppercase() {
+                            c.to_lowercase().next().unwrap_or(*c)
+                        } else {
+                            c.to_uppercase().next().unwrap_or(*c)
+                        };
+                    }
+                    self.set_chars(&chars);
+                    self.column = end.min(chars.len().saturating_sub(1));
+                }
+                'r' => {
+                    let Some(&c) = keys.get(i) else {
+                        break;
+                    };
+                    i += 1;
+                    if self.column + n > chars.len() {
+                        break;
+                    }
+                    let mut chars = chars;
+                    for slot in &mut chars[self.column..self.column + n] {
+                        *slot = c;
+                    }
+                    self.set_chars(&chars);
+                    self.column += n - 1;
+                }
+                'J' => {
+                    let end = (self.cursor + n.max(2) - 1).min(self.len());
+                    if end == self.cursor {
+                        break;
+                    }
+                    self.column = self.join(self.cursor, end, true);
+                }
+                'p' | 'P' => {
+                    let register = self
+                        .register
+                        .clone()
+                        .ok_or_else(|| tr!("Nothing in register"))?;
+                    self.put(&register, n, key == 'p');
+                }
+                'd' | 'c' | 'y' => {
+                    let mut motion_count = None;
+                    while let Some(d) = keys.get(i).and_then(|c| c.to_digit(10)) {
+                        if d == 0 && motion_count.is_none() {
+                            break;
+                        }
+                        motion_count = Some(motion_count.unwrap_or(0) * 10 + d as usize);
+                        i += 1;
+                    }
+                    let Some(&motion_key) = keys.get(i) else {
+                        break;
+                    };
+                    i += 1;
+                    let n = n * motion_count.unwrap_or(1).max(1);
+                    let motion = if motion_key == key {
+                        Some(Motion::Line((self.cursor + n - 1).min(self.len())))
+                    } else if key == 'c'
+                        && motion_key == 'w'
+                        && chars.get(self.column).is_some_and(|c| !c.is_whitespace())
+                    {
+                        // `cw` は単語の終わりまで
+                        self.motion(keys, &mut i, 'e', Some(n))
+                    } else {
+                        self.motion(keys, &mut i, motion_key, count.or(motion_count).map(|_| n))
+                    };
+                    let Some(motion) = motion else {
+                        break;
+                    };
+                    self.operate(key, motion);
+                    if key == 'c' {
+                        let (text, next) = insert_text(keys, i);
+                        i = next;
+                        self.insert(&text);
+                    }
+                }
+                key => match self.motion(keys, &mut i, key, count) {
+                    // 行の最後の単語からの `w` は次の行へ
+                    Some(Motion::Char { to, .. })
+                        if key == 'w' && to >= chars.len() && self.cursor < self.len() =>
+                    {
+                        self.goto(self.cursor + 1);
+                    }
+                    Some(Motion::Char { to, .. }) => {
+                        self.column = to.min(chars.len().saturating_sub(1));
+                    }
+                    Some(Motion::Line(line)) => {
+                        self.cursor = line;
+                        self.column = self.column.min(self.chars().len().saturating_sub(1));
+                    }
+                    None if "hl0^$wbeftFTjkGg".contains(key) => break,
+                    None => return Err(tr!("Unsupported key in :normal: {}", key)),
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// 移動先（移動できなければ `None`）
+    fn motion(
+        &self,
+        keys: &[char],
+        i: &mut usize,
+        key: char,
+        count: Option<usize>,
+    ) -> Option<Motion> {
+        let n = count.unwrap_or(1).max(1);
+        let chars = self.chars();
+        let col = self.column;
+        let char_motion = |to: usize, inclusive: bool| Some(Motion::Char { to, inclusive });
+        match key {
+            'h' => char_motion(col.checked_sub(1)?.saturating_sub(n - 1), false),
+            'l' => {
+                (!chars.is_empty()).then_some(())?;
+                char_motion((col + n).min(chars.len()), false)
+            }
+            '0' => char_motion(0, false),
+            '^' => char_motion(first_non_blank(&chars), false),
+            '$' => char_motion(chars.len().saturating_sub(1), true),
+            'w' => char_motion(
+                (0..n).fold(col, |pos, _| next_word_start(&chars, pos)),
+                false,
+            ),
+            'e' => char_motion((0..n).fold(col, |pos, _| word_end(&chars, pos)), true),
+            'b' => char_motion(
+                (0..n).fold(col, |pos, _| previous_word_start(&chars, pos)),
+                false,
+            ),
+            'f' | 't' | 'F' | 'T' => {
+                let target = *keys.get(*i)?;
+                *i += 1;
+                let mut pos = col;
+                for _ in 0..n {
+                    pos = if key == 'f' || key == 't' {
+                        pos + 1 + chars.get(pos + 1..)?.iter().position(|&c| c == target)?
+                    } else {
+                        chars[..pos].iter().rposition(|&c| c == target)?
+                    };
+                }
+                match key {
+                    'f' => char_motion(pos, true),
+                    't' => char_motion(pos - 1, true),
+                    'F' => char_motion(pos, false),
+                    _ => char_motion(pos + 1, false),
+                }
+            }
+            'j' => {
+                (self.cursor < self.len()).then_some(())?;
+                Some(Motion::Line((self.cursor + n).min(self.len())))
+            }
+            'k' => {
+                (self.cursor > 1).then_some(())?;
+                Some(Motion::Line(self.cursor.saturating_sub(n).max(1)))
+            }
+            'G' => Some(Motion::Line(
+                count.unwrap_or(self.len()).clamp(1, self.len()),
+            )),
+            'g' if keys.get(*i) == Some(&'g') => {
+                *i += 1;
+                Some(Motion::Line(count.unwrap_or(1).clamp(1, self.len())))
+            }
+            _ => None,
+        }
+    }
+
+    /// `d` `c` `y` を移動の範囲に適用する
+    fn operate(&mut self, operator: char, motion: Motion) {
+        match motion {
+            Motion::Char { to, inclusive } => {
+                let len = self.chars().len();
+                let start = self.column.min(to);
+                let end = (self.column.max(to) + usize::from(inclusive)).min(len);
+                if operator == 'y' {
+                    let chars = self.chars();
+                    self.register = Some(Register {
+                        text: chars[start..end].iter().collect(),
+                        linewise: false,
+                    });
+                    self.column = start;
+                } else {
+                    self.delete_chars(start, end);
+                    if operator == 'c' {
+                        self.column = start;
+                    }
+                }
+            }
+            Motion::Line(line) => {
+                let (start, end) = (self.cursor.min(line), self.cursor.max(line));
+                let texts: Vec<String> = (start..=end).map(|l| self.text(l).to_string()).collect();
+                self.register = Some(Register {
+                    text: texts.join("\n") + "\n",
+                    linewise: true,
+                });
+                match operator {
+                    'y' => self.cursor = start,
+                    'd' => {
+                        self.remove_lines(start, end);
+                        self.goto(start);
+                    }
+                    _ => {
+                        self.cursor = start;
+                        self.change_lines(end - start + 1);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 現在行から `count` 行を空の 1 行にする（`S` `cc`）
+    fn change_lines(&mut self, count: usize) {
+        let end = (self.cursor + count - 1).min(self.len());
+        self.replace_lines(self.cursor, end, vec![String::new()]);
+        self.column = 0;
+    }
+
+    /// 行の `start`〜`end` 文字目を消してレジスタに入れる
+    fn delete_chars(&mut self, start: usize, end: usize) {
+        let mut chars = self.chars();
+        if start >= end {
+            return;
+        }
+        let removed: String = chars.drain(start..end).collect();
+        self.register = Some(Register {
+            text: removed,
+            linewise: false,
+        });
+        self.set_chars(&chars);
+        self.column = start.min(chars.len().saturating_sub(1));
+    }
+
+    /// 桁の位置に文字を入れ、桁を入れた最後の文字にする（改行で行を分ける）
+    fn insert(&mut self, inserted: &str) {
+        let chars = self.chars();
+        let column = self.column.min(chars.len());
+        let before: String = chars[..column].iter().collect();
+        let after: String = chars[column..].iter().collect();
+        let text = before + inserted;
+        let mut texts: Vec<String> = text.split('\n').map(String::from).collect();
+        let last = texts.len() - 1;
+        let end = texts[last].chars().count();
+        texts[last].push_str(&after);
+        let added = texts.len() - 1;
+        self.replace_lines(self.cursor, self.cursor, texts);
+        self.cursor += added;
+        self.column = end.saturating_sub(1);
+    }
+
+    /// レジスタの内容を `count` 回貼り付ける
+    fn put(&mut self, register: &Register, count: usize, after: bool) {
+        if register.linewise {
+            let text = register.text.strip_suffix('\n').unwrap_or(&register.text);
+            let texts: Vec<String> = vec![text; count]
+                .join("\n")
+                .split('\n')
+                .map(String::from)
+                .collect();
+            let at = if after { self.cursor } else { self.cursor - 1 };
+            self.insert_lines(at, texts);
+            self.goto(at + 1);
+        } else {
+            if after && !self.chars().is_empty() {
+                self.column += 1;
+            }
+            self.insert(&register.text.repeat(count));
+        }
+    }
+}
+
+/// Ex コマンドを実行し、実行後の内容と元の内容に対する置き換えを返す
+#[tauri::command]
+pub fn run_ex_command(buffer: ExBuffer, command: String) -> Result<ExResult, String> {
+    let eol = if buffer.content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let trailing_newline = buffer.content.ends_with('\n');
+    let mut ex = Ex::new(&buffer);
+    ex.execute(command.trim_start().trim_start_matches(':'))?;
+
+    let mut content = ex
+        .lines
+        .iter()
+        .map(|line| line.text.as_str())
+        .collect::<Vec<_>>()
+        .join(eol);
+    if trailing_newline {
+        content.push_str(eol);
+    }
+    let line_text = ex.text(ex.cursor);
+    let column: usize = line_text.chars().take(ex.column).map(char::len_utf16).sum();
+    let marks = ex
+        .marks
+        .iter()
+        .filter_map(|(name, &id)| Some((name.clone(), ex.position(id)?)))
+        .collect();
+    Ok(ExResult {
+        changed: content != buffer.content,
+        edits: formatter::text_edits(&buffer.content, &content),
+        cursor: EditorPosition {
+            line: ex.cursor as u64,
+            column: column as u64 + 1,
+        },
+        marks,
+        register: ex.register,
+        last_pattern: ex.last_pattern,
+        output: ex.output,
+        content,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_ex_command, ExBuffer};
+
+    const TEXT: &str = "a1\nb2\na3\nb4\nc5\n";
+
+    fn run(command: &str) -> String {
+        let buffer = ExBuffer {
+            content: TEXT.to_string(),
+            ..Default::default()
+        };
+        run_ex_command(buffer, command.to_string()).unwrap().content
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(run(":2,3d"), "a1\nb4\nc5\n");
+        assert_eq!(run(":$-1,$d"), "a1\nb2\na3\n");
+        assert_eq!(run(":/c/d"), "a1\nb2\na3\nb4\n");
+        // `;` は前の位置を現在行にしてから次の位置を求める
+        assert_eq!(run(":2;+1d"), "a1\nb4\nc5\n");
+        assert_eq!(run(":2ma x|'xd"), "a1\na3\nb4\nc5\n");
+        assert_eq!(run(":1m$"), "b2\na3\nb4\nc5\na1\n");
+    }
+
+    #[test]
+    fn substitute() {
+        assert_eq!(run(":%s/\\d/X/"), "aX\nbX\naX\nbX\ncX\n");
+        assert_eq!(run(":2s/./Y/g"), "a1\nYY\na3\nb4\nc5\n");
+        assert_eq!(run(":s/(a)(1)/\\2\\u\\1/"), "1A\nb2\na3\nb4\nc5\n");
+        assert_eq!(run(":g/b/s/\\d/#/"), "a1\nb#\na3\nb#\nc5\n");
+    }
+
+    #[test]
+    fn global_and_normal() {
+        assert_eq!(run(":g/^a/d"), "b2\nb4\nc5\n");
+        assert_eq!(run(":v/a/d"), "a1\na3\n");
+        assert_eq!(run(":g/b/normal Axx"), "a1\nb2xx\na3\nb4xx\nc5\n");
+    }
+
+    #[test]
+    fn errors() {
+        let buffer = || ExBuffer {
+            content: TEXT.to_string(),
+            ..Default::default()
+        };
+        assert!(run_ex_command(buffer(), ":foo".to_string()).is_err());
+        assert!(run_ex_command(buffer(), ":9d".to_string()).is_err());
+        assert!(run_ex_command(buffer(), ":%s/q/r/".to_string()).is_err());
+    }
+}
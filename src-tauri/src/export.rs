@@ -0,0 +1,1221 @@
+// HTML エクスポート
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::annotations::{self, Rendering, ANNOTATION_CSS};
+use crate::diff;
+use crate::links;
+use crate::markdown;
+use crate::obsidian;
+use crate::preprocess::{preprocess, Preprocessor};
+use crate::render::{self, HeadingNumbering};
+use crate::shell::{self, RunOptions};
+use crate::state::AppState;
+use crate::tasks::Task;
+use crate::templates;
+use crate::text::{self, escape_html};
+use crate::tr;
+use crate::vault::{self, path_string, Vault};
+use crate::watcher::{self, Changes};
+
+/// テーマの配色（フロントエンドの `[data-theme]` と同じ値）
+struct Palette {
+    bg_primary: &'static str,
+    bg_secondary: &'static str,
+    text_primary: &'static str,
+    text_secondary: &'static str,
+    accent: &'static str,
+    border: &'static str,
+}
+
+fn palette(theme: &str) -> Palette {
+    let (bg_primary, bg_secondary, text_primary, text_secondary, accent, border) = match theme {
+        "dark" => (
+            "#1e1e1e", "#252526", "#d4d4d4", "#808080", "#569cd6", "#3c3c3c",
+        ),
+        "monokai" => (
+            "#272822", "#1e1f1c", "#f8f8f2", "#75715e", "#66d9ef", "#49483e",
+        ),
+        "solarized-dark" => (
+            "#002b36", "#073642", "#839496", "#586e75", "#268bd2", "#094959",
+        ),
+        "solarized-light" => (
+            "#fdf6e3", "#eee8d5", "#657b83", "#93a1a1", "#268bd2", "#d6cdb6",
+        ),
+        "nord" => (
+            "#2e3440", "#3b4252", "#eceff4", "#d8dee9", "#88c0d0", "#4c566a",
+        ),
+        "dracula" => (
+            "#282a36", "#21222c", "#f8f8f2", "#6272a4", "#bd93f9", "#44475a",
+        ),
+        "github-dark" => (
+            "#0d1117", "#161b22", "#c9d1d9", "#8b949e", "#58a6ff", "#30363d",
+        ),
+        "github-light" => (
+            "#ffffff", "#f6f8fa", "#24292f", "#57606a", "#0969da", "#d0d7de",
+        ),
+        _ => (
+            "#ffffff", "#f3f3f3", "#1e1e1e", "#6e6e6e", "#0066b8", "#e0e0e0",
+        ),
+    };
+    Palette {
+        bg_primary,
+        bg_secondary,
+        text_primary,
+        text_secondary,
+        accent,
+        border,
+    }
+}
+
+/// テーマの配色の CSS 変数
+fn palette_vars(theme: &str) -> String {
+    let p = palette(theme);
+    format!(
+        r#"  --bg-primary: {};
+  --bg-secondary: {};
+  --text-primary: {};
+  --text-secondary: {};
+  --accent: {};
+  --border: {};
+"#,
+        p.bg_primary, p.bg_secondary, p.text_primary, p.text_secondary, p.accent, p.border
+    )
+}
+
+/// テーマによらない共通のスタイル
+const BASE_CSS: &str = r#"body {
+  font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif;
+  line-height: 1.6;
+  max-width: 800px;
+  margin: 0 auto;
+  padding: 2rem;
+  color: var(--text-primary);
+  background: var(--bg-primary);
+}
+h1, h2, h3, h4, h5, h6 { margin-top: 1.5em; }
+h1 { border-bottom: 2px solid var(--border); padding-bottom: 0.3em; }
+h2 { border-bottom: 1px solid var(--border); padding-bottom: 0.3em; }
+a { color: var(--accent); }
+pre { background: var(--bg-secondary); padding: 1em; overflow-x: auto; border-radius: 4px; }
+code { background: var(--bg-secondary); padding: 0.2em 0.4em; border-radius: 3px; }
+pre code { background: none; padding: 0; }
+blockquote { border-left: 4px solid var(--border); margin: 0; padding-left: 1em; color: var(--text-secondary); }
+img { max-width: 100%; height: auto; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border: 1px solid var(--border); padding: 0.5em; text-align: left; }
+th { background: var(--bg-secondary); }
+.toc { background: var(--bg-secondary); padding: 1em; border-radius: 4px; margin-bottom: 2em; }
+.toc ul { margin: 0; padding-left: 1.5em; }
+nav.site-index ul { list-style: none; padding-left: 1.2em; }
+nav.site-index .folder { font-weight: bold; color: var(--text-secondary); }
+.markdown-embed { border-left: 3px solid var(--accent); padding-left: 1em; margin: 1em 0; }
+.callout { border-left: 4px solid var(--accent); background: var(--bg-secondary); border-radius: 4px; padding: 0.5em 1em; margin: 1em 0; }
+.callout-title { font-weight: bold; }
+details.callout > summary { cursor: pointer; }
+.callout[data-callout="warning"], .callout[data-callout="caution"] { border-left-color: #e0a030; }
+.callout[data-callout="danger"], .callout[data-callout="error"], .callout[data-callout="bug"] { border-left-color: #e05050; }
+.callout[data-callout="tip"], .callout[data-callout="success"] { border-left-color: #40a060; }
+.metadata { color: var(--text-secondary); margin-bottom: 1.5em; }
+.metadata .author::after { content: " · "; }
+.metadata .author:last-child::after { content: ""; }
+.document-footer { border-top: 1px solid var(--border); margin-top: 3em; padding-top: 1em; color: var(--text-secondary); font-size: 0.9em; }
+.task-checkbox { margin-right: 0.4em; }
+li:has(> .task-checkbox) { list-style: none; }
+.task-state { margin-top: 2em; }
+.heading-number { color: var(--text-secondary); }
+.code-block { margin: 1em 0; }
+.inline-svg svg { max-width: 100%; height: auto; }
+.diagram { margin: 1em 0; text-align: center; overflow-x: auto; }
+.diagram svg { max-width: 100%; height: auto; }
+.diagram-error { color: #e05050; }
+.datatable, .chart { margin: 1em 0; overflow-x: auto; }
+.chart { text-align: center; }
+.chart svg { max-width: 100%; height: auto; }
+.datatable figcaption, .chart figcaption { font-weight: bold; margin-bottom: 0.5em; }
+.data-block-error { color: #e05050; }
+ins.critic { background: rgba(46, 160, 67, 0.2); text-decoration: none; }
+del.critic { background: rgba(248, 81, 73, 0.2); }
+mark.critic { background: rgba(255, 200, 0, 0.35); color: inherit; }
+.critic-comment { background: var(--bg-secondary); color: var(--text-secondary); border-radius: 3px; padding: 0 0.3em; font-size: 0.9em; }
+.code-block pre { margin: 0; }
+.code-filename { font-family: monospace; font-size: 0.85em; color: var(--text-secondary); background: var(--bg-secondary); border-bottom: 1px solid var(--border); border-radius: 4px 4px 0 0; padding: 0.3em 1em; }
+.code-filename + pre { border-radius: 0 0 4px 4px; }
+pre.line-numbers { counter-reset: line; }
+pre.line-numbers .line::before { counter-increment: line; content: counter(line); display: inline-block; width: 2.5em; margin-right: 1em; text-align: right; color: var(--text-secondary); user-select: none; }
+.line.highlighted { display: inline-block; width: 100%; background: color-mix(in srgb, var(--accent) 15%, transparent); }
+.tag { color: var(--accent); background: var(--bg-secondary); border-radius: 1em; padding: 0 0.5em; font-size: 0.9em; }
+"#;
+
+/// GitHub の README と同じ見た目（共通のスタイルの後に重ねる）
+const GITHUB_CSS: &str = r#"body {
+  font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', 'Noto Sans', Helvetica, Arial, sans-serif;
+  font-size: 16px;
+  line-height: 1.5;
+  max-width: 980px;
+  padding: 45px;
+}
+h1, h2, h3, h4, h5, h6 { margin-top: 24px; margin-bottom: 16px; font-weight: 600; line-height: 1.25; }
+h1 { font-size: 2em; border-bottom: 1px solid var(--border); }
+h2 { font-size: 1.5em; }
+h3 { font-size: 1.25em; }
+h6 { color: var(--text-secondary); }
+p, blockquote, ul, ol, table, pre, details { margin-top: 0; margin-bottom: 16px; }
+a { text-decoration: none; }
+a:hover { text-decoration: underline; }
+code { font-family: ui-monospace, SFMono-Regular, 'SF Mono', Menlo, Consolas, monospace; font-size: 85%; border-radius: 6px; }
+pre { font-size: 85%; line-height: 1.45; padding: 16px; border-radius: 6px; }
+pre code { font-size: 100%; }
+blockquote { border-left: 0.25em solid var(--border); padding: 0 1em; }
+table { width: max-content; max-width: 100%; overflow: auto; display: block; }
+th, td { padding: 6px 13px; }
+th { font-weight: 600; }
+tr:nth-child(2n) { background: var(--bg-secondary); }
+hr { height: 0.25em; margin: 24px 0; padding: 0; border: 0; background: var(--border); }
+.markdown-alert { padding: 0.5rem 1rem; margin-bottom: 16px; border-left: 0.25em solid var(--border); }
+.markdown-alert > :last-child { margin-bottom: 0; }
+.markdown-alert-title { font-weight: 500; margin-bottom: 4px; }
+.markdown-alert-note { border-left-color: #0969da; }
+.markdown-alert-note .markdown-alert-title { color: #0969da; }
+.markdown-alert-tip { border-left-color: #1a7f37; }
+.markdown-alert-tip .markdown-alert-title { color: #1a7f37; }
+.markdown-alert-important { border-left-color: #8250df; }
+.markdown-alert-important .markdown-alert-title { color: #8250df; }
+.markdown-alert-warning { border-left-color: #9a6700; }
+.markdown-alert-warning .markdown-alert-title { color: #9a6700; }
+.markdown-alert-caution { border-left-color: #cf222e; }
+.markdown-alert-caution .markdown-alert-title { color: #cf222e; }
+"#;
+
+/// エクスポートする HTML のスタイルシート
+pub fn theme_css(theme: &str) -> String {
+    format!(":root {{\n{}}}\n{BASE_CSS}", palette_vars(theme))
+}
+
+/// ライトとダークの両方のテーマ（ブラウザの `prefers-color-scheme` で切り替える）
+pub fn dual_theme_css(light: &str, dark: &str) -> String {
+    format!(
+        ":root {{\n  color-scheme: light dark;\n{}}}\n@media (prefers-color-scheme: dark) {{\n:root {{\n{}}}\n}}\n{BASE_CSS}",
+        palette_vars(light),
+        palette_vars(dark)
+    )
+}
+
+/// 本文 HTML とスタイルシートを完全な HTML 文書に包む
+pub fn html_document_with_css(title: &str, body: &str, css: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="ja">
+<head>
+  <meta charset="UTF-8">
+  <meta name="viewport" content="width=device-width, initial-scale=1.0">
+  <title>{}</title>
+  <style>
+{}  </style>
+</head>
+<body>
+{}
+</body>
+</html>
+"#,
+        escape_html(title),
+        css,
+        body
+    )
+}
+
+/// 本文 HTML を完全な HTML 文書に包む
+pub fn html_document(title: &str, body: &str, theme: &str) -> String {
+    html_document_with_css(title, body, &theme_css(theme))
+}
+
+/// 文書のタイトル（最初の見出し、なければファイル名）
+pub fn document_title(path: &Path, content: &str) -> String {
+    markdown::headings(content)
+        .into_iter()
+        .next()
+        .map(|h| h.text)
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        })
+}
+
+/// ワークスペース一括エクスポートの結果
+#[derive(Debug, Serialize)]
+pub struct VaultExportSummary {
+    pub pages: usize,
+    pub assets: usize,
+    pub index: String,
+}
+
+/// エクスポート先での HTML ファイルのパス
+fn output_path(vault: &Vault, out_dir: &Path, source: &Path) -> PathBuf {
+    let relative = source.strip_prefix(&vault.root).unwrap_or(source);
+    out_dir.join(relative).with_extension("html")
+}
+
+/// ノート間リンクを `.html` に、ウィキリンクの埋め込み画像を相対パスに書き換える
+fn rewrite_vault_link(vault: &Vault, doc: &Path, dest: &str, wiki: bool) -> Option<String> {
+    if !wiki && links::is_external(dest) {
+        return None;
+    }
+    let (target, anchor) = match dest.split_once('#') {
+        Some((target, anchor)) => (target, Some(anchor)),
+        None => (dest, None),
+    };
+    let anchor = anchor.map(|a| {
+        if wiki {
+            markdown::slugify(a)
+        } else {
+            a.to_string()
+        }
+    });
+    if target.is_empty() {
+        return anchor.map(|a| format!("#{a}"));
+    }
+
+    let resolved = vault.resolve_target(doc, target, wiki)?;
+    let doc_dir = doc.parent().unwrap_or(&vault.root);
+    let link = if vault::is_markdown(&resolved) {
+        vault::relative_path(doc_dir, &resolved.with_extension("html"))
+    } else if wiki {
+        vault::relative_path(doc_dir, &resolved)
+    } else {
+        return None;
+    };
+    let link = links::percent_encode(&link);
+    Some(match anchor {
+        Some(a) => format!("{link}#{a}"),
+        None => link,
+    })
+}
+
+/// 目次ページのフォルダ階層
+#[derive(Default)]
+struct IndexTree {
+    pages: Vec<(String, String)>,
+    folders: BTreeMap<String, IndexTree>,
+}
+
+impl IndexTree {
+    fn insert(&mut self, components: &[String], href: String, title: String) {
+        match components {
+            [] | [_] => self.pages.push((href, title)),
+            [folder, rest @ ..] => self
+                .folders
+                .entry(folder.clone())
+                .or_default()
+                .insert(rest, href, title),
+        }
+    }
+
+    fn render(&self, out: &mut String) {
+        out.push_str("<ul>\n");
+        for (href, title) in &self.pages {
+            out.push_str(&format!(
+                "<li><a href=\"{}\">{}</a></li>\n",
+                escape_html(href),
+                escape_html(title)
+            ));
+        }
+        for (name, folder) in &self.folders {
+            out.push_str(&format!(
+                "<li><span class=\"folder\">{}/</span>\n",
+                escape_html(name)
+            ));
+            folder.render(out);
+            out.push_str("</li>\n");
+        }
+        out.push_str("</ul>\n");
+    }
+}
+
+fn write_file(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| tr!("Failed to create {}: {e}", parent.display()))?;
+    }
+    fs::write(path, content).map_err(|e| tr!("Failed to write {}: {e}", path.display()))
+}
+
+/// 書き出し中の静的サイト（監視中は変更のあったファイルだけ更新する）
+struct Site {
+    out_dir: PathBuf,
+    theme: String,
+    /// ノートのパスとタイトル（目次ページ用）
+    titles: BTreeMap<PathBuf, String>,
+    preprocessors: Vec<Preprocessor>,
+    /// ワークスペースを信頼しているか（外部コマンドの前処理を使えるか）
+    trusted: bool,
+}
+
+impl Site {
+    fn new(out_dir: &Path, theme: &str, preprocessors: Vec<Preprocessor>, trusted: bool) -> Self {
+        Self {
+            out_dir: vault::normalize(out_dir),
+            theme: theme.to_string(),
+            titles: BTreeMap::new(),
+            preprocessors,
+            trusted,
+        }
+    }
+
+    /// 書き出し対象のファイル（出力先がワークスペース内にある場合、過去の出力は除く）
+    fn sources<'a>(&'a self, vault: &'a Vault) -> impl Iterator<Item = &'a PathBuf> {
+        vault.files.iter().filter(|p| !p.starts_with(&self.out_dir))
+    }
+
+    /// ノートは HTML に変換し、それ以外のファイルはそのままコピー
+    fn export_file(&mut self, vault: &Vault, source: &Path) -> Result<(), String> {
+        let relative = source.strip_prefix(&vault.root).unwrap_or(source);
+        if !vault::is_markdown(source) {
+            let dest = self.out_dir.join(relative);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| tr!("Failed to create {}: {e}", parent.display()))?;
+            }
+            fs::copy(source, &dest).map_err(|e| tr!("Failed to copy {}: {e}", source.display()))?;
+            return Ok(());
+        }
+
+        let content = fs::read_to_string(source)
+            .map_err(|e| tr!("Failed to read {}: {e}", source.display()))?;
+        let title = document_title(source, &content);
+        let content = preprocess(
+            &content,
+            Some(source),
+            Some(vault),
+            &self.preprocessors,
+            self.trusted,
+        )?;
+        let rewrite = |dest: &str, wiki: bool| rewrite_vault_link(vault, source, dest, wiki);
+        let body = render::render_html(&content, Some(&rewrite));
+        write_file(
+            &output_path(vault, &self.out_dir, source),
+            &html_document(&title, &body, &self.theme),
+        )?;
+        self.titles.insert(source.to_path_buf(), title);
+        Ok(())
+    }
+
+    /// 削除されたファイルの出力を消す
+    fn remove_file(&mut self, vault: &Vault, source: &Path) {
+        let dest = if vault::is_markdown(source) {
+            self.titles.remove(source);
+            output_path(vault, &self.out_dir, source)
+        } else {
+            let relative = source.strip_prefix(&vault.root).unwrap_or(source);
+            self.out_dir.join(relative)
+        };
+        let _ = fs::remove_file(dest);
+    }
+
+    /// フォルダ階層の目次ページを書き出す
+    fn write_index(&self, vault: &Vault) -> Result<PathBuf, String> {
+        let mut index = IndexTree::default();
+        for (source, title) in &self.titles {
+            let relative = source.strip_prefix(&vault.root).unwrap_or(source);
+            let components: Vec<String> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            let href = links::percent_encode(&vault::to_slash(&relative.with_extension("html")));
+            index.insert(&components, href, title.clone());
+        }
+
+        let site_title = vault
+            .root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "mdvim".to_string());
+        let mut body = format!(
+            "<h1>{}</h1>\n<nav class=\"site-index\">\n",
+            escape_html(&site_title)
+        );
+        index.render(&mut body);
+        body.push_str("</nav>");
+        // ワークスペースに index.md がある場合はそのページを優先する
+        let index_name = if vault.root.join("index.md").is_file() {
+            "sitemap.html"
+        } else {
+            "index.html"
+        };
+        let index_path = self.out_dir.join(index_name);
+        write_file(&index_path, &html_document(&site_title, &body, &self.theme))?;
+        Ok(index_path)
+    }
+}
+
+/// ワークスペースの全ノートを静的 HTML サイトとして書き出す（`task_id` を指定すると進捗を送り、中断できる）
+#[tauri::command(async)]
+pub fn export_vault_html(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    root: String,
+    out_dir: String,
+    theme: Option<String>,
+    task_id: Option<String>,
+) -> Result<VaultExportSummary, String> {
+    let task = Task::start(&app, &state, task_id)?;
+    let vault = Vault::scan(Path::new(&root));
+    let mut site = Site::new(
+        Path::new(&out_dir),
+        theme.as_deref().unwrap_or("light"),
+        state.preprocess.get(),
+        state.trust.is_trusted(&vault.root),
+    );
+    let sources: Vec<PathBuf> = site.sources(&vault).cloned().collect();
+    for (i, source) in sources.iter().enumerate() {
+        task.check()?;
+        site.export_file(&vault, source)?;
+        task.progress(i + 1, sources.len(), || path_string(source));
+    }
+    let index_path = site.write_index(&vault)?;
+
+    Ok(VaultExportSummary {
+        pages: site.titles.len(),
+        assets: sources.len() - site.titles.len(),
+        index: path_string(&index_path),
+    })
+}
+
+/// 見出しの目次（入れ子のリスト。見出しがなければ空）
+pub fn toc_html(content: &str, numbering: Option<&HeadingNumbering>) -> String {
+    let headings = markdown::headings(content);
+    let Some(base) = headings.iter().map(|h| h.level).min() else {
+        return String::new();
+    };
+    let slugs = markdown::heading_slugs(&headings);
+    let levels: Vec<usize> = headings.iter().map(|h| h.level).collect();
+    let numbers = match numbering {
+        Some(numbering) => numbering.numbers(&levels),
+        None => vec![None; headings.len()],
+    };
+    let mut out = String::from("<nav class=\"toc\">\n");
+    let mut depth = 0;
+    for ((heading, slug), number) in headings.iter().zip(&slugs).zip(&numbers) {
+        let level = heading.level - base + 1;
+        if level <= depth {
+            out.push_str("</li>\n");
+        }
+        while depth > level {
+            out.push_str("</ul>\n</li>\n");
+            depth -= 1;
+        }
+        while depth < level {
+            out.push_str("<ul>\n");
+            depth += 1;
+            if depth < level {
+                out.push_str("<li>");
+            }
+        }
+        let number = number
+            .as_ref()
+            .map(|n| format!("<span class=\"heading-number\">{n}</span> "))
+            .unwrap_or_default();
+        out.push_str(&format!(
+            "<li><a href=\"#{}\">{number}{}</a>",
+            escape_html(slug),
+            escape_html(&heading.text)
+        ));
+    }
+    while depth > 0 {
+        out.push_str("</li>\n</ul>\n");
+        depth -= 1;
+    }
+    out.push_str("</nav>\n");
+    out
+}
+
+/// エクスポートする文書の配色
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorScheme {
+    /// ブラウザの設定に従ってライトとダークを切り替える
+    Auto,
+    Light,
+    Dark,
+}
+
+/// 単一の文書のエクスポートの設定
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    /// 未指定なら "light"
+    pub theme: Option<String>,
+    /// `color_scheme` が "auto" か "dark" のときのダークテーマ（未指定なら "dark"）
+    pub dark_theme: Option<String>,
+    /// 未指定なら `theme` だけを使う
+    pub color_scheme: Option<ColorScheme>,
+    /// `.mdvim/templates` のテンプレート名か HTML ファイルのパス
+    pub template: Option<String>,
+    /// 本文の先頭に目次を入れる
+    pub include_toc: bool,
+    /// フロントマターの作成者と日付を本文の先頭に入れる
+    pub include_metadata: bool,
+    /// 本文の末尾に入れるフッター（Markdown）
+    pub footer: Option<String>,
+    /// タスクのチェックボックスを操作できるようにし、状態を保存するスクリプトを埋め込む
+    pub interactive_tasks: bool,
+    /// 見出しと目次に番号を振る
+    pub heading_numbering: Option<HeadingNumbering>,
+    /// 未解決の注釈の番号と一覧を入れる
+    pub annotations: bool,
+}
+
+/// タスクの状態（`<出力>.tasks.json` に書き出し、`apply_task_state` で文書に戻す）
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct TaskState {
+    pub source: String,
+    pub tasks: Vec<TaskEntry>,
+}
+
+/// タスクの 1 項目（`data-task` の順）
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TaskEntry {
+    /// 元の文書の行（埋め込んだファイルや前処理で加わったタスクは `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub text: String,
+    pub checked: bool,
+    /// 期日（`YYYY-MM-DD`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+}
+
+/// チェックボックスの状態をブラウザに保存し、JSON としてダウンロードできるようにする
+const TASK_SCRIPT: &str = r#"(function () {
+  var data = document.getElementById('mdvim-tasks');
+  var state = JSON.parse(data.textContent);
+  var key = 'mdvim-tasks:' + location.pathname;
+  try {
+    var saved = JSON.parse(localStorage.getItem(key));
+    if (saved && saved.tasks && saved.tasks.length === state.tasks.length) state = saved;
+  } catch (e) {}
+  document.querySelectorAll('input.task-checkbox').forEach(function (box) {
+    var task = state.tasks[box.dataset.task];
+    if (!task) return;
+    box.checked = task.checked;
+    box.addEventListener('change', function () {
+      task.checked = box.checked;
+      localStorage.setItem(key, JSON.stringify(state));
+    });
+  });
+  var button = document.getElementById('mdvim-tasks-download');
+  button.addEventListener('click', function () {
+    var blob = new Blob([JSON.stringify(state, null, 2)], { type: 'application/json' });
+    var link = document.createElement('a');
+    link.href = URL.createObjectURL(blob);
+    link.download = button.dataset.file;
+    link.click();
+  });
+})();
+"#;
+
+/// タスクの状態の JSON と保存用のスクリプト
+fn task_script_html(state: &TaskState, file_name: &str) -> Result<String, String> {
+    let json = serde_json::to_string(state).map_err(|e| tr!("Invalid task state: {e}"))?;
+    Ok(format!(
+        "<p class=\"task-state\"><button type=\"button\" id=\"mdvim-tasks-download\" data-file=\"{}\">{}</button></p>\n<script type=\"application/json\" id=\"mdvim-tasks\">{}</script>\n<script>\n{TASK_SCRIPT}</script>\n",
+        escape_html(file_name),
+        escape_html(&tr!("Download task state")),
+        json.replace("</", "<\\/")
+    ))
+}
+
+/// 前処理した文書 `expanded` の各行（0 始まり）に対応する元の文書 `source` の行（1 始まり）
+///
+/// 埋め込みや前処理で加わった・書き換わった行は `None`。
+fn source_lines(source: &str, expanded: &str) -> Vec<Option<usize>> {
+    let old: Vec<&str> = source.lines().collect();
+    let new: Vec<&str> = expanded.lines().collect();
+    let mut lines = vec![None; new.len()];
+    let (mut i, mut j) = (0, 0);
+    let end = diff::Hunk {
+        old: old.len()..old.len(),
+        new: new.len()..new.len(),
+    };
+    for hunk in diff::diff_lines(&old, &new).into_iter().chain([end]) {
+        while j < hunk.new.start {
+            lines[j] = Some(i + 1);
+            i += 1;
+            j += 1;
+        }
+        (i, j) = (hunk.old.end, hunk.new.end);
+    }
+    lines
+}
+
+/// フロントマターの作成者と日付（どちらもなければ空）
+fn metadata_html(fields: &[(String, String)]) -> String {
+    let field = |key: &str| {
+        fields
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+            .filter(|v| !v.is_empty())
+    };
+    let mut items = Vec::new();
+    if let Some(author) = field("author").or_else(|| field("authors")) {
+        items.push(format!(
+            "<span class=\"author\">{}</span>",
+            escape_html(author)
+        ));
+    }
+    if let Some(date) = field("date") {
+        items.push(format!("<time class=\"date\">{}</time>", escape_html(date)));
+    }
+    if items.is_empty() {
+        return String::new();
+    }
+    format!("<div class=\"metadata\">{}</div>\n", items.join("\n"))
+}
+
+/// 開いている文書を HTML に書き出す
+///
+/// テンプレートでは `{{title}}`・`{{content}}`・`{{toc}}`・`{{metadata}}`・`{{footer}}`・
+/// `{{date}}`・`{{css}}` とフロントマターのキー（`{{author}}` など）が使える。
+#[tauri::command(async)]
+pub fn export_html(
+    state: State<'_, AppState>,
+    path: String,
+    content: String,
+    out_path: String,
+    options: Option<ExportOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let source = vault::normalize(Path::new(&path));
+    let out = vault::normalize(Path::new(&out_path));
+    let title = document_title(&source, &content);
+    let fields = obsidian::front_matter_fields(&content);
+    let profile = state.profiles.resolve(&content, Some(&source));
+    let trusted = state.trust.is_trusted(&source);
+    let annotations = options
+        .annotations
+        .then(|| Rendering::prepare(&content, &annotations::located(&source, &content)))
+        .flatten();
+    // 注釈の番号の印は描画する本文にだけ埋める（目次やタスクには含めない）
+    let marked = match &annotations {
+        Some(annotations) => Some(preprocess(
+            &annotations.content,
+            Some(&source),
+            None,
+            &state.preprocess.get(),
+            trusted,
+        )?),
+        None => None,
+    };
+    let original = content;
+    let content = preprocess(
+        &original,
+        Some(&source),
+        None,
+        &state.preprocess.get(),
+        trusted,
+    )?;
+
+    // 別のフォルダに書き出す場合は、画像などの相対パスを出力先から見たものにする
+    let source_dir = source.parent().unwrap_or(Path::new(""));
+    let out_dir = out.parent().unwrap_or(Path::new(""));
+    let rewrite = |dest: &str, wiki: bool| {
+        if wiki || links::is_external(dest) || dest.starts_with(['#', '/']) || source_dir == out_dir
+        {
+            return None;
+        }
+        let target = links::percent_decode(dest.split_once('#').map_or(dest, |(t, _)| t));
+        let asset = source_dir.join(&target);
+        (!vault::is_markdown(&asset))
+            .then(|| links::percent_encode(&vault::relative_path(out_dir, &asset)))
+    };
+    let toc = toc_html(&content, options.heading_numbering.as_ref());
+    let metadata = metadata_html(&fields);
+    let footer = match options.footer.as_deref().filter(|f| !f.trim().is_empty()) {
+        Some(footer) => format!(
+            "<footer class=\"document-footer\">\n{}</footer>\n",
+            render::render_html(footer, None)
+        ),
+        None => String::new(),
+    };
+    let mut body = String::new();
+    if options.include_metadata {
+        body.push_str(&metadata);
+    }
+    if options.include_toc {
+        body.push_str(&toc);
+    }
+    let rendered = render::render_html_with(
+        marked.as_deref().unwrap_or(&content),
+        &render::RenderOptions {
+            rewrite_link: Some(&rewrite),
+            interactive_tasks: options.interactive_tasks,
+            heading_numbering: options.heading_numbering.as_ref(),
+            diagrams: trusted.then_some(&state.diagrams),
+            base_dir: Some(source_dir),
+            data_blocks: true,
+            profile: Some(&profile),
+            read_files: None,
+        },
+    );
+    match &annotations {
+        Some(annotations) => {
+            body.push_str(&annotations.replace_markers(&rendered));
+            body.push_str(&annotations.list);
+        }
+        None => body.push_str(&rendered),
+    }
+    // チェックボックスは前処理した文書の順に並ぶので、その順のまま元の文書の行を付ける
+    let lines = source_lines(&original, &content);
+    let tasks = markdown::tasks(&content);
+    let state_path = out.with_extension("tasks.json");
+    let task_state = TaskState {
+        source: path_string(&source),
+        tasks: tasks
+            .into_iter()
+            .map(|t| TaskEntry {
+                line: lines.get(t.line - 1).copied().flatten(),
+                text: t.text,
+                checked: t.checked,
+                due: t.due.map(|d| d.format("%Y-%m-%d").to_string()),
+            })
+            .collect(),
+    };
+    let interactive = options.interactive_tasks && !task_state.tasks.is_empty();
+    if interactive {
+        let file_name = state_path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        body.push_str(&task_script_html(&task_state, &file_name)?);
+    }
+    body.push_str(&footer);
+    let light = options.theme.as_deref().unwrap_or("light");
+    let dark = options.dark_theme.as_deref().unwrap_or("dark");
+    let css = match options.color_scheme {
+        None | Some(ColorScheme::Light) => theme_css(light),
+        Some(ColorScheme::Dark) => theme_css(dark),
+        Some(ColorScheme::Auto) => dual_theme_css(light, dark),
+    };
+    let css = if profile.github_style {
+        format!("{css}{GITHUB_CSS}")
+    } else {
+        css
+    };
+    let css = if annotations.is_some() {
+        format!("{css}{ANNOTATION_CSS}")
+    } else {
+        css
+    };
+
+    let html = match options.template.as_deref().filter(|t| !t.trim().is_empty()) {
+        Some(name) => {
+            let template = templates::load(&source, name)?;
+            let mut values: HashMap<String, String> = fields
+                .into_iter()
+                .map(|(key, value)| (key, escape_html(&value)))
+                .collect();
+            values.insert("title".to_string(), escape_html(&title));
+            values.insert("content".to_string(), body);
+            values.insert("toc".to_string(), toc);
+            values.insert("metadata".to_string(), metadata);
+            values.insert("footer".to_string(), footer);
+            values.insert(
+                "date".to_string(),
+                chrono::Local::now().format("%Y-%m-%d").to_string(),
+            );
+            values.insert("css".to_string(), css);
+            templates::fill(&template, &values)
+        }
+        None => html_document_with_css(&title, &body, &css),
+    };
+    write_file(&out, &html)?;
+    if interactive {
+        let json = serde_json::to_string_pretty(&task_state)
+            .map_err(|e| tr!("Invalid task state: {e}"))?;
+        write_file(&state_path, &json)?;
+    }
+    Ok(path_string(&out))
+}
+
+/// エクスポートした文書で変更したタスクの状態（`tasks.json`）を文書に反映した内容を返す
+///
+/// 行番号とテキストが一致する項目を優先し、行がずれていればテキストで照合する。
+/// 埋め込んだファイルのタスク（行のない項目）は反映しない。
+#[tauri::command]
+pub fn apply_task_state(content: String, state: String) -> Result<String, String> {
+    let state: TaskState =
+        serde_json::from_str(&state).map_err(|e| tr!("Invalid task state: {e}"))?;
+    let tasks = markdown::tasks(&content);
+    let mut used = vec![false; tasks.len()];
+    let mut edits = Vec::new();
+    for entry in &state.tasks {
+        let Some(line) = entry.line else {
+            continue;
+        };
+        let found = tasks
+            .iter()
+            .position(|t| t.line == line && t.text == entry.text)
+            .filter(|&i| !used[i])
+            .or_else(|| {
+                tasks
+                    .iter()
+                    .enumerate()
+                    .position(|(i, t)| !used[i] && t.text == entry.text)
+            });
+        let Some(i) = found else {
+            continue;
+        };
+        used[i] = true;
+        if tasks[i].checked != entry.checked {
+            let mark = if entry.checked { "[x]" } else { "[ ]" };
+            edits.push((tasks[i].marker.clone(), mark.to_string()));
+        }
+    }
+    Ok(text::apply_edits(&content, edits))
+}
+
+/// HTML から PDF への変換に使うコマンド（見つかったものを使う）
+const PDF_CONVERTERS: &[&str] = &[
+    "wkhtmltopdf",
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "msedge",
+];
+
+/// PDF への変換を待つ時間（1 ファイル）
+const PDF_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 取り込むエラー出力の上限
+const MAX_STDERR: usize = 64 << 10;
+
+/// ローカルファイルの `file://` URL
+pub fn file_url(path: &Path) -> String {
+    let path = links::percent_encode(&vault::to_slash(path));
+    if path.starts_with('/') {
+        format!("file://{path}")
+    } else {
+        format!("file:///{path}")
+    }
+}
+
+/// HTML ファイルを PDF に変換（wkhtmltopdf またはヘッドレス Chrome を利用）
+pub fn html_to_pdf(html: &Path, pdf: &Path) -> Result<(), String> {
+    for &converter in PDF_CONVERTERS {
+        let mut command = Command::new(converter);
+        if converter.ends_with("wkhtmltopdf") {
+            command
+                .args(["--quiet", "--enable-local-file-access"])
+                .arg(html)
+                .arg(pdf);
+        } else {
+            command
+                .args(["--headless", "--disable-gpu", "--no-pdf-header-footer"])
+                .arg(format!("--print-to-pdf={}", pdf.display()))
+                .arg(file_url(html));
+        }
+        let options = RunOptions {
+            timeout: PDF_TIMEOUT,
+            max_stdout: MAX_STDERR,
+            max_stderr: MAX_STDERR,
+            ..Default::default()
+        };
+        let Some(output) = shell::run_if_found(command, converter, options)? else {
+            continue;
+        };
+        return match output.status {
+            Some(status) if status.success() => Ok(()),
+            Some(_) => Err(tr!(
+                "PDF conversion failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+            None => Err(tr!(
+                "{} timed out after {} s",
+                converter,
+                PDF_TIMEOUT.as_secs()
+            )),
+        };
+    }
+    Err(tr!(
+        "No PDF converter found. Install wkhtmltopdf or Chrome/Chromium."
+    ))
+}
+
+/// ノートを PDF に書き出す（画像は元の場所を `file://` で参照する）
+fn export_pdf(vault: &Vault, site: &Site, source: &Path) -> Result<(), String> {
+    let content =
+        fs::read_to_string(source).map_err(|e| tr!("Failed to read {}: {e}", source.display()))?;
+    let title = document_title(source, &content);
+    let content = preprocess(
+        &content,
+        Some(source),
+        Some(vault),
+        &site.preprocessors,
+        site.trusted,
+    )?;
+    let rewrite = |dest: &str, wiki: bool| {
+        if !wiki && links::is_external(dest) {
+            return None;
+        }
+        let target = dest.split_once('#').map_or(dest, |(target, _)| target);
+        let path = vault.resolve_target(source, target, wiki)?;
+        (!target.is_empty() && !vault::is_markdown(&path)).then(|| file_url(&path))
+    };
+    let body = render::render_html(&content, Some(&rewrite));
+    let html = html_document(&title, &body, &site.theme);
+
+    let pdf = output_path(vault, &site.out_dir, source).with_extension("pdf");
+    let temp = std::env::temp_dir().join(format!("mdvim-export-{}.html", std::process::id()));
+    write_file(&temp, &html)?;
+    if let Some(parent) = pdf.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| tr!("Failed to create {}: {e}", parent.display()))?;
+    }
+    let result = html_to_pdf(&temp, &pdf);
+    let _ = fs::remove_file(&temp);
+    result
+}
+
+/// 監視ビルドの出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildFormat {
+    Html,
+    Pdf,
+}
+
+/// ビルドの状態
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuildPhase {
+    Building,
+    Success,
+    Error,
+}
+
+/// `build-status` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildStatus {
+    pub status: BuildPhase,
+    /// 変更のあったファイル
+    pub changed: Vec<String>,
+    /// 書き出したファイルの数
+    pub outputs: usize,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// 監視中のビルド（送信側を破棄すると監視スレッドが終了する）
+#[derive(Default)]
+pub struct WatchExportState {
+    stop: Mutex<Option<Sender<()>>>,
+}
+
+/// 変更のあったファイルだけを書き出し直す
+struct Builder {
+    root: PathBuf,
+    format: BuildFormat,
+    site: Site,
+}
+
+impl Builder {
+    fn build(&mut self, changes: &Changes) -> Result<usize, String> {
+        let vault = Vault::scan(&self.root);
+        match self.format {
+            BuildFormat::Html => self.build_html(&vault, changes),
+            BuildFormat::Pdf => self.build_pdf(&vault, changes),
+        }
+    }
+
+    fn build_html(&mut self, vault: &Vault, changes: &Changes) -> Result<usize, String> {
+        // ノートの追加・削除はウィキリンクの解決先が変わるため全ページを更新する
+        let structure_changed = changes.removed.iter().any(|p| vault::is_markdown(p))
+            || changes
+                .modified
+                .iter()
+                .any(|p| vault::is_markdown(p) && !self.site.titles.contains_key(p));
+        let mut targets: Vec<PathBuf> = changes.modified.clone();
+        if structure_changed {
+            targets.retain(|p| !vault::is_markdown(p));
+            targets.extend(
+                self.site
+                    .sources(vault)
+                    .filter(|p| vault::is_markdown(p))
+                    .cloned(),
+            );
+        }
+
+        for removed in &changes.removed {
+            self.site.remove_file(vault, removed);
+        }
+        for source in &targets {
+            self.site.export_file(vault, source)?;
+        }
+        self.site.write_index(vault)?;
+        Ok(targets.len() + 1)
+    }
+
+    fn build_pdf(&mut self, vault: &Vault, changes: &Changes) -> Result<usize, String> {
+        let out_dir = &self.site.out_dir;
+        for removed in changes.removed.iter().filter(|p| vault::is_markdown(p)) {
+            let _ = fs::remove_file(output_path(vault, out_dir, removed).with_extension("pdf"));
+        }
+        let notes: Vec<&PathBuf> = changes
+            .modified
+            .iter()
+            .filter(|p| vault::is_markdown(p))
+            .collect();
+        for source in &notes {
+            export_pdf(vault, &self.site, source)?;
+        }
+        Ok(notes.len())
+    }
+}
+
+fn emit_build_status(app: &AppHandle, status: BuildStatus) {
+    let _ = app.emit("build-status", status);
+}
+
+/// ワークスペースを書き出し、以後ファイルが変わるたびに書き出し直す（状態は `build-status` イベントで通知）
+#[tauri::command]
+pub fn watch_and_export(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    root: String,
+    format: BuildFormat,
+    out_dir: String,
+    theme: Option<String>,
+) -> Result<(), String> {
+    let root = vault::normalize(Path::new(&root));
+    if !root.is_dir() {
+        return Err(tr!("Folder not found: {}", root.display()));
+    }
+    let mut stop = state.watch_export.stop.lock().unwrap();
+    stop.take();
+
+    let (sender, receiver) = mpsc::channel::<()>();
+    let mut builder = Builder {
+        root: root.clone(),
+        format,
+        site: Site::new(
+            Path::new(&out_dir),
+            theme.as_deref().unwrap_or("light"),
+            state.preprocess.get(),
+            state.trust.is_trusted(&root),
+        ),
+    };
+    thread::spawn(move || {
+        let out_dir = builder.site.out_dir.clone();
+        let initial: Vec<PathBuf> = builder.site.sources(&Vault::scan(&root)).cloned().collect();
+        let mut run = |changes: Changes| {
+            let changed: Vec<String> = changes
+                .modified
+                .iter()
+                .chain(&changes.removed)
+                .map(|p| path_string(p))
+                .collect();
+            emit_build_status(
+                &app,
+                BuildStatus {
+                    status: BuildPhase::Building,
+                    changed: changed.clone(),
+                    outputs: 0,
+                    error: None,
+                    duration_ms: 0,
+                },
+            );
+            let started = Instant::now();
+            let result = builder.build(&changes);
+            let duration_ms = started.elapsed().as_millis() as u64;
+            emit_build_status(
+                &app,
+                match result {
+                    Ok(outputs) => BuildStatus {
+                        status: BuildPhase::Success,
+                        changed,
+                        outputs,
+                        error: None,
+                        duration_ms,
+                    },
+                    Err(e) => BuildStatus {
+                        status: BuildPhase::Error,
+                        changed,
+                        outputs: 0,
+                        error: Some(e),
+                        duration_ms,
+                    },
+                },
+            );
+        };
+
+        // 最初に全体を書き出す
+        run(Changes {
+            modified: initial,
+            removed: Vec::new(),
+        });
+        watcher::watch(
+            &root,
+            Some(&out_dir),
+            Duration::from_millis(500),
+            &receiver,
+            run,
+        );
+    });
+    *stop = Some(sender);
+    Ok(())
+}
+
+/// 監視ビルドを停止
+#[tauri::command]
+pub fn stop_watch_export(state: State<'_, AppState>) {
+    state.watch_export.stop.lock().unwrap().take();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_lines_map_back_to_the_source() {
+        let source = "# Plan\n\n{{include \"a.md\"}}\n\n- [ ] first\n- [x] second\n";
+        let expanded = "# Plan\n\n- [ ] first\n\n- [ ] first\n- [x] second\n";
+        let lines = source_lines(source, expanded);
+        let tasks: Vec<Option<usize>> = markdown::tasks(expanded)
+            .iter()
+            .map(|t| lines[t.line - 1])
+            .collect();
+        assert_eq!(tasks, [None, Some(5), Some(6)]);
+
+        // 埋め込んだタスクを変えても、同じテキストの元の文書のタスクは変えない
+        let entry = |line, text: &str, checked| TaskEntry {
+            line,
+            text: text.to_string(),
+            checked,
+            due: None,
+        };
+        let state = TaskState {
+            source: String::new(),
+            tasks: vec![
+                entry(None, "first", true),
+                entry(Some(5), "first", false),
+                entry(Some(6), "second", false),
+            ],
+        };
+        let state = serde_json::to_string(&state).unwrap();
+        let applied = apply_task_state(source.to_string(), state).unwrap();
+        assert_eq!(applied, source.replace("[x] second", "[ ] second"));
+    }
+}
@@ -0,0 +1,346 @@
+// 名前付きのエクスポートの設定（「ブログ用」「取引先向け PDF」など。設定フォルダの `export-profiles.json`）
+//
+// 出力先のフォルダは `{{dir}}` などを置き換えたパターンで指定し、書き出し後のコマンドは
+// エクスポート後のフックと同じ変数で実行する。
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Local;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::app_data;
+use crate::docx::{self, DocxOptions};
+use crate::export::{self, ExportOptions};
+use crate::hooks::{self, Hook, HookEvent, HookResult};
+use crate::links;
+use crate::output_template::{self, Collision};
+use crate::preview_server;
+use crate::state::AppState;
+use crate::templates;
+use crate::tr;
+use crate::vault::{self, path_string};
+
+/// 設定フォルダ内のファイル
+const EXPORT_PROFILES_FILE: &str = "export-profiles.json";
+
+/// 埋め込む画像の大きさの上限（それより大きいものは参照のまま）
+const MAX_INLINE_IMAGE: u64 = 20 << 20;
+
+/// `<img src="...">` の `src`
+static IMAGE_SRC: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(<img\b[^>]*?\bsrc=")([^"]+)(")"#).unwrap());
+
+/// 書き出す形式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Html,
+    Pdf,
+    Docx,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Pdf => "pdf",
+            Self::Docx => "docx",
+        }
+    }
+}
+
+/// エクスポートの設定
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ExportProfile {
+    pub name: String,
+    pub format: ExportFormat,
+    /// 未指定なら "light"
+    pub theme: Option<String>,
+    /// `.mdvim/templates` のテンプレート名か HTML ファイルのパス（HTML と PDF）
+    pub template: Option<String>,
+    pub include_toc: bool,
+    pub include_metadata: bool,
+    /// ローカルの画像を data URI で HTML に埋め込む
+    pub self_contained: bool,
+    /// 出力先のフォルダ（`{{dir}}` `{{stem}}` `{{date}}` `{{home}}`、先頭の `~` が使える。
+    /// 空なら文書と同じフォルダ）
+    pub out_dir: String,
+    /// 書き出し先のファイル名のテンプレート（例: `{{slug}}-{{date}}.html`。相対パスは `out_dir` から。
+    /// 空なら `<ファイル名>.<拡張子>`）
+    pub output: Option<String>,
+    /// 書き出し先がすでにあるときの扱い
+    pub collision: Collision,
+    /// 書き出した後に実行するコマンド（フックと同じく `{output}` `{file}` などが使える）
+    pub post_export: Option<String>,
+}
+
+/// `run_export_profile` の結果
+#[derive(Debug, Serialize)]
+pub struct ProfileExport {
+    pub profile: String,
+    pub output: String,
+    /// 書き出し後のコマンドの結果
+    pub hook: Option<HookResult>,
+}
+
+fn load(app: &AppHandle) -> Result<Vec<ExportProfile>, String> {
+    app_data::read_json(&app_data::config_file(app, EXPORT_PROFILES_FILE)?)
+}
+
+fn store(app: &AppHandle, profiles: &[ExportProfile]) -> Result<(), String> {
+    app_data::write_json(&app_data::config_file(app, EXPORT_PROFILES_FILE)?, profiles)
+}
+
+/// 名前の設定
+pub fn find(app: &AppHandle, name: &str) -> Result<ExportProfile, String> {
+    load(app)?
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| tr!("Export profile not found: {}", name))
+}
+
+/// 出力先のフォルダのパターンを文書に合わせて展開する
+pub fn out_dir(app: &AppHandle, pattern: &str, source: &Path) -> PathBuf {
+    let dir = source.parent().unwrap_or(Path::new(""));
+    if pattern.trim().is_empty() {
+        return dir.to_path_buf();
+    }
+    let home = app
+        .path()
+        .home_dir()
+        .map(|h| path_string(&h))
+        .unwrap_or_default();
+    let values = [
+        ("dir", path_string(dir)),
+        (
+            "stem",
+            source
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        ),
+        ("date", Local::now().format("%Y-%m-%d").to_string()),
+        ("home", home.clone()),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v))
+    .collect();
+    let expanded = templates::fill(pattern.trim(), &values);
+    let expanded = match expanded.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => format!("{home}{rest}"),
+        _ => expanded,
+    };
+    // 相対パスは文書のフォルダから
+    vault::normalize(&dir.join(expanded))
+}
+
+/// 書き出し先のファイル（`output` のテンプレートがあればそれ、なければ `<ファイル名>.<拡張子>`）
+///
+/// 相対パスは `base`（省略時は `out_dir` のパターン）から。重なりはまだ扱わない。
+pub fn output_path(
+    app: &AppHandle,
+    profile: &ExportProfile,
+    source: &Path,
+    content: &str,
+    root: Option<&Path>,
+    base: Option<&Path>,
+) -> PathBuf {
+    let base = base
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| out_dir(app, &profile.out_dir, source));
+    let extension = profile.format.extension();
+    match profile.output.as_deref().filter(|t| !t.trim().is_empty()) {
+        Some(template) => {
+            let values = output_template::variables(app, source, content, root, extension);
+            output_template::expand(template, &values, &base)
+        }
+        None => {
+            let stem = source
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            base.join(format!("{stem}.{extension}"))
+        }
+    }
+}
+
+/// 出力先のローカルの画像を data URI にして HTML に埋め込む
+fn inline_images(html: &str, base_dir: &Path) -> String {
+    IMAGE_SRC
+        .replace_all(html, |caps: &Captures| {
+            let src = &caps[2];
+            let file = src.split(['?', '#']).next().unwrap_or_default();
+            let inlined = (!links::is_external(src) && !src.starts_with(['#', '/']))
+                .then(|| base_dir.join(links::percent_decode(file)))
+                .filter(|path| {
+                    fs::metadata(path).is_ok_and(|m| m.is_file() && m.len() <= MAX_INLINE_IMAGE)
+                })
+                .and_then(|path| {
+                    let data = fs::read(&path).ok()?;
+                    let mime = preview_server::content_type(&path);
+                    Some(format!("data:{mime};base64,{}", STANDARD.encode(data)))
+                });
+            format!(
+                "{}{}{}",
+                &caps[1],
+                inlined.as_deref().unwrap_or(src),
+                &caps[3]
+            )
+        })
+        .into_owned()
+}
+
+/// 設定に従って文書を `out` に書き出す
+pub fn export_with(
+    state: State<'_, AppState>,
+    profile: &ExportProfile,
+    source: &Path,
+    content: String,
+    out: &Path,
+) -> Result<(), String> {
+    let options = ExportOptions {
+        theme: profile.theme.clone(),
+        template: profile.template.clone(),
+        include_toc: profile.include_toc,
+        include_metadata: profile.include_metadata,
+        ..Default::default()
+    };
+    let path = path_string(source);
+    match profile.format {
+        ExportFormat::Html => {
+            export::export_html(state, path, content, path_string(out), Some(options))?;
+            if profile.self_contained {
+                let html = fs::read_to_string(out)
+                    .map_err(|e| tr!("Failed to read {}: {e}", out.display()))?;
+                let html = inline_images(&html, out.parent().unwrap_or(Path::new("")));
+                fs::write(out, html).map_err(|e| tr!("Failed to write {}: {e}", out.display()))?;
+            }
+        }
+        ExportFormat::Pdf => {
+            // 画像の相対パスが変わらないよう、一時的な HTML は出力先と同じフォルダに置く
+            let stem = out
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let temp =
+                out.with_file_name(format!(".{stem}.mdvim-export-{}.html", std::process::id()));
+            let result =
+                export::export_html(state, path, content, path_string(&temp), Some(options))
+                    .and_then(|_| export::html_to_pdf(&temp, out));
+            let _ = fs::remove_file(&temp);
+            let _ = fs::remove_file(temp.with_extension("tasks.json"));
+            result?;
+        }
+        ExportFormat::Docx => {
+            docx::export_docx(
+                state,
+                path,
+                content,
+                path_string(out),
+                Some(DocxOptions::default()),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// 書き出し後のコマンドを実行する（信頼したワークスペースでだけ）
+pub fn run_post_export(
+    app: &AppHandle,
+    state: &AppState,
+    profile: &ExportProfile,
+    source: &Path,
+    out: &Path,
+) -> Result<Option<HookResult>, String> {
+    let Some(command) = profile
+        .post_export
+        .as_deref()
+        .filter(|c| !c.trim().is_empty())
+    else {
+        return Ok(None);
+    };
+    state.trust.require(app, source, "hooks")?;
+    let hook = Hook {
+        event: HookEvent::PostExport,
+        command: command.to_string(),
+        extensions: Vec::new(),
+        timeout_secs: None,
+        cwd: None,
+    };
+    let vars = hooks::variables(Some(source), Some(&path_string(out)), None);
+    Ok(Some(hooks::run_hook(&hook, &vars, source.parent())))
+}
+
+/// エクスポートの設定の一覧
+#[tauri::command]
+pub fn list_export_profiles(app: AppHandle) -> Result<Vec<ExportProfile>, String> {
+    load(&app)
+}
+
+/// エクスポートの設定を保存する（同じ名前があれば置き換える）
+#[tauri::command]
+pub fn save_export_profile(
+    app: AppHandle,
+    profile: ExportProfile,
+) -> Result<Vec<ExportProfile>, String> {
+    let mut profile = profile;
+    profile.name = profile.name.trim().to_string();
+    if profile.name.is_empty() {
+        return Err(tr!("Export profile name is empty"));
+    }
+    let mut profiles = load(&app)?;
+    match profiles.iter_mut().find(|p| p.name == profile.name) {
+        Some(existing) => *existing = profile,
+        None => profiles.push(profile),
+    }
+    store(&app, &profiles)?;
+    Ok(profiles)
+}
+
+/// エクスポートの設定を削除する
+#[tauri::command]
+pub fn delete_export_profile(app: AppHandle, name: String) -> Result<Vec<ExportProfile>, String> {
+    let mut profiles = load(&app)?;
+    let before = profiles.len();
+    profiles.retain(|p| p.name != name);
+    if profiles.len() == before {
+        return Err(tr!("Export profile not found: {}", name));
+    }
+    store(&app, &profiles)?;
+    Ok(profiles)
+}
+
+/// 保存したファイル `path` をエクスポートの設定 `name` で書き出し、書き出し後のコマンドを実行する
+#[tauri::command(async)]
+pub fn run_export_profile(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+    path: String,
+) -> Result<ProfileExport, String> {
+    let profile = find(&app, &name)?;
+    let source = vault::normalize(Path::new(&path));
+    let content =
+        fs::read_to_string(&source).map_err(|e| tr!("Failed to read {}: {e}", source.display()))?;
+    let out = output_template::resolve(
+        output_path(&app, &profile, &source, &content, None, None),
+        profile.collision,
+        &mut HashSet::new(),
+    )?;
+    export_with(state.clone(), &profile, &source, content, &out)?;
+    let hook = run_post_export(&app, &state, &profile, &source, &out)?;
+    Ok(ProfileExport {
+        profile: profile.name,
+        output: path_string(&out),
+        hook,
+    })
+}
@@ -0,0 +1,527 @@
+// ファイルの読み書き（改行コードの保持と、保存時の空白・末尾の改行の正規化）
+//
+// 開いているファイルは更新時刻を監視し、ほかのアプリで変更されたら `file-changed` を通知する。
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::app_data;
+use crate::formatter;
+use crate::markdown;
+use crate::state::AppState;
+use crate::tr;
+use crate::vault::{self, path_string};
+use crate::watcher::{self, Changes};
+use crate::FileInfo;
+
+/// 最近使ったファイルとして残す数
+const MAX_RECENT: usize = 20;
+
+/// データフォルダ内のセッションのファイル
+const SESSION_FILE: &str = "session.json";
+
+/// 開いているファイルの更新時刻を確かめる間隔
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 改行コード
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+
+    /// OS の標準の改行コード
+    pub fn native() -> Self {
+        if cfg!(windows) {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        }
+    }
+}
+
+/// 多い方の改行コード（改行がなければ `None`）
+pub fn detect_line_ending(content: &str) -> Option<LineEnding> {
+    let crlf = content.matches("\r\n").count();
+    let lf = content.matches('\n').count() - crlf;
+    match (crlf, lf) {
+        (0, 0) => None,
+        (crlf, lf) if crlf > lf => Some(LineEnding::Crlf),
+        _ => Some(LineEnding::Lf),
+    }
+}
+
+/// 改行コードをそろえる
+pub fn convert_line_endings(content: &str, ending: LineEnding) -> String {
+    let lf = content.replace("\r\n", "\n");
+    match ending {
+        LineEnding::Lf => lf,
+        LineEnding::Crlf => lf.replace('\n', "\r\n"),
+    }
+}
+
+/// 保存時の正規化の設定
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SaveNormalization {
+    pub enabled: bool,
+    /// 行末の空白を除く（ハードブレイクの 2 つの空白は残す）
+    pub trim_trailing_whitespace: bool,
+    /// 末尾の改行をちょうど 1 つにする
+    pub final_newline: bool,
+    /// 改行コードをそろえる（未指定なら変えない）
+    pub line_ending: Option<LineEnding>,
+}
+
+impl Default for SaveNormalization {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            trim_trailing_whitespace: true,
+            final_newline: true,
+            line_ending: None,
+        }
+    }
+}
+
+/// 開いているファイルと最近使ったファイル
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Session {
+    pub open: Vec<String>,
+    /// 新しい順
+    pub recent: Vec<String>,
+}
+
+impl Session {
+    fn touch(&mut self, path: &str) {
+        self.recent.retain(|p| p != path);
+        self.recent.insert(0, path.to_string());
+        self.recent.truncate(MAX_RECENT);
+    }
+}
+
+/// ファイルの保存の設定と開いているファイル
+#[derive(Default)]
+pub struct FileState {
+    normalization: Mutex<SaveNormalization>,
+    session: Mutex<Session>,
+    /// セッションの保存先（起動時に決まる）
+    session_file: Mutex<Option<PathBuf>>,
+    /// 監視している開いているファイルと、最後に確かめた更新時刻（`session` の後にロックする）
+    watched: Mutex<HashMap<String, Option<SystemTime>>>,
+}
+
+impl FileState {
+    /// セッションを保存する（失敗しても編集は続けられるので無視する）
+    fn store_session(&self) {
+        let file = self.session_file.lock().unwrap().clone();
+        if let Some(file) = file {
+            let _ = app_data::write_json(&file, &*self.session.lock().unwrap());
+        }
+    }
+}
+
+/// `file-changed` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct FileChanged {
+    pub path: String,
+    /// 削除されたか
+    pub removed: bool,
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// 監視しているファイルのうち、更新時刻が記録と変わったものを通知する（自分の保存は除く）
+fn notify_changed(app: &AppHandle, changes: &Changes) {
+    let state = app.state::<AppState>();
+    let changed: Vec<FileChanged> = state
+        .files
+        .watched
+        .lock()
+        .unwrap()
+        .iter_mut()
+        .filter(|(path, _)| {
+            let path = Path::new(path.as_str());
+            changes
+                .modified
+                .iter()
+                .chain(&changes.removed)
+                .any(|p| p == path)
+        })
+        .filter_map(|(path, known)| {
+            let current = modified_time(path);
+            (*known != current).then(|| {
+                *known = current;
+                FileChanged {
+                    path: path.clone(),
+                    removed: current.is_none(),
+                }
+            })
+        })
+        .collect();
+    for change in changed {
+        let _ = app.emit("file-changed", change);
+    }
+}
+
+/// 起動時に前回のセッションを読み込み、開いているファイルの監視を始める
+pub fn load_at_startup(app: &AppHandle) {
+    let app_handle = app.clone();
+    thread::spawn(move || {
+        // アプリの終了まで監視する
+        let (_keep, stop) = mpsc::channel::<()>();
+        let files = || {
+            let state = app_handle.state::<AppState>();
+            let watched = state.files.watched.lock().unwrap();
+            watched.keys().map(PathBuf::from).collect()
+        };
+        watcher::watch_files(files, WATCH_INTERVAL, &stop, |changes| {
+            notify_changed(&app_handle, &changes)
+        });
+    });
+    let state = app.state::<AppState>();
+    let Ok(file) = app_data::data_file(app, SESSION_FILE) else {
+        return;
+    };
+    if let Ok(session) = app_data::read_json(&file) {
+        *state.files.session.lock().unwrap() = session;
+    }
+    *state.files.session_file.lock().unwrap() = Some(file);
+}
+
+/// 名前を付けて保存の設定
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SaveAsOptions {
+    /// 既存のファイルを上書きする
+    pub overwrite: bool,
+    /// 保存する前のパス（開いているファイルを置き換える）
+    pub previous_path: Option<String>,
+    pub line_ending: Option<LineEnding>,
+}
+
+/// `file-saved-as` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedAs {
+    pub previous_path: Option<String>,
+    pub path: String,
+}
+
+/// 行末の空白を除く（コードブロックなどはそのまま）
+fn trim_trailing_whitespace(content: &str) -> String {
+    let verbatim = formatter::verbatim_ranges(content);
+    let mut out = String::with_capacity(content.len());
+    let mut offset = 0;
+    for raw in content.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        if markdown::in_ranges(&verbatim, start) {
+            out.push_str(raw);
+            continue;
+        }
+        let line = raw.trim_end_matches(['\n', '\r']);
+        let eol = &raw[line.len()..];
+        let trimmed = line.trim_end();
+        let spaces = &line[trimmed.len()..];
+        out.push_str(trimmed);
+        // 2 つ以上の空白で終わる行はハードブレイク
+        if !trimmed.is_empty() && spaces.starts_with("  ") && !spaces.contains('\t') {
+            out.push_str("  ");
+        }
+        out.push_str(eol);
+    }
+    out
+}
+
+/// 設定に従って保存する内容を正規化する
+pub fn normalize(content: &str, options: &SaveNormalization) -> String {
+    let mut content = content.to_string();
+    if options.trim_trailing_whitespace {
+        content = trim_trailing_whitespace(&content);
+    }
+    if let Some(ending) = options.line_ending {
+        content = convert_line_endings(&content, ending);
+    }
+    if options.final_newline {
+        let eol = match options.line_ending {
+            Some(ending) => ending.as_str(),
+            None if content.contains("\r\n") => "\r\n",
+            None => "\n",
+        };
+        let body = if options.trim_trailing_whitespace {
+            // 最後の行のハードブレイクは意味がないので空白ごと除く
+            content.trim_end_matches(['\n', '\r', ' ', '\t'])
+        } else {
+            content.trim_end_matches(['\n', '\r'])
+        };
+        content.truncate(body.len());
+        if !content.is_empty() {
+            content.push_str(eol);
+        }
+    }
+    content
+}
+
+/// 保存時の正規化を設定する
+#[tauri::command]
+pub fn configure_save_normalization(state: State<'_, AppState>, options: SaveNormalization) {
+    *state.files.normalization.lock().unwrap() = options;
+}
+
+/// 保存時の正規化の設定を取得する
+#[tauri::command]
+pub fn get_save_normalization(state: State<'_, AppState>) -> SaveNormalization {
+    state.files.normalization.lock().unwrap().clone()
+}
+
+/// 読み取り専用か（ユーザーが書き込めるか）
+fn permissions(path: &Path) -> (bool, bool) {
+    let Ok(metadata) = fs::metadata(path) else {
+        return (false, true);
+    };
+    let permissions = metadata.permissions();
+    #[cfg(unix)]
+    let owner_writable = {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.mode() & 0o200 != 0
+    };
+    #[cfg(not(unix))]
+    let owner_writable = !permissions.readonly();
+    (permissions.readonly(), owner_writable)
+}
+
+/// 書き込みのエラー（権限の問題は UI が判別できるようにする）
+fn write_error(path: &Path, e: std::io::Error) -> String {
+    if e.kind() == ErrorKind::PermissionDenied {
+        tr!("Permission denied: {} is not writable", path.display())
+    } else {
+        tr!("Failed to write {}: {e}", path.display())
+    }
+}
+
+fn file_info(path: String, content: String, line_ending: LineEnding) -> FileInfo {
+    let (read_only, owner_writable) = permissions(Path::new(&path));
+    FileInfo {
+        name: Path::new(&path)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        path,
+        content,
+        modified: false,
+        line_ending,
+        read_only,
+        owner_writable,
+    }
+}
+
+/// ファイルを読み込む（改行コードは多い方を `line_ending` に返す）
+#[tauri::command]
+pub fn read_file(state: State<'_, AppState>, path: String) -> Result<FileInfo, String> {
+    let file = Path::new(&path);
+    let content =
+        fs::read_to_string(file).map_err(|e| tr!("Failed to read {}: {e}", file.display()))?;
+    let line_ending = detect_line_ending(&content).unwrap_or_else(LineEnding::native);
+    let mut session = state.files.session.lock().unwrap();
+    if !session.open.contains(&path) {
+        session.open.push(path.clone());
+    }
+    session.touch(&path);
+    state
+        .files
+        .watched
+        .lock()
+        .unwrap()
+        .insert(path.clone(), modified_time(&path));
+    drop(session);
+    state.files.store_session();
+    Ok(file_info(path, content, line_ending))
+}
+
+fn save(
+    state: &AppState,
+    path: String,
+    content: String,
+    line_ending: Option<LineEnding>,
+) -> Result<FileInfo, String> {
+    let mut options = state.files.normalization.lock().unwrap().clone();
+    let file = Path::new(&path);
+    let existed = file.exists();
+    let ending = line_ending
+        .or(options.line_ending.filter(|_| options.enabled))
+        .or_else(|| {
+            // 既存のファイルの改行コードを保つ
+            fs::read_to_string(file)
+                .ok()
+                .and_then(|old| detect_line_ending(&old))
+        });
+    options.line_ending = ending;
+    let content = if options.enabled {
+        normalize(&content, &options)
+    } else if let Some(ending) = ending {
+        convert_line_endings(&content, ending)
+    } else {
+        content
+    };
+    // 自分で保存した変更は通知しない（書き込みと更新時刻の記録の間に確かめないようロックしておく）
+    let mut watched = state.files.watched.lock().unwrap();
+    fs::write(file, &content).map_err(|e| write_error(file, e))?;
+    if let Some(known) = watched.get_mut(&path) {
+        *known = modified_time(&path);
+    }
+    drop(watched);
+    // 埋め込み先のプレビューに反映する（新しいファイルは未解決だった埋め込みを解決しうる）
+    if existed {
+        state.parse_cache.invalidate(file);
+    } else {
+        state.parse_cache.clear();
+    }
+    let line_ending = ending
+        .or_else(|| detect_line_ending(&content))
+        .unwrap_or_else(LineEnding::native);
+    Ok(file_info(path, content, line_ending))
+}
+
+/// ファイルに書き込む（正規化が有効なら適用し、保存した内容を返す）
+///
+/// 改行コードは `line_ending`、正規化の設定、既存のファイルの改行コードの順に決める
+#[tauri::command]
+pub fn write_file(
+    state: State<'_, AppState>,
+    path: String,
+    content: String,
+    line_ending: Option<LineEnding>,
+) -> Result<FileInfo, String> {
+    save(&state, path, content, line_ending)
+}
+
+/// 名前を付けて保存する（拡張子がなければ `.md` を付け、フォルダがなければ作る）
+///
+/// 保存後に開いているファイル・最近使ったファイル・ファイルの監視をまとめて新しいパスに移し、
+/// `file-saved-as` を通知する
+#[tauri::command]
+pub fn save_as(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    content: String,
+    options: Option<SaveAsOptions>,
+) -> Result<FileInfo, String> {
+    let options = options.unwrap_or_default();
+    let mut file = PathBuf::from(path.trim());
+    if file.file_name().is_none() {
+        return Err(tr!("Invalid file name: {path}"));
+    }
+    if file.extension().is_none() {
+        file.set_extension("md");
+    }
+    let file = vault::normalize(&file);
+    if file.exists() && !options.overwrite {
+        return Err(tr!("File already exists: {}", file.display()));
+    }
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| tr!("Failed to create {}: {e}", parent.display()))?;
+    }
+    let info = save(&state, path_string(&file), content, options.line_ending)?;
+
+    let mut session = state.files.session.lock().unwrap();
+    let mut watched = state.files.watched.lock().unwrap();
+    let previous = options.previous_path.filter(|p| *p != info.path);
+    session.open.retain(|p| *p != info.path);
+    match previous
+        .as_ref()
+        .and_then(|p| session.open.iter().position(|o| o == p))
+    {
+        Some(i) => session.open[i] = info.path.clone(),
+        None => session.open.push(info.path.clone()),
+    }
+    session.touch(&info.path);
+    if let Some(previous) = &previous {
+        watched.remove(previous);
+    }
+    watched.insert(info.path.clone(), modified_time(&info.path));
+    drop(watched);
+    drop(session);
+    state.files.store_session();
+
+    let _ = app.emit(
+        "file-saved-as",
+        SavedAs {
+            previous_path: previous,
+            path: info.path.clone(),
+        },
+    );
+    Ok(info)
+}
+
+/// 開いているファイルと最近使ったファイル
+#[tauri::command]
+pub fn get_session(state: State<'_, AppState>) -> Session {
+    state.files.session.lock().unwrap().clone()
+}
+
+/// ファイルを閉じる（開いているファイルから除き、監視をやめる）
+#[tauri::command]
+pub fn close_file(state: State<'_, AppState>, path: String) {
+    let mut session = state.files.session.lock().unwrap();
+    session.open.retain(|p| *p != path);
+    state.files.watched.lock().unwrap().remove(&path);
+    drop(session);
+    state.files.store_session();
+}
+
+/// ファイルを書き込めるようにする（所有者の書き込み権限を付ける）
+#[tauri::command]
+pub fn make_writable(path: String) -> Result<(), String> {
+    let file = Path::new(&path);
+    let metadata = fs::metadata(file).map_err(|e| tr!("Failed to read {}: {e}", file.display()))?;
+    let mut permissions = metadata.permissions();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(permissions.mode() | 0o200);
+    }
+    #[cfg(not(unix))]
+    permissions.set_readonly(false);
+    fs::set_permissions(file, permissions).map_err(|e| write_error(file, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn final_newline_keeps_trailing_whitespace_unless_trimming() {
+        let mut options = SaveNormalization {
+            enabled: true,
+            trim_trailing_whitespace: false,
+            final_newline: true,
+            line_ending: None,
+        };
+        assert_eq!(normalize("a\nlast  \n\n\n", &options), "a\nlast  \n");
+        assert_eq!(normalize("a\r\nb\t", &options), "a\r\nb\t\r\n");
+        options.trim_trailing_whitespace = true;
+        assert_eq!(normalize("a  \nlast  \n\n", &options), "a  \nlast\n");
+    }
+}
@@ -0,0 +1,136 @@
+// 外部コマンドによるフィルタ（Vim の `:%!sort` `:'<,'>!jq .` と同じく、テキストを標準入力に渡して標準出力で置き換える）
+//
+// 任意のコマンドを実行するため、信頼したワークスペースでだけ使える（`trust`）。
+// 入力と出力の大きさに上限を設け、時間がかかりすぎたら止める。
+
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::hooks;
+use crate::shell::{self, RunOptions};
+use crate::state::AppState;
+use crate::tr;
+
+/// 既定のタイムアウト
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+/// 渡せる入力と受け取る出力の上限
+const MAX_INPUT: usize = 8 << 20;
+const MAX_OUTPUT: usize = 8 << 20;
+
+/// 標準エラー出力の上限（それ以降は読み捨てる）
+const MAX_STDERR: usize = 64 << 10;
+
+/// フィルタの設定
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FilterOptions {
+    pub timeout_secs: Option<u64>,
+    /// 作業フォルダ（省略時はワークスペースのフォルダ）
+    pub cwd: Option<String>,
+}
+
+/// `filter_through_command` の結果
+#[derive(Debug, Serialize)]
+pub struct FilterResult {
+    /// 標準出力（置き換える内容）
+    pub output: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+}
+
+/// テキストを外部コマンドに通し、標準出力を返す
+///
+/// `args` を省略すると `cmd` をシェルで実行する（`sort | uniq` のようにパイプも使える）。
+/// 渡せば `cmd` をプログラムとして `args` を付けて直接実行する。
+/// `workspace`（開いているファイルか保管庫）を信頼していなければエラーにする。
+/// 失敗した（終了コードが 0 でない）ときも内容を壊さないようエラーにする。
+#[tauri::command(async)]
+pub fn filter_through_command(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    text: String,
+    cmd: String,
+    args: Option<Vec<String>>,
+    workspace: Option<String>,
+    options: Option<FilterOptions>,
+) -> Result<FilterResult, String> {
+    let options = options.unwrap_or_default();
+    let workspace = workspace.ok_or_else(|| tr!("External filters require a trusted workspace"))?;
+    let workspace = Path::new(&workspace);
+    state.trust.require(&app, workspace, "external filters")?;
+    if text.len() > MAX_INPUT {
+        return Err(tr!(
+            "Input is too large for a filter ({} bytes, limit {})",
+            text.len(),
+            MAX_INPUT
+        ));
+    }
+    let cmd = cmd.trim();
+    if cmd.is_empty() {
+        return Err(tr!("No filter command"));
+    }
+
+    let mut command = match &args {
+        Some(args) => {
+            let mut command = Command::new(cmd);
+            command.args(args);
+            command
+        }
+        None => hooks::shell(cmd),
+    };
+    let cwd = match options.cwd.as_deref() {
+        Some(cwd) => Some(Path::new(cwd)),
+        None if workspace.is_dir() => Some(workspace),
+        None => workspace.parent(),
+    };
+    if let Some(cwd) = cwd.filter(|dir| dir.is_dir()) {
+        command.current_dir(cwd);
+    }
+    if workspace.is_file() {
+        command.env("MDVIM_FILE", workspace);
+    }
+    let timeout = Duration::from_secs(options.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    let result = shell::run(
+        command,
+        cmd,
+        RunOptions {
+            input: Some(text.into_bytes()),
+            timeout,
+            max_stdout: MAX_OUTPUT,
+            max_stderr: MAX_STDERR,
+            ..Default::default()
+        },
+    )?;
+    let stderr = String::from_utf8_lossy(&result.stderr).into_owned();
+    let Some(status) = result.status else {
+        return Err(tr!(
+            "Filter {} timed out after {} s",
+            cmd,
+            timeout.as_secs()
+        ));
+    };
+    if !status.success() {
+        let message = match stderr.trim() {
+            "" => status.to_string(),
+            message => message.to_string(),
+        };
+        return Err(tr!("Filter {cmd} failed: {message}"));
+    }
+    if result.stdout_truncated {
+        return Err(tr!("Filter output exceeded {} bytes", MAX_OUTPUT));
+    }
+    let output =
+        String::from_utf8(result.stdout).map_err(|_| tr!("Filter {cmd} returned invalid UTF-8"))?;
+    Ok(FilterResult {
+        output,
+        stderr,
+        exit_code: status.code(),
+        duration_ms: result.duration.as_millis() as u64,
+    })
+}
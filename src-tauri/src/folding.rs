@@ -0,0 +1,71 @@
+// 折りたたみ範囲（見出しのセクション・リスト・コードブロック・フロントマター）
+
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Parser, Tag};
+use serde::Serialize;
+
+use crate::markdown;
+use crate::text::LineIndex;
+
+/// 折りたたみ範囲（行番号は 1 始まりで、終了行を含む）
+#[derive(Debug, Serialize)]
+pub struct FoldingRange {
+    pub start_line: usize,
+    pub end_line: usize,
+    /// "section" | "list" | "code" | "quote" | "front_matter"
+    pub kind: String,
+}
+
+/// バイト範囲の行（末尾の空行は含めない）
+fn lines(content: &str, index: &LineIndex, range: Range<usize>) -> (usize, usize) {
+    let text = content[range.clone()].trim_end();
+    let end = range.start + text.len().max(1) - 1;
+    (index.line_of(range.start), index.line_of(end))
+}
+
+/// 文書の折りたたみ範囲（開始行の順）
+#[tauri::command]
+pub fn get_folding_ranges(content: String) -> Vec<FoldingRange> {
+    let index = LineIndex::new(&content);
+    let mut ranges = Vec::new();
+    let mut push = |range: Range<usize>, kind: &str| {
+        if range.is_empty() {
+            return;
+        }
+        let (start_line, end_line) = lines(&content, &index, range);
+        if end_line > start_line {
+            ranges.push(FoldingRange {
+                start_line,
+                end_line,
+                kind: kind.to_string(),
+            });
+        }
+    };
+
+    // セクションは次の同じか上のレベルの見出しの手前まで
+    let headings = markdown::headings(&content);
+    for (i, heading) in headings.iter().enumerate() {
+        let end = headings[i + 1..]
+            .iter()
+            .find(|h| h.level <= heading.level)
+            .map_or(content.len(), |h| h.range.start);
+        push(heading.range.start..end, "section");
+    }
+
+    for (event, range) in Parser::new_ext(&content, markdown::parser_options()).into_offset_iter() {
+        let kind = match event {
+            Event::Start(Tag::List(_) | Tag::Item) => "list",
+            Event::Start(Tag::CodeBlock(_)) => "code",
+            Event::Start(Tag::BlockQuote(_)) => "quote",
+            Event::Start(Tag::MetadataBlock(_)) => "front_matter",
+            _ => continue,
+        };
+        push(range, kind);
+    }
+
+    // 同じ行範囲（1 項目だけのリストなど）は 1 つにまとめる
+    ranges.sort_by_key(|r| (r.start_line, std::cmp::Reverse(r.end_line)));
+    ranges.dedup_by(|a, b| a.start_line == b.start_line && a.end_line == b.end_line);
+    ranges
+}
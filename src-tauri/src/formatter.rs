@@ -0,0 +1,317 @@
+// Markdown の整形（`.mdvimfmt.toml` と `.editorconfig` の設定に従い、保存時にも実行する）
+
+use std::collections::HashSet;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+use pulldown_cmark::{Event, Parser, Tag};
+use serde::{Deserialize, Serialize};
+
+use crate::diff;
+use crate::editorconfig;
+use crate::lsp::LspRange;
+use crate::markdown;
+use crate::text::{self, LineIndex};
+use crate::tr;
+use crate::vault;
+
+/// 整形の設定ファイル
+const CONFIG_FILE: &str = ".mdvimfmt.toml";
+
+/// 整形の設定（`.mdvimfmt.toml`）
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FormatConfig {
+    /// 保存時に整形する
+    pub format_on_save: bool,
+    /// 箇条書きの記号（`-`・`*`・`+`。未指定なら変えない）
+    pub list_marker: Option<char>,
+    /// 見出しの `#` の後の空白を 1 つにし、閉じ `#` を除く
+    pub normalize_headings: bool,
+    /// 見出しの前後に空行を入れる
+    pub blank_lines_around_headings: bool,
+    /// 連続する空行を 1 行にする
+    pub collapse_blank_lines: bool,
+    /// 行末の空白を除く（ハードブレイクの 2 つの空白は残す。未指定なら `.editorconfig` に従う）
+    pub trim_trailing_whitespace: Option<bool>,
+    /// 末尾の改行を 1 つにする（未指定なら `.editorconfig` に従う）
+    pub insert_final_newline: Option<bool>,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self {
+            format_on_save: false,
+            list_marker: None,
+            normalize_headings: true,
+            blank_lines_around_headings: true,
+            collapse_blank_lines: true,
+            trim_trailing_whitespace: None,
+            insert_final_newline: None,
+        }
+    }
+}
+
+/// エディタに適用する置き換え
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TextEdit {
+    pub range: LspRange,
+    pub text: String,
+}
+
+/// 整形の結果
+#[derive(Debug, Serialize)]
+pub struct FormatResult {
+    pub changed: bool,
+    pub content: String,
+    /// 元の内容への差分（カーソル位置を保ったまま更新するため）
+    pub edits: Vec<TextEdit>,
+    /// 読み込んだ設定ファイル
+    pub config_path: Option<String>,
+}
+
+/// ファイルのフォルダから上へ `.mdvimfmt.toml` を探して読む
+pub fn discover(path: &Path) -> Result<(FormatConfig, Option<String>), String> {
+    let path = vault::normalize(path);
+    let mut dir = path.parent();
+    let mut found = None;
+    while let Some(d) = dir {
+        let candidate = d.join(CONFIG_FILE);
+        if candidate.is_file() {
+            found = Some(candidate);
+            break;
+        }
+        dir = d.parent();
+    }
+    let mut config = match &found {
+        Some(file) => {
+            let content = fs::read_to_string(file)
+                .map_err(|e| tr!("Failed to read {}: {e}", file.display()))?;
+            toml::from_str(&content).map_err(|e| tr!("Invalid {}: {e}", file.display()))?
+        }
+        None => FormatConfig::default(),
+    };
+    let properties = editorconfig::properties(&path);
+    config.trim_trailing_whitespace = config
+        .trim_trailing_whitespace
+        .or_else(|| editorconfig::flag(&properties, "trim_trailing_whitespace"));
+    config.insert_final_newline = config
+        .insert_final_newline
+        .or_else(|| editorconfig::flag(&properties, "insert_final_newline"));
+    Ok((config, found.map(|f| vault::path_string(&f))))
+}
+
+/// コードブロック・フロントマター・HTML ブロックのバイト範囲（整形しない）
+pub fn verbatim_ranges(content: &str) -> Vec<Range<usize>> {
+    Parser::new_ext(content, markdown::parser_options())
+        .into_offset_iter()
+        .filter(|(event, _)| {
+            matches!(
+                event,
+                Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_) | Tag::HtmlBlock)
+                    | Event::DisplayMath(_)
+            )
+        })
+        .map(|(_, range)| range)
+        .collect()
+}
+
+/// 箇条書きの記号と ATX 見出しを書き換える
+fn rewrite_syntax(content: &str, config: &FormatConfig) -> String {
+    let mut edits = Vec::new();
+    if let Some(marker) = config.list_marker.filter(|m| matches!(m, '-' | '*' | '+')) {
+        for (event, range) in
+            Parser::new_ext(content, markdown::parser_options()).into_offset_iter()
+        {
+            if !matches!(event, Event::Start(Tag::Item)) {
+                continue;
+            }
+            let item = &content[range.clone()];
+            let at = range.start + (item.len() - item.trim_start().len());
+            let current = content[at..].chars().next();
+            let followed =
+                content[at + 1..].starts_with([' ', '\t', '\n', '\r']) || at + 1 == content.len();
+            if matches!(current, Some('-' | '*' | '+')) && current != Some(marker) && followed {
+                edits.push((at..at + 1, marker.to_string()));
+            }
+        }
+    }
+    if config.normalize_headings {
+        for heading in markdown::headings(content) {
+            let source = &content[heading.range.clone()];
+            let hashes = source
+                .trim_start()
+                .chars()
+                .take_while(|&c| c == '#')
+                .count();
+            // ATX 見出しのみ（Setext は下線があるので複数行になる）
+            if hashes == 0 || source.trim_end().contains('\n') {
+                continue;
+            }
+            let indent = &source[..source.len() - source.trim_start().len()];
+            let title = &content[heading.text_range.clone()];
+            let line = if title.is_empty() {
+                format!("{indent}{}", "#".repeat(hashes))
+            } else {
+                format!("{indent}{} {title}", "#".repeat(hashes))
+            };
+            let end = heading.range.start + source.trim_end().len();
+            if content[heading.range.start..end] != line {
+                edits.push((heading.range.start..end, line));
+            }
+        }
+    }
+    text::apply_edits(content, edits)
+}
+
+fn is_atx_heading(line: &str) -> bool {
+    let trimmed = line.trim_start_matches(' ');
+    line.len() - trimmed.len() <= 3
+        && trimmed.starts_with('#')
+        && trimmed
+            .trim_start_matches('#')
+            .chars()
+            .next()
+            .is_none_or(|c| c == ' ' || c == '\t')
+        && trimmed.chars().take_while(|&c| c == '#').count() <= 6
+}
+
+/// 行単位の整形（空白・空行・末尾の改行）
+fn normalize_lines(content: &str, config: &FormatConfig) -> String {
+    let eol = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let verbatim = verbatim_ranges(content);
+    let mut lines: Vec<(&str, bool)> = Vec::new();
+    let mut offset = 0;
+    for raw in content.split_inclusive('\n') {
+        let line = raw.trim_end_matches(['\n', '\r']);
+        lines.push((line, markdown::in_ranges(&verbatim, offset)));
+        offset += raw.len();
+    }
+    let verbatim_lines: HashSet<usize> = (0..lines.len()).filter(|&i| lines[i].1).collect();
+    let headings: HashSet<usize> = (0..lines.len())
+        .filter(|&i| !verbatim_lines.contains(&i) && is_atx_heading(lines[i].0))
+        .collect();
+    let blank = |i: usize| !verbatim_lines.contains(&i) && lines[i].0.trim().is_empty();
+
+    let mut out: Vec<String> = Vec::with_capacity(lines.len());
+    for (i, &(line, is_verbatim)) in lines.iter().enumerate() {
+        let previous_blank = out.last().is_none_or(|l| l.trim().is_empty());
+        if config.blank_lines_around_headings && !previous_blank {
+            let after_heading = i > 0 && headings.contains(&(i - 1)) && !blank(i);
+            if headings.contains(&i) || after_heading {
+                out.push(String::new());
+            }
+        }
+        if is_verbatim {
+            out.push(line.to_string());
+            continue;
+        }
+        let mut line = line.to_string();
+        if config.trim_trailing_whitespace.unwrap_or(true) {
+            let trimmed = line.trim_end();
+            // 次の行が続く段落なら、2 つ以上の空白はハードブレイクとして残す
+            let spaces = &line[trimmed.len()..];
+            let hard_break = spaces.starts_with("  ")
+                && !spaces.contains('\t')
+                && !trimmed.is_empty()
+                && i + 1 < lines.len()
+                && !blank(i + 1)
+                && !headings.contains(&i);
+            line = if hard_break {
+                format!("{trimmed}  ")
+            } else {
+                trimmed.to_string()
+            };
+        }
+        // 先頭の空行と連続する空行は除く
+        let previous_blank = out.last().is_none_or(|l| l.trim().is_empty());
+        if config.collapse_blank_lines && line.trim().is_empty() && previous_blank {
+            continue;
+        }
+        out.push(line);
+    }
+
+    // 末尾の空行は除き、改行を 1 つだけ付ける
+    if config.collapse_blank_lines {
+        while out.last().is_some_and(|l| l.is_empty()) {
+            out.pop();
+        }
+    }
+    let mut result = out.join(eol);
+    let final_newline = config.insert_final_newline.unwrap_or(true);
+    if final_newline {
+        let trimmed_len = result.trim_end_matches(['\n', '\r']).len();
+        result.truncate(trimmed_len);
+    }
+    if !result.is_empty() && (final_newline || content.ends_with('\n')) {
+        result.push_str(eol);
+    }
+    result
+}
+
+/// 設定に従って整形した内容
+pub fn format_markdown(content: &str, config: &FormatConfig) -> String {
+    normalize_lines(&rewrite_syntax(content, config), config)
+}
+
+/// 元の内容から整形後の内容への置き換えの一覧
+pub fn text_edits(old: &str, new: &str) -> Vec<TextEdit> {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let mut starts = Vec::with_capacity(old_lines.len() + 1);
+    let mut offset = 0;
+    for line in &old_lines {
+        starts.push(offset);
+        offset += line.len();
+    }
+    starts.push(offset);
+    let index = LineIndex::new(old);
+    diff::diff_lines(&old_lines, &new_lines)
+        .into_iter()
+        .map(|hunk| TextEdit {
+            range: LspRange::from_offsets(
+                &index,
+                old,
+                starts[hunk.old.start]..starts[hunk.old.end],
+            ),
+            text: new_lines[hunk.new].concat(),
+        })
+        .collect()
+}
+
+fn result(path: &str, content: String, always: bool) -> Result<FormatResult, String> {
+    let (config, config_path) = discover(Path::new(path))?;
+    if !always && !config.format_on_save {
+        return Ok(FormatResult {
+            changed: false,
+            content,
+            edits: Vec::new(),
+            config_path,
+        });
+    }
+    let formatted = format_markdown(&content, &config);
+    let edits = text_edits(&content, &formatted);
+    Ok(FormatResult {
+        changed: !edits.is_empty(),
+        content: formatted,
+        edits,
+        config_path,
+    })
+}
+
+/// 文書を整形する（`path` から設定ファイルを探す）
+#[tauri::command]
+pub fn format_document(path: String, content: String) -> Result<FormatResult, String> {
+    result(&path, content, true)
+}
+
+/// 保存の前に呼び、`format_on_save` が有効なら整形した内容と差分を返す
+#[tauri::command]
+pub fn format_on_save(path: String, content: String) -> Result<FormatResult, String> {
+    result(&path, content, false)
+}
@@ -0,0 +1,675 @@
+// フロントマターの読み取り（YAML のよく使う範囲）と JSON Schema による検証
+//
+// スキーマは引数で渡すか、文書のフォルダから上へ探した `.mdvim/front-matter.schema.json` を使う
+// （フォルダごとに置き換えられる）。使えるキーワード:
+// `type` `enum` `const` `required` `properties` `additionalProperties` `items` `minItems` `maxItems`
+// `uniqueItems` `minLength` `maxLength` `pattern` `format`（`date` `date-time` `email` `uri`）
+// `minimum` `maximum` `exclusiveMinimum` `exclusiveMaximum`
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+use crate::markdown;
+use crate::tr;
+use crate::vault::{self, path_string, Vault};
+
+/// スキーマを置くファイル（文書のフォルダから上へ探す）
+const SCHEMA_FILE: &str = ".mdvim/front-matter.schema.json";
+
+/// 検証で見つかった問題
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    /// JSON Pointer 形式の場所（`/title` `/tags/0`。フロントマター全体は空）
+    pub field: String,
+    /// 文書の行番号（1 始まり）
+    pub line: Option<usize>,
+    /// 満たさなかったキーワード（`required` `type` など。フロントマターが読めなければ `syntax`）
+    pub keyword: String,
+    pub message: String,
+}
+
+/// `validate_front_matter` の結果
+#[derive(Debug, Clone, Serialize)]
+pub struct FrontMatterReport {
+    /// 使ったスキーマのファイル（引数で渡したときは `None`）
+    pub schema: Option<String>,
+    pub valid: bool,
+    pub errors: Vec<FieldError>,
+}
+
+/// 問題のあったノート
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteFrontMatterErrors {
+    pub path: String,
+    pub schema: Option<String>,
+    pub errors: Vec<FieldError>,
+}
+
+/// 読み取ったフロントマター
+pub struct FrontMatter {
+    pub value: Value,
+    /// 場所（JSON Pointer）ごとの行番号（1 始まり）
+    pub lines: HashMap<String, usize>,
+}
+
+/// インデントとそれを除いた内容のある行
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    text: &'a str,
+}
+
+fn pointer_escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// 行末のコメントを除く（引用符の中の `#` は残す）
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '#') if prev.is_whitespace() => return text[..i].trim_end(),
+            _ => {}
+        }
+        prev = c;
+    }
+    text.trim_end()
+}
+
+/// `,` で区切る（引用符と括弧の中は区切らない）
+fn split_flow(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut quote, mut start) = (0, None, 0);
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    let last = text[start..].trim();
+    if !last.is_empty() {
+        parts.push(last);
+    }
+    parts
+}
+
+/// `key: value` の形なら名前と値（引用符で囲んだ名前も可）
+fn split_key(text: &str) -> Option<(String, &str)> {
+    let (key, rest) = if let Some(quoted) = text.strip_prefix(['"', '\'']) {
+        let q = text.chars().next()?;
+        let end = quoted.find(q)?;
+        (
+            quoted[..end].to_string(),
+            quoted[end + 1..].strip_prefix(':')?,
+        )
+    } else {
+        let i = text
+            .find(": ")
+            .or_else(|| text.ends_with(':').then(|| text.len() - 1))?;
+        (text[..i].trim().to_string(), &text[i + 1..])
+    };
+    (!key.is_empty() && !key.starts_with(['[', '{'])).then_some((key, rest.trim()))
+}
+
+/// 値 1 つ（引用符・フロー形式のリストとマッピング・数値・真偽値・null）
+fn scalar(text: &str) -> Value {
+    let text = text.trim();
+    if let Some(inner) = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+        return Value::String(
+            inner
+                .replace("\\\"", "\"")
+                .replace("\\n", "\n")
+                .replace("\\\\", "\\"),
+        );
+    }
+    if let Some(inner) = text.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')) {
+        return Value::String(inner.replace("''", "'"));
+    }
+    if let Some(inner) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        return Value::Array(split_flow(inner).into_iter().map(scalar).collect());
+    }
+    if let Some(inner) = text.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+        let map = split_flow(inner)
+            .into_iter()
+            .filter_map(|part| split_key(part).map(|(k, v)| (k, scalar(v))))
+            .collect();
+        return Value::Object(map);
+    }
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return Value::Null,
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        _ => {}
+    }
+    if let Ok(n) = text.parse::<i64>() {
+        return Value::Number(n.into());
+    }
+    if text.contains(['.', 'e', 'E']) && text.starts_with(|c: char| c.is_ascii_digit() || c == '-')
+    {
+        if let Some(n) = text.parse::<f64>().ok().and_then(Number::from_f64) {
+            return Value::Number(n);
+        }
+    }
+    Value::String(text.to_string())
+}
+
+struct Parser<'a> {
+    lines: Vec<Line<'a>>,
+    pos: usize,
+    locations: HashMap<String, usize>,
+}
+
+impl Parser<'_> {
+    fn is_item(text: &str) -> bool {
+        text == "-" || text.starts_with("- ")
+    }
+
+    /// `parent` より深いインデントの行からなるブロック（`parent` が `None` ならフロントマター全体）
+    fn block(&mut self, parent: Option<usize>, pointer: &str) -> Result<Value, FieldError> {
+        let Some(first) = self.lines.get(self.pos) else {
+            return Ok(Value::Null);
+        };
+        if parent.is_some_and(|parent| first.indent <= parent) {
+            return Ok(Value::Null);
+        }
+        let level = first.indent;
+        if Self::is_item(first.text) {
+            self.sequence(level, pointer)
+        } else {
+            self.mapping(level, pointer)
+        }
+    }
+
+    fn sequence(&mut self, level: usize, pointer: &str) -> Result<Value, FieldError> {
+        let mut items = Vec::new();
+        while let Some(&Line {
+            number,
+            indent,
+            text,
+        }) = self.lines.get(self.pos)
+        {
+            if indent != level || !Self::is_item(text) {
+                break;
+            }
+            let item_pointer = format!("{pointer}/{}", items.len());
+            self.locations.insert(item_pointer.clone(), number);
+            let rest = text[1..].trim_start();
+            if rest.is_empty() {
+                self.pos += 1;
+                items.push(self.block(Some(level), &item_pointer)?);
+            } else if split_key(rest).is_some() && !rest.starts_with(['"', '\'', '[', '{']) {
+                // `- key: value` はその位置から始まるマッピング
+                let item_indent = indent + text.len() - rest.len();
+                self.lines[self.pos] = Line {
+                    number,
+                    indent: item_indent,
+                    text: rest,
+                };
+                items.push(self.mapping(item_indent, &item_pointer)?);
+            } else {
+                self.pos += 1;
+                items.push(scalar(rest));
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn mapping(&mut self, level: usize, pointer: &str) -> Result<Value, FieldError> {
+        let mut map = Map::new();
+        while let Some(&Line {
+            number,
+            indent,
+            text,
+        }) = self.lines.get(self.pos)
+        {
+            if indent < level {
+                break;
+            }
+            let Some((key, rest)) = split_key(text).filter(|_| indent == level) else {
+                return Err(FieldError {
+                    field: pointer.to_string(),
+                    line: Some(number),
+                    keyword: "syntax".to_string(),
+                    message: tr!("Cannot read front matter line {number}"),
+                });
+            };
+            let key_pointer = format!("{pointer}/{}", pointer_escape(&key));
+            self.locations.insert(key_pointer.clone(), number);
+            self.pos += 1;
+            let value = match rest {
+                // `key:` の次の行から同じインデントで始まるリストも値とする
+                "" if self
+                    .lines
+                    .get(self.pos)
+                    .is_some_and(|l| l.indent == level && Self::is_item(l.text)) =>
+                {
+                    self.sequence(level, &key_pointer)?
+                }
+                "" => self.block(Some(level), &key_pointer)?,
+                "|" | "|-" | ">" | ">-" => self.block_scalar(level, rest),
+                _ => scalar(rest),
+            };
+            map.insert(key, value);
+        }
+        Ok(Value::Object(map))
+    }
+
+    /// `|`（改行を残す）と `>`（空白でつなぐ）
+    fn block_scalar(&mut self, level: usize, style: &str) -> Value {
+        let mut parts = Vec::new();
+        while let Some(line) = self.lines.get(self.pos).filter(|l| l.indent > level) {
+            parts.push(line.text);
+            self.pos += 1;
+        }
+        let separator = if style.starts_with('|') { "\n" } else { " " };
+        let mut text = parts.join(separator);
+        if !style.ends_with('-') && !text.is_empty() {
+            text.push('\n');
+        }
+        Value::String(text)
+    }
+}
+
+/// 文書のフロントマター（なければ `None`）
+pub fn parse(content: &str) -> Result<Option<FrontMatter>, FieldError> {
+    let end = markdown::body_start(content);
+    if end == 0 {
+        return Ok(None);
+    }
+    // 1 行目は `---`
+    let lines = content[..end]
+        .lines()
+        .enumerate()
+        .skip(1)
+        .filter_map(|(i, raw)| {
+            let text = strip_comment(raw.trim_end_matches('\r'));
+            let trimmed = text.trim_start();
+            (!trimmed.is_empty() && !matches!(trimmed, "---" | "...")).then_some(Line {
+                number: i + 1,
+                indent: text.len() - trimmed.len(),
+                text: trimmed,
+            })
+        })
+        .collect();
+    let mut parser = Parser {
+        lines,
+        pos: 0,
+        locations: HashMap::new(),
+    };
+    let value = match parser.block(None, "")? {
+        Value::Null => Value::Object(Map::new()),
+        value => value,
+    };
+    Ok(Some(FrontMatter {
+        value,
+        lines: parser.locations,
+    }))
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    let actual = type_name(value);
+    actual == expected || (expected == "number" && actual == "integer")
+}
+
+fn matches_format(value: &str, format: &str) -> bool {
+    match format {
+        "date" => NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+        "date-time" => DateTime::parse_from_rfc3339(value).is_ok(),
+        "email" => value
+            .split_once('@')
+            .is_some_and(|(user, host)| !user.is_empty() && host.contains('.')),
+        "uri" => value.split_once("://").is_some_and(|(scheme, rest)| {
+            !rest.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+')
+        }),
+        _ => true,
+    }
+}
+
+/// JSON Schema での検証
+struct Validator<'a> {
+    lines: &'a HashMap<String, usize>,
+    errors: Vec<FieldError>,
+}
+
+impl Validator<'_> {
+    fn error(&mut self, pointer: &str, keyword: &str, message: String) {
+        // 値のない場所は、親の場所（なければフロントマターの先頭の行）
+        let line = std::iter::successors(Some(pointer), |p| p.rsplit_once('/').map(|(p, _)| p))
+            .find_map(|p| self.lines.get(p).copied())
+            .unwrap_or(1);
+        self.errors.push(FieldError {
+            field: pointer.to_string(),
+            line: Some(line),
+            keyword: keyword.to_string(),
+            message,
+        });
+    }
+
+    fn field_name(pointer: &str) -> &str {
+        if pointer.is_empty() {
+            "front matter"
+        } else {
+            pointer.trim_start_matches('/')
+        }
+    }
+
+    fn validate(&mut self, value: &Value, schema: &Value, pointer: &str) {
+        let Some(schema) = schema.as_object() else {
+            return;
+        };
+        let name = Self::field_name(pointer).to_string();
+
+        if let Some(expected) = schema.get("type") {
+            let types: Vec<&str> = match expected {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            if !types.is_empty() && !types.iter().any(|t| matches_type(value, t)) {
+                let expected = types.join(" or ");
+                let actual = type_name(value);
+                self.error(
+                    pointer,
+                    "type",
+                    tr!("{name} must be {expected}, not {actual}"),
+                );
+                return;
+            }
+        }
+        if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+            if !options.contains(value) {
+                let options: Vec<String> = options.iter().map(Value::to_string).collect();
+                let options = options.join(", ");
+                self.error(pointer, "enum", tr!("{name} must be one of {options}"));
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if expected != value {
+                self.error(pointer, "const", tr!("{name} must be {expected}"));
+            }
+        }
+
+        match value {
+            Value::String(s) => self.validate_string(s, schema, pointer, &name),
+            Value::Number(n) => self.validate_number(n, schema, pointer, &name),
+            Value::Array(items) => self.validate_array(items, schema, pointer, &name),
+            Value::Object(map) => self.validate_object(map, schema, pointer),
+            _ => {}
+        }
+    }
+
+    fn validate_string(&mut self, s: &str, schema: &Map<String, Value>, pointer: &str, name: &str) {
+        let len = s.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if len < min {
+                self.error(
+                    pointer,
+                    "minLength",
+                    tr!("{name} must be at least {min} characters"),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if len > max {
+                self.error(
+                    pointer,
+                    "maxLength",
+                    tr!("{name} must be at most {max} characters"),
+                );
+            }
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+            match Regex::new(pattern) {
+                Ok(re) if !re.is_match(s) => {
+                    self.error(pointer, "pattern", tr!("{name} must match {pattern}"))
+                }
+                Ok(_) => {}
+                Err(e) => self.error(
+                    pointer,
+                    "pattern",
+                    tr!("Invalid pattern {pattern} in schema: {e}"),
+                ),
+            }
+        }
+        if let Some(format) = schema.get("format").and_then(Value::as_str) {
+            if !matches_format(s, format) {
+                self.error(pointer, "format", tr!("{name} must be a valid {format}"));
+            }
+        }
+    }
+
+    fn validate_number(
+        &mut self,
+        n: &Number,
+        schema: &Map<String, Value>,
+        pointer: &str,
+        name: &str,
+    ) {
+        let Some(n) = n.as_f64() else {
+            return;
+        };
+        let bound = |key: &str| schema.get(key).and_then(Value::as_f64);
+        if let Some(min) = bound("minimum").filter(|&min| n < min) {
+            self.error(pointer, "minimum", tr!("{name} must be at least {min}"));
+        }
+        if let Some(max) = bound("maximum").filter(|&max| n > max) {
+            self.error(pointer, "maximum", tr!("{name} must be at most {max}"));
+        }
+        if let Some(min) = bound("exclusiveMinimum").filter(|&min| n <= min) {
+            self.error(
+                pointer,
+                "exclusiveMinimum",
+                tr!("{name} must be greater than {min}"),
+            );
+        }
+        if let Some(max) = bound("exclusiveMaximum").filter(|&max| n >= max) {
+            self.error(
+                pointer,
+                "exclusiveMaximum",
+                tr!("{name} must be less than {max}"),
+            );
+        }
+    }
+
+    fn validate_array(
+        &mut self,
+        items: &[Value],
+        schema: &Map<String, Value>,
+        pointer: &str,
+        name: &str,
+    ) {
+        let len = items.len() as u64;
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if len < min {
+                self.error(
+                    pointer,
+                    "minItems",
+                    tr!("{name} must have at least {min} items"),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if len > max {
+                self.error(
+                    pointer,
+                    "maxItems",
+                    tr!("{name} must have at most {max} items"),
+                );
+            }
+        }
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            let duplicate = items
+                .iter()
+                .enumerate()
+                .any(|(i, item)| items[..i].contains(item));
+            if duplicate {
+                self.error(
+                    pointer,
+                    "uniqueItems",
+                    tr!("{name} must not contain duplicates"),
+                );
+            }
+        }
+        if let Some(item_schema) = schema.get("items") {
+            for (i, item) in items.iter().enumerate() {
+                self.validate(item, item_schema, &format!("{pointer}/{i}"));
+            }
+        }
+    }
+
+    fn validate_object(
+        &mut self,
+        map: &Map<String, Value>,
+        schema: &Map<String, Value>,
+        pointer: &str,
+    ) {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !map.contains_key(key) {
+                    let field = format!("{pointer}/{}", pointer_escape(key));
+                    self.error(&field, "required", tr!("Missing required field: {key}"));
+                }
+            }
+        }
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (key, value) in map {
+            let field = format!("{pointer}/{}", pointer_escape(key));
+            match (
+                properties.and_then(|p| p.get(key)),
+                schema.get("additionalProperties"),
+            ) {
+                (Some(property), _) => self.validate(value, property, &field),
+                (None, Some(Value::Bool(false))) => {
+                    self.error(&field, "additionalProperties", tr!("Unknown field: {key}"))
+                }
+                (None, Some(additional)) => self.validate(value, additional, &field),
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+/// フロントマターを検証する（フロントマターがなければ空のマッピングとして検証する）
+pub fn validate(content: &str, schema: &Value) -> Vec<FieldError> {
+    let front_matter = match parse(content) {
+        Ok(Some(front_matter)) => front_matter,
+        Ok(None) => FrontMatter {
+            value: Value::Object(Map::new()),
+            lines: HashMap::new(),
+        },
+        Err(error) => return vec![error],
+    };
+    let mut validator = Validator {
+        lines: &front_matter.lines,
+        errors: Vec::new(),
+    };
+    validator.validate(&front_matter.value, schema, "");
+    validator.errors
+}
+
+/// 文書に近いスキーマのファイル
+pub fn find_schema(doc: &Path) -> Option<PathBuf> {
+    vault::normalize(doc)
+        .ancestors()
+        .skip(1)
+        .map(|d| d.join(SCHEMA_FILE))
+        .find(|p| p.is_file())
+}
+
+fn read_schema(path: &Path) -> Result<Value, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+    serde_json::from_str(&text).map_err(|e| tr!("Invalid {}: {e}", path.display()))
+}
+
+/// フロントマターをスキーマで検証する
+///
+/// `schema` はスキーマそのものかスキーマのファイルのパス。省くと `path` のフォルダから上へ探した
+/// `.mdvim/front-matter.schema.json` を使う（見つからなければ問題なしとする）。
+#[tauri::command]
+pub fn validate_front_matter(
+    content: String,
+    schema: Option<Value>,
+    path: Option<String>,
+) -> Result<FrontMatterReport, String> {
+    let (schema, schema_path) = match schema {
+        Some(Value::String(file)) => (read_schema(Path::new(&file))?, Some(file)),
+        Some(schema) => (schema, None),
+        None => match path.as_deref().and_then(|p| find_schema(Path::new(p))) {
+            Some(file) => (read_schema(&file)?, Some(path_string(&file))),
+            None => {
+                return Ok(FrontMatterReport {
+                    schema: None,
+                    valid: true,
+                    errors: Vec::new(),
+                })
+            }
+        },
+    };
+    let errors = validate(&content, &schema);
+    Ok(FrontMatterReport {
+        schema: schema_path,
+        valid: errors.is_empty(),
+        errors,
+    })
+}
+
+/// 保管庫の全ノートをそれぞれのフォルダのスキーマで検証し、問題のあったノートを返す（公開前の確認用）
+#[tauri::command(async)]
+pub fn validate_vault_front_matter(
+    vault_root: String,
+) -> Result<Vec<NoteFrontMatterErrors>, String> {
+    let vault = Vault::scan(Path::new(&vault_root));
+    let mut schemas: HashMap<PathBuf, Value> = HashMap::new();
+    let mut notes = Vec::new();
+    for doc in vault.markdown_files() {
+        let Some(file) = find_schema(doc) else {
+            continue;
+        };
+        if !schemas.contains_key(&file) {
+            schemas.insert(file.clone(), read_schema(&file)?);
+        }
+        let content =
+            fs::read_to_string(doc).map_err(|e| tr!("Failed to read {}: {e}", doc.display()))?;
+        let errors = validate(&content, &schemas[&file]);
+        if !errors.is_empty() {
+            notes.push(NoteFrontMatterErrors {
+                path: path_string(doc),
+                schema: Some(path_string(&file)),
+                errors,
+            });
+        }
+    }
+    Ok(notes)
+}
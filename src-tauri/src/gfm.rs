@@ -0,0 +1,248 @@
+// GitHub と同じ描画のための拡張（GFM の拡張自動リンクとアラートのブロック）
+
+use std::ops::Range;
+
+use pulldown_cmark::{BlockQuoteKind, CowStr, Event, LinkType, Tag, TagEnd};
+
+/// リンクの末尾に含めない記号
+const TRAILING_PUNCTUATION: &[char] = &['?', '!', '.', ',', ':', '*', '_', '~'];
+
+fn is_domain_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '_' | '.')
+}
+
+/// 有効なドメイン（`.` で区切った 2 つ以上の部分で、最後の 2 つに `_` を含まない）
+fn valid_domain(domain: &str) -> bool {
+    let parts: Vec<&str> = domain.split('.').collect();
+    parts.len() >= 2
+        && parts.iter().all(|p| !p.is_empty())
+        && parts[parts.len() - 2..].iter().all(|p| !p.contains('_'))
+}
+
+/// 末尾の記号・対応しない `)`・文字参照らしい `&name;` を除く
+fn trim_trailing(link: &str) -> &str {
+    let mut link = link;
+    loop {
+        if let Some(rest) = link.strip_suffix(TRAILING_PUNCTUATION) {
+            link = rest;
+        } else if link.ends_with(')') && link.matches(')').count() > link.matches('(').count() {
+            link = &link[..link.len() - 1];
+        } else if let Some(rest) = link.strip_suffix(';') {
+            let name = rest.trim_end_matches(|c: char| c.is_ascii_alphanumeric());
+            match name.strip_suffix('&') {
+                Some(before) if name.len() < rest.len() => link = before,
+                _ => return link,
+            }
+        } else {
+            return link;
+        }
+    }
+}
+
+/// `start` から始まる `www.`・`http://`・`https://`・`ftp://` のリンク（終わりの位置と URL）
+fn url_at(text: &str, start: usize) -> Option<(usize, String)> {
+    let rest = &text[start..];
+    let (domain_start, prefix) = if rest.starts_with("www.") {
+        (0, "http://")
+    } else if let Some(scheme) = ["http://", "https://", "ftp://"]
+        .into_iter()
+        .find(|s| rest.starts_with(s))
+    {
+        (scheme.len(), "")
+    } else {
+        return None;
+    };
+    let end = rest
+        .find(|c: char| c.is_whitespace() || c == '<')
+        .unwrap_or(rest.len());
+    let link = trim_trailing(&rest[..end]);
+    let domain = &link[domain_start..];
+    let domain = &domain[..domain.find(|c| !is_domain_char(c)).unwrap_or(domain.len())];
+    if !valid_domain(domain.trim_end_matches('.')) {
+        return None;
+    }
+    Some((start + link.len(), format!("{prefix}{link}")))
+}
+
+/// `@` の前後のメールアドレス
+fn email_at(text: &str, at: usize) -> Option<Range<usize>> {
+    let start = text[..at]
+        .char_indices()
+        .rev()
+        .find(|&(_, c)| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '+')))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let domain = &text[at + 1..];
+    let domain = &domain[..domain
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_')))
+        .unwrap_or(domain.len())];
+    let domain = domain.trim_end_matches('.');
+    let valid = start < at
+        && domain.contains('.')
+        && !domain.ends_with(['-', '_'])
+        && !text[at + 1 + domain.len()..].starts_with(['-', '_']);
+    valid.then(|| start..at + 1 + domain.len())
+}
+
+/// テキスト中の拡張自動リンク（範囲と URL）
+pub fn autolinks(text: &str) -> Vec<(Range<usize>, String)> {
+    let mut links: Vec<(Range<usize>, String)> = Vec::new();
+    let mut previous = None;
+    for (i, c) in text.char_indices() {
+        let boundary =
+            previous.is_none_or(|c: char| c.is_whitespace() || matches!(c, '*' | '_' | '~' | '('));
+        previous = Some(c);
+        if !boundary || links.last().is_some_and(|(r, _)| i < r.end) {
+            continue;
+        }
+        if let Some((end, url)) = url_at(text, i) {
+            links.push((i..end, url));
+        }
+    }
+    let mut emails = Vec::new();
+    for (at, _) in text.match_indices('@') {
+        if links.iter().any(|(r, _)| r.contains(&at)) {
+            continue;
+        }
+        if let Some(range) = email_at(text, at) {
+            emails.push((range.clone(), format!("mailto:{}", &text[range])));
+        }
+    }
+    links.extend(emails);
+    links.sort_by_key(|(r, _)| r.start);
+    links
+}
+
+/// テキスト中の URL とメールアドレスをリンクにする（リンク・コード・画像の中は除く）
+pub fn link_urls(events: Vec<Event>) -> Vec<Event> {
+    let mut out: Vec<Event> = Vec::with_capacity(events.len());
+    let mut verbatim = 0usize;
+    let mut pending = String::new();
+    let flush = |pending: &mut String, out: &mut Vec<Event>| {
+        if pending.is_empty() {
+            return;
+        }
+        let text = std::mem::take(pending);
+        let mut last = 0;
+        for (range, url) in autolinks(&text) {
+            if range.start > last {
+                out.push(Event::Text(CowStr::from(
+                    text[last..range.start].to_string(),
+                )));
+            }
+            out.push(Event::Start(Tag::Link {
+                link_type: LinkType::Autolink,
+                dest_url: CowStr::from(url),
+                title: CowStr::from(""),
+                id: CowStr::from(""),
+            }));
+            out.push(Event::Text(CowStr::from(text[range.clone()].to_string())));
+            out.push(Event::End(TagEnd::Link));
+            last = range.end;
+        }
+        if last < text.len() {
+            out.push(Event::Text(CowStr::from(text[last..].to_string())));
+        }
+    };
+    for event in events {
+        match &event {
+            // 分かれたテキストはまとめてから探す
+            Event::Text(text) if verbatim == 0 => {
+                pending.push_str(text);
+                continue;
+            }
+            Event::Start(
+                Tag::Link { .. } | Tag::Image { .. } | Tag::CodeBlock(_) | Tag::MetadataBlock(_),
+            ) => verbatim += 1,
+            Event::End(
+                TagEnd::Link | TagEnd::Image | TagEnd::CodeBlock | TagEnd::MetadataBlock(_),
+            ) => verbatim = verbatim.saturating_sub(1),
+            _ => {}
+        }
+        flush(&mut pending, &mut out);
+        out.push(event);
+    }
+    flush(&mut pending, &mut out);
+    out
+}
+
+fn alert_name(kind: BlockQuoteKind) -> (&'static str, &'static str) {
+    match kind {
+        BlockQuoteKind::Note => ("note", "Note"),
+        BlockQuoteKind::Tip => ("tip", "Tip"),
+        BlockQuoteKind::Important => ("important", "Important"),
+        BlockQuoteKind::Warning => ("warning", "Warning"),
+        BlockQuoteKind::Caution => ("caution", "Caution"),
+    }
+}
+
+/// `> [!NOTE]` を GitHub と同じ `<div class="markdown-alert">` にする
+pub fn alert_blocks(events: Vec<Event>) -> Vec<Event> {
+    events
+        .into_iter()
+        .map(|event| match event {
+            Event::Start(Tag::BlockQuote(Some(kind))) => {
+                let (class, title) = alert_name(kind);
+                Event::Html(CowStr::from(format!(
+                    "<div class=\"markdown-alert markdown-alert-{class}\">\n<p class=\"markdown-alert-title\">{title}</p>\n"
+                )))
+            }
+            Event::End(TagEnd::BlockQuote(Some(_))) => Event::Html(CowStr::from("</div>\n")),
+            event => event,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::render::{render_html_with, RenderOptions};
+    use crate::render_profile::RenderProfile;
+    use crate::spec;
+
+    #[test]
+    fn gfm_spec_examples() {
+        let profile = RenderProfile::gfm();
+        let failures = spec::failures(include_str!("../tests/spec/gfm.txt"), |markdown| {
+            render_html_with(
+                markdown,
+                &RenderOptions {
+                    profile: Some(&profile),
+                    ..Default::default()
+                },
+            )
+        });
+        assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+    }
+
+    #[test]
+    fn autolink_boundaries() {
+        let links = super::autolinks("see www.example.com, (https://example.com/a_(b)) x@y.z.");
+        let urls: Vec<&str> = links.iter().map(|(_, url)| url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "http://www.example.com",
+                "https://example.com/a_(b)",
+                "mailto:x@y.z"
+            ]
+        );
+    }
+
+    #[test]
+    fn autolinks_in_non_ascii_text() {
+        let text = "café www.example.com と日本語。連絡はfoo@bar.baz まで。詳細 https://例え.jp/パス を参照";
+        let links = super::autolinks(text);
+        let found: Vec<(&str, &str)> = links
+            .iter()
+            .map(|(r, url)| (&text[r.clone()], url.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("www.example.com", "http://www.example.com"),
+                ("foo@bar.baz", "mailto:foo@bar.baz"),
+                ("https://例え.jp/パス", "https://例え.jp/パス"),
+            ]
+        );
+        assert!(super::autolinks("日本語だけの文").is_empty());
+    }
+}
@@ -0,0 +1,340 @@
+// LanguageTool による文法チェック（コードを除いた段落ごとに結果をキャッシュする）
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::sync::Mutex;
+
+use pulldown_cmark::{Event, LinkType, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::State;
+use tauri_plugin_http::reqwest;
+
+use crate::keychain;
+use crate::lsp::LspRange;
+use crate::markdown;
+use crate::state::AppState;
+use crate::text::{self, LineIndex};
+use crate::tr;
+
+/// 1 回の要求で送る文字数の目安（公開サーバーの上限より小さくする）
+const REQUEST_CHARS: usize = 15_000;
+/// キャッシュする段落の数の上限
+const CACHE_LIMIT: usize = 5_000;
+
+/// LanguageTool サーバーの設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LanguageToolConfig {
+    /// ローカルのサーバー、または `https://api.languagetool.org`
+    pub url: String,
+    /// 有料版のユーザー名
+    pub username: Option<String>,
+    /// キーチェーンに保存した API キーの名前
+    pub api_key_name: Option<String>,
+    pub disabled_rules: Vec<String>,
+}
+
+impl Default for LanguageToolConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:8081".to_string(),
+            username: None,
+            api_key_name: None,
+            disabled_rules: Vec::new(),
+        }
+    }
+}
+
+/// 段落内の指摘（段落先頭からのバイト範囲）
+#[derive(Debug, Clone)]
+struct CachedMatch {
+    range: Range<usize>,
+    message: String,
+    short_message: String,
+    rule_id: String,
+    category: String,
+    replacements: Vec<String>,
+}
+
+/// 文法チェックの設定と段落ごとの結果
+#[derive(Default)]
+pub struct GrammarState {
+    config: Mutex<LanguageToolConfig>,
+    cache: Mutex<HashMap<u64, Vec<CachedMatch>>>,
+}
+
+/// 文法・スペルの指摘
+#[derive(Debug, Serialize)]
+pub struct GrammarIssue {
+    pub range: LspRange,
+    pub message: String,
+    pub short_message: String,
+    pub rule_id: String,
+    /// "TYPOS" | "GRAMMAR" | "STYLE" など
+    pub category: String,
+    pub replacements: Vec<String>,
+}
+
+/// チェックする段落（`pieces` はテキストとインラインコードの範囲）
+struct Paragraph {
+    span: Range<usize>,
+    pieces: Vec<(Range<usize>, bool)>,
+}
+
+/// コードブロック・フロントマターを除いた段落・見出し・リスト項目・表セル
+fn paragraphs(content: &str) -> Vec<Paragraph> {
+    let mut paragraphs = Vec::new();
+    let mut pieces: Vec<(Range<usize>, bool)> = Vec::new();
+    let mut skip_depth = 0usize;
+
+    let flush = |pieces: &mut Vec<(Range<usize>, bool)>, paragraphs: &mut Vec<Paragraph>| {
+        if pieces.iter().any(|(_, code)| !code) {
+            let span = pieces[0].0.start..pieces[pieces.len() - 1].0.end;
+            paragraphs.push(Paragraph {
+                span,
+                pieces: std::mem::take(pieces),
+            });
+        }
+        pieces.clear();
+    };
+
+    for (event, range) in Parser::new_ext(content, markdown::parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_)) => {
+                flush(&mut pieces, &mut paragraphs);
+                skip_depth += 1;
+            }
+            Event::End(TagEnd::CodeBlock | TagEnd::MetadataBlock(_)) => {
+                skip_depth = skip_depth.saturating_sub(1);
+            }
+            // ウィキリンク・自動リンクはノート名や URL なのでチェックしない
+            Event::Start(Tag::Link {
+                link_type: LinkType::WikiLink { .. } | LinkType::Autolink | LinkType::Email,
+                ..
+            }) => skip_depth += 1,
+            Event::End(TagEnd::Link) if skip_depth > 0 => skip_depth -= 1,
+            Event::Start(
+                Tag::Paragraph
+                | Tag::Heading { .. }
+                | Tag::Item
+                | Tag::TableCell
+                | Tag::BlockQuote(_)
+                | Tag::List(_),
+            )
+            | Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::Item
+                | TagEnd::TableCell
+                | TagEnd::BlockQuote(_)
+                | TagEnd::List(_),
+            ) if skip_depth == 0 => flush(&mut pieces, &mut paragraphs),
+            Event::Text(_) if skip_depth == 0 => pieces.push((range, false)),
+            Event::Code(_) if skip_depth == 0 => pieces.push((range, true)),
+            _ => {}
+        }
+    }
+    flush(&mut pieces, &mut paragraphs);
+    paragraphs
+}
+
+/// LanguageTool の注釈付きテキスト（記法は markup として送り、位置を元の文書と一致させる）
+fn annotation(content: &str, paragraph: &Paragraph) -> Vec<Value> {
+    let mut items = Vec::new();
+    let mut cursor = paragraph.span.start;
+    let markup = |items: &mut Vec<Value>, text: &str| {
+        if text.is_empty() {
+            return;
+        }
+        // 改行をまたぐ記法（引用の `>` など）は空白として読ませる
+        if text.contains('\n') {
+            items.push(json!({ "markup": text, "interpretAs": " " }));
+        } else {
+            items.push(json!({ "markup": text }));
+        }
+    };
+    for (range, code) in &paragraph.pieces {
+        markup(&mut items, &content[cursor..range.start]);
+        if *code {
+            items.push(json!({ "markup": &content[range.clone()], "interpretAs": "code" }));
+        } else {
+            items.push(json!({ "text": &content[range.clone()] }));
+        }
+        cursor = range.end;
+    }
+    items
+}
+
+fn cache_key(config: &LanguageToolConfig, lang: &str, paragraph: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (&config.url, &config.disabled_rules, lang, paragraph).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 段落をまとめて LanguageTool に送り、段落ごとの指摘を返す
+async fn check(
+    config: &LanguageToolConfig,
+    lang: &str,
+    texts: &[&str],
+    annotations: Vec<Vec<Value>>,
+) -> Result<Vec<Vec<CachedMatch>>, String> {
+    // 段落を空行の markup でつなぎ、各段落の先頭位置（UTF-16）を覚えておく
+    let mut data = Vec::new();
+    let mut starts = Vec::with_capacity(texts.len());
+    let mut position = 0;
+    for (text, items) in texts.iter().zip(annotations) {
+        if !data.is_empty() {
+            data.push(json!({ "markup": "\n\n", "interpretAs": "\n\n" }));
+            position += 2;
+        }
+        starts.push(position);
+        position += text.encode_utf16().count();
+        data.extend(items);
+    }
+
+    let api_key = match &config.api_key_name {
+        Some(name) => {
+            Some(keychain::get_secret(name)?.ok_or_else(|| tr!("API key \"{name}\" is not set"))?)
+        }
+        None => None,
+    };
+    let data = json!({ "annotation": data }).to_string();
+    let disabled = config.disabled_rules.join(",");
+    let mut form: Vec<(&str, &str)> = vec![("language", lang), ("data", &data)];
+    if !disabled.is_empty() {
+        form.push(("disabledRules", &disabled));
+    }
+    if let (Some(username), Some(key)) = (&config.username, &api_key) {
+        form.push(("username", username));
+        form.push(("apiKey", key));
+    }
+    let url = format!("{}/v2/check", config.url.trim_end_matches('/'));
+    let response = reqwest::Client::new()
+        .post(&url)
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| tr!("Failed to connect to {url}: {e}"))?;
+    let status = response.status();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| tr!("Failed to read response from {url}: {e}"))?;
+    if !status.is_success() {
+        return Err(tr!("Grammar check failed ({status}): {text}"));
+    }
+    let response: Value =
+        serde_json::from_str(&text).map_err(|e| tr!("Invalid response from {url}: {e}"))?;
+
+    let mut results = vec![Vec::new(); texts.len()];
+    for m in response["matches"].as_array().into_iter().flatten() {
+        let offset = m["offset"].as_u64().unwrap_or(0) as usize;
+        let length = m["length"].as_u64().unwrap_or(0) as usize;
+        let i = starts.partition_point(|&s| s <= offset).saturating_sub(1);
+        let paragraph = texts[i];
+        let start = text::byte_offset(paragraph, offset - starts[i]);
+        let end = text::byte_offset(paragraph, offset - starts[i] + length);
+        let string = |v: &Value| v.as_str().unwrap_or_default().to_string();
+        results[i].push(CachedMatch {
+            range: start..end,
+            message: string(&m["message"]),
+            short_message: string(&m["shortMessage"]),
+            rule_id: string(&m["rule"]["id"]),
+            category: string(&m["rule"]["category"]["id"]),
+            replacements: m["replacements"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|r| string(&r["value"]))
+                .collect(),
+        });
+    }
+    Ok(results)
+}
+
+/// LanguageTool サーバーを設定（キャッシュも消す）
+#[tauri::command]
+pub fn configure_grammar_check(state: State<'_, AppState>, config: LanguageToolConfig) {
+    *state.grammar.config.lock().unwrap() = config;
+    state.grammar.cache.lock().unwrap().clear();
+}
+
+/// 文法・スペルをチェックし、指摘をエディタ上の範囲で返す（`lang` は "en-US"・"ja-JP"・"auto" など）
+#[tauri::command]
+pub async fn grammar_check(
+    state: State<'_, AppState>,
+    content: String,
+    lang: String,
+) -> Result<Vec<GrammarIssue>, String> {
+    let config = state.grammar.config.lock().unwrap().clone();
+    let lang = match lang.trim() {
+        "" => "auto",
+        lang => lang,
+    };
+    let paragraphs = paragraphs(&content);
+    let keys: Vec<u64> = paragraphs
+        .iter()
+        .map(|p| cache_key(&config, lang, &content[p.span.clone()]))
+        .collect();
+
+    // キャッシュにない段落だけを送る
+    let uncached: Vec<usize> = {
+        let cache = state.grammar.cache.lock().unwrap();
+        (0..paragraphs.len())
+            .filter(|&i| !cache.contains_key(&keys[i]))
+            .collect()
+    };
+    // 文字数の目安ごとに区切って送る
+    let mut start = 0;
+    while start < uncached.len() {
+        let mut end = start;
+        let mut chars = 0;
+        while end < uncached.len() && (end == start || chars < REQUEST_CHARS) {
+            chars += paragraphs[uncached[end]].span.len();
+            end += 1;
+        }
+        let part = &uncached[start..end];
+        let texts: Vec<&str> = part
+            .iter()
+            .map(|&i| &content[paragraphs[i].span.clone()])
+            .collect();
+        let annotations = part
+            .iter()
+            .map(|&i| annotation(&content, &paragraphs[i]))
+            .collect();
+        let results = check(&config, lang, &texts, annotations).await?;
+        let mut cache = state.grammar.cache.lock().unwrap();
+        if cache.len() > CACHE_LIMIT {
+            cache.clear();
+        }
+        for (&i, matches) in part.iter().zip(results) {
+            cache.insert(keys[i], matches);
+        }
+        start = end;
+    }
+
+    let index = LineIndex::new(&content);
+    let cache = state.grammar.cache.lock().unwrap();
+    let mut issues = Vec::new();
+    for (paragraph, key) in paragraphs.iter().zip(&keys) {
+        for m in cache.get(key).into_iter().flatten() {
+            let start = paragraph.span.start;
+            issues.push(GrammarIssue {
+                range: LspRange::from_offsets(
+                    &index,
+                    &content,
+                    start + m.range.start..start + m.range.end,
+                ),
+                message: m.message.clone(),
+                short_message: m.short_message.clone(),
+                rule_id: m.rule_id.clone(),
+                category: m.category.clone(),
+                replacements: m.replacements.clone(),
+            });
+        }
+    }
+    Ok(issues)
+}
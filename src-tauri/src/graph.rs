@@ -0,0 +1,194 @@
+// ノート間のリンクのグラフ（グラフビュー用）
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::links;
+use crate::markdown;
+use crate::obsidian;
+use crate::refactor::read;
+use crate::vault::{self, path_string, Vault};
+
+/// グラフのノード（ノート 1 件）
+#[derive(Debug, Serialize)]
+pub struct GraphNode {
+    /// `edges` から参照される番号
+    pub id: usize,
+    pub path: String,
+    /// 最初の H1 見出し（なければファイル名）
+    pub title: String,
+    pub tags: Vec<String>,
+    /// リンクしているノートの数
+    pub out_degree: usize,
+    /// バックリンク（このノートにリンクしているノート）の数
+    pub in_degree: usize,
+    pub degree: usize,
+    /// 連結成分の番号（リンクの向きは区別しない）
+    pub component: usize,
+    /// 存在しないノート（リンク先のみ）
+    pub missing: bool,
+}
+
+/// リンク（同じノートへの複数のリンクは 1 本にまとめる）
+#[derive(Debug, Serialize)]
+pub struct GraphEdge {
+    pub source: usize,
+    pub target: usize,
+    /// リンクの数
+    pub count: usize,
+}
+
+/// `get_link_graph` の結果
+#[derive(Debug, Serialize)]
+pub struct LinkGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+fn note_title(path: &Path, content: &str) -> String {
+    markdown::headings(content)
+        .into_iter()
+        .find(|h| h.level == 1)
+        .map(|h| h.text)
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        })
+}
+
+/// 連結成分の番号を振る（Union-Find）
+fn components(count: usize, edges: &[GraphEdge]) -> Vec<usize> {
+    fn find(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    let mut parent: Vec<usize> = (0..count).collect();
+    for edge in edges {
+        let (a, b) = (
+            find(&mut parent, edge.source),
+            find(&mut parent, edge.target),
+        );
+        if a != b {
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+    // 代表のノードの順に 0 から番号を振る
+    let mut numbers = HashMap::new();
+    (0..count)
+        .map(|i| {
+            let root = find(&mut parent, i);
+            let next = numbers.len();
+            *numbers.entry(root).or_insert(next)
+        })
+        .collect()
+}
+
+/// ワークスペースの全ノートとノート間のリンクからグラフを作る
+fn build(vault: &Vault) -> Result<LinkGraph, String> {
+    let docs: Vec<PathBuf> = vault.markdown_files().cloned().collect();
+    let mut index: HashMap<PathBuf, usize> = docs
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (p.clone(), i))
+        .collect();
+    let mut nodes = Vec::with_capacity(docs.len());
+    let mut counts: HashMap<(usize, usize), usize> = HashMap::new();
+
+    for (id, doc) in docs.iter().enumerate() {
+        let content = read(doc)?;
+        let mut tags = obsidian::note_tags(&content);
+        tags.sort();
+        tags.dedup();
+        nodes.push(GraphNode {
+            id,
+            path: path_string(doc),
+            title: note_title(doc, &content),
+            tags,
+            out_degree: 0,
+            in_degree: 0,
+            degree: 0,
+            component: 0,
+            missing: false,
+        });
+
+        for link in links::extract_links(&content) {
+            if link.target.is_empty() || (!link.is_wiki() && links::is_external(&link.target)) {
+                continue;
+            }
+            // 見つからないウィキリンクはルート直下のノートとみなす
+            let target = match vault.resolve(doc, &link) {
+                Some(target) => target,
+                None if link.is_wiki() && Path::new(&link.target).extension().is_none() => {
+                    vault.root.join(format!("{}.md", link.target))
+                }
+                None => continue,
+            };
+            if target == *doc || !vault::is_markdown(&target) {
+                continue;
+            }
+            let target_id = match index.get(&target) {
+                Some(&i) => i,
+                None if target.is_file() => continue,
+                None => {
+                    let i = index.len();
+                    index.insert(target.clone(), i);
+                    i
+                }
+            };
+            *counts.entry((id, target_id)).or_default() += 1;
+        }
+    }
+
+    // 存在しないノートは最後に並べる
+    let mut missing: Vec<(&PathBuf, &usize)> =
+        index.iter().filter(|(_, &i)| i >= docs.len()).collect();
+    missing.sort_by_key(|(_, &i)| i);
+    for (path, &id) in missing {
+        nodes.push(GraphNode {
+            id,
+            path: path_string(path),
+            title: path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            tags: Vec::new(),
+            out_degree: 0,
+            in_degree: 0,
+            degree: 0,
+            component: 0,
+            missing: true,
+        });
+    }
+
+    let mut edges: Vec<GraphEdge> = counts
+        .into_iter()
+        .map(|((source, target), count)| GraphEdge {
+            source,
+            target,
+            count,
+        })
+        .collect();
+    edges.sort_by_key(|e| (e.source, e.target));
+    for edge in &edges {
+        nodes[edge.source].out_degree += 1;
+        nodes[edge.target].in_degree += 1;
+    }
+    let component = components(nodes.len(), &edges);
+    for node in &mut nodes {
+        node.degree = node.in_degree + node.out_degree;
+        node.component = component[node.id];
+    }
+    Ok(LinkGraph { nodes, edges })
+}
+
+/// ワークスペースのリンクグラフ（ノード・リンク・次数）
+#[tauri::command(async)]
+pub fn get_link_graph(root: String) -> Result<LinkGraph, String> {
+    build(&Vault::scan(Path::new(&root)))
+}
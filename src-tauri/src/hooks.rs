@@ -0,0 +1,278 @@
+// 外部コマンドのフック（保存・エクスポートなどの前後にユーザーのコマンドを実行）
+//
+// フックは信頼したワークスペースでだけ実行する（`trust`）。
+
+use std::path::Path;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::shell::{self, RunOptions};
+use crate::state::AppState;
+use crate::tr;
+use crate::vault::{self, path_string};
+
+/// 既定のタイムアウト
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// 取り込む出力の上限（それ以降は読み捨てる）
+const MAX_OUTPUT: usize = 1 << 20;
+
+/// フックを実行するタイミング
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    OnOpen,
+    OnSave,
+    PreExport,
+    PostExport,
+}
+
+impl HookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::OnOpen => "on_open",
+            HookEvent::OnSave => "on_save",
+            HookEvent::PreExport => "pre_export",
+            HookEvent::PostExport => "post_export",
+        }
+    }
+}
+
+/// フックの設定
+///
+/// `command` 中の `{file}` `{dir}` `{name}` `{stem}` `{output}` `{vault}` は
+/// シェル用に引用符で囲んだ値に置き換える。同じ値は環境変数 `MDVIM_FILE` などでも渡す。
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Hook {
+    pub event: HookEvent,
+    pub command: String,
+    /// 対象にする拡張子（空ならすべて）
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// 作業フォルダ（省略時はファイルのフォルダ）
+    #[serde(default)]
+    pub cwd: Option<String>,
+}
+
+/// フック 1 件の実行結果
+#[derive(Debug, Clone, Serialize)]
+pub struct HookResult {
+    pub event: HookEvent,
+    /// 置き換え後のコマンド
+    pub command: String,
+    pub success: bool,
+    /// 終了コード（シグナルで終了した・起動できなかった場合は `None`）
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+}
+
+/// 設定済みのフック
+#[derive(Default)]
+pub struct HookState {
+    hooks: Mutex<Vec<Hook>>,
+}
+
+impl HookState {
+    /// フックが設定されているタイミング（設定順）
+    pub fn events(&self) -> Vec<HookEvent> {
+        let mut events = Vec::new();
+        for hook in self.hooks.lock().unwrap().iter() {
+            if !events.contains(&hook.event) {
+                events.push(hook.event);
+            }
+        }
+        events
+    }
+}
+
+/// シェルの 1 引数として扱われるよう引用符で囲む
+fn shell_quote(value: &str) -> String {
+    if cfg!(target_os = "windows") {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// 置き換える変数（名前と値）
+pub fn variables(
+    path: Option<&Path>,
+    output: Option<&str>,
+    vault: Option<&str>,
+) -> Vec<(&'static str, String)> {
+    let mut vars = Vec::new();
+    if let Some(path) = path {
+        let name = |p: Option<&std::ffi::OsStr>| p.map(|n| n.to_string_lossy().into_owned());
+        vars.push(("file", path_string(path)));
+        vars.push(("dir", path.parent().map(path_string).unwrap_or_default()));
+        vars.push(("name", name(path.file_name()).unwrap_or_default()));
+        vars.push(("stem", name(path.file_stem()).unwrap_or_default()));
+    }
+    if let Some(output) = output {
+        vars.push(("output", output.to_string()));
+    }
+    if let Some(vault) = vault {
+        vars.push(("vault", vault.to_string()));
+    }
+    vars
+}
+
+/// 変数を置き換える（置き換えた値の中はもう一度置き換えない）
+fn expand(command: &str, vars: &[(&str, String)]) -> String {
+    let mut expanded = String::with_capacity(command.len());
+    let mut rest = command;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let var = rest.find('}').and_then(|end| {
+            let (_, value) = vars.iter().find(|(name, _)| *name == &rest[1..end])?;
+            Some((end, value))
+        });
+        match var {
+            Some((end, value)) => {
+                expanded.push_str(&shell_quote(value));
+                rest = &rest[end + 1..];
+            }
+            None => {
+                expanded.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// シェルでコマンドを実行する `Command`（Windows では `cmd /C`、ほかは `sh -c`）
+pub fn shell(command: &str) -> Command {
+    if cfg!(target_os = "windows") {
+        let mut c = Command::new("cmd");
+        c.arg("/C").arg(command);
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(command);
+        c
+    }
+}
+
+pub fn run_hook(hook: &Hook, vars: &[(&str, String)], default_cwd: Option<&Path>) -> HookResult {
+    let command = expand(&hook.command, vars);
+    let started = Instant::now();
+    let mut result = HookResult {
+        event: hook.event,
+        command: command.clone(),
+        success: false,
+        exit_code: None,
+        stdout: String::new(),
+        stderr: String::new(),
+        timed_out: false,
+        duration_ms: 0,
+    };
+
+    let mut process = shell(&command);
+    for (name, value) in vars {
+        process.env(format!("MDVIM_{}", name.to_ascii_uppercase()), value);
+    }
+    process.env("MDVIM_EVENT", hook.event.name());
+    match hook.cwd.as_deref().map(Path::new).or(default_cwd) {
+        Some(cwd) if cwd.is_dir() => {
+            process.current_dir(cwd);
+        }
+        _ => {}
+    }
+    let timeout = Duration::from_secs(hook.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    let output = shell::run(
+        process,
+        &command,
+        RunOptions {
+            timeout,
+            max_stdout: MAX_OUTPUT,
+            max_stderr: MAX_OUTPUT,
+            ..Default::default()
+        },
+    );
+    match output {
+        Ok(output) => {
+            result.stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+            result.stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+            result.exit_code = output.status.and_then(|s| s.code());
+            result.success = output.status.is_some_and(|s| s.success());
+            result.timed_out = output.timed_out;
+        }
+        Err(e) => result.stderr = e,
+    }
+    result.duration_ms = started.elapsed().as_millis() as u64;
+    result
+}
+
+/// フックを設定（以前の設定は置き換える）
+#[tauri::command]
+pub fn configure_hooks(state: State<'_, AppState>, hooks: Vec<Hook>) {
+    *state.hooks.hooks.lock().unwrap() = hooks;
+}
+
+/// 現在のフックの設定
+#[tauri::command]
+pub fn get_hooks(state: State<'_, AppState>) -> Vec<Hook> {
+    state.hooks.hooks.lock().unwrap().clone()
+}
+
+/// `event` のフックを設定順に実行して結果を返す
+///
+/// `path` は対象のファイル、`output` はエクスポート先。
+/// ワークスペース（`vault`、なければ `path`）を信頼していなければエラーにする。
+#[tauri::command(async)]
+pub fn run_hooks(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    event: HookEvent,
+    path: Option<String>,
+    output: Option<String>,
+    vault: Option<String>,
+) -> Result<Vec<HookResult>, String> {
+    let hooks: Vec<Hook> = state
+        .hooks
+        .hooks
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|h| h.event == event)
+        .cloned()
+        .collect();
+    if hooks.is_empty() {
+        return Ok(Vec::new());
+    }
+    match vault.as_deref().or(path.as_deref()) {
+        Some(workspace) => state.trust.require(&app, Path::new(workspace), "hooks")?,
+        None => return Err(tr!("Hooks require a trusted workspace")),
+    }
+    let path = path.map(|p| vault::normalize(Path::new(&p)));
+    let vars = variables(path.as_deref(), output.as_deref(), vault.as_deref());
+    let cwd = path.as_deref().and_then(Path::parent);
+    Ok(hooks
+        .iter()
+        .filter(|hook| {
+            hook.extensions.is_empty()
+                || path
+                    .as_ref()
+                    .and_then(|p| p.extension())
+                    .is_some_and(|ext| {
+                        hook.extensions.iter().any(|e| {
+                            e.trim_start_matches('.')
+                                .eq_ignore_ascii_case(&ext.to_string_lossy())
+                        })
+                    })
+        })
+        .map(|hook| run_hook(hook, &vars, cwd))
+        .collect())
+}
@@ -0,0 +1,134 @@
+// リンクにカーソルを合わせたときのプレビュー（リンク先ノートのタイトルと冒頭）
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::export;
+use crate::links;
+use crate::markdown;
+use crate::refactor::read;
+use crate::render;
+use crate::vault::{self, path_string, Vault};
+
+/// 表示する行数の既定値
+const DEFAULT_LINES: usize = 10;
+
+/// プレビューカードの内容
+#[derive(Debug, Serialize)]
+pub struct HoverPreview {
+    pub path: String,
+    /// 見出しへのリンクはその見出し、それ以外は文書のタイトル
+    pub title: String,
+    /// 冒頭の行を HTML にしたもの（画像などのリンク先は絶対パス）
+    pub html: String,
+    /// 表示しきれない続きがある
+    pub truncated: bool,
+}
+
+/// リンク先の表記を分解する（`[[ノート#見出し|別名]]`・`path.md#anchor`）
+fn parse_target(target: &str) -> (String, Option<String>, bool) {
+    let trimmed = target.trim().trim_start_matches('!');
+    let (inner, wiki) = match trimmed
+        .strip_prefix("[[")
+        .and_then(|t| t.strip_suffix("]]"))
+    {
+        Some(inner) => (inner.split('|').next().unwrap_or(inner), true),
+        None => (trimmed.trim_start_matches('<').trim_end_matches('>'), false),
+    };
+    match inner.split_once('#') {
+        Some((path, anchor)) => (
+            path.trim().to_string(),
+            Some(anchor.trim().to_string()),
+            wiki,
+        ),
+        None => (inner.trim().to_string(), None, wiki),
+    }
+}
+
+fn resolve(vault: &Vault, base: &Path, target: &str, wiki: bool) -> Option<PathBuf> {
+    let path = vault.resolve_target(base, target, wiki)?;
+    if path.is_file() {
+        return Some(path);
+    }
+    // 拡張子のない通常のリンクはウィキリンクとしても探す
+    let bare = !wiki && Path::new(target).extension().is_none();
+    bare.then(|| vault.resolve_target(base, target, true))
+        .flatten()
+        .filter(|p| p.is_file())
+}
+
+/// 内部リンク・ウィキリンクの先のタイトルと冒頭 `lines` 行（リンク先がなければ `None`）
+#[tauri::command]
+pub fn get_hover_preview(
+    target: String,
+    base_path: String,
+    vault_root: Option<String>,
+    lines: Option<usize>,
+) -> Result<Option<HoverPreview>, String> {
+    let (target, anchor, wiki) = parse_target(&target);
+    if !wiki && links::is_external(&target) && !target.is_empty() {
+        return Ok(None);
+    }
+    let base = vault::normalize(Path::new(&base_path));
+    let root = vault_root
+        .map(PathBuf::from)
+        .unwrap_or_else(|| base.parent().map(Path::to_path_buf).unwrap_or_default());
+    let vault = Vault::scan(&root);
+    let Some(path) = resolve(&vault, &base, &target, wiki) else {
+        return Ok(None);
+    };
+    if !vault::is_markdown(&path) {
+        return Ok(None);
+    }
+    let content = read(&path)?;
+
+    // 見出しへのリンクはそのセクション、それ以外はフロントマターと先頭の H1 を除いた本文
+    let section = anchor
+        .as_deref()
+        .filter(|a| !a.is_empty() && !a.starts_with('^'))
+        .and_then(|a| markdown::heading_section(&content, a));
+    let headings = markdown::headings(&content);
+    let (title, start, end) = match section {
+        Some(range) => {
+            let heading = headings.iter().find(|h| h.range.start == range.start);
+            let title =
+                heading.map_or_else(|| anchor.clone().unwrap_or_default(), |h| h.text.clone());
+            (
+                title,
+                heading.map_or(range.start, |h| h.range.end),
+                range.end,
+            )
+        }
+        None => {
+            let start = markdown::body_start(&content);
+            let first = headings
+                .iter()
+                .find(|h| h.level == 1 && content[start..h.range.start].trim().is_empty());
+            (
+                export::document_title(&path, &content),
+                first.map_or(start, |h| h.range.end),
+                content.len(),
+            )
+        }
+    };
+
+    let limit = lines.unwrap_or(DEFAULT_LINES).max(1);
+    let body = content[start..end].trim_start_matches(['\r', '\n']);
+    let shown: Vec<&str> = body.lines().take(limit).collect();
+    let truncated = body.lines().skip(limit).any(|line| !line.trim().is_empty());
+    let rewrite = |dest: &str, wiki: bool| {
+        if !wiki && links::is_external(dest) {
+            return None;
+        }
+        let target = dest.split_once('#').map_or(dest, |(target, _)| target);
+        let resolved = vault.resolve_target(&path, target, wiki)?;
+        (!target.is_empty() && !vault::is_markdown(&resolved)).then(|| path_string(&resolved))
+    };
+    Ok(Some(HoverPreview {
+        path: path_string(&path),
+        title,
+        html: render::render_html(&shown.join("\n"), Some(&rewrite)),
+        truncated,
+    }))
+}
@@ -0,0 +1,652 @@
+// HTML から Markdown への変換（Evernote のノートや貼り付けた HTML 向けの簡易版）
+
+use std::collections::HashMap;
+
+use crate::table;
+
+/// 中身を持たない要素
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "wbr",
+    "en-media", "en-todo",
+];
+
+/// 中身を出力しない要素
+const SKIPPED_ELEMENTS: &[&str] = &["head", "script", "style", "title", "noscript", "template"];
+
+/// ブロック要素（前後で行を分ける）
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "center",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "en-note",
+    "figcaption",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "html",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "ul",
+];
+
+enum Node {
+    Element {
+        name: String,
+        attrs: HashMap<String, String>,
+        children: Vec<Node>,
+    },
+    Text(String),
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest[1..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '#'))
+            .map(|i| i + 1);
+        let entity = end
+            .filter(|&e| e > 1 && rest[e..].starts_with(';'))
+            .and_then(|e| {
+                let name = &rest[1..e];
+                let c = match name {
+                    "amp" => '&',
+                    "lt" => '<',
+                    "gt" => '>',
+                    "quot" => '"',
+                    "apos" => '\'',
+                    "nbsp" => ' ',
+                    "copy" => '©',
+                    "reg" => '®',
+                    "trade" => '™',
+                    "hellip" => '…',
+                    "mdash" => '—',
+                    "ndash" => '–',
+                    "lsquo" => '‘',
+                    "rsquo" => '’',
+                    "ldquo" => '“',
+                    "rdquo" => '”',
+                    "bull" => '•',
+                    "middot" => '·',
+                    "times" => '×',
+                    "yen" => '¥',
+                    "euro" => '€',
+                    _ => {
+                        let code = match name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+                            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                            None => name.strip_prefix('#').and_then(|d| d.parse().ok()),
+                        };
+                        char::from_u32(code?)?
+                    }
+                };
+                Some((c, e + 1))
+            });
+        match entity {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 開始タグの名前と属性
+fn parse_tag(tag: &str) -> (String, HashMap<String, String>) {
+    let tag = tag.trim_end_matches('/').trim();
+    let name_end = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+    let name = tag[..name_end].to_ascii_lowercase();
+    let mut attrs = HashMap::new();
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = rest[..key_end].to_ascii_lowercase();
+        rest = rest[key_end..].trim_start();
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (v, remaining) = match after.chars().next() {
+                Some(q @ ('"' | '\'')) => {
+                    let body = &after[1..];
+                    let close = body.find(q).unwrap_or(body.len());
+                    (&body[..close], body.get(close + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = decode_entities(v);
+            rest = remaining.trim_start();
+        }
+        if !key.is_empty() {
+            attrs.insert(key, value);
+        }
+    }
+    (name, attrs)
+}
+
+/// HTML を要素の木にする（閉じ忘れや余分な閉じタグは適当に補う）
+fn parse(html: &str) -> Vec<Node> {
+    // (要素名, 属性, 子)
+    let mut stack: Vec<(String, HashMap<String, String>, Vec<Node>)> =
+        vec![(String::new(), HashMap::new(), Vec::new())];
+    let close = |stack: &mut Vec<(String, HashMap<String, String>, Vec<Node>)>| {
+        let (name, attrs, children) = stack.pop().unwrap();
+        stack.last_mut().unwrap().2.push(Node::Element {
+            name,
+            attrs,
+            children,
+        });
+    };
+    let mut rest = html;
+    while !rest.is_empty() {
+        let Some(lt) = rest.find('<') else {
+            stack
+                .last_mut()
+                .unwrap()
+                .2
+                .push(Node::Text(decode_entities(rest)));
+            break;
+        };
+        if lt > 0 {
+            let text = decode_entities(&rest[..lt]);
+            stack.last_mut().unwrap().2.push(Node::Text(text));
+        }
+        rest = &rest[lt..];
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |i| &after[i + 3..]);
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>").unwrap_or(after.len());
+            stack
+                .last_mut()
+                .unwrap()
+                .2
+                .push(Node::Text(after[..end].to_string()));
+            rest = after.get(end + 3..).unwrap_or("");
+            continue;
+        }
+        let Some(gt) = rest.find('>') else {
+            stack
+                .last_mut()
+                .unwrap()
+                .2
+                .push(Node::Text(rest.to_string()));
+            break;
+        };
+        let tag = &rest[1..gt];
+        rest = &rest[gt + 1..];
+        if tag.starts_with('!') || tag.starts_with('?') {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim().to_ascii_lowercase();
+            if let Some(depth) = stack.iter().rposition(|(n, _, _)| *n == name) {
+                if depth > 0 {
+                    while stack.len() > depth {
+                        close(&mut stack);
+                    }
+                }
+            }
+            continue;
+        }
+        let self_closing = tag.ends_with('/');
+        let (name, attrs) = parse_tag(tag);
+        if name.is_empty() {
+            continue;
+        }
+        // `<p>` と `<li>` は同じ要素が始まったら閉じる
+        if matches!(name.as_str(), "p" | "li" | "dt" | "dd" | "tr" | "td" | "th") {
+            let closes: &[&str] = match name.as_str() {
+                "li" => &["li"],
+                "dt" | "dd" => &["dt", "dd"],
+                "tr" => &["tr"],
+                "td" | "th" => &["td", "th"],
+                _ => &["p"],
+            };
+            if closes.contains(&stack.last().unwrap().0.as_str()) {
+                close(&mut stack);
+            }
+        }
+        if self_closing || VOID_ELEMENTS.contains(&name.as_str()) {
+            stack.last_mut().unwrap().2.push(Node::Element {
+                name,
+                attrs,
+                children: Vec::new(),
+            });
+        } else {
+            stack.push((name, attrs, Vec::new()));
+        }
+    }
+    while stack.len() > 1 {
+        close(&mut stack);
+    }
+    stack.pop().unwrap().2
+}
+
+/// 改行（`<br>`）を表す仮の文字（空白の正規化で消えないように）
+const LINE_BREAK: char = '\u{0}';
+
+/// Markdown の記号として解釈される文字をエスケープ
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            space = true;
+        } else {
+            if space && !out.is_empty() && !out.ends_with(LINE_BREAK) && c != LINE_BREAK {
+                out.push(' ');
+            }
+            space = false;
+            out.push(c);
+        }
+    }
+    if space && !out.is_empty() {
+        out.push(' ');
+    }
+    out
+}
+
+fn text_content(nodes: &[Node]) -> String {
+    nodes
+        .iter()
+        .map(|node| match node {
+            Node::Text(text) => text.clone(),
+            Node::Element { name, children, .. } if name == "br" => {
+                format!("\n{}", text_content(children))
+            }
+            Node::Element { children, .. } => text_content(children),
+        })
+        .collect()
+}
+
+/// リストの項目で始まるか
+fn is_list(text: &str) -> bool {
+    text.starts_with("- ")
+        || text
+            .split_once(". ")
+            .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// 区切り方（`Tight` は `<div>` の行のように空行を挟まない）
+#[derive(Clone, Copy, PartialEq)]
+enum Spacing {
+    Tight,
+    Loose,
+}
+
+/// `<en-media>` の `hash` と `type` から（リンク先, 名前）を返す
+pub type MediaResolver<'a> = dyn Fn(&str, &str) -> Option<(String, String)> + 'a;
+
+struct Converter<'a> {
+    media: Option<&'a MediaResolver<'a>>,
+}
+
+impl Converter<'_> {
+    fn inline(&self, nodes: &[Node]) -> String {
+        let mut out = String::new();
+        for node in nodes {
+            match node {
+                Node::Text(text) => out.push_str(&escape_text(text)),
+                Node::Element {
+                    name,
+                    attrs,
+                    children,
+                } => out.push_str(&self.inline_element(name, attrs, children)),
+            }
+        }
+        out
+    }
+
+    fn inline_element(
+        &self,
+        name: &str,
+        attrs: &HashMap<String, String>,
+        children: &[Node],
+    ) -> String {
+        let wrap = |mark: &str| {
+            let inner = collapse_whitespace(&self.inline(children));
+            let trimmed = inner.trim();
+            if trimmed.is_empty() {
+                inner
+            } else {
+                // 記号の内側に空白があると強調にならないので外に出す
+                let lead = if inner.starts_with(' ') { " " } else { "" };
+                let trail = if inner.ends_with(' ') { " " } else { "" };
+                format!("{lead}{mark}{trimmed}{mark}{trail}")
+            }
+        };
+        match name {
+            _ if SKIPPED_ELEMENTS.contains(&name) => String::new(),
+            "br" => LINE_BREAK.to_string(),
+            "strong" | "b" => wrap("**"),
+            "em" | "i" | "cite" => wrap("*"),
+            "s" | "del" | "strike" => wrap("~~"),
+            "code" | "kbd" | "samp" | "tt" => {
+                let code = text_content(children);
+                let fence = if code.contains('`') { "``" } else { "`" };
+                format!("{fence}{code}{fence}")
+            }
+            "mark" => format!("<mark>{}</mark>", self.inline(children)),
+            "sup" => format!("<sup>{}</sup>", self.inline(children)),
+            "sub" => format!("<sub>{}</sub>", self.inline(children)),
+            "a" => {
+                let label = collapse_whitespace(&self.inline(children));
+                match attrs
+                    .get("href")
+                    .filter(|h| !h.is_empty() && !h.starts_with("javascript:"))
+                {
+                    Some(href) if label.trim().is_empty() => format!("<{href}>"),
+                    Some(href) => format!("[{}]({})", label.trim(), href.replace(' ', "%20")),
+                    None => label,
+                }
+            }
+            "img" => {
+                let alt = attrs.get("alt").map_or("", String::as_str);
+                match attrs.get("src") {
+                    Some(src) => format!("![{}]({})", escape_text(alt), src.replace(' ', "%20")),
+                    None => String::new(),
+                }
+            }
+            "en-todo" => {
+                let checked = attrs.get("checked").is_some_and(|v| v == "true");
+                if checked { "[x] " } else { "[ ] " }.to_string()
+            }
+            "en-media" => {
+                let hash = attrs.get("hash").map_or("", String::as_str);
+                let kind = attrs.get("type").map_or("", String::as_str);
+                match self.media.and_then(|resolve| resolve(hash, kind)) {
+                    Some((target, name)) if kind.starts_with("image/") => {
+                        format!("![{}]({target})", escape_text(&name))
+                    }
+                    Some((target, name)) => format!("[{}]({target})", escape_text(&name)),
+                    None => String::new(),
+                }
+            }
+            _ if BLOCK_ELEMENTS.contains(&name) => {
+                // 行内に置かれたブロック要素（表のセル内の段落など）
+                format!("{LINE_BREAK}{}{LINE_BREAK}", self.inline(children))
+            }
+            _ => self.inline(children),
+        }
+    }
+
+    /// 行内の内容を 1 つのブロックに整える
+    fn finish_inline(text: &str) -> String {
+        let text = collapse_whitespace(text);
+        text.split(LINE_BREAK)
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_matches('\n')
+            .to_string()
+    }
+
+    /// ブロックの並び（要素ごとの Markdown と前の要素との区切り方）
+    fn blocks(&self, nodes: &[Node]) -> Vec<(String, Spacing)> {
+        let mut blocks = Vec::new();
+        let mut inline = String::new();
+        let flush = |inline: &mut String, blocks: &mut Vec<(String, Spacing)>| {
+            let text = Self::finish_inline(inline);
+            if !text.is_empty() {
+                blocks.push((text, Spacing::Tight));
+            }
+            inline.clear();
+        };
+        for node in nodes {
+            let Node::Element {
+                name,
+                attrs,
+                children,
+            } = node
+            else {
+                if let Node::Text(text) = node {
+                    inline.push_str(&escape_text(text));
+                }
+                continue;
+            };
+            if !BLOCK_ELEMENTS.contains(&name.as_str()) {
+                inline.push_str(&self.inline_element(name, attrs, children));
+                continue;
+            }
+            flush(&mut inline, &mut blocks);
+            let block = self.block(name, children);
+            // 空の `<div>`（`<div><br></div>`）は空行として扱う
+            if name == "div" && block.0.is_empty() {
+                blocks.push((String::new(), Spacing::Tight));
+            } else if !block.0.is_empty() {
+                blocks.push(block);
+            }
+        }
+        flush(&mut inline, &mut blocks);
+        blocks
+    }
+
+    fn join(blocks: Vec<(String, Spacing)>) -> String {
+        let mut out = String::new();
+        let mut previous: Option<Spacing> = None;
+        for (text, spacing) in blocks {
+            if let Some(previous) = previous {
+                out.push('\n');
+                if previous == Spacing::Loose || spacing == Spacing::Loose {
+                    out.push('\n');
+                }
+            }
+            out.push_str(&text);
+            previous = Some(spacing);
+        }
+        // 空の行が続いたものはまとめる
+        while out.contains("\n\n\n") {
+            out = out.replace("\n\n\n", "\n\n");
+        }
+        out.trim_matches('\n').to_string()
+    }
+
+    fn list(&self, ordered: bool, children: &[Node]) -> String {
+        let mut items = Vec::new();
+        let mut number = 1;
+        for child in children {
+            let Node::Element { name, children, .. } = child else {
+                continue;
+            };
+            if name != "li" {
+                continue;
+            }
+            let marker = if ordered {
+                format!("{number}.")
+            } else {
+                "-".to_string()
+            };
+            number += 1;
+            // 入れ子のリストは項目の直後に続ける
+            let blocks = self
+                .blocks(children)
+                .into_iter()
+                .map(|(text, spacing)| match text.chars().next() {
+                    Some('-' | '0'..='9') if spacing == Spacing::Loose && is_list(&text) => {
+                        (text, Spacing::Tight)
+                    }
+                    _ => (text, spacing),
+                })
+                .collect();
+            let body = Self::join(blocks);
+            let indent = " ".repeat(marker.len() + 1);
+            let mut lines = body.lines();
+            let mut item = format!("{marker} {}", lines.next().unwrap_or_default());
+            for line in lines {
+                item.push('\n');
+                if !line.is_empty() {
+                    item.push_str(&indent);
+                    item.push_str(line);
+                }
+            }
+            items.push(item);
+        }
+        items.join("\n")
+    }
+
+    fn table(&self, children: &[Node]) -> String {
+        let mut rows = Vec::new();
+        fn collect_rows<'n>(nodes: &'n [Node], rows: &mut Vec<&'n [Node]>) {
+            for node in nodes {
+                if let Node::Element { name, children, .. } = node {
+                    match name.as_str() {
+                        "tr" => rows.push(children),
+                        "thead" | "tbody" | "tfoot" => collect_rows(children, rows),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        collect_rows(children, &mut rows);
+        let cells: Vec<Vec<String>> = rows
+            .into_iter()
+            .map(|row| {
+                row.iter()
+                    .filter_map(|cell| match cell {
+                        Node::Element { name, children, .. } if name == "td" || name == "th" => {
+                            let text = Self::finish_inline(&self.inline(children));
+                            Some(table::escape_cell(&text))
+                        }
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .filter(|row: &Vec<String>| !row.is_empty())
+            .collect();
+        if cells.is_empty() {
+            return String::new();
+        }
+        table::format_table(cells, false).trim_end().to_string()
+    }
+
+    fn block(&self, name: &str, children: &[Node]) -> (String, Spacing) {
+        let loose = |text: String| (text, Spacing::Loose);
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                let text = Self::finish_inline(&self.inline(children)).replace('\n', " ");
+                loose(format!("{} {text}", "#".repeat(level)))
+            }
+            "p" => loose(Self::join(self.blocks(children))),
+            "hr" => loose("---".to_string()),
+            "pre" => {
+                let code = text_content(children);
+                let language = children.iter().find_map(|c| match c {
+                    Node::Element { name, attrs, .. } if name == "code" => attrs
+                        .get("class")
+                        .and_then(|c| {
+                            c.split_whitespace()
+                                .find_map(|c| c.strip_prefix("language-"))
+                        })
+                        .map(str::to_string),
+                    _ => None,
+                });
+                let fence = if code.contains("```") { "````" } else { "```" };
+                loose(format!(
+                    "{fence}{}\n{}\n{fence}",
+                    language.unwrap_or_default(),
+                    code.trim_end_matches('\n')
+                ))
+            }
+            "blockquote" => {
+                let body = Self::join(self.blocks(children));
+                loose(
+                    body.lines()
+                        .map(|l| format!("> {l}").trim_end().to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                )
+            }
+            "ul" => loose(self.list(false, children)),
+            "ol" => loose(self.list(true, children)),
+            "table" => loose(self.table(children)),
+            "dt" => loose(format!(
+                "**{}**",
+                Self::finish_inline(&self.inline(children))
+            )),
+            "dd" => (
+                format!(": {}", Self::finish_inline(&self.inline(children))),
+                Spacing::Tight,
+            ),
+            _ if SKIPPED_ELEMENTS.contains(&name) => (String::new(), Spacing::Tight),
+            // div などは中身の区切りをそのまま使う（Evernote は 1 行を 1 つの div にする）
+            _ => {
+                let blocks = self.blocks(children);
+                let spacing = blocks
+                    .iter()
+                    .map(|(_, s)| *s)
+                    .find(|s| *s == Spacing::Loose)
+                    .unwrap_or(Spacing::Tight);
+                (Self::join(blocks), spacing)
+            }
+        }
+    }
+}
+
+/// HTML を Markdown に変換（`media` は Evernote の `<en-media>` の解決に使う）
+pub fn html_to_markdown(html: &str, media: Option<&MediaResolver>) -> String {
+    let nodes = parse(html);
+    let converter = Converter { media };
+    let markdown = Converter::join(converter.blocks(&nodes));
+    // リストの外のチェックボックス（Evernote の `<div><en-todo/>…</div>`）はタスクリストにする
+    let mut out: String = markdown
+        .lines()
+        .map(|line| {
+            if line.starts_with("[ ] ") || line.starts_with("[x] ") {
+                format!("- {line}\n")
+            } else {
+                format!("{line}\n")
+            }
+        })
+        .collect();
+    if out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
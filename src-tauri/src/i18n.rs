@@ -0,0 +1,476 @@
+// バックエンドが返す文言（エラー・既定のタイトルなど）の翻訳
+//
+// 文言は英語で書き、`tr!` で組み立てる（書き方は `format!` と同じ）。英語以外の言語では、書式の文字列を
+// 鍵にして訳文を探し、英語の文言から埋め込んだ値を取り出して訳文に差し込む。訳のない文言は英語のまま返す。
+//
+// 言語は保存した設定、なければ環境変数（`LC_ALL`・`LC_MESSAGES`・`LANG`）から決める。
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::{LazyLock, RwLock};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::app_data;
+
+/// 設定フォルダ内のファイル
+const LOCALE_FILE: &str = "locale.json";
+
+/// 表示の言語
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Ja,
+}
+
+impl Locale {
+    /// `ja`・`ja-JP`・`ja_JP.UTF-8` などから（対応していない言語は `None`）
+    pub fn parse(lang: &str) -> Option<Self> {
+        let lang = lang.trim().to_ascii_lowercase();
+        let primary = lang.split(['-', '_', '.', '@']).next().unwrap_or_default();
+        match primary {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "ja" => Some(Locale::Ja),
+            _ => None,
+        }
+    }
+
+    /// HTML の `lang` 属性などに使う言語コード
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Ja => "ja",
+        }
+    }
+}
+
+/// 保存する設定（`lang` がなければ環境から決める）
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct LocaleSettings {
+    lang: Option<Locale>,
+}
+
+static LOCALE: RwLock<Locale> = RwLock::new(Locale::En);
+
+/// 英語の書式（埋め込む値は `{}`）と日本語の訳（`{0}` `{1}` … で英語の順に値を参照する）
+const JA: &[(&str, &str)] = &[
+    ("Failed to read {}: {}", "{0} を読み込めませんでした: {1}"),
+    ("Failed to write {}: {}", "{0} に書き込めませんでした: {1}"),
+    ("Failed to create {}: {}", "{0} を作成できませんでした: {1}"),
+    ("Failed to open {}: {}", "{0} を開けませんでした: {1}"),
+    ("Failed to copy {}: {}", "{0} をコピーできませんでした: {1}"),
+    ("Failed to parse {}: {}", "{0} を解析できませんでした: {1}"),
+    ("Failed to include {}: {}", "{0} を埋め込めませんでした: {1}"),
+    ("Failed to back up {}: {}", "{0} の控えを作れませんでした: {1}"),
+    ("Failed to decompress {}: {}", "{0} を展開できませんでした: {1}"),
+    ("Archive entry is too large: {}", "アーカイブ内のファイルが大きすぎます: {0}"),
+    ("Archive entry size mismatch: {}", "アーカイブ内のファイルの大きさが一致しません: {0}"),
+    ("Failed to run {}: {}", "{0} を実行できませんでした: {1}"),
+    ("Failed to start {}: {}", "{0} を起動できませんでした: {1}"),
+    ("Failed to start {}", "{0} を起動できませんでした"),
+    ("Failed to connect to {}: {}", "{0} に接続できませんでした: {1}"),
+    (
+        "Failed to read response from {}: {}",
+        "{0} からの応答を読み込めませんでした: {1}",
+    ),
+    ("Invalid response from {}: {}", "{0} からの応答が不正です: {1}"),
+    (
+        "Failed to locate config directory: {}",
+        "設定フォルダが見つかりません: {0}",
+    ),
+    (
+        "Failed to locate data directory: {}",
+        "データフォルダが見つかりません: {0}",
+    ),
+    ("Preprocessor {} failed: {}", "前処理 {0} が失敗しました: {1}"),
+    ("Preprocessor {} timed out after {} s", "前処理 {0} が {1} 秒で終わりませんでした"),
+    ("Preprocessor {} output exceeded {} bytes", "前処理 {0} の出力が {1} バイトを超えました"),
+    (
+        "Invalid preprocessor pattern {}: {}",
+        "前処理の正規表現 {0} が不正です: {1}",
+    ),
+    ("Failed to encode PNG: {}", "PNG に変換できませんでした: {0}"),
+    ("Failed to decode PNG: {}", "PNG を読み込めませんでした: {0}"),
+    (
+        "Failed to read the clipboard: {}",
+        "クリップボードを読み込めませんでした: {0}",
+    ),
+    ("File not found: {}", "ファイルが見つかりません: {0}"),
+    ("File already exists: {}", "ファイルはすでに存在します: {0}"),
+    ("Folder not found: {}", "フォルダが見つかりません: {0}"),
+    ("Invalid file name: {}", "ファイル名が不正です: {0}"),
+    ("Invalid {}: {}", "{0} が不正です: {1}"),
+    ("Invalid date: {}", "日付が不正です: {0}"),
+    ("Invalid date format: {}", "日付の書式が不正です: {0}"),
+    ("Invalid timezone: {}", "タイムゾーンが不正です: {0}"),
+    ("Invalid duration: {}", "期間が不正です: {0}"),
+    ("Date out of range", "日付が範囲外です"),
+    ("Invalid range: {}", "範囲が不正です: {0}"),
+    ("Invalid range in edit {}", "{0} 番目の置き換えの範囲が不正です"),
+    ("Invalid position: {}:{}", "位置が不正です: {0}:{1}"),
+    ("Overlapping edits: {} and {}", "{0} 番目と {1} 番目の置き換えが重なっています"),
+    ("Invalid key: {}", "キーが不正です: {0}"),
+    ("Invalid mark: {}", "マークが不正です: {0}"),
+    ("Invalid register: {}", "レジスタが不正です: {0}"),
+    ("Invalid image data: {}", "画像のデータが不正です: {0}"),
+    ("Unknown command: {}", "不明なコマンドです: {0}"),
+    ("Unknown flag: {}", "不明なフラグです: {0}"),
+    ("Not an editor command: {}", "エディタのコマンドではありません: {0}"),
+    ("Invalid range", "範囲が正しくありません"),
+    ("Pattern not found: {}", "パターンが見つかりません: {0}"),
+    ("Mark not set: {}", "マークが設定されていません: {0}"),
+    ("No previous regular expression", "前回の検索パターンがありません"),
+    ("Trailing characters: {}", "余分な文字があります: {0}"),
+    ("Nothing in register", "レジスタが空です"),
+    ("Cannot use :global recursively", ":global は入れ子にできません"),
+    ("Cannot move a range of lines into itself", "行の範囲をその中へは移動できません"),
+    ("{} matches on {} lines", "{1} 行で {0} 件一致しました"),
+    ("Unsupported key in :normal: {}", ":normal では使えないキーです: {0}"),
+    ("External filters require a trusted workspace", "外部フィルタには信頼したワークスペースが必要です"),
+    (
+        "Input is too large for a filter ({} bytes, limit {})",
+        "フィルタに渡す入力が大きすぎます（{0} バイト、上限 {1}）",
+    ),
+    ("No filter command", "フィルタのコマンドがありません"),
+    ("Filter {} timed out after {} s", "フィルタ {0} が {1} 秒で終わりませんでした"),
+    ("Filter {} failed: {}", "フィルタ {0} が失敗しました: {1}"),
+    ("Filter output exceeded {} bytes", "フィルタの出力が {0} バイトを超えました"),
+    ("Filter {} returned invalid UTF-8", "フィルタ {0} の出力が UTF-8 ではありません"),
+    ("No shell command", "シェルのコマンドがありません"),
+    ("Code execution is not enabled for {}", "{0} のコードの実行は有効になっていません"),
+    ("[timed out]", "[タイムアウト]"),
+    ("[exit code {}]", "[終了コード {0}]"),
+    ("No code block at line {}", "{0} 行目にコードブロックがありません"),
+    ("Invalid formula: {}", "式が正しくありません: {0}"),
+    ("A range cannot be used here", "ここでは範囲を使えません"),
+    ("Division by zero", "0 で割りました"),
+    ("Numeric overflow: {}", "数値が大きすぎます: {0}"),
+    ("No numbers for {}", "{0} に渡す数値がありません"),
+    ("Not a number: @{}${}", "数値ではありません: @{0}${1}"),
+    ("Reference out of range: {}", "参照が表の外です: {0}"),
+    ("Unknown function: {}", "不明な関数です: {0}"),
+    ("Could not understand date: {}", "日付を解釈できません: {0}"),
+    ("Invalid time: {}", "時刻が不正です: {0}"),
+    ("{} tasks are due", "期日のタスクが {0} 件あります"),
+    ("Task overdue ({})", "期日を過ぎたタスク（{0}）"),
+    ("Task due today", "今日が期日のタスク"),
+    ("No column {} (the board has {})", "列 {0} はありません（列の数は {1}）"),
+    ("No card {} in column {}", "列 {1} にカード {0} はありません"),
+    ("Invalid limit: {}", "行の上限が不正です: {0}"),
+    ("Unknown data block option: {}", "データブロックの不明な項目です: {0}"),
+    ("No data source in the block", "ブロックにデータのパスがありません"),
+    ("Unsupported chart type: {}", "対応していないグラフの種類です: {0}"),
+    ("Row {} has {} fields, expected {}", "{0} 行目の項目の数が {1} です（{2} のはずです）"),
+    ("Unsupported data layout in JSON", "JSON のデータの形に対応していません"),
+    ("Data file is too large ({} bytes, limit {})", "データのファイルが大きすぎます（{0} バイト、上限 {1}）"),
+    ("Unsupported data file: {}", "対応していないデータのファイルです: {0}"),
+    ("Unknown column: {}", "不明な列です: {0}"),
+    ("Column {} is not numeric", "列 {0} は数値ではありません"),
+    ("No numeric columns to chart", "グラフにする数値の列がありません"),
+    ("Not a data block: {}", "データブロックではありません: {0}"),
+    ("Export profile not found: {}", "エクスポートの設定がありません: {0}"),
+    ("Export profile name is empty", "エクスポートの設定の名前が空です"),
+    ("Invalid glob: {}", "glob が不正です: {0}"),
+    ("Output file already exists: {}", "書き出し先のファイルがすでにあります: {0}"),
+    ("Unknown export format: {}", "不明な書き出し形式です: {0}"),
+    ("Unsupported diagram: {}", "対応していない図です: {0}"),
+    ("Include cycle: {}", "埋め込みが循環しています: {0}"),
+    (
+        "Includes are nested too deeply at {}",
+        "{0} で埋め込みの入れ子が深すぎます",
+    ),
+    ("include requires a file path", "include にはファイルのパスが必要です"),
+    ("Cancelled", "中断しました"),
+    ("Task {} is already running", "処理 {0} はすでに実行中です"),
+    (
+        "Workspace is not trusted: {} ({} is disabled)",
+        "ワークスペース {0} は信頼されていません（{1} は使えません）",
+    ),
+    (
+        "Workspace is not trusted: cannot include {} from outside {}",
+        "ワークスペースは信頼されていません: {1} の外にある {0} は埋め込めません",
+    ),
+    (
+        "Workspace is not trusted: preprocessor {} is disabled",
+        "ワークスペースは信頼されていません: 前処理 {0} は使えません",
+    ),
+    (
+        "Hooks require a trusted workspace",
+        "フックは信頼したワークスペースでだけ使えます",
+    ),
+    (
+        "Diagrams require a trusted workspace",
+        "図の外部レンダラーは信頼したワークスペースでだけ使えます",
+    ),
+    ("Preview server is not running", "プレビューサーバーは起動していません"),
+    (
+        "No PDF converter found. Install wkhtmltopdf or Chrome/Chromium.",
+        "PDF に変換するツールが見つかりません。wkhtmltopdf または Chrome/Chromium をインストールしてください。",
+    ),
+    ("Unsupported language: {}", "対応していない言語です: {0}"),
+    ("Cannot read front matter line {}", "フロントマターの {0} 行目を読み取れません"),
+    ("Missing required field: {}", "必須の項目がありません: {0}"),
+    ("Unknown field: {}", "不明な項目です: {0}"),
+    ("{} must be {}, not {}", "{0} は {2} ではなく {1} にしてください"),
+    ("{} must be one of {}", "{0} は {1} のいずれかにしてください"),
+    ("{} must be {}", "{0} は {1} にしてください"),
+    ("{} must be at least {} characters", "{0} は {1} 文字以上にしてください"),
+    ("{} must be at most {} characters", "{0} は {1} 文字以下にしてください"),
+    ("{} must match {}", "{0} は {1} に一致させてください"),
+    ("{} must be a valid {}", "{0} は正しい {1} の形式にしてください"),
+    ("{} must be at least {}", "{0} は {1} 以上にしてください"),
+    ("{} must be at most {}", "{0} は {1} 以下にしてください"),
+    ("{} must be greater than {}", "{0} は {1} より大きくしてください"),
+    ("{} must be less than {}", "{0} は {1} より小さくしてください"),
+    ("{} must have at least {} items", "{0} は {1} 個以上にしてください"),
+    ("{} must have at most {} items", "{0} は {1} 個以下にしてください"),
+    ("{} must not contain duplicates", "{0} に重複があります"),
+    ("Invalid pattern {} in schema: {}", "スキーマの正規表現 {0} が不正です: {1}"),
+    ("No date in front matter ({})", "フロントマターに日付がありません（{0}）"),
+    ("Missing front matter field: {}", "フロントマターに項目がありません: {0}"),
+    ("Target is outside the vault: {}", "移動先が保管庫の外です: {0}"),
+    ("Invalid pattern {}: {}", "正規表現 {0} が不正です: {1}"),
+    ("Changes", "変更点"),
+    ("No changes", "変更はありません"),
+    ("Before", "変更前"),
+    ("After", "変更後"),
+    ("{} unchanged lines", "変更のない {0} 行"),
+    ("{} lines added, {} lines removed", "{0} 行を追加、{1} 行を削除"),
+    ("Annotations", "注釈"),
+    ("Annotation not found: {}", "注釈が見つかりません: {0}"),
+    ("Untitled", "無題"),
+    ("Invalid response from AI server: {}", "AI サーバーの応答が正しくありません: {0}"),
+    ("AI server returned an error: {}", "AI サーバーがエラーを返しました: {0}"),
+    ("AI request failed ({}): {}", "AI へのリクエストに失敗しました（{0}）: {1}"),
+    ("API key \"{}\" is not set", "API キー「{0}」が設定されていません"),
+    ("Failed to move {} to {}: {}", "{0} を {1} に移動できませんでした: {2}"),
+    ("Backup interval must be at least 1 minute", "バックアップの間隔は 1 分以上にしてください"),
+    ("Backups are not configured", "バックアップが設定されていません"),
+    ("Backup was not created", "バックアップは作成されませんでした"),
+    ("Bookmark not found: {}", "ブックマークが見つかりません: {0}"),
+    ("Window and region capture are not supported on Windows", "Windows ではウィンドウと範囲のキャプチャに対応していません"),
+    ("Screenshot was cancelled", "スクリーンショットは取り消されました"),
+    ("Screenshot failed: {}", "スクリーンショットに失敗しました: {0}"),
+    ("No screenshot tool found (install gnome-screenshot, spectacle, grim, scrot or ImageMagick)", "スクリーンショットのツールが見つかりません（gnome-screenshot・spectacle・grim・scrot・ImageMagick のいずれかを入れてください）"),
+    ("{}. Install wl-clipboard or xclip to read the clipboard.", "{0}。クリップボードを読むには wl-clipboard か xclip を入れてください。"),
+    ("Invalid session code", "セッションのコードが正しくありません"),
+    ("Participant ID is already in use", "参加者の ID がすでに使われています"),
+    ("Failed to generate random number: {}", "乱数を生成できませんでした: {0}"),
+    ("Failed to start collaboration session: {}", "共同編集のセッションを開始できませんでした: {0}"),
+    ("Invalid address: {}", "アドレスが正しくありません: {0}"),
+    ("No response from collaboration host", "共同編集のホストから応答がありません"),
+    ("Disconnected from host", "ホストとの接続が切れました"),
+    ("Not in a collaboration session", "共同編集のセッションに参加していません"),
+    ("No rows found", "行がありません"),
+    ("{} failed: {}", "{0} が失敗しました: {1}"),
+    ("{} returned invalid UTF-8", "{0} が UTF-8 でない出力を返しました"),
+    ("Failed to compress data: {}", "データを圧縮できませんでした: {0}"),
+    ("PlantUML server returned {}", "PlantUML サーバーが {0} を返しました"),
+    ("PlantUML server request failed", "PlantUML サーバーへのリクエストが失敗しました"),
+    ("{} timed out after {} s", "{0} が {1} 秒で終わりませんでした"),
+    ("{} output exceeded {} bytes", "{0} の出力が {1} バイトを超えました"),
+    ("PlantUML is not configured (set a server or plantuml.jar)", "PlantUML が設定されていません（サーバーか plantuml.jar を設定してください）"),
+    ("Invalid task state: {}", "タスクの状態が正しくありません: {0}"),
+    ("Download task state", "タスクの状態をダウンロード"),
+    ("PDF conversion failed: {}", "PDF に変換できませんでした: {0}"),
+    ("Permission denied: {} is not writable", "権限がありません: {0} には書き込めません"),
+    ("Grammar check failed ({}): {}", "文法チェックに失敗しました（{0}）: {1}"),
+    ("Unsupported PNG color type", "対応していない PNG の色の種類です"),
+    ("{}. Install libwebp to convert to WebP.", "{0}。WebP に変換するには libwebp を入れてください。"),
+    ("cwebp failed: {}", "cwebp が失敗しました: {0}"),
+    ("Unrecognized image format", "画像の形式が分かりません"),
+    ("Unrecognized image format: {}", "画像の形式が分かりません: {0}"),
+    ("Image not found", "画像が見つかりません"),
+    ("Invalid image data in notebook: {}", "ノートブックの画像データが正しくありません: {0}"),
+    ("Unsupported notebook format (nbformat 4 is required)", "対応していないノートブックの形式です（nbformat 4 が必要です）"),
+    ("Invalid notebook: {}", "ノートブックが正しくありません: {0}"),
+    ("Failed to serialize result: {}", "結果を JSON にできませんでした: {0}"),
+    ("Failed to serialize key bindings: {}", "キー割り当てを書き出せませんでした: {0}"),
+    ("Keychain is not available: {}", "キーチェーンを使えません: {0}"),
+    ("Keychain command failed ({})", "キーチェーンのコマンドが失敗しました（{0}）"),
+    ("Keychain command failed: {}", "キーチェーンのコマンドが失敗しました: {0}"),
+    ("API key name is empty", "API キーの名前が空です"),
+    ("Failed to write to language server: {}", "言語サーバーに書き込めませんでした: {0}"),
+    ("Language server did not respond to {}", "言語サーバーが {0} に応答しませんでした"),
+    ("Language server error: {}", "言語サーバーのエラー: {0}"),
+    ("Language server exited", "言語サーバーが終了しました"),
+    ("Language server is not running: {}", "言語サーバーは起動していません: {0}"),
+    ("Macro not found: {}", "マクロが見つかりません: {0}"),
+    ("Unsupported macros file version: {}", "対応していないマクロのファイルのバージョンです: {0}"),
+    ("No TextBundle found in {}", "{0} に TextBundle がありません"),
+    ("Invalid ENEX file: {}", "ENEX ファイルが正しくありません: {0}"),
+    ("Invalid attachment data in ENEX file: {}", "ENEX ファイルの添付ファイルのデータが正しくありません: {0}"),
+    ("{}. Install Tesseract OCR and add it to PATH.", "{0}。Tesseract OCR を入れて PATH に加えてください。"),
+    ("tesseract failed: {}", "tesseract が失敗しました: {0}"),
+    ("{}. Install Poppler to OCR PDF files.", "{0}。PDF の文字認識には Poppler を入れてください。"),
+    ("pdftoppm failed with {}", "pdftoppm が失敗しました（{0}）"),
+    ("Plugin does not have the {} permission", "プラグインに {0} の権限がありません"),
+    ("Unknown host function {}.{}", "不明なホスト関数です: {0}.{1}"),
+    ("Plugin {} failed: {}", "プラグイン {0} が失敗しました: {1}"),
+    ("{} must return an i64", "{0} は i64 を返す必要があります"),
+    ("Plugin {} imports unsupported function {}.{}", "プラグイン {0} が対応していない関数 {1}.{2} を使っています"),
+    ("Plugin {} does not export alloc", "プラグイン {0} に alloc がありません"),
+    ("Plugin not found: {}", "プラグインが見つかりません: {0}"),
+    ("Plugin {} returned invalid UTF-8", "プラグイン {0} が UTF-8 でない出力を返しました"),
+    ("Plugin is not enabled: {}", "プラグインは有効になっていません: {0}"),
+    ("Unknown plugin command: {}", "不明なプラグインのコマンドです: {0}"),
+    ("Preprocessor {} returned invalid UTF-8", "前処理 {0} が UTF-8 でない出力を返しました"),
+    ("Failed to start preview server: {}", "プレビューサーバーを起動できませんでした: {0}"),
+    ("Opus encoding requires ffmpeg or opusenc", "Opus での保存には ffmpeg か opusenc が必要です"),
+    ("Already recording", "すでに録音しています"),
+    ("Not recording", "録音していません"),
+    ("Recording produced no audio", "録音した音声がありません"),
+    ("Heading text must not be empty", "見出しを空にはできません"),
+    ("Heading not found: {}", "見出しが見つかりません: {0}"),
+    ("Saved search name is empty", "保存した検索の名前が空です"),
+    ("Saved search not found: {}", "保存した検索が見つかりません: {0}"),
+    ("Invalid response from embedding server", "埋め込みのサーバーの応答が正しくありません"),
+    ("Embedding server returned a wrong number of vectors", "埋め込みのサーバーが返したベクトルの数が合いません"),
+    ("Embedding request failed ({}): {}", "埋め込みのリクエストに失敗しました（{0}）: {1}"),
+    ("Invalid embedding in response", "応答の埋め込みが正しくありません"),
+    ("Embedding model is not specified", "埋め込みのモデルが指定されていません"),
+    ("Semantic index is not built", "意味検索の索引が作られていません"),
+    ("Semantic index was built with a different embedding model; rebuild it", "意味検索の索引は別の埋め込みのモデルで作られています。作り直してください"),
+    ("Failed to embed query", "検索語を埋め込めませんでした"),
+    ("Unterminated quoted field in CSV", "CSV の引用符が閉じられていません"),
+    ("No table found", "表がありません"),
+    ("Template not found: {}", "テンプレートが見つかりません: {0}"),
+    ("Translation request failed ({}): {}", "翻訳のリクエストに失敗しました（{0}）: {1}"),
+    ("Translation server returned a wrong number of texts", "翻訳のサーバーが返した文の数が合いません"),
+    ("Invalid response from translation server", "翻訳のサーバーの応答が正しくありません"),
+    ("Target language is empty", "翻訳先の言語が空です"),
+    ("Trust settings are not loaded", "信頼の設定が読み込まれていません"),
+    ("No trust decision for {}", "{0} の信頼の判断はありません"),
+    ("Failed to start speech synthesizer: {}", "音声合成を開始できませんでした: {0}"),
+    ("Failed to serialize undo history: {}", "元に戻す履歴を保存できませんでした: {0}"),
+    ("Undo history for {} is too large", "{0} の元に戻す履歴が大きすぎます"),
+    ("Invalid option: {}", "オプションが正しくありません: {0}"),
+    ("Unsupported variable: {}", "対応していない変数です: {0}"),
+    ("Incomplete mapping: {}", "マッピングが不完全です: {0}"),
+    ("Incomplete abbreviation: {}", "短縮入力が不完全です: {0}"),
+    ("Unsupported command: {}", "対応していないコマンドです: {0}"),
+    ("Invalid spreadsheet XML: {}", "スプレッドシートの XML が正しくありません: {0}"),
+    ("Not an Excel workbook: {}", "Excel のブックではありません: {0}"),
+    ("Not an Excel workbook: xl/workbook.xml is missing", "Excel のブックではありません: xl/workbook.xml がありません"),
+    ("Sheet not found: {}", "シートが見つかりません: {0}"),
+    ("Sheet data is missing: {}", "シートのデータがありません: {0}"),
+    ("The sheet is empty", "シートが空です"),
+    ("Failed to write archive: {}", "アーカイブに書き込めませんでした: {0}"),
+    ("Too many files for a zip archive", "ZIP アーカイブに入れるファイルが多すぎます"),
+    ("Archive is too large", "アーカイブが大きすぎます"),
+    ("Invalid zip archive", "ZIP アーカイブが正しくありません"),
+    ("Encrypted entry is not supported: {}", "暗号化されたファイルには対応していません: {0}"),
+    ("Unsupported compression method {}: {}", "対応していない圧縮方式 {0} です: {1}"),
+    ("Checksum mismatch: {}", "チェックサムが一致しません: {0}"),
+    ("Unsafe path in archive: {}", "アーカイブに安全でないパスがあります: {0}"),
+];
+
+/// 書式の文字列の埋め込み（`{}` `{name}` `{:?}` など。`{{` `}}` は除く）
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{|\}\}|\{[^{}]*\}").unwrap());
+
+/// 訳文と、英語の文言から値を取り出す正規表現
+type Catalog = HashMap<&'static str, (Regex, &'static str)>;
+
+static CATALOG_JA: LazyLock<Catalog> = LazyLock::new(|| {
+    JA.iter()
+        .map(|&(english, translated)| {
+            let pattern = english
+                .split("{}")
+                .map(regex::escape)
+                .collect::<Vec<_>>()
+                .join("(.*?)");
+            let re = Regex::new(&format!("(?s)^{pattern}$")).unwrap();
+            (english, (re, translated))
+        })
+        .collect()
+});
+
+/// 書式の文字列の埋め込みを `{}` にそろえる（`{{` `}}` は文字そのものにする）
+fn normalize(format: &str) -> String {
+    PLACEHOLDER
+        .replace_all(format, |caps: &regex::Captures| match &caps[0] {
+            "{{" => "{",
+            "}}" => "}",
+            _ => "{}",
+        })
+        .into_owned()
+}
+
+/// 今の言語
+pub fn locale() -> Locale {
+    *LOCALE.read().unwrap()
+}
+
+/// `format` で組み立てた英語の文言 `english` を今の言語に訳す（`tr!` から呼ぶ）
+pub fn translate(format: &str, english: String) -> String {
+    let catalog = match locale() {
+        Locale::En => return english,
+        Locale::Ja => &*CATALOG_JA,
+    };
+    let Some((re, translated)) = catalog.get(normalize(format).as_str()) else {
+        return english;
+    };
+    let Some(caps) = re.captures(&english) else {
+        return english;
+    };
+    let mut out = translated.to_string();
+    for i in 1..caps.len() {
+        out = out.replace(&format!("{{{}}}", i - 1), &caps[i]);
+    }
+    out
+}
+
+/// `format!` と同じ書き方で文言を組み立て、今の言語に訳す
+#[macro_export]
+macro_rules! tr {
+    ($format:literal $(, $($arg:tt)*)?) => {
+        $crate::i18n::translate($format, format!($format $(, $($arg)*)?))
+    };
+}
+
+/// 環境変数から言語を決める（分からなければ英語）
+fn detect() -> Locale {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| Locale::parse(&value))
+        .unwrap_or_default()
+}
+
+/// 起動時に言語を決める（ほかの設定を読み込む前に呼ぶ）
+pub fn load_at_startup(app: &AppHandle) {
+    let saved = app_data::config_file(app, LOCALE_FILE)
+        .and_then(|file| app_data::read_json::<LocaleSettings>(&file))
+        .ok()
+        .and_then(|settings| settings.lang);
+    *LOCALE.write().unwrap() = saved.unwrap_or_else(detect);
+}
+
+/// 今の言語のコード
+#[tauri::command]
+pub fn get_locale() -> &'static str {
+    locale().code()
+}
+
+/// バックエンドの文言の言語を設定して保存する（`auto` なら環境から決める。設定した言語のコードを返す）
+#[tauri::command]
+pub fn set_locale(app: AppHandle, lang: String) -> Result<&'static str, String> {
+    let chosen = match lang.trim() {
+        "" | "auto" => None,
+        lang => Some(Locale::parse(lang).ok_or_else(|| tr!("Unsupported language: {lang}"))?),
+    };
+    app_data::write_json(
+        &app_data::config_file(&app, LOCALE_FILE)?,
+        &LocaleSettings { lang: chosen },
+    )?;
+    let locale = chosen.unwrap_or_else(detect);
+    *LOCALE.write().unwrap() = locale;
+    Ok(locale.code())
+}
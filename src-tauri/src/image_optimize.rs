@@ -0,0 +1,1078 @@
+// 貼り付け・ドロップした画像の保存時の最適化（縮小・形式の変換・メタデータの除去）
+//
+// image クレートは依存に含まれていないため、PNG の読み書きは png クレート、JPEG への変換は
+// 下の手書きのエンコーダ（ベースライン・4:4:4 のみ）、WebP は `cwebp` で行う。JPEG は
+// デコードしないので、縮小せずメタデータの除去だけを行う。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::images;
+use crate::shell::{self, RunOptions};
+use crate::state::AppState;
+use crate::tr;
+use crate::vault::path_string;
+
+/// `cwebp` を待つ時間
+const WEBP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 取り込むエラー出力の上限
+const MAX_STDERR: usize = 64 << 10;
+
+/// 変換後の形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConvertFormat {
+    Jpeg,
+    Webp,
+}
+
+/// 保存時の最適化の設定
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ImageOptimization {
+    pub enabled: bool,
+    /// 幅・高さの最大値（超える画像は縦横比を保って縮小する。PNG のみ）
+    pub max_dimension: Option<u32>,
+    /// PNG（スクリーンショット）を変換する形式（透過のある画像は JPEG にしない）
+    pub convert_png: Option<ConvertFormat>,
+    /// JPEG・WebP の品質（1〜100）
+    pub quality: u8,
+    /// EXIF などのメタデータを除く（JPEG の向きの情報は残す）
+    pub strip_metadata: bool,
+    /// WebP への変換に使う `cwebp` コマンド
+    pub cwebp_path: String,
+}
+
+impl Default for ImageOptimization {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_dimension: Some(2048),
+            convert_png: None,
+            quality: 85,
+            strip_metadata: true,
+            cwebp_path: "cwebp".to_string(),
+        }
+    }
+}
+
+/// 最適化の設定
+#[derive(Default)]
+pub struct ImageOptimizeState {
+    options: Mutex<ImageOptimization>,
+}
+
+impl ImageOptimizeState {
+    pub fn get(&self) -> ImageOptimization {
+        self.options.lock().unwrap().clone()
+    }
+}
+
+/// 保存した画像
+#[derive(Debug, Serialize)]
+pub struct SavedImage {
+    /// 実際に保存したパス（変換した場合は拡張子が変わる）
+    pub path: String,
+    pub format: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub size: u64,
+    /// 最適化する前のバイト数
+    pub original_size: u64,
+}
+
+/// RGBA（8 ビット）の画素
+struct Rgba {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Rgba {
+    fn opaque(&self) -> bool {
+        self.pixels.chunks_exact(4).all(|p| p[3] == 255)
+    }
+
+    /// 長辺が `max` 以下になるように面積平均で縮小する
+    fn downscale(&self, max: u32) -> Option<Rgba> {
+        let longest = self.width.max(self.height);
+        if max == 0 || longest <= max {
+            return None;
+        }
+        let scale = max as f64 / longest as f64;
+        let width = ((self.width as f64 * scale).round() as u32).max(1);
+        let height = ((self.height as f64 * scale).round() as u32).max(1);
+        let (sw, sh) = (self.width as usize, self.height as usize);
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height as usize {
+            let y0 = y * sh / height as usize;
+            let y1 = ((y + 1) * sh / height as usize).max(y0 + 1);
+            for x in 0..width as usize {
+                let x0 = x * sw / width as usize;
+                let x1 = ((x + 1) * sw / width as usize).max(x0 + 1);
+                let mut sum = [0u64; 4];
+                for row in y0..y1 {
+                    for pixel in
+                        self.pixels[(row * sw + x0) * 4..(row * sw + x1) * 4].chunks_exact(4)
+                    {
+                        sum.iter_mut().zip(pixel).for_each(|(s, &c)| *s += c as u64);
+                    }
+                }
+                let count = ((y1 - y0) * (x1 - x0)) as u64;
+                pixels.extend(sum.iter().map(|s| ((s + count / 2) / count) as u8));
+            }
+        }
+        Some(Rgba {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+fn decode_png(data: &[u8]) -> Result<Rgba, String> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| tr!("Failed to decode PNG: {e}"))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader
+        .next_frame(&mut buffer)
+        .map_err(|e| tr!("Failed to decode PNG: {e}"))?;
+    buffer.truncate(frame.buffer_size());
+    let pixels = match frame.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+        png::ColorType::Indexed => return Err(tr!("Unsupported PNG color type")),
+    };
+    Ok(Rgba {
+        width: frame.width,
+        height: frame.height,
+        pixels,
+    })
+}
+
+fn encode_png(image: &Rgba) -> Result<Vec<u8>, String> {
+    let opaque = image.opaque();
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, image.width, image.height);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(png::Compression::Best);
+    let data: Vec<u8> = if opaque {
+        encoder.set_color(png::ColorType::Rgb);
+        image
+            .pixels
+            .chunks_exact(4)
+            .flat_map(|p| [p[0], p[1], p[2]])
+            .collect()
+    } else {
+        encoder.set_color(png::ColorType::Rgba);
+        image.pixels.clone()
+    };
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| tr!("Failed to encode PNG: {e}"))?;
+    writer
+        .write_image_data(&data)
+        .map_err(|e| tr!("Failed to encode PNG: {e}"))?;
+    writer
+        .finish()
+        .map_err(|e| tr!("Failed to encode PNG: {e}"))?;
+    Ok(out)
+}
+
+/// PNG のチャンク（長さ・種類・データ）
+fn png_chunks(data: &[u8]) -> Option<Vec<(&[u8], &[u8])>> {
+    let mut chunks = Vec::new();
+    let mut at = 8;
+    while at + 12 <= data.len() {
+        let length = u32::from_be_bytes(data[at..at + 4].try_into().ok()?) as usize;
+        let end = at.checked_add(12 + length).filter(|&e| e <= data.len())?;
+        chunks.push((&data[at + 4..at + 8], &data[at..end]));
+        at = end;
+    }
+    Some(chunks)
+}
+
+/// PNG からテキストと EXIF のチャンクを除く（画素は再圧縮しない）
+fn strip_png(data: &[u8]) -> Vec<u8> {
+    let Some(chunks) = png_chunks(data) else {
+        return data.to_vec();
+    };
+    let mut out = data[..8].to_vec();
+    for (kind, chunk) in chunks {
+        if !matches!(kind, b"tEXt" | b"zTXt" | b"iTXt" | b"eXIf" | b"tIME") {
+            out.extend_from_slice(chunk);
+        }
+    }
+    out
+}
+
+/// APNG（アニメーション）か
+fn is_animated_png(data: &[u8]) -> bool {
+    png_chunks(data).is_some_and(|chunks| chunks.iter().any(|(kind, _)| *kind == b"acTL"))
+}
+
+/// EXIF の向き（1〜8）
+fn exif_orientation(exif: &[u8]) -> Option<u16> {
+    let tiff = exif.strip_prefix(b"Exif\0\0")?;
+    let big = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |at: usize| {
+        let b: [u8; 2] = tiff.get(at..at + 2)?.try_into().ok()?;
+        Some(if big {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        })
+    };
+    let u32_at = |at: usize| {
+        let b: [u8; 4] = tiff.get(at..at + 4)?.try_into().ok()?;
+        Some(if big {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        })
+    };
+    let ifd = u32_at(4)? as usize;
+    (0..u16_at(ifd)? as usize)
+        .map(|i| ifd + 2 + i * 12)
+        .find(|&entry| u16_at(entry) == Some(0x0112))
+        .and_then(|entry| u16_at(entry + 8))
+}
+
+/// 向きだけを持つ EXIF の APP1 セグメント
+fn orientation_segment(orientation: u16) -> Vec<u8> {
+    let mut segment = vec![0xff, 0xe1, 0, 34];
+    segment.extend_from_slice(b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01");
+    segment.extend_from_slice(&orientation.to_be_bytes());
+    segment.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    segment
+}
+
+/// JPEG から EXIF・XMP・IPTC・コメントを除く（向きの情報は残す）
+fn strip_jpeg(data: &[u8]) -> Vec<u8> {
+    let mut out = data[..2].to_vec();
+    let mut at = 2;
+    while at + 4 <= data.len() && data[at] == 0xff {
+        let marker = data[at + 1];
+        if marker == 0xda {
+            break;
+        }
+        let length = u16::from_be_bytes([data[at + 2], data[at + 3]]) as usize;
+        let Some(segment) = data.get(at..at + 2 + length) else {
+            return data.to_vec();
+        };
+        match marker {
+            0xe1 => {
+                if let Some(orientation) = exif_orientation(&segment[4..]).filter(|&o| o != 1) {
+                    out.extend(orientation_segment(orientation));
+                }
+            }
+            0xed | 0xfe => {}
+            _ => out.extend_from_slice(segment),
+        }
+        at += 2 + length;
+    }
+    out.extend_from_slice(&data[at..]);
+    out
+}
+
+/// 画素を WebP にする（`cwebp` を利用）
+fn encode_webp(image: &Rgba, options: &ImageOptimization) -> Result<Vec<u8>, String> {
+    let work = std::env::temp_dir().join(format!("mdvim-webp-{}", std::process::id()));
+    fs::create_dir_all(&work).map_err(|e| tr!("Failed to create {}: {e}", work.display()))?;
+    let (input, output) = (work.join("input.png"), work.join("output.webp"));
+    let result = (|| {
+        fs::write(&input, encode_png(image)?)
+            .map_err(|e| tr!("Failed to write {}: {e}", input.display()))?;
+        let mut command = Command::new(&options.cwebp_path);
+        command
+            .args(["-quiet", "-metadata", "none", "-q"])
+            .arg(options.quality.clamp(1, 100).to_string())
+            .arg(&input)
+            .arg("-o")
+            .arg(&output);
+        let result = shell::run(
+            command,
+            "cwebp",
+            RunOptions {
+                timeout: WEBP_TIMEOUT,
+                max_stdout: MAX_STDERR,
+                max_stderr: MAX_STDERR,
+                ..Default::default()
+            },
+        )
+        .map_err(|e| tr!("{e}. Install libwebp to convert to WebP."))?;
+        let Some(status) = result.status else {
+            return Err(tr!(
+                "{} timed out after {} s",
+                "cwebp",
+                WEBP_TIMEOUT.as_secs()
+            ));
+        };
+        if !status.success() {
+            return Err(tr!(
+                "cwebp failed: {}",
+                String::from_utf8_lossy(&result.stderr).trim()
+            ));
+        }
+        fs::read(&output).map_err(|e| tr!("Failed to read {}: {e}", output.display()))
+    })();
+    let _ = fs::remove_dir_all(&work);
+    result
+}
+
+/// 最適化する（PNG は縮小・変換、JPEG はメタデータの除去のみ）。戻り値は形式とデータ
+fn optimize(data: &[u8], options: &ImageOptimization) -> Result<(&'static str, Vec<u8>), String> {
+    let Some((format, ..)) = images::probe_bytes(data) else {
+        return Err(tr!("Unrecognized image format"));
+    };
+    match format {
+        "png" if !is_animated_png(data) => {
+            let mut image = decode_png(data)?;
+            let resized = match options.max_dimension.and_then(|max| image.downscale(max)) {
+                Some(smaller) => {
+                    image = smaller;
+                    true
+                }
+                None => false,
+            };
+            match options.convert_png {
+                // JPEG は 65535 px までで、透過がないものに限る
+                Some(ConvertFormat::Jpeg)
+                    if image.opaque() && image.width.max(image.height) <= u16::MAX as u32 =>
+                {
+                    Ok(("jpeg", jpeg::encode(&image, options.quality)))
+                }
+                Some(ConvertFormat::Webp) => Ok(("webp", encode_webp(&image, options)?)),
+                _ if resized => encode_png(&image).map(|png| ("png", png)),
+                _ if options.strip_metadata => Ok(("png", strip_png(data))),
+                _ => Ok(("png", data.to_vec())),
+            }
+        }
+        "png" if options.strip_metadata => Ok(("png", strip_png(data))),
+        "jpeg" if options.strip_metadata => Ok(("jpeg", strip_jpeg(data))),
+        _ => Ok((format, data.to_vec())),
+    }
+}
+
+/// 形式に合わせた拡張子にし、既存のファイルと重ならないパスにする
+fn output_path(path: &Path, format: &str) -> PathBuf {
+    let extension = match format {
+        "jpeg" => "jpg",
+        other => other,
+    };
+    let current = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    let same = match current.as_deref() {
+        Some("jpg" | "jpeg") => format == "jpeg",
+        Some(current) => current == extension,
+        None => false,
+    };
+    let path = if same {
+        path.to_path_buf()
+    } else {
+        path.with_extension(extension)
+    };
+    if !path.exists() {
+        return path;
+    }
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("image")
+        .to_string();
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or(extension)
+        .to_string();
+    (1..)
+        .map(|n| path.with_file_name(format!("{stem}-{n}.{extension}")))
+        .find(|p| !p.exists())
+        .unwrap()
+}
+
+/// 画像の保存時の最適化を設定する
+#[tauri::command]
+pub fn configure_image_optimization(state: State<'_, AppState>, options: ImageOptimization) {
+    *state.image_optimize.options.lock().unwrap() = options;
+}
+
+/// 画像の保存時の最適化の設定を取得する
+#[tauri::command]
+pub fn get_image_optimization(state: State<'_, AppState>) -> ImageOptimization {
+    state.image_optimize.get()
+}
+
+/// 画像を保存する。最適化が有効なら縮小・変換してから保存する
+pub fn write_image(
+    options: &ImageOptimization,
+    path: &Path,
+    data: &[u8],
+) -> Result<SavedImage, String> {
+    let (format, optimized) = if options.enabled {
+        optimize(data, options)?
+    } else {
+        let format = images::probe_bytes(data).map_or("unknown", |(format, ..)| format);
+        (format, data.to_vec())
+    };
+    let target = if options.enabled {
+        output_path(path, format)
+    } else {
+        path.to_path_buf()
+    };
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| tr!("Failed to create {}: {e}", parent.display()))?;
+    }
+    fs::write(&target, &optimized).map_err(|e| tr!("Failed to write {}: {e}", target.display()))?;
+    let (width, height) = match images::probe_bytes(&optimized) {
+        Some((_, width, height)) => (width, height),
+        None => (None, None),
+    };
+    Ok(SavedImage {
+        path: path_string(&target),
+        format: format.to_string(),
+        width,
+        height,
+        size: optimized.len() as u64,
+        original_size: data.len() as u64,
+    })
+}
+
+/// 貼り付け・ドロップした画像（Base64）を保存する
+#[tauri::command]
+pub fn save_image(
+    state: State<'_, AppState>,
+    path: String,
+    data: String,
+) -> Result<SavedImage, String> {
+    let data = STANDARD
+        .decode(data.trim())
+        .map_err(|e| tr!("Invalid image data: {e}"))?;
+    write_image(&state.image_optimize.get(), Path::new(&path), &data)
+}
+
+/// ベースライン JPEG のエンコーダ（4:4:4・標準のハフマン表）
+mod jpeg {
+    use super::Rgba;
+
+    const ZIGZAG: [usize; 64] = [
+        0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27,
+        20, 13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51,
+        58, 59, 52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+    ];
+
+    const LUMA_QUANT: [u8; 64] = [
+        16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69,
+        56, 14, 17, 22, 29, 51, 87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81,
+        104, 113, 92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+    ];
+
+    const CHROMA_QUANT: [u8; 64] = [
+        17, 18, 24, 47, 99, 99, 99, 99, 18, 21, 26, 66, 99, 99, 99, 99, 24, 26, 56, 99, 99, 99, 99,
+        99, 47, 66, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+        99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+    ];
+
+    const DC_LUMA_BITS: [u8; 16] = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+    const DC_CHROMA_BITS: [u8; 16] = [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0];
+    const DC_VALUES: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+    const AC_LUMA_BITS: [u8; 16] = [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7d];
+    const AC_LUMA_VALUES: [u8; 162] = [
+        0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61,
+        0x07, 0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xa1, 0x08, 0x23, 0x42, 0xb1, 0xc1, 0x15, 0x52,
+        0xd1, 0xf0, 0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0a, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x25,
+        0x26, 0x27, 0x28, 0x29, 0x2a, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45,
+        0x46, 0x47, 0x48, 0x49, 0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64,
+        0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x83,
+        0x84, 0x85, 0x86, 0x87, 0x88, 0x89, 0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99,
+        0x9a, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7, 0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6,
+        0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3,
+        0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda, 0xe1, 0xe2, 0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8,
+        0xe9, 0xea, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa,
+    ];
+
+    const AC_CHROMA_BITS: [u8; 16] = [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77];
+    const AC_CHROMA_VALUES: [u8; 162] = [
+        0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61,
+        0x71, 0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91, 0xa1, 0xb1, 0xc1, 0x09, 0x23, 0x33,
+        0x52, 0xf0, 0x15, 0x62, 0x72, 0xd1, 0x0a, 0x16, 0x24, 0x34, 0xe1, 0x25, 0xf1, 0x17, 0x18,
+        0x19, 0x1a, 0x26, 0x27, 0x28, 0x29, 0x2a, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44,
+        0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63,
+        0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a,
+        0x82, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89, 0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97,
+        0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7, 0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4,
+        0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca,
+        0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda, 0xe2, 0xe3, 0xe4, 0xe5, 0xe6, 0xe7,
+        0xe8, 0xe9, 0xea, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa,
+    ];
+
+    /// 記号ごとの（符号, 長さ）
+    struct Huffman {
+        codes: [(u16, u8); 256],
+    }
+
+    impl Huffman {
+        fn new(bits: &[u8; 16], values: &[u8]) -> Self {
+            let mut codes = [(0, 0); 256];
+            let mut code = 0u16;
+            let mut values = values.iter();
+            for (i, &count) in bits.iter().enumerate() {
+                for _ in 0..count {
+                    codes[*values.next().unwrap() as usize] = (code, i as u8 + 1);
+                    code += 1;
+                }
+                code <<= 1;
+            }
+            Self { codes }
+        }
+    }
+
+    struct BitWriter {
+        out: Vec<u8>,
+        byte: u8,
+        count: u8,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, code: u16, length: u8) {
+            for i in (0..length).rev() {
+                self.byte = self.byte << 1 | (code >> i & 1) as u8;
+                self.count += 1;
+                if self.count == 8 {
+                    self.out.push(self.byte);
+                    // 0xFF の後には 0x00 を入れる
+                    if self.byte == 0xff {
+                        self.out.push(0);
+                    }
+                    self.byte = 0;
+                    self.count = 0;
+                }
+            }
+        }
+
+        fn flush(&mut self) {
+            if self.count > 0 {
+                self.write(0x7f, 8 - self.count);
+            }
+        }
+    }
+
+    /// 値の桁数（カテゴリ）と付加ビット
+    fn magnitude(value: i32) -> (u8, u16) {
+        let size = (32 - value.unsigned_abs().leading_zeros()) as u8;
+        let bits = if value < 0 { value - 1 } else { value };
+        (size, (bits & ((1 << size) - 1)) as u16)
+    }
+
+    /// 品質に合わせて量子化表を調整する（IJG と同じ換算）
+    fn scale_quant(base: &[u8; 64], quality: u8) -> [u8; 64] {
+        let quality = quality.clamp(1, 100) as u32;
+        let scale = if quality < 50 {
+            5000 / quality
+        } else {
+            200 - quality * 2
+        };
+        base.map(|q| ((q as u32 * scale + 50) / 100).clamp(1, 255) as u8)
+    }
+
+    struct Component<'a> {
+        quant: &'a [u8; 64],
+        dc: &'a Huffman,
+        ac: &'a Huffman,
+        previous: i32,
+    }
+
+    /// 8×8 のブロックを離散コサイン変換し、量子化して書き込む
+    fn encode_block(
+        writer: &mut BitWriter,
+        block: &[f32; 64],
+        cosines: &[[f32; 8]; 8],
+        component: &mut Component,
+    ) {
+        let mut rows = [0f32; 64];
+        for y in 0..8 {
+            for u in 0..8 {
+                rows[y * 8 + u] = (0..8).map(|x| cosines[u][x] * block[y * 8 + x]).sum();
+            }
+        }
+        let mut coefficients = [0i32; 64];
+        for (k, &index) in ZIGZAG.iter().enumerate() {
+            let (v, u) = (index / 8, index % 8);
+            let value: f32 = (0..8).map(|y| cosines[v][y] * rows[y * 8 + u]).sum();
+            coefficients[k] = (value / component.quant[index] as f32).round() as i32;
+        }
+
+        let (size, bits) = magnitude(coefficients[0] - component.previous);
+        component.previous = coefficients[0];
+        let (code, length) = component.dc.codes[size as usize];
+        writer.write(code, length);
+        writer.write(bits, size);
+
+        let mut run = 0;
+        for &coefficient in &coefficients[1..] {
+            if coefficient == 0 {
+                run += 1;
+                continue;
+            }
+            while run > 15 {
+                let (code, length) = component.ac.codes[0xf0];
+                writer.write(code, length);
+                run -= 16;
+            }
+            let (size, bits) = magnitude(coefficient);
+            let (code, length) = component.ac.codes[(run << 4 | size) as usize];
+            writer.write(code, length);
+            writer.write(bits, size);
+            run = 0;
+        }
+        if run > 0 {
+            let (code, length) = component.ac.codes[0];
+            writer.write(code, length);
+        }
+    }
+
+    fn segment(out: &mut Vec<u8>, marker: u8, data: &[u8]) {
+        out.extend_from_slice(&[0xff, marker]);
+        out.extend_from_slice(&(data.len() as u16 + 2).to_be_bytes());
+        out.extend_from_slice(data);
+    }
+
+    /// 不透明な画素を JPEG にする（透過は白と合成する）
+    pub fn encode(image: &Rgba, quality: u8) -> Vec<u8> {
+        let quants = [
+            scale_quant(&LUMA_QUANT, quality),
+            scale_quant(&CHROMA_QUANT, quality),
+        ];
+        let mut out = vec![0xff, 0xd8];
+        segment(&mut out, 0xe0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0");
+        let mut dqt = Vec::with_capacity(130);
+        for (id, quant) in quants.iter().enumerate() {
+            dqt.push(id as u8);
+            dqt.extend(ZIGZAG.iter().map(|&i| quant[i]));
+        }
+        segment(&mut out, 0xdb, &dqt);
+        let (width, height) = (image.width as u16, image.height as u16);
+        let mut sof = vec![8];
+        sof.extend_from_slice(&height.to_be_bytes());
+        sof.extend_from_slice(&width.to_be_bytes());
+        sof.extend_from_slice(&[3, 1, 0x11, 0, 2, 0x11, 1, 3, 0x11, 1]);
+        segment(&mut out, 0xc0, &sof);
+        let mut dht = Vec::new();
+        for (class, bits, values) in [
+            (0x00, &DC_LUMA_BITS, &DC_VALUES[..]),
+            (0x10, &AC_LUMA_BITS, &AC_LUMA_VALUES[..]),
+            (0x01, &DC_CHROMA_BITS, &DC_VALUES[..]),
+            (0x11, &AC_CHROMA_BITS, &AC_CHROMA_VALUES[..]),
+        ] {
+            dht.push(class);
+            dht.extend_from_slice(bits);
+            dht.extend_from_slice(values);
+        }
+        segment(&mut out, 0xc4, &dht);
+        segment(&mut out, 0xda, &[3, 1, 0x00, 2, 0x11, 3, 0x11, 0, 63, 0]);
+
+        let tables = [
+            Huffman::new(&DC_LUMA_BITS, &DC_VALUES),
+            Huffman::new(&AC_LUMA_BITS, &AC_LUMA_VALUES),
+            Huffman::new(&DC_CHROMA_BITS, &DC_VALUES),
+            Huffman::new(&AC_CHROMA_BITS, &AC_CHROMA_VALUES),
+        ];
+        let mut components = [
+            Component {
+                quant: &quants[0],
+                dc: &tables[0],
+                ac: &tables[1],
+                previous: 0,
+            },
+            Component {
+                quant: &quants[1],
+                dc: &tables[2],
+                ac: &tables[3],
+                previous: 0,
+            },
+            Component {
+                quant: &quants[1],
+                dc: &tables[2],
+                ac: &tables[3],
+                previous: 0,
+            },
+        ];
+        let mut cosines = [[0f32; 8]; 8];
+        for (u, row) in cosines.iter_mut().enumerate() {
+            let c = if u == 0 {
+                std::f32::consts::FRAC_1_SQRT_2
+            } else {
+                1.0
+            };
+            for (x, value) in row.iter_mut().enumerate() {
+                *value =
+                    c / 2.0 * ((2 * x + 1) as f32 * u as f32 * std::f32::consts::PI / 16.0).cos();
+            }
+        }
+
+        let mut writer = BitWriter {
+            out,
+            byte: 0,
+            count: 0,
+        };
+        let (w, h) = (image.width as usize, image.height as usize);
+        let mut pixels = [[0f32; 3]; 64];
+        for by in (0..h).step_by(8) {
+            for bx in (0..w).step_by(8) {
+                for (i, pixel) in pixels.iter_mut().enumerate() {
+                    // 端のブロックは最後の画素を繰り返す
+                    let y = (by + i / 8).min(h - 1);
+                    let x = (bx + i % 8).min(w - 1);
+                    let p = &image.pixels[(y * w + x) * 4..(y * w + x) * 4 + 4];
+                    let alpha = p[3] as f32 / 255.0;
+                    let [r, g, b] =
+                        [p[0], p[1], p[2]].map(|c| c as f32 * alpha + 255.0 * (1.0 - alpha));
+                    *pixel = [
+                        0.299 * r + 0.587 * g + 0.114 * b - 128.0,
+                        -0.168_736 * r - 0.331_264 * g + 0.5 * b,
+                        0.5 * r - 0.418_688 * g - 0.081_312 * b,
+                    ];
+                }
+                for (channel, component) in components.iter_mut().enumerate() {
+                    let block = pixels.map(|p| p[channel]);
+                    encode_block(&mut writer, &block, &cosines, component);
+                }
+            }
+        }
+        writer.flush();
+        let mut out = writer.out;
+        out.extend_from_slice(&[0xff, 0xd9]);
+        out
+    }
+
+    /// `encode` の出力を読むテスト用のデコーダ（ベースライン・サブサンプリングなしのみ）
+    #[cfg(test)]
+    pub(super) fn decode(data: &[u8]) -> Rgba {
+        use std::collections::HashMap;
+
+        assert_eq!(data[..2], [0xff, 0xd8], "missing SOI");
+        let mut quants = [[0u16; 64]; 4];
+        let mut huffman: HashMap<u8, HashMap<(u8, u16), u8>> = HashMap::new();
+        let (mut width, mut height) = (0usize, 0usize);
+        // 成分ごとの（量子化表, DC 表, AC 表）
+        let mut components: Vec<(usize, u8, u8)> = Vec::new();
+        let mut at = 2;
+        let scan = loop {
+            assert_eq!(data[at], 0xff, "expected a marker at {at}");
+            let marker = data[at + 1];
+            let length = u16::from_be_bytes([data[at + 2], data[at + 3]]) as usize;
+            let body = &data[at + 4..at + 2 + length];
+            at += 2 + length;
+            match marker {
+                0xdb => {
+                    for table in body.chunks(65) {
+                        for (k, &q) in table[1..].iter().enumerate() {
+                            quants[table[0] as usize][ZIGZAG[k]] = q as u16;
+                        }
+                    }
+                }
+                0xc0 => {
+                    assert_eq!(body[0], 8);
+                    height = u16::from_be_bytes([body[1], body[2]]) as usize;
+                    width = u16::from_be_bytes([body[3], body[4]]) as usize;
+                    for c in body[6..].chunks(3) {
+                        assert_eq!(c[1], 0x11, "subsampling is not supported");
+                        components.push((c[2] as usize, 0, 0));
+                    }
+                }
+                0xc4 => {
+                    let mut rest = body;
+                    while !rest.is_empty() {
+                        let (class, bits) = (rest[0], &rest[1..17]);
+                        let total: usize = bits.iter().map(|&b| b as usize).sum();
+                        let mut values = rest[17..17 + total].iter();
+                        let table = huffman.entry(class).or_default();
+                        let mut code = 0u16;
+                        for (i, &count) in bits.iter().enumerate() {
+                            for _ in 0..count {
+                                table.insert((i as u8 + 1, code), *values.next().unwrap());
+                                code += 1;
+                            }
+                            code <<= 1;
+                        }
+                        rest = &rest[17 + total..];
+                    }
+                }
+                0xda => {
+                    for (component, c) in components.iter_mut().zip(body[1..].chunks(2)) {
+                        component.1 = c[1] >> 4;
+                        component.2 = 0x10 | (c[1] & 0x0f);
+                    }
+                    break &data[at..];
+                }
+                _ => {}
+            }
+        };
+
+        // 詰め物の 0x00 を除いたビット列
+        let mut bytes = Vec::new();
+        let mut i = 0;
+        while !(scan[i] == 0xff && scan[i + 1] != 0) {
+            bytes.push(scan[i]);
+            i += if scan[i] == 0xff { 2 } else { 1 };
+        }
+        let mut bit = 0usize;
+        let mut read = |n: u8| {
+            let mut value = 0u16;
+            for _ in 0..n {
+                value = value << 1 | (bytes[bit / 8] >> (7 - bit % 8) & 1) as u16;
+                bit += 1;
+            }
+            value
+        };
+        let symbol = |read: &mut dyn FnMut(u8) -> u16, table: &HashMap<(u8, u16), u8>| {
+            let mut code = 0u16;
+            for length in 1..=16 {
+                code = code << 1 | read(1);
+                if let Some(&value) = table.get(&(length, code)) {
+                    return value;
+                }
+            }
+            panic!("invalid Huffman code");
+        };
+        let extend = |value: u16, size: u8| -> i32 {
+            if size == 0 {
+                0
+            } else if value < 1 << (size - 1) {
+                value as i32 - (1 << size) + 1
+            } else {
+                value as i32
+            }
+        };
+
+        let c = |u: usize| {
+            if u == 0 {
+                std::f32::consts::FRAC_1_SQRT_2
+            } else {
+                1.0
+            }
+        };
+        let basis = |x: usize, u: usize| {
+            c(u) / 2.0 * ((2 * x + 1) as f32 * u as f32 * std::f32::consts::PI / 16.0).cos()
+        };
+        let mut planes = vec![vec![0f32; width * height]; components.len()];
+        let mut previous = vec![0i32; components.len()];
+        for by in (0..height).step_by(8) {
+            for bx in (0..width).step_by(8) {
+                for (n, &(quant, dc, ac)) in components.iter().enumerate() {
+                    let mut coefficients = [0f32; 64];
+                    let size = symbol(&mut read, &huffman[&dc]);
+                    previous[n] += extend(read(size), size);
+                    coefficients[0] = (previous[n] * quants[quant][0] as i32) as f32;
+                    let mut k = 1;
+                    while k < 64 {
+                        let rs = symbol(&mut read, &huffman[&ac]);
+                        let (run, size) = ((rs >> 4) as usize, rs & 0x0f);
+                        if size == 0 {
+                            if run != 15 {
+                                break;
+                            }
+                            k += 16;
+                            continue;
+                        }
+                        k += run;
+                        let index = ZIGZAG[k];
+                        coefficients[index] =
+                            (extend(read(size), size) * quants[quant][index] as i32) as f32;
+                        k += 1;
+                    }
+                    for y in 0..8 {
+                        for x in 0..8 {
+                            let (px, py) = (bx + x, by + y);
+                            if px >= width || py >= height {
+                                continue;
+                            }
+                            let mut value = 0.0;
+                            for v in 0..8 {
+                                for u in 0..8 {
+                                    value += basis(x, u) * basis(y, v) * coefficients[v * 8 + u];
+                                }
+                            }
+                            planes[n][py * width + px] = value;
+                        }
+                    }
+                }
+            }
+        }
+        let pixels = (0..width * height)
+            .flat_map(|i| {
+                let (y, cb, cr) = (planes[0][i] + 128.0, planes[1][i], planes[2][i]);
+                [
+                    y + 1.402 * cr,
+                    y - 0.344_136 * cb - 0.714_136 * cr,
+                    y + 1.772 * cb,
+                ]
+                .map(|v| v.round().clamp(0.0, 255.0) as u8)
+                .into_iter()
+                .chain([255])
+            })
+            .collect();
+        Rgba {
+            width: width as u32,
+            height: height as u32,
+            pixels,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 横に赤、縦に緑が変わるグラデーション
+    fn gradient(width: u32, height: u32, alpha: u8) -> Rgba {
+        let pixels = (0..height)
+            .flat_map(|y| {
+                (0..width).flat_map(move |x| {
+                    [
+                        (x * 255 / width) as u8,
+                        (y * 255 / height) as u8,
+                        128,
+                        alpha,
+                    ]
+                })
+            })
+            .collect();
+        Rgba {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn size(image: &Rgba) -> (u32, u32) {
+        (image.width, image.height)
+    }
+
+    fn options() -> ImageOptimization {
+        ImageOptimization {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn jpeg_decodes_to_same_size_and_colors() {
+        for (width, height) in [(1, 1), (8, 8), (21, 13), (64, 3)] {
+            let image = gradient(width, height, 255);
+            let decoded = jpeg::decode(&jpeg::encode(&image, 90));
+            assert_eq!(size(&decoded), (width, height));
+            let error = image
+                .pixels
+                .iter()
+                .zip(&decoded.pixels)
+                .map(|(a, b)| a.abs_diff(*b))
+                .max()
+                .unwrap();
+            assert!(error <= 16, "{width}x{height}: max error {error}");
+        }
+    }
+
+    #[test]
+    fn downscales_png_keeping_aspect_ratio() {
+        let options = ImageOptimization {
+            max_dimension: Some(100),
+            ..options()
+        };
+        let png = encode_png(&gradient(300, 150, 255)).unwrap();
+        let (format, data) = optimize(&png, &options).unwrap();
+        assert_eq!(format, "png");
+        assert_eq!(size(&decode_png(&data).unwrap()), (100, 50));
+
+        // 上限以下の画像は縮小しない
+        let png = encode_png(&gradient(40, 90, 255)).unwrap();
+        let (_, data) = optimize(&png, &options).unwrap();
+        assert_eq!(size(&decode_png(&data).unwrap()), (40, 90));
+    }
+
+    #[test]
+    fn converts_only_opaque_png_to_jpeg() {
+        let options = ImageOptimization {
+            max_dimension: Some(120),
+            convert_png: Some(ConvertFormat::Jpeg),
+            ..options()
+        };
+        let png = encode_png(&gradient(300, 150, 255)).unwrap();
+        let (format, data) = optimize(&png, &options).unwrap();
+        assert_eq!(format, "jpeg");
+        assert_eq!(size(&jpeg::decode(&data)), (120, 60));
+
+        let png = encode_png(&gradient(300, 150, 128)).unwrap();
+        let (format, data) = optimize(&png, &options).unwrap();
+        assert_eq!(format, "png");
+        let decoded = decode_png(&data).unwrap();
+        assert_eq!(size(&decoded), (120, 60));
+        assert_eq!(decoded.pixels[3], 128);
+    }
+
+    #[test]
+    fn downscale_averages_pixels() {
+        let image = Rgba {
+            width: 2,
+            height: 2,
+            pixels: [[0, 0, 0, 255], [255, 255, 255, 255]].repeat(2).concat(),
+        };
+        let small = image.downscale(1).unwrap();
+        assert_eq!(size(&small), (1, 1));
+        assert_eq!(small.pixels, [128, 128, 128, 255]);
+        assert!(image.downscale(2).is_none());
+    }
+
+    #[test]
+    fn strips_metadata_but_keeps_orientation() {
+        // IHDR（シグネチャの後の 25 バイト）の後にテキストのチャンクを入れる
+        let png = encode_png(&gradient(4, 4, 255)).unwrap();
+        let text = b"Comment\0secret";
+        let mut with_text = png[..33].to_vec();
+        with_text.extend((text.len() as u32).to_be_bytes());
+        with_text.extend(b"tEXt");
+        with_text.extend(text);
+        with_text.extend([0; 4]);
+        with_text.extend(&png[33..]);
+        assert_eq!(strip_png(&with_text), png);
+
+        let jpeg = jpeg::encode(&gradient(16, 8, 255), 80);
+        let mut with_exif = jpeg[..2].to_vec();
+        with_exif.extend(orientation_segment(6));
+        with_exif.extend([0xff, 0xfe, 0, 7]);
+        with_exif.extend(b"hello");
+        with_exif.extend(&jpeg[2..]);
+        let stripped = strip_jpeg(&with_exif);
+        assert!(!stripped.windows(5).any(|w| w == b"hello"));
+        assert_eq!(exif_orientation(&stripped[6..]), Some(6));
+        assert_eq!(size(&jpeg::decode(&stripped)), (16, 8));
+    }
+}
@@ -0,0 +1,241 @@
+// 画像の形式と大きさの判定（ヘッダーのみ読む）と、見つからない画像の診断
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::links::{self, LinkKind};
+use crate::lsp::LspRange;
+use crate::text::LineIndex;
+use crate::tr;
+use crate::vault::{self, Vault};
+
+/// 大きさを調べるために読む最大のバイト数（JPEG は EXIF の後に大きさがある）
+const PROBE_LIMIT: u64 = 1024 * 1024;
+
+/// この大きさまでの SVG は HTML に直接埋め込む
+pub const INLINE_SVG_LIMIT: u64 = 32 * 1024;
+
+/// 画像の情報
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageInfo {
+    /// "png" | "jpeg" | "gif" | "webp" | "bmp" | "svg"
+    pub format: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// ファイルのバイト数
+    pub size: u64,
+}
+
+/// 画像の参照の問題
+#[derive(Debug, Serialize)]
+pub struct ImageDiagnostic {
+    pub range: LspRange,
+    pub target: String,
+    pub message: String,
+}
+
+fn be16(data: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn le16(data: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn le24(data: &[u8], at: usize) -> Option<u32> {
+    let b = data.get(at..at + 3)?;
+    Some(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16)
+}
+
+fn jpeg_size(data: &[u8]) -> Option<(u32, u32)> {
+    let mut i = 2;
+    while i + 9 < data.len() {
+        if data[i] != 0xff {
+            i += 1;
+            continue;
+        }
+        let marker = data[i + 1];
+        // SOF0〜SOF15（DHT・JPG・DAC を除く）にフレームの大きさがある
+        if (0xc0..=0xcf).contains(&marker) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+            return Some((be16(data, i + 7)?, be16(data, i + 5)?));
+        }
+        if marker == 0xd8 || marker == 0x01 || (0xd0..=0xd7).contains(&marker) || marker == 0xff {
+            i += if marker == 0xff { 1 } else { 2 };
+            continue;
+        }
+        i += 2 + be16(data, i + 2)? as usize;
+    }
+    None
+}
+
+fn webp_size(data: &[u8]) -> Option<(u32, u32)> {
+    match data.get(12..16)? {
+        b"VP8 " => Some((le16(data, 26)? & 0x3fff, le16(data, 28)? & 0x3fff)),
+        b"VP8L" => {
+            let b = data.get(21..25)?;
+            let width = 1 + (b[0] as u32 | ((b[1] as u32 & 0x3f) << 8));
+            let height =
+                1 + ((b[1] as u32 >> 6) | ((b[2] as u32) << 2) | ((b[3] as u32 & 0x0f) << 10));
+            Some((width, height))
+        }
+        b"VP8X" => Some((1 + le24(data, 24)?, 1 + le24(data, 27)?)),
+        _ => None,
+    }
+}
+
+/// SVG の `width`・`height`（単位が px 以外や指定がなければ `viewBox` から）
+fn svg_size(text: &str) -> (Option<u32>, Option<u32>) {
+    let Some(start) = text.find("<svg") else {
+        return (None, None);
+    };
+    let tag = &text[start..text[start..].find('>').map_or(text.len(), |i| start + i)];
+    let attribute = |name: &str| {
+        let pattern = format!(" {name}=");
+        let at = tag.find(&pattern)? + pattern.len();
+        let quote = tag[at..].chars().next()?;
+        let value = &tag[at + 1..];
+        Some(value[..value.find(quote)?].trim().to_string())
+    };
+    let pixels = |value: Option<String>| {
+        let value = value?;
+        let number = value.strip_suffix("px").unwrap_or(&value);
+        number.parse::<f64>().ok().map(|n| n.round() as u32)
+    };
+    let (mut width, mut height) = (pixels(attribute("width")), pixels(attribute("height")));
+    if width.is_none() || height.is_none() {
+        if let Some(view_box) = attribute("viewBox") {
+            let numbers: Vec<f64> = view_box
+                .split([' ', ','])
+                .filter_map(|n| n.parse().ok())
+                .collect();
+            if let [_, _, w, h] = numbers[..] {
+                width = width.or(Some(w.round() as u32));
+                height = height.or(Some(h.round() as u32));
+            }
+        }
+    }
+    (width, height)
+}
+
+/// 先頭のバイト列から形式と大きさを判定する
+pub fn probe_bytes(data: &[u8]) -> Option<(&'static str, Option<u32>, Option<u32>)> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        let width = u32::from_be_bytes(data.get(16..20)?.try_into().ok()?);
+        let height = u32::from_be_bytes(data.get(20..24)?.try_into().ok()?);
+        return Some(("png", Some(width), Some(height)));
+    }
+    if data.starts_with(&[0xff, 0xd8]) {
+        let size = jpeg_size(data);
+        return Some(("jpeg", size.map(|s| s.0), size.map(|s| s.1)));
+    }
+    if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        return Some(("gif", le16(data, 6), le16(data, 8)));
+    }
+    if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        let size = webp_size(data);
+        return Some(("webp", size.map(|s| s.0), size.map(|s| s.1)));
+    }
+    if data.starts_with(b"BM") {
+        let width = i32::from_le_bytes(data.get(18..22)?.try_into().ok()?);
+        let height = i32::from_le_bytes(data.get(22..26)?.try_into().ok()?);
+        return Some((
+            "bmp",
+            Some(width.unsigned_abs()),
+            Some(height.unsigned_abs()),
+        ));
+    }
+    let text = String::from_utf8_lossy(&data[..data.len().min(8192)]);
+    if text.contains("<svg") {
+        let (width, height) = svg_size(&text);
+        return Some(("svg", width, height));
+    }
+    None
+}
+
+/// 画像ファイルの形式と大きさ
+pub fn probe(path: &Path) -> Result<ImageInfo, String> {
+    let file = File::open(path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut data = Vec::new();
+    file.take(PROBE_LIMIT)
+        .read_to_end(&mut data)
+        .map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+    let (format, width, height) =
+        probe_bytes(&data).ok_or_else(|| tr!("Unrecognized image format: {}", path.display()))?;
+    Ok(ImageInfo {
+        format: format.to_string(),
+        width,
+        height,
+        size,
+    })
+}
+
+/// HTML に埋め込む SVG（小さいファイルのみ。XML 宣言などは除く）
+pub fn inline_svg(path: &Path) -> Option<String> {
+    let size = fs::metadata(path).ok()?.len();
+    if size > INLINE_SVG_LIMIT
+        || !path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("svg"))
+    {
+        return None;
+    }
+    let text = fs::read_to_string(path).ok()?;
+    let start = text.find("<svg")?;
+    // スクリプトを含むものは埋め込まない
+    (!text.to_lowercase().contains("<script")).then(|| text[start..].trim_end().to_string())
+}
+
+/// 画像ファイルの形式と大きさ（プレビューで表示領域を確保するため）
+#[tauri::command]
+pub fn probe_image(path: String) -> Result<ImageInfo, String> {
+    probe(Path::new(&path))
+}
+
+/// 文書中の画像のうち、見つからないもの・読めないものを返す
+#[tauri::command(async)]
+pub fn check_images(
+    content: String,
+    path: String,
+    vault_root: Option<String>,
+) -> Vec<ImageDiagnostic> {
+    let doc = vault::normalize(Path::new(&path));
+    let root = vault_root
+        .map(|r| vault::normalize(Path::new(&r)))
+        .unwrap_or_else(|| doc.parent().map(Path::to_path_buf).unwrap_or_default());
+    let vault = Vault::scan(&root);
+    let index = LineIndex::new(&content);
+    links::extract_links(&content)
+        .into_iter()
+        .filter(|link| !link.target.is_empty() && !links::is_external(&link.target))
+        .filter(|link| match link.kind {
+            LinkKind::Image => true,
+            // 埋め込みと HTML は拡張子が画像のものだけ
+            LinkKind::Embed | LinkKind::Html => Path::new(&link.target)
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| {
+                    ["png", "jpg", "jpeg", "gif", "webp", "bmp", "svg"]
+                        .contains(&e.to_ascii_lowercase().as_str())
+                }),
+            _ => false,
+        })
+        .filter_map(|link| {
+            let message = match vault.resolve(&doc, &link).filter(|p| p.is_file()) {
+                None => tr!("Image not found"),
+                Some(file) => match probe(&file) {
+                    Ok(_) => return None,
+                    Err(e) => e,
+                },
+            };
+            Some(ImageDiagnostic {
+                range: LspRange::from_offsets(&index, &content, link.target_range.clone()),
+                target: link.target,
+                message,
+            })
+        })
+        .collect()
+}
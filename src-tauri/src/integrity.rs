@@ -0,0 +1,191 @@
+// ファイルのハッシュ値と整合性マニフェスト
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, State};
+
+use crate::blake3;
+use crate::state::AppState;
+use crate::tasks::Task;
+use crate::tr;
+use crate::vault;
+
+/// ハッシュアルゴリズム
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// マニフェストのファイル名から推定（`*.b3` や `b3sums` なら BLAKE3）
+    fn from_manifest_name(path: &Path) -> Self {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name.contains("b3") || name.contains("blake3") {
+            HashAlgorithm::Blake3
+        } else {
+            HashAlgorithm::Sha256
+        }
+    }
+}
+
+/// マニフェストの照合結果（パスはマニフェストに書かれた相対パス）
+#[derive(Debug, Default, Serialize)]
+pub struct ManifestReport {
+    pub algorithm: HashAlgorithm,
+    pub verified: Vec<String>,
+    /// 内容が変わっているファイル
+    pub mismatched: Vec<String>,
+    /// マニフェストにあるが存在しないファイル
+    pub missing: Vec<String>,
+    /// フォルダにあるがマニフェストにないファイル
+    pub unlisted: Vec<String>,
+    /// 解釈できなかった行番号
+    pub invalid_lines: Vec<usize>,
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// ファイルのハッシュ値（16 進小文字）
+pub fn file_hash(path: &Path, algo: HashAlgorithm) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| tr!("Failed to open {}: {e}", path.display()))?;
+    let mut sha256 = Sha256::new();
+    let mut blake3 = blake3::Hasher::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let n = file
+            .read(&mut buffer)
+            .map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+        if n == 0 {
+            break;
+        }
+        match algo {
+            HashAlgorithm::Sha256 => sha256.update(&buffer[..n]),
+            HashAlgorithm::Blake3 => blake3.update(&buffer[..n]),
+        }
+    }
+    Ok(match algo {
+        HashAlgorithm::Sha256 => to_hex(&sha256.finalize()),
+        HashAlgorithm::Blake3 => to_hex(&blake3.finalize()),
+    })
+}
+
+/// ファイルのハッシュ値を計算（既定は SHA-256）
+#[tauri::command(async)]
+pub fn hash_file(path: String, algo: Option<HashAlgorithm>) -> Result<String, String> {
+    file_hash(Path::new(&path), algo.unwrap_or_default())
+}
+
+/// マニフェストの 1 行（`sha256sum` / `b3sum` 形式: `<hash>  <path>`）
+fn parse_manifest_line(line: &str) -> Option<(&str, &str)> {
+    let (hash, path) = line.split_once(' ')?;
+    // `*` はバイナリモードの印
+    let path = path.strip_prefix([' ', '*'])?;
+    (hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()) && !path.is_empty())
+        .then_some((hash, path))
+}
+
+/// マニフェストの対象とするファイル（マニフェスト自身は除く）
+fn listed_files(dir: &Path, manifest: &Path) -> Vec<PathBuf> {
+    let manifest = vault::normalize(manifest);
+    vault::walk_files(dir)
+        .into_iter()
+        .filter(|p| *p != manifest)
+        .collect()
+}
+
+/// フォルダ内の全ファイルのマニフェストを作成
+#[tauri::command(async)]
+pub fn create_manifest(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    dir: String,
+    manifest: String,
+    algo: Option<HashAlgorithm>,
+    task_id: Option<String>,
+) -> Result<usize, String> {
+    let task = Task::start(&app, &state, task_id)?;
+    let dir = vault::normalize(Path::new(&dir));
+    let manifest = PathBuf::from(manifest);
+    let algo = algo.unwrap_or_else(|| HashAlgorithm::from_manifest_name(&manifest));
+    let files = listed_files(&dir, &manifest);
+
+    let mut out = String::new();
+    for (i, file) in files.iter().enumerate() {
+        task.check()?;
+        task.progress(i + 1, files.len(), || vault::path_string(file));
+        let relative = file.strip_prefix(&dir).unwrap_or(file);
+        out.push_str(&format!(
+            "{}  {}\n",
+            file_hash(file, algo)?,
+            vault::to_slash(relative)
+        ));
+    }
+    fs::write(&manifest, out).map_err(|e| tr!("Failed to write {}: {e}", manifest.display()))?;
+    Ok(files.len())
+}
+
+/// マニフェストに記録されたハッシュ値とフォルダ内のファイルを照合
+#[tauri::command(async)]
+pub fn verify_manifest(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    dir: String,
+    manifest: String,
+    algo: Option<HashAlgorithm>,
+    task_id: Option<String>,
+) -> Result<ManifestReport, String> {
+    let task = Task::start(&app, &state, task_id)?;
+    let dir = vault::normalize(Path::new(&dir));
+    let manifest = PathBuf::from(manifest);
+    let algo = algo.unwrap_or_else(|| HashAlgorithm::from_manifest_name(&manifest));
+    let content = fs::read_to_string(&manifest)
+        .map_err(|e| tr!("Failed to read {}: {e}", manifest.display()))?;
+
+    let mut report = ManifestReport {
+        algorithm: algo,
+        ..Default::default()
+    };
+    let mut listed = HashSet::new();
+    let total = content.lines().count();
+    for (i, line) in content.lines().enumerate() {
+        task.check()?;
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((expected, relative)) = parse_manifest_line(line) else {
+            report.invalid_lines.push(i + 1);
+            continue;
+        };
+        task.progress(i + 1, total, || relative.to_string());
+        let path = vault::normalize(&dir.join(relative));
+        listed.insert(path.clone());
+        if !path.is_file() {
+            report.missing.push(relative.to_string());
+        } else if file_hash(&path, algo)?.eq_ignore_ascii_case(expected) {
+            report.verified.push(relative.to_string());
+        } else {
+            report.mismatched.push(relative.to_string());
+        }
+    }
+
+    report.unlisted = listed_files(&dir, &manifest)
+        .into_iter()
+        .filter(|p| !listed.contains(p))
+        .map(|p| vault::to_slash(p.strip_prefix(&dir).unwrap_or(&p)))
+        .collect();
+    Ok(report)
+}
@@ -0,0 +1,230 @@
+// Jupyter ノートブック（.ipynb）を Markdown 文書に変換
+
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::links;
+use crate::tr;
+use crate::vault;
+
+/// 端末の色付けなどのエスケープシーケンス（エラーのトレースバックに含まれる）
+static ANSI_ESCAPE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").unwrap());
+
+/// 画像として取り込む出力の形式と拡張子
+const IMAGE_TYPES: &[(&str, &str)] = &[
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/gif", "gif"),
+    ("image/svg+xml", "svg"),
+];
+
+/// `import_ipynb` のオプション
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct IpynbOptions {
+    /// コードセルの出力を含める
+    pub outputs: bool,
+    /// 画像の出力を含める（`image_dir` がなければ data URI として埋め込む）
+    pub images: bool,
+    /// 画像を書き出すフォルダ（リンクはノートブックのフォルダからの相対パス）
+    pub image_dir: Option<String>,
+}
+
+impl Default for IpynbOptions {
+    fn default() -> Self {
+        Self {
+            outputs: true,
+            images: false,
+            image_dir: None,
+        }
+    }
+}
+
+/// `source` や `text` は文字列または文字列の配列
+fn joined(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// 中身に含まれない長さのバッククォートで囲む
+fn fenced(info: &str, body: &str) -> String {
+    let longest = body.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{fence}{info}\n{}\n{fence}", body.trim_end_matches('\n'))
+}
+
+struct Converter<'a> {
+    options: &'a IpynbOptions,
+    notebook_dir: &'a Path,
+    stem: String,
+    image_count: usize,
+}
+
+impl Converter<'_> {
+    /// 画像を data URI または書き出したファイルへのリンクにする
+    fn image(&mut self, mime: &str, extension: &str, data: &Value) -> Result<String, String> {
+        let data = joined(data);
+        // SVG はテキストのまま、それ以外は Base64（改行を含むことがある）
+        let encoded: String = if mime == "image/svg+xml" {
+            STANDARD.encode(data.as_bytes())
+        } else {
+            data.chars().filter(|c| !c.is_whitespace()).collect()
+        };
+        self.image_count += 1;
+        let Some(dir) = self.options.image_dir.as_deref() else {
+            return Ok(format!("data:{mime};base64,{encoded}"));
+        };
+        let bytes = STANDARD
+            .decode(&encoded)
+            .map_err(|e| tr!("Invalid image data in notebook: {e}"))?;
+        let dir = vault::normalize(&self.notebook_dir.join(dir));
+        fs::create_dir_all(&dir).map_err(|e| tr!("Failed to create {}: {e}", dir.display()))?;
+        let path = dir.join(format!("{}-{}.{extension}", self.stem, self.image_count));
+        fs::write(&path, bytes).map_err(|e| tr!("Failed to write {}: {e}", path.display()))?;
+        Ok(links::percent_encode(&vault::relative_path(
+            self.notebook_dir,
+            &path,
+        )))
+    }
+
+    /// 表示用の出力（画像 > Markdown > テキスト > HTML の順で 1 つ選ぶ）
+    fn rich_output(&mut self, data: &Value) -> Result<Option<String>, String> {
+        if self.options.images {
+            for (mime, extension) in IMAGE_TYPES {
+                if let Some(image) = data.get(*mime) {
+                    let src = self.image(mime, extension, image)?;
+                    return Ok(Some(format!("![output]({src})")));
+                }
+            }
+        }
+        if let Some(markdown) = data.get("text/markdown") {
+            return Ok(Some(joined(markdown)));
+        }
+        if let Some(text) = data.get("text/plain") {
+            return Ok(Some(fenced("text", &joined(text))));
+        }
+        Ok(data.get("text/html").map(joined))
+    }
+
+    fn outputs(&mut self, outputs: &[Value]) -> Result<Vec<String>, String> {
+        let mut blocks = Vec::new();
+        for output in outputs {
+            let block = match output.get("output_type").and_then(Value::as_str) {
+                Some("stream") => Some(fenced("text", &joined(&output["text"]))),
+                Some("execute_result") | Some("display_data") => {
+                    self.rich_output(&output["data"])?
+                }
+                Some("error") => {
+                    let traceback = output["traceback"]
+                        .as_array()
+                        .map(|lines| {
+                            let lines: Vec<&str> = lines.iter().filter_map(Value::as_str).collect();
+                            lines.join("\n")
+                        })
+                        .unwrap_or_default();
+                    let text = if traceback.is_empty() {
+                        format!(
+                            "{}: {}",
+                            output["ename"].as_str().unwrap_or("Error"),
+                            output["evalue"].as_str().unwrap_or_default()
+                        )
+                    } else {
+                        traceback
+                    };
+                    Some(fenced("text", &ANSI_ESCAPE.replace_all(&text, "")))
+                }
+                _ => None,
+            };
+            blocks.extend(block.filter(|b| !b.trim().is_empty()));
+        }
+        Ok(blocks)
+    }
+
+    /// Markdown セルの添付画像（`attachment:名前`）を置き換える
+    fn attachments(&mut self, source: String, cell: &Value) -> Result<String, String> {
+        let Some(attachments) = cell.get("attachments").and_then(Value::as_object) else {
+            return Ok(source);
+        };
+        let mut source = source;
+        for (name, data) in attachments {
+            let reference = format!("attachment:{name}");
+            if !source.contains(&reference) {
+                continue;
+            }
+            let image = IMAGE_TYPES
+                .iter()
+                .find_map(|(mime, ext)| data.get(*mime).map(|d| (*mime, *ext, d)));
+            if let Some((mime, extension, data)) = image.filter(|_| self.options.images) {
+                let src = self.image(mime, extension, data)?;
+                source = source.replace(&reference, &src);
+            }
+        }
+        Ok(source)
+    }
+
+    fn convert(&mut self, notebook: &Value) -> Result<String, String> {
+        let cells = notebook
+            .get("cells")
+            .and_then(Value::as_array)
+            .ok_or_else(|| tr!("Unsupported notebook format (nbformat 4 is required)"))?;
+        let language = notebook["metadata"]["kernelspec"]["language"]
+            .as_str()
+            .or_else(|| notebook["metadata"]["language_info"]["name"].as_str())
+            .unwrap_or("python")
+            .to_string();
+
+        let mut blocks = Vec::new();
+        for cell in cells {
+            let source = joined(&cell["source"]);
+            match cell.get("cell_type").and_then(Value::as_str) {
+                Some("markdown") => {
+                    let source = self.attachments(source, cell)?;
+                    blocks.push(source.trim_end().to_string());
+                }
+                Some("code") => {
+                    if !source.trim().is_empty() {
+                        blocks.push(fenced(&language, &source));
+                    }
+                    if self.options.outputs {
+                        let outputs = cell["outputs"].as_array().map_or(&[][..], Vec::as_slice);
+                        blocks.extend(self.outputs(outputs)?);
+                    }
+                }
+                _ => blocks.push(source.trim_end().to_string()),
+            }
+        }
+        blocks.retain(|b| !b.trim().is_empty());
+        Ok(blocks.join("\n\n") + "\n")
+    }
+}
+
+/// ノートブックの Markdown セルとコードセル（と出力）を 1 つの Markdown 文書にする
+#[tauri::command]
+pub fn import_ipynb(path: String, options: Option<IpynbOptions>) -> Result<String, String> {
+    let path = vault::normalize(Path::new(&path));
+    let text =
+        fs::read_to_string(&path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+    let notebook: Value = serde_json::from_str(&text).map_err(|e| tr!("Invalid notebook: {e}"))?;
+    let options = options.unwrap_or_default();
+    let mut converter = Converter {
+        options: &options,
+        notebook_dir: path.parent().unwrap_or(Path::new(".")),
+        stem: path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "notebook".to_string()),
+        image_count: 0,
+    };
+    converter.convert(&notebook)
+}
@@ -0,0 +1,246 @@
+// 時間のかかる処理の共通の手順（番号を返して別スレッドで実行し、`job-progress` / `job-done` / `job-error` を送る）
+
+use std::thread;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::attachments;
+use crate::backup::{self, BackupConfig};
+use crate::bundle;
+use crate::export;
+use crate::integrity::{self, HashAlgorithm};
+use crate::note_import::{self, NoteSource};
+use crate::search_index;
+use crate::semantic;
+use crate::similar;
+use crate::state::AppState;
+use crate::tasks::cancelled_error;
+use crate::tr;
+
+/// `start_job` で始める処理（引数は同名のコマンドと同じ）
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobRequest {
+    ExportVaultHtml {
+        root: String,
+        out_dir: String,
+        theme: Option<String>,
+    },
+    ExportBundle {
+        path: String,
+        out_zip: String,
+        vault_root: Option<String>,
+    },
+    BuildSemanticIndex {
+        vault_root: String,
+    },
+    BuildSearchIndex {
+        vault_root: String,
+    },
+    ImportNotes {
+        source_type: NoteSource,
+        path: String,
+        dest_dir: String,
+    },
+    CreateManifest {
+        dir: String,
+        manifest: String,
+        algo: Option<HashAlgorithm>,
+    },
+    VerifyManifest {
+        dir: String,
+        manifest: String,
+        algo: Option<HashAlgorithm>,
+    },
+    FindOrphanedAssets {
+        vault: String,
+    },
+    FindSimilarNotes {
+        path: String,
+        limit: Option<usize>,
+        vault_root: Option<String>,
+    },
+    Backup {
+        config: Option<BackupConfig>,
+    },
+}
+
+impl JobRequest {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::ExportVaultHtml { .. } => "export_vault_html",
+            Self::ExportBundle { .. } => "export_bundle",
+            Self::BuildSemanticIndex { .. } => "build_semantic_index",
+            Self::BuildSearchIndex { .. } => "build_search_index",
+            Self::ImportNotes { .. } => "import_notes",
+            Self::CreateManifest { .. } => "create_manifest",
+            Self::VerifyManifest { .. } => "verify_manifest",
+            Self::FindOrphanedAssets { .. } => "find_orphaned_assets",
+            Self::FindSimilarNotes { .. } => "find_similar_notes",
+            Self::Backup { .. } => "backup",
+        }
+    }
+}
+
+/// `job-done` イベント
+#[derive(Debug, Clone, Serialize)]
+pub struct JobDone {
+    pub job_id: String,
+    pub kind: String,
+    /// 同名のコマンドの戻り値
+    pub result: Value,
+    pub duration_ms: u64,
+}
+
+/// `job-error` イベント
+#[derive(Debug, Clone, Serialize)]
+pub struct JobError {
+    pub job_id: String,
+    pub kind: String,
+    pub error: String,
+    /// `cancel_task` で中断された
+    pub cancelled: bool,
+}
+
+fn json<T: Serialize>(result: Result<T, String>) -> Result<Value, String> {
+    serde_json::to_value(result?).map_err(|e| tr!("Failed to serialize result: {e}"))
+}
+
+/// 処理を実行する（進捗はコマンドが `id` を使って送る）
+fn run(app: &AppHandle, id: &str, job: JobRequest) -> Result<Value, String> {
+    let state = || app.state::<AppState>();
+    let id = Some(id.to_string());
+    match job {
+        JobRequest::ExportVaultHtml {
+            root,
+            out_dir,
+            theme,
+        } => json(export::export_vault_html(
+            app.clone(),
+            state(),
+            root,
+            out_dir,
+            theme,
+            id,
+        )),
+        JobRequest::ExportBundle {
+            path,
+            out_zip,
+            vault_root,
+        } => json(bundle::export_bundle(path, out_zip, vault_root)),
+        // 埋め込みの API を待つ非同期のコマンドなので、このスレッドで完了まで待つ
+        JobRequest::BuildSemanticIndex { vault_root } => json(tauri::async_runtime::block_on(
+            semantic::build_semantic_index(app.clone(), state(), vault_root, id),
+        )),
+        JobRequest::BuildSearchIndex { vault_root } => json(search_index::build_search_index(
+            app.clone(),
+            state(),
+            vault_root,
+            id,
+        )),
+        JobRequest::ImportNotes {
+            source_type,
+            path,
+            dest_dir,
+        } => json(note_import::import_notes(
+            app.clone(),
+            state(),
+            source_type,
+            path,
+            dest_dir,
+            id,
+        )),
+        JobRequest::CreateManifest {
+            dir,
+            manifest,
+            algo,
+        } => json(integrity::create_manifest(
+            app.clone(),
+            state(),
+            dir,
+            manifest,
+            algo,
+            id,
+        )),
+        JobRequest::VerifyManifest {
+            dir,
+            manifest,
+            algo,
+        } => json(integrity::verify_manifest(
+            app.clone(),
+            state(),
+            dir,
+            manifest,
+            algo,
+            id,
+        )),
+        JobRequest::FindOrphanedAssets { vault } => json(attachments::find_orphaned_assets(
+            app.clone(),
+            state(),
+            vault,
+            id,
+        )),
+        JobRequest::FindSimilarNotes {
+            path,
+            limit,
+            vault_root,
+        } => json(similar::find_similar_notes(
+            app.clone(),
+            state(),
+            path,
+            limit,
+            vault_root,
+            id,
+        )),
+        JobRequest::Backup { config } => json(backup::backup_now(app.clone(), state(), config)),
+    }
+}
+
+/// 時間のかかる処理を別スレッドで始め、番号をすぐに返す
+///
+/// 進捗は `job-progress`、結果は `job-done`、失敗や中断は `job-error` で送る。中断は `cancel_task` に番号を渡す。
+#[tauri::command]
+pub fn start_job(app: AppHandle, state: State<'_, AppState>, job: JobRequest) -> String {
+    let kind = job.kind();
+    let id = state.tasks.reserve(kind);
+    let job_id = id.clone();
+    thread::spawn(move || {
+        let started = Instant::now();
+        let result = if app.state::<AppState>().tasks.cancelled(&id) {
+            Err(cancelled_error())
+        } else {
+            run(&app, &id, job)
+        };
+        let tasks = &app.state::<AppState>().tasks;
+        let cancelled = tasks.cancelled(&id);
+        tasks.release(&id);
+        match result {
+            Ok(result) => {
+                let _ = app.emit(
+                    "job-done",
+                    JobDone {
+                        job_id: id,
+                        kind: kind.to_string(),
+                        result,
+                        duration_ms: started.elapsed().as_millis() as u64,
+                    },
+                );
+            }
+            Err(error) => {
+                let _ = app.emit(
+                    "job-error",
+                    JobError {
+                        job_id: id,
+                        kind: kind.to_string(),
+                        cancelled: cancelled || error == cancelled_error(),
+                        error,
+                    },
+                );
+            }
+        }
+    });
+    job_id
+}
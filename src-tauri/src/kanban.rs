@@ -0,0 +1,417 @@
+// Markdown のかんばんボード（H2 の節を列、その中のリストの項目をカードとして扱う）
+//
+// カードの移動はソースの置き換えとして計算し、ボードとソースが常に一致するようにする。
+
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+use crate::formatter::{self, TextEdit};
+use crate::markdown;
+use crate::text::LineIndex;
+use crate::tr;
+
+/// カード
+#[derive(Debug, Serialize)]
+pub struct KanbanCard {
+    /// 列の中の番号（0 始まり）
+    pub index: usize,
+    /// 行番号（1 始まり）
+    pub line: usize,
+    /// 最後の行（入れ子のリストや続きの行を含む）
+    pub end_line: usize,
+    /// 最初の行のリスト記号とチェックボックスを除いたテキスト
+    pub text: String,
+    /// チェックボックスの状態（なければ `None`）
+    pub checked: Option<bool>,
+}
+
+/// 列
+#[derive(Debug, Serialize)]
+pub struct KanbanColumn {
+    /// 列の番号（0 始まり）
+    pub index: usize,
+    pub title: String,
+    /// 見出しの行番号（1 始まり）
+    pub line: usize,
+    pub cards: Vec<KanbanCard>,
+}
+
+/// ボード
+#[derive(Debug, Serialize)]
+pub struct KanbanBoard {
+    pub columns: Vec<KanbanColumn>,
+}
+
+/// カードの移動先
+#[derive(Debug, Deserialize)]
+pub struct KanbanTarget {
+    /// 移動先の列
+    pub column: usize,
+    /// 列の中の位置（省略時は末尾）
+    #[serde(default)]
+    pub index: Option<usize>,
+}
+
+/// `apply_kanban_move` の結果
+#[derive(Debug, Serialize)]
+pub struct KanbanMove {
+    pub content: String,
+    pub changed: bool,
+    pub edits: Vec<TextEdit>,
+    pub board: KanbanBoard,
+}
+
+/// 解析した列（カードはソースの行単位のバイト範囲）
+struct Column {
+    title: String,
+    heading: Range<usize>,
+    cards: Vec<Range<usize>>,
+}
+
+/// 行の先頭（`offset` を含む行）
+fn line_start(content: &str, offset: usize) -> usize {
+    content[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// 行の末尾の次（改行を含む）
+fn line_end(content: &str, offset: usize) -> usize {
+    content[offset..]
+        .find('\n')
+        .map_or(content.len(), |i| offset + i + 1)
+}
+
+/// `end` の直前の文字を含む行の末尾の次
+fn line_end_before(content: &str, end: usize) -> usize {
+    let last = content[..end]
+        .char_indices()
+        .next_back()
+        .map_or(0, |(i, _)| i);
+    line_end(content, last)
+}
+
+/// 最上位のリストの項目（行単位のバイト範囲。末尾の空行は含めない）
+fn top_level_items(content: &str) -> Vec<Range<usize>> {
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    // 引用やリストの中のリストは数えない
+    let mut container = 0usize;
+    for (event, range) in Parser::new_ext(content, markdown::parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::List(_)) => depth += 1,
+            Event::End(TagEnd::List(_)) => depth -= 1,
+            Event::Start(Tag::BlockQuote(_)) => container += 1,
+            Event::End(TagEnd::BlockQuote(_)) => container -= 1,
+            Event::Start(Tag::Item) if depth == 1 && container == 0 => {
+                let source = content[range.clone()].trim_end();
+                let end = range.start + source.len();
+                items.push(line_start(content, range.start)..line_end_before(content, end));
+            }
+            _ => {}
+        }
+    }
+    items
+}
+
+/// H2 の見出しごとの列（次の H1・H2 までの最上位のリストの項目がカード）
+fn parse_columns(content: &str) -> Vec<Column> {
+    let headings = markdown::headings(content);
+    let items = top_level_items(content);
+    let mut columns = Vec::new();
+    for (i, heading) in headings.iter().enumerate() {
+        if heading.level != 2 {
+            continue;
+        }
+        let end = headings[i + 1..]
+            .iter()
+            .find(|h| h.level <= 2)
+            .map_or(content.len(), |h| h.range.start);
+        let start = heading.range.end;
+        columns.push(Column {
+            title: heading.text.clone(),
+            heading: line_start(content, heading.range.start)
+                ..line_end_before(content, heading.range.end),
+            cards: items
+                .iter()
+                .filter(|item| start <= item.start && item.start < end)
+                .cloned()
+                .collect(),
+        });
+    }
+    columns
+}
+
+/// カードの最初の行のテキストとチェックボックス
+fn card_text(line: &str) -> (String, Option<bool>) {
+    let line = line.trim();
+    let rest = match line.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            line[digits..]
+                .strip_prefix(['.', ')'])
+                .unwrap_or(&line[digits..])
+        }
+    };
+    let rest = rest.trim_start();
+    let checked = match rest.get(..3) {
+        Some("[ ]") => Some(false),
+        Some("[x]" | "[X]") => Some(true),
+        _ => None,
+    };
+    let text = if checked.is_some() { &rest[3..] } else { rest };
+    (text.trim().to_string(), checked)
+}
+
+fn board(content: &str, columns: &[Column]) -> KanbanBoard {
+    let index = LineIndex::new(content);
+    KanbanBoard {
+        columns: columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| KanbanColumn {
+                index: i,
+                title: column.title.clone(),
+                line: index.line_of(column.heading.start),
+                cards: column
+                    .cards
+                    .iter()
+                    .enumerate()
+                    .map(|(j, card)| {
+                        let first = content[card.clone()].lines().next().unwrap_or_default();
+                        let (text, checked) = card_text(first);
+                        KanbanCard {
+                            index: j,
+                            line: index.line_of(card.start),
+                            end_line: index.line_of(card.end.max(card.start + 1) - 1),
+                            text,
+                            checked,
+                        }
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+/// 文書をかんばんボードとして解析する（H2 の節が列、最上位のリストの項目がカード）
+#[tauri::command]
+pub fn parse_kanban(content: String) -> KanbanBoard {
+    board(&content, &parse_columns(&content))
+}
+
+/// 列 `from` の `card` 番目のカードを `to` に移動し、置き換えと移動後のボードを返す
+///
+/// カードは入れ子のリストや続きの行ごと移動する。空の列には見出しの下に空行を挟んで入れる。
+#[tauri::command]
+pub fn apply_kanban_move(
+    content: String,
+    card: usize,
+    from: usize,
+    to: KanbanTarget,
+) -> Result<KanbanMove, String> {
+    let columns = parse_columns(&content);
+    let column_count = columns.len();
+    let source = columns
+        .get(from)
+        .ok_or_else(|| tr!("No column {} (the board has {})", from, column_count))?;
+    let moved = source
+        .cards
+        .get(card)
+        .ok_or_else(|| tr!("No card {} in column {}", card, source.title))?
+        .clone();
+    let target = columns
+        .get(to.column)
+        .ok_or_else(|| tr!("No column {} (the board has {})", to.column, column_count))?;
+
+    let mut text = content[moved.clone()].to_string();
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    // 移動元のカードを除いた移動先の列の中の位置
+    let others: Vec<&Range<usize>> = target.cards.iter().filter(|c| **c != moved).collect();
+    let position = to.index.unwrap_or(others.len()).min(others.len());
+    let insert = match (others.get(position), others.last()) {
+        (Some(next), _) => next.start,
+        (None, Some(last)) => last.end,
+        // 列の唯一のカードを同じ列に戻すときは動かさない
+        (None, None) if to.column == from => moved.start,
+        (None, None) => {
+            text.insert(0, '\n');
+            target.heading.end
+        }
+    };
+
+    let mut new = String::with_capacity(content.len() + 1);
+    if insert <= moved.start {
+        new.push_str(&content[..insert]);
+        new.push_str(&text);
+        new.push_str(&content[insert..moved.start]);
+        new.push_str(&content[moved.end..]);
+    } else {
+        new.push_str(&content[..moved.start]);
+        new.push_str(&content[moved.end..insert]);
+        if !content[..insert].ends_with('\n') {
+            new.push('\n');
+        }
+        new.push_str(&text);
+        new.push_str(&content[insert..]);
+    }
+    // 最後の行に改行がなかった文書はそのままにする
+    if !content.ends_with('\n') && new.ends_with('\n') {
+        new.pop();
+    }
+
+    let changed = new != content;
+    let board = board(&new, &parse_columns(&new));
+    Ok(KanbanMove {
+        edits: if changed {
+            formatter::text_edits(&content, &new)
+        } else {
+            Vec::new()
+        },
+        changed,
+        board,
+        content: new,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_kanban_move, parse_kanban, KanbanTarget};
+
+    const BOARD: &str = "# Board\n\n## Todo\n\n- [ ] write\n  - sub item\n- [x] read\n\n## Doing\n\n## Done\n- ship\n\n```\n- not a card\n```\n";
+
+    const BOARD_JA: &str = "## 未着手\n- [ ] 書く\n- 読む\n## 完了\n- カード";
+
+    fn move_card(card: usize, from: usize, column: usize, index: Option<usize>) -> String {
+        apply_kanban_move(
+            BOARD.to_string(),
+            card,
+            from,
+            KanbanTarget { column, index },
+        )
+        .unwrap()
+        .content
+    }
+
+    #[test]
+    fn parses_columns_and_cards() {
+        let board = parse_kanban(BOARD.to_string());
+        let titles: Vec<&str> = board.columns.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, ["Todo", "Doing", "Done"]);
+        let todo = &board.columns[0].cards;
+        assert_eq!(todo.len(), 2);
+        assert_eq!(
+            (todo[0].text.as_str(), todo[0].checked),
+            ("write", Some(false))
+        );
+        assert_eq!((todo[0].line, todo[0].end_line), (5, 6));
+        // コードブロックの中のリストはカードにしない
+        assert_eq!(board.columns[2].cards.len(), 1);
+    }
+
+    #[test]
+    fn moves_cards_across_columns() {
+        // 空の列には見出しの下に空行を挟んで入れ、入れ子の項目ごと移動する
+        assert_eq!(
+            move_card(0, 0, 1, None),
+            "# Board\n\n## Todo\n\n- [x] read\n\n## Doing\n\n- [ ] write\n  - sub item\n\n## Done\n- ship\n\n```\n- not a card\n```\n"
+        );
+        assert_eq!(
+            move_card(1, 0, 2, Some(0)),
+            "# Board\n\n## Todo\n\n- [ ] write\n  - sub item\n\n## Doing\n\n## Done\n- [x] read\n- ship\n\n```\n- not a card\n```\n"
+        );
+        assert_eq!(
+            move_card(0, 2, 0, Some(0)),
+            "# Board\n\n## Todo\n\n- ship\n- [ ] write\n  - sub item\n- [x] read\n\n## Doing\n\n## Done\n\n```\n- not a card\n```\n"
+        );
+        let moved = apply_kanban_move(
+            "## A\n- a\n## B\n- b".to_string(),
+            0,
+            0,
+            KanbanTarget {
+                column: 1,
+                index: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(moved.content, "## A\n## B\n- b\n- a");
+        assert_eq!(moved.board.columns[1].cards[1].text, "a");
+    }
+
+    #[test]
+    fn moves_within_a_column_and_rejects_missing_cards() {
+        assert_eq!(
+            move_card(0, 0, 0, Some(1)),
+            "# Board\n\n## Todo\n\n- [x] read\n- [ ] write\n  - sub item\n\n## Doing\n\n## Done\n- ship\n\n```\n- not a card\n```\n"
+        );
+        let unchanged = apply_kanban_move(
+            BOARD.to_string(),
+            0,
+            0,
+            KanbanTarget {
+                column: 0,
+                index: Some(0),
+            },
+        )
+        .unwrap();
+        assert!(!unchanged.changed && unchanged.edits.is_empty());
+        let missing = |card, from, column| {
+            apply_kanban_move(
+                BOARD.to_string(),
+                card,
+                from,
+                KanbanTarget {
+                    column,
+                    index: None,
+                },
+            )
+            .is_err()
+        };
+        assert!(missing(5, 0, 1) && missing(0, 9, 1) && missing(0, 0, 9));
+    }
+
+    #[test]
+    fn japanese_board() {
+        let board = parse_kanban(BOARD_JA.to_string());
+        let columns: Vec<(&str, Vec<&str>)> = board
+            .columns
+            .iter()
+            .map(|c| {
+                let cards = c.cards.iter().map(|card| card.text.as_str()).collect();
+                (c.title.as_str(), cards)
+            })
+            .collect();
+        assert_eq!(
+            columns,
+            [("未着手", vec!["書く", "読む"]), ("完了", vec!["カード"])]
+        );
+        let moved = apply_kanban_move(
+            BOARD_JA.to_string(),
+            0,
+            1,
+            KanbanTarget {
+                column: 0,
+                index: Some(0),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            moved.content,
+            "## 未着手\n- カード\n- [ ] 書く\n- 読む\n## 完了"
+        );
+        let moved = apply_kanban_move(
+            "## 列\n- カード".to_string(),
+            0,
+            0,
+            KanbanTarget {
+                column: 0,
+                index: None,
+            },
+        )
+        .unwrap();
+        assert!(!moved.changed);
+    }
+}
@@ -0,0 +1,258 @@
+// 利用者のキー割り当て（アプリの設定フォルダの `keybindings.toml` または `keybindings.json`）
+//
+// コマンドの `id` ごとにキーを書く。空の配列は既定のキーを外す。書かなかったコマンドは既定のキーのまま。
+//
+// ```toml
+// "file.save" = "Ctrl+S"
+// "view.toc" = ["Ctrl+Shift+O", "F2"]
+// "help.show" = []
+// ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::app_data;
+use crate::commands;
+use crate::state::AppState;
+use crate::storage;
+use crate::tr;
+
+/// 設定フォルダ内のファイル（TOML を優先する）
+const TOML_FILE: &str = "keybindings.toml";
+const JSON_FILE: &str = "keybindings.json";
+
+/// 修飾キー（正規化した順）
+const MODIFIERS: &[&str] = &["Ctrl", "Alt", "Shift", "Meta"];
+/// 名前で書くキー
+const NAMED_KEYS: &[&str] = &[
+    "Tab",
+    "Enter",
+    "Escape",
+    "Space",
+    "Backspace",
+    "Delete",
+    "Insert",
+    "Home",
+    "End",
+    "PageUp",
+    "PageDown",
+    "Up",
+    "Down",
+    "Left",
+    "Right",
+];
+
+/// コマンドに割り当てるキー（1 つなら文字列でも書ける）
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Keys {
+    fn list(&self) -> Vec<String> {
+        match self {
+            Keys::One(key) => vec![key.clone()],
+            Keys::Many(keys) => keys.clone(),
+        }
+    }
+}
+
+/// 割り当ての問題
+#[derive(Debug, Clone, Serialize)]
+pub struct KeybindingProblem {
+    /// "unknown_command" | "invalid_key" | "conflict"
+    pub kind: String,
+    pub command: String,
+    pub key: Option<String>,
+    pub message: String,
+}
+
+/// 読み込んだキー割り当て（`keybindings-changed` イベントのペイロードにもなる）
+#[derive(Debug, Clone, Serialize)]
+pub struct KeybindingReport {
+    /// 設定ファイル（まだなければ保存先）
+    pub path: String,
+    /// 利用者が書いた割り当て
+    pub overrides: BTreeMap<String, Keys>,
+    /// 既定と利用者の割り当てを合わせた、コマンドごとのキー（正規化済み）
+    pub bindings: BTreeMap<String, Vec<String>>,
+    pub problems: Vec<KeybindingProblem>,
+}
+
+/// キーの表記を正規化する（`ctrl+shift+s` → `Ctrl+Shift+S`。`Cmd` は `Meta`）
+pub fn normalize_key(key: &str) -> Option<String> {
+    let parts: Vec<&str> = key.split('+').map(str::trim).collect();
+    let (last, modifiers) = parts.split_last()?;
+    let mut found = Vec::new();
+    for modifier in modifiers {
+        let name = match modifier.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => "Ctrl",
+            "alt" | "option" => "Alt",
+            "shift" => "Shift",
+            "meta" | "cmd" | "command" | "super" => "Meta",
+            _ => return None,
+        };
+        if !found.contains(&name) {
+            found.push(name);
+        }
+    }
+    let lower = last.to_ascii_lowercase();
+    // `ArrowLeft` などブラウザの名前も受け付ける
+    let lower = lower.strip_prefix("arrow").unwrap_or(&lower);
+    let mut chars = last.chars();
+    let key = if let (Some(c), None) = (chars.next(), chars.next()) {
+        c.to_uppercase().to_string()
+    } else if lower == "esc" {
+        "Escape".to_string()
+    } else if let Some(named) = NAMED_KEYS.iter().find(|k| k.eq_ignore_ascii_case(lower)) {
+        named.to_string()
+    } else {
+        let n: u8 = lower.strip_prefix('f')?.parse().ok()?;
+        if !(1..=24).contains(&n) {
+            return None;
+        }
+        format!("F{n}")
+    };
+    let mut normalized: Vec<&str> = MODIFIERS
+        .iter()
+        .copied()
+        .filter(|m| found.contains(m))
+        .collect();
+    normalized.push(&key);
+    Some(normalized.join("+"))
+}
+
+fn keybindings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let toml = app_data::config_file(app, TOML_FILE)?;
+    storage::recover_missing(&toml);
+    if toml.is_file() {
+        return Ok(toml);
+    }
+    let json = app_data::config_file(app, JSON_FILE)?;
+    storage::recover_missing(&json);
+    Ok(json)
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "toml")
+}
+
+fn read_overrides(path: &Path) -> Result<BTreeMap<String, Keys>, String> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Ok(BTreeMap::new());
+    };
+    if is_toml(path) {
+        toml::from_str(&content).map_err(|e| tr!("Invalid {}: {e}", path.display()))
+    } else {
+        serde_json::from_str(&content).map_err(|e| tr!("Invalid {}: {e}", path.display()))
+    }
+}
+
+/// 既定の割り当てに利用者の割り当てを重ね、問題を調べる
+fn resolve(state: &AppState, path: &Path, overrides: BTreeMap<String, Keys>) -> KeybindingReport {
+    let commands = commands::all_commands(state);
+    let mut problems = Vec::new();
+    let mut bindings: BTreeMap<String, Vec<String>> = commands
+        .iter()
+        .map(|c| (c.id.clone(), c.keybinding.iter().cloned().collect()))
+        .collect();
+    for (command, keys) in &overrides {
+        if !bindings.contains_key(command) {
+            problems.push(KeybindingProblem {
+                kind: "unknown_command".to_string(),
+                command: command.clone(),
+                key: None,
+                message: tr!("Unknown command: {command}"),
+            });
+            continue;
+        }
+        let mut normalized = Vec::new();
+        for key in keys.list() {
+            match normalize_key(&key) {
+                Some(key) if !normalized.contains(&key) => normalized.push(key),
+                Some(_) => {}
+                None => problems.push(KeybindingProblem {
+                    kind: "invalid_key".to_string(),
+                    command: command.clone(),
+                    message: tr!("Invalid key: {key}"),
+                    key: Some(key),
+                }),
+            }
+        }
+        bindings.insert(command.clone(), normalized);
+    }
+
+    let mut by_key: BTreeMap<&String, Vec<&String>> = BTreeMap::new();
+    for (command, keys) in &bindings {
+        for key in keys {
+            by_key.entry(key).or_default().push(command);
+        }
+    }
+    for (key, commands) in by_key.iter().filter(|(_, c)| c.len() > 1) {
+        let names: Vec<&str> = commands.iter().map(|c| c.as_str()).collect();
+        for command in commands {
+            problems.push(KeybindingProblem {
+                kind: "conflict".to_string(),
+                command: command.to_string(),
+                key: Some(key.to_string()),
+                message: format!("{key} is bound to {}", names.join(", ")),
+            });
+        }
+    }
+    bindings.retain(|_, keys| !keys.is_empty());
+    KeybindingReport {
+        path: path.to_string_lossy().into_owned(),
+        overrides,
+        bindings,
+        problems,
+    }
+}
+
+/// キー割り当てを読み込む（コマンドの一覧と照合し、重複を報告する）
+#[tauri::command]
+pub fn load_keybindings(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<KeybindingReport, String> {
+    let path = keybindings_path(&app)?;
+    let overrides = read_overrides(&path)?;
+    Ok(resolve(&state, &path, overrides))
+}
+
+/// キー割り当てを保存し、`keybindings-changed` イベントを送る
+///
+/// 知らないコマンドや書式の誤ったキーがあれば保存しない。重複は報告するが保存する。
+#[tauri::command]
+pub fn save_keybindings(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    map: BTreeMap<String, Keys>,
+) -> Result<KeybindingReport, String> {
+    let path = keybindings_path(&app)?;
+    let report = resolve(&state, &path, map);
+    let invalid: Vec<&str> = report
+        .problems
+        .iter()
+        .filter(|p| p.kind != "conflict")
+        .map(|p| p.message.as_str())
+        .collect();
+    if !invalid.is_empty() {
+        return Err(invalid.join("\n"));
+    }
+    let content = if is_toml(&path) {
+        toml::to_string(&report.overrides)
+            .map_err(|e| tr!("Failed to serialize key bindings: {e}"))?
+    } else {
+        serde_json::to_string_pretty(&report.overrides)
+            .map_err(|e| tr!("Failed to serialize key bindings: {e}"))?
+    };
+    storage::write_atomic(&path, content.as_bytes())?;
+    let _ = app.emit("keybindings-changed", report.clone());
+    Ok(report)
+}
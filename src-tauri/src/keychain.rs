@@ -0,0 +1,175 @@
+// API キーなどの秘密情報の保存（OS のキーチェーンを利用し、WebView には渡さない）
+//
+// - macOS … `security`（ログインキーチェーン）
+// - Windows … PowerShell から資格情報コンテナー（PasswordVault）
+// - Linux … `secret-tool`（Secret Service）
+
+use std::process::{Command, Output};
+use std::time::Duration;
+
+use crate::shell::{self, RunOptions};
+use crate::tr;
+
+/// キーチェーンに登録するサービス名
+const SERVICE: &str = "mdvim";
+
+/// キーチェーンのコマンドを待つ時間（ロックの解除を求められる間も含む）
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 取り込む出力の上限
+const MAX_OUTPUT: usize = 1 << 20;
+
+/// PowerShell の単一引用符の文字列
+fn ps_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+const PS_VAULT: &str = "[void][Windows.Security.Credentials.PasswordVault,Windows.Security.Credentials,ContentType=WindowsRuntime];$v=New-Object Windows.Security.Credentials.PasswordVault;";
+
+fn run(command: Command, stdin: Option<&str>) -> Result<Output, String> {
+    let name = command.get_program().to_string_lossy().into_owned();
+    let output = shell::run(
+        command,
+        &name,
+        RunOptions {
+            input: stdin.map(|input| input.as_bytes().to_vec()),
+            timeout: TIMEOUT,
+            max_stdout: MAX_OUTPUT,
+            max_stderr: MAX_OUTPUT,
+            ..Default::default()
+        },
+    )
+    .map_err(|e| tr!("Keychain is not available: {e}"))?;
+    let Some(status) = output.status else {
+        return Err(tr!("{} timed out after {} s", name, TIMEOUT.as_secs()));
+    };
+    Ok(Output {
+        status,
+        stdout: output.stdout,
+        stderr: output.stderr,
+    })
+}
+
+fn failure(output: &Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match stderr.trim() {
+        "" => tr!("Keychain command failed ({})", output.status),
+        message => tr!("Keychain command failed: {message}"),
+    }
+}
+
+/// 秘密情報を保存（同じ名前があれば置き換える）
+pub fn set_secret(account: &str, secret: &str) -> Result<(), String> {
+    let output = if cfg!(target_os = "macos") {
+        let mut c = Command::new("security");
+        c.args([
+            "add-generic-password",
+            "-U",
+            "-s",
+            SERVICE,
+            "-a",
+            account,
+            "-w",
+            secret,
+        ]);
+        run(c, None)?
+    } else if cfg!(target_os = "windows") {
+        // 秘密情報はコマンドラインに載せず標準入力で渡す
+        let script = format!(
+            "{PS_VAULT}$s=[Console]::In.ReadToEnd();try{{$v.Remove($v.Retrieve({s},{a}))}}catch{{}};$v.Add((New-Object Windows.Security.Credentials.PasswordCredential({s},{a},$s)))",
+            s = ps_quote(SERVICE),
+            a = ps_quote(account)
+        );
+        let mut c = Command::new("powershell");
+        c.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+        run(c, Some(secret))?
+    } else {
+        let mut c = Command::new("secret-tool");
+        c.args(["store", "--label", &format!("{SERVICE}: {account}")]);
+        c.args(["service", SERVICE, "account", account]);
+        run(c, Some(secret))?
+    };
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(failure(&output))
+    }
+}
+
+/// 秘密情報を読む（登録されていなければ `None`）
+pub fn get_secret(account: &str) -> Result<Option<String>, String> {
+    let output = if cfg!(target_os = "macos") {
+        let mut c = Command::new("security");
+        c.args(["find-generic-password", "-s", SERVICE, "-a", account, "-w"]);
+        run(c, None)?
+    } else if cfg!(target_os = "windows") {
+        let script = format!(
+            "{PS_VAULT}try{{$c=$v.Retrieve({},{});$c.RetrievePassword();[Console]::Out.Write($c.Password)}}catch{{exit 1}}",
+            ps_quote(SERVICE),
+            ps_quote(account)
+        );
+        let mut c = Command::new("powershell");
+        c.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+        run(c, None)?
+    } else {
+        let mut c = Command::new("secret-tool");
+        c.args(["lookup", "service", SERVICE, "account", account]);
+        run(c, None)?
+    };
+    // 見つからない場合はどのツールも失敗の終了コードを返す
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let secret = String::from_utf8_lossy(&output.stdout);
+    let secret = secret.trim_end_matches(['\r', '\n']);
+    Ok((!secret.is_empty()).then(|| secret.to_string()))
+}
+
+/// 秘密情報を削除
+pub fn delete_secret(account: &str) -> Result<(), String> {
+    let output = if cfg!(target_os = "macos") {
+        let mut c = Command::new("security");
+        c.args(["delete-generic-password", "-s", SERVICE, "-a", account]);
+        run(c, None)?
+    } else if cfg!(target_os = "windows") {
+        let script = format!(
+            "{PS_VAULT}try{{$v.Remove($v.Retrieve({},{}))}}catch{{}}",
+            ps_quote(SERVICE),
+            ps_quote(account)
+        );
+        let mut c = Command::new("powershell");
+        c.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+        run(c, None)?
+    } else {
+        let mut c = Command::new("secret-tool");
+        c.args(["clear", "service", SERVICE, "account", account]);
+        run(c, None)?
+    };
+    // 登録されていなかった場合も成功とする
+    if output.status.success() || get_secret(account)?.is_none() {
+        Ok(())
+    } else {
+        Err(failure(&output))
+    }
+}
+
+/// API キーを保存（`name` は設定から参照する名前）
+#[tauri::command]
+pub fn set_api_key(name: String, key: String) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err(tr!("API key name is empty"));
+    }
+    set_secret(&name, &key)
+}
+
+/// API キーが保存されているか（キー自体は返さない）
+#[tauri::command]
+pub fn has_api_key(name: String) -> Result<bool, String> {
+    Ok(get_secret(&name)?.is_some())
+}
+
+/// 保存した API キーを削除
+#[tauri::command]
+pub fn delete_api_key(name: String) -> Result<(), String> {
+    delete_secret(&name)
+}
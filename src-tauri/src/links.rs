@@ -0,0 +1,272 @@
+// 文書内のリンク・画像・ウィキリンクの抽出
+
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::markdown;
+use crate::text::LineIndex;
+
+static WIKI_LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(!?)\[\[([^\[\]|#\n]*)(#[^\[\]|\n]*)?(\|[^\[\]\n]*)?\]\]").unwrap()
+});
+static REFERENCE_DEFINITION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?m)^ {0,3}\[([^\]\n]+)\]:[ \t]*(<[^>\n]*>|\S+)"#).unwrap());
+static HTML_SOURCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)<(?:img|a|source|video|audio|iframe)\b[^>]*?\b(?:src|href)\s*=\s*["']([^"']+)["']"#,
+    )
+    .unwrap()
+});
+
+/// リンクの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    /// `[text](target)`
+    Link,
+    /// `![alt](target)`
+    Image,
+    /// `[[target]]`
+    Wiki,
+    /// `![[target]]`
+    Embed,
+    /// `[id]: target`
+    Definition,
+    /// `<img src>` / `<a href>` など
+    Html,
+}
+
+/// 文書中の 1 件のリンク
+#[derive(Debug, Clone)]
+pub struct LinkRef {
+    pub kind: LinkKind,
+    /// `#` より前のリンク先（ウィキリンクはノート名）
+    pub target: String,
+    /// `#` 以降のアンカー（`#` は含まない）
+    pub anchor: Option<String>,
+    /// `target` のソース上のバイト範囲
+    pub target_range: Range<usize>,
+    /// `anchor` のソース上のバイト範囲
+    pub anchor_range: Option<Range<usize>>,
+    pub line: usize,
+}
+
+impl LinkRef {
+    /// ウィキリンク・埋め込みか
+    pub fn is_wiki(&self) -> bool {
+        matches!(self.kind, LinkKind::Wiki | LinkKind::Embed)
+    }
+}
+
+/// URL や同一文書内アンカーなど、ファイルを指さないリンク先か
+pub fn is_external(target: &str) -> bool {
+    target.is_empty()
+        || target.contains("://")
+        || ["mailto:", "data:", "tel:", "javascript:"]
+            .iter()
+            .any(|scheme| target.to_ascii_lowercase().starts_with(scheme))
+}
+
+/// `%20` などのパーセントエンコードを復元
+pub fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit()
+        {
+            let hex = |b: u8| (b as char).to_digit(16).unwrap() as u8;
+            out.push(hex(bytes[i + 1]) * 16 + hex(bytes[i + 2]));
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// リンク先に使えない文字（空白・括弧）をパーセントエンコード
+pub fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            ' ' => out.push_str("%20"),
+            '(' => out.push_str("%28"),
+            ')' => out.push_str("%29"),
+            '<' => out.push_str("%3C"),
+            '>' => out.push_str("%3E"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// `target#anchor` を分割して LinkRef を作る（`range` は `#` を含むリンク先全体）
+fn split_anchor(kind: LinkKind, raw: &str, range: Range<usize>, line: usize) -> LinkRef {
+    match raw.find('#') {
+        Some(i) => LinkRef {
+            kind,
+            target: raw[..i].to_string(),
+            anchor: Some(raw[i + 1..].to_string()),
+            target_range: range.start..range.start + i,
+            anchor_range: Some(range.start + i + 1..range.end),
+            line,
+        },
+        None => LinkRef {
+            kind,
+            target: raw.to_string(),
+            anchor: None,
+            target_range: range,
+            anchor_range: None,
+            line,
+        },
+    }
+}
+
+/// `](` の直後からリンク先を読み取り、(開始, 終了) を返す
+fn parse_destination(bytes: &[u8], start: usize) -> Option<(usize, usize)> {
+    let mut i = start;
+    while i < bytes.len() && (bytes[i] == b' ' || bytes[i] == b'\t') {
+        i += 1;
+    }
+    if bytes.get(i) == Some(&b'<') {
+        let begin = i + 1;
+        let end = begin
+            + bytes[begin..]
+                .iter()
+                .position(|&b| b == b'>' || b == b'\n')?;
+        return (bytes[end] == b'>').then_some((begin, end));
+    }
+    let begin = i;
+    let mut depth = 0usize;
+    while i < bytes.len() {
+        match bytes[i] {
+            b' ' | b'\t' | b'\n' | b'\r' => break,
+            b'(' => depth += 1,
+            b')' if depth == 0 => break,
+            b')' => depth -= 1,
+            b'\\' => i += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    let end = i.min(bytes.len());
+    (end > begin).then_some((begin, end))
+}
+
+/// 対応する `[` を後方に探し、その位置を返す（段落をまたがない）
+fn find_label_start(bytes: &[u8], close: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut j = close;
+    while j > 0 {
+        j -= 1;
+        match bytes[j] {
+            b']' => depth += 1,
+            b'[' if depth == 0 => return Some(j),
+            b'[' => depth -= 1,
+            b'\n' if j > 0 && bytes[j - 1] == b'\n' => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 文書中のリンクをソース順に抽出（コード内は除外）
+pub fn extract_links(content: &str) -> Vec<LinkRef> {
+    let code = markdown::code_ranges(content);
+    let index = LineIndex::new(content);
+    let bytes = content.as_bytes();
+    let mut links = Vec::new();
+
+    // インラインリンク・画像
+    for (close, _) in content.match_indices("](") {
+        if markdown::in_ranges(&code, close) || (close > 0 && bytes[close - 1] == b'\\') {
+            continue;
+        }
+        let Some(open) = find_label_start(bytes, close) else {
+            continue;
+        };
+        // `[[...]](` はウィキリンクとして扱う
+        if open > 0 && bytes[open - 1] == b'[' {
+            continue;
+        }
+        let Some((begin, end)) = parse_destination(bytes, close + 2) else {
+            continue;
+        };
+        let kind = if open > 0 && bytes[open - 1] == b'!' {
+            LinkKind::Image
+        } else {
+            LinkKind::Link
+        };
+        links.push(split_anchor(
+            kind,
+            &content[begin..end],
+            begin..end,
+            index.line_of(open),
+        ));
+    }
+
+    for caps in WIKI_LINK.captures_iter(content) {
+        let whole = caps.get(0).unwrap();
+        if markdown::in_ranges(&code, whole.start()) {
+            continue;
+        }
+        let target = caps.get(2).unwrap();
+        let anchor = caps.get(3).map(|m| m.start() + 1..m.end());
+        links.push(LinkRef {
+            kind: if &caps[1] == "!" {
+                LinkKind::Embed
+            } else {
+                LinkKind::Wiki
+            },
+            target: target.as_str().trim().to_string(),
+            anchor: anchor.clone().map(|r| content[r].to_string()),
+            target_range: target.range(),
+            anchor_range: anchor,
+            line: index.line_of(whole.start()),
+        });
+    }
+
+    for caps in REFERENCE_DEFINITION.captures_iter(content) {
+        let dest = caps.get(2).unwrap();
+        if markdown::in_ranges(&code, dest.start()) || caps[1].starts_with('^') {
+            continue;
+        }
+        let range = if dest.as_str().starts_with('<') {
+            dest.start() + 1..dest.end() - 1
+        } else {
+            dest.range()
+        };
+        let line = index.line_of(dest.start());
+        links.push(split_anchor(
+            LinkKind::Definition,
+            &content[range.clone()],
+            range,
+            line,
+        ));
+    }
+
+    for caps in HTML_SOURCE.captures_iter(content) {
+        let src = caps.get(1).unwrap();
+        if markdown::in_ranges(&code, src.start()) {
+            continue;
+        }
+        let line = index.line_of(src.start());
+        links.push(split_anchor(
+            LinkKind::Html,
+            src.as_str(),
+            src.range(),
+            line,
+        ));
+    }
+
+    links.sort_by_key(|l| l.target_range.start);
+    links
+}
@@ -0,0 +1,688 @@
+// 言語サーバー（marksman など）との LSP 通信（標準入出力の JSON-RPC）
+//
+// 位置はエディタ（Monaco）に合わせて 1 始まりの行・列で受け渡す。応答を待つコマンドは
+// UI のスレッドを止めないよう非同期のコマンドとして実行する。
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::export;
+use crate::links;
+use crate::state::AppState;
+use crate::text::{self, LineIndex};
+use crate::tr;
+use crate::vault;
+
+/// 要求への応答を待つ時間
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Reply = Result<Value, String>;
+
+struct Server {
+    stdin: Mutex<ChildStdin>,
+    child: Mutex<Child>,
+    next_id: AtomicU64,
+    /// 応答待ちの要求
+    pending: Mutex<HashMap<u64, Sender<Reply>>>,
+    /// 開いている文書のバージョン
+    versions: Mutex<HashMap<String, i64>>,
+}
+
+/// 起動中の言語サーバー（名前ごと）
+#[derive(Default)]
+pub struct LspState {
+    servers: Mutex<HashMap<String, Arc<Server>>>,
+}
+
+/// エディタ上の範囲（1 始まり、終了位置は含まない）
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LspRange {
+    pub start_line: u64,
+    pub start_column: u64,
+    pub end_line: u64,
+    pub end_column: u64,
+}
+
+impl LspRange {
+    /// 文書のバイト範囲からエディタ上の範囲を作る
+    pub fn from_offsets(index: &LineIndex, content: &str, range: Range<usize>) -> Self {
+        let (start_line, start_column) = index.position(content, range.start);
+        let (end_line, end_column) = index.position(content, range.end);
+        Self {
+            start_line: start_line as u64,
+            start_column: start_column as u64,
+            end_line: end_line as u64,
+            end_column: end_column as u64,
+        }
+    }
+
+    /// 文書のバイト範囲
+    pub fn to_offsets(&self, content: &str) -> Range<usize> {
+        let start = text::offset_at(
+            content,
+            self.start_line as usize,
+            self.start_column as usize,
+        );
+        let end = text::offset_at(content, self.end_line as usize, self.end_column as usize);
+        start..end.max(start)
+    }
+}
+
+/// `lsp-diagnostics` イベントの 1 件
+#[derive(Debug, Clone, Serialize)]
+pub struct LspDiagnostic {
+    pub range: LspRange,
+    /// "error" | "warning" | "info" | "hint"
+    pub severity: String,
+    pub message: String,
+    pub source: Option<String>,
+    pub code: Option<String>,
+}
+
+/// `lsp-diagnostics` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct LspDiagnostics {
+    pub server: String,
+    pub path: String,
+    pub diagnostics: Vec<LspDiagnostic>,
+}
+
+/// `lsp-message` イベントのペイロード（サーバーからの通知メッセージ）
+#[derive(Debug, Clone, Serialize)]
+pub struct LspMessage {
+    pub server: String,
+    /// "error" | "warning" | "info" | "log"
+    pub level: String,
+    pub message: String,
+}
+
+/// 補完候補
+#[derive(Debug, Clone, Serialize)]
+pub struct LspCompletion {
+    pub label: String,
+    /// 種類の名前（"file" や "reference" など）
+    pub kind: Option<String>,
+    pub detail: Option<String>,
+    pub documentation: Option<String>,
+    pub insert_text: String,
+    /// 置き換える範囲（省略時は入力中の単語）
+    pub range: Option<LspRange>,
+    pub sort_text: Option<String>,
+}
+
+/// 定義・参照の位置
+#[derive(Debug, Clone, Serialize)]
+pub struct LspLocation {
+    pub path: String,
+    pub range: LspRange,
+}
+
+fn path_to_uri(path: &str) -> String {
+    export::file_url(&vault::normalize(Path::new(path)))
+}
+
+fn uri_to_path(uri: &str) -> String {
+    let path = links::percent_decode(uri.strip_prefix("file://").unwrap_or(uri));
+    // Windows の `file:///C:/...`
+    let bytes = path.as_bytes();
+    if bytes.len() > 2 && bytes[0] == b'/' && bytes[2] == b':' {
+        return path[1..].to_string();
+    }
+    path
+}
+
+fn write_message(stdin: &mut ChildStdin, message: &Value) -> Result<(), String> {
+    let body = message.to_string();
+    let data = format!("Content-Length: {}\r\n\r\n{body}", body.len());
+    stdin
+        .write_all(data.as_bytes())
+        .and_then(|_| stdin.flush())
+        .map_err(|e| tr!("Failed to write to language server: {e}"))
+}
+
+fn read_message(reader: &mut impl BufRead) -> Option<Value> {
+    let mut length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let mut body = vec![0; length?];
+    reader.read_exact(&mut body).ok()?;
+    // 解釈できない本文は空のオブジェクトとして読み飛ばす
+    Some(serde_json::from_slice(&body).unwrap_or_else(|_| json!({})))
+}
+
+impl Server {
+    fn send(&self, message: &Value) -> Result<(), String> {
+        write_message(&mut self.stdin.lock().unwrap(), message)
+    }
+
+    fn notify(&self, method: &str, params: Value) -> Result<(), String> {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+
+    fn request_with_timeout(&self, method: &str, params: Value, timeout: Duration) -> Reply {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = mpsc::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if let Err(e) = self.send(&message) {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(e);
+        }
+        match rx.recv_timeout(timeout) {
+            Ok(reply) => reply,
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                Err(tr!("Language server did not respond to {method}"))
+            }
+        }
+    }
+
+    fn request(&self, method: &str, params: Value) -> Reply {
+        self.request_with_timeout(method, params, REQUEST_TIMEOUT)
+    }
+
+    /// サーバーからの要求に答える（設定などは提供しないので空で返す）
+    fn answer(&self, id: Value, method: &str, params: &Value) {
+        let result = match method {
+            "workspace/configuration" => {
+                let count = params["items"].as_array().map_or(0, Vec::len);
+                Value::Array(vec![Value::Null; count])
+            }
+            _ => Value::Null,
+        };
+        let _ = self.send(&json!({ "jsonrpc": "2.0", "id": id, "result": result }));
+    }
+}
+
+fn range(value: &Value) -> LspRange {
+    let at = |pos: &Value, key: &str| pos[key].as_u64().unwrap_or(0) + 1;
+    LspRange {
+        start_line: at(&value["start"], "line"),
+        start_column: at(&value["start"], "character"),
+        end_line: at(&value["end"], "line"),
+        end_column: at(&value["end"], "character"),
+    }
+}
+
+fn optional_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// MarkupContent・MarkedString を Markdown の文字列に
+fn markup(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Array(items) => {
+            let parts: Vec<String> = items.iter().filter_map(markup).collect();
+            (!parts.is_empty()).then(|| parts.join("\n\n"))
+        }
+        Value::Object(object) => {
+            let text = object.get("value")?.as_str()?;
+            match object.get("language").and_then(Value::as_str) {
+                Some(language) => Some(format!("```{language}\n{text}\n```")),
+                None => Some(text.to_string()),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn diagnostic(value: &Value) -> LspDiagnostic {
+    let severity = match value["severity"].as_u64() {
+        Some(1) => "error",
+        Some(2) => "warning",
+        Some(4) => "hint",
+        _ => "info",
+    };
+    LspDiagnostic {
+        range: range(&value["range"]),
+        severity: severity.to_string(),
+        message: value["message"].as_str().unwrap_or_default().to_string(),
+        source: optional_string(&value["source"]),
+        code: optional_string(&value["code"]),
+    }
+}
+
+/// LSP の CompletionItemKind の名前
+fn completion_kind(kind: u64) -> Option<&'static str> {
+    const KINDS: [&str; 25] = [
+        "text",
+        "method",
+        "function",
+        "constructor",
+        "field",
+        "variable",
+        "class",
+        "interface",
+        "module",
+        "property",
+        "unit",
+        "value",
+        "enum",
+        "keyword",
+        "snippet",
+        "color",
+        "file",
+        "reference",
+        "folder",
+        "enum_member",
+        "constant",
+        "struct",
+        "event",
+        "operator",
+        "type_parameter",
+    ];
+    KINDS.get(kind.checked_sub(1)? as usize).copied()
+}
+
+fn completion(value: &Value) -> LspCompletion {
+    let label = value["label"].as_str().unwrap_or_default().to_string();
+    let edit = &value["textEdit"];
+    // InsertReplaceEdit は挿入範囲を使う
+    let edit_range = if edit["range"].is_object() {
+        Some(range(&edit["range"]))
+    } else if edit["insert"].is_object() {
+        Some(range(&edit["insert"]))
+    } else {
+        None
+    };
+    let insert_text = edit["newText"]
+        .as_str()
+        .or_else(|| value["insertText"].as_str())
+        .unwrap_or(&label)
+        .to_string();
+    LspCompletion {
+        kind: value["kind"]
+            .as_u64()
+            .and_then(completion_kind)
+            .map(str::to_string),
+        detail: optional_string(&value["detail"]),
+        documentation: markup(&value["documentation"]),
+        insert_text,
+        range: edit_range,
+        sort_text: optional_string(&value["sortText"]),
+        label,
+    }
+}
+
+/// Location・Location[]・LocationLink[] を位置の一覧に
+fn locations(value: &Value) -> Vec<LspLocation> {
+    let items = match value {
+        Value::Array(items) => items.as_slice(),
+        Value::Null => &[],
+        single => std::slice::from_ref(single),
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            let (uri, target) = match item.get("targetUri") {
+                Some(uri) => (uri, &item["targetSelectionRange"]),
+                None => (item.get("uri")?, &item["range"]),
+            };
+            Some(LspLocation {
+                path: uri_to_path(uri.as_str()?),
+                range: range(target),
+            })
+        })
+        .collect()
+}
+
+/// サーバーからのメッセージを読み続ける（終了したら状態から外す）
+fn read_loop(app: AppHandle, name: String, server: Arc<Server>, stdout: ChildStdout) {
+    let mut reader = BufReader::new(stdout);
+    while let Some(message) = read_message(&mut reader) {
+        let method = message["method"].as_str();
+        match (message.get("id"), method) {
+            (Some(id), None) => {
+                let Some(sender) = id
+                    .as_u64()
+                    .and_then(|id| server.pending.lock().unwrap().remove(&id))
+                else {
+                    continue;
+                };
+                let reply = match message.get("error") {
+                    Some(error) => Err(tr!(
+                        "Language server error: {}",
+                        error["message"].as_str().unwrap_or("unknown error")
+                    )),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = sender.send(reply);
+            }
+            (Some(id), Some(method)) => server.answer(id.clone(), method, &message["params"]),
+            (None, Some("textDocument/publishDiagnostics")) => {
+                let params = &message["params"];
+                let diagnostics = params["diagnostics"]
+                    .as_array()
+                    .map(|d| d.iter().map(diagnostic).collect())
+                    .unwrap_or_default();
+                let _ = app.emit(
+                    "lsp-diagnostics",
+                    LspDiagnostics {
+                        server: name.clone(),
+                        path: uri_to_path(params["uri"].as_str().unwrap_or_default()),
+                        diagnostics,
+                    },
+                );
+            }
+            (None, Some("window/showMessage" | "window/logMessage")) => {
+                let params = &message["params"];
+                let level = match params["type"].as_u64() {
+                    Some(1) => "error",
+                    Some(2) => "warning",
+                    Some(3) => "info",
+                    _ => "log",
+                };
+                let _ = app.emit(
+                    "lsp-message",
+                    LspMessage {
+                        server: name.clone(),
+                        level: level.to_string(),
+                        message: params["message"].as_str().unwrap_or_default().to_string(),
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+
+    // 応答待ちの要求をすべて失敗させる
+    for (_, sender) in server.pending.lock().unwrap().drain() {
+        let _ = sender.send(Err(tr!("Language server exited")));
+    }
+    let _ = server.child.lock().unwrap().wait();
+    let state = app.state::<AppState>();
+    let mut servers = state.lsp.servers.lock().unwrap();
+    if servers.get(&name).is_some_and(|s| Arc::ptr_eq(s, &server)) {
+        servers.remove(&name);
+        drop(servers);
+        let _ = app.emit("lsp-exited", name);
+    }
+}
+
+fn shutdown(server: &Server) {
+    let _ = server.request_with_timeout("shutdown", Value::Null, Duration::from_secs(2));
+    let _ = server.notify("exit", Value::Null);
+    let _ = server.child.lock().unwrap().kill();
+}
+
+fn server(state: &LspState, name: &str) -> Result<Arc<Server>, String> {
+    state
+        .servers
+        .lock()
+        .unwrap()
+        .get(name)
+        .cloned()
+        .ok_or_else(|| tr!("Language server is not running: {name}"))
+}
+
+fn position_params(path: &str, line: u64, column: u64) -> Value {
+    json!({
+        "textDocument": { "uri": path_to_uri(path) },
+        "position": { "line": line.saturating_sub(1), "character": column.saturating_sub(1) },
+    })
+}
+
+/// 言語サーバーを起動して初期化（サーバーの capabilities を返す）
+#[tauri::command(async)]
+pub fn lsp_start(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+    command: String,
+    args: Option<Vec<String>>,
+    root: String,
+) -> Result<Value, String> {
+    // 言語サーバーは任意のコマンドなので、信頼したワークスペースでだけ起動する
+    state
+        .trust
+        .require(&app, Path::new(&root), "language servers")?;
+    let previous = state.lsp.servers.lock().unwrap().remove(&name);
+    if let Some(previous) = previous {
+        shutdown(&previous);
+    }
+
+    let root = vault::normalize(Path::new(&root));
+    let mut child = Command::new(&command)
+        .args(args.unwrap_or_default())
+        .current_dir(&root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| tr!("Failed to start {command}: {e}"))?;
+    let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+        let _ = child.kill();
+        return Err(tr!("Failed to start {command}"));
+    };
+    let server = Arc::new(Server {
+        stdin: Mutex::new(stdin),
+        child: Mutex::new(child),
+        next_id: AtomicU64::new(1),
+        pending: Mutex::new(HashMap::new()),
+        versions: Mutex::new(HashMap::new()),
+    });
+    state
+        .lsp
+        .servers
+        .lock()
+        .unwrap()
+        .insert(name.clone(), server.clone());
+    let reader_server = server.clone();
+    let reader_name = name.clone();
+    thread::spawn(move || read_loop(app, reader_name, reader_server, stdout));
+
+    let root_uri = export::file_url(&root);
+    let folder_name = root
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let params = json!({
+        "processId": std::process::id(),
+        "clientInfo": { "name": "mdvim" },
+        "rootUri": root_uri,
+        "rootPath": vault::path_string(&root),
+        "workspaceFolders": [{ "uri": root_uri, "name": folder_name }],
+        "capabilities": {
+            "textDocument": {
+                "synchronization": { "didSave": false },
+                "completion": {
+                    "completionItem": {
+                        "snippetSupport": false,
+                        "documentationFormat": ["markdown", "plaintext"],
+                    },
+                },
+                "hover": { "contentFormat": ["markdown", "plaintext"] },
+                "definition": { "linkSupport": true },
+                "references": {},
+                "publishDiagnostics": {},
+            },
+            "workspace": { "workspaceFolders": true, "configuration": true },
+        },
+    });
+    let result = match server.request("initialize", params) {
+        Ok(result) => result,
+        Err(e) => {
+            state.lsp.servers.lock().unwrap().remove(&name);
+            let _ = server.child.lock().unwrap().kill();
+            return Err(e);
+        }
+    };
+    server.notify("initialized", json!({}))?;
+    Ok(result["capabilities"].clone())
+}
+
+/// 言語サーバーを終了
+#[tauri::command(async)]
+pub fn lsp_stop(state: State<'_, AppState>, name: String) -> Result<(), String> {
+    let server = state.lsp.servers.lock().unwrap().remove(&name);
+    if let Some(server) = server {
+        shutdown(&server);
+    }
+    Ok(())
+}
+
+/// エディタで開いた文書をサーバーに知らせる
+#[tauri::command(async)]
+pub fn lsp_open(
+    state: State<'_, AppState>,
+    name: String,
+    path: String,
+    content: String,
+) -> Result<(), String> {
+    let server = server(&state.lsp, &name)?;
+    server.versions.lock().unwrap().insert(path.clone(), 1);
+    server.notify(
+        "textDocument/didOpen",
+        json!({
+            "textDocument": {
+                "uri": path_to_uri(&path),
+                "languageId": "markdown",
+                "version": 1,
+                "text": content,
+            },
+        }),
+    )
+}
+
+/// 文書の変更を知らせる（全文を送る）
+#[tauri::command(async)]
+pub fn lsp_change(
+    state: State<'_, AppState>,
+    name: String,
+    path: String,
+    content: String,
+) -> Result<(), String> {
+    let server = server(&state.lsp, &name)?;
+    let version = {
+        let mut versions = server.versions.lock().unwrap();
+        let version = versions.entry(path.clone()).or_insert(0);
+        *version += 1;
+        *version
+    };
+    server.notify(
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": path_to_uri(&path), "version": version },
+            "contentChanges": [{ "text": content }],
+        }),
+    )
+}
+
+/// 文書を閉じたことを知らせる
+#[tauri::command(async)]
+pub fn lsp_close(state: State<'_, AppState>, name: String, path: String) -> Result<(), String> {
+    let server = server(&state.lsp, &name)?;
+    server.versions.lock().unwrap().remove(&path);
+    server.notify(
+        "textDocument/didClose",
+        json!({ "textDocument": { "uri": path_to_uri(&path) } }),
+    )
+}
+
+/// カーソル位置の補完候補
+#[tauri::command(async)]
+pub fn lsp_completion(
+    state: State<'_, AppState>,
+    name: String,
+    path: String,
+    line: u64,
+    column: u64,
+) -> Result<Vec<LspCompletion>, String> {
+    let server = server(&state.lsp, &name)?;
+    let result = server.request(
+        "textDocument/completion",
+        position_params(&path, line, column),
+    )?;
+    // CompletionItem[] または CompletionList
+    let items = match &result {
+        Value::Array(items) => items.as_slice(),
+        list => list["items"].as_array().map_or(&[][..], Vec::as_slice),
+    };
+    Ok(items.iter().map(completion).collect())
+}
+
+/// カーソル位置のホバー情報（Markdown）
+#[tauri::command(async)]
+pub fn lsp_hover(
+    state: State<'_, AppState>,
+    name: String,
+    path: String,
+    line: u64,
+    column: u64,
+) -> Result<Option<String>, String> {
+    let server = server(&state.lsp, &name)?;
+    let result = server.request("textDocument/hover", position_params(&path, line, column))?;
+    Ok(markup(&result["contents"]))
+}
+
+/// カーソル位置のリンク先（ウィキリンクなど）の定義
+#[tauri::command(async)]
+pub fn lsp_definition(
+    state: State<'_, AppState>,
+    name: String,
+    path: String,
+    line: u64,
+    column: u64,
+) -> Result<Vec<LspLocation>, String> {
+    let server = server(&state.lsp, &name)?;
+    let result = server.request(
+        "textDocument/definition",
+        position_params(&path, line, column),
+    )?;
+    Ok(locations(&result))
+}
+
+/// カーソル位置の見出し・ノートを参照している箇所
+#[tauri::command(async)]
+pub fn lsp_references(
+    state: State<'_, AppState>,
+    name: String,
+    path: String,
+    line: u64,
+    column: u64,
+) -> Result<Vec<LspLocation>, String> {
+    let server = server(&state.lsp, &name)?;
+    let mut params = position_params(&path, line, column);
+    params["context"] = json!({ "includeDeclaration": true });
+    let result = server.request("textDocument/references", params)?;
+    Ok(locations(&result))
+}
+
+/// 起動中の言語サーバーの名前
+#[tauri::command]
+pub fn lsp_servers(state: State<'_, AppState>) -> Vec<String> {
+    let mut names: Vec<String> = state.lsp.servers.lock().unwrap().keys().cloned().collect();
+    names.sort();
+    names
+}
@@ -0,0 +1,150 @@
+// Vim のマクロの保存（アプリのデータフォルダの `macros.json`。ファイルへの書き出しと読み込みもできる）
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::app_data;
+use crate::tr;
+
+/// データフォルダ内のファイル
+const MACROS_FILE: &str = "macros.json";
+/// 書き出すファイルの形式
+const EXPORT_VERSION: u32 = 1;
+
+/// マクロ
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Macro {
+    /// レジスタ（`a`〜`z`）
+    pub register: char,
+    /// キーの列（Vim の記法。`<Esc>` `<CR>` など）
+    pub keys: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// 書き出したマクロのファイル
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct MacrosFile {
+    version: u32,
+    macros: Vec<Macro>,
+}
+
+fn load(app: &AppHandle) -> Result<BTreeMap<char, Macro>, String> {
+    app_data::read_json(&app_data::data_file(app, MACROS_FILE)?)
+}
+
+fn store(app: &AppHandle, macros: &BTreeMap<char, Macro>) -> Result<(), String> {
+    app_data::write_json(&app_data::data_file(app, MACROS_FILE)?, macros)
+}
+
+/// レジスタの文字（大文字は追記として小文字のレジスタに、Vim の `qA` と同じ）
+fn register_name(register: &str) -> Result<(char, bool), String> {
+    let mut chars = register.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_lowercase() => Ok((c, false)),
+        (Some(c), None) if c.is_ascii_uppercase() => Ok((c.to_ascii_lowercase(), true)),
+        _ => Err(tr!("Invalid register: {register}")),
+    }
+}
+
+/// マクロを保存する（大文字のレジスタなら既存のマクロに追記する）
+#[tauri::command]
+pub fn save_macro(
+    app: AppHandle,
+    register: String,
+    keys: String,
+    description: Option<String>,
+) -> Result<Macro, String> {
+    let (name, append) = register_name(&register)?;
+    let mut macros = load(&app)?;
+    let saved = match macros.get_mut(&name) {
+        Some(existing) if append => {
+            existing.keys.push_str(&keys);
+            if description.is_some() {
+                existing.description = description;
+            }
+            existing.clone()
+        }
+        _ => {
+            let saved = Macro {
+                register: name,
+                keys,
+                description,
+            };
+            macros.insert(name, saved.clone());
+            saved
+        }
+    };
+    store(&app, &macros)?;
+    Ok(saved)
+}
+
+/// 保存したマクロ（レジスタの順）
+#[tauri::command]
+pub fn list_macros(app: AppHandle) -> Result<Vec<Macro>, String> {
+    Ok(load(&app)?.into_values().collect())
+}
+
+/// マクロを削除する
+#[tauri::command]
+pub fn delete_macro(app: AppHandle, register: String) -> Result<(), String> {
+    let (name, _) = register_name(&register)?;
+    let mut macros = load(&app)?;
+    if macros.remove(&name).is_none() {
+        return Err(tr!("Macro not found: {register}"));
+    }
+    store(&app, &macros)
+}
+
+/// マクロをファイルに書き出す（別の環境で `import_macros` で読み込む）
+#[tauri::command]
+pub fn export_macros(app: AppHandle, path: String) -> Result<usize, String> {
+    let macros: Vec<Macro> = load(&app)?.into_values().collect();
+    let count = macros.len();
+    app_data::write_json(
+        Path::new(&path),
+        &MacrosFile {
+            version: EXPORT_VERSION,
+            macros,
+        },
+    )?;
+    Ok(count)
+}
+
+/// 書き出したマクロを読み込む（`overwrite` でなければ同じレジスタの既存のマクロを残す。読み込んだ数を返す）
+#[tauri::command]
+pub fn import_macros(
+    app: AppHandle,
+    path: String,
+    overwrite: Option<bool>,
+) -> Result<usize, String> {
+    let path = Path::new(&path);
+    if !path.is_file() {
+        return Err(tr!("File not found: {}", path.display()));
+    }
+    // 利用者が選んだファイルなので、壊れていても退避や復旧はせずにエラーにする
+    let text =
+        fs::read_to_string(path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+    let file: MacrosFile =
+        serde_json::from_str(&text).map_err(|e| tr!("Failed to parse {}: {e}", path.display()))?;
+    if file.version > EXPORT_VERSION {
+        return Err(tr!("Unsupported macros file version: {}", file.version));
+    }
+    let mut macros = load(&app)?;
+    let mut imported = 0;
+    for mut item in file.macros {
+        let (name, _) = register_name(&item.register.to_string())?;
+        if !overwrite.unwrap_or(false) && macros.contains_key(&name) {
+            continue;
+        }
+        item.register = name;
+        macros.insert(name, item);
+        imported += 1;
+    }
+    store(&app, &macros)?;
+    Ok(imported)
+}
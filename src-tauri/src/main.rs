@@ -6,6 +6,114 @@
     windows_subsystem = "windows"
 )]
 
+mod agenda;
+mod ai;
+mod annotations;
+mod app_data;
+mod asciidoc;
+mod attachments;
+mod backup;
+mod batch_export;
+mod benchmark;
+mod blake3;
+mod bookmarks;
+mod bundle;
+mod capture;
+mod chart;
+mod clipboard;
+mod code_block;
+mod collab;
+mod commands;
+mod commonmark;
+mod completion;
+mod crdt;
+mod critic;
+mod data_block;
+mod dates;
+mod diagram;
+mod diff;
+mod diff_report;
+mod docx;
+mod duplicates;
+mod editorconfig;
+mod edits;
+mod ex;
+mod export;
+mod export_profiles;
+mod files;
+mod filter;
+mod folding;
+mod formatter;
+mod front_matter;
+mod gfm;
+mod grammar;
+mod graph;
+mod hooks;
+mod hover;
+mod html_markdown;
+mod i18n;
+mod image_optimize;
+mod images;
+mod integrity;
+mod ipynb;
+mod jobs;
+mod kanban;
+mod keybindings;
+mod keychain;
+mod links;
+mod lsp;
+mod macros;
+mod manuscript;
+mod markdown;
+mod marks;
+mod md5;
+mod note_import;
+mod notebook;
+mod obsidian;
+mod ocr;
+mod org;
+mod organize;
+mod output_template;
+mod plugins;
+mod preprocess;
+mod preview;
+mod preview_server;
+mod prose;
+mod recording;
+mod refactor;
+mod reference;
+mod reminders;
+mod render;
+mod render_profile;
+mod render_queue;
+mod replace;
+mod saved_searches;
+mod search_index;
+mod selection;
+mod semantic;
+mod shell;
+mod similar;
+#[cfg(test)]
+mod spec;
+mod state;
+mod storage;
+mod symbols;
+mod table;
+mod table_formula;
+mod tasks;
+mod templates;
+mod text;
+mod translate;
+mod trust;
+mod tts;
+mod undo_history;
+mod vault;
+mod vimrc;
+mod wasm;
+mod watcher;
+mod xlsx;
+mod zip;
+
 use serde::{Deserialize, Serialize};
 
 /// ファイル情報
@@ -15,6 +123,12 @@ pub struct FileInfo {
     pub name: String,
     pub content: String,
     pub modified: bool,
+    /// 改行コード
+    pub line_ending: files::LineEnding,
+    /// 読み取り専用
+    pub read_only: bool,
+    /// 所有者に書き込み権限がある
+    pub owner_writable: bool,
 }
 
 /// アプリケーション情報を取得
@@ -35,8 +149,210 @@ fn main() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
+        .manage(state::AppState::default())
+        .setup(|app| {
+            i18n::load_at_startup(app.handle());
+            vimrc::load_at_startup(app.handle());
+            trust::load_at_startup(app.handle());
+            files::load_at_startup(app.handle());
+            reminders::load_at_startup(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_app_info,
+            prose::analyze_prose,
+            manuscript::get_manuscript_stats,
+            tts::speak_text,
+            tts::stop_speaking,
+            ocr::ocr_import,
+            attachments::list_attachments,
+            attachments::find_orphaned_assets,
+            attachments::relink_attachment,
+            refactor::rename_with_link_update,
+            refactor::rename_heading,
+            export::export_vault_html,
+            export::watch_and_export,
+            export::stop_watch_export,
+            bundle::export_bundle,
+            bundle::import_bundle,
+            integrity::hash_file,
+            integrity::create_manifest,
+            integrity::verify_manifest,
+            backup::configure_backups,
+            backup::get_backup_config,
+            backup::backup_now,
+            backup::list_backups,
+            backup::restore_backup,
+            preview_server::start_preview_server,
+            preview_server::update_preview,
+            preview_server::stop_preview_server,
+            collab::start_collab_session,
+            collab::join_collab_session,
+            collab::collab_edit,
+            collab::collab_cursor,
+            collab::leave_collab_session,
+            lsp::lsp_start,
+            lsp::lsp_stop,
+            lsp::lsp_servers,
+            lsp::lsp_open,
+            lsp::lsp_change,
+            lsp::lsp_close,
+            lsp::lsp_completion,
+            lsp::lsp_hover,
+            lsp::lsp_definition,
+            lsp::lsp_references,
+            plugins::list_plugins,
+            plugins::enable_plugin,
+            plugins::run_plugin_transforms,
+            plugins::plugin_export,
+            plugins::run_plugin_command,
+            hooks::configure_hooks,
+            hooks::get_hooks,
+            hooks::run_hooks,
+            preprocess::configure_preprocessors,
+            preprocess::preprocess_markdown,
+            table::csv_to_table,
+            table::table_to_csv,
+            xlsx::xlsx_sheets,
+            xlsx::import_xlsx_table,
+            ipynb::import_ipynb,
+            org::import_org,
+            asciidoc::import_asciidoc,
+            note_import::import_notes,
+            obsidian::obsidian_settings,
+            obsidian::list_tags,
+            obsidian::attachment_folder,
+            obsidian::open_daily_note,
+            graph::get_link_graph,
+            similar::find_similar_notes,
+            semantic::configure_embeddings,
+            semantic::build_semantic_index,
+            semantic::semantic_search,
+            keychain::set_api_key,
+            keychain::has_api_key,
+            keychain::delete_api_key,
+            ai::run_ai_action,
+            ai::cancel_ai_action,
+            translate::translate_text,
+            grammar::configure_grammar_check,
+            grammar::grammar_check,
+            completion::get_completions,
+            symbols::get_document_symbols,
+            folding::get_folding_ranges,
+            selection::get_selection_ranges,
+            hover::get_hover_preview,
+            reference::resolve_reference,
+            formatter::format_document,
+            formatter::format_on_save,
+            editorconfig::get_editorconfig,
+            files::configure_save_normalization,
+            files::get_save_normalization,
+            files::write_file,
+            files::read_file,
+            files::make_writable,
+            files::save_as,
+            files::get_session,
+            files::close_file,
+            export::export_html,
+            export::apply_task_state,
+            templates::list_export_templates,
+            preview::parse_markdown,
+            diagram::configure_diagrams,
+            diagram::render_diagram,
+            images::probe_image,
+            images::check_images,
+            image_optimize::configure_image_optimization,
+            image_optimize::get_image_optimization,
+            image_optimize::save_image,
+            capture::capture_screenshot,
+            recording::start_audio_recording,
+            recording::stop_audio_recording,
+            clipboard::get_clipboard_contents,
+            render_profile::configure_render_profiles,
+            render_profile::list_render_profiles,
+            render_profile::resolve_render_profile,
+            commonmark::validate_commonmark,
+            benchmark::benchmark_parse,
+            preview::get_parse_cache_stats,
+            preview::clear_parse_cache,
+            render_queue::request_render,
+            render_queue::cancel_render,
+            tasks::cancel_task,
+            tasks::list_tasks,
+            jobs::start_job,
+            search_index::build_search_index,
+            search_index::fts_search,
+            saved_searches::save_search,
+            saved_searches::list_saved_searches,
+            saved_searches::delete_saved_search,
+            saved_searches::run_saved_search,
+            bookmarks::add_bookmark,
+            bookmarks::list_bookmarks,
+            bookmarks::remove_bookmark,
+            marks::set_mark,
+            marks::delete_mark,
+            marks::get_marks,
+            marks::push_jump,
+            marks::get_jumplist,
+            undo_history::save_undo_history,
+            undo_history::load_undo_history,
+            undo_history::prune_undo_history,
+            macros::save_macro,
+            macros::list_macros,
+            macros::delete_macro,
+            macros::export_macros,
+            macros::import_macros,
+            commands::list_commands,
+            keybindings::load_keybindings,
+            keybindings::save_keybindings,
+            vimrc::get_vim_config,
+            vimrc::reload_vim_config,
+            trust::get_workspace_trust,
+            trust::set_workspace_trust,
+            trust::forget_workspace_trust,
+            trust::list_workspace_trust,
+            storage::storage_health,
+            i18n::get_locale,
+            i18n::set_locale,
+            dates::format_date,
+            front_matter::validate_front_matter,
+            front_matter::validate_vault_front_matter,
+            organize::organize_vault,
+            duplicates::find_duplicates,
+            organize::archive_notes,
+            diff_report::export_diff_html,
+            annotations::add_annotation,
+            annotations::resolve_annotation,
+            annotations::remove_annotation,
+            annotations::list_annotations,
+            critic::accept_all_changes,
+            critic::reject_all_changes,
+            edits::apply_text_edits,
+            replace::regex_replace,
+            ex::run_ex_command,
+            filter::filter_through_command,
+            shell::run_shell_capture,
+            notebook::get_notebook_config,
+            notebook::set_notebook_config,
+            notebook::execute_code_block,
+            notebook::place_code_result,
+            table_formula::evaluate_table_formulas,
+            dates::parse_natural_date,
+            agenda::get_agenda,
+            reminders::get_reminder_config,
+            reminders::set_reminder_config,
+            reminders::set_vault_reminders,
+            reminders::snooze_reminder,
+            kanban::parse_kanban,
+            kanban::apply_kanban_move,
+            data_block::load_data_block,
+            docx::export_docx,
+            export_profiles::list_export_profiles,
+            export_profiles::save_export_profile,
+            export_profiles::delete_export_profile,
+            export_profiles::run_export_profile,
+            batch_export::batch_export,
+            output_template::resolve_output_path,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,135 @@
+// 日本語原稿の統計（原稿用紙換算・字種構成）
+
+use serde::{Deserialize, Serialize};
+
+use crate::markdown;
+use crate::text::{is_hiragana, is_kanji, is_katakana, is_punctuation};
+
+/// 行頭に置けない文字（行頭禁則）。行末にぶら下げる
+const NO_LINE_START: &[char] = &[
+    '、', '。', '，', '．', '」', '』', '）', '〕', '】', '〉', '》', '］', '｝', '！', '？', 'ー',
+    '・', '：', '；', 'ぁ', 'ぃ', 'ぅ', 'ぇ', 'ぉ', 'っ', 'ゃ', 'ゅ', 'ょ', 'ゎ', 'ァ', 'ィ', 'ゥ',
+    'ェ', 'ォ', 'ッ', 'ャ', 'ュ', 'ョ', 'ヮ', 'ヵ', 'ヶ', '々', '…', '‥',
+];
+
+/// 段落冒頭の字下げを行わない開き括弧
+const OPENING_BRACKETS: &[char] = &['「', '『', '（', '〔', '【', '〈', '《', '［', '｛'];
+
+/// 原稿用紙の書式（既定は 20 字 × 20 行 = 400 字詰め）
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ManuscriptOptions {
+    pub chars_per_line: usize,
+    pub lines_per_page: usize,
+    /// 段落冒頭を 1 字下げる
+    pub indent_paragraphs: bool,
+}
+
+impl Default for ManuscriptOptions {
+    fn default() -> Self {
+        Self {
+            chars_per_line: 20,
+            lines_per_page: 20,
+            indent_paragraphs: true,
+        }
+    }
+}
+
+/// 日本語原稿の統計
+#[derive(Debug, Default, Serialize)]
+pub struct ManuscriptStats {
+    /// 空白を除く文字数
+    pub total_chars: usize,
+    /// 句読点・記号を除く文字数
+    pub chars_excluding_punctuation: usize,
+    pub hiragana: usize,
+    pub katakana: usize,
+    pub kanji: usize,
+    /// 英数字（全角・半角）
+    pub alphanumeric: usize,
+    pub punctuation: usize,
+    pub other: usize,
+    /// 句読点・記号を除く文字数に対するかな（ひらがな＋カタカナ）の割合
+    pub kana_ratio: f64,
+    /// 句読点・記号を除く文字数に対する漢字の割合
+    pub kanji_ratio: f64,
+    pub paragraphs: usize,
+    /// 禁則処理・字下げを考慮した原稿用紙の行数
+    pub manuscript_lines: usize,
+    /// 禁則処理・字下げを考慮した原稿用紙の枚数
+    pub manuscript_pages: usize,
+    /// 単純に総文字数を 1 枚の字数で割った枚数
+    pub simple_pages: usize,
+}
+
+/// 段落を原稿用紙に書いたときの行数。半角英数字は 2 字で 1 マス
+fn layout_lines(text: &str, options: &ManuscriptOptions) -> usize {
+    let width = options.chars_per_line.max(1) * 2;
+    let chars: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if chars.is_empty() {
+        return 0;
+    }
+
+    let mut lines = 1;
+    let mut column = 0;
+    if options.indent_paragraphs && !OPENING_BRACKETS.contains(&chars[0]) {
+        column = 2;
+    }
+    for c in chars {
+        let cells = if c.is_ascii() { 1 } else { 2 };
+        if column + cells > width {
+            // 行頭禁則の文字は前の行末にぶら下げる
+            if NO_LINE_START.contains(&c) && column <= width {
+                column = width + 1;
+                continue;
+            }
+            lines += 1;
+            column = 0;
+        }
+        column += cells;
+    }
+    lines
+}
+
+/// Markdown 文書の日本語原稿としての統計を取得（コードブロックは対象外）
+#[tauri::command]
+pub fn get_manuscript_stats(
+    content: String,
+    options: Option<ManuscriptOptions>,
+) -> ManuscriptStats {
+    let options = options.unwrap_or_default();
+    let mut stats = ManuscriptStats::default();
+
+    for block in markdown::prose_blocks(&content) {
+        for c in block.text.chars().filter(|c| !c.is_whitespace()) {
+            stats.total_chars += 1;
+            if is_punctuation(c) {
+                stats.punctuation += 1;
+            } else if is_hiragana(c) {
+                stats.hiragana += 1;
+            } else if is_katakana(c) {
+                stats.katakana += 1;
+            } else if is_kanji(c) {
+                stats.kanji += 1;
+            } else if c.is_alphanumeric() {
+                stats.alphanumeric += 1;
+            } else {
+                stats.other += 1;
+            }
+        }
+        stats.paragraphs += 1;
+        stats.manuscript_lines += layout_lines(&block.text, &options);
+    }
+
+    stats.chars_excluding_punctuation = stats.total_chars - stats.punctuation;
+    if stats.chars_excluding_punctuation > 0 {
+        let base = stats.chars_excluding_punctuation as f64;
+        stats.kana_ratio = (stats.hiragana + stats.katakana) as f64 / base;
+        stats.kanji_ratio = stats.kanji as f64 / base;
+    }
+    let lines_per_page = options.lines_per_page.max(1);
+    let chars_per_page = options.chars_per_line.max(1) * lines_per_page;
+    stats.manuscript_pages = stats.manuscript_lines.div_ceil(lines_per_page);
+    stats.simple_pages = stats.total_chars.div_ceil(chars_per_page);
+    stats
+}
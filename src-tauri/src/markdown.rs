@@ -0,0 +1,272 @@
+// Markdown 解析の共通処理
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use chrono::{Local, NaiveDate};
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+use crate::dates;
+use crate::text::LineIndex;
+
+/// エディタのプレビューに合わせた解析オプション
+pub fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_MATH
+        | Options::ENABLE_WIKILINKS
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+}
+
+/// 本文テキストのブロック（段落・見出し・リスト項目・表セル）
+#[derive(Debug)]
+pub struct ProseBlock {
+    /// ブロック先頭の行番号（1 始まり）
+    pub line: usize,
+    /// インライン記法を除いたテキスト（ソフト改行は `\n`）
+    pub text: String,
+}
+
+/// コードブロック・数式・HTML・フロントマターを除いた本文ブロックを抽出
+pub fn prose_blocks(content: &str) -> Vec<ProseBlock> {
+    let index = LineIndex::new(content);
+    let mut blocks = Vec::new();
+    let mut current: Option<ProseBlock> = None;
+    let mut skip_depth = 0usize;
+
+    let flush = |current: &mut Option<ProseBlock>, blocks: &mut Vec<ProseBlock>| {
+        if let Some(block) = current.take() {
+            if !block.text.trim().is_empty() {
+                blocks.push(block);
+            }
+        }
+    };
+
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) | Event::Start(Tag::MetadataBlock(_)) => {
+                flush(&mut current, &mut blocks);
+                skip_depth += 1;
+            }
+            Event::End(TagEnd::CodeBlock) | Event::End(TagEnd::MetadataBlock(_)) => {
+                skip_depth = skip_depth.saturating_sub(1);
+            }
+            Event::Start(
+                Tag::Paragraph
+                | Tag::Heading { .. }
+                | Tag::Item
+                | Tag::TableCell
+                | Tag::BlockQuote(_),
+            )
+            | Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::Item
+                | TagEnd::TableCell
+                | TagEnd::BlockQuote(_),
+            ) => flush(&mut current, &mut blocks),
+            Event::Text(text) if skip_depth == 0 => {
+                current
+                    .get_or_insert_with(|| ProseBlock {
+                        line: index.line_of(range.start),
+                        text: String::new(),
+                    })
+                    .text
+                    .push_str(&text);
+            }
+            Event::SoftBreak | Event::HardBreak if skip_depth == 0 => {
+                if let Some(block) = current.as_mut() {
+                    block.text.push('\n');
+                }
+            }
+            _ => {}
+        }
+    }
+    flush(&mut current, &mut blocks);
+    blocks
+}
+
+/// コードブロックとインラインコードのバイト範囲（リンク抽出などで除外する）
+pub fn code_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) | Event::Code(_) => ranges.push(range),
+            _ => {}
+        }
+    }
+    ranges
+}
+
+/// オフセットがいずれかの範囲に含まれるか（`ranges` は開始位置の昇順）
+pub fn in_ranges(ranges: &[Range<usize>], offset: usize) -> bool {
+    let i = ranges.partition_point(|r| r.start <= offset);
+    i > 0 && ranges[i - 1].contains(&offset)
+}
+
+/// 見出し
+#[derive(Debug, Clone)]
+pub struct Heading {
+    /// インライン記法を除いた見出しテキスト
+    pub text: String,
+    /// `#` や下線を除いた見出し本文のソース上のバイト範囲
+    pub text_range: Range<usize>,
+    /// 見出しのレベル（1〜6）
+    pub level: usize,
+    /// 見出し全体のソース上のバイト範囲
+    pub range: Range<usize>,
+}
+
+/// 見出しソースから本文部分の範囲を求める（ATX は `#` と閉じ `#`、Setext は下線を除く）
+fn heading_text_range(content: &str, range: Range<usize>) -> Range<usize> {
+    let source = &content[range.clone()];
+    let first_line = source.lines().next().unwrap_or("");
+    let indent = first_line.len() - first_line.trim_start().len();
+    let line = &first_line[indent..];
+    if !line.starts_with('#') {
+        let trimmed = line.trim_end();
+        return range.start + indent..range.start + indent + trimmed.len();
+    }
+    let after_hashes = line.trim_start_matches('#');
+    let body = after_hashes.trim_start();
+    let start = range.start + indent + (line.len() - body.len());
+    // 閉じ `#` は直前に空白がある場合のみ
+    let mut end_text = body.trim_end();
+    let without_closing = end_text.trim_end_matches('#');
+    if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        end_text = without_closing.trim_end();
+    }
+    start..start + end_text.len()
+}
+
+/// 文書中の見出しを抽出
+pub fn headings(content: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut current: Option<(Range<usize>, usize, String)> = None;
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                current = Some((range, level as usize, String::new()))
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, _, buf)) = current.as_mut() {
+                    buf.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((range, level, text)) = current.take() {
+                    headings.push(Heading {
+                        text: text.trim().to_string(),
+                        text_range: heading_text_range(content, range.clone()),
+                        level,
+                        range,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    headings
+}
+
+/// YAML フロントマターを除いた本文の開始位置
+pub fn body_start(content: &str) -> usize {
+    match Parser::new_ext(content, parser_options())
+        .into_offset_iter()
+        .next()
+    {
+        Some((Event::Start(Tag::MetadataBlock(_)), range)) => range.end,
+        _ => 0,
+    }
+}
+
+/// 見出し `heading`（テキストまたはアンカー）から、同じか上のレベルの次の見出しまでの範囲
+pub fn heading_section(content: &str, heading: &str) -> Option<Range<usize>> {
+    let headings = headings(content);
+    let slug = slugify(heading);
+    let index = headings
+        .iter()
+        .position(|h| h.text.eq_ignore_ascii_case(heading.trim()) || slugify(&h.text) == slug)?;
+    let start = headings[index].range.start;
+    let end = headings[index + 1..]
+        .iter()
+        .find(|h| h.level <= headings[index].level)
+        .map_or(content.len(), |h| h.range.start);
+    Some(start..end)
+}
+
+/// GitHub 互換の見出しアンカー（小文字化し、英数字・`-`・`_`・空白以外を除去）
+pub fn slugify(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 文書内で一意なアンカー（重複には `-1`, `-2` … を付ける）
+pub fn unique_slug(seen: &mut HashMap<String, usize>, base: String) -> String {
+    let count = seen.entry(base.clone()).or_insert(0);
+    let slug = if *count == 0 {
+        base
+    } else {
+        format!("{base}-{count}")
+    };
+    *count += 1;
+    slug
+}
+
+/// 見出しごとのアンカー
+pub fn heading_slugs(headings: &[Heading]) -> Vec<String> {
+    let mut seen = HashMap::new();
+    headings
+        .iter()
+        .map(|h| unique_slug(&mut seen, slugify(&h.text)))
+        .collect()
+}
+
+/// タスクリストの項目
+#[derive(Debug, Clone)]
+pub struct Task {
+    /// 行番号（1 始まり）
+    pub line: usize,
+    pub checked: bool,
+    /// チェックボックスの後のテキスト（同じ行のみ）
+    pub text: String,
+    /// `[ ]` のバイト範囲
+    pub marker: Range<usize>,
+    /// テキストの期日（`📅 2025-05-03` `due: next friday` など。`dates::due_date`）
+    pub due: Option<NaiveDate>,
+}
+
+/// 文書中のタスクリストの項目（出現順）
+pub fn tasks(content: &str) -> Vec<Task> {
+    let index = LineIndex::new(content);
+    let today = Local::now().date_naive();
+    Parser::new_ext(content, parser_options())
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::TaskListMarker(checked) => {
+                let line_end = content[range.end..]
+                    .find('\n')
+                    .map_or(content.len(), |i| range.end + i);
+                let text = content[range.end..line_end].trim().to_string();
+                Some(Task {
+                    line: index.line_of(range.start),
+                    checked,
+                    due: dates::due_date(&text, today),
+                    text,
+                    marker: range,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
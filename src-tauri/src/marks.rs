@@ -0,0 +1,154 @@
+// Vim のマークとジャンプリストの保存（アプリのデータフォルダの `marks.json`）
+//
+// `a`〜`z` はファイルごと、`A`〜`Z` はファイルをまたぐマーク。再起動後も `'a` で戻れる。
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::app_data;
+use crate::tr;
+use crate::vault::{self, path_string};
+
+/// データフォルダ内のファイル
+const MARKS_FILE: &str = "marks.json";
+/// ジャンプリストに残す数（Vim と同じ）
+const JUMPLIST_SIZE: usize = 100;
+
+/// 位置（行・列とも 1 始まり）
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Position {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// マーク
+#[derive(Debug, Clone, Serialize)]
+pub struct Mark {
+    /// マークの文字（`a`〜`z` / `A`〜`Z`）
+    pub name: char,
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct StoredMarks {
+    /// ファイルごとの `a`〜`z`
+    files: BTreeMap<String, BTreeMap<char, Position>>,
+    /// `A`〜`Z`
+    global: BTreeMap<char, Position>,
+    /// 古いものから順に
+    jumplist: Vec<Position>,
+}
+
+fn load(app: &AppHandle) -> Result<StoredMarks, String> {
+    app_data::read_json(&app_data::data_file(app, MARKS_FILE)?)
+}
+
+fn store(app: &AppHandle, marks: &StoredMarks) -> Result<(), String> {
+    app_data::write_json(&app_data::data_file(app, MARKS_FILE)?, marks)
+}
+
+fn normalized(path: &str) -> String {
+    path_string(&vault::normalize(Path::new(path)))
+}
+
+fn mark_name(mark: &str) -> Result<char, String> {
+    let mut chars = mark.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => Ok(c),
+        _ => Err(tr!("Invalid mark: {mark}")),
+    }
+}
+
+fn marks_of(marks: BTreeMap<char, Position>) -> impl Iterator<Item = Mark> {
+    marks.into_iter().map(|(name, p)| Mark {
+        name,
+        path: p.path,
+        line: p.line,
+        column: p.column,
+    })
+}
+
+/// マークを設定する（小文字はファイルごと、大文字はファイルをまたぐ）
+#[tauri::command]
+pub fn set_mark(
+    app: AppHandle,
+    path: String,
+    mark: String,
+    line: usize,
+    column: usize,
+) -> Result<(), String> {
+    let name = mark_name(&mark)?;
+    let position = Position {
+        path: normalized(&path),
+        line,
+        column,
+    };
+    let mut marks = load(&app)?;
+    if name.is_ascii_uppercase() {
+        marks.global.insert(name, position);
+    } else {
+        marks
+            .files
+            .entry(position.path.clone())
+            .or_default()
+            .insert(name, position);
+    }
+    store(&app, &marks)
+}
+
+/// マークを削除する（`:delmarks`）
+#[tauri::command]
+pub fn delete_mark(app: AppHandle, path: String, mark: String) -> Result<(), String> {
+    let name = mark_name(&mark)?;
+    let mut marks = load(&app)?;
+    if name.is_ascii_uppercase() {
+        marks.global.remove(&name);
+    } else {
+        let path = normalized(&path);
+        if let Some(file) = marks.files.get_mut(&path) {
+            file.remove(&name);
+            if file.is_empty() {
+                marks.files.remove(&path);
+            }
+        }
+    }
+    store(&app, &marks)
+}
+
+/// ファイルのマークとファイルをまたぐマーク（`:marks` の一覧）
+#[tauri::command]
+pub fn get_marks(app: AppHandle, path: String) -> Result<Vec<Mark>, String> {
+    let mut marks = load(&app)?;
+    let local = marks.files.remove(&normalized(&path)).unwrap_or_default();
+    Ok(marks_of(local).chain(marks_of(marks.global)).collect())
+}
+
+/// ジャンプリストに位置を加える（同じ行の古い項目は除く）
+#[tauri::command]
+pub fn push_jump(app: AppHandle, path: String, line: usize, column: usize) -> Result<(), String> {
+    let position = Position {
+        path: normalized(&path),
+        line,
+        column,
+    };
+    let mut marks = load(&app)?;
+    marks
+        .jumplist
+        .retain(|p| !(p.path == position.path && p.line == position.line));
+    marks.jumplist.push(position);
+    let excess = marks.jumplist.len().saturating_sub(JUMPLIST_SIZE);
+    marks.jumplist.drain(..excess);
+    store(&app, &marks)
+}
+
+/// ジャンプリスト（古いものから順に。`Ctrl-O` は末尾から戻る）
+#[tauri::command]
+pub fn get_jumplist(app: AppHandle) -> Result<Vec<Position>, String> {
+    Ok(load(&app)?.jumplist)
+}
@@ -0,0 +1,455 @@
+// 他のノートアプリからの取り込み（TextBundle / Bear のバックアップ / Evernote の ENEX）
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::NaiveDateTime;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::bundle::ASSETS_DIR;
+use crate::html_markdown;
+use crate::links;
+use crate::md5;
+use crate::state::AppState;
+use crate::tasks::Task;
+use crate::tr;
+use crate::vault;
+use crate::zip;
+
+/// TextBundle 内の本文のファイル名（優先順）
+const BUNDLE_TEXTS: &[&str] = &["text.md", "text.markdown", "text.txt"];
+
+/// 取り込み元の形式
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteSource {
+    /// `.textbundle` フォルダまたは `.textpack`（ZIP）
+    Textbundle,
+    /// Bear のバックアップ（`.bear2bk`）またはTextBundle を書き出したフォルダ
+    Bear,
+    /// Evernote の書き出し（`.enex`）
+    Enex,
+}
+
+/// `import-progress` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteImportProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+/// 取り込みの結果
+#[derive(Debug, Serialize)]
+pub struct NoteImportSummary {
+    /// 作成した Markdown 文書
+    pub notes: Vec<String>,
+    /// 書き出した添付ファイル
+    pub assets: Vec<String>,
+}
+
+/// 添付ファイル
+struct Attachment {
+    /// 元のファイル名
+    name: String,
+    data: Vec<u8>,
+    /// ENEX の `<en-media hash>` で参照される MD5
+    hash: String,
+    mime: String,
+}
+
+/// 取り込む 1 件のノート
+#[derive(Default)]
+struct Note {
+    title: String,
+    /// Markdown（ENEX では変換前の ENML）
+    body: String,
+    created: Option<String>,
+    tags: Vec<String>,
+    source_url: Option<String>,
+    attachments: Vec<Attachment>,
+}
+
+/// フォルダまたは ZIP の中のファイル（`/` 区切りの相対パスと内容）
+fn read_entries(path: &Path) -> Result<Vec<zip::Entry>, String> {
+    if path.is_dir() {
+        return vault::walk_files(path)
+            .into_iter()
+            .map(|file| {
+                let data =
+                    fs::read(&file).map_err(|e| tr!("Failed to read {}: {e}", file.display()))?;
+                let name = vault::to_slash(file.strip_prefix(path).unwrap_or(&file));
+                Ok(zip::Entry { name, data })
+            })
+            .collect();
+    }
+    let data = fs::read(path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+    zip::read_zip(&data)
+}
+
+/// 本文の最初の見出し（なければフォルダ名）をタイトルにする
+fn bundle_title(text: &str, prefix: &str) -> String {
+    let heading = text
+        .lines()
+        .find(|l| !l.trim().is_empty())
+        .and_then(|l| l.trim_start().strip_prefix("# "))
+        .map(|h| h.trim().to_string());
+    heading.unwrap_or_else(|| {
+        let dir = prefix.trim_end_matches('/');
+        let name = dir.rsplit('/').next().unwrap_or(dir);
+        name.strip_suffix(".textbundle").unwrap_or(name).to_string()
+    })
+}
+
+/// TextBundle（フォルダ・ZIP 内に複数あってもよい）のノート
+fn bundle_notes(path: &Path) -> Result<Vec<Note>, String> {
+    let entries = read_entries(path)?;
+    let mut prefixes: Vec<&str> = Vec::new();
+    for entry in &entries {
+        let (prefix, file) = match entry.name.rsplit_once('/') {
+            Some((dir, file)) => (&entry.name[..dir.len() + 1], file),
+            None => ("", entry.name.as_str()),
+        };
+        // アセットのフォルダ内にある同名のファイルは本文ではない
+        if BUNDLE_TEXTS.contains(&file)
+            && !prefix.ends_with(&format!("{ASSETS_DIR}/"))
+            && !prefixes.contains(&prefix)
+        {
+            prefixes.push(prefix);
+        }
+    }
+    if prefixes.is_empty() {
+        return Err(tr!("No TextBundle found in {}", path.display()));
+    }
+    prefixes.sort();
+
+    let mut notes = Vec::new();
+    for prefix in prefixes {
+        let text = BUNDLE_TEXTS.iter().find_map(|name| {
+            let name = format!("{prefix}{name}");
+            entries.iter().find(|e| e.name == name)
+        });
+        let Some(text) = text else {
+            continue;
+        };
+        let body = String::from_utf8_lossy(&text.data).into_owned();
+        let assets = format!("{prefix}{ASSETS_DIR}/");
+        let attachments = entries
+            .iter()
+            .filter_map(|e| {
+                let name = e.name.strip_prefix(&assets)?;
+                Some(Attachment {
+                    name: name.to_string(),
+                    data: e.data.clone(),
+                    hash: String::new(),
+                    mime: String::new(),
+                })
+            })
+            .collect();
+        notes.push(Note {
+            title: bundle_title(&body, prefix),
+            body,
+            attachments,
+            ..Note::default()
+        });
+    }
+    Ok(notes)
+}
+
+/// ENEX の日時（`20230315T101500Z`）を ISO 8601 にする
+fn enex_date(value: &str) -> Option<String> {
+    NaiveDateTime::parse_from_str(value.trim(), "%Y%m%dT%H%M%SZ")
+        .ok()
+        .map(|t| t.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+}
+
+/// ENEX のノート（本文は ENML のまま）
+fn enex_notes(path: &Path) -> Result<Vec<Note>, String> {
+    let xml =
+        fs::read_to_string(path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+    let mut reader = Reader::from_str(&xml);
+    let mut notes = Vec::new();
+    let mut note: Option<Note> = None;
+    let mut resource: Option<(Vec<u8>, String, String)> = None;
+    // 開いている要素の名前
+    let mut stack: Vec<String> = Vec::new();
+    let mut text = String::new();
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| tr!("Invalid ENEX file: {e}"))?;
+        match event {
+            Event::Start(e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                match name.as_str() {
+                    "note" => note = Some(Note::default()),
+                    "resource" => resource = Some((Vec::new(), String::new(), String::new())),
+                    _ => {}
+                }
+                stack.push(name);
+                text.clear();
+            }
+            Event::Text(e) => {
+                if let Ok(t) = e.xml_content() {
+                    text.push_str(&t);
+                }
+            }
+            Event::CData(e) => {
+                if let Ok(t) = e.decode() {
+                    text.push_str(&t);
+                }
+            }
+            Event::GeneralRef(e) => {
+                let resolved = match e.resolve_char_ref() {
+                    Ok(Some(c)) => c.to_string(),
+                    _ => {
+                        let name = String::from_utf8_lossy(&e);
+                        quick_xml::escape::unescape(&format!("&{name};"))
+                            .map(|s| s.into_owned())
+                            .unwrap_or_default()
+                    }
+                };
+                text.push_str(&resolved);
+            }
+            Event::End(_) => {
+                let name = stack.pop().unwrap_or_default();
+                let value = std::mem::take(&mut text);
+                let Some(current) = note.as_mut() else {
+                    continue;
+                };
+                match (name.as_str(), resource.as_mut()) {
+                    ("data", Some(res)) => {
+                        let encoded: String =
+                            value.chars().filter(|c| !c.is_whitespace()).collect();
+                        res.0 = STANDARD
+                            .decode(encoded)
+                            .map_err(|e| tr!("Invalid attachment data in ENEX file: {e}"))?;
+                    }
+                    ("mime", Some(res)) => res.1 = value.trim().to_string(),
+                    ("file-name", Some(res)) => res.2 = value.trim().to_string(),
+                    ("resource", Some(_)) => {
+                        let (data, mime, name) = resource.take().unwrap();
+                        current.attachments.push(Attachment {
+                            hash: md5::hex_digest(&data),
+                            name,
+                            data,
+                            mime,
+                        });
+                    }
+                    ("title", None) => current.title = value.trim().to_string(),
+                    ("content", None) => current.body = value,
+                    ("created", None) => current.created = enex_date(&value),
+                    ("tag", None) => current.tags.push(value.trim().to_string()),
+                    ("source-url", None) if !value.trim().is_empty() => {
+                        current.source_url = Some(value.trim().to_string())
+                    }
+                    ("note", _) => notes.extend(note.take()),
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(notes)
+}
+
+/// ファイル名に使えない文字を置き換える
+fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .take(100)
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('.').trim();
+    if cleaned.is_empty() {
+        tr!("Untitled")
+    } else {
+        cleaned.to_string()
+    }
+}
+
+/// 既存のファイルや今回作成したものと重ならないパス（`名前-2.拡張子` …）
+fn unique_path(dir: &Path, name: &str, used: &mut HashSet<PathBuf>) -> PathBuf {
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{ext}")),
+        _ => (name, String::new()),
+    };
+    let path = (1..)
+        .map(|n| match n {
+            1 => dir.join(format!("{stem}{ext}")),
+            n => dir.join(format!("{stem}-{n}{ext}")),
+        })
+        .find(|p| !p.exists() && !used.contains(p))
+        .unwrap_or_default();
+    used.insert(path.clone());
+    path
+}
+
+/// 拡張子がない添付ファイルの拡張子（MIME タイプから）
+fn extension_for(mime: &str) -> &str {
+    match mime {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/svg+xml" => "svg",
+        "image/webp" => "webp",
+        "application/pdf" => "pdf",
+        "audio/mpeg" => "mp3",
+        "audio/wav" => "wav",
+        _ => "bin",
+    }
+}
+
+fn yaml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+struct Importer {
+    dest_dir: PathBuf,
+    used: HashSet<PathBuf>,
+    notes: Vec<String>,
+    assets: Vec<String>,
+}
+
+impl Importer {
+    /// 添付ファイルを `assets/` に書き出し、元の名前（と MD5）から文書内のリンク先を引けるようにする
+    fn write_attachments(&mut self, note: &Note) -> Result<HashMap<String, String>, String> {
+        let dir = self.dest_dir.join(ASSETS_DIR);
+        let mut targets = HashMap::new();
+        for attachment in &note.attachments {
+            let name = if attachment.name.is_empty() {
+                let kind = attachment.mime.split('/').next().unwrap_or("file");
+                format!("{kind}.{}", extension_for(&attachment.mime))
+            } else if !attachment.name.contains('.') && !attachment.mime.is_empty() {
+                format!("{}.{}", attachment.name, extension_for(&attachment.mime))
+            } else {
+                attachment.name.clone()
+            };
+            // TextBundle のアセットはサブフォルダを持つことがある
+            let name = sanitize_file_name(&name.replace('/', "-"));
+            fs::create_dir_all(&dir).map_err(|e| tr!("Failed to create {}: {e}", dir.display()))?;
+            let path = unique_path(&dir, &name, &mut self.used);
+            fs::write(&path, &attachment.data)
+                .map_err(|e| tr!("Failed to write {}: {e}", path.display()))?;
+            let target = links::percent_encode(&vault::relative_path(&self.dest_dir, &path));
+            if !attachment.hash.is_empty() {
+                targets.insert(attachment.hash.clone(), target.clone());
+            }
+            targets.insert(attachment.name.clone(), target);
+            self.assets.push(vault::path_string(&path));
+        }
+        Ok(targets)
+    }
+
+    /// TextBundle の本文のアセットへのリンクを書き出し先に合わせる
+    fn rebase_bundle_links(body: &str, targets: &HashMap<String, String>) -> String {
+        let mut body = body.to_string();
+        for (name, target) in targets {
+            for original in [name.clone(), links::percent_encode(name)] {
+                let original = format!("{ASSETS_DIR}/{original}");
+                if original != *target {
+                    body = body.replace(&format!("({original})"), &format!("({target})"));
+                    body = body.replace(&format!("({original} "), &format!("({target} "));
+                }
+            }
+        }
+        body
+    }
+
+    /// ENEX のノートを Markdown にする（タイトル・作成日時・タグはフロントマターに）
+    fn enex_markdown(note: &Note, targets: &HashMap<String, String>) -> String {
+        let names: HashMap<&str, &str> = note
+            .attachments
+            .iter()
+            .map(|a| (a.hash.as_str(), a.name.as_str()))
+            .collect();
+        let resolve = |hash: &str, _mime: &str| {
+            let target = targets.get(hash)?.clone();
+            let name = names.get(hash).copied().unwrap_or_default();
+            Some((target, name.to_string()))
+        };
+        let body = html_markdown::html_to_markdown(&note.body, Some(&resolve));
+
+        let mut front_matter = vec![format!("title: {}", yaml_string(&note.title))];
+        if let Some(created) = &note.created {
+            front_matter.push(format!("created: {created}"));
+        }
+        if !note.tags.is_empty() {
+            let tags: Vec<String> = note.tags.iter().map(|t| yaml_string(t)).collect();
+            front_matter.push(format!("tags: [{}]", tags.join(", ")));
+        }
+        if let Some(url) = &note.source_url {
+            front_matter.push(format!("source: {}", yaml_string(url)));
+        }
+        format!(
+            "---\n{}\n---\n\n# {}\n\n{}",
+            front_matter.join("\n"),
+            note.title,
+            body.trim_start()
+        )
+    }
+
+    fn import(&mut self, source: NoteSource, note: &Note) -> Result<(), String> {
+        let targets = self.write_attachments(note)?;
+        let markdown = match source {
+            NoteSource::Textbundle | NoteSource::Bear => {
+                Self::rebase_bundle_links(&note.body, &targets)
+            }
+            NoteSource::Enex => Self::enex_markdown(note, &targets),
+        };
+        let name = format!("{}.md", sanitize_file_name(&note.title));
+        let path = unique_path(&self.dest_dir, &name, &mut self.used);
+        fs::write(&path, markdown).map_err(|e| tr!("Failed to write {}: {e}", path.display()))?;
+        self.notes.push(vault::path_string(&path));
+        Ok(())
+    }
+}
+
+/// 他のアプリのノートを Markdown 文書として `dest_dir` に取り込む（添付ファイルは `assets/` に置く）
+#[tauri::command(async)]
+pub fn import_notes(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    source_type: NoteSource,
+    path: String,
+    dest_dir: String,
+    task_id: Option<String>,
+) -> Result<NoteImportSummary, String> {
+    let task = Task::start(&app, &state, task_id)?;
+    let path = Path::new(&path);
+    let notes = match source_type {
+        NoteSource::Textbundle | NoteSource::Bear => bundle_notes(path)?,
+        NoteSource::Enex => enex_notes(path)?,
+    };
+    let dest_dir = PathBuf::from(dest_dir);
+    fs::create_dir_all(&dest_dir)
+        .map_err(|e| tr!("Failed to create {}: {e}", dest_dir.display()))?;
+
+    let mut importer = Importer {
+        dest_dir,
+        used: HashSet::new(),
+        notes: Vec::new(),
+        assets: Vec::new(),
+    };
+    let total = notes.len();
+    for (i, note) in notes.iter().enumerate() {
+        task.check()?;
+        importer.import(source_type, note)?;
+        task.progress(i + 1, total, || note.title.clone());
+        let _ = app.emit("import-progress", NoteImportProgress { done: i + 1, total });
+    }
+    Ok(NoteImportSummary {
+        notes: importer.notes,
+        assets: importer.assets,
+    })
+}
@@ -0,0 +1,272 @@
+// コードブロックの実行（ノートブックのように、ブロックの下に結果のフェンスを置く）
+//
+// 実行する言語は設定（`notebook.json`）で個別に有効にする（既定ではどれも無効）。
+// コードはインタープリタの標準入力に渡し、信頼したワークスペースでだけ実行する（`trust`）。
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::app_data;
+use crate::formatter::TextEdit;
+use crate::lsp::LspRange;
+use crate::markdown;
+use crate::shell::{self, ShellCapture};
+use crate::state::AppState;
+use crate::tasks::Task;
+use crate::text::LineIndex;
+use crate::tr;
+
+/// 設定フォルダ内のファイル
+const NOTEBOOK_FILE: &str = "notebook.json";
+
+/// インタープリタ 1 つの設定
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Interpreter {
+    #[serde(default)]
+    pub enabled: bool,
+    pub program: String,
+    /// コードを標準入力から読ませる引数
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// 同じインタープリタで実行する言語名（`bash` `py` など）
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// コードブロックの実行の設定
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NotebookConfig {
+    /// 言語名ごとのインタープリタ
+    pub languages: BTreeMap<String, Interpreter>,
+    /// 既定のタイムアウト
+    pub timeout_secs: u64,
+    /// 結果のフェンスの情報文字列
+    pub result_info: String,
+}
+
+impl Default for NotebookConfig {
+    fn default() -> Self {
+        let interpreter = |program: &str, args: &[&str], aliases: &[&str]| Interpreter {
+            enabled: false,
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            aliases: aliases.iter().map(|a| a.to_string()).collect(),
+            timeout_secs: None,
+        };
+        let python = if cfg!(target_os = "windows") {
+            "python"
+        } else {
+            "python3"
+        };
+        Self {
+            languages: BTreeMap::from([
+                (
+                    "sh".to_string(),
+                    interpreter("sh", &["-s"], &["bash", "shell"]),
+                ),
+                (
+                    "python".to_string(),
+                    interpreter(python, &["-"], &["py", "python3"]),
+                ),
+                (
+                    "node".to_string(),
+                    interpreter("node", &["-"], &["js", "javascript"]),
+                ),
+            ]),
+            timeout_secs: 30,
+            result_info: "output".to_string(),
+        }
+    }
+}
+
+impl NotebookConfig {
+    /// 言語名のインタープリタ（名前か別名が一致するもの）
+    fn interpreter(&self, language: &str) -> Option<&Interpreter> {
+        let language = language.trim().to_lowercase();
+        self.languages.get(&language).or_else(|| {
+            self.languages
+                .values()
+                .find(|i| i.aliases.iter().any(|a| a.eq_ignore_ascii_case(&language)))
+        })
+    }
+}
+
+/// `execute_code_block` の結果
+#[derive(Debug, Serialize)]
+pub struct CodeBlockResult {
+    #[serde(flatten)]
+    pub capture: ShellCapture,
+    /// ブロックの下に置く結果のフェンス
+    pub result: String,
+}
+
+fn load(app: &AppHandle) -> Result<NotebookConfig, String> {
+    app_data::read_json(&app_data::config_file(app, NOTEBOOK_FILE)?)
+}
+
+/// コードブロックの実行の設定
+#[tauri::command]
+pub fn get_notebook_config(app: AppHandle) -> Result<NotebookConfig, String> {
+    load(&app)
+}
+
+/// コードブロックの実行の設定を保存する
+#[tauri::command]
+pub fn set_notebook_config(app: AppHandle, config: NotebookConfig) -> Result<(), String> {
+    app_data::write_json(&app_data::config_file(&app, NOTEBOOK_FILE)?, &config)
+}
+
+/// 中身に含まれない長さのバッククォートで囲む
+fn fenced(info: &str, body: &str) -> String {
+    let longest = body.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    match body.trim_end_matches('\n') {
+        "" => format!("{fence}{info}\n{fence}\n"),
+        body => format!("{fence}{info}\n{body}\n{fence}\n"),
+    }
+}
+
+/// 結果のフェンスの本文（標準出力の後に標準エラー出力と終了の状態を続ける）
+fn result_body(capture: &ShellCapture) -> String {
+    let mut body = capture.stdout.trim_end_matches('\n').to_string();
+    let mut push = |text: &str| {
+        if !body.is_empty() {
+            body.push('\n');
+        }
+        body.push_str(text);
+    };
+    let stderr = capture.stderr.trim_end_matches('\n');
+    if !stderr.is_empty() {
+        push(stderr);
+    }
+    if capture.timed_out {
+        push(&tr!("[timed out]"));
+    } else if let Some(code) = capture.exit_code.filter(|&code| code != 0) {
+        push(&tr!("[exit code {}]", code));
+    }
+    body
+}
+
+/// コードブロックを設定のインタープリタで実行し、出力と結果のフェンスを返す
+///
+/// `cwd`（開いているファイルかフォルダ）のワークスペースを信頼していないか、言語が有効でなければエラーにする。
+/// 出力は `shell-output` で逐次送る（`task_id` を付ける）。`cancel_task` で中断できる。
+#[tauri::command(async)]
+pub fn execute_code_block(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    language: String,
+    code: String,
+    cwd: String,
+    task_id: Option<String>,
+) -> Result<CodeBlockResult, String> {
+    let config = load(&app)?;
+    let interpreter = config
+        .interpreter(&language)
+        .filter(|i| i.enabled)
+        .ok_or_else(|| tr!("Code execution is not enabled for {}", language))?;
+    let cwd = Path::new(&cwd);
+    state.trust.require(&app, cwd, "code execution")?;
+    let task = Task::start(&app, &state, task_id.clone())?;
+
+    let mut command = Command::new(&interpreter.program);
+    command.args(&interpreter.args);
+    shell::set_cwd(&mut command, cwd);
+    let timeout = Duration::from_secs(interpreter.timeout_secs.unwrap_or(config.timeout_secs));
+    let capture = shell::capture(
+        &app,
+        &task,
+        task_id,
+        command,
+        &interpreter.program,
+        Some(code),
+        timeout,
+    )?;
+    Ok(CodeBlockResult {
+        result: fenced(&config.result_info, &result_body(&capture)),
+        capture,
+    })
+}
+
+/// 内容のフェンスのコードブロック（範囲と情報文字列の最初の語）
+fn fenced_blocks(content: &str) -> Vec<(Range<usize>, String)> {
+    Parser::new_ext(content, markdown::parser_options())
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => Some((
+                range,
+                info.split_whitespace()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 結果のフェンスを `line` 行を含むコードブロックの下に置く置き換え
+///
+/// ブロックのすぐ下（空行を挟んでもよい）に前の結果（情報文字列が `result_info`）があれば置き換える。
+#[tauri::command]
+pub fn place_code_result(
+    app: AppHandle,
+    content: String,
+    line: usize,
+    result: String,
+) -> Result<TextEdit, String> {
+    let result_info = load(&app)?.result_info;
+    let index = LineIndex::new(&content);
+    let blocks = fenced_blocks(&content);
+    let (block, _) = blocks
+        .iter()
+        .find(|(range, _)| {
+            index.line_of(range.start) <= line && line <= index.line_of(range.end.max(1) - 1)
+        })
+        .ok_or_else(|| tr!("No code block at line {}", line))?;
+
+    let after = block.end;
+    let gap = content[after..].len()
+        - content[after..]
+            .trim_start_matches(['\n', '\r', ' ', '\t'])
+            .len();
+    let previous = blocks
+        .iter()
+        .find(|(range, info)| range.start == after + gap && *info == result_info);
+    let result = result.trim_end_matches('\n').to_string() + "\n";
+    let (range, text) = match previous {
+        Some((range, _)) => {
+            // 閉じるフェンスの行の改行まで置き換える
+            let end = content[range.end..]
+                .find('\n')
+                .map_or(content.len(), |i| range.end + i + 1);
+            (range.start..end, result)
+        }
+        None => {
+            // ブロックの閉じるフェンスの行の次に、空行を挟んで入れる
+            let at = content[after..]
+                .find('\n')
+                .map_or(content.len(), |i| after + i + 1);
+            let prefix = if content[..at].ends_with('\n') {
+                "\n"
+            } else {
+                "\n\n"
+            };
+            (at..at, format!("{prefix}{result}"))
+        }
+    };
+    Ok(TextEdit {
+        range: LspRange::from_offsets(&index, &content, range),
+        text,
+    })
+}
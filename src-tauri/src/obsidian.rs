@@ -0,0 +1,402 @@
+// Obsidian の保管庫との互換（`.obsidian` の設定・コールアウト・タグ・別名・デイリーノート）
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use chrono::{Local, NaiveDate, NaiveTime};
+use regex::{Captures, Regex};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::dates;
+use crate::i18n;
+use crate::markdown;
+use crate::text;
+use crate::tr;
+use crate::vault::{self, path_string, Vault};
+
+/// Obsidian の設定フォルダ
+const CONFIG_DIR: &str = ".obsidian";
+
+static CALLOUT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}>[ \t]?\[!([\w-]+)\]([+-]?)[ \t]*(.*)$").unwrap());
+/// `#tag` `#親/子`（数字だけのものはタグではない）
+static TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[\s、。])#([\p{L}\p{N}_/-]+)").unwrap());
+static TEMPLATE_VARIABLE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*(date|time|title)(?::([^}]*))?\s*\}\}").unwrap());
+
+/// デイリーノートの設定（`.obsidian/daily-notes.json`）
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyNotesSettings {
+    /// 保管庫のルートからのフォルダ
+    pub folder: String,
+    /// ファイル名の書式（Moment.js 形式）
+    pub format: String,
+    /// テンプレートのノート
+    pub template: Option<String>,
+}
+
+/// 保管庫の Obsidian の設定
+#[derive(Debug, Clone, Serialize)]
+pub struct ObsidianSettings {
+    /// 新しい添付ファイルの置き場所（`/` はルート、`./` で始まるものはノートのフォルダから）
+    pub attachment_folder: String,
+    /// ウィキリンクでなく Markdown のリンクを使う
+    pub use_markdown_links: bool,
+    /// 新しいリンクのパスの書き方（`shortest` `relative` `absolute`）
+    pub new_link_format: String,
+    pub daily_notes: DailyNotesSettings,
+    /// テンプレートのフォルダ
+    pub templates_folder: Option<String>,
+}
+
+/// タグと使われている回数
+#[derive(Debug, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// 開いた（または作成した）デイリーノート
+#[derive(Debug, Serialize)]
+pub struct DailyNote {
+    pub path: String,
+    /// 今回作成した
+    pub created: bool,
+}
+
+fn read_json(path: &Path) -> Value {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or(Value::Null)
+}
+
+fn string_setting(value: &Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Obsidian の保管庫か（`.obsidian` フォルダがあるか）
+pub fn is_vault(root: &Path) -> bool {
+    root.join(CONFIG_DIR).is_dir()
+}
+
+/// 保管庫の設定を読む（Obsidian の保管庫でなければ `None`）
+pub fn detect(root: &Path) -> Option<ObsidianSettings> {
+    if !is_vault(root) {
+        return None;
+    }
+    let config = root.join(CONFIG_DIR);
+    let app = read_json(&config.join("app.json"));
+    let daily = read_json(&config.join("daily-notes.json"));
+    let templates = read_json(&config.join("templates.json"));
+    Some(ObsidianSettings {
+        attachment_folder: string_setting(&app, "attachmentFolderPath")
+            .unwrap_or_else(|| "/".to_string()),
+        use_markdown_links: app
+            .get("useMarkdownLinks")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        new_link_format: string_setting(&app, "newLinkFormat")
+            .unwrap_or_else(|| "shortest".to_string()),
+        daily_notes: DailyNotesSettings {
+            folder: string_setting(&daily, "folder").unwrap_or_default(),
+            format: string_setting(&daily, "format").unwrap_or_else(|| "YYYY-MM-DD".to_string()),
+            template: string_setting(&daily, "template"),
+        },
+        templates_folder: string_setting(&templates, "folder"),
+    })
+}
+
+/// フロントマターの文字列またはリスト（`key: a` `key: [a, b]` `key:\n  - a`）
+pub fn front_matter_list(content: &str, key: &str) -> Vec<String> {
+    let end = markdown::body_start(content);
+    if end == 0 {
+        return Vec::new();
+    }
+    let clean = |value: &str| {
+        value
+            .trim()
+            .trim_matches(|c| c == '"' || c == '\'')
+            .trim()
+            .to_string()
+    };
+    let mut values = Vec::new();
+    let mut lines = content[..end].lines().skip(1).peekable();
+    while let Some(line) = lines.next() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        if name.trim() != key || name.starts_with([' ', '\t']) {
+            continue;
+        }
+        let value = value.trim();
+        if let Some(inner) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            values.extend(inner.split(',').map(clean));
+        } else if !value.is_empty() {
+            // `tags: a b` や `tags: a, b` も受け付ける
+            values.extend(value.split([',', ' ']).map(clean));
+        } else {
+            while let Some(item) = lines.peek().and_then(|l| l.trim_start().strip_prefix("- ")) {
+                values.push(clean(item));
+                lines.next();
+            }
+        }
+    }
+    values.retain(|v| !v.is_empty());
+    values
+}
+
+/// フロントマターの最上位のキーと値（リストは `, ` でつなぐ）
+pub fn front_matter_fields(content: &str) -> Vec<(String, String)> {
+    let end = markdown::body_start(content);
+    if end == 0 {
+        return Vec::new();
+    }
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in content[..end].lines().skip(1) {
+        if line.starts_with([' ', '\t', '-', '#']) {
+            continue;
+        }
+        let Some((name, raw)) = line.split_once(':') else {
+            continue;
+        };
+        let name = name.trim();
+        if name.is_empty() || fields.iter().any(|(n, _)| n == name) {
+            continue;
+        }
+        // リストでなければ区切らずにそのまま使う
+        let raw = raw.trim().trim_matches(|c| c == '"' || c == '\'').trim();
+        let value = if raw.is_empty() || raw.starts_with('[') {
+            front_matter_list(content, name).join(", ")
+        } else {
+            raw.to_string()
+        };
+        fields.push((name.to_string(), value));
+    }
+    fields
+}
+
+/// テキスト中のタグ（`#` を含む範囲）
+pub fn inline_tags(text: &str) -> Vec<Range<usize>> {
+    TAG.captures_iter(text)
+        .filter_map(|caps| {
+            let name = caps.get(1).unwrap();
+            let name_str = name.as_str().trim_end_matches(['/', '-']);
+            let is_tag = !name_str.is_empty() && !name_str.chars().all(|c| c.is_ascii_digit());
+            is_tag.then(|| name.start() - 1..name.start() + name_str.len())
+        })
+        .collect()
+}
+
+/// ノートのタグ（本文の `#tag` とフロントマターの `tags`、コード内は除く）
+pub fn note_tags(content: &str) -> Vec<String> {
+    let mut tags: Vec<String> = ["tags", "tag"]
+        .iter()
+        .flat_map(|key| front_matter_list(content, key))
+        .map(|t| t.trim_start_matches('#').to_string())
+        .collect();
+    let start = markdown::body_start(content);
+    let code = markdown::code_ranges(content);
+    let body = &content[start..];
+    for range in inline_tags(body) {
+        if !markdown::in_ranges(&code, start + range.start) {
+            tags.push(body[range.start + 1..range.end].to_string());
+        }
+    }
+    tags
+}
+
+/// ノートの別名（フロントマターの `aliases`）
+pub fn note_aliases(content: &str) -> Vec<String> {
+    ["aliases", "alias"]
+        .iter()
+        .flat_map(|key| front_matter_list(content, key))
+        .collect()
+}
+
+/// 引用の行から `>` を 1 段外す
+fn unquote(line: &str) -> &str {
+    let rest = line.trim_start().strip_prefix('>').unwrap_or(line);
+    rest.strip_prefix([' ', '\t']).unwrap_or(rest)
+}
+
+/// コールアウト（`> [!note] タイトル`）を HTML のブロックにする（入れ子も変換）
+pub fn convert_callouts(content: &str) -> String {
+    if !content.contains("[!") {
+        return content.to_string();
+    }
+    let code = markdown::code_ranges(content);
+    let mut out = String::with_capacity(content.len());
+    let mut lines = content.split_inclusive('\n').peekable();
+    let mut offset = 0;
+    while let Some(line) = lines.next() {
+        let start = offset;
+        offset += line.len();
+        let caps = CALLOUT.captures(line.trim_end_matches(['\n', '\r']));
+        let Some(caps) = caps.filter(|_| !markdown::in_ranges(&code, start)) else {
+            out.push_str(line);
+            continue;
+        };
+        let mut body = String::new();
+        while let Some(next) = lines.peek().filter(|l| l.trim_start().starts_with('>')) {
+            body.push_str(unquote(next));
+            offset += next.len();
+            lines.next();
+        }
+        out.push_str(&callout_html(&caps, &convert_callouts(&body)));
+    }
+    out
+}
+
+fn callout_html(caps: &Captures, body: &str) -> String {
+    let kind = caps[1].to_lowercase();
+    let title = match caps[3].trim() {
+        "" => {
+            let mut chars = kind.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        }
+        title => title.to_string(),
+    };
+    let title = text::escape_html(&title);
+    let kind = text::escape_html(&kind);
+    let body = body.trim_matches('\n');
+    let body = if body.is_empty() {
+        String::new()
+    } else {
+        format!("\n{body}\n\n")
+    };
+    match &caps[2] {
+        "" => format!(
+            "<div class=\"callout\" data-callout=\"{kind}\">\n<div class=\"callout-title\">{title}</div>\n{body}</div>\n\n"
+        ),
+        fold => {
+            let open = if fold == "+" { " open" } else { "" };
+            format!(
+                "<details class=\"callout\" data-callout=\"{kind}\"{open}>\n<summary class=\"callout-title\">{title}</summary>\n{body}</details>\n\n"
+            )
+        }
+    }
+}
+
+/// テンプレートの `{{date}}` `{{time}}` `{{title}}`（`{{date:YYYY}}` のような書式指定も可）を置き換える
+fn fill_template(template: &str, title: &str, date: NaiveDate) -> String {
+    let now = date.and_time(Local::now().time());
+    let locale = i18n::locale();
+    TEMPLATE_VARIABLE
+        .replace_all(template, |caps: &Captures| {
+            let format = caps.get(2).map(|m| m.as_str().trim());
+            match &caps[1] {
+                "title" => title.to_string(),
+                "date" => dates::format_moment(&now, format.unwrap_or("YYYY-MM-DD"), locale),
+                _ => dates::format_moment(&now, format.unwrap_or("HH:mm"), locale),
+            }
+        })
+        .into_owned()
+}
+
+/// ルートからのノートのパス（拡張子がなければ `.md` を付ける）
+fn note_path(root: &Path, name: &str) -> PathBuf {
+    let path = root.join(name.trim_start_matches('/'));
+    if vault::is_markdown(&path) {
+        path
+    } else {
+        path.with_extension("md")
+    }
+}
+
+/// 保管庫の Obsidian の設定（Obsidian の保管庫でなければ `None`）
+#[tauri::command]
+pub fn obsidian_settings(vault_root: String) -> Option<ObsidianSettings> {
+    detect(Path::new(&vault_root))
+}
+
+/// 保管庫で使われているタグと回数（回数の多い順）
+#[tauri::command]
+pub fn list_tags(vault_root: String) -> Result<Vec<TagCount>, String> {
+    let vault = Vault::scan(Path::new(&vault_root));
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for doc in vault.markdown_files() {
+        let content =
+            fs::read_to_string(doc).map_err(|e| tr!("Failed to read {}: {e}", doc.display()))?;
+        for tag in note_tags(&content) {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+    let mut tags: Vec<TagCount> = counts
+        .into_iter()
+        .map(|(tag, count)| TagCount { tag, count })
+        .collect();
+    tags.sort_by_key(|t| Reverse(t.count));
+    Ok(tags)
+}
+
+/// 新しい添付ファイルを置くフォルダ（Obsidian の設定に従う。既定は文書のフォルダ）
+#[tauri::command]
+pub fn attachment_folder(vault_root: String, path: String) -> String {
+    let root = Path::new(&vault_root);
+    let doc_dir = Path::new(&path).parent().unwrap_or(root);
+    let folder = detect(root).map(|s| s.attachment_folder);
+    let dir = match folder.as_deref() {
+        None => doc_dir.to_path_buf(),
+        Some("/") => root.to_path_buf(),
+        Some(f) if f.starts_with("./") || f == "." => doc_dir.join(f),
+        Some(f) => root.join(f),
+    };
+    path_string(&vault::normalize(&dir))
+}
+
+/// 日付（`YYYY-MM-DD`、省略時は今日）のデイリーノートを開く。なければテンプレートから作成する
+#[tauri::command]
+pub fn open_daily_note(vault_root: String, date: Option<String>) -> Result<DailyNote, String> {
+    let root = Path::new(&vault_root);
+    let date = match date {
+        Some(date) => dates::parse_date(&date)?,
+        None => Local::now().date_naive(),
+    };
+    let settings = detect(root).map(|s| s.daily_notes);
+    let (folder, format, template) = match &settings {
+        Some(s) => (s.folder.as_str(), s.format.as_str(), s.template.as_deref()),
+        None => ("", "YYYY-MM-DD", None),
+    };
+    // 書式に `/` があればサブフォルダになる
+    let name = dates::format_moment(&date.and_time(NaiveTime::MIN), format, i18n::locale());
+    let path = vault::normalize(&note_path(&root.join(folder), &name));
+    if path.exists() {
+        return Ok(DailyNote {
+            path: path_string(&path),
+            created: false,
+        });
+    }
+
+    let content = match template {
+        Some(template) => {
+            let template_path = note_path(root, template);
+            let template = fs::read_to_string(&template_path)
+                .map_err(|e| tr!("Failed to read {}: {e}", template_path.display()))?;
+            let title = name.rsplit('/').next().unwrap_or(&name);
+            fill_template(&template, title, date)
+        }
+        None => String::new(),
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| tr!("Failed to create {}: {e}", dir.display()))?;
+    }
+    fs::write(&path, content).map_err(|e| tr!("Failed to write {}: {e}", path.display()))?;
+    Ok(DailyNote {
+        path: path_string(&path),
+        created: true,
+    })
+}
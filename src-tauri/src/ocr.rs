@@ -0,0 +1,203 @@
+// OCR による画像・PDF の取り込み（tesseract / pdftoppm を利用）
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::shell::{self, RunOptions};
+use crate::text::is_cjk;
+use crate::tr;
+
+/// 画像 1 枚の文字認識を待つ時間
+const RECOGNIZE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// PDF を画像にするのを待つ時間（全ページ）
+const RASTERIZE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 認識したテキストの上限
+const MAX_TEXT: usize = 8 << 20;
+
+/// 取り込むエラー出力の上限
+const MAX_STDERR: usize = 64 << 10;
+
+/// 画像 1 枚を OCR してプレーンテキストを返す
+fn recognize(image: &Path, lang: &str) -> Result<String, String> {
+    let mut command = Command::new("tesseract");
+    command.arg(image).arg("stdout").args(["-l", lang]);
+    let output = shell::run(
+        command,
+        "tesseract",
+        RunOptions {
+            timeout: RECOGNIZE_TIMEOUT,
+            max_stdout: MAX_TEXT,
+            max_stderr: MAX_STDERR,
+            ..Default::default()
+        },
+    )
+    .map_err(|e| tr!("{e}. Install Tesseract OCR and add it to PATH."))?;
+    let Some(status) = output.status else {
+        return Err(tr!(
+            "{} timed out after {} s",
+            "tesseract",
+            RECOGNIZE_TIMEOUT.as_secs()
+        ));
+    };
+    if !status.success() {
+        return Err(tr!(
+            "tesseract failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    if output.stdout_truncated {
+        return Err(tr!("{} output exceeded {} bytes", "tesseract", MAX_TEXT));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// PDF の各ページを PNG に変換し、ページ順のパスを返す
+fn rasterize_pdf(pdf: &Path, work_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut command = Command::new("pdftoppm");
+    command
+        .args(["-r", "300", "-png"])
+        .arg(pdf)
+        .arg(work_dir.join("page"));
+    let output = shell::run(
+        command,
+        "pdftoppm",
+        RunOptions {
+            timeout: RASTERIZE_TIMEOUT,
+            max_stdout: MAX_STDERR,
+            max_stderr: MAX_STDERR,
+            ..Default::default()
+        },
+    )
+    .map_err(|e| tr!("{e}. Install Poppler to OCR PDF files."))?;
+    let Some(status) = output.status else {
+        return Err(tr!(
+            "{} timed out after {} s",
+            "pdftoppm",
+            RASTERIZE_TIMEOUT.as_secs()
+        ));
+    };
+    if !status.success() {
+        return Err(tr!("pdftoppm failed with {status}"));
+    }
+    let mut pages: Vec<PathBuf> = fs::read_dir(work_dir)
+        .map_err(|e| tr!("Failed to read {}: {e}", work_dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "png"))
+        .collect();
+    // page-1.png, page-2.png … の番号順（桁数が揃わない場合に備えて数値で比較）
+    pages.sort_by_key(|p| {
+        p.file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.rsplit('-').next())
+            .and_then(|n| n.parse::<usize>().ok())
+            .unwrap_or(0)
+    });
+    Ok(pages)
+}
+
+fn list_marker(line: &str) -> Option<(&'static str, &str)> {
+    for bullet in ["•", "・", "●", "○", "■", "□", "- ", "* ", "– "] {
+        if let Some(rest) = line.strip_prefix(bullet) {
+            return Some(("- ", rest.trim_start()));
+        }
+    }
+    None
+}
+
+/// OCR 結果を Markdown の段落・リストに整形
+fn to_markdown(text: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut paragraph = String::new();
+
+    let flush = |paragraph: &mut String, out: &mut Vec<String>| {
+        if !paragraph.is_empty() {
+            out.push(std::mem::take(paragraph));
+        }
+    };
+
+    for raw in text.lines() {
+        let line = raw.trim();
+        if line.is_empty() {
+            flush(&mut paragraph, &mut out);
+            continue;
+        }
+        if let Some((marker, rest)) = list_marker(line) {
+            flush(&mut paragraph, &mut out);
+            paragraph.push_str(marker);
+            paragraph.push_str(rest);
+            continue;
+        }
+        if paragraph.is_empty() {
+            paragraph.push_str(line);
+        } else if paragraph.ends_with('-') && line.chars().next().is_some_and(|c| c.is_lowercase())
+        {
+            // 行末ハイフネーションを結合
+            paragraph.pop();
+            paragraph.push_str(line);
+        } else {
+            // 和文同士は空白を挟まずに結合
+            let joins_cjk = paragraph.chars().last().is_some_and(is_cjk)
+                && line.chars().next().is_some_and(is_cjk);
+            if !joins_cjk {
+                paragraph.push(' ');
+            }
+            paragraph.push_str(line);
+        }
+    }
+    flush(&mut paragraph, &mut out);
+
+    // 連続するリスト項目は空行を挟まずに並べる
+    let mut markdown = String::new();
+    for (i, block) in out.iter().enumerate() {
+        if i > 0 {
+            let both_items = block.starts_with("- ") && out[i - 1].starts_with("- ");
+            markdown.push_str(if both_items { "\n" } else { "\n\n" });
+        }
+        markdown.push_str(block);
+    }
+    markdown
+}
+
+/// 画像または PDF を OCR して Markdown テキストを返す（`lang` は tesseract の言語指定、例: "jpn+eng"）
+#[tauri::command(async)]
+pub fn ocr_import(path: String, lang: Option<String>) -> Result<String, String> {
+    let source = Path::new(&path);
+    if !source.is_file() {
+        return Err(tr!("File not found: {path}"));
+    }
+    let lang = lang.unwrap_or_else(|| "eng".to_string());
+    let is_pdf = source
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("pdf"));
+
+    if !is_pdf {
+        return recognize(source, &lang).map(|text| to_markdown(&text));
+    }
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let work_dir = std::env::temp_dir().join(format!("mdvim-ocr-{}-{nanos}", std::process::id()));
+    fs::create_dir_all(&work_dir)
+        .map_err(|e| tr!("Failed to create {}: {e}", work_dir.display()))?;
+
+    let result = rasterize_pdf(source, &work_dir).and_then(|pages| {
+        pages
+            .iter()
+            .map(|page| recognize(page, &lang).map(|text| to_markdown(&text)))
+            .collect::<Result<Vec<_>, _>>()
+    });
+    let _ = fs::remove_dir_all(&work_dir);
+
+    Ok(result?
+        .into_iter()
+        .filter(|page| !page.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n"))
+}
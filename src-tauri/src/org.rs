@@ -0,0 +1,298 @@
+// Org-mode 文書を Markdown に変換（見出し・リスト・ブロック・表・リンクなど主要な構造のみ）
+
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+use crate::markdown;
+use crate::table;
+use crate::tr;
+
+static HEADLINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\*+)\s+(?:(TODO|DONE|NEXT|WAITING|CANCELLED)\s+)?(?:\[#[A-Z0-9]\]\s+)?(.*?)(?:\s+(:[\w@#%:]+:))?\s*$")
+        .unwrap()
+});
+static KEYWORD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*#\+(\w+):\s*(.*)$").unwrap());
+static BLOCK_BEGIN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^\s*#\+begin_(\w+)\s*(\S*)").unwrap());
+static LIST_ITEM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\s*)(?:[-+]|(\d+)[.)])\s+(?:\[([ Xx-])\]\s+)?(.*)$").unwrap());
+static DESCRIPTION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(.*?)\s+::\s+(.*)$").unwrap());
+static CODE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(^|[\s(\[{'])(?:=([^\s=](?:[^=]*?[^\s=])?)=|~([^\s~](?:[^~]*?[^\s~])?)~)").unwrap()
+});
+static LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[\[([^\[\]]+)\](?:\[([^\[\]]+)\])?\]").unwrap());
+static FOOTNOTE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[fn:([\w-]+)\]").unwrap());
+
+/// 強調記法（`*太字*` `/斜体/` `+取り消し+`）と Markdown の記号
+static EMPHASIS: LazyLock<Vec<(Regex, &str)>> = LazyLock::new(|| {
+    [('*', "**"), ('/', "*"), ('+', "~~")]
+        .into_iter()
+        .map(|(mark, replacement)| {
+            let m = regex::escape(&mark.to_string());
+            let pattern = format!(
+                r#"(^|[\s(\[{{'"]){m}([^\s{m}](?:[^{m}]*?[^\s{m}])?){m}($|[\s.,;:!?)\]}}'"-])"#
+            );
+            (Regex::new(&pattern).unwrap(), replacement)
+        })
+        .collect()
+});
+
+/// 強調記法を置き換える（前後が空白か句読点の場合のみ）
+fn convert_emphasis(text: &str) -> String {
+    let mut text = text.to_string();
+    for (pattern, replacement) in EMPHASIS.iter() {
+        // 区切りの文字を共有する隣の強調のために 2 回適用する
+        for _ in 0..2 {
+            text = pattern
+                .replace_all(&text, |c: &Captures| {
+                    format!("{}{replacement}{}{replacement}{}", &c[1], &c[2], &c[3])
+                })
+                .into_owned();
+        }
+    }
+    text
+}
+
+/// `[[target][説明]]` を Markdown のリンクにする
+fn convert_link(target: &str, description: Option<&str>) -> String {
+    if target.contains("://") && description.is_none() {
+        return format!("<{target}>");
+    }
+    let label = description.unwrap_or(target);
+    if let Some(heading) = target.strip_prefix('*') {
+        let label = description.unwrap_or(heading);
+        return format!("[{label}](#{})", markdown::slugify(heading));
+    }
+    let target = target.strip_prefix("file:").unwrap_or(target);
+    if is_image(target) && description.is_none() {
+        return format!("![]({target})");
+    }
+    let target = match target.strip_suffix(".org") {
+        Some(stem) if !target.contains("://") => format!("{stem}.md"),
+        _ => target.to_string(),
+    };
+    format!("[{label}]({target})")
+}
+
+fn is_image(target: &str) -> bool {
+    let lower = target.to_ascii_lowercase();
+    [".png", ".jpg", ".jpeg", ".gif", ".svg", ".webp"]
+        .iter()
+        .any(|ext| lower.ends_with(ext))
+}
+
+/// 行内の記法を変換（コード `=…=` `~…~` の中は変換しない）
+fn convert_inline(text: &str) -> String {
+    let mut out = String::new();
+    let mut last = 0;
+    let convert = |segment: &str| {
+        let mut converted = String::new();
+        let mut last = 0;
+        for caps in LINK.captures_iter(segment) {
+            let whole = caps.get(0).unwrap();
+            converted.push_str(&convert_emphasis(&segment[last..whole.start()]));
+            converted.push_str(&convert_link(&caps[1], caps.get(2).map(|m| m.as_str())));
+            last = whole.end();
+        }
+        converted.push_str(&convert_emphasis(&segment[last..]));
+        FOOTNOTE.replace_all(&converted, "[^$1]").into_owned()
+    };
+    for caps in CODE.captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        out.push_str(&convert(&text[last..whole.start()]));
+        out.push_str(&caps[1]);
+        let code = caps.get(2).or(caps.get(3)).unwrap().as_str();
+        out.push_str(&format!("`{code}`"));
+        last = whole.end();
+    }
+    out.push_str(&convert(&text[last..]));
+    out
+}
+
+/// 表の行（区切り行は `None`）
+fn table_row(line: &str) -> Option<Vec<String>> {
+    let inner = line.trim().trim_start_matches('|');
+    if inner.starts_with('-') {
+        return None;
+    }
+    let inner = inner.strip_suffix('|').unwrap_or(inner);
+    Some(
+        inner
+            .split('|')
+            .map(|cell| table::escape_cell(&convert_inline(cell.trim())))
+            .collect(),
+    )
+}
+
+/// Org-mode のテキストを Markdown に変換
+pub fn org_to_markdown(org: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut front_matter: Vec<String> = Vec::new();
+    let mut lines = org.lines().peekable();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+
+        // プロパティなどのドロワーは除く
+        if trimmed.starts_with(':') && trimmed.ends_with(':') && trimmed.len() > 2 {
+            let name = trimmed.trim_matches(':').to_ascii_uppercase();
+            if name != "END"
+                && lines
+                    .clone()
+                    .any(|l| l.trim().eq_ignore_ascii_case(":END:"))
+            {
+                for l in lines.by_ref() {
+                    if l.trim().eq_ignore_ascii_case(":END:") {
+                        break;
+                    }
+                }
+                continue;
+            }
+        }
+
+        if let Some(caps) = BLOCK_BEGIN.captures(line) {
+            let kind = caps[1].to_ascii_lowercase();
+            let end = format!("#+end_{kind}");
+            let body: Vec<&str> = lines
+                .by_ref()
+                .take_while(|l| !l.trim().to_ascii_lowercase().starts_with(&end))
+                .collect();
+            match kind.as_str() {
+                "src" | "example" | "export" => {
+                    let info = if kind == "src" { &caps[2] } else { "" };
+                    let body = body.join("\n");
+                    let fence = if body.contains("```") { "````" } else { "```" };
+                    out.push(format!("{fence}{info}\n{body}\n{fence}"));
+                }
+                "quote" | "verse" => {
+                    for l in body {
+                        out.push(
+                            format!("> {}", convert_inline(l.trim()))
+                                .trim_end()
+                                .to_string(),
+                        );
+                    }
+                }
+                "comment" => {}
+                _ => out.extend(body.iter().map(|l| convert_inline(l))),
+            }
+            continue;
+        }
+
+        if let Some(caps) = KEYWORD.captures(line) {
+            let value = caps[2].trim();
+            match caps[1].to_ascii_lowercase().as_str() {
+                "title" | "author" | "date" if !value.is_empty() => {
+                    let key = caps[1].to_ascii_lowercase();
+                    front_matter.push(format!("{key}: \"{}\"", value.replace('"', "\\\"")));
+                }
+                "filetags" => {
+                    let tags: Vec<&str> = value.split(':').filter(|t| !t.is_empty()).collect();
+                    if !tags.is_empty() {
+                        front_matter.push(format!("tags: [{}]", tags.join(", ")));
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        // コメント行
+        if trimmed == "#" || trimmed.starts_with("# ") {
+            out.push(format!(
+                "<!-- {} -->",
+                trimmed.trim_start_matches('#').trim()
+            ));
+            continue;
+        }
+
+        if line.starts_with('*') {
+            if let Some(caps) = HEADLINE.captures(line) {
+                let mut heading = format!("{} ", "#".repeat(caps[1].len().min(6)));
+                if let Some(keyword) = caps.get(2) {
+                    heading.push_str(keyword.as_str());
+                    heading.push(' ');
+                }
+                heading.push_str(&convert_inline(&caps[3]));
+                if let Some(tags) = caps.get(4) {
+                    for tag in tags.as_str().split(':').filter(|t| !t.is_empty()) {
+                        heading.push_str(&format!(" #{tag}"));
+                    }
+                }
+                out.push(heading);
+                continue;
+            }
+        }
+
+        if trimmed.starts_with('|') {
+            let mut rows: Vec<Vec<String>> = table_row(line).into_iter().collect();
+            while let Some(next) = lines.peek().filter(|l| l.trim().starts_with('|')) {
+                rows.extend(table_row(next));
+                lines.next();
+            }
+            if !rows.is_empty() {
+                out.push(table::format_table(rows, false).trim_end().to_string());
+            }
+            continue;
+        }
+
+        if trimmed.len() >= 5 && trimmed.chars().all(|c| c == '-') {
+            out.push("---".to_string());
+            continue;
+        }
+
+        if let Some(caps) = LIST_ITEM.captures(line) {
+            let indent = &caps[1];
+            let marker = match caps.get(2) {
+                Some(number) => format!("{}.", number.as_str()),
+                None => "-".to_string(),
+            };
+            let checkbox = match caps.get(3).map(|m| m.as_str()) {
+                Some("X") | Some("x") => "[x] ",
+                Some(_) => "[ ] ",
+                None => "",
+            };
+            let body = match DESCRIPTION.captures(&caps[4]) {
+                Some(d) if caps.get(2).is_none() => {
+                    format!("**{}**: {}", convert_inline(&d[1]), convert_inline(&d[2]))
+                }
+                _ => convert_inline(&caps[4]),
+            };
+            out.push(format!("{indent}{marker} {checkbox}{body}"));
+            continue;
+        }
+
+        // 脚注の定義
+        if let Some(rest) = trimmed.strip_prefix("[fn:") {
+            if let Some((name, text)) = rest.split_once(']') {
+                out.push(format!("[^{name}]: {}", convert_inline(text.trim())));
+                continue;
+            }
+        }
+
+        let line = line
+            .strip_suffix("\\\\")
+            .map_or(line.to_string(), |l| format!("{l}\\"));
+        out.push(convert_inline(&line));
+    }
+
+    let mut markdown = String::new();
+    if !front_matter.is_empty() {
+        markdown.push_str(&format!("---\n{}\n---\n\n", front_matter.join("\n")));
+    }
+    markdown.push_str(out.join("\n").trim());
+    markdown.push('\n');
+    markdown
+}
+
+/// Org-mode のファイルを Markdown に変換
+#[tauri::command]
+pub fn import_org(path: String) -> Result<String, String> {
+    let path = Path::new(&path);
+    let org =
+        fs::read_to_string(path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+    Ok(org_to_markdown(&org))
+}
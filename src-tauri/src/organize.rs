@@ -0,0 +1,517 @@
+// メタデータに基づくノートの整理（タグやフロントマターの値で移動・名前の変更、古いノートの保管）
+//
+// 規則は引数で渡すか、保管庫の `.mdvim/organize.json`（規則の配列）に書く。例:
+// - `{"tag": "archive", "move_to": "Archive"}` … `#archive` のノートを `Archive/` へ移す
+// - `{"rename": "{{date:YYYY-MM-DD}}-{{slug}}"}` … フロントマターの `date` と `title` から名前を付ける
+//
+// `move_to` と `rename` では `{{title}}` `{{slug}}` `{{name}}` `{{date:書式}}` とフロントマターの項目が使える。
+// 既定では移動・名前の変更の予定だけを返し、`apply` を指定すると実行して他のノートのリンクを書き換える。
+//
+// `archive_notes` は一定の期間変更していないノートを保管用のフォルダへ移す。
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::dates;
+use crate::export;
+use crate::front_matter;
+use crate::i18n;
+use crate::markdown;
+use crate::obsidian;
+use crate::refactor::{self, read, UpdatedFile};
+use crate::tr;
+use crate::vault::{self, path_string, Vault};
+
+/// 保管庫内の規則のファイル
+const RULES_FILE: &str = ".mdvim/organize.json";
+
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*([\w.-]+)(?::([^}]*))?\s*\}\}").unwrap());
+
+/// 整理の規則（条件はすべて満たすものに、移動と名前の変更を行う）
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OrganizeRule {
+    /// 結果に表示する名前
+    pub name: Option<String>,
+    /// このタグを持つ（`#` は省略可。`archive` は `archive/2024` にも一致する）
+    pub tag: Option<String>,
+    /// 保管庫のこのフォルダ内にある
+    pub folder: Option<String>,
+    /// フロントマターにこの項目がある
+    pub field: Option<String>,
+    /// `field` の値がこれと等しい
+    pub equals: Option<String>,
+    /// ファイル名（拡張子を除く）がこの正規表現に一致する
+    pub pattern: Option<String>,
+    /// 移動先のフォルダ（保管庫からの相対パス）
+    pub move_to: Option<String>,
+    /// 新しいファイル名（拡張子がなければ `.md` を付ける）
+    pub rename: Option<String>,
+}
+
+/// 予定した移動・名前の変更
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedMove {
+    pub from: String,
+    pub to: String,
+    /// 当てはまった規則（名前がなければ番号）
+    pub rules: Vec<String>,
+}
+
+/// 規則に当てはまったが動かせないノート
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedNote {
+    pub path: String,
+    pub rule: String,
+    pub reason: String,
+}
+
+/// `organize_vault` の結果
+#[derive(Debug, Serialize)]
+pub struct OrganizeReport {
+    pub moves: Vec<PlannedMove>,
+    pub skipped: Vec<SkippedNote>,
+    /// 実行したか（`false` なら予定だけ）
+    pub applied: bool,
+    /// リンクを書き換えたノート
+    pub updated_files: Vec<UpdatedFile>,
+}
+
+/// 整理するノートの情報
+struct Note<'a> {
+    path: &'a Path,
+    content: String,
+    fields: serde_json::Map<String, Value>,
+    tags: Vec<String>,
+}
+
+impl Note<'_> {
+    fn title(&self) -> String {
+        match self.fields.get("title").and_then(Value::as_str) {
+            Some(title) if !title.trim().is_empty() => title.trim().to_string(),
+            _ => export::document_title(self.path, &self.content),
+        }
+    }
+
+    /// フロントマターの `date`（`YYYY-MM-DD` か RFC 3339）
+    fn date(&self) -> Option<NaiveDateTime> {
+        let value = self.fields.get("date")?.as_str()?.trim();
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map(|d| d.and_time(NaiveTime::MIN))
+            .or_else(|_| DateTime::parse_from_rfc3339(value).map(|d| d.naive_local()))
+            .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M"))
+            .ok()
+    }
+
+    /// `{{name}}` 1 つの値（ファイル名に使えない文字は `-` にする）
+    fn placeholder(&self, name: &str, format: Option<&str>) -> Result<String, String> {
+        let value = match name {
+            "title" => self.title(),
+            "slug" => markdown::slugify(&self.title()),
+            "name" => self
+                .path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            "date" => {
+                let date = self
+                    .date()
+                    .ok_or_else(|| tr!("No date in front matter ({name})"))?;
+                dates::format_moment(&date, format.unwrap_or("YYYY-MM-DD"), i18n::locale())
+            }
+            field => match self.fields.get(field) {
+                Some(Value::String(s)) => s.clone(),
+                Some(Value::Array(items)) => items
+                    .iter()
+                    .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                Some(Value::Null) | None => return Err(tr!("Missing front matter field: {field}")),
+                Some(value) => value.to_string(),
+            },
+        };
+        let value: String = value
+            .chars()
+            .map(|c| match c {
+                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+                c if c.is_control() => '-',
+                c => c,
+            })
+            .collect();
+        let value = value.trim().trim_matches('.').to_string();
+        if value.is_empty() {
+            return Err(tr!("Missing front matter field: {name}"));
+        }
+        Ok(value)
+    }
+
+    fn fill(&self, template: &str) -> Result<String, String> {
+        let mut error = None;
+        let filled = PLACEHOLDER.replace_all(template, |caps: &Captures| {
+            match self.placeholder(&caps[1], caps.get(2).map(|m| m.as_str().trim())) {
+                Ok(value) => value,
+                Err(e) => {
+                    error.get_or_insert(e);
+                    String::new()
+                }
+            }
+        });
+        match error {
+            Some(e) => Err(e),
+            None => Ok(filled.into_owned()),
+        }
+    }
+}
+
+fn has_tag(tags: &[String], tag: &str) -> bool {
+    let tag = tag.trim().trim_start_matches('#');
+    tags.iter().any(|t| {
+        t.eq_ignore_ascii_case(tag)
+            || t.len() > tag.len()
+                && t.is_char_boundary(tag.len())
+                && t[..tag.len()].eq_ignore_ascii_case(tag)
+                && t[tag.len()..].starts_with('/')
+    })
+}
+
+impl OrganizeRule {
+    fn label(&self, index: usize) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("#{}", index + 1))
+    }
+
+    fn matches(&self, root: &Path, note: &Note, pattern: Option<&Regex>) -> bool {
+        if self
+            .tag
+            .as_deref()
+            .is_some_and(|tag| !has_tag(&note.tags, tag))
+        {
+            return false;
+        }
+        if let Some(folder) = &self.folder {
+            let dir = vault::normalize(&root.join(folder.trim_matches('/')));
+            if !note.path.starts_with(&dir) {
+                return false;
+            }
+        }
+        if let Some(field) = &self.field {
+            let Some(value) = note.fields.get(field).filter(|v| !v.is_null()) else {
+                return false;
+            };
+            if let Some(expected) = &self.equals {
+                let matches = match value {
+                    Value::String(s) => s.trim() == expected.trim(),
+                    Value::Array(items) => items
+                        .iter()
+                        .any(|v| v.as_str().is_some_and(|s| s.trim() == expected.trim())),
+                    value => {
+                        serde_json::from_str::<Value>(expected.trim()).is_ok_and(|e| e == *value)
+                    }
+                };
+                if !matches {
+                    return false;
+                }
+            }
+        }
+        if let Some(re) = pattern {
+            let stem = note
+                .path
+                .file_stem()
+                .map(|s| s.to_string_lossy())
+                .unwrap_or_default();
+            if !re.is_match(&stem) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// 規則を当てはめた後のパス
+    fn apply(&self, root: &Path, note: &Note, current: &Path) -> Result<PathBuf, String> {
+        let mut target = current.to_path_buf();
+        if let Some(folder) = &self.move_to {
+            let folder = note.fill(folder)?;
+            let name = target
+                .file_name()
+                .map(ToOwned::to_owned)
+                .unwrap_or_default();
+            target = root.join(folder.trim_matches('/')).join(name);
+        }
+        if let Some(template) = &self.rename {
+            let mut name = note.fill(template)?;
+            if Path::new(&name).extension().is_none() {
+                name.push_str(".md");
+            }
+            target.set_file_name(name);
+        }
+        let target = vault::normalize(&target);
+        if !target.starts_with(root) {
+            return Err(tr!("Target is outside the vault: {}", target.display()));
+        }
+        Ok(target)
+    }
+}
+
+fn load_rules(root: &Path) -> Result<Vec<OrganizeRule>, String> {
+    let file = root.join(RULES_FILE);
+    let text =
+        fs::read_to_string(&file).map_err(|e| tr!("Failed to read {}: {e}", file.display()))?;
+    serde_json::from_str(&text).map_err(|e| tr!("Invalid {}: {e}", file.display()))
+}
+
+/// 規則に従って移動・名前の変更を予定する
+fn plan(root: &Path, rules: &[OrganizeRule]) -> Result<OrganizeReport, String> {
+    let patterns = rules
+        .iter()
+        .map(|rule| {
+            rule.pattern
+                .as_deref()
+                .map(|p| Regex::new(p).map_err(|e| tr!("Invalid pattern {p}: {e}")))
+                .transpose()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let vault = Vault::scan(root);
+    let mut moves = Vec::new();
+    let mut skipped = Vec::new();
+    let mut targets: HashSet<PathBuf> = HashSet::new();
+    for doc in vault.markdown_files() {
+        let content = read(doc)?;
+        let fields = match front_matter::parse(&content) {
+            Ok(Some(fm)) => match fm.value {
+                Value::Object(map) => map,
+                _ => Default::default(),
+            },
+            _ => Default::default(),
+        };
+        let note = Note {
+            path: doc,
+            tags: obsidian::note_tags(&content),
+            content,
+            fields,
+        };
+        let mut target = doc.clone();
+        let mut applied = Vec::new();
+        let mut failed = false;
+        for (i, rule) in rules.iter().enumerate() {
+            if !rule.matches(&vault.root, &note, patterns[i].as_ref()) {
+                continue;
+            }
+            match rule.apply(&vault.root, &note, &target) {
+                Ok(next) => {
+                    target = next;
+                    applied.push(rule.label(i));
+                }
+                Err(reason) => {
+                    skipped.push(SkippedNote {
+                        path: path_string(doc),
+                        rule: rule.label(i),
+                        reason,
+                    });
+                    failed = true;
+                    break;
+                }
+            }
+        }
+        if failed || target == *doc {
+            continue;
+        }
+        let rule = applied.last().cloned().unwrap_or_default();
+        if (target.exists() && !same_file(&target, doc)) || !targets.insert(target.clone()) {
+            skipped.push(SkippedNote {
+                path: path_string(doc),
+                rule,
+                reason: tr!("File already exists: {}", target.display()),
+            });
+            continue;
+        }
+        moves.push(PlannedMove {
+            from: path_string(doc),
+            to: path_string(&target),
+            rules: applied,
+        });
+    }
+    Ok(OrganizeReport {
+        moves,
+        skipped,
+        applied: false,
+        updated_files: Vec::new(),
+    })
+}
+
+/// 大文字と小文字だけが違う名前への変更（大文字小文字を区別しないファイルシステムでは同じファイル）
+fn same_file(a: &Path, b: &Path) -> bool {
+    path_string(a).to_lowercase() == path_string(b).to_lowercase()
+}
+
+/// 規則に従ってノートを整理する
+///
+/// `rules` を省くと保管庫の `.mdvim/organize.json` を使う。`apply` が `true` でなければ予定だけを返す。
+#[tauri::command(async)]
+pub fn organize_vault(
+    root: String,
+    rules: Option<Vec<OrganizeRule>>,
+    apply: Option<bool>,
+) -> Result<OrganizeReport, String> {
+    let root = vault::normalize(Path::new(&root));
+    if !root.is_dir() {
+        return Err(tr!("Folder not found: {}", root.display()));
+    }
+    let rules = match rules {
+        Some(rules) => rules,
+        None => load_rules(&root)?,
+    };
+    let mut report = plan(&root, &rules)?;
+    if !apply.unwrap_or(false) {
+        return Ok(report);
+    }
+    for planned in &report.moves {
+        // 移動するたびにリンクの解決先が変わるので、保管庫を読み直す
+        let vault = Vault::scan(&root);
+        let updated = refactor::move_with_link_update(
+            &vault,
+            Path::new(&planned.from),
+            Path::new(&planned.to),
+        )?;
+        report.updated_files.extend(updated);
+    }
+    report.applied = true;
+    Ok(report)
+}
+
+/// 古いノートを移したときの既定のフォルダ
+const ARCHIVE_DIR: &str = "Archive";
+
+/// 保管庫に移したノート
+#[derive(Debug, Serialize)]
+pub struct ArchivedNote {
+    pub from: String,
+    pub to: String,
+    /// 最後に変更した日時
+    pub modified: String,
+}
+
+/// `archive_notes` の結果
+#[derive(Debug, Serialize)]
+pub struct ArchiveReport {
+    /// この日時より前に変更したノートを対象にした
+    pub cutoff: String,
+    pub moved: Vec<ArchivedNote>,
+    pub skipped: Vec<SkippedNote>,
+    /// 実行したか（`false` なら予定だけ）
+    pub applied: bool,
+    pub updated_files: Vec<UpdatedFile>,
+}
+
+/// `older_than`（`90d`・`12w`・`6m`・`1y`・日数・`YYYY-MM-DD`）から、対象にする変更日時の上限を求める
+fn archive_cutoff(older_than: &str) -> Result<DateTime<Local>, String> {
+    let value = older_than.trim();
+    if let Ok(date) = dates::parse_date(value) {
+        return date
+            .and_time(NaiveTime::MIN)
+            .and_local_timezone(Local)
+            .earliest()
+            .ok_or_else(|| tr!("Invalid date: {value}"));
+    }
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => value.split_at(i),
+        None => (value, "d"),
+    };
+    let days_per_unit = match unit.trim() {
+        "d" => 1,
+        "w" => 7,
+        "m" => 30,
+        "y" => 365,
+        _ => return Err(tr!("Invalid duration: {older_than}")),
+    };
+    let number: i64 = number
+        .parse()
+        .map_err(|_| tr!("Invalid duration: {older_than}"))?;
+    Local::now()
+        .checked_sub_signed(Duration::days(number.saturating_mul(days_per_unit)))
+        .ok_or_else(|| tr!("Date out of range"))
+}
+
+/// 一定の期間変更していないノートを保管用のフォルダ（既定は `Archive/`）へ移し、リンクを書き換える
+///
+/// 保管用のフォルダの中では元のフォルダ構成を保つ。`dry_run` が `true` なら予定だけを返す。
+#[tauri::command(async)]
+pub fn archive_notes(
+    root: String,
+    older_than: String,
+    dest: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<ArchiveReport, String> {
+    let root = vault::normalize(Path::new(&root));
+    if !root.is_dir() {
+        return Err(tr!("Folder not found: {}", root.display()));
+    }
+    let cutoff = archive_cutoff(&older_than)?;
+    let dest = vault::normalize(
+        &root.join(
+            dest.as_deref()
+                .unwrap_or(ARCHIVE_DIR)
+                .trim_matches(['/', '\\']),
+        ),
+    );
+    if !dest.starts_with(&root) || dest == root {
+        return Err(tr!("Target is outside the vault: {}", dest.display()));
+    }
+
+    let vault = Vault::scan(&root);
+    let mut moved = Vec::new();
+    let mut skipped = Vec::new();
+    for doc in vault.markdown_files().filter(|doc| !doc.starts_with(&dest)) {
+        let Ok(modified) = fs::metadata(doc).and_then(|m| m.modified()) else {
+            continue;
+        };
+        let modified: DateTime<Local> = modified.into();
+        if modified >= cutoff {
+            continue;
+        }
+        let Ok(relative) = doc.strip_prefix(&root) else {
+            continue;
+        };
+        let target = dest.join(relative);
+        if target.exists() {
+            skipped.push(SkippedNote {
+                path: path_string(doc),
+                rule: ARCHIVE_DIR.to_string(),
+                reason: tr!("File already exists: {}", target.display()),
+            });
+            continue;
+        }
+        moved.push(ArchivedNote {
+            from: path_string(doc),
+            to: path_string(&target),
+            modified: modified.to_rfc3339(),
+        });
+    }
+
+    let mut report = ArchiveReport {
+        cutoff: cutoff.to_rfc3339(),
+        moved,
+        skipped,
+        applied: false,
+        updated_files: Vec::new(),
+    };
+    if dry_run.unwrap_or(false) {
+        return Ok(report);
+    }
+    for note in &report.moved {
+        let vault = Vault::scan(&root);
+        let updated =
+            refactor::move_with_link_update(&vault, Path::new(&note.from), Path::new(&note.to))?;
+        report.updated_files.extend(updated);
+    }
+    report.applied = true;
+    Ok(report)
+}
@@ -0,0 +1,220 @@
+// 書き出し先のファイル名のテンプレート（`{{dir}}/{{slug}}-{{date}}.html` など）
+//
+// フロントマターの項目も `{{author}}` のように使える。値の中の `/` などパスに使えない文字は `-` にする。
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::export;
+use crate::export_profiles::ExportFormat;
+use crate::markdown;
+use crate::obsidian;
+use crate::templates;
+use crate::tr;
+use crate::vault::{self, path_string, to_slash};
+
+/// 番号を付けて試す回数の上限
+const MAX_NUMBER: usize = 10_000;
+
+/// 書き出し先がすでにあるときの扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Collision {
+    /// 上書きする（同じ一括エクスポートの中で重なったものには番号を付ける）
+    #[default]
+    Overwrite,
+    /// `-2` `-3` … を付けて空いている名前にする
+    Number,
+    /// エラーにする
+    Error,
+}
+
+/// パスに使えない文字を `-` にする
+fn sanitize(value: &str) -> String {
+    value
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | '<' | '>' | ':' | '"' | '|' | '?' | '*' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .collect()
+}
+
+/// テンプレートの変数
+///
+/// `dir`（文書のフォルダ）・`reldir`（`root` から見た文書のフォルダ）・`name`・`stem`・`title`・`slug`・
+/// `date`・`year`・`month`・`day`・`ext`・`home` とフロントマターの項目（同じ名前なら
+/// `dir` `reldir` `ext` `home` 以外はフロントマターを優先する）。
+pub fn variables(
+    app: &AppHandle,
+    source: &Path,
+    content: &str,
+    root: Option<&Path>,
+    extension: &str,
+) -> HashMap<String, String> {
+    let dir = source.parent().unwrap_or(Path::new(""));
+    let file = |name: Option<&std::ffi::OsStr>| {
+        name.map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    let title = export::document_title(source, content);
+    let slug = match markdown::slugify(&title) {
+        slug if slug.is_empty() => markdown::slugify(&file(source.file_stem())),
+        slug => slug,
+    };
+    let today = Local::now();
+    let mut values: HashMap<String, String> = [
+        ("name", file(source.file_name())),
+        ("stem", file(source.file_stem())),
+        ("title", title),
+        ("slug", slug),
+        ("date", today.format("%Y-%m-%d").to_string()),
+        ("year", today.format("%Y").to_string()),
+        ("month", today.format("%m").to_string()),
+        ("day", today.format("%d").to_string()),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v))
+    .collect();
+    values.extend(obsidian::front_matter_fields(content));
+    for value in values.values_mut() {
+        *value = sanitize(value);
+    }
+    let reldir = root
+        .and_then(|root| dir.strip_prefix(root).ok())
+        .map(to_slash)
+        .unwrap_or_default();
+    let home = app
+        .path()
+        .home_dir()
+        .map(|h| path_string(&h))
+        .unwrap_or_default();
+    values.insert("dir".to_string(), path_string(dir));
+    values.insert("reldir".to_string(), reldir);
+    values.insert("home".to_string(), home);
+    values.insert("ext".to_string(), extension.to_string());
+    values
+}
+
+/// テンプレートの最後の部分が拡張子で終わるか（`.html` や `.{{ext}}`）
+fn has_extension(template: &str) -> bool {
+    let name = template.rsplit(['/', '\\']).next().unwrap_or_default();
+    name.ends_with(".{{ext}}")
+        || name.rsplit_once('.').is_some_and(|(stem, ext)| {
+            !stem.is_empty() && !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+/// テンプレートから書き出し先のパスを作る
+///
+/// 相対パスは `base` から、先頭の `~` はホームフォルダから。テンプレートが拡張子で終わらなければ
+/// `.{{ext}}` を付ける（タイトルの `1.5` などは拡張子とみなさない）。変数を置き換えた部分が
+/// `.` や `..` になった場合は `-` にして、`base` の外に出ないようにする。
+pub fn expand(template: &str, values: &HashMap<String, String>, base: &Path) -> PathBuf {
+    let template = template.trim();
+    let mut expanded = template
+        .split_inclusive(['/', '\\'])
+        .map(|part| {
+            let filled = templates::fill(part, values);
+            if filled == part {
+                return filled;
+            }
+            filled
+                .split_inclusive(['/', '\\'])
+                .map(|piece| {
+                    let name = piece.trim_end_matches(['/', '\\']);
+                    match name {
+                        "." | ".." => format!("-{}", &piece[name.len()..]),
+                        _ => piece.to_string(),
+                    }
+                })
+                .collect()
+        })
+        .collect::<String>();
+    if !has_extension(template) {
+        if let Some(extension) = values.get("ext").filter(|e| !e.is_empty()) {
+            expanded.push('.');
+            expanded.push_str(extension);
+        }
+    }
+    let home = values.get("home").cloned().unwrap_or_default();
+    let expanded = match expanded.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => format!("{home}{rest}"),
+        _ => expanded,
+    };
+    vault::normalize(&base.join(expanded))
+}
+
+/// `-2` `-3` … を付けたパス
+fn numbered(path: &Path, n: usize) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}-{n}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{n}"),
+    };
+    path.with_file_name(name)
+}
+
+/// 重なりを扱った書き出し先（`taken` は同じ処理の中ですでに使ったパス。決めたパスを加える）
+pub fn resolve(
+    path: PathBuf,
+    collision: Collision,
+    taken: &mut HashSet<PathBuf>,
+) -> Result<PathBuf, String> {
+    let used = |p: &Path, taken: &HashSet<PathBuf>| {
+        taken.contains(p) || (collision != Collision::Overwrite && p.exists())
+    };
+    let resolved = if !used(&path, taken) {
+        path
+    } else if collision == Collision::Error {
+        return Err(tr!("Output file already exists: {}", path.display()));
+    } else {
+        (2..MAX_NUMBER)
+            .map(|n| numbered(&path, n))
+            .find(|p| !used(p, taken))
+            .ok_or_else(|| tr!("Output file already exists: {}", path.display()))?
+    };
+    taken.insert(resolved.clone());
+    Ok(resolved)
+}
+
+/// 文書 `path` の書き出し先をテンプレートから求める（`export_html` などの `out_path` に使う）
+///
+/// `content` を省略するとファイルから読む。相対パスは文書のフォルダから。
+#[tauri::command]
+pub fn resolve_output_path(
+    app: AppHandle,
+    path: String,
+    template: String,
+    content: Option<String>,
+    format: Option<ExportFormat>,
+    root: Option<String>,
+    collision: Option<Collision>,
+) -> Result<String, String> {
+    let source = vault::normalize(Path::new(&path));
+    let content = match content {
+        Some(content) => content,
+        None => fs::read_to_string(&source)
+            .map_err(|e| tr!("Failed to read {}: {e}", source.display()))?,
+    };
+    let root = root.map(|r| vault::normalize(Path::new(&r)));
+    let extension = format.unwrap_or_default().extension();
+    let values = variables(&app, &source, &content, root.as_deref(), extension);
+    let base = source.parent().unwrap_or(Path::new(""));
+    let out = resolve(
+        expand(&template, &values, base),
+        collision.unwrap_or_default(),
+        &mut HashSet::new(),
+    )?;
+    Ok(path_string(&out))
+}
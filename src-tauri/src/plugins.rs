@@ -0,0 +1,454 @@
+// WebAssembly プラグイン（Markdown の変換・エクスポート形式・コマンドを追加する）
+//
+// プラグインは `<plugins_dir>/<名前>/plugin.json` と WASM モジュールからなる。
+// 呼び出し規約: ホストが `alloc(len) -> ptr` で確保した領域に入力（UTF-8）を書き、
+// `func(ptr, len) -> i64` を呼ぶ。戻り値は出力の `(ptr << 32) | len`。
+// `dealloc(ptr, len)` があれば入出力の領域の解放に使う。
+//
+// ホスト API（`mdvim` モジュール）:
+// - `log(ptr, len)` … ログを出力
+// - `read_file(path_ptr, path_len, buf_ptr, buf_cap) -> i32` … ワークスペースのファイルを読む
+//   （`read_vault` 権限が必要。長さを返し、`buf_cap` を超える場合は書き込まない。失敗時は -1）
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::state::AppState;
+use crate::tr;
+use crate::vault::{self, path_string};
+use crate::wasm::{self, Host, Instance, Limits};
+
+const MANIFEST_FILE: &str = "plugin.json";
+
+/// プラグインごとの実行制限
+const LIMITS: Limits = Limits {
+    max_pages: 1024,
+    fuel: 500_000_000,
+};
+
+/// ワークスペースのファイルの読み込みを許可する権限
+const READ_VAULT: &str = "read_vault";
+
+/// プラグインが追加するエクスポート形式
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PluginExportFormat {
+    pub id: String,
+    pub name: String,
+    /// 出力ファイルの拡張子（`.` なし）
+    pub extension: String,
+    /// 呼び出す関数
+    pub function: String,
+}
+
+/// プラグインが追加するコマンド
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PluginCommand {
+    pub id: String,
+    pub title: String,
+    pub function: String,
+}
+
+/// `plugin.json` の内容
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    /// WASM モジュールのファイル名
+    #[serde(default = "default_main")]
+    pub main: String,
+    /// Markdown を変換する関数
+    #[serde(default)]
+    pub transform: Option<String>,
+    #[serde(default)]
+    pub export_formats: Vec<PluginExportFormat>,
+    #[serde(default)]
+    pub commands: Vec<PluginCommand>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+fn default_main() -> String {
+    "plugin.wasm".to_string()
+}
+
+/// `list_plugins` の 1 件
+#[derive(Debug, Serialize)]
+pub struct PluginInfo {
+    pub dir: String,
+    pub enabled: bool,
+    /// マニフェストを読めなかった場合のエラー
+    pub error: Option<String>,
+    pub manifest: Option<PluginManifest>,
+}
+
+/// `plugin-log` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginLog {
+    pub plugin: String,
+    pub message: String,
+}
+
+struct LoadedPlugin {
+    manifest: PluginManifest,
+    /// 同じプラグインの呼び出しは 1 つずつ実行する
+    instance: Mutex<Instance>,
+}
+
+/// 有効なプラグイン（有効にした順）
+///
+/// 実行中は一覧のロックを外し、一覧の表示や他のプラグインの有効化を妨げない。
+#[derive(Default)]
+pub struct PluginState {
+    loaded: Mutex<Vec<Arc<LoadedPlugin>>>,
+}
+
+impl PluginState {
+    fn get(&self, id: &str) -> Result<Arc<LoadedPlugin>, String> {
+        self.loaded
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|p| p.manifest.id == id)
+            .cloned()
+            .ok_or_else(|| tr!("Plugin is not enabled: {id}"))
+    }
+
+    /// 有効なプラグインのコマンド（プラグインの `id`・名前と組にする）
+    pub fn commands(&self) -> Vec<(String, String, PluginCommand)> {
+        self.loaded
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|p| {
+                p.manifest
+                    .commands
+                    .iter()
+                    .map(|c| (p.manifest.id.clone(), p.manifest.name.clone(), c.clone()))
+            })
+            .collect()
+    }
+}
+
+/// プラグインから呼ばれるホスト関数
+struct PluginHost<'a> {
+    manifest: &'a PluginManifest,
+    vault: Option<&'a Path>,
+    logs: Vec<String>,
+}
+
+fn guest_slice(memory: &[u8], ptr: u64, len: u64) -> Result<&[u8], String> {
+    let (ptr, len) = (ptr as u32 as usize, len as u32 as usize);
+    memory
+        .get(ptr..ptr + len)
+        .ok_or_else(|| "out of bounds memory access".to_string())
+}
+
+impl PluginHost<'_> {
+    fn read_file(&self, memory: &mut [u8], args: &[u64]) -> Result<i32, String> {
+        let &[path_ptr, path_len, buf_ptr, buf_cap] = args else {
+            return Err("read_file takes 4 arguments".to_string());
+        };
+        if !self.manifest.permissions.iter().any(|p| p == READ_VAULT) {
+            return Err(tr!("Plugin does not have the {READ_VAULT} permission"));
+        }
+        let Some(vault) = self.vault else {
+            return Ok(-1);
+        };
+        let relative = String::from_utf8_lossy(guest_slice(memory, path_ptr, path_len)?);
+        let path = vault::normalize(&vault.join(relative.as_ref()));
+        if !vault::is_within(vault, &path) {
+            return Ok(-1);
+        }
+        let Ok(data) = fs::read(&path) else {
+            return Ok(-1);
+        };
+        let Ok(len) = i32::try_from(data.len()) else {
+            return Ok(-1);
+        };
+        if data.len() <= buf_cap as u32 as usize {
+            let start = buf_ptr as u32 as usize;
+            memory
+                .get_mut(start..start + data.len())
+                .ok_or_else(|| "out of bounds memory access".to_string())?
+                .copy_from_slice(&data);
+        }
+        Ok(len)
+    }
+}
+
+impl Host for PluginHost<'_> {
+    fn call(
+        &mut self,
+        module: &str,
+        name: &str,
+        args: &[u64],
+        memory: &mut [u8],
+    ) -> Result<Vec<u64>, String> {
+        match (module, name) {
+            ("mdvim", "log") => {
+                let &[ptr, len] = args else {
+                    return Err("log takes 2 arguments".to_string());
+                };
+                let message = String::from_utf8_lossy(guest_slice(memory, ptr, len)?);
+                self.logs.push(message.into_owned());
+                Ok(Vec::new())
+            }
+            ("mdvim", "read_file") => {
+                let len = self.read_file(memory, args)?;
+                Ok(vec![u64::from(len as u32)])
+            }
+            _ => Err(tr!("Unknown host function {module}.{name}")),
+        }
+    }
+}
+
+impl LoadedPlugin {
+    /// 関数に入力を渡して呼び、出力を返す
+    fn call(
+        &self,
+        app: &AppHandle,
+        function: &str,
+        input: &[u8],
+        vault: Option<&Path>,
+    ) -> Result<Vec<u8>, String> {
+        let mut host = PluginHost {
+            manifest: &self.manifest,
+            vault,
+            logs: Vec::new(),
+        };
+        let mut instance = self.instance.lock().unwrap();
+        let result = call_guest(&mut instance, &mut host, function, input);
+        drop(instance);
+        for message in host.logs {
+            let _ = app.emit(
+                "plugin-log",
+                PluginLog {
+                    plugin: self.manifest.id.clone(),
+                    message,
+                },
+            );
+        }
+        result.map_err(|e| tr!("Plugin {} failed: {e}", self.manifest.name))
+    }
+}
+
+fn call_guest(
+    instance: &mut Instance,
+    host: &mut PluginHost,
+    function: &str,
+    input: &[u8],
+) -> Result<Vec<u8>, String> {
+    let len = input.len() as u64;
+    let ptr = *instance
+        .call("alloc", &[len], host)?
+        .first()
+        .ok_or_else(|| "alloc must return a pointer".to_string())?;
+    let start = ptr as u32 as usize;
+    instance
+        .memory_mut()
+        .get_mut(start..start + input.len())
+        .ok_or_else(|| "alloc returned an invalid pointer".to_string())?
+        .copy_from_slice(input);
+
+    let packed = *instance
+        .call(function, &[ptr, len], host)?
+        .first()
+        .ok_or_else(|| tr!("{function} must return an i64"))?;
+    let (out_ptr, out_len) = (packed >> 32, packed & 0xffff_ffff);
+    let output = guest_slice(instance.memory(), out_ptr, out_len)?.to_vec();
+    if instance.has_export("dealloc") {
+        instance.call("dealloc", &[ptr, len], host)?;
+        instance.call("dealloc", &[out_ptr, out_len], host)?;
+    }
+    Ok(output)
+}
+
+fn read_manifest(dir: &Path) -> Result<PluginManifest, String> {
+    let path = dir.join(MANIFEST_FILE);
+    let text =
+        fs::read_to_string(&path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+    serde_json::from_str(&text).map_err(|e| tr!("Invalid {}: {e}", path.display()))
+}
+
+/// プラグインフォルダ内の各プラグインのフォルダ
+fn plugin_dirs(plugins_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(plugins_dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.join(MANIFEST_FILE).is_file())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+fn load(dir: &Path, manifest: PluginManifest) -> Result<LoadedPlugin, String> {
+    let path = dir.join(&manifest.main);
+    let bytes = fs::read(&path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+    let module = wasm::Module::parse(&bytes)?;
+    // 提供していないホスト関数（WASI など）を使うモジュールは読み込まない
+    if let Some((module, name)) = module
+        .imports()
+        .find(|&(module, name)| module != "mdvim" || !matches!(name, "log" | "read_file"))
+    {
+        return Err(tr!(
+            "Plugin {} imports unsupported function {module}.{name}",
+            manifest.name
+        ));
+    }
+    let mut host = PluginHost {
+        manifest: &manifest,
+        vault: None,
+        logs: Vec::new(),
+    };
+    let instance = Instance::new(module, LIMITS, &mut host)?;
+    if !instance.has_export("alloc") {
+        return Err(tr!("Plugin {} does not export alloc", manifest.name));
+    }
+    Ok(LoadedPlugin {
+        manifest,
+        instance: Mutex::new(instance),
+    })
+}
+
+/// プラグインフォルダ内のプラグイン
+#[tauri::command]
+pub fn list_plugins(state: State<'_, AppState>, plugins_dir: String) -> Vec<PluginInfo> {
+    let loaded = state.plugins.loaded.lock().unwrap();
+    plugin_dirs(Path::new(&plugins_dir))
+        .iter()
+        .map(|dir| {
+            let (manifest, error) = match read_manifest(dir) {
+                Ok(manifest) => (Some(manifest), None),
+                Err(e) => (None, Some(e)),
+            };
+            let enabled = manifest
+                .as_ref()
+                .is_some_and(|m| loaded.iter().any(|p| p.manifest.id == m.id));
+            PluginInfo {
+                dir: path_string(dir),
+                enabled,
+                error,
+                manifest,
+            }
+        })
+        .collect()
+}
+
+/// プラグインを有効化（読み込み）または無効化
+#[tauri::command(async)]
+pub fn enable_plugin(
+    state: State<'_, AppState>,
+    plugins_dir: String,
+    id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let loaded = &state.plugins.loaded;
+    loaded.lock().unwrap().retain(|p| p.manifest.id != id);
+    if !enabled {
+        return Ok(());
+    }
+    let (dir, manifest) = plugin_dirs(Path::new(&plugins_dir))
+        .into_iter()
+        .find_map(|dir| {
+            let manifest = read_manifest(&dir).ok()?;
+            (manifest.id == id).then_some((dir, manifest))
+        })
+        .ok_or_else(|| tr!("Plugin not found: {id}"))?;
+    // 開始関数の実行中は一覧をロックしない
+    let plugin = Arc::new(load(&dir, manifest)?);
+    let mut loaded = loaded.lock().unwrap();
+    loaded.retain(|p| p.manifest.id != id);
+    loaded.push(plugin);
+    Ok(())
+}
+
+/// 有効なプラグインの変換を順に適用
+#[tauri::command(async)]
+pub fn run_plugin_transforms(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    content: String,
+    vault: Option<String>,
+) -> Result<String, String> {
+    let vault = vault.map(|v| vault::normalize(Path::new(&v)));
+    let loaded = state.plugins.loaded.lock().unwrap().clone();
+    let mut content = content;
+    for plugin in &loaded {
+        let Some(function) = plugin.manifest.transform.clone() else {
+            continue;
+        };
+        let output = plugin.call(&app, &function, content.as_bytes(), vault.as_deref())?;
+        content = String::from_utf8(output)
+            .map_err(|_| tr!("Plugin {} returned invalid UTF-8", plugin.manifest.name))?;
+    }
+    Ok(content)
+}
+
+/// プラグインのエクスポート形式で書き出す（書き出したファイルのパスを返す）
+#[tauri::command(async)]
+pub fn plugin_export(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    plugin: String,
+    format: String,
+    content: String,
+    out_path: String,
+    vault: Option<String>,
+) -> Result<String, String> {
+    let vault = vault.map(|v| vault::normalize(Path::new(&v)));
+    let loaded = state.plugins.get(&plugin)?;
+    let export_format = loaded
+        .manifest
+        .export_formats
+        .iter()
+        .find(|f| f.id == format)
+        .cloned()
+        .ok_or_else(|| tr!("Unknown export format: {format}"))?;
+    // 拡張子を省略した場合は形式の拡張子を付ける
+    let mut out = PathBuf::from(out_path);
+    if out.extension().is_none() {
+        out.set_extension(&export_format.extension);
+    }
+    let output = loaded.call(
+        &app,
+        &export_format.function,
+        content.as_bytes(),
+        vault.as_deref(),
+    )?;
+    fs::write(&out, output).map_err(|e| tr!("Failed to write {}: {e}", out.display()))?;
+    Ok(path_string(&out))
+}
+
+/// プラグインのコマンドを実行し、出力を返す
+#[tauri::command(async)]
+pub fn run_plugin_command(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    plugin: String,
+    command: String,
+    input: String,
+    vault: Option<String>,
+) -> Result<String, String> {
+    let vault = vault.map(|v| vault::normalize(Path::new(&v)));
+    let loaded = state.plugins.get(&plugin)?;
+    let function = loaded
+        .manifest
+        .commands
+        .iter()
+        .find(|c| c.id == command)
+        .map(|c| c.function.clone())
+        .ok_or_else(|| tr!("Unknown plugin command: {command}"))?;
+    let output = loaded.call(&app, &function, input.as_bytes(), vault.as_deref())?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
@@ -0,0 +1,479 @@
+// 描画前の Markdown の前処理（ショートコードの展開とユーザー定義の変換）
+//
+// 組み込みのショートコード:
+// - `{{include path.md}}` … ファイルの内容を埋め込む（パスは文書のフォルダからの相対。入れ子可）
+// - `{{date}}` `{{date:YYYY年M月D日}}` `{{date %Y/%m/%d}}` `{{time}}` … 現在の日付・時刻（書式は `dates`）
+// - `![[note]]` `![[note#見出し]]` … 他のノート（またはその見出しの節）の埋め込み
+// コードブロックとインラインコードの中は展開しない。
+//
+// 信頼していないワークスペースでは外部コマンドの前処理を行わず、ワークスペースの外のファイルは埋め込まない。
+
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::dates;
+use crate::links::{self, LinkKind, LinkRef};
+use crate::markdown;
+use crate::shell::{self, RunOptions};
+use crate::state::AppState;
+use crate::text;
+use crate::tr;
+use crate::vault::{self, Vault};
+
+static SHORTCODE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{[ \t]*(include|date|time)(?:(?:[ \t]+|[ \t]*:)([^}\n]*?))?[ \t]*\}\}").unwrap()
+});
+
+/// 入れ子の `include` の上限
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// 外部コマンドの前処理のタイムアウト（描画のたびに実行する）
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+/// 外部コマンドの出力の上限
+const MAX_OUTPUT: usize = 8 << 20;
+/// 外部コマンドのエラー出力の上限
+const MAX_STDERR: usize = 64 << 10;
+
+/// ユーザー定義の前処理
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Preprocessor {
+    /// 正規表現の置換（`replacement` では `$1` や `${name}` でキャプチャを参照できる）
+    Regex {
+        pattern: String,
+        replacement: String,
+    },
+    /// 外部コマンド（本文を標準入力に渡し、標準出力を結果とする）
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+/// 設定済みの前処理
+#[derive(Default)]
+pub struct PreprocessState {
+    preprocessors: Mutex<Vec<Preprocessor>>,
+}
+
+impl PreprocessState {
+    pub fn get(&self) -> Vec<Preprocessor> {
+        self.preprocessors.lock().unwrap().clone()
+    }
+}
+
+/// 埋め込んだ文書中の相対リンクを、埋め込み先の文書からのパスに書き換える
+fn rebase_links(content: &str, from_dir: &Path, to_dir: &Path) -> String {
+    if from_dir == to_dir {
+        return content.to_string();
+    }
+    let edits = links::extract_links(content)
+        .into_iter()
+        .filter(|link| {
+            !link.is_wiki()
+                && !link.target.is_empty()
+                && !link.target.starts_with('/')
+                && !links::is_external(&link.target)
+        })
+        .map(|link| {
+            let target = links::percent_decode(&link.target);
+            let rebased = vault::relative_path(to_dir, &from_dir.join(target));
+            let rebased = match link.kind {
+                LinkKind::Html => rebased,
+                _ => links::percent_encode(&rebased),
+            };
+            (link.target_range, rebased)
+        })
+        .collect();
+    text::apply_edits(content, edits)
+}
+
+/// 展開する記法
+enum Directive<'a> {
+    Shortcode(regex::Captures<'a>),
+    Embed(LinkRef),
+}
+
+/// ショートコードと埋め込みの展開
+struct Expander<'a> {
+    vault: Option<&'a Vault>,
+    /// `vault` が渡されなかった場合に文書のフォルダから作る索引
+    scanned: Option<Vault>,
+    /// 展開中のファイル（循環の検出用）
+    stack: Vec<PathBuf>,
+    /// `include` できる範囲（信頼したワークスペースでは制限しない）
+    root: Option<PathBuf>,
+    /// 展開したファイル
+    sources: Vec<PathBuf>,
+}
+
+impl Expander<'_> {
+    /// `path` が読める範囲の外なら、その範囲を返す
+    fn outside_root(&self, path: &Path) -> Option<&PathBuf> {
+        // シンボリックリンクで外に出ないよう、存在するファイルは解決したパスでも確かめる
+        self.root.as_ref().filter(|root| {
+            !path.starts_with(root) || (path.exists() && !vault::is_within(root, path))
+        })
+    }
+
+    fn vault(&mut self, doc: &Path) -> Option<&Vault> {
+        if self.vault.is_none() && self.scanned.is_none() {
+            self.scanned = doc.parent().map(Vault::scan);
+        }
+        self.vault.or(self.scanned.as_ref())
+    }
+
+    /// `doc` の内容 `content` を展開
+    fn expand(&mut self, content: &str, doc: Option<&Path>) -> Result<String, String> {
+        let code = markdown::code_ranges(content);
+        let mut directives: Vec<(Range<usize>, Directive)> = SHORTCODE
+            .captures_iter(content)
+            .map(|caps| (caps.get(0).unwrap().range(), Directive::Shortcode(caps)))
+            .filter(|(range, _)| !markdown::in_ranges(&code, range.start))
+            .collect();
+        for link in links::extract_links(content) {
+            if link.kind != LinkKind::Embed {
+                continue;
+            }
+            // 対象の範囲は `![[` の直後から始まる
+            let start = link.target_range.start - 3;
+            let Some(close) = content[link.target_range.end..].find("]]") else {
+                continue;
+            };
+            let end = link.target_range.end + close + 2;
+            directives.push((start..end, Directive::Embed(link)));
+        }
+        directives.sort_by_key(|(range, _)| range.start);
+
+        let dir = doc.and_then(Path::parent);
+        let mut out = String::with_capacity(content.len());
+        let mut last = 0;
+        for (range, directive) in directives {
+            if range.start < last {
+                continue;
+            }
+            let replacement = match directive {
+                Directive::Shortcode(caps) => self.shortcode(&caps, dir)?,
+                Directive::Embed(link) => match doc {
+                    Some(doc) => self.embed(content, &range, &link, doc)?,
+                    None => None,
+                },
+            };
+            let Some(replacement) = replacement else {
+                continue;
+            };
+            out.push_str(&content[last..range.start]);
+            out.push_str(&replacement);
+            last = range.end;
+        }
+        out.push_str(&content[last..]);
+        Ok(out)
+    }
+
+    fn shortcode(
+        &mut self,
+        caps: &regex::Captures,
+        dir: Option<&Path>,
+    ) -> Result<Option<String>, String> {
+        let arg = caps.get(2).map(|m| m.as_str().trim()).unwrap_or_default();
+        let replacement = match &caps[1] {
+            "date" => dates::format_now(if arg.is_empty() { "YYYY-MM-DD" } else { arg })?,
+            "time" => dates::format_now(if arg.is_empty() { "HH:mm" } else { arg })?,
+            _ => {
+                let name = arg.trim_matches(|c| c == '"' || c == '\'');
+                if name.is_empty() {
+                    return Err(tr!("include requires a file path"));
+                }
+                let path = vault::normalize(&match dir {
+                    Some(dir) => dir.join(name),
+                    None => PathBuf::from(name),
+                });
+                if let Some(root) = self.outside_root(&path) {
+                    return Err(tr!(
+                        "Workspace is not trusted: cannot include {} from outside {}",
+                        path.display(),
+                        root.display()
+                    ));
+                }
+                let content = fs::read_to_string(&path)
+                    .map_err(|e| tr!("Failed to include {}: {e}", path.display()))?;
+                self.expand_file(&path, &content, dir)?
+            }
+        };
+        Ok(Some(replacement))
+    }
+
+    /// `![[note]]` `![[note#見出し]]` をノート（またはその見出しの節）の内容に置き換える
+    ///
+    /// 見つからないノートや画像、信頼していないワークスペースの外のノートの埋め込みはそのまま残す。
+    fn embed(
+        &mut self,
+        content: &str,
+        range: &Range<usize>,
+        link: &LinkRef,
+        doc: &Path,
+    ) -> Result<Option<String>, String> {
+        let Some(path) = self
+            .vault(doc)
+            .and_then(|v| v.resolve_target(doc, &link.target, true))
+        else {
+            return Ok(None);
+        };
+        if !vault::is_markdown(&path) || self.outside_root(&path).is_some() {
+            return Ok(None);
+        }
+        let Ok(note) = fs::read_to_string(&path) else {
+            return Ok(None);
+        };
+        let section = match link.anchor.as_deref() {
+            Some(heading) => match markdown::heading_section(&note, heading) {
+                Some(section) => section,
+                None => return Ok(None),
+            },
+            None => markdown::body_start(&note)..note.len(),
+        };
+        let expanded = self.expand_file(&path, &note[section], doc.parent())?;
+        let expanded = expanded.trim();
+
+        // 行全体が埋め込みならブロックとして囲み、文中ならそのまま差し込む
+        let line_start = content[..range.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = content[range.end..]
+            .find('\n')
+            .map_or(content.len(), |i| range.end + i);
+        let own_line = line_start == range.start && content[range.end..line_end].trim().is_empty();
+        Ok(Some(if own_line {
+            format!(
+                "<div class=\"markdown-embed\" data-source=\"{}\">\n\n{expanded}\n\n</div>",
+                text::escape_html(&link.target)
+            )
+        } else {
+            expanded.to_string()
+        }))
+    }
+
+    /// `path` の内容 `content` を展開し、リンクを `into_dir` からの相対パスに直す
+    fn expand_file(
+        &mut self,
+        path: &Path,
+        content: &str,
+        into_dir: Option<&Path>,
+    ) -> Result<String, String> {
+        if self.stack.iter().any(|p| p == path) {
+            let chain: Vec<String> = self
+                .stack
+                .iter()
+                .chain(std::iter::once(&path.to_path_buf()))
+                .map(|p| {
+                    p.file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default()
+                })
+                .collect();
+            return Err(tr!("Include cycle: {}", chain.join(" → ")));
+        }
+        if self.stack.len() >= MAX_INCLUDE_DEPTH {
+            return Err(tr!("Includes are nested too deeply at {}", path.display()));
+        }
+        if !self.sources.iter().any(|p| p == path) {
+            self.sources.push(path.to_path_buf());
+        }
+        self.stack.push(path.to_path_buf());
+        let expanded = self.expand(content, Some(path));
+        self.stack.pop();
+        let expanded = expanded?;
+        Ok(match (path.parent(), into_dir) {
+            (Some(from), Some(to)) => rebase_links(&expanded, from, to),
+            _ => expanded,
+        })
+    }
+}
+
+fn run_command(
+    program: &str,
+    args: &[String],
+    content: &str,
+    doc: Option<&Path>,
+) -> Result<String, String> {
+    let mut command = Command::new(program);
+    command.args(args);
+    if let Some(doc) = doc {
+        command.env("MDVIM_FILE", vault::path_string(doc));
+        if let Some(dir) = doc.parent().filter(|d| d.is_dir()) {
+            command.current_dir(dir);
+        }
+    }
+    let output = shell::run(
+        command,
+        program,
+        RunOptions {
+            input: Some(content.as_bytes().to_vec()),
+            timeout: COMMAND_TIMEOUT,
+            max_stdout: MAX_OUTPUT,
+            max_stderr: MAX_STDERR,
+            ..Default::default()
+        },
+    )?;
+    let Some(status) = output.status else {
+        return Err(tr!(
+            "Preprocessor {program} timed out after {} s",
+            COMMAND_TIMEOUT.as_secs()
+        ));
+    };
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = match stderr.trim() {
+            "" => status.to_string(),
+            message => message.to_string(),
+        };
+        return Err(tr!("Preprocessor {program} failed: {message}"));
+    }
+    if output.stdout_truncated {
+        return Err(tr!(
+            "Preprocessor {program} output exceeded {} bytes",
+            MAX_OUTPUT
+        ));
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|_| tr!("Preprocessor {program} returned invalid UTF-8"))
+}
+
+/// ショートコードと埋め込みを展開し、設定された前処理を順に適用する
+///
+/// `vault` はウィキリンクの解決に使う（省略時は文書のフォルダ内で探す）。
+/// `trusted` が `false` なら外部コマンドの前処理はエラーにし、`include` は保管庫
+/// （なければ文書のフォルダ）の中に限る。
+pub fn preprocess(
+    content: &str,
+    doc: Option<&Path>,
+    vault: Option<&Vault>,
+    preprocessors: &[Preprocessor],
+    trusted: bool,
+) -> Result<String, String> {
+    preprocess_with_sources(content, doc, vault, preprocessors, trusted).map(|(content, _)| content)
+}
+
+/// `preprocess` と同じだが、埋め込んだファイルの一覧も返す（キャッシュの破棄用）
+pub fn preprocess_with_sources(
+    content: &str,
+    doc: Option<&Path>,
+    vault: Option<&Vault>,
+    preprocessors: &[Preprocessor],
+    trusted: bool,
+) -> Result<(String, Vec<PathBuf>), String> {
+    let doc = doc.map(vault::normalize);
+    let root = match vault {
+        _ if trusted => None,
+        Some(vault) => Some(vault::normalize(&vault.root)),
+        None => doc.as_deref().and_then(Path::parent).map(Path::to_path_buf),
+    };
+    let mut expander = Expander {
+        vault,
+        scanned: None,
+        stack: doc.iter().cloned().collect(),
+        root,
+        sources: Vec::new(),
+    };
+    let mut content = expander.expand(content, doc.as_deref())?;
+    for preprocessor in preprocessors {
+        content = match preprocessor {
+            Preprocessor::Regex {
+                pattern,
+                replacement,
+            } => {
+                let re = Regex::new(pattern)
+                    .map_err(|e| tr!("Invalid preprocessor pattern {pattern}: {e}"))?;
+                re.replace_all(&content, replacement.as_str()).into_owned()
+            }
+            Preprocessor::Command { program, .. } if !trusted => {
+                return Err(tr!(
+                    "Workspace is not trusted: preprocessor {program} is disabled"
+                ));
+            }
+            Preprocessor::Command { program, args } => {
+                run_command(program, args, &content, doc.as_deref())?
+            }
+        };
+    }
+    Ok((content, expander.sources))
+}
+
+/// 前処理を設定（以前の設定は置き換える）
+#[tauri::command]
+pub fn configure_preprocessors(
+    state: State<'_, AppState>,
+    preprocessors: Vec<Preprocessor>,
+) -> Result<(), String> {
+    // 不正な正規表現は設定時に知らせる
+    for preprocessor in &preprocessors {
+        if let Preprocessor::Regex { pattern, .. } = preprocessor {
+            Regex::new(pattern).map_err(|e| tr!("Invalid preprocessor pattern {pattern}: {e}"))?;
+        }
+    }
+    *state.preprocess.preprocessors.lock().unwrap() = preprocessors;
+    Ok(())
+}
+
+/// 描画前の Markdown を返す（プレビュー用）
+#[tauri::command(async)]
+pub fn preprocess_markdown(
+    state: State<'_, AppState>,
+    content: String,
+    path: Option<String>,
+    vault_root: Option<String>,
+) -> Result<String, String> {
+    let trusted = vault_root
+        .as_deref()
+        .or(path.as_deref())
+        .is_some_and(|p| state.trust.is_trusted(Path::new(p)));
+    let vault = vault_root.map(|root| Vault::scan(Path::new(&root)));
+    preprocess(
+        &content,
+        path.as_deref().map(Path::new),
+        vault.as_ref(),
+        &state.preprocess.get(),
+        trusted,
+    )
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::symlink;
+
+    use super::preprocess;
+    use crate::vault::Vault;
+
+    #[test]
+    fn untrusted_embeds_stay_inside_the_vault() {
+        let base = std::env::temp_dir().join(format!("mdvim-embed-{}", std::process::id()));
+        let (root, outside) = (base.join("vault"), base.join("outside"));
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(root.join("inner.md"), "inner text").unwrap();
+        fs::write(outside.join("secret.md"), "secret text").unwrap();
+        symlink(&outside, root.join("shared")).unwrap();
+        let doc = root.join("doc.md");
+        let content = "![[inner]]\n\n![[../outside/secret]]\n\n![[shared/secret]]\n";
+        fs::write(&doc, content).unwrap();
+        let vault = Vault::scan(&root);
+
+        let untrusted = preprocess(content, Some(&doc), Some(&vault), &[], false).unwrap();
+        assert!(untrusted.contains("inner text"));
+        assert!(untrusted.contains("![[../outside/secret]]"));
+        assert!(untrusted.contains("![[shared/secret]]"));
+        assert!(!untrusted.contains("secret text"));
+
+        let trusted = preprocess(content, Some(&doc), Some(&vault), &[], true).unwrap();
+        assert_eq!(trusted.matches("secret text").count(), 2);
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,383 @@
+// アプリ内プレビューの描画（HTML と見出しの一覧）
+
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::annotations::{self, Rendering};
+use crate::markdown;
+use crate::preprocess::{preprocess_with_sources, Preprocessor};
+use crate::render::{self, HeadingNumbering};
+use crate::render_profile::RenderProfile;
+use crate::state::AppState;
+use crate::text::LineIndex;
+use crate::vault::{self, Vault};
+
+/// キャッシュする描画結果の数
+const CACHE_CAPACITY: usize = 32;
+
+/// プレビューの設定
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PreviewOptions {
+    /// 見出しに番号を振る
+    pub heading_numbering: Option<HeadingNumbering>,
+    /// 未解決の注釈の番号と一覧を入れる
+    pub annotations: bool,
+}
+
+/// 目次に表示する見出し
+#[derive(Debug, Clone, Serialize)]
+pub struct OutlineHeading {
+    pub level: usize,
+    pub text: String,
+    /// プレビューの見出しの `id`
+    pub anchor: String,
+    /// 自動番号（無効なら `None`）
+    pub number: Option<String>,
+    /// エディタ上の行番号（1 始まり）
+    pub line: usize,
+}
+
+/// 描画結果
+#[derive(Debug, Clone, Serialize)]
+pub struct ParseResult {
+    pub html: String,
+    pub headings: Vec<OutlineHeading>,
+}
+
+/// キャッシュの項目
+struct CacheEntry {
+    result: ParseResult,
+    /// 最後に使った順番（小さいものから捨てる）
+    used: u64,
+    /// 文書自身と、埋め込んだノートや描画で読んだ画像・データのファイル（読んだときの更新日時と一緒に）
+    sources: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+/// キャッシュの統計（デバッグ用）
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ParseCacheStats {
+    pub entries: usize,
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// キャッシュしている HTML の合計の大きさ
+    pub bytes: usize,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    entries: HashMap<u64, CacheEntry>,
+    clock: u64,
+    stats: ParseCacheStats,
+}
+
+/// 更新日時（ファイルがなければ `None`）
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// 描画結果の LRU キャッシュ（内容・パス・設定のハッシュをキーにする）
+///
+/// 前処理の前に引けるよう、キーには前処理の結果ではなく入力を使う。埋め込んだノートの変更が
+/// 反映されるように、ファイルを保存したらそのファイルを使った描画結果を破棄する。アプリの外で
+/// 変更された画像やデータも反映されるよう、引くときに使ったファイルの更新日時も確かめる。
+#[derive(Default)]
+pub struct ParseCache {
+    inner: Mutex<CacheInner>,
+}
+
+impl ParseCache {
+    fn get(&self, key: u64) -> Option<ParseResult> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.clock += 1;
+        let clock = inner.clock;
+        let stale = inner.entries.get(&key).is_some_and(|entry| {
+            entry
+                .sources
+                .iter()
+                .any(|(path, modified)| modified_time(path) != *modified)
+        });
+        if stale {
+            inner.entries.remove(&key);
+        }
+        match inner.entries.get_mut(&key) {
+            Some(entry) => {
+                entry.used = clock;
+                let result = entry.result.clone();
+                inner.stats.hits += 1;
+                Some(result)
+            }
+            None => {
+                inner.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&self, key: u64, result: ParseResult, sources: Vec<PathBuf>) {
+        let sources = sources
+            .into_iter()
+            .map(|path| {
+                let modified = modified_time(&path);
+                (path, modified)
+            })
+            .collect();
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.entries.contains_key(&key) && inner.entries.len() >= CACHE_CAPACITY {
+            let oldest = inner
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.used)
+                .map(|(k, _)| *k);
+            if let Some(oldest) = oldest {
+                inner.entries.remove(&oldest);
+                inner.stats.evictions += 1;
+            }
+        }
+        inner.clock += 1;
+        let used = inner.clock;
+        inner.entries.insert(
+            key,
+            CacheEntry {
+                result,
+                used,
+                sources,
+            },
+        );
+    }
+
+    /// `path` を描画・埋め込みに使った結果を捨てる（保存したときなど）
+    pub fn invalidate(&self, path: &Path) {
+        let path = vault::normalize(path);
+        self.inner
+            .lock()
+            .unwrap()
+            .entries
+            .retain(|_, entry| !entry.sources.iter().any(|(p, _)| *p == path));
+    }
+
+    /// キャッシュを捨てる（図の設定を変えたときなど）
+    pub fn clear(&self) {
+        self.inner.lock().unwrap().entries.clear();
+    }
+
+    pub fn stats(&self) -> ParseCacheStats {
+        let inner = self.inner.lock().unwrap();
+        ParseCacheStats {
+            entries: inner.entries.len(),
+            capacity: CACHE_CAPACITY,
+            bytes: inner.entries.values().map(|e| e.result.html.len()).sum(),
+            ..inner.stats.clone()
+        }
+    }
+}
+
+/// 描画結果を左右するものすべてのハッシュ
+#[allow(clippy::too_many_arguments)]
+fn cache_key(
+    content: &str,
+    path: Option<&Path>,
+    vault_root: Option<&str>,
+    preprocessors: &[Preprocessor],
+    profile: &RenderProfile,
+    options: &PreviewOptions,
+    trusted: bool,
+    annotations: Option<&str>,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    trusted.hash(&mut hasher);
+    annotations.hash(&mut hasher);
+    content.hash(&mut hasher);
+    path.hash(&mut hasher);
+    vault_root.hash(&mut hasher);
+    serde_json::to_string(preprocessors)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    serde_json::to_string(profile)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    serde_json::to_string(&options.heading_numbering)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 見出しの一覧（行番号は編集中の内容のもの）
+pub fn outline(content: &str, numbering: Option<&HeadingNumbering>) -> Vec<OutlineHeading> {
+    let headings = markdown::headings(content);
+    let slugs = markdown::heading_slugs(&headings);
+    let levels: Vec<usize> = headings.iter().map(|h| h.level).collect();
+    let numbers = match numbering {
+        Some(numbering) => numbering.numbers(&levels),
+        None => vec![None; headings.len()],
+    };
+    let index = LineIndex::new(content);
+    headings
+        .into_iter()
+        .zip(slugs)
+        .zip(numbers)
+        .map(|((heading, anchor), number)| OutlineHeading {
+            level: heading.level,
+            line: index.line_of(heading.range.start),
+            text: heading.text,
+            anchor,
+            number,
+        })
+        .collect()
+}
+
+/// 編集中の Markdown をプレビュー用の HTML にする（同じ内容と設定ならキャッシュを使う）
+pub fn parse(
+    state: &AppState,
+    content: &str,
+    path: Option<&str>,
+    vault_root: Option<&str>,
+    options: &PreviewOptions,
+) -> Result<ParseResult, String> {
+    let path = path.map(|p| vault::normalize(Path::new(p)));
+    let profile = state.profiles.resolve(content, path.as_deref());
+    // 信頼していないワークスペースでは外部コマンドと外部の図のレンダラーを使わない
+    let trusted = vault_root
+        .map(Path::new)
+        .or(path.as_deref())
+        .is_some_and(|p| state.trust.is_trusted(p));
+    let annotations = match path.as_deref() {
+        Some(path) if options.annotations => {
+            Rendering::prepare(content, &annotations::located(path, content))
+        }
+        _ => None,
+    };
+    let preprocessors = state.preprocess.get();
+    let key = cache_key(
+        content,
+        path.as_deref(),
+        vault_root,
+        &preprocessors,
+        &profile,
+        options,
+        trusted,
+        annotations.as_ref().map(|a| a.list.as_str()),
+    );
+    if let Some(result) = state.parse_cache.get(key) {
+        return Ok(result);
+    }
+    // ボールトの走査と前処理はキャッシュにないときだけ
+    let vault = vault_root.map(|root| Vault::scan(Path::new(root)));
+    let (body, mut sources) = preprocess_with_sources(
+        annotations.as_ref().map_or(content, |a| a.content.as_str()),
+        path.as_deref(),
+        vault.as_ref(),
+        &preprocessors,
+        trusted,
+    )?;
+    let read_files = RefCell::new(Vec::new());
+    let html = render::render_html_with(
+        &body,
+        &render::RenderOptions {
+            heading_numbering: options.heading_numbering.as_ref(),
+            diagrams: trusted.then_some(&state.diagrams),
+            base_dir: path.as_deref().and_then(Path::parent),
+            profile: Some(&profile),
+            read_files: Some(&read_files),
+            ..Default::default()
+        },
+    );
+    let html = match &annotations {
+        Some(annotations) => annotations.replace_markers(&html) + &annotations.list,
+        None => html,
+    };
+    let result = ParseResult {
+        html,
+        headings: outline(content, options.heading_numbering.as_ref()),
+    };
+    sources.extend(path);
+    for file in read_files.into_inner() {
+        let file = vault::normalize(&file);
+        if !sources.contains(&file) {
+            sources.push(file);
+        }
+    }
+    state.parse_cache.insert(key, result.clone(), sources);
+    Ok(result)
+}
+
+/// 編集中の Markdown をプレビュー用の HTML にする
+///
+/// 図のレンダラーや前処理のコマンドを待つことがあるので、UI のスレッドでは実行しない。
+#[tauri::command(async)]
+pub fn parse_markdown(
+    state: State<'_, AppState>,
+    content: String,
+    path: Option<String>,
+    vault_root: Option<String>,
+    options: Option<PreviewOptions>,
+) -> Result<ParseResult, String> {
+    parse(
+        &state,
+        &content,
+        path.as_deref(),
+        vault_root.as_deref(),
+        &options.unwrap_or_default(),
+    )
+}
+
+/// 描画結果のキャッシュの統計
+#[tauri::command]
+pub fn get_parse_cache_stats(state: State<'_, AppState>) -> ParseCacheStats {
+    state.parse_cache.stats()
+}
+
+/// 描画結果のキャッシュを捨てる
+#[tauri::command]
+pub fn clear_parse_cache(state: State<'_, AppState>) {
+    state.parse_cache.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::time::{Duration, SystemTime};
+
+    use super::{ParseCache, ParseResult};
+
+    #[test]
+    fn drops_entries_whose_sources_changed_on_disk() {
+        let dir = std::env::temp_dir().join(format!("mdvim-preview-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (image, missing) = (dir.join("image.svg"), dir.join("later.csv"));
+        fs::write(&image, "<svg/>").unwrap();
+        let result = || ParseResult {
+            html: "<p>cached</p>".to_string(),
+            headings: Vec::new(),
+        };
+        let cache = ParseCache::default();
+
+        cache.insert(1, result(), vec![image.clone()]);
+        assert!(cache.get(1).is_some());
+        File::options()
+            .write(true)
+            .open(&image)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        assert!(cache.get(1).is_none());
+
+        // 読めなかったファイルができたときも作り直す
+        cache.insert(2, result(), vec![missing.clone()]);
+        assert!(cache.get(2).is_some());
+        fs::write(&missing, "a,b\n1,2\n").unwrap();
+        assert!(cache.get(2).is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,579 @@
+// 外部ブラウザ向けのプレビューサーバー（Server-Sent Events でライブ更新）
+//
+// URL の最初の部分は起動ごとに OS の乱数から作るトークンで、それがないリクエストには応じない。
+// DNS リバインディングを防ぐため、`Host` が `localhost` か IP アドレス（LAN に公開しなければループバック）
+// でなければ拒否する。
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{IpAddr, Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::collab;
+use crate::export;
+use crate::links;
+use crate::preprocess::preprocess;
+use crate::render;
+use crate::state::AppState;
+use crate::tr;
+use crate::vault::{self, Vault};
+use crate::watcher;
+
+/// ブラウザ側でライブ更新を受け取るスクリプト
+const LIVE_RELOAD_SCRIPT: &str = r#"<script>
+const source = new EventSource('events');
+source.addEventListener('update', (e) => {
+  document.getElementById('mdvim-preview').innerHTML = e.data;
+});
+source.addEventListener('title', (e) => { document.title = e.data; });
+</script>"#;
+
+/// SSE 接続を維持するためのコメントを送る間隔
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+/// ワークスペースの変更を確かめる間隔
+const VAULT_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+/// 同時に扱う接続の上限（ライブ更新を受け取っているものを含む）
+const MAX_CONNECTIONS: usize = 32;
+/// 1 回の送信の待ち時間（超えた接続は切る）
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 配信中の文書
+#[derive(Default)]
+struct Document {
+    /// 画像などの相対パスの基準
+    dir: Option<PathBuf>,
+    title: String,
+    html: String,
+    version: u64,
+    /// ワークスペースを信頼しているか（リンク先のノートの展開にも使う）
+    trusted: bool,
+    /// 反映した `update_preview` の番号
+    update: u64,
+}
+
+/// 配信に使うワークスペースの索引（ファイルが変わったら次の更新で作り直す）
+struct VaultCache {
+    root: PathBuf,
+    vault: Option<Arc<Vault>>,
+    /// 監視が変更を見つけたら立てる
+    stale: Arc<AtomicBool>,
+    /// 破棄すると監視が終わる
+    _stop: Sender<()>,
+}
+
+impl VaultCache {
+    fn watch(root: PathBuf) -> Self {
+        let stale = Arc::new(AtomicBool::new(false));
+        let (stop, stopped) = mpsc::channel();
+        let (watch_root, watch_stale) = (root.clone(), stale.clone());
+        thread::spawn(move || {
+            watcher::watch(&watch_root, None, VAULT_WATCH_INTERVAL, &stopped, |_| {
+                watch_stale.store(true, Ordering::SeqCst)
+            });
+        });
+        Self {
+            root,
+            vault: None,
+            stale,
+            _stop: stop,
+        }
+    }
+}
+
+/// サーバーのスレッド間で共有する状態
+#[derive(Default)]
+struct Shared {
+    document: Mutex<Document>,
+    changed: Condvar,
+    stopped: AtomicBool,
+    theme: Mutex<String>,
+    /// URL の最初の部分
+    token: String,
+    /// LAN に公開しているか
+    lan: bool,
+    vault: Mutex<Option<VaultCache>>,
+    /// 始まった `update_preview` の数
+    updates: AtomicU64,
+    /// 処理中の接続の数
+    active: AtomicUsize,
+}
+
+struct Server {
+    shared: Arc<Shared>,
+    addr: SocketAddr,
+}
+
+/// プレビューサーバーの状態
+#[derive(Default)]
+pub struct PreviewServerState {
+    server: Mutex<Option<Server>>,
+}
+
+/// 起動したサーバーの URL
+#[derive(Debug, Serialize)]
+pub struct PreviewServerInfo {
+    pub url: String,
+    /// 同じネットワークの端末から開く URL（LAN に公開した場合）
+    pub lan_url: Option<String>,
+    pub port: u16,
+}
+
+/// 同じネットワークから見たこのマシンのアドレス（実際には送信しない）
+pub fn lan_address() -> Option<Ipv4Addr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9)).ok()?;
+    match socket.local_addr().ok()?.ip() {
+        IpAddr::V4(ip) if !ip.is_loopback() && !ip.is_unspecified() => Some(ip),
+        _ => None,
+    }
+}
+
+pub fn content_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" | "md" | "markdown" => "text/plain; charset=utf-8",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "bmp" => "image/bmp",
+        "pdf" => "application/pdf",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8], head: bool) {
+    let header = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    let _ = stream.write_all(header.as_bytes());
+    if !head {
+        let _ = stream.write_all(body);
+    }
+}
+
+/// SSE の 1 イベント（改行を含むデータは複数の `data:` 行に分ける）
+fn sse_event(name: &str, data: &str) -> String {
+    let mut event = format!("event: {name}\n");
+    for line in data.split('\n') {
+        event.push_str("data: ");
+        event.push_str(line.trim_end_matches('\r'));
+        event.push('\n');
+    }
+    event.push('\n');
+    event
+}
+
+/// HTML ページ（`live` なら編集中の文書としてライブ更新を受け取る）
+fn page(shared: &Shared, content: &str, title: &str, live: bool) -> String {
+    let theme = shared.theme.lock().unwrap().clone();
+    let script = if live { LIVE_RELOAD_SCRIPT } else { "" };
+    let body = format!("<main id=\"mdvim-preview\">\n{content}</main>\n{script}");
+    export::html_document(title, &body, &theme)
+}
+
+/// 文書が更新されるたびに本文を送り続ける
+fn stream_events(shared: &Shared, mut stream: TcpStream) {
+    let header = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n";
+    if stream.write_all(header.as_bytes()).is_err() {
+        return;
+    }
+    let mut sent = 0;
+    let mut last_write = Instant::now();
+    loop {
+        let document = shared.document.lock().unwrap();
+        let (document, _) = shared
+            .changed
+            .wait_timeout_while(document, Duration::from_secs(1), |d| {
+                d.version == sent && !shared.stopped.load(Ordering::SeqCst)
+            })
+            .unwrap();
+        if shared.stopped.load(Ordering::SeqCst) {
+            return;
+        }
+        let message = if document.version != sent {
+            sent = document.version;
+            let mut message = sse_event("title", &document.title);
+            message.push_str(&sse_event("update", &document.html));
+            Some(message)
+        } else if last_write.elapsed() >= KEEP_ALIVE {
+            Some(": keep-alive\n\n".to_string())
+        } else {
+            None
+        };
+        drop(document);
+        if let Some(message) = message {
+            if stream.write_all(message.as_bytes()).is_err() {
+                return;
+            }
+            last_write = Instant::now();
+        }
+    }
+}
+
+/// `Host` ヘッダーがこのサーバーを指しているか（ドメイン名は `localhost` だけ認める）
+fn is_allowed_host(shared: &Shared, host: &str, port: u16) -> bool {
+    let (name, host_port) = match host.rsplit_once(':') {
+        Some((name, p)) if !p.contains(']') => (name, p.parse::<u16>().ok()),
+        _ => (host, Some(80)),
+    };
+    if host_port != Some(port) {
+        return false;
+    }
+    if name.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    let name = name.trim_start_matches('[').trim_end_matches(']');
+    match name.parse::<IpAddr>() {
+        Ok(ip) => ip.is_loopback() || shared.lan,
+        Err(_) => false,
+    }
+}
+
+fn handle_connection(app: &AppHandle, shared: &Shared, mut stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(10)));
+    // 受け取らなくなったブラウザが接続の枠を持ち続けないよう、送信も待ちすぎない
+    let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+    let mut reader = BufReader::new(match stream.try_clone() {
+        Ok(s) => s,
+        Err(_) => return,
+    });
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    // `Host` 以外のヘッダーは使わないので読み捨てる
+    let mut host = None;
+    let mut line = String::new();
+    while reader.read_line(&mut line).is_ok_and(|n| n > 0) && line.trim_end() != "" {
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_string());
+            }
+        }
+        line.clear();
+    }
+    let port = stream.local_addr().map(|a| a.port()).unwrap_or(0);
+    if !host.is_some_and(|host| is_allowed_host(shared, &host, port)) {
+        respond(
+            &mut stream,
+            "403 Forbidden",
+            "text/plain",
+            b"Forbidden",
+            false,
+        );
+        return;
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or("/"));
+    let head = method == "HEAD";
+    if method != "GET" && !head {
+        respond(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Method Not Allowed",
+            head,
+        );
+        return;
+    }
+    let path = target.split(['?', '#']).next().unwrap_or("/");
+    let Some(path) = path
+        .strip_prefix('/')
+        .and_then(|p| p.strip_prefix(shared.token.as_str()))
+        .filter(|p| p.is_empty() || p.starts_with('/'))
+    else {
+        respond(
+            &mut stream,
+            "404 Not Found",
+            "text/plain",
+            b"Not Found",
+            head,
+        );
+        return;
+    };
+    if path.is_empty() {
+        // 相対パスが解決できるよう、トークンの後に `/` を付ける
+        let header = format!(
+            "HTTP/1.1 301 Moved Permanently\r\nLocation: /{}/\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            shared.token
+        );
+        let _ = stream.write_all(header.as_bytes());
+        return;
+    }
+
+    match path {
+        "/" | "/index.html" => {
+            let document = shared.document.lock().unwrap();
+            let html = page(shared, &document.html, &document.title, true);
+            drop(document);
+            respond(
+                &mut stream,
+                "200 OK",
+                "text/html; charset=utf-8",
+                html.as_bytes(),
+                head,
+            );
+        }
+        "/events" => stream_events(shared, stream),
+        _ => {
+            // 文書のフォルダ内のファイル（他のノートは HTML に変換して返す。`.git` などの隠しファイルは返さない）
+            let (dir, trusted) = {
+                let document = shared.document.lock().unwrap();
+                (document.dir.clone(), document.trusted)
+            };
+            let relative = links::percent_decode(path.trim_start_matches('/'));
+            let file = dir.as_ref().map(|d| vault::normalize(&d.join(&relative)));
+            match file {
+                Some(file)
+                    if dir.as_ref().is_some_and(|d| {
+                        vault::is_within(d, &file)
+                            && file.strip_prefix(d).is_ok_and(|rel| !vault::is_hidden(rel))
+                    }) && file.is_file() =>
+                {
+                    if vault::is_markdown(&file) {
+                        let content = fs::read_to_string(&file).unwrap_or_default();
+                        // リンク先のノートは組み込みのショートコードだけ展開する
+                        let body = preprocess(&content, Some(&file), None, &[], trusted)
+                            .unwrap_or_else(|_| content.clone());
+                        let profile = app
+                            .state::<AppState>()
+                            .profiles
+                            .resolve(&content, Some(&file));
+                        let rendered = render::render_html_with(
+                            &body,
+                            &render::RenderOptions {
+                                profile: Some(&profile),
+                                ..Default::default()
+                            },
+                        );
+                        let html = page(
+                            shared,
+                            &rendered,
+                            &export::document_title(&file, &content),
+                            false,
+                        );
+                        respond(
+                            &mut stream,
+                            "200 OK",
+                            "text/html; charset=utf-8",
+                            html.as_bytes(),
+                            head,
+                        );
+                    } else {
+                        let body = fs::read(&file).unwrap_or_default();
+                        respond(&mut stream, "200 OK", content_type(&file), &body, head);
+                    }
+                }
+                _ => respond(
+                    &mut stream,
+                    "404 Not Found",
+                    "text/plain",
+                    b"Not Found",
+                    head,
+                ),
+            }
+        }
+    }
+}
+
+/// `root` の索引（変更がなければ前回のもの。読み込みはロックの外で行う）
+fn cached_vault(shared: &Shared, root: &Path) -> Arc<Vault> {
+    let root = vault::normalize(root);
+    {
+        let mut cache = shared.vault.lock().unwrap();
+        if cache.as_ref().is_none_or(|c| c.root != root) {
+            *cache = Some(VaultCache::watch(root.clone()));
+        }
+        if let Some(cache) = cache.as_ref() {
+            // 読み直している間の変更は次の更新で反映する
+            let stale = cache.stale.swap(false, Ordering::SeqCst);
+            if let (false, Some(vault)) = (stale, &cache.vault) {
+                return vault.clone();
+            }
+        }
+    }
+    let vault = Arc::new(Vault::scan(&root));
+    if let Some(cache) = shared
+        .vault
+        .lock()
+        .unwrap()
+        .as_mut()
+        .filter(|c| c.root == root)
+    {
+        cache.vault = Some(vault.clone());
+    }
+    vault
+}
+
+fn stop(state: &PreviewServerState) {
+    if let Some(server) = state.server.lock().unwrap().take() {
+        server.shared.stopped.store(true, Ordering::SeqCst);
+        server.shared.changed.notify_all();
+        // accept() の待機を解くため自分に接続する
+        let _ = TcpStream::connect_timeout(
+            &SocketAddr::from((Ipv4Addr::LOCALHOST, server.addr.port())),
+            Duration::from_millis(200),
+        );
+    }
+}
+
+/// プレビューサーバーを起動（`lan` が真なら同じネットワークの端末からも接続できる。URL にはトークンを含む）
+#[tauri::command]
+pub fn start_preview_server(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    port: Option<u16>,
+    lan: Option<bool>,
+    theme: Option<String>,
+) -> Result<PreviewServerInfo, String> {
+    stop(&state.preview_server);
+    let lan = lan.unwrap_or(false);
+    let host = if lan {
+        Ipv4Addr::UNSPECIFIED
+    } else {
+        Ipv4Addr::LOCALHOST
+    };
+    let listener = TcpListener::bind((host, port.unwrap_or(0)))
+        .map_err(|e| tr!("Failed to start preview server: {e}"))?;
+    let addr = listener
+        .local_addr()
+        .map_err(|e| tr!("Failed to start preview server: {e}"))?;
+
+    let token = format!(
+        "{:016x}{:016x}",
+        collab::random_u64()?,
+        collab::random_u64()?
+    );
+    let shared = Arc::new(Shared {
+        theme: Mutex::new(theme.unwrap_or_else(|| "light".to_string())),
+        token: token.clone(),
+        lan,
+        ..Default::default()
+    });
+    let accept_shared = shared.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            if accept_shared.stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            let Ok(stream) = stream else {
+                continue;
+            };
+            if accept_shared.active.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+                let _ = stream.shutdown(Shutdown::Both);
+                continue;
+            }
+            accept_shared.active.fetch_add(1, Ordering::SeqCst);
+            let (app, shared) = (app.clone(), accept_shared.clone());
+            thread::spawn(move || {
+                handle_connection(&app, &shared, stream);
+                shared.active.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+
+    let port = addr.port();
+    *state.preview_server.server.lock().unwrap() = Some(Server { shared, addr });
+    Ok(PreviewServerInfo {
+        url: format!("http://localhost:{port}/{token}/"),
+        lan_url: lan
+            .then(lan_address)
+            .flatten()
+            .map(|ip| format!("http://{ip}:{port}/{token}/")),
+        port,
+    })
+}
+
+/// 配信する文書を更新（接続中のブラウザに本文を送る）
+///
+/// ワークスペースの索引は監視して変更があったときだけ作り直す。
+#[tauri::command(async)]
+pub fn update_preview(
+    state: State<'_, AppState>,
+    content: String,
+    path: Option<String>,
+    vault_root: Option<String>,
+    theme: Option<String>,
+) -> Result<(), String> {
+    let shared = match state.preview_server.server.lock().unwrap().as_ref() {
+        Some(server) => server.shared.clone(),
+        None => return Err(tr!("Preview server is not running")),
+    };
+    let update = shared.updates.fetch_add(1, Ordering::SeqCst) + 1;
+    if let Some(theme) = theme {
+        *shared.theme.lock().unwrap() = theme;
+    }
+    let path = path.map(|p| vault::normalize(Path::new(&p)));
+    let vault = vault_root.map(|root| cached_vault(&shared, Path::new(&root)));
+    let profile = state.profiles.resolve(&content, path.as_deref());
+    let trusted = vault
+        .as_ref()
+        .map(|v| v.root.as_path())
+        .or(path.as_deref())
+        .is_some_and(|p| state.trust.is_trusted(p));
+    let body = preprocess(
+        &content,
+        path.as_deref(),
+        vault.as_deref(),
+        &state.preprocess.get(),
+        trusted,
+    )?;
+    let html = render::render_html_with(
+        &body,
+        &render::RenderOptions {
+            diagrams: trusted.then_some(&state.diagrams),
+            base_dir: path.as_deref().and_then(Path::parent),
+            profile: Some(&profile),
+            ..Default::default()
+        },
+    );
+
+    let mut document = shared.document.lock().unwrap();
+    // 後から始まった更新が先に反映されていれば、古い内容で上書きしない
+    if document.update > update {
+        return Ok(());
+    }
+    document.update = update;
+    document.title = match &path {
+        Some(path) => export::document_title(path, &content),
+        None => "mdvim".to_string(),
+    };
+    document.dir = path.and_then(|p| p.parent().map(Path::to_path_buf));
+    document.html = html;
+    document.trusted = trusted;
+    document.version += 1;
+    shared.changed.notify_all();
+    Ok(())
+}
+
+/// プレビューサーバーを停止
+#[tauri::command]
+pub fn stop_preview_server(state: State<'_, AppState>) {
+    stop(&state.preview_server);
+}
@@ -0,0 +1,457 @@
+// 文章の読みやすさ・文体分析
+
+use serde::{Deserialize, Serialize};
+
+use crate::markdown;
+use crate::text::is_cjk;
+
+const EN_WEASEL_WORDS: &[&str] = &[
+    "many",
+    "various",
+    "very",
+    "fairly",
+    "several",
+    "extremely",
+    "exceedingly",
+    "quite",
+    "remarkably",
+    "few",
+    "surprisingly",
+    "mostly",
+    "largely",
+    "huge",
+    "tiny",
+    "excellent",
+    "interestingly",
+    "significantly",
+    "substantially",
+    "clearly",
+    "vast",
+    "relatively",
+    "completely",
+    "basically",
+    "arguably",
+    "virtually",
+    "really",
+];
+
+const JA_WEASEL_WORDS: &[&str] = &[
+    "非常に",
+    "かなり",
+    "とても",
+    "様々な",
+    "いくつかの",
+    "多くの",
+    "ほとんど",
+    "おそらく",
+    "かもしれない",
+    "と思われる",
+    "ある程度",
+    "基本的に",
+    "一般的に",
+];
+
+const EN_BE_VERBS: &[&str] = &["am", "is", "are", "was", "were", "be", "been", "being"];
+
+const EN_IRREGULAR_PARTICIPLES: &[&str] = &[
+    "awoken",
+    "been",
+    "born",
+    "beaten",
+    "become",
+    "begun",
+    "bent",
+    "bound",
+    "bitten",
+    "blown",
+    "broken",
+    "brought",
+    "built",
+    "bought",
+    "caught",
+    "chosen",
+    "come",
+    "cut",
+    "dealt",
+    "done",
+    "drawn",
+    "driven",
+    "eaten",
+    "fallen",
+    "fed",
+    "felt",
+    "fought",
+    "found",
+    "forbidden",
+    "forgotten",
+    "forgiven",
+    "frozen",
+    "given",
+    "gone",
+    "grown",
+    "heard",
+    "hidden",
+    "hit",
+    "held",
+    "hurt",
+    "kept",
+    "known",
+    "laid",
+    "led",
+    "left",
+    "lent",
+    "let",
+    "lost",
+    "made",
+    "meant",
+    "met",
+    "paid",
+    "put",
+    "read",
+    "ridden",
+    "run",
+    "said",
+    "seen",
+    "sold",
+    "sent",
+    "set",
+    "shaken",
+    "shown",
+    "shut",
+    "spoken",
+    "spent",
+    "stolen",
+    "struck",
+    "sung",
+    "sunk",
+    "taken",
+    "taught",
+    "thrown",
+    "told",
+    "thought",
+    "understood",
+    "woken",
+    "worn",
+    "won",
+    "written",
+];
+
+const JA_PASSIVE_ENDINGS: &[&str] = &["される", "された", "されて", "られる", "られた", "られて"];
+
+/// 分析オプション（未指定の項目は言語ごとの既定値）
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ProseOptions {
+    /// 曖昧語のリスト
+    pub weasel_words: Option<Vec<String>>,
+    /// 受動態とみなす過去分詞（英語）または語尾（日本語）のリスト
+    pub passive_words: Option<Vec<String>>,
+    /// 長文とみなす文の長さ（英語は語数、日本語は文字数）
+    pub long_sentence_threshold: Option<usize>,
+}
+
+/// 文体上の指摘
+#[derive(Debug, Serialize)]
+pub struct ProseFlag {
+    /// "passive" | "weasel" | "long_sentence"
+    pub kind: String,
+    pub line: usize,
+    /// 該当箇所のテキスト
+    pub text: String,
+    pub sentence: String,
+}
+
+/// 英語の読みやすさ指標
+#[derive(Debug, Serialize)]
+pub struct Readability {
+    pub syllable_count: usize,
+    pub flesch_reading_ease: f64,
+    pub flesch_kincaid_grade: f64,
+}
+
+/// 文の長さのヒストグラムの区間（`max` が `None` の区間は上限なし）
+#[derive(Debug, Serialize)]
+pub struct HistogramBucket {
+    pub min: usize,
+    pub max: Option<usize>,
+    pub count: usize,
+}
+
+/// 文章分析の結果
+#[derive(Debug, Serialize)]
+pub struct ProseReport {
+    pub lang: String,
+    pub sentence_count: usize,
+    /// 英語は語数、日本語は文字数
+    pub word_count: usize,
+    pub average_sentence_length: f64,
+    pub readability: Option<Readability>,
+    pub histogram: Vec<HistogramBucket>,
+    pub flags: Vec<ProseFlag>,
+}
+
+/// 本文中の 1 文
+pub struct Sentence {
+    /// 文の先頭の行番号（1 始まり）
+    pub line: usize,
+    pub text: String,
+}
+
+/// 本文の文字種から言語（"ja" / "en"）を判定
+pub fn detect_lang(blocks: &[markdown::ProseBlock]) -> &'static str {
+    let (mut cjk, mut letters) = (0usize, 0usize);
+    for c in blocks.iter().flat_map(|b| b.text.chars()) {
+        if is_cjk(c) {
+            cjk += 1;
+            letters += 1;
+        } else if c.is_alphabetic() {
+            letters += 1;
+        }
+    }
+    if letters > 0 && cjk * 10 >= letters * 3 {
+        "ja"
+    } else {
+        "en"
+    }
+}
+
+/// ブロックを文に分割
+pub fn split_sentences(block: &markdown::ProseBlock, ja: bool) -> Vec<Sentence> {
+    const ABBREVIATIONS: &[&str] = &["mr", "mrs", "ms", "dr", "e.g", "i.e", "etc", "vs", "cf"];
+
+    let mut sentences = Vec::new();
+    let mut line = block.line;
+    let mut start_line = line;
+    let mut buf = String::new();
+    let chars: Vec<char> = block.text.chars().collect();
+    let mut i = 0;
+
+    let push = |buf: &mut String, start_line: usize, sentences: &mut Vec<Sentence>| {
+        let text = buf
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(if ja { "" } else { " " });
+        if !text.is_empty() {
+            sentences.push(Sentence {
+                line: start_line,
+                text,
+            });
+        }
+        buf.clear();
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        if buf.trim().is_empty() {
+            start_line = line;
+        }
+        if c == '\n' {
+            line += 1;
+        }
+        buf.push(c);
+        let terminal = match c {
+            '。' | '！' | '？' => true,
+            '.' | '!' | '?' => {
+                let at_break = chars.get(i + 1).is_none_or(|n| n.is_whitespace());
+                let word = buf
+                    .trim_end_matches('.')
+                    .rsplit(|ch: char| ch.is_whitespace())
+                    .next()
+                    .unwrap_or("")
+                    .to_lowercase();
+                at_break && !(c == '.' && ABBREVIATIONS.contains(&word.as_str()))
+            }
+            _ => false,
+        };
+        if terminal {
+            // 閉じ括弧・引用符は文に含める
+            while let Some(&next) = chars.get(i + 1) {
+                if matches!(next, '」' | '』' | '）' | ')' | '"' | '\'' | '”' | '’') {
+                    buf.push(next);
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            push(&mut buf, start_line, &mut sentences);
+        }
+        i += 1;
+    }
+    push(&mut buf, start_line, &mut sentences);
+    sentences
+}
+
+fn words(sentence: &str) -> Vec<String> {
+    sentence
+        .split_whitespace()
+        .map(|w| {
+            w.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'')
+                .to_lowercase()
+        })
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+fn count_syllables(word: &str) -> usize {
+    let chars: Vec<char> = word.chars().filter(|c| c.is_ascii_alphabetic()).collect();
+    if chars.is_empty() {
+        return 0;
+    }
+    let is_vowel = |c: char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+    let mut count = 0;
+    let mut prev_vowel = false;
+    for &c in &chars {
+        let vowel = is_vowel(c);
+        if vowel && !prev_vowel {
+            count += 1;
+        }
+        prev_vowel = vowel;
+    }
+    // 語末の黙字 e（-le は除く）
+    let n = chars.len();
+    if n > 2 && chars[n - 1] == 'e' && chars[n - 2] != 'l' && !is_vowel(chars[n - 2]) {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+fn ja_length(sentence: &str) -> usize {
+    sentence
+        .chars()
+        .filter(|c| c.is_alphanumeric() || is_cjk(*c))
+        .count()
+}
+
+fn histogram(lengths: &[usize], width: usize) -> Vec<HistogramBucket> {
+    const BUCKETS: usize = 8;
+    let mut buckets: Vec<HistogramBucket> = (0..BUCKETS)
+        .map(|i| HistogramBucket {
+            min: i * width + 1,
+            max: (i + 1 < BUCKETS).then_some((i + 1) * width),
+            count: 0,
+        })
+        .collect();
+    for &len in lengths {
+        let i = (len.saturating_sub(1) / width).min(BUCKETS - 1);
+        buckets[i].count += 1;
+    }
+    buckets
+}
+
+/// Markdown 文書の読みやすさと文体を分析（コードブロックは対象外）
+#[tauri::command]
+pub fn analyze_prose(
+    content: String,
+    lang: Option<String>,
+    options: Option<ProseOptions>,
+) -> ProseReport {
+    let options = options.unwrap_or_default();
+    let blocks = markdown::prose_blocks(&content);
+    let lang = match lang.as_deref() {
+        Some("ja") => "ja",
+        Some("en") => "en",
+        _ => detect_lang(&blocks),
+    };
+    let ja = lang == "ja";
+
+    let sentences: Vec<Sentence> = blocks.iter().flat_map(|b| split_sentences(b, ja)).collect();
+
+    let weasel_words: Vec<String> = options.weasel_words.unwrap_or_else(|| {
+        let defaults = if ja { JA_WEASEL_WORDS } else { EN_WEASEL_WORDS };
+        defaults.iter().map(|w| w.to_string()).collect()
+    });
+    let passive_words: Vec<String> = options.passive_words.unwrap_or_else(|| {
+        let defaults = if ja {
+            JA_PASSIVE_ENDINGS
+        } else {
+            EN_IRREGULAR_PARTICIPLES
+        };
+        defaults.iter().map(|w| w.to_string()).collect()
+    });
+    let long_threshold = options
+        .long_sentence_threshold
+        .unwrap_or(if ja { 80 } else { 25 });
+
+    let mut flags = Vec::new();
+    let mut lengths = Vec::with_capacity(sentences.len());
+    let mut syllables = 0;
+
+    for sentence in &sentences {
+        let flag = |kind: &str, text: &str| ProseFlag {
+            kind: kind.to_string(),
+            line: sentence.line,
+            text: text.to_string(),
+            sentence: sentence.text.clone(),
+        };
+
+        let length = if ja {
+            for word in weasel_words
+                .iter()
+                .filter(|w| sentence.text.contains(w.as_str()))
+            {
+                flags.push(flag("weasel", word));
+            }
+            for ending in passive_words
+                .iter()
+                .filter(|w| sentence.text.contains(w.as_str()))
+            {
+                flags.push(flag("passive", ending));
+            }
+            ja_length(&sentence.text)
+        } else {
+            let tokens = words(&sentence.text);
+            syllables += tokens.iter().map(|w| count_syllables(w)).sum::<usize>();
+            for token in tokens.iter().filter(|t| weasel_words.contains(t)) {
+                flags.push(flag("weasel", token));
+            }
+            for (i, token) in tokens.iter().enumerate() {
+                if !EN_BE_VERBS.contains(&token.as_str()) {
+                    continue;
+                }
+                // "was quickly written" のように副詞を 1 語挟む形も対象
+                let mut j = i + 1;
+                if tokens.get(j).is_some_and(|t| t.ends_with("ly")) {
+                    j += 1;
+                }
+                if let Some(next) = tokens.get(j) {
+                    if (next.ends_with("ed") && next.len() > 3) || passive_words.contains(next) {
+                        flags.push(flag("passive", &tokens[i..=j].join(" ")));
+                    }
+                }
+            }
+            tokens.len()
+        };
+
+        if length > long_threshold {
+            flags.push(flag("long_sentence", &length.to_string()));
+        }
+        lengths.push(length);
+    }
+
+    let sentence_count = sentences.len();
+    let word_count: usize = lengths.iter().sum();
+    let average_sentence_length = if sentence_count > 0 {
+        word_count as f64 / sentence_count as f64
+    } else {
+        0.0
+    };
+    let readability = (!ja && word_count > 0).then(|| {
+        let words_per_sentence = average_sentence_length;
+        let syllables_per_word = syllables as f64 / word_count as f64;
+        Readability {
+            syllable_count: syllables,
+            flesch_reading_ease: 206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word,
+            flesch_kincaid_grade: 0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59,
+        }
+    });
+
+    ProseReport {
+        lang: lang.to_string(),
+        sentence_count,
+        word_count,
+        average_sentence_length,
+        readability,
+        histogram: histogram(&lengths, if ja { 20 } else { 5 }),
+        flags,
+    }
+}
@@ -0,0 +1,314 @@
+// 音声メモの録音（OS の録音ツールで WAV に録り、文書の assets フォルダに添付する）
+
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use serde::Deserialize;
+use tauri::State;
+
+use crate::attachments;
+use crate::capture::CapturedAttachment;
+use crate::shell::{self, RunOptions};
+use crate::state::AppState;
+use crate::text::escape_html;
+use crate::tr;
+use crate::vault::{self, path_string};
+
+/// 録音ツールの終了を待つ時間
+const STOP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Opus への変換を待つ時間
+const ENCODE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 取り込むエラー出力の上限
+const MAX_STDERR: usize = 64 << 10;
+
+/// 保存する形式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    #[default]
+    Wav,
+    /// Ogg Opus（録音後に ffmpeg または opusenc で変換する）
+    Opus,
+}
+
+/// 録音中のプロセス
+struct Recording {
+    child: Child,
+    tool: &'static str,
+    /// 録音中の WAV（一時ファイル）
+    path: PathBuf,
+    format: AudioFormat,
+    stamp: String,
+}
+
+/// 録音の状態（同時に 1 つだけ）
+#[derive(Default)]
+pub struct RecordingState {
+    current: Mutex<Option<Recording>>,
+}
+
+/// 録音に使うコマンドの候補（見つかった最初のものを使う）
+fn recorders(output: &Path) -> Vec<(&'static str, Command)> {
+    let command = |program: &str, args: &[&str]| {
+        let mut command = Command::new(program);
+        command.args(args).arg(output);
+        command
+    };
+    if cfg!(target_os = "macos") {
+        return vec![
+            (
+                "ffmpeg",
+                command(
+                    "ffmpeg",
+                    &[
+                        "-loglevel",
+                        "error",
+                        "-y",
+                        "-f",
+                        "avfoundation",
+                        "-i",
+                        ":0",
+                        "-ac",
+                        "1",
+                    ],
+                ),
+            ),
+            ("sox", command("sox", &["-q", "-d", "-c", "1"])),
+        ];
+    }
+    if cfg!(windows) {
+        return vec![(
+            "sox",
+            command("sox", &["-q", "-t", "waveaudio", "default", "-c", "1"]),
+        )];
+    }
+    // Linux（PipeWire・PulseAudio・ALSA の順に試す）
+    vec![
+        (
+            "pw-record",
+            command("pw-record", &["--rate", "48000", "--channels", "1"]),
+        ),
+        (
+            "parecord",
+            command("parecord", &["--file-format=wav", "--channels=1"]),
+        ),
+        (
+            "arecord",
+            command(
+                "arecord",
+                &["-q", "-f", "S16_LE", "-r", "48000", "-c", "1", "-t", "wav"],
+            ),
+        ),
+        (
+            "ffmpeg",
+            command(
+                "ffmpeg",
+                &[
+                    "-loglevel",
+                    "error",
+                    "-y",
+                    "-f",
+                    "pulse",
+                    "-i",
+                    "default",
+                    "-ac",
+                    "1",
+                ],
+            ),
+        ),
+    ]
+}
+
+/// 強制終了などで壊れた WAV のヘッダーの長さをファイルの大きさに合わせる
+fn fix_wav_header(path: &Path) -> Result<(), String> {
+    let mut data = fs::read(path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+    if data.len() < 12 || &data[..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return Ok(());
+    }
+    let mut at = 12;
+    while at + 8 <= data.len() {
+        let size = u32::from_le_bytes(data[at + 4..at + 8].try_into().unwrap()) as usize;
+        if &data[at..at + 4] == b"data" {
+            let actual = (data.len() - at - 8) as u32;
+            let riff = (data.len() - 8) as u32;
+            if size as u32 == actual && data[4..8] == riff.to_le_bytes() {
+                return Ok(());
+            }
+            data[4..8].copy_from_slice(&riff.to_le_bytes());
+            data[at + 4..at + 8].copy_from_slice(&actual.to_le_bytes());
+            return fs::write(path, data)
+                .map_err(|e| tr!("Failed to write {}: {e}", path.display()));
+        }
+        at += 8 + size + size % 2;
+    }
+    Ok(())
+}
+
+/// 中断のシグナルを送る（Unix 以外では止める）
+fn interrupt(child: &mut Child) {
+    #[cfg(unix)]
+    // SAFETY: `kill` は引数を読むだけで、起動した録音ツールだけに送る
+    unsafe {
+        libc::kill(child.id() as libc::pid_t, libc::SIGINT);
+    }
+    #[cfg(not(unix))]
+    let _ = child.kill();
+}
+
+/// 録音ツールを止める（ヘッダーを書き終えるように、中断のシグナルか `q` を送る）
+fn stop(recording: &mut Recording) {
+    let child = &mut recording.child;
+    if recording.tool == "ffmpeg" {
+        if let Some(stdin) = child.stdin.as_mut() {
+            let _ = stdin.write_all(b"q");
+        }
+    } else {
+        interrupt(child);
+    }
+    let started = Instant::now();
+    while started.elapsed() < STOP_TIMEOUT {
+        if !matches!(child.try_wait(), Ok(None)) {
+            return;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// WAV を Opus に変換する
+fn encode_opus(input: &Path, output: &Path) -> Result<(), String> {
+    let mut ffmpeg = Command::new("ffmpeg");
+    ffmpeg
+        .args(["-loglevel", "error", "-y", "-i"])
+        .arg(input)
+        .args(["-c:a", "libopus", "-b:a", "32k"])
+        .arg(output);
+    let mut opusenc = Command::new("opusenc");
+    opusenc
+        .args(["--quiet", "--bitrate", "32"])
+        .arg(input)
+        .arg(output);
+    for (name, command) in [("ffmpeg", ffmpeg), ("opusenc", opusenc)] {
+        let options = RunOptions {
+            timeout: ENCODE_TIMEOUT,
+            max_stdout: MAX_STDERR,
+            max_stderr: MAX_STDERR,
+            ..Default::default()
+        };
+        let Some(result) = shell::run_if_found(command, name, options)? else {
+            continue;
+        };
+        return match result.status {
+            Some(status) if status.success() => Ok(()),
+            Some(_) => Err(tr!(
+                "{name} failed: {}",
+                String::from_utf8_lossy(&result.stderr).trim()
+            )),
+            None => Err(tr!(
+                "{} timed out after {} s",
+                name,
+                ENCODE_TIMEOUT.as_secs()
+            )),
+        };
+    }
+    Err(tr!("Opus encoding requires ffmpeg or opusenc"))
+}
+
+/// マイクからの録音を始める（`stop_audio_recording` で文書に添付する）
+#[tauri::command]
+pub fn start_audio_recording(
+    state: State<'_, AppState>,
+    format: Option<AudioFormat>,
+) -> Result<(), String> {
+    let mut current = state.recording.current.lock().unwrap();
+    if current.is_some() {
+        return Err(tr!("Already recording"));
+    }
+    let stamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let path = std::env::temp_dir().join(format!(
+        "mdvim-recording-{}-{stamp}.wav",
+        std::process::id()
+    ));
+    let mut failure = None;
+    for (tool, mut command) in recorders(&path) {
+        let mut child = match command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(tr!("Failed to run {tool}: {e}")),
+        };
+        // デバイスを開けずにすぐ終了した場合は次を試す
+        thread::sleep(Duration::from_millis(200));
+        if let Ok(Some(status)) = child.try_wait() {
+            failure = Some(format!("{tool} exited with {status}"));
+            continue;
+        }
+        *current = Some(Recording {
+            child,
+            tool,
+            path,
+            format: format.unwrap_or_default(),
+            stamp,
+        });
+        return Ok(());
+    }
+    Err(failure.unwrap_or_else(|| {
+        "No audio recorder found (install PipeWire, PulseAudio, ALSA utils, SoX or ffmpeg)"
+            .to_string()
+    }))
+}
+
+/// 録音を止めて文書の assets フォルダに保存し、埋め込み用の `<audio>` を返す
+#[tauri::command]
+pub fn stop_audio_recording(
+    state: State<'_, AppState>,
+    document_path: String,
+) -> Result<CapturedAttachment, String> {
+    let mut recording = state
+        .recording
+        .current
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| tr!("Not recording"))?;
+    stop(&mut recording);
+    if !recording.path.is_file() {
+        return Err(tr!("Recording produced no audio"));
+    }
+    fix_wav_header(&recording.path)?;
+
+    let doc = vault::normalize(Path::new(&document_path));
+    let dir = attachments::assets_dir(&doc);
+    fs::create_dir_all(&dir).map_err(|e| tr!("Failed to create {}: {e}", dir.display()))?;
+    let extension = match recording.format {
+        AudioFormat::Wav => "wav",
+        AudioFormat::Opus => "opus",
+    };
+    let target = dir.join(format!("recording-{}.{extension}", recording.stamp));
+    let result = match recording.format {
+        AudioFormat::Wav => fs::copy(&recording.path, &target)
+            .map(|_| ())
+            .map_err(|e| tr!("Failed to write {}: {e}", target.display())),
+        AudioFormat::Opus => encode_opus(&recording.path, &target),
+    };
+    let _ = fs::remove_file(&recording.path);
+    result?;
+    let src = escape_html(&attachments::link_target(&doc, &target));
+    Ok(CapturedAttachment {
+        path: path_string(&target),
+        markdown: format!("<audio controls src=\"{src}\"></audio>"),
+    })
+}
@@ -0,0 +1,328 @@
+// ワークスペース全体にわたるリンクの書き換え（ファイル名変更など）
+
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::annotations;
+use crate::links;
+use crate::markdown;
+use crate::text;
+use crate::tr;
+use crate::vault::{self, path_string, Vault};
+
+/// リンクを書き換えた文書
+#[derive(Debug, Serialize)]
+pub struct UpdatedFile {
+    pub path: String,
+    pub replacements: usize,
+}
+
+/// ファイル名変更の結果
+#[derive(Debug, Serialize)]
+pub struct RenameResult {
+    pub old_path: String,
+    pub new_path: String,
+    pub updated_files: Vec<UpdatedFile>,
+}
+
+/// 見出し名変更の結果
+#[derive(Debug, Serialize)]
+pub struct RenameHeadingResult {
+    pub old_anchor: String,
+    pub new_anchor: String,
+    pub updated_files: Vec<UpdatedFile>,
+}
+
+/// 書き換え後の文書内容
+pub struct Rewrite {
+    /// 書き込み先（移動した文書は移動後のパス）
+    pub path: PathBuf,
+    pub content: String,
+    pub replacements: usize,
+}
+
+pub fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))
+}
+
+/// 内容が変わらない置換を除いて適用
+fn rewrite(path: PathBuf, content: &str, edits: Vec<(Range<usize>, String)>) -> Option<Rewrite> {
+    let edits: Vec<_> = edits
+        .into_iter()
+        .filter(|(range, replacement)| content[range.clone()] != *replacement)
+        .collect();
+    (!edits.is_empty()).then(|| Rewrite {
+        path,
+        replacements: edits.len(),
+        content: text::apply_edits(content, edits),
+    })
+}
+
+/// `old_path` を指すリンクを `new_path` へ書き換える（`old_path` 自身の文書は対象外）
+pub fn rewrite_references(
+    vault: &Vault,
+    old_path: &Path,
+    new_path: &Path,
+) -> Result<Vec<Rewrite>, String> {
+    let mut rewrites = Vec::new();
+    for doc in vault.markdown_files().filter(|doc| *doc != old_path) {
+        let content = read(doc)?;
+        let edits = links::extract_links(&content)
+            .into_iter()
+            .filter(|link| !link.target.is_empty())
+            .filter(|link| vault.resolve(doc, link).as_deref() == Some(old_path))
+            .map(|link| {
+                let replacement = vault.link_text(doc, &link, new_path);
+                (link.target_range, replacement)
+            })
+            .collect();
+        rewrites.extend(rewrite(doc.clone(), &content, edits));
+    }
+    Ok(rewrites)
+}
+
+/// 移動する文書自身の相対リンクを、移動後の位置から同じファイルを指すように書き換える
+///
+/// 自分自身へのリンクはウィキリンクも移動後の名前にする。
+fn rewrite_moved_document(
+    vault: &Vault,
+    old_path: &Path,
+    new_path: &Path,
+) -> Result<Option<Rewrite>, String> {
+    let content = read(old_path)?;
+    let edits = links::extract_links(&content)
+        .into_iter()
+        .filter(|link| !link.target.is_empty())
+        .filter_map(|link| {
+            let target = vault.resolve(old_path, &link)?;
+            if link.is_wiki() && target != old_path {
+                return None;
+            }
+            let target = if target == old_path {
+                new_path.to_path_buf()
+            } else {
+                target
+            };
+            let replacement = vault.link_text(new_path, &link, &target);
+            Some((link.target_range, replacement))
+        })
+        .collect();
+    Ok(rewrite(new_path.to_path_buf(), &content, edits))
+}
+
+/// 書き込む前の内容に戻す
+fn restore(written: &[(PathBuf, Vec<u8>)]) {
+    for (path, original) in written.iter().rev() {
+        let _ = fs::write(path, original);
+    }
+}
+
+/// 書き換えた内容を保存（途中で失敗したら、それまでに書いたファイルを元に戻す）
+pub fn write_rewrites(rewrites: Vec<Rewrite>) -> Result<Vec<UpdatedFile>, String> {
+    let mut written = Vec::new();
+    let mut updated = Vec::new();
+    for r in rewrites {
+        let original = match fs::read(&r.path) {
+            Ok(original) => original,
+            Err(e) => {
+                restore(&written);
+                return Err(tr!("Failed to read {}: {e}", r.path.display()));
+            }
+        };
+        written.push((r.path.clone(), original));
+        if let Err(e) = fs::write(&r.path, &r.content) {
+            restore(&written);
+            return Err(tr!("Failed to write {}: {e}", r.path.display()));
+        }
+        updated.push(UpdatedFile {
+            path: path_string(&r.path),
+            replacements: r.replacements,
+        });
+    }
+    Ok(updated)
+}
+
+/// ファイルを移動しつつ、ワークスペース内のウィキリンク・相対リンクを更新
+pub fn move_with_link_update(
+    vault: &Vault,
+    old_path: &Path,
+    new_path: &Path,
+) -> Result<Vec<UpdatedFile>, String> {
+    if !old_path.is_file() {
+        return Err(tr!("File not found: {}", old_path.display()));
+    }
+    if new_path.exists() {
+        return Err(tr!("File already exists: {}", new_path.display()));
+    }
+
+    let mut rewrites = rewrite_references(vault, old_path, new_path)?;
+    if vault::is_markdown(old_path) {
+        rewrites.extend(rewrite_moved_document(vault, old_path, new_path)?);
+    }
+
+    if let Some(parent) = new_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| tr!("Failed to create {}: {e}", parent.display()))?;
+    }
+    fs::rename(old_path, new_path).map_err(|e| {
+        tr!(
+            "Failed to move {} to {}: {e}",
+            old_path.display(),
+            new_path.display()
+        )
+    })?;
+    // リンクを書き換えられなければ移動も取り消す
+    let result =
+        annotations::move_sidecar(old_path, new_path).and_then(|_| write_rewrites(rewrites));
+    if result.is_err() {
+        let _ = annotations::move_sidecar(new_path, old_path);
+        let _ = fs::rename(new_path, old_path);
+    }
+    result
+}
+
+/// Markdown ファイルの名前を変更し、他の文書からのリンクを書き換える
+#[tauri::command(async)]
+pub fn rename_with_link_update(
+    old_path: String,
+    new_path: String,
+    vault_root: String,
+) -> Result<RenameResult, String> {
+    let vault = Vault::scan(Path::new(&vault_root));
+    let old = vault::normalize(Path::new(&old_path));
+    let new = vault::normalize(Path::new(&new_path));
+    let updated_files = move_with_link_update(&vault, &old, &new)?;
+    Ok(RenameResult {
+        old_path: path_string(&old),
+        new_path: path_string(&new),
+        updated_files,
+    })
+}
+
+/// 見出しテキストの照合（前後の空白と大文字小文字を無視）
+fn same_heading(a: &str, b: &str) -> bool {
+    a.trim().to_lowercase() == b.trim().to_lowercase()
+}
+
+/// 見出しの変更で変わるアンカー（変更前 → 変更後）
+///
+/// 変更した見出しだけでなく、重複見出しの連番（`-1` など）がずれた見出しも含む。
+fn anchor_changes(before: &str, after: &str) -> HashMap<String, String> {
+    let old = markdown::heading_slugs(&markdown::headings(before));
+    let new = markdown::heading_slugs(&markdown::headings(after));
+    old.into_iter()
+        .zip(new)
+        .filter(|(old, new)| old != new)
+        .collect()
+}
+
+/// 見出しの名前を変更し、ワークスペース内の `file.md#anchor` / `[[file#見出し]]` リンクを更新
+#[tauri::command(async)]
+pub fn rename_heading(
+    vault_root: String,
+    file: String,
+    old_heading: String,
+    new_text: String,
+) -> Result<RenameHeadingResult, String> {
+    let vault = Vault::scan(Path::new(&vault_root));
+    let file = vault::normalize(Path::new(&file));
+    let old_heading = old_heading.trim_start_matches('#').trim();
+    let new_text = new_text.trim();
+    if new_text.is_empty() {
+        return Err(tr!("Heading text must not be empty"));
+    }
+
+    let content = read(&file)?;
+    let headings = markdown::headings(&content);
+    let index = headings
+        .iter()
+        .position(|h| same_heading(&h.text, old_heading))
+        .ok_or_else(|| tr!("Heading not found: {old_heading}"))?;
+    let old_anchor = markdown::heading_slugs(&headings)[index].clone();
+
+    let renamed = text::apply_edits(
+        &content,
+        vec![(headings[index].text_range.clone(), new_text.to_string())],
+    );
+    let new_anchor = markdown::heading_slugs(&markdown::headings(&renamed))
+        .get(index)
+        .cloned()
+        .unwrap_or_default();
+    let changes = anchor_changes(&content, &renamed);
+
+    let mut docs: Vec<PathBuf> = vault.markdown_files().cloned().collect();
+    if !docs.contains(&file) {
+        docs.push(file.clone());
+    }
+
+    let mut rewrites = Vec::new();
+    for doc in &docs {
+        let source = if *doc == file {
+            renamed.clone()
+        } else {
+            read(doc)?
+        };
+        let edits = links::extract_links(&source)
+            .into_iter()
+            .filter(|link| vault.resolve(doc, link).as_deref() == Some(file.as_path()))
+            .filter_map(|link| {
+                let (anchor, range) = (link.anchor.as_deref()?, link.anchor_range.clone()?);
+                if link.is_wiki() {
+                    same_heading(anchor, old_heading).then(|| (range, new_text.to_string()))
+                } else {
+                    let anchor = links::percent_decode(anchor).to_lowercase();
+                    changes.get(&anchor).map(|new| (range, new.clone()))
+                }
+            })
+            .collect();
+        match rewrite(doc.clone(), &source, edits) {
+            Some(r) => rewrites.push(r),
+            // 見出しだけを変更した文書
+            None if *doc == file => rewrites.push(Rewrite {
+                path: file.clone(),
+                content: renamed.clone(),
+                replacements: 0,
+            }),
+            None => {}
+        }
+    }
+
+    Ok(RenameHeadingResult {
+        old_anchor,
+        new_anchor,
+        updated_files: write_rewrites(rewrites)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::anchor_changes;
+
+    #[test]
+    fn duplicate_heading_suffixes_shift() {
+        let before = "# Intro\n## Usage\n# Intro\n# Intro\n";
+        let after = "# Overview\n## Usage\n# Intro\n# Intro\n";
+        let expected: HashMap<String, String> = [
+            ("intro", "overview"),
+            ("intro-1", "intro"),
+            ("intro-2", "intro-1"),
+        ]
+        .into_iter()
+        .map(|(old, new)| (old.to_string(), new.to_string()))
+        .collect();
+        assert_eq!(anchor_changes(before, after), expected);
+
+        // 後ろの見出しを変えても前の見出しのアンカーは変わらない
+        let after = "# Intro\n## Usage\n# Intro\n# Usage\n";
+        let expected: HashMap<String, String> = [("intro-2".to_string(), "usage-1".to_string())]
+            .into_iter()
+            .collect();
+        assert_eq!(anchor_changes(before, after), expected);
+    }
+}
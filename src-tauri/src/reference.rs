@@ -0,0 +1,134 @@
+// 参照リンク・脚注の定義と参照箇所の間の移動
+
+use std::ops::Range;
+
+use pulldown_cmark::{Event, LinkType, Parser, Tag};
+use serde::Serialize;
+
+use crate::lsp::LspRange;
+use crate::markdown;
+use crate::text::{self, LineIndex};
+
+/// 移動先
+#[derive(Debug, Serialize)]
+pub struct ReferenceLocation {
+    /// "definition" | "reference"
+    pub kind: String,
+    pub range: LspRange,
+}
+
+/// 参照の種類
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Link,
+    Footnote,
+}
+
+struct Item {
+    kind: Kind,
+    label: String,
+    range: Range<usize>,
+    definition: bool,
+}
+
+/// ラベルの照合キー（大文字小文字と空白の違いを無視する）
+fn label_key(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// 定義の `[label]` の部分
+fn label_range(content: &str, range: &Range<usize>) -> Range<usize> {
+    let end = content[range.clone()]
+        .find("]:")
+        .map_or(range.end, |i| range.start + i + 1);
+    let start = range.start
+        + (content[range.start..end].len() - content[range.start..end].trim_start().len());
+    start..end
+}
+
+fn items(content: &str) -> Vec<Item> {
+    let mut items = Vec::new();
+    let mut parser = Parser::new_ext(content, markdown::parser_options()).into_offset_iter();
+    for (event, range) in parser.by_ref() {
+        match event {
+            Event::Start(
+                Tag::Link {
+                    link_type: LinkType::Reference | LinkType::Collapsed | LinkType::Shortcut,
+                    id,
+                    ..
+                }
+                | Tag::Image {
+                    link_type: LinkType::Reference | LinkType::Collapsed | LinkType::Shortcut,
+                    id,
+                    ..
+                },
+            ) => items.push(Item {
+                kind: Kind::Link,
+                label: label_key(&id),
+                range,
+                definition: false,
+            }),
+            Event::FootnoteReference(label) => items.push(Item {
+                kind: Kind::Footnote,
+                label: label_key(&label),
+                range,
+                definition: false,
+            }),
+            Event::Start(Tag::FootnoteDefinition(label)) => items.push(Item {
+                kind: Kind::Footnote,
+                label: label_key(&label),
+                range: label_range(content, &range),
+                definition: true,
+            }),
+            _ => {}
+        }
+    }
+    for (label, def) in parser.reference_definitions().iter() {
+        items.push(Item {
+            kind: Kind::Link,
+            label: label_key(label),
+            range: label_range(content, &def.span),
+            definition: true,
+        });
+    }
+    items
+}
+
+/// カーソル（エディタの UTF-16 オフセット）が参照の上なら定義を、定義の上ならすべての参照箇所を返す
+#[tauri::command]
+pub fn resolve_reference(content: String, offset: usize) -> Vec<ReferenceLocation> {
+    let offset = text::byte_offset(&content, offset);
+    let items = items(&content);
+    // 入れ子（画像を含むリンクなど）は内側を優先する
+    let Some(current) = items
+        .iter()
+        .filter(|i| i.range.start <= offset && offset < i.range.end.max(i.range.start + 1))
+        .min_by_key(|i| i.range.len())
+    else {
+        return Vec::new();
+    };
+    let index = LineIndex::new(&content);
+    let mut targets: Vec<&Item> = items
+        .iter()
+        .filter(|i| {
+            i.kind == current.kind && i.label == current.label && i.definition != current.definition
+        })
+        .collect();
+    targets.sort_by_key(|i| i.range.start);
+    targets
+        .into_iter()
+        .map(|i| ReferenceLocation {
+            kind: if i.definition {
+                "definition"
+            } else {
+                "reference"
+            }
+            .to_string(),
+            range: LspRange::from_offsets(&index, &content, i.range.clone()),
+        })
+        .collect()
+}
@@ -0,0 +1,363 @@
+// 期日のあるタスクの通知（設定した時刻に、今日が期日か期日を過ぎた未完了のタスクを知らせる）
+//
+// 通知のプラグイン（tauri-plugin-notification）はビルドの依存に含まれていないため、
+// 読み上げ（`tts`）と同じくプラットフォームのコマンドで OS の通知を出し、`reminder` イベントも送る。
+// 期日はファイルの更新日時が変わったときだけ読み直す（保管庫ごとの索引）。
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::app_data;
+use crate::markdown;
+use crate::semantic::modified_secs;
+use crate::state::AppState;
+use crate::tr;
+use crate::vault::{path_string, Vault};
+
+/// 設定フォルダ内のファイル
+const REMINDERS_FILE: &str = "reminders.json";
+/// データフォルダ内のファイル（延期と最後に確認した時刻）
+const LOG_FILE: &str = "reminder-log.json";
+/// 確認の間隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// 個別に通知する数（それより多ければまとめて 1 件にする）
+const MAX_SEPARATE: usize = 3;
+
+/// 通知の設定
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReminderConfig {
+    /// 保管庫のルートごとの有効・無効（載っていない保管庫は通知しない）
+    pub vaults: BTreeMap<String, bool>,
+    /// 毎日通知する時刻（`HH:MM`）
+    pub times: Vec<String>,
+    /// 期日を過ぎた未完了のタスクも通知する
+    pub include_overdue: bool,
+    /// `snooze_reminder` の既定の分数
+    pub snooze_minutes: u64,
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        Self {
+            vaults: BTreeMap::new(),
+            times: vec!["09:00".to_string()],
+            include_overdue: true,
+            snooze_minutes: 15,
+        }
+    }
+}
+
+/// 延期と最後に確認した時刻（UNIX 秒）
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct ReminderLog {
+    /// タスク（`key`）ごとの延期の期限
+    snoozed: HashMap<String, i64>,
+    last_check: Option<i64>,
+}
+
+/// 索引の 1 タスク
+#[derive(Debug, Clone)]
+struct DueTask {
+    line: usize,
+    text: String,
+    due: NaiveDate,
+}
+
+/// 通知の状態
+#[derive(Default)]
+pub struct ReminderState {
+    config: Mutex<ReminderConfig>,
+    log: Mutex<ReminderLog>,
+    /// ファイルごとの更新日時と未完了で期日のあるタスク
+    index: Mutex<HashMap<PathBuf, (u64, Vec<DueTask>)>>,
+}
+
+/// `reminder` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct Reminder {
+    pub path: String,
+    pub line: usize,
+    pub text: String,
+    /// 期日（`YYYY-MM-DD`）
+    pub due: String,
+    pub overdue: bool,
+}
+
+/// 延期の対象を表すキー（行はずれるのでパスとテキストで区別する）
+fn key(path: &str, text: &str) -> String {
+    format!("{path}\n{text}")
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| tr!("Invalid time: {}", time))
+}
+
+/// 日付と時刻のローカル時刻の UNIX 秒
+fn timestamp(date: NaiveDate, time: NaiveTime) -> Option<i64> {
+    Local
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .map(|t| t.timestamp())
+}
+
+fn save_log(app: &AppHandle, log: &ReminderLog) {
+    if let Ok(file) = app_data::data_file(app, LOG_FILE) {
+        let _ = app_data::write_json(&file, log);
+    }
+}
+
+/// 保管庫の未完了で期日のあるタスク（更新されたファイルだけ読み直す）
+fn due_tasks(state: &ReminderState, root: &Path) -> Vec<(PathBuf, DueTask)> {
+    let vault = Vault::scan(root);
+    let mut index = state.index.lock().unwrap();
+    let mut tasks = Vec::new();
+    for doc in vault.markdown_files() {
+        let modified = modified_secs(doc);
+        let cached = index.get(doc).filter(|(m, _)| *m == modified);
+        let entries = match cached {
+            Some((_, entries)) => entries.clone(),
+            None => {
+                let Ok(content) = fs::read_to_string(doc) else {
+                    continue;
+                };
+                let entries: Vec<DueTask> = markdown::tasks(&content)
+                    .into_iter()
+                    .filter(|t| !t.checked)
+                    .filter_map(|t| {
+                        Some(DueTask {
+                            due: t.due?,
+                            line: t.line,
+                            text: t.text,
+                        })
+                    })
+                    .collect();
+                index.insert(doc.clone(), (modified, entries.clone()));
+                entries
+            }
+        };
+        tasks.extend(entries.into_iter().map(|t| (doc.clone(), t)));
+    }
+    tasks
+}
+
+/// OS の通知を出す（失敗しても何もしない）
+fn notify(title: &str, body: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let quote = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut c = Command::new("osascript");
+        c.args([
+            "-e",
+            &format!(
+                "display notification \"{}\" with title \"{}\"",
+                quote(body),
+                quote(title)
+            ),
+        ]);
+        c
+    } else if cfg!(target_os = "windows") {
+        let quote = |s: &str| s.replace('\'', "''");
+        let script = format!(
+            "Add-Type -AssemblyName System.Windows.Forms; \
+             $n = New-Object System.Windows.Forms.NotifyIcon; \
+             $n.Icon = [System.Drawing.SystemIcons]::Information; $n.Visible = $true; \
+             $n.ShowBalloonTip(10000, '{}', '{}', 'Info'); Start-Sleep -Seconds 10; $n.Dispose()",
+            quote(title),
+            quote(body)
+        );
+        let mut c = Command::new("powershell");
+        c.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+        c
+    } else {
+        let mut c = Command::new("notify-send");
+        // `-` で始まる本文をオプションとみなさないよう `--` で区切る
+        c.args(["--app-name=mdvim", "--", title, body]);
+        c
+    };
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    // 終わるのを待って回収する（ゾンビにしない）
+    if let Ok(mut child) = child {
+        thread::spawn(move || {
+            let _ = child.wait();
+        });
+    }
+}
+
+/// 通知してイベントを送る
+fn fire(app: &AppHandle, reminders: &[Reminder]) {
+    if reminders.len() > MAX_SEPARATE {
+        let body: Vec<&str> = reminders
+            .iter()
+            .take(MAX_SEPARATE)
+            .map(|r| r.text.as_str())
+            .collect();
+        notify(&tr!("{} tasks are due", reminders.len()), &body.join("\n"));
+    } else {
+        for reminder in reminders {
+            let title = if reminder.overdue {
+                tr!("Task overdue ({})", reminder.due)
+            } else {
+                tr!("Task due today")
+            };
+            notify(&title, &reminder.text);
+        }
+    }
+    for reminder in reminders {
+        let _ = app.emit("reminder", reminder.clone());
+    }
+}
+
+/// `since` より後、`now` までに通知の時刻か延期の期限が来ていれば通知する
+fn check(app: &AppHandle, since: i64, now: i64) {
+    let state = app.state::<AppState>();
+    let reminders = &state.reminders;
+    let config = reminders.config.lock().unwrap().clone();
+    let today = Local::now().date_naive();
+    let slot = config
+        .times
+        .iter()
+        .filter_map(|t| parse_time(t).ok())
+        .filter_map(|t| timestamp(today, t))
+        .any(|t| since < t && t <= now);
+    let expired: Vec<String> = {
+        let mut log = reminders.log.lock().unwrap();
+        let expired = log
+            .snoozed
+            .iter()
+            .filter(|(_, &until)| until <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        log.snoozed.retain(|_, until| *until > now);
+        expired
+    };
+    if !slot && expired.is_empty() {
+        return;
+    }
+
+    let mut due = Vec::new();
+    for (root, _) in config.vaults.iter().filter(|(_, enabled)| **enabled) {
+        for (path, task) in due_tasks(reminders, Path::new(root)) {
+            let overdue = task.due < today;
+            if task.due > today || overdue && !config.include_overdue {
+                continue;
+            }
+            let path = path_string(&path);
+            let key = key(&path, &task.text);
+            let snoozed = reminders.log.lock().unwrap().snoozed.contains_key(&key);
+            if snoozed || !slot && !expired.contains(&key) {
+                continue;
+            }
+            due.push(Reminder {
+                path,
+                line: task.line,
+                text: task.text,
+                due: task.due.format("%Y-%m-%d").to_string(),
+                overdue,
+            });
+        }
+    }
+    due.sort_by(|a, b| a.due.cmp(&b.due));
+    if !due.is_empty() {
+        fire(app, &due);
+    }
+}
+
+/// 起動時に設定を読み込み、通知のスケジューラを動かす
+///
+/// 前回の終了から今日までに過ぎた時刻の分は、起動したときに通知する。
+pub fn load_at_startup(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if let Ok(file) = app_data::config_file(app, REMINDERS_FILE) {
+        if let Ok(config) = app_data::read_json(&file) {
+            *state.reminders.config.lock().unwrap() = config;
+        }
+    }
+    if let Ok(file) = app_data::data_file(app, LOG_FILE) {
+        if let Ok(log) = app_data::read_json(&file) {
+            *state.reminders.log.lock().unwrap() = log;
+        }
+    }
+    let start_of_day = timestamp(Local::now().date_naive(), NaiveTime::MIN).unwrap_or_default();
+    let last_check = state.reminders.log.lock().unwrap().last_check;
+    let mut since = last_check
+        .unwrap_or_else(|| Local::now().timestamp())
+        .max(start_of_day);
+
+    let app = app.clone();
+    thread::spawn(move || loop {
+        let now = Local::now().timestamp();
+        check(&app, since, now);
+        since = now;
+        let state = app.state::<AppState>();
+        let mut log = state.reminders.log.lock().unwrap();
+        log.last_check = Some(now);
+        save_log(&app, &log);
+        drop(log);
+        thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+/// 通知の設定
+#[tauri::command]
+pub fn get_reminder_config(state: State<'_, AppState>) -> ReminderConfig {
+    state.reminders.config.lock().unwrap().clone()
+}
+
+/// 通知の設定を保存する（時刻は `HH:MM`）
+#[tauri::command]
+pub fn set_reminder_config(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    config: ReminderConfig,
+) -> Result<(), String> {
+    for time in &config.times {
+        parse_time(time)?;
+    }
+    app_data::write_json(&app_data::config_file(&app, REMINDERS_FILE)?, &config)?;
+    *state.reminders.config.lock().unwrap() = config;
+    Ok(())
+}
+
+/// 保管庫の通知を有効・無効にする
+#[tauri::command]
+pub fn set_vault_reminders(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    root: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut config = state.reminders.config.lock().unwrap().clone();
+    config.vaults.insert(root, enabled);
+    set_reminder_config(app, state, config)
+}
+
+/// タスクの通知を延期する（`minutes` の省略時は設定の分数）。次に通知する時刻（ローカル時刻）を返す
+#[tauri::command]
+pub fn snooze_reminder(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    text: String,
+    minutes: Option<u64>,
+) -> Result<String, String> {
+    let minutes = minutes.unwrap_or_else(|| state.reminders.config.lock().unwrap().snooze_minutes);
+    let until = Local::now() + chrono::Duration::minutes(minutes.max(1) as i64);
+    let mut log = state.reminders.log.lock().unwrap();
+    log.snoozed.insert(key(&path, &text), until.timestamp());
+    app_data::write_json(&app_data::data_file(&app, LOG_FILE)?, &*log)?;
+    Ok(until.format("%Y-%m-%d %H:%M").to_string())
+}
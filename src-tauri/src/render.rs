@@ -0,0 +1,490 @@
+// Markdown から HTML への変換
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, LinkType, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+use crate::code_block;
+use crate::critic;
+use crate::data_block::{self, DataBlockKind};
+use crate::diagram::{DiagramKind, DiagramState};
+use crate::gfm;
+use crate::images;
+use crate::links;
+use crate::markdown;
+use crate::obsidian;
+use crate::render_profile::{self, RenderProfile, SanitizeLevel};
+use crate::text;
+
+/// リンク先の書き換え。引数はリンク先とウィキリンクかどうかで、`None` なら元のまま
+pub type LinkRewriter<'a> = dyn Fn(&str, bool) -> Option<String> + 'a;
+
+/// 見出しの自動番号（`1.`・`1.1`・`1.1.1`。ソースは変更しない）
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HeadingNumbering {
+    /// 番号を振る最上位の見出しのレベル（H1 を文書のタイトルにするなら 2）
+    pub start_level: usize,
+    /// 番号を振る階層の数
+    pub depth: usize,
+}
+
+impl Default for HeadingNumbering {
+    fn default() -> Self {
+        Self {
+            start_level: 1,
+            depth: 3,
+        }
+    }
+}
+
+impl HeadingNumbering {
+    /// 見出しのレベルの並びに対する番号（対象外の見出しは `None`）
+    pub fn numbers(&self, levels: &[usize]) -> Vec<Option<String>> {
+        let start = self.start_level.clamp(1, 6);
+        let mut counters = vec![0usize; self.depth.clamp(1, 6)];
+        levels
+            .iter()
+            .map(|&level| {
+                let depth = level.checked_sub(start).filter(|&d| d < counters.len())?;
+                counters[depth] += 1;
+                counters[depth + 1..].iter_mut().for_each(|c| *c = 0);
+                let parts: Vec<String> = counters[..=depth].iter().map(|c| c.to_string()).collect();
+                Some(if depth == 0 {
+                    format!("{}.", parts[0])
+                } else {
+                    parts.join(".")
+                })
+            })
+            .collect()
+    }
+}
+
+/// 描画の設定
+#[derive(Default)]
+pub struct RenderOptions<'a> {
+    pub rewrite_link: Option<&'a LinkRewriter<'a>>,
+    /// タスクのチェックボックスを操作できるようにする（既定は `disabled`）
+    pub interactive_tasks: bool,
+    pub heading_numbering: Option<&'a HeadingNumbering>,
+    /// ```dot・```plantuml を SVG にする（`None` ならコードブロックのまま）
+    pub diagrams: Option<&'a DiagramState>,
+    /// 画像の相対パスの基準。指定すると小さな SVG は埋め込み、他の画像には大きさを付ける
+    pub base_dir: Option<&'a Path>,
+    /// ```chart・```datatable を `base_dir` からのデータの SVG のグラフと表にする（プレビューはフロントエンドで描く）
+    pub data_blocks: bool,
+    /// 解析の拡張と HTML の扱い（`None` ならノート用の既定）
+    pub profile: Option<&'a RenderProfile>,
+    /// 描画中に読んだファイル（画像とデータ）を加える（描画結果のキャッシュの無効化に使う）
+    pub read_files: Option<&'a RefCell<Vec<PathBuf>>>,
+}
+
+/// 文書中の生の HTML を無害化する（続いたブロックの HTML はまとめてから処理する）
+fn sanitize<'a>(events: Vec<Event<'a>>, level: SanitizeLevel) -> Vec<Event<'a>> {
+    if level == SanitizeLevel::Allow {
+        return events;
+    }
+    let mut out: Vec<Event> = Vec::with_capacity(events.len());
+    for event in events {
+        match (level, event) {
+            (SanitizeLevel::Escape, Event::Html(html) | Event::InlineHtml(html)) => {
+                out.push(Event::Text(html))
+            }
+            (_, Event::Html(html)) => match out.last_mut() {
+                Some(Event::Html(previous)) => {
+                    *previous = CowStr::from(format!("{previous}{html}"));
+                }
+                _ => out.push(Event::Html(html)),
+            },
+            (_, event) => out.push(event),
+        }
+    }
+    for event in out.iter_mut() {
+        if let Event::Html(html) | Event::InlineHtml(html) = event {
+            *html = CowStr::from(render_profile::sanitize_html(html));
+        }
+    }
+    out
+}
+
+/// ローカルの画像を埋め込みの SVG か、`width`・`height` 付きの `<img>` にする
+///
+/// SVG は生の HTML と同じく `level` で無害化する（`Escape` なら埋め込まない）。
+fn resolve_images<'a>(
+    events: Vec<Event<'a>>,
+    base_dir: &Path,
+    rewrite_link: Option<&LinkRewriter>,
+    level: SanitizeLevel,
+    read_files: Option<&RefCell<Vec<PathBuf>>>,
+) -> Vec<Event<'a>> {
+    let mut out = Vec::with_capacity(events.len());
+    let mut events = events.into_iter();
+    while let Some(event) = events.next() {
+        let Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            ..
+        }) = &event
+        else {
+            out.push(event);
+            continue;
+        };
+        if matches!(link_type, LinkType::WikiLink { .. }) || links::is_external(dest_url) {
+            out.push(event);
+            continue;
+        }
+        let target = dest_url.split_once('#').map_or(&**dest_url, |(t, _)| t);
+        let file = base_dir.join(links::percent_decode(target));
+        if let Some(read_files) = read_files {
+            read_files.borrow_mut().push(file.clone());
+        }
+        let svg = match level {
+            SanitizeLevel::Allow => images::inline_svg(&file),
+            SanitizeLevel::Safe => {
+                images::inline_svg(&file).map(|svg| render_profile::sanitize_html(&svg))
+            }
+            SanitizeLevel::Escape => None,
+        };
+        let html = match svg {
+            Some(svg) => Some((true, svg)),
+            None => match images::probe(&file) {
+                Ok(images::ImageInfo {
+                    width: Some(width),
+                    height: Some(height),
+                    ..
+                }) => {
+                    let src = rewrite_link
+                        .and_then(|rewrite| rewrite(dest_url, false))
+                        .unwrap_or_else(|| dest_url.to_string());
+                    let title = if title.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" title=\"{}\"", text::escape_html(title))
+                    };
+                    Some((
+                        false,
+                        format!(
+                            "<img src=\"{}\"{title} width=\"{width}\" height=\"{height}\"",
+                            text::escape_html(&src)
+                        ),
+                    ))
+                }
+                _ => None,
+            },
+        };
+        let Some((svg, html)) = html else {
+            out.push(event);
+            continue;
+        };
+        // 代替テキストを集めて画像の終わりまで読み飛ばす
+        let mut alt = String::new();
+        for inner in events.by_ref() {
+            match inner {
+                Event::End(TagEnd::Image) => break,
+                Event::Text(t) | Event::Code(t) => alt.push_str(&t),
+                _ => {}
+            }
+        }
+        let alt = text::escape_html(&alt);
+        out.push(Event::InlineHtml(CowStr::from(if svg {
+            format!("<span class=\"inline-svg\" role=\"img\" aria-label=\"{alt}\">{html}</span>")
+        } else {
+            format!("{html} alt=\"{alt}\" loading=\"lazy\">")
+        })));
+    }
+    out
+}
+
+/// 図とファイル名や強調行の指定があるコードブロックを専用の HTML にする（`attributes` が偽なら図だけ）
+///
+/// `data_dir` を渡すと ```chart・```datatable もそのフォルダからのデータで HTML にする。
+fn extend_code_blocks<'a>(
+    events: Vec<Event<'a>>,
+    diagrams: Option<&DiagramState>,
+    attributes: bool,
+    data_dir: Option<&Path>,
+    read_files: Option<&RefCell<Vec<PathBuf>>>,
+) -> Vec<Event<'a>> {
+    let mut out = Vec::with_capacity(events.len());
+    let mut current: Option<(code_block::CodeInfo, String)> = None;
+    for event in events {
+        match (&mut current, &event) {
+            (None, Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info)))) => {
+                let info = code_block::parse_info(info);
+                let diagram = diagrams.is_some() && DiagramKind::from_lang(&info.lang).is_some();
+                let data = data_dir.is_some() && DataBlockKind::from_lang(&info.lang).is_some();
+                if diagram || data || (attributes && info.is_extended()) {
+                    current = Some((info, String::new()));
+                    continue;
+                }
+            }
+            (Some((_, code)), Event::Text(text)) => {
+                code.push_str(text);
+                continue;
+            }
+            (Some(_), Event::End(TagEnd::CodeBlock)) => {
+                let (info, code) = current.take().unwrap();
+                let html = match (diagrams, DiagramKind::from_lang(&info.lang)) {
+                    (Some(diagrams), Some(kind)) => diagrams.html(kind, &code),
+                    _ => match (data_dir, DataBlockKind::from_lang(&info.lang)) {
+                        (Some(dir), Some(kind)) => {
+                            if let Some((read_files, source)) =
+                                read_files.zip(data_block::source_path(kind, &code, dir))
+                            {
+                                read_files.borrow_mut().push(source);
+                            }
+                            data_block::html(kind, &code, dir)
+                        }
+                        _ => code_block::code_block_html(&info, &code),
+                    },
+                };
+                out.push(Event::Html(CowStr::from(html)));
+                continue;
+            }
+            _ => {}
+        }
+        out.push(event);
+    }
+    out
+}
+
+/// 見出しの先頭に番号を入れる
+fn insert_heading_numbers<'a>(
+    events: Vec<Event<'a>>,
+    numbering: &HeadingNumbering,
+) -> Vec<Event<'a>> {
+    let levels: Vec<usize> = events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Start(Tag::Heading { level, .. }) => Some(*level as usize),
+            _ => None,
+        })
+        .collect();
+    let mut numbers = numbering.numbers(&levels).into_iter();
+    let mut out = Vec::with_capacity(events.len() + levels.len());
+    for event in events {
+        let heading = matches!(event, Event::Start(Tag::Heading { .. }));
+        out.push(event);
+        if let Some(number) = heading.then(|| numbers.next()).flatten().flatten() {
+            out.push(Event::InlineHtml(CowStr::from(format!(
+                "<span class=\"heading-number\">{number}</span> "
+            ))));
+        }
+    }
+    out
+}
+
+/// 見出しに GitHub 互換のアンカー ID を付ける
+fn assign_heading_ids(events: &mut [Event]) {
+    let mut seen = HashMap::new();
+    for i in 0..events.len() {
+        if !matches!(events[i], Event::Start(Tag::Heading { id: None, .. })) {
+            continue;
+        }
+        let text: String = events[i + 1..]
+            .iter()
+            .take_while(|e| !matches!(e, Event::End(TagEnd::Heading(_))))
+            .filter_map(|e| match e {
+                Event::Text(t) | Event::Code(t) => Some(t.as_ref()),
+                _ => None,
+            })
+            .collect();
+        let slug = markdown::unique_slug(&mut seen, markdown::slugify(&text));
+        if let Event::Start(Tag::Heading { id, .. }) = &mut events[i] {
+            *id = Some(slug.into());
+        }
+    }
+}
+
+/// 本文中の `#tag` を `<span class="tag">` にする（コードとフロントマターは除く）
+fn mark_tags(events: Vec<Event>) -> Vec<Event> {
+    let mut out = Vec::with_capacity(events.len());
+    let mut verbatim = 0;
+    for event in events {
+        match &event {
+            Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_)) => verbatim += 1,
+            Event::End(TagEnd::CodeBlock | TagEnd::MetadataBlock(_)) => verbatim -= 1,
+            Event::Text(t) if verbatim == 0 => {
+                let tags = obsidian::inline_tags(t);
+                if !tags.is_empty() {
+                    let mut last = 0;
+                    for range in tags {
+                        if range.start > last {
+                            out.push(Event::Text(CowStr::from(t[last..range.start].to_string())));
+                        }
+                        let tag = text::escape_html(&t[range.start + 1..range.end]);
+                        out.push(Event::InlineHtml(CowStr::from(format!(
+                            "<span class=\"tag\" data-tag=\"{tag}\">#{tag}</span>"
+                        ))));
+                        last = range.end;
+                    }
+                    if last < t.len() {
+                        out.push(Event::Text(CowStr::from(t[last..].to_string())));
+                    }
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push(event);
+    }
+    out
+}
+
+/// Markdown を HTML に変換（プレビューと同様に改行は `<br>` として扱う。Obsidian のコールアウトとタグにも対応）
+pub fn render_html(content: &str, rewrite_link: Option<&LinkRewriter>) -> String {
+    render_html_with(
+        content,
+        &RenderOptions {
+            rewrite_link,
+            ..Default::default()
+        },
+    )
+}
+
+/// 設定を指定して Markdown を HTML に変換する
+///
+/// タスクのチェックボックスには出現順の `data-task` と元の行番号の `data-line` を付ける。
+pub fn render_html_with(content: &str, options: &RenderOptions) -> String {
+    let rewrite_link = options.rewrite_link;
+    let default = RenderProfile::default();
+    let profile = options.profile.unwrap_or(&default);
+    // コールアウトの変換で行がずれる前に、タスクの行番号を求めておく
+    let task_lines: Vec<usize> = markdown::tasks(content).iter().map(|t| t.line).collect();
+    let mut task = 0;
+    let content = if profile.critic_markup && profile.sanitize != SanitizeLevel::Escape {
+        critic::to_html(content)
+    } else {
+        content.to_string()
+    };
+    let content = if profile.callouts && profile.sanitize != SanitizeLevel::Escape {
+        obsidian::convert_callouts(&content)
+    } else {
+        content
+    };
+    let content = content.as_str();
+    let events: Vec<Event> = Parser::new_ext(content, profile.parser_options()).collect();
+    let mut events = sanitize(events, profile.sanitize);
+    if profile.autolinks {
+        events = gfm::link_urls(events);
+    }
+    if profile.alerts {
+        events = gfm::alert_blocks(events);
+    }
+    if profile.heading_ids {
+        assign_heading_ids(&mut events);
+    }
+    if profile.tags {
+        events = mark_tags(events);
+    }
+    let diagrams = options.diagrams.filter(|_| profile.diagrams);
+    let data_dir = options.base_dir.filter(|_| options.data_blocks);
+    if diagrams.is_some() || profile.code_blocks || data_dir.is_some() {
+        events = extend_code_blocks(
+            events,
+            diagrams,
+            profile.code_blocks,
+            data_dir,
+            options.read_files,
+        );
+    }
+    if let Some(base_dir) = options.base_dir {
+        events = resolve_images(
+            events,
+            base_dir,
+            rewrite_link,
+            profile.sanitize,
+            options.read_files,
+        );
+    }
+
+    for event in events.iter_mut() {
+        match event {
+            Event::SoftBreak if profile.hard_breaks => *event = Event::HardBreak,
+            Event::TaskListMarker(checked) => {
+                let line = task_lines
+                    .get(task)
+                    .map(|l| format!(" data-line=\"{l}\""))
+                    .unwrap_or_default();
+                *event = Event::InlineHtml(CowStr::from(format!(
+                    "<input type=\"checkbox\" class=\"task-checkbox\" data-task=\"{task}\"{line}{}{}> ",
+                    if *checked { " checked" } else { "" },
+                    if options.interactive_tasks { "" } else { " disabled" },
+                )));
+                task += 1;
+            }
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                ..
+            })
+            | Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                ..
+            }) => {
+                let wiki = matches!(link_type, LinkType::WikiLink { .. });
+                if let Some(url) = rewrite_link.and_then(|rewrite| rewrite(dest_url, wiki)) {
+                    *dest_url = url.into();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(numbering) = options.heading_numbering {
+        events = insert_heading_numbers(events, numbering);
+    }
+
+    let mut out = String::with_capacity(content.len() * 3 / 2);
+    html::push_html(&mut out, events.into_iter());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{render_html_with, RenderOptions};
+    use crate::render_profile::RenderProfile;
+
+    #[test]
+    fn sanitizes_inlined_svg_by_profile() {
+        let dir = std::env::temp_dir().join(format!("mdvim-render-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("evil.svg"),
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10" onload="alert(1)"><a href="javascript:alert(2)"><rect width="10" height="10"/></a><foreignObject><img src="x" onerror="alert(3)"></foreignObject></svg>"#,
+        )
+        .unwrap();
+        let render = |profile: RenderProfile| {
+            render_html_with(
+                "![evil](evil.svg)",
+                &RenderOptions {
+                    base_dir: Some(&dir),
+                    profile: Some(&profile),
+                    ..Default::default()
+                },
+            )
+        };
+
+        let safe = render(RenderProfile::gfm());
+        assert!(safe.contains("<svg"), "{safe}");
+        assert!(safe.contains("<rect"), "{safe}");
+        for unsafe_part in ["onload", "onerror", "javascript:", "foreignObject"] {
+            assert!(!safe.contains(unsafe_part), "{unsafe_part}: {safe}");
+        }
+
+        let escaped = render(RenderProfile {
+            sanitize: crate::render_profile::SanitizeLevel::Escape,
+            ..RenderProfile::gfm()
+        });
+        assert!(!escaped.contains("<svg"), "{escaped}");
+        assert!(escaped.contains("<img src=\"evil.svg\""), "{escaped}");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
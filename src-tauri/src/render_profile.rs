@@ -0,0 +1,368 @@
+// 文書ごとの描画プロファイル（CommonMark・GFM・ノート用の拡張と HTML の無害化の組み合わせ）
+
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+
+use pulldown_cmark::Options;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::editorconfig;
+use crate::markdown;
+use crate::state::AppState;
+use crate::vault;
+
+/// 中身ごと取り除く要素
+const DANGEROUS_ELEMENTS: &[&str] = &[
+    "script",
+    "style",
+    "iframe",
+    "object",
+    "embed",
+    "frame",
+    "frameset",
+    "applet",
+    "base",
+    "meta",
+    "link",
+    "form",
+    "foreignObject",
+];
+
+static EVENT_ATTRIBUTE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\s+on[a-z]+\s*=\s*(?:"[^"]*"|'[^']*'|[^\s>]+)"#).unwrap());
+static SCRIPT_URL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)\b(href|src|xlink:href|action|formaction)\s*=\s*(?:"\s*(?:javascript|vbscript|data:text/html)[^"]*"|'\s*(?:javascript|vbscript|data:text/html)[^']*'|(?:javascript|vbscript):[^\s>]*)"#,
+    )
+    .unwrap()
+});
+static DANGEROUS_BLOCK: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    DANGEROUS_ELEMENTS
+        .iter()
+        .map(|name| Regex::new(&format!(r"(?is)<{name}\b[^>]*>.*?</{name}\s*>")).unwrap())
+        .collect()
+});
+static DANGEROUS_TAG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(?i)</?(?:{})\b[^>]*>",
+        DANGEROUS_ELEMENTS.join("|")
+    ))
+    .unwrap()
+});
+
+/// 生の HTML の扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SanitizeLevel {
+    /// そのまま出力する
+    #[default]
+    Allow,
+    /// スクリプト・イベント属性・`javascript:` の URL を除く（GitHub 相当）
+    Safe,
+    /// すべてテキストとして表示する（コールアウトも変換しない）
+    Escape,
+}
+
+/// 描画プロファイル（既定はエディタのプレビューと同じノート用の設定）
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RenderProfile {
+    pub name: String,
+    pub tables: bool,
+    pub footnotes: bool,
+    pub strikethrough: bool,
+    pub task_lists: bool,
+    /// `$...$`・`$$...$$`
+    pub math: bool,
+    /// `[[wikilink]]`
+    pub wikilinks: bool,
+    /// YAML のフロントマターを本文として描画しない
+    pub front_matter: bool,
+    /// GitHub のアラート（`> [!NOTE]`）
+    pub alerts: bool,
+    /// `www.`・`https://` で始まる URL とメールアドレスを自動でリンクにする（GFM の拡張自動リンク）
+    pub autolinks: bool,
+    pub smart_punctuation: bool,
+    pub definition_lists: bool,
+    /// ソフト改行を `<br>` にする
+    pub hard_breaks: bool,
+    /// Obsidian のコールアウト（`> [!note] タイトル`）
+    pub callouts: bool,
+    /// 本文の `#tag`
+    pub tags: bool,
+    /// CriticMarkup（`{++追加++}` `{--削除--}` など）
+    pub critic_markup: bool,
+    /// ```dot・```plantuml の図
+    pub diagrams: bool,
+    /// コードブロックのファイル名・行番号・強調行の指定（```rust title="main.rs" {3-5}）
+    pub code_blocks: bool,
+    /// 見出しに GitHub 互換のアンカー ID を付ける
+    pub heading_ids: bool,
+    pub sanitize: SanitizeLevel,
+    /// エクスポートに GitHub と同じ見た目のスタイルシートを使う
+    pub github_style: bool,
+}
+
+impl Default for RenderProfile {
+    fn default() -> Self {
+        Self {
+            name: "vault".to_string(),
+            tables: true,
+            footnotes: true,
+            strikethrough: true,
+            task_lists: true,
+            math: true,
+            wikilinks: true,
+            front_matter: true,
+            alerts: false,
+            autolinks: false,
+            smart_punctuation: false,
+            definition_lists: false,
+            hard_breaks: true,
+            callouts: true,
+            tags: true,
+            critic_markup: true,
+            diagrams: true,
+            code_blocks: true,
+            heading_ids: true,
+            sanitize: SanitizeLevel::Allow,
+            github_style: false,
+        }
+    }
+}
+
+impl RenderProfile {
+    /// 拡張のない CommonMark
+    pub fn commonmark() -> Self {
+        Self {
+            name: "commonmark".to_string(),
+            tables: false,
+            footnotes: false,
+            strikethrough: false,
+            task_lists: false,
+            math: false,
+            wikilinks: false,
+            front_matter: false,
+            hard_breaks: false,
+            callouts: false,
+            tags: false,
+            critic_markup: false,
+            diagrams: false,
+            code_blocks: false,
+            ..Self::default()
+        }
+    }
+
+    /// 仕様書どおりの CommonMark（見出しの ID も付けない）
+    pub fn strict() -> Self {
+        Self {
+            name: "strict".to_string(),
+            heading_ids: false,
+            ..Self::commonmark()
+        }
+    }
+
+    /// GitHub の README と同じ描画
+    pub fn gfm() -> Self {
+        Self {
+            name: "gfm".to_string(),
+            tables: true,
+            footnotes: true,
+            strikethrough: true,
+            task_lists: true,
+            math: true,
+            front_matter: true,
+            alerts: true,
+            autolinks: true,
+            sanitize: SanitizeLevel::Safe,
+            github_style: true,
+            ..Self::commonmark()
+        }
+    }
+
+    /// 組み込みのプロファイル
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "vault" | "notes" => Some(Self::default()),
+            "commonmark" => Some(Self::commonmark()),
+            "strict" | "commonmark-strict" => Some(Self::strict()),
+            "gfm" | "github" => Some(Self::gfm()),
+            _ => None,
+        }
+    }
+
+    /// pulldown-cmark の解析オプション
+    pub fn parser_options(&self) -> Options {
+        let mut options = Options::empty();
+        for (enabled, option) in [
+            (self.tables, Options::ENABLE_TABLES),
+            (self.footnotes, Options::ENABLE_FOOTNOTES),
+            (self.strikethrough, Options::ENABLE_STRIKETHROUGH),
+            (self.task_lists, Options::ENABLE_TASKLISTS),
+            (self.math, Options::ENABLE_MATH),
+            (self.wikilinks, Options::ENABLE_WIKILINKS),
+            (
+                self.front_matter,
+                Options::ENABLE_YAML_STYLE_METADATA_BLOCKS,
+            ),
+            (self.alerts, Options::ENABLE_GFM),
+            (self.smart_punctuation, Options::ENABLE_SMART_PUNCTUATION),
+            (self.definition_lists, Options::ENABLE_DEFINITION_LIST),
+        ] {
+            if enabled {
+                options |= option;
+            }
+        }
+        options
+    }
+}
+
+/// HTML からスクリプトなどの危険な部分を除く
+pub fn sanitize_html(html: &str) -> String {
+    let mut html = html.to_string();
+    for block in DANGEROUS_BLOCK.iter() {
+        html = block.replace_all(&html, "").into_owned();
+    }
+    let html = DANGEROUS_TAG.replace_all(&html, "");
+    let html = EVENT_ATTRIBUTE.replace_all(&html, "");
+    SCRIPT_URL.replace_all(&html, "$1=\"#\"").into_owned()
+}
+
+/// パスのパターンとプロファイル名
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProfileRule {
+    /// EditorConfig と同じ glob（`/` を含まなければファイル名に当てはめる。例: `README.md`）
+    pub pattern: String,
+    pub profile: String,
+}
+
+/// プロファイルの設定
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProfileConfig {
+    /// フロントマターにも規則にも当てはまらない文書のプロファイル
+    pub default: String,
+    /// 先に書いたものを優先する
+    pub rules: Vec<ProfileRule>,
+    /// 独自のプロファイル（組み込みと同じ名前なら置き換える）
+    pub profiles: Vec<RenderProfile>,
+}
+
+impl Default for ProfileConfig {
+    fn default() -> Self {
+        Self {
+            default: "vault".to_string(),
+            rules: vec![ProfileRule {
+                pattern: "README.md".to_string(),
+                profile: "gfm".to_string(),
+            }],
+            profiles: Vec::new(),
+        }
+    }
+}
+
+impl ProfileConfig {
+    fn find(&self, name: &str) -> Option<RenderProfile> {
+        self.profiles
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+            .cloned()
+            .or_else(|| RenderProfile::builtin(name))
+    }
+}
+
+/// プロファイルの設定
+#[derive(Default)]
+pub struct ProfileState {
+    config: Mutex<ProfileConfig>,
+}
+
+impl ProfileState {
+    /// 文書のプロファイル（フロントマターの `mdvim`、パスの規則、既定の順）
+    pub fn resolve(&self, content: &str, path: Option<&Path>) -> RenderProfile {
+        let config = self.config.lock().unwrap();
+        let from_path = path.and_then(|path| {
+            let path = vault::to_slash(&vault::normalize(path));
+            config
+                .rules
+                .iter()
+                .find(|rule| {
+                    editorconfig::section_regex(&rule.pattern).is_some_and(|re| re.is_match(&path))
+                })
+                .map(|rule| rule.profile.clone())
+        });
+        front_matter_profile(content)
+            .into_iter()
+            .chain(from_path)
+            .chain([config.default.clone()])
+            .find_map(|name| config.find(&name))
+            .unwrap_or_default()
+    }
+}
+
+/// フロントマターの `mdvim: gfm` または `mdvim:\n  profile: gfm`
+fn front_matter_profile(content: &str) -> Option<String> {
+    let end = markdown::body_start(content);
+    let mut lines = content[..end].lines().skip(1);
+    while let Some(line) = lines.next() {
+        let Some(value) = line.strip_prefix("mdvim:") else {
+            continue;
+        };
+        let clean = |v: &str| v.trim().trim_matches(|c| c == '"' || c == '\'').to_string();
+        let value = value.trim();
+        if let Some(inner) = value.strip_prefix('{').and_then(|v| v.strip_suffix('}')) {
+            return inner
+                .split(',')
+                .filter_map(|pair| pair.split_once(':'))
+                .find(|(key, _)| key.trim() == "profile")
+                .map(|(_, v)| clean(v));
+        }
+        if !value.is_empty() {
+            return Some(clean(value));
+        }
+        return lines
+            .take_while(|l| l.starts_with([' ', '\t']))
+            .filter_map(|l| l.trim().strip_prefix("profile:"))
+            .map(clean)
+            .next();
+    }
+    None
+}
+
+/// 描画プロファイルを設定する
+#[tauri::command]
+pub fn configure_render_profiles(state: State<'_, AppState>, config: ProfileConfig) {
+    *state.profiles.config.lock().unwrap() = config;
+}
+
+/// 使えるプロファイルの一覧（組み込みと独自のもの）
+#[tauri::command]
+pub fn list_render_profiles(state: State<'_, AppState>) -> Vec<RenderProfile> {
+    let config = state.profiles.config.lock().unwrap();
+    let mut profiles: Vec<RenderProfile> = ["vault", "gfm", "commonmark", "strict"]
+        .into_iter()
+        .filter_map(|name| config.find(name))
+        .collect();
+    profiles.extend(
+        config
+            .profiles
+            .iter()
+            .filter(|p| RenderProfile::builtin(&p.name).is_none())
+            .cloned(),
+    );
+    profiles
+}
+
+/// 文書に使われるプロファイル
+#[tauri::command]
+pub fn resolve_render_profile(
+    state: State<'_, AppState>,
+    content: String,
+    path: Option<String>,
+) -> RenderProfile {
+    state
+        .profiles
+        .resolve(&content, path.as_deref().map(Path::new))
+}
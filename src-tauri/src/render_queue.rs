@@ -0,0 +1,179 @@
+// プレビューの描画の予約（文書ごとに続けて来た要求をまとめ、最新の結果だけを `render-complete` で送る）
+
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::preview::{self, ParseResult, PreviewOptions};
+use crate::state::AppState;
+
+/// 待ち時間（この間に次の要求が来なければ描画する）
+const DELAY: Duration = Duration::from_millis(150);
+/// 要求がないまま待つと描画のスレッドを終える時間
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 描画を待っている要求
+struct Request {
+    seq: u64,
+    content: String,
+    path: Option<String>,
+    vault_root: Option<String>,
+    options: PreviewOptions,
+    /// この時刻まで次の要求を待つ
+    due: Instant,
+}
+
+#[derive(Default)]
+struct Pending {
+    request: Option<Request>,
+    /// 最後に受け付けた要求の番号（描画中の結果が古いかどうかの判定に使う）
+    latest: u64,
+    /// 描画のスレッドが動いているか
+    running: bool,
+}
+
+/// 文書ごとの要求
+#[derive(Default)]
+struct DocQueue {
+    pending: Mutex<Pending>,
+    wake: Condvar,
+}
+
+/// `render-complete` イベント
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderComplete {
+    pub doc_id: String,
+    /// `request_render` が返した番号
+    pub seq: u64,
+    pub result: Option<ParseResult>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// 描画の予約の状態
+#[derive(Default)]
+pub struct RenderQueueState {
+    docs: Mutex<HashMap<String, Arc<DocQueue>>>,
+    next_seq: Mutex<u64>,
+}
+
+/// 次の要求を待ち、待ち時間が過ぎたものを取り出す（しばらく要求がなければ `None`）
+fn next_request(queue: &DocQueue) -> Option<Request> {
+    let mut pending = queue.pending.lock().unwrap();
+    let idle_since = Instant::now();
+    loop {
+        let now = Instant::now();
+        match &pending.request {
+            Some(request) if request.due <= now => return pending.request.take(),
+            Some(request) => {
+                let wait = request.due - now;
+                pending = queue.wake.wait_timeout(pending, wait).unwrap().0;
+            }
+            None if now.duration_since(idle_since) >= IDLE_TIMEOUT => {
+                pending.running = false;
+                return None;
+            }
+            None => {
+                let wait = IDLE_TIMEOUT - now.duration_since(idle_since);
+                pending = queue.wake.wait_timeout(pending, wait).unwrap().0;
+            }
+        }
+    }
+}
+
+/// 文書の描画のスレッド（1 文書につき 1 つなので、結果は要求の順に送られる）
+fn worker(app: AppHandle, doc_id: String, queue: Arc<DocQueue>) {
+    while let Some(request) = next_request(&queue) {
+        let started = Instant::now();
+        let state = app.state::<AppState>();
+        let result = preview::parse(
+            &state,
+            &request.content,
+            request.path.as_deref(),
+            request.vault_root.as_deref(),
+            &request.options,
+        );
+        // 描画中に新しい要求が来ていれば、この結果は送らない
+        if queue.pending.lock().unwrap().latest != request.seq {
+            continue;
+        }
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(e) => (None, Some(e)),
+        };
+        let _ = app.emit(
+            "render-complete",
+            RenderComplete {
+                doc_id: doc_id.clone(),
+                seq: request.seq,
+                result,
+                error,
+                duration_ms: started.elapsed().as_millis() as u64,
+            },
+        );
+    }
+}
+
+/// プレビューの描画を予約し、要求の番号を返す
+///
+/// 同じ文書への要求は 150ms の間に次が来なければ描画し、古い要求は捨てる。
+/// 結果は `render-complete` イベントで送り、新しい要求が来た後に古い結果を送ることはない。
+#[tauri::command]
+pub fn request_render(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    doc_id: String,
+    content: String,
+    path: Option<String>,
+    vault_root: Option<String>,
+    options: Option<PreviewOptions>,
+) -> u64 {
+    let queue = state
+        .render_queue
+        .docs
+        .lock()
+        .unwrap()
+        .entry(doc_id.clone())
+        .or_default()
+        .clone();
+    // 番号は文書の要求を更新する間に割り当てる（並んだ要求の順序を保つ）
+    let mut pending = queue.pending.lock().unwrap();
+    let seq = {
+        let mut next = state.render_queue.next_seq.lock().unwrap();
+        *next += 1;
+        *next
+    };
+    pending.request = Some(Request {
+        seq,
+        content,
+        path,
+        vault_root,
+        options: options.unwrap_or_default(),
+        due: Instant::now() + DELAY,
+    });
+    pending.latest = seq;
+    if pending.running {
+        queue.wake.notify_one();
+    } else {
+        pending.running = true;
+        let queue = queue.clone();
+        thread::spawn(move || worker(app, doc_id, queue));
+    }
+    seq
+}
+
+/// 文書の予約を取り消す（描画中の結果も送らない。文書を閉じたときに使う）
+#[tauri::command]
+pub fn cancel_render(state: State<'_, AppState>, doc_id: String) {
+    let docs = state.render_queue.docs.lock().unwrap();
+    if let Some(queue) = docs.get(&doc_id) {
+        let mut pending = queue.pending.lock().unwrap();
+        pending.request = None;
+        pending.latest = 0;
+        queue.wake.notify_one();
+    }
+}
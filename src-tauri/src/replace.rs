@@ -0,0 +1,389 @@
+// 正規表現による置換（Vim の `:s/pattern/replacement/flags` と同じ扱い）
+//
+// 検索パターンは regex クレートの構文（`\<` `\>` は単語の境界として受け付ける）。`^` と `$` は行ごとに当てはまる。
+// 置換後の文字列は Vim と同じく `\1`〜`\9`・`\0` と `&`（一致した全体）・`\n`（改行）・`\t`・
+// `\u` `\l`（次の 1 文字を大文字・小文字に）・`\U` `\L` … `\E`（範囲を大文字・小文字に）を使える。
+//
+// フラグ: `g` 各行のすべての一致（なければ各行の最初だけ）・`i` 大文字と小文字を区別しない・`I` 区別する・
+// `s` `.` が改行にも一致する・`n` 置換せず一致だけを返す。
+
+use std::ops::Range;
+
+use regex::{Captures, Regex, RegexBuilder};
+use serde::Serialize;
+
+use crate::lsp::LspRange;
+use crate::text::{self, LineIndex};
+use crate::tr;
+
+/// 結果に含める一致の数の上限
+const MAX_MATCHES: usize = 1000;
+
+/// 置換した（置換する）一致
+#[derive(Debug, Serialize)]
+pub struct ReplaceMatch {
+    /// 元の内容での範囲
+    pub range: LspRange,
+    pub text: String,
+    pub replacement: String,
+}
+
+/// `regex_replace` の結果
+#[derive(Debug, Serialize)]
+pub struct ReplaceResult {
+    /// 置換後の内容（一致だけを求めたときは `None`）
+    pub content: Option<String>,
+    /// 一致の数
+    pub count: usize,
+    /// 一致を含む行の数
+    pub lines: usize,
+    /// 一致の一覧（先頭から `MAX_MATCHES` 件まで）
+    pub matches: Vec<ReplaceMatch>,
+    pub truncated: bool,
+}
+
+struct Flags {
+    global: bool,
+    ignore_case: bool,
+    dot_all: bool,
+    count_only: bool,
+}
+
+fn parse_flags(flags: &str) -> Result<Flags, String> {
+    let mut parsed = Flags {
+        global: false,
+        ignore_case: false,
+        dot_all: false,
+        count_only: false,
+    };
+    for c in flags.chars() {
+        match c {
+            'g' => parsed.global = true,
+            'i' => parsed.ignore_case = true,
+            'I' => parsed.ignore_case = false,
+            's' => parsed.dot_all = true,
+            'n' => parsed.count_only = true,
+            // Vim では確認や行の表示のためのフラグ（ここでは何もしない）
+            'c' | 'e' | '&' | 'p' | '#' | 'l' => {}
+            c if c.is_whitespace() => {}
+            c => return Err(tr!("Unknown flag: {c}")),
+        }
+    }
+    Ok(parsed)
+}
+
+/// Vim の単語の境界 `\<` `\>` を regex クレートの `\b` にする
+fn translate_pattern(pattern: &str) -> String {
+    let mut out = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('<' | '>') => out.push_str(r"\b"),
+            Some(next) => {
+                out.push('\\');
+                out.push(next);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// 置換後の文字列の 1 要素
+enum Piece {
+    Literal(String),
+    Group(usize),
+    /// `\u` `\l`（次の 1 文字）
+    NextUpper,
+    NextLower,
+    /// `\U` `\L`（`\E` まで）・`\E`
+    Upper,
+    Lower,
+    End,
+}
+
+fn parse_replacement(replacement: &str) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let mut literal = String::new();
+    let mut chars = replacement.chars();
+    let push = |pieces: &mut Vec<Piece>, literal: &mut String, piece: Piece| {
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(std::mem::take(literal)));
+        }
+        pieces.push(piece);
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '&' => push(&mut pieces, &mut literal, Piece::Group(0)),
+            '\\' => match chars.next() {
+                Some(d @ '0'..='9') => push(
+                    &mut pieces,
+                    &mut literal,
+                    Piece::Group(d.to_digit(10).unwrap_or(0) as usize),
+                ),
+                Some('n' | 'r') => literal.push('\n'),
+                Some('t') => literal.push('\t'),
+                Some('u') => push(&mut pieces, &mut literal, Piece::NextUpper),
+                Some('l') => push(&mut pieces, &mut literal, Piece::NextLower),
+                Some('U') => push(&mut pieces, &mut literal, Piece::Upper),
+                Some('L') => push(&mut pieces, &mut literal, Piece::Lower),
+                Some('E' | 'e') => push(&mut pieces, &mut literal, Piece::End),
+                Some(other) => literal.push(other),
+                None => literal.push('\\'),
+            },
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        pieces.push(Piece::Literal(literal));
+    }
+    pieces
+}
+
+/// 一致 1 つの置換後の文字列
+fn expand(pieces: &[Piece], caps: &Captures) -> String {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Case {
+        Keep,
+        Upper,
+        Lower,
+    }
+    let mut out = String::new();
+    let mut range = Case::Keep;
+    let mut next = Case::Keep;
+    let emit = |out: &mut String, text: &str, range: Case, next: &mut Case| {
+        for c in text.chars() {
+            let case = if *next != Case::Keep { *next } else { range };
+            *next = Case::Keep;
+            match case {
+                Case::Upper => out.extend(c.to_uppercase()),
+                Case::Lower => out.extend(c.to_lowercase()),
+                Case::Keep => out.push(c),
+            }
+        }
+    };
+    for piece in pieces {
+        match piece {
+            Piece::Literal(text) => emit(&mut out, text, range, &mut next),
+            Piece::Group(i) => {
+                let text = caps.get(*i).map_or("", |m| m.as_str());
+                emit(&mut out, text, range, &mut next);
+            }
+            Piece::NextUpper => next = Case::Upper,
+            Piece::NextLower => next = Case::Lower,
+            Piece::Upper => range = Case::Upper,
+            Piece::Lower => range = Case::Lower,
+            Piece::End => range = Case::Keep,
+        }
+    }
+    out
+}
+
+/// 検索パターンを組み立てる（`^` `$` は行ごと）
+pub fn build_regex(pattern: &str, ignore_case: bool, dot_all: bool) -> Result<Regex, String> {
+    RegexBuilder::new(&translate_pattern(pattern))
+        .multi_line(true)
+        .crlf(true)
+        .case_insensitive(ignore_case)
+        .dot_matches_new_line(dot_all)
+        .build()
+        .map_err(|e| tr!("Invalid pattern {pattern}: {e}"))
+}
+
+/// 置換する一致 1 つ
+pub struct Replacement {
+    pub range: Range<usize>,
+    /// 一致の始まりの行番号（1 始まり）
+    pub line: usize,
+    pub text: String,
+}
+
+/// 解釈したパターン・置換後の文字列・フラグ
+pub struct Substitution {
+    re: Regex,
+    pieces: Vec<Piece>,
+    flags: Flags,
+}
+
+impl Substitution {
+    pub fn new(pattern: &str, replacement: &str, flags: &str) -> Result<Self, String> {
+        let flags = parse_flags(flags)?;
+        Ok(Self {
+            re: build_regex(pattern, flags.ignore_case, flags.dot_all)?,
+            pieces: parse_replacement(replacement),
+            flags,
+        })
+    }
+
+    /// 置換せず一致だけを求める（`n` フラグ）
+    pub fn count_only(&self) -> bool {
+        self.flags.count_only
+    }
+
+    /// `first_line`〜`last_line` の行で始まる一致（`g` がなければ各行の最初だけ）
+    pub fn replacements(
+        &self,
+        content: &str,
+        index: &LineIndex,
+        first_line: usize,
+        last_line: usize,
+    ) -> Vec<Replacement> {
+        let mut replacements = Vec::new();
+        let mut previous_line = 0;
+        for caps in self.re.captures_iter(content) {
+            let whole = caps.get(0).unwrap();
+            let line = index.line_of(whole.start());
+            if line < first_line {
+                continue;
+            }
+            if line > last_line {
+                break;
+            }
+            if line == previous_line && !self.flags.global {
+                continue;
+            }
+            previous_line = line;
+            replacements.push(Replacement {
+                range: whole.range(),
+                line,
+                text: expand(&self.pieces, &caps),
+            });
+        }
+        replacements
+    }
+}
+
+/// 正規表現で置換する（`:%s` と同じ扱い。`start_line` と `end_line` で行の範囲を絞れる）
+///
+/// `preview` が `true` か `n` フラグがあれば、置換せずに一致と置換後の文字列だけを返す。
+#[tauri::command(async)]
+pub fn regex_replace(
+    content: String,
+    pattern: String,
+    replacement: String,
+    flags: Option<String>,
+    start_line: Option<usize>,
+    end_line: Option<usize>,
+    preview: Option<bool>,
+) -> Result<ReplaceResult, String> {
+    let substitution =
+        Substitution::new(&pattern, &replacement, flags.as_deref().unwrap_or_default())?;
+    let index = LineIndex::new(&content);
+    let replacements = substitution.replacements(
+        &content,
+        &index,
+        start_line.unwrap_or(1).max(1),
+        end_line.unwrap_or(usize::MAX),
+    );
+
+    let mut lines = 0;
+    let mut previous_line = 0;
+    let mut matches = Vec::new();
+    for r in &replacements {
+        if r.line != previous_line {
+            lines += 1;
+            previous_line = r.line;
+        }
+        if matches.len() < MAX_MATCHES {
+            matches.push(ReplaceMatch {
+                range: LspRange::from_offsets(&index, &content, r.range.clone()),
+                text: content[r.range.clone()].to_string(),
+                replacement: r.text.clone(),
+            });
+        }
+    }
+    let count = replacements.len();
+    let preview = preview.unwrap_or(false) || substitution.count_only();
+    Ok(ReplaceResult {
+        content: (!preview).then(|| {
+            text::apply_edits(
+                &content,
+                replacements
+                    .into_iter()
+                    .map(|r| (r.range, r.text))
+                    .collect(),
+            )
+        }),
+        count,
+        lines,
+        truncated: count > matches.len(),
+        matches,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::regex_replace;
+
+    fn replace(content: &str, pattern: &str, replacement: &str, flags: &str) -> String {
+        let result = regex_replace(
+            content.to_string(),
+            pattern.to_string(),
+            replacement.to_string(),
+            Some(flags.to_string()),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        result.content.unwrap()
+    }
+
+    #[test]
+    fn global_flag_and_groups() {
+        assert_eq!(
+            replace("a-b a-b\nc-d\n", r"(\w)-(\w)", r"\2\1", ""),
+            "ba a-b\ndc\n"
+        );
+        assert_eq!(
+            replace("a-b a-b\n", r"(\w)-(\w)", "[&]", "g"),
+            "[a-b] [a-b]\n"
+        );
+        assert_eq!(replace("Foo foo\n", "foo", "x", "gi"), "x x\n");
+    }
+
+    #[test]
+    fn case_modifiers_and_word_boundaries() {
+        assert_eq!(
+            replace("cat concat\n", r"\<cat\>", r"\u&", "g"),
+            "Cat concat\n"
+        );
+        assert_eq!(
+            replace("hello world\n", r"(\w+) (\w+)", r"\U\1\E \2", ""),
+            "HELLO world\n"
+        );
+        assert_eq!(replace("a,b\n", ",", r"\n", ""), "a\nb\n");
+    }
+
+    #[test]
+    fn line_range_and_preview() {
+        let result = regex_replace(
+            "x\nx\nx\n".to_string(),
+            "x".to_string(),
+            "y".to_string(),
+            Some("n".to_string()),
+            Some(2),
+            Some(3),
+            None,
+        )
+        .unwrap();
+        assert!(result.content.is_none());
+        assert_eq!((result.count, result.lines), (2, 2));
+        assert_eq!(result.matches[0].range.start_line, 2);
+        assert!(regex_replace("x".into(), "(".into(), "".into(), None, None, None, None).is_err());
+        assert!(regex_replace(
+            "x".into(),
+            "x".into(),
+            "".into(),
+            Some("z".into()),
+            None,
+            None,
+            None
+        )
+        .is_err());
+    }
+}
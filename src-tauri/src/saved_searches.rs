@@ -0,0 +1,89 @@
+// 保存した検索（スマートフォルダ。アプリの設定フォルダの `saved-searches.json` に保存する）
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::app_data;
+use crate::search_index::{self, FtsHit, SearchOptions};
+use crate::state::AppState;
+use crate::tr;
+
+/// 設定フォルダ内のファイル
+const SAVED_SEARCHES_FILE: &str = "saved-searches.json";
+
+/// 保存した検索
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SavedSearch {
+    pub name: String,
+    /// `fts_search` と同じ検索語（空なら絞り込みだけ）
+    pub query: String,
+    pub options: SearchOptions,
+}
+
+fn load(app: &AppHandle) -> Result<Vec<SavedSearch>, String> {
+    app_data::read_json(&app_data::config_file(app, SAVED_SEARCHES_FILE)?)
+}
+
+fn store(app: &AppHandle, searches: &[SavedSearch]) -> Result<(), String> {
+    app_data::write_json(&app_data::config_file(app, SAVED_SEARCHES_FILE)?, searches)
+}
+
+/// 検索を保存する（同じ名前があれば置き換える）
+#[tauri::command]
+pub fn save_search(
+    app: AppHandle,
+    name: String,
+    query: String,
+    options: Option<SearchOptions>,
+) -> Result<Vec<SavedSearch>, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(tr!("Saved search name is empty"));
+    }
+    let mut searches = load(&app)?;
+    let search = SavedSearch {
+        name,
+        query,
+        options: options.unwrap_or_default(),
+    };
+    match searches.iter_mut().find(|s| s.name == search.name) {
+        Some(existing) => *existing = search,
+        None => searches.push(search),
+    }
+    store(&app, &searches)?;
+    Ok(searches)
+}
+
+/// 保存した検索の一覧（サイドバーのスマートフォルダ）
+#[tauri::command]
+pub fn list_saved_searches(app: AppHandle) -> Result<Vec<SavedSearch>, String> {
+    load(&app)
+}
+
+/// 保存した検索を削除する
+#[tauri::command]
+pub fn delete_saved_search(app: AppHandle, name: String) -> Result<Vec<SavedSearch>, String> {
+    let mut searches = load(&app)?;
+    let before = searches.len();
+    searches.retain(|s| s.name != name);
+    if searches.len() == before {
+        return Err(tr!("Saved search not found: {name}"));
+    }
+    store(&app, &searches)?;
+    Ok(searches)
+}
+
+/// 保存した検索をワークスペースで実行する
+#[tauri::command(async)]
+pub fn run_saved_search(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+    vault_root: String,
+) -> Result<Vec<FtsHit>, String> {
+    let search = load(&app)?
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| tr!("Saved search not found: {name}"))?;
+    search_index::run_search(&app, &state, &vault_root, &search.query, &search.options)
+}
@@ -0,0 +1,519 @@
+// 全文検索の索引（ワークスペースの `.mdvim/search-index.json` に保存し、変更のあった文書だけ更新する）
+//
+// SQLite（FTS5）はビルドの依存に含まれていないため、文書ごとの語の出現回数を JSON に保存し、BM25 で順位を付ける。
+// 検索のたびに更新日時だけを確認するので、ファイルを読み直すのは変更された文書に限られる。
+//
+// FTS5 との違い: 索引は検索のたびに全体を読み込んでメモリに置き、変更があれば全体を書き直す（数万件の
+// ノートでは SQLite より遅く大きい）。語の分け方は `similar::terms`（英数字は単語、かな・漢字は 2 文字ずつ）で、
+// 語幹の処理や前方一致（`term*`）・`NEAR` はない。語句の検索は保存した本文との照合で行う。
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::export;
+use crate::markdown;
+use crate::obsidian;
+use crate::refactor::read;
+use crate::semantic::modified_secs;
+use crate::similar;
+use crate::state::AppState;
+use crate::storage;
+use crate::tasks::Task;
+use crate::text::{escape_html, LineIndex};
+use crate::vault::{self, path_string};
+
+/// ワークスペース内の索引ファイル
+const INDEX_FILE: &str = ".mdvim/search-index.json";
+/// 索引の形式（変えたら作り直す）
+const INDEX_VERSION: u32 = 1;
+/// 既定の検索結果の数
+const DEFAULT_LIMIT: usize = 20;
+/// 抜粋の文字数
+const SNIPPET_CHARS: usize = 160;
+/// 抜粋で最初の一致より前に含める文字数
+const SNIPPET_CONTEXT: usize = 40;
+/// タイトル・見出し・タグの語の重み（本文の何回分に数えるか）
+const FIELD_WEIGHT: u32 = 3;
+/// BM25 の係数
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct StoredDoc {
+    /// 更新日時（UNIX 秒）
+    modified: u64,
+    title: String,
+    tags: Vec<String>,
+    /// 見出しの行番号と本文
+    headings: Vec<(usize, String)>,
+    /// 本文ブロックの行番号と本文
+    passages: Vec<(usize, String)>,
+    /// 語ごとの出現回数（タイトルなどは重みを付ける）
+    terms: HashMap<String, u32>,
+    /// 語の数（重みを含む）
+    length: u32,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct StoredIndex {
+    version: u32,
+    /// ワークスペースからの相対パスごとの文書
+    files: HashMap<String, StoredDoc>,
+}
+
+/// 読み込んだ索引（ワークスペースのルートごと）
+#[derive(Default)]
+pub struct SearchIndexState {
+    indexes: Mutex<HashMap<PathBuf, StoredIndex>>,
+}
+
+/// 索引の更新結果
+#[derive(Debug, Serialize)]
+pub struct SearchIndexSummary {
+    pub files: usize,
+    /// 異なる語の数
+    pub terms: usize,
+    /// 読み直した文書
+    pub updated: usize,
+    /// 索引から除いた文書
+    pub removed: usize,
+}
+
+/// 検索結果の並べ方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSort {
+    /// 適合する順（検索語がなければ更新日時の新しい順）
+    #[default]
+    Relevance,
+    /// 更新日時の新しい順
+    Modified,
+    Title,
+}
+
+/// 検索の絞り込みと並べ方
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    /// すべてを持つ文書に絞るタグ（`#` は不要。`project` は `project/a` にも一致する）
+    pub tags: Vec<String>,
+    /// 最近この日数のうちに更新された文書に絞る
+    pub modified_within_days: Option<u64>,
+    /// ワークスペースからの相対パスのフォルダに絞る
+    pub folder: Option<String>,
+    pub sort: SearchSort,
+    pub limit: Option<usize>,
+}
+
+/// 検索結果
+#[derive(Debug, Serialize)]
+pub struct FtsHit {
+    pub path: String,
+    pub title: String,
+    /// 抜粋の行番号（1 始まり）
+    pub line: usize,
+    /// 抜粋を含む節の見出し
+    pub heading: Option<String>,
+    /// 一致した部分を `<mark>` で囲んだ抜粋（HTML エスケープ済み）
+    pub snippet: String,
+    /// BM25 のスコア（大きいほど適合）
+    pub score: f32,
+    pub tags: Vec<String>,
+    /// 更新日時（UNIX 秒）
+    pub modified: u64,
+}
+
+/// 検索語（`"..."` は語句、`-word` は除外）
+#[derive(Debug, Default)]
+struct Query {
+    terms: Vec<String>,
+    phrases: Vec<String>,
+    excluded: Vec<String>,
+}
+
+fn parse_query(query: &str) -> Query {
+    let mut parsed = Query::default();
+    for (i, part) in query.split('"').enumerate() {
+        if i % 2 == 1 {
+            let phrase = part.trim().to_lowercase();
+            if !phrase.is_empty() {
+                parsed.terms.extend(similar::terms(&phrase));
+                parsed.phrases.push(phrase);
+            }
+            continue;
+        }
+        for word in part.split_whitespace() {
+            match word.strip_prefix('-') {
+                Some(word) => parsed.excluded.extend(similar::terms(word)),
+                None => parsed.terms.extend(similar::terms(word)),
+            }
+        }
+    }
+    parsed.terms.sort();
+    parsed.terms.dedup();
+    parsed
+}
+
+impl Query {
+    fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.phrases.is_empty() && self.excluded.is_empty()
+    }
+}
+
+fn index_doc(path: &Path, content: &str, modified: u64) -> StoredDoc {
+    let index = LineIndex::new(content);
+    let title = export::document_title(path, content);
+    let tags = obsidian::note_tags(content);
+    let headings: Vec<(usize, String)> = markdown::headings(content)
+        .into_iter()
+        .map(|h| (index.line_of(h.range.start), h.text))
+        .collect();
+    let passages: Vec<(usize, String)> = markdown::prose_blocks(content)
+        .into_iter()
+        .map(|b| (b.line, b.text))
+        .collect();
+
+    let mut terms: HashMap<String, u32> = HashMap::new();
+    for (_, text) in &passages {
+        for term in similar::terms(text) {
+            *terms.entry(term).or_default() += 1;
+        }
+    }
+    let fields = [title.as_str()]
+        .into_iter()
+        .chain(headings.iter().map(|(_, h)| h.as_str()))
+        .chain(tags.iter().map(String::as_str));
+    for field in fields {
+        for term in similar::terms(field) {
+            *terms.entry(term).or_default() += FIELD_WEIGHT;
+        }
+    }
+    StoredDoc {
+        modified,
+        length: terms.values().sum(),
+        title,
+        tags,
+        headings,
+        passages,
+        terms,
+    }
+}
+
+fn index_path(root: &Path) -> PathBuf {
+    root.join(INDEX_FILE)
+}
+
+fn load_index(root: &Path) -> StoredIndex {
+    storage::read_json::<StoredIndex>(&index_path(root))
+        .filter(|index| index.version == INDEX_VERSION)
+        .unwrap_or(StoredIndex {
+            version: INDEX_VERSION,
+            ..Default::default()
+        })
+}
+
+fn save_index(root: &Path, index: &StoredIndex) -> Result<(), String> {
+    storage::write_json(&index_path(root), index, false)
+}
+
+/// 変更された文書を読み直し、削除された文書を除く（変更があれば保存する）
+fn refresh(
+    root: &Path,
+    index: &mut StoredIndex,
+    task: &Task,
+) -> Result<SearchIndexSummary, String> {
+    let docs: Vec<PathBuf> = vault::walk_files(root)
+        .into_iter()
+        .filter(|p| vault::is_markdown(p))
+        .collect();
+    let mut files = HashMap::with_capacity(docs.len());
+    let mut updated = 0;
+    for (i, doc) in docs.iter().enumerate() {
+        task.check()?;
+        let relative = vault::to_slash(doc.strip_prefix(root).unwrap_or(doc));
+        let modified = modified_secs(doc);
+        let stored = match index.files.remove(&relative) {
+            Some(stored) if stored.modified == modified => stored,
+            _ => {
+                updated += 1;
+                index_doc(doc, &read(doc)?, modified)
+            }
+        };
+        files.insert(relative, stored);
+        task.progress(i + 1, docs.len(), || path_string(doc));
+    }
+    let removed = index.files.len();
+    index.files = files;
+    if updated > 0 || removed > 0 {
+        save_index(root, index)?;
+    }
+    let mut terms: Vec<&String> = index.files.values().flat_map(|d| d.terms.keys()).collect();
+    terms.sort();
+    terms.dedup();
+    Ok(SearchIndexSummary {
+        files: index.files.len(),
+        terms: terms.len(),
+        updated,
+        removed,
+    })
+}
+
+/// `needles` の出現範囲（重なりはまとめる）
+fn match_ranges(text: &str, needles: &[String]) -> Vec<Range<usize>> {
+    let lower = text.to_lowercase();
+    // 小文字にすると長さの変わる文字を含むときは大文字と小文字を区別する
+    let haystack = if lower.len() == text.len() {
+        &lower
+    } else {
+        text
+    };
+    let mut ranges: Vec<Range<usize>> = needles
+        .iter()
+        .filter(|n| !n.is_empty())
+        .flat_map(|n| {
+            haystack
+                .match_indices(n.as_str())
+                .map(|(i, m)| i..i + m.len())
+        })
+        .filter(|r| text.is_char_boundary(r.start) && text.is_char_boundary(r.end))
+        .collect();
+    ranges.sort_by_key(|r| (r.start, r.end));
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// 最初の一致の周りを抜き出し、一致した部分を `<mark>` で囲む
+fn snippet(text: &str, needles: &[String]) -> String {
+    let text = text.replace('\n', " ");
+    let ranges = match_ranges(&text, needles);
+    let first = ranges.first().map_or(0, |r| r.start);
+    let start = text[..first]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map_or(0, |(i, _)| i);
+    let end = text[start..]
+        .char_indices()
+        .nth(SNIPPET_CHARS)
+        .map_or(text.len(), |(i, _)| start + i);
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    let mut pos = start;
+    for range in ranges.iter().filter(|r| r.start >= start && r.end <= end) {
+        out.push_str(&escape_html(&text[pos..range.start]));
+        out.push_str("<mark>");
+        out.push_str(&escape_html(&text[range.clone()]));
+        out.push_str("</mark>");
+        pos = range.end;
+    }
+    out.push_str(&escape_html(&text[pos..end]));
+    if end < text.len() {
+        out.push('…');
+    }
+    out
+}
+
+/// 文書が語句をすべて含み、除外する語を含まないか
+fn matches(doc: &StoredDoc, query: &Query) -> bool {
+    if !query.terms.iter().all(|t| doc.terms.contains_key(t))
+        || query.excluded.iter().any(|t| doc.terms.contains_key(t))
+    {
+        return false;
+    }
+    if query.phrases.is_empty() {
+        return true;
+    }
+    let text: String = [doc.title.as_str()]
+        .into_iter()
+        .chain(doc.headings.iter().map(|(_, h)| h.as_str()))
+        .chain(doc.passages.iter().map(|(_, p)| p.as_str()))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .to_lowercase();
+    query.phrases.iter().all(|p| text.contains(p.as_str()))
+}
+
+/// 絞り込みの条件を満たすか（`since` は更新日時の下限）
+fn passes(relative: &str, doc: &StoredDoc, options: &SearchOptions, since: Option<u64>) -> bool {
+    let has_tag = |wanted: &String| {
+        let wanted = wanted.trim_start_matches('#').to_lowercase();
+        doc.tags.iter().any(|tag| {
+            let tag = tag.to_lowercase();
+            tag == wanted || tag.starts_with(&format!("{wanted}/"))
+        })
+    };
+    let in_folder = |folder: &String| {
+        let folder = folder.trim_matches('/');
+        folder.is_empty()
+            || relative
+                .strip_prefix(folder)
+                .is_some_and(|rest| rest.starts_with('/'))
+    };
+    options.tags.iter().all(has_tag)
+        && options.folder.as_ref().is_none_or(in_folder)
+        && since.is_none_or(|since| doc.modified >= since)
+}
+
+fn search(root: &Path, index: &StoredIndex, query: &Query, options: &SearchOptions) -> Vec<FtsHit> {
+    let since = options.modified_within_days.map(|days| {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        now.saturating_sub(days * 24 * 60 * 60)
+    });
+    let total = index.files.len() as f32;
+    let average = index.files.values().map(|d| d.length as f32).sum::<f32>() / total.max(1.0);
+    let idf: HashMap<&String, f32> = query
+        .terms
+        .iter()
+        .map(|term| {
+            let df = index
+                .files
+                .values()
+                .filter(|d| d.terms.contains_key(term))
+                .count() as f32;
+            (term, ((total - df + 0.5) / (df + 0.5) + 1.0).ln())
+        })
+        .collect();
+    let needles: Vec<String> = query
+        .phrases
+        .iter()
+        .cloned()
+        .chain(query.terms.iter().cloned())
+        .collect();
+
+    let mut hits: Vec<FtsHit> = index
+        .files
+        .iter()
+        .filter(|(relative, doc)| passes(relative, doc, options, since) && matches(doc, query))
+        .map(|(relative, doc)| {
+            let score = query
+                .terms
+                .iter()
+                .map(|term| {
+                    let tf = doc.terms[term] as f32;
+                    let norm = 1.0 - B + B * doc.length as f32 / average.max(1.0);
+                    idf[term] * tf * (K1 + 1.0) / (tf + K1 * norm)
+                })
+                .sum();
+            // 一致する語の最も多い本文ブロックを抜粋にする（なければ最初のブロック）
+            let best = doc
+                .passages
+                .iter()
+                .max_by_key(|(line, text)| {
+                    let hits = match_ranges(text, &needles).len();
+                    (hits, std::cmp::Reverse(*line))
+                })
+                .cloned()
+                .unwrap_or((1, doc.title.clone()));
+            let heading = doc
+                .headings
+                .iter()
+                .rev()
+                .find(|(line, _)| *line <= best.0)
+                .map(|(_, h)| h.clone());
+            FtsHit {
+                path: path_string(&root.join(relative)),
+                title: doc.title.clone(),
+                line: best.0,
+                heading,
+                snippet: snippet(&best.1, &needles),
+                score,
+                tags: doc.tags.clone(),
+                modified: doc.modified,
+            }
+        })
+        .collect();
+    match options.sort {
+        SearchSort::Relevance => hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(b.modified.cmp(&a.modified))
+                .then(a.path.cmp(&b.path))
+        }),
+        SearchSort::Modified => {
+            hits.sort_by(|a, b| b.modified.cmp(&a.modified).then(a.path.cmp(&b.path)))
+        }
+        SearchSort::Title => hits.sort_by(|a, b| a.title.cmp(&b.title).then(a.path.cmp(&b.path))),
+    }
+    hits.truncate(options.limit.unwrap_or(DEFAULT_LIMIT));
+    hits
+}
+
+/// 索引を更新してから検索する（検索語も絞り込みもなければ何も返さない）
+pub fn run_search(
+    app: &AppHandle,
+    state: &AppState,
+    vault_root: &str,
+    query: &str,
+    options: &SearchOptions,
+) -> Result<Vec<FtsHit>, String> {
+    let query = parse_query(query);
+    let filtered = !options.tags.is_empty()
+        || options.modified_within_days.is_some()
+        || options.folder.is_some();
+    if query.is_empty() && !filtered {
+        return Ok(Vec::new());
+    }
+    let task = Task::start(app, state, None)?;
+    let root = vault::normalize(Path::new(vault_root));
+    let mut indexes = state.search_index.indexes.lock().unwrap();
+    let index = indexes
+        .entry(root.clone())
+        .or_insert_with(|| load_index(&root));
+    refresh(&root, index, &task)?;
+    Ok(search(&root, index, &query, options))
+}
+
+/// 全文検索の索引を更新する（変更のあった文書だけ読み直す）
+#[tauri::command(async)]
+pub fn build_search_index(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    vault_root: String,
+    task_id: Option<String>,
+) -> Result<SearchIndexSummary, String> {
+    let task = Task::start(&app, &state, task_id)?;
+    let root = vault::normalize(Path::new(&vault_root));
+    let mut indexes = state.search_index.indexes.lock().unwrap();
+    let index = indexes
+        .entry(root.clone())
+        .or_insert_with(|| load_index(&root));
+    refresh(&root, index, &task)
+}
+
+/// ワークスペースを全文検索し、適合する順に返す
+///
+/// 語はすべて含む文書に絞る（`"..."` は語句として一致、`-word` は除外）。検索語を省くと絞り込みだけで探す。
+/// 検索の前に索引を更新するので、保存した変更はすぐに反映される。
+#[tauri::command(async)]
+pub fn fts_search(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    vault_root: String,
+    query: String,
+    options: Option<SearchOptions>,
+) -> Result<Vec<FtsHit>, String> {
+    run_search(
+        &app,
+        &state,
+        &vault_root,
+        &query,
+        &options.unwrap_or_default(),
+    )
+}
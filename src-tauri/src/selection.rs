@@ -0,0 +1,304 @@
+// 構文に沿った選択範囲の拡大（単語 → インライン → 文 → リスト項目 → ブロック → セクション）
+
+use std::ops::Range;
+
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
+use serde::Serialize;
+
+use crate::lsp::LspRange;
+use crate::markdown;
+use crate::text::{self, LineIndex};
+
+/// 選択範囲（内側から外側の順に返す）
+#[derive(Debug, Serialize)]
+pub struct SelectionRange {
+    /// "word" | "inline" | "sentence" | "table_cell" | "table_row" | "list_item" | "list"
+    /// | "block" | "code_block" | "table" | "section" | "document"
+    pub kind: String,
+    /// 記号を含む範囲（Vim の `a` テキストオブジェクト）
+    pub range: LspRange,
+    /// 記号を除いた中身（Vim の `i` テキストオブジェクト）
+    pub inner: LspRange,
+}
+
+struct Candidate {
+    kind: &'static str,
+    range: Range<usize>,
+    inner: Range<usize>,
+}
+
+/// 前後の空白を除いた範囲
+fn trim(content: &str, range: Range<usize>) -> Range<usize> {
+    let s = &content[range.clone()];
+    let start = range.start + (s.len() - s.trim_start().len());
+    start..(range.end - (s.len() - s.trim_end().len())).max(start)
+}
+
+/// 文字の種類（単語の区切りの判定用）
+fn char_class(c: char) -> u8 {
+    if c.is_whitespace() {
+        0
+    } else if text::is_hiragana(c) {
+        1
+    } else if text::is_katakana(c) {
+        2
+    } else if text::is_kanji(c) {
+        3
+    } else if c.is_alphanumeric() || c == '_' {
+        4
+    } else {
+        5
+    }
+}
+
+/// カーソル位置の単語（空白と記号は単語にしない）
+fn word(content: &str, offset: usize) -> Option<Range<usize>> {
+    let c = content[offset..]
+        .chars()
+        .next()
+        .filter(|&c| !matches!(char_class(c), 0 | 5))
+        .or_else(|| content[..offset].chars().next_back())?;
+    let class = char_class(c);
+    if matches!(class, 0 | 5) {
+        return None;
+    }
+    let start = content[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| char_class(c) == class)
+        .last()
+        .map_or(offset, |(i, _)| i);
+    let end = content[offset..]
+        .char_indices()
+        .find(|&(_, c)| char_class(c) != class)
+        .map_or(content.len(), |(i, _)| offset + i);
+    Some(start..end)
+}
+
+/// インライン記法の中身（強調・取り消し線の記号、リンクの `[...]`、コードの `` ` ``）
+fn inline_inner(content: &str, range: &Range<usize>, tag: Option<&Tag>) -> Range<usize> {
+    let s = &content[range.clone()];
+    let (start, end) = match tag {
+        Some(Tag::Link { .. } | Tag::Image { .. }) => {
+            let open = s.find('[').map_or(0, |i| i + 1);
+            let close = s.rfind("](").or_else(|| s.rfind(']')).unwrap_or(s.len());
+            (open, close.max(open))
+        }
+        Some(_) => {
+            let marks = |c: char| matches!(c, '*' | '_' | '~' | '=');
+            let lead = s.len() - s.trim_start_matches(marks).len();
+            let trail = s.len() - s.trim_end_matches(marks).len();
+            (lead, s.len().saturating_sub(trail).max(lead))
+        }
+        None => {
+            let lead = s.len() - s.trim_start_matches(['`', '$']).len();
+            let trail = s.len() - s.trim_end_matches(['`', '$']).len();
+            (lead, s.len().saturating_sub(trail).max(lead))
+        }
+    };
+    range.start + start..range.start + end
+}
+
+/// リスト項目の記号（`- `・`1. `・`- [ ] `）の後ろ
+fn item_inner(content: &str, range: &Range<usize>) -> Range<usize> {
+    let s = &content[range.clone()];
+    let body = s.trim_start();
+    let after_marker = match body.chars().next() {
+        Some('-' | '*' | '+') => &body[1..],
+        _ => body.trim_start_matches(|c: char| c.is_ascii_digit()),
+    };
+    let after_marker = after_marker
+        .strip_prefix(['.', ')'])
+        .unwrap_or(after_marker)
+        .trim_start_matches([' ', '\t']);
+    let after_task = ["[ ] ", "[x] ", "[X] "]
+        .iter()
+        .find_map(|t| after_marker.strip_prefix(t))
+        .unwrap_or(after_marker);
+    let start = range.start + (s.len() - after_task.len());
+    trim(content, start..range.end)
+}
+
+/// フェンスのコードブロックの中身（フェンスの行を除く）
+fn code_inner(content: &str, range: &Range<usize>, fenced: bool) -> Range<usize> {
+    let s = &content[range.clone()];
+    if !fenced {
+        return range.clone();
+    }
+    let start = s.find('\n').map_or(s.len(), |i| i + 1);
+    let body = s[start..].trim_end_matches(['\n', '\r']);
+    let end = match body.rfind('\n') {
+        Some(i) if body[i + 1..].trim_start().starts_with(['`', '~']) => i + 1,
+        None if body.trim_start().starts_with(['`', '~']) => 0,
+        _ => body.len(),
+    };
+    range.start + start..range.start + start + end
+}
+
+/// ブロック内の文の範囲
+fn sentences(content: &str, block: Range<usize>) -> Vec<Range<usize>> {
+    let s = &content[block.clone()];
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let end = match c {
+            '。' | '！' | '？' => Some(i + c.len_utf8()),
+            '.' | '!' | '?' => match chars.peek() {
+                Some(&(_, next)) if next.is_whitespace() => Some(i + 1),
+                None => Some(i + 1),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(end) = end {
+            sentences.push(trim(content, block.start + start..block.start + end));
+            start = end;
+        }
+    }
+    if !s[start..].trim().is_empty() {
+        sentences.push(trim(content, block.start + start..block.end));
+    }
+    sentences
+}
+
+fn candidates(content: &str, offset: usize) -> Vec<Candidate> {
+    let mut found = Vec::new();
+    let mut push = |kind, range: Range<usize>, inner: Range<usize>| {
+        if range.start <= offset && offset <= range.end {
+            found.push(Candidate { kind, range, inner });
+        }
+    };
+    if let Some(range) = word(content, offset) {
+        push("word", range.clone(), range);
+    }
+
+    let mut sentence_blocks = Vec::new();
+    for (event, range) in Parser::new_ext(content, markdown::parser_options()).into_offset_iter() {
+        if offset < range.start || range.end < offset {
+            continue;
+        }
+        match event {
+            Event::Start(
+                tag @ (Tag::Emphasis
+                | Tag::Strong
+                | Tag::Strikethrough
+                | Tag::Link { .. }
+                | Tag::Image { .. }),
+            ) => {
+                let inner = inline_inner(content, &range, Some(&tag));
+                push("inline", range, inner);
+            }
+            Event::Code(_) | Event::InlineMath(_) | Event::DisplayMath(_) => {
+                let inner = inline_inner(content, &range, None);
+                push("inline", range, inner);
+            }
+            Event::Start(Tag::Paragraph) => {
+                sentence_blocks.push(range.clone());
+                push("block", trim(content, range.clone()), trim(content, range));
+            }
+            Event::Start(Tag::Heading { .. }) => {
+                let heading = trim(content, range);
+                let inner = markdown::headings(content)
+                    .into_iter()
+                    .find(|h| h.range.start == heading.start)
+                    .map_or(heading.clone(), |h| h.text_range);
+                sentence_blocks.push(inner.clone());
+                push("block", heading, inner);
+            }
+            Event::Start(Tag::TableCell) => {
+                let inner = trim(content, range.clone());
+                let inner = trim(
+                    content,
+                    inner.start + usize::from(content[inner.clone()].starts_with('|'))
+                        ..inner.end - usize::from(content[inner.clone()].ends_with('|')),
+                );
+                push("table_cell", range, inner);
+            }
+            Event::Start(Tag::TableHead | Tag::TableRow) => {
+                let range = trim(content, range);
+                push("table_row", range.clone(), range);
+            }
+            Event::Start(Tag::Table(_)) => {
+                let range = trim(content, range);
+                push("table", range.clone(), range);
+            }
+            Event::Start(Tag::Item) => {
+                let inner = item_inner(content, &range);
+                sentence_blocks.push(inner.clone());
+                push("list_item", trim(content, range), inner);
+            }
+            Event::Start(Tag::List(_)) => {
+                let range = trim(content, range);
+                push("list", range.clone(), range);
+            }
+            Event::Start(Tag::BlockQuote(_)) => {
+                let range = trim(content, range);
+                push("block", range.clone(), range);
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let fenced = matches!(kind, CodeBlockKind::Fenced(_));
+                let inner = code_inner(content, &range, fenced);
+                push("code_block", trim(content, range), inner);
+            }
+            _ => {}
+        }
+    }
+    for block in sentence_blocks {
+        if let Some(sentence) = sentences(content, block)
+            .into_iter()
+            .find(|s| s.start <= offset && offset <= s.end)
+        {
+            push("sentence", sentence.clone(), sentence);
+        }
+    }
+
+    // セクションの中身は見出しの行を除く
+    let headings = markdown::headings(content);
+    for (i, heading) in headings.iter().enumerate() {
+        let end = headings[i + 1..]
+            .iter()
+            .find(|h| h.level <= heading.level)
+            .map_or(content.len(), |h| h.range.start);
+        let range = trim(content, heading.range.start..end);
+        let inner = trim(content, heading.range.end.min(range.end)..range.end);
+        push("section", range, inner);
+    }
+    push(
+        "document",
+        0..content.len(),
+        trim(content, 0..content.len()),
+    );
+    found
+}
+
+/// `offset`（エディタの UTF-16 オフセット）を含む構文上の範囲を内側から順に返す
+#[tauri::command]
+pub fn get_selection_ranges(content: String, offset: usize) -> Vec<SelectionRange> {
+    let offset = text::byte_offset(&content, offset);
+    let mut candidates = candidates(&content, offset);
+    // 狭い順に並べ、外側が内側を含むものだけを残す
+    candidates.sort_by_key(|c| c.range.len());
+    let mut chain: Vec<Candidate> = Vec::new();
+    for candidate in candidates {
+        match chain.last() {
+            Some(last) if last.range == candidate.range => continue,
+            Some(last)
+                if candidate.range.start > last.range.start
+                    || candidate.range.end < last.range.end =>
+            {
+                continue
+            }
+            _ => chain.push(candidate),
+        }
+    }
+    let index = LineIndex::new(&content);
+    chain
+        .into_iter()
+        .map(|c| SelectionRange {
+            kind: c.kind.to_string(),
+            range: LspRange::from_offsets(&index, &content, c.range),
+            inner: LspRange::from_offsets(&index, &content, c.inner),
+        })
+        .collect()
+}
@@ -0,0 +1,426 @@
+// 埋め込みベクトルによる意味検索（索引はワークスペースの `.mdvim/embeddings.json` に保存する）
+//
+// 埋め込みの生成元:
+// - `hashing` … 語のハッシュによる組み込みのベクトル（外部のモデル不要。語の重なりに基づく）
+// - `ollama` … Ollama などローカルで動くモデル（`/api/embed`）
+// - `open_ai` … OpenAI 互換の API（`/embeddings`。LM Studio や llama.cpp のサーバーも可）
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, State};
+use tauri_plugin_http::reqwest;
+
+use crate::markdown;
+use crate::refactor::read;
+use crate::similar;
+use crate::state::AppState;
+use crate::storage;
+use crate::tasks::Task;
+use crate::tr;
+use crate::vault::{self, path_string, Vault};
+
+/// ワークスペース内の索引ファイル
+const INDEX_FILE: &str = ".mdvim/embeddings.json";
+/// 1 つの文節（検索結果の単位）の目安の文字数
+const PASSAGE_CHARS: usize = 600;
+/// 1 回の API 呼び出しで埋め込む文節の数
+const BATCH_SIZE: usize = 32;
+/// 埋め込みの API 呼び出しのタイムアウト（ローカルのモデルは最初の読み込みに時間がかかる）
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 埋め込みの生成元
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EmbeddingProvider {
+    Hashing {
+        #[serde(default = "default_dimensions")]
+        dimensions: usize,
+    },
+    Ollama {
+        #[serde(default = "default_ollama_url")]
+        url: String,
+        model: String,
+    },
+    OpenAi {
+        #[serde(default = "default_openai_url")]
+        url: String,
+        model: String,
+        #[serde(default)]
+        api_key: Option<String>,
+    },
+}
+
+fn default_dimensions() -> usize {
+    256
+}
+
+pub fn default_ollama_url() -> String {
+    "http://localhost:11434".to_string()
+}
+
+pub fn default_openai_url() -> String {
+    "https://api.openai.com/v1".to_string()
+}
+
+impl Default for EmbeddingProvider {
+    fn default() -> Self {
+        Self::Hashing {
+            dimensions: default_dimensions(),
+        }
+    }
+}
+
+impl EmbeddingProvider {
+    /// 索引と照合する識別子（モデルが変われば索引を作り直す）
+    fn id(&self) -> String {
+        match self {
+            Self::Hashing { dimensions } => format!("hashing:{dimensions}"),
+            Self::Ollama { model, .. } => format!("ollama:{model}"),
+            Self::OpenAi { url, model, .. } => format!("open_ai:{url}:{model}"),
+        }
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let vectors: Vec<Vec<f32>> = match self {
+            Self::Hashing { dimensions } => texts
+                .iter()
+                .map(|t| hashing_vector(t, (*dimensions).max(1)))
+                .collect(),
+            Self::Ollama { url, model } => {
+                let url = format!("{}/api/embed", url.trim_end_matches('/'));
+                let response =
+                    post_json(&url, None, json!({ "model": model, "input": texts })).await?;
+                response["embeddings"]
+                    .as_array()
+                    .ok_or_else(|| tr!("Invalid response from embedding server"))?
+                    .iter()
+                    .map(parse_vector)
+                    .collect::<Result<_, _>>()?
+            }
+            Self::OpenAi {
+                url,
+                model,
+                api_key,
+            } => {
+                let url = format!("{}/embeddings", url.trim_end_matches('/'));
+                let body = json!({ "model": model, "input": texts });
+                let response = post_json(&url, api_key.as_deref(), body).await?;
+                let mut data: Vec<&Value> = response["data"]
+                    .as_array()
+                    .ok_or_else(|| tr!("Invalid response from embedding server"))?
+                    .iter()
+                    .collect();
+                data.sort_by_key(|d| d["index"].as_u64().unwrap_or(0));
+                data.iter()
+                    .map(|d| parse_vector(&d["embedding"]))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        if vectors.len() != texts.len() {
+            return Err(tr!("Embedding server returned a wrong number of vectors"));
+        }
+        Ok(vectors.into_iter().map(normalized).collect())
+    }
+}
+
+/// 意味検索の設定
+#[derive(Default)]
+pub struct SemanticState {
+    provider: Mutex<EmbeddingProvider>,
+}
+
+/// `semantic-index-progress` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanticIndexProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+/// 索引の更新結果
+#[derive(Debug, Serialize)]
+pub struct SemanticIndexSummary {
+    pub files: usize,
+    pub passages: usize,
+    /// 埋め込みを作り直した文書
+    pub updated: usize,
+}
+
+/// 検索結果の文節
+#[derive(Debug, Serialize)]
+pub struct SemanticHit {
+    pub path: String,
+    /// 文節の先頭の行番号（1 始まり）
+    pub line: usize,
+    pub text: String,
+    /// コサイン類似度
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct StoredPassage {
+    line: usize,
+    text: String,
+    /// f32 のリトルエンディアンを Base64 にしたもの
+    vector: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct StoredFile {
+    /// 更新日時（UNIX 秒）
+    modified: u64,
+    passages: Vec<StoredPassage>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct StoredIndex {
+    provider: String,
+    /// ワークスペースからの相対パスごとの文節
+    files: HashMap<String, StoredFile>,
+}
+
+async fn post_json(url: &str, api_key: Option<&str>, body: Value) -> Result<Value, String> {
+    let mut request = reqwest::Client::new()
+        .post(url)
+        .timeout(REQUEST_TIMEOUT)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string());
+    if let Some(key) = api_key.filter(|k| !k.is_empty()) {
+        request = request.bearer_auth(key);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| tr!("Failed to connect to {url}: {e}"))?;
+    let status = response.status();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| tr!("Failed to read response from {url}: {e}"))?;
+    if !status.is_success() {
+        return Err(tr!("Embedding request failed ({status}): {text}"));
+    }
+    serde_json::from_str(&text).map_err(|e| tr!("Invalid response from {url}: {e}"))
+}
+
+fn parse_vector(value: &Value) -> Result<Vec<f32>, String> {
+    value
+        .as_array()
+        .ok_or_else(|| tr!("Invalid embedding in response"))?
+        .iter()
+        .map(|v| {
+            v.as_f64()
+                .map(|f| f as f32)
+                .ok_or_else(|| tr!("Invalid embedding in response"))
+        })
+        .collect()
+}
+
+fn normalized(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+    vector
+}
+
+/// 語のハッシュを次元に割り振ったベクトル（符号もハッシュで決める）
+fn hashing_vector(text: &str, dimensions: usize) -> Vec<f32> {
+    let mut counts: HashMap<String, f32> = HashMap::new();
+    for term in similar::terms(text) {
+        *counts.entry(term).or_default() += 1.0;
+    }
+    let mut vector = vec![0.0f32; dimensions];
+    for (term, tf) in counts {
+        let hash = crc32fast::hash(term.as_bytes());
+        let sign = if hash & 0x8000_0000 == 0 { 1.0 } else { -1.0 };
+        vector[(hash as usize & 0x7fff_ffff) % dimensions] += sign * (1.0 + tf.ln());
+    }
+    vector
+}
+
+fn encode_vector(vector: &[f32]) -> String {
+    let bytes: Vec<u8> = vector.iter().flat_map(|v| v.to_le_bytes()).collect();
+    STANDARD.encode(bytes)
+}
+
+fn decode_vector(encoded: &str) -> Vec<f32> {
+    STANDARD
+        .decode(encoded)
+        .unwrap_or_default()
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+/// 本文を段落単位でまとめた文節（先頭の行番号と本文）
+fn passages(content: &str) -> Vec<(usize, String)> {
+    let mut passages: Vec<(usize, String)> = Vec::new();
+    for block in markdown::prose_blocks(content) {
+        let text = block.text.trim();
+        match passages.last_mut() {
+            Some((_, current)) if current.chars().count() < PASSAGE_CHARS => {
+                current.push('\n');
+                current.push_str(text);
+            }
+            _ => passages.push((block.line, text.to_string())),
+        }
+    }
+    passages
+}
+
+/// 更新日時（UNIX 秒。取得できなければ 0）
+pub fn modified_secs(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
+fn index_path(root: &Path) -> PathBuf {
+    root.join(INDEX_FILE)
+}
+
+fn load_index(root: &Path) -> Option<StoredIndex> {
+    storage::read_json(&index_path(root))
+}
+
+/// 埋め込みの生成元を設定
+#[tauri::command]
+pub fn configure_embeddings(
+    state: State<'_, AppState>,
+    provider: EmbeddingProvider,
+) -> Result<(), String> {
+    if let EmbeddingProvider::Ollama { model, .. } | EmbeddingProvider::OpenAi { model, .. } =
+        &provider
+    {
+        if model.trim().is_empty() {
+            return Err(tr!("Embedding model is not specified"));
+        }
+    }
+    *state.semantic.provider.lock().unwrap() = provider;
+    Ok(())
+}
+
+/// ワークスペースの索引を更新（変更のあった文書だけ埋め込みを作り直す）
+#[tauri::command]
+pub async fn build_semantic_index(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    vault_root: String,
+    task_id: Option<String>,
+) -> Result<SemanticIndexSummary, String> {
+    let task = Task::start(&app, &state, task_id)?;
+    let provider = state.semantic.provider.lock().unwrap().clone();
+    let vault = Vault::scan(Path::new(&vault_root));
+    let mut index = load_index(&vault.root)
+        .filter(|index| index.provider == provider.id())
+        .unwrap_or_default();
+    index.provider = provider.id();
+
+    let docs: Vec<&PathBuf> = vault.markdown_files().collect();
+    let mut files = HashMap::new();
+    let mut updated = 0;
+    for (i, doc) in docs.iter().enumerate() {
+        task.check()?;
+        let relative = vault::to_slash(doc.strip_prefix(&vault.root).unwrap_or(doc));
+        let modified = modified_secs(doc);
+        let file = match index.files.remove(&relative) {
+            Some(file) if file.modified == modified => file,
+            _ => {
+                let content = read(doc)?;
+                let passages = passages(&content);
+                let mut stored = Vec::with_capacity(passages.len());
+                for batch in passages.chunks(BATCH_SIZE) {
+                    let texts: Vec<String> = batch.iter().map(|(_, t)| t.clone()).collect();
+                    let vectors = provider.embed(&texts).await?;
+                    stored.extend(batch.iter().zip(vectors).map(|((line, text), vector)| {
+                        StoredPassage {
+                            line: *line,
+                            text: text.clone(),
+                            vector: encode_vector(&vector),
+                        }
+                    }));
+                }
+                updated += 1;
+                StoredFile {
+                    modified,
+                    passages: stored,
+                }
+            }
+        };
+        files.insert(relative, file);
+        let done = i + 1;
+        task.progress(done, docs.len(), || path_string(doc));
+        if done % 20 == 0 || done == docs.len() {
+            let _ = app.emit(
+                "semantic-index-progress",
+                SemanticIndexProgress {
+                    done,
+                    total: docs.len(),
+                },
+            );
+        }
+    }
+    // 削除された文書は索引からも除く
+    index.files = files;
+
+    storage::write_json(&index_path(&vault.root), &index, false)?;
+    Ok(SemanticIndexSummary {
+        files: index.files.len(),
+        passages: index.files.values().map(|f| f.passages.len()).sum(),
+        updated,
+    })
+}
+
+/// 質問文に意味の近い文節を `k` 件返す（事前に `build_semantic_index` が必要）
+#[tauri::command]
+pub async fn semantic_search(
+    state: State<'_, AppState>,
+    vault_root: String,
+    query: String,
+    k: Option<usize>,
+) -> Result<Vec<SemanticHit>, String> {
+    let provider = state.semantic.provider.lock().unwrap().clone();
+    let root = vault::normalize(Path::new(&vault_root));
+    let index = load_index(&root).ok_or_else(|| tr!("Semantic index is not built"))?;
+    if index.provider != provider.id() {
+        return Err(tr!(
+            "Semantic index was built with a different embedding model; rebuild it"
+        ));
+    }
+    let query = provider
+        .embed(&[query])
+        .await?
+        .pop()
+        .ok_or_else(|| tr!("Failed to embed query"))?;
+
+    let mut hits: Vec<SemanticHit> = index
+        .files
+        .iter()
+        .flat_map(|(relative, file)| {
+            let (query, root) = (&query, &root);
+            file.passages.iter().map(move |passage| {
+                let vector = decode_vector(&passage.vector);
+                let score = vector.iter().zip(query).map(|(a, b)| a * b).sum();
+                SemanticHit {
+                    path: path_string(&root.join(relative)),
+                    line: passage.line,
+                    text: passage.text.clone(),
+                    score,
+                }
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(k.unwrap_or(10));
+    Ok(hits)
+}
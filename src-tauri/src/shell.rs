@@ -0,0 +1,397 @@
+// シェルのコマンドの実行と出力の取り込み（Vim の `:r !date` `:!make`）
+//
+// 出力は `shell-output` イベントで逐次送り、終わったらまとめて返す。
+// 任意のコマンドを実行するため、信頼したワークスペースでだけ使える（`trust`）。
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::hooks;
+use crate::state::AppState;
+use crate::tasks::{cancelled_error, Task};
+use crate::tr;
+
+/// 既定のタイムアウト
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// 取り込む出力の上限（それ以降はイベントでも結果でも読み捨てる）
+const MAX_OUTPUT: usize = 4 << 20;
+
+/// 出力の種類
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// `shell-output` イベント
+#[derive(Debug, Clone, Serialize)]
+pub struct ShellOutput {
+    pub task_id: Option<String>,
+    pub stream: OutputStream,
+    pub text: String,
+}
+
+/// `run_shell_capture` の結果
+#[derive(Debug, Serialize)]
+pub struct ShellCapture {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub timed_out: bool,
+    /// 出力が上限を超えて途中で切った
+    pub truncated: bool,
+    pub duration_ms: u64,
+}
+
+/// 出力を送り終えてからパイプが閉じるまで待つ時間（孫のプロセスがパイプを持ち続けても戻れるように）
+const READER_GRACE: Duration = Duration::from_secs(1);
+
+/// 送ってよいバイト数（末尾の途中の文字は次の読み込みまで残す）
+fn text_end(bytes: &[u8]) -> usize {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => bytes.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => bytes.len(),
+    }
+}
+
+/// 読み込んだ出力（上限を超えたかどうかと一緒に、読み込みのスレッドと共有する）
+type Output = Arc<Mutex<(Vec<u8>, bool)>>;
+
+/// 出力を上限まで読み（残りは読み捨てる）、`events` があれば読むたびに `shell-output` を送る
+fn read_output(
+    mut reader: impl Read,
+    limit: usize,
+    output: &Output,
+    events: Option<(AppHandle, Option<String>, OutputStream)>,
+) {
+    let mut sent = 0;
+    let mut buf = [0u8; 8192];
+    while let Ok(n) = reader.read(&mut buf) {
+        if n == 0 {
+            break;
+        }
+        let mut output = output.lock().unwrap();
+        let (data, truncated) = &mut *output;
+        let room = limit.saturating_sub(data.len());
+        if n > room {
+            *truncated = true;
+        }
+        data.extend_from_slice(&buf[..n.min(room)]);
+        if let Some((app, task_id, stream)) = &events {
+            let end = sent + text_end(&data[sent..]);
+            if end > sent {
+                let _ = app.emit(
+                    "shell-output",
+                    ShellOutput {
+                        task_id: task_id.clone(),
+                        stream: *stream,
+                        text: String::from_utf8_lossy(&data[sent..end]).into_owned(),
+                    },
+                );
+                sent = end;
+            }
+        }
+    }
+}
+
+/// 読み込みのスレッドが終わるのを `deadline` まで待ち、それまでに読んだ出力を取り出す
+fn take_output(reader: Option<(JoinHandle<()>, Output)>, deadline: Instant) -> (Vec<u8>, bool) {
+    let Some((handle, output)) = reader else {
+        return (Vec::new(), false);
+    };
+    while !handle.is_finished() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    let taken = std::mem::take(&mut *output.lock().unwrap());
+    taken
+}
+
+/// コマンドと、そこから起動したプロセスをまとめて止める
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    // SAFETY: `killpg` は引数を読むだけで、起動時に作ったプロセスグループだけに送る
+    unsafe {
+        libc::killpg(child.id() as libc::pid_t, libc::SIGKILL);
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// `run` の設定
+pub struct RunOptions<'a> {
+    /// 標準入力に渡す内容（`None` なら標準入力を閉じる）
+    pub input: Option<Vec<u8>>,
+    pub timeout: Duration,
+    /// 標準出力と標準エラー出力それぞれの上限
+    pub max_stdout: usize,
+    pub max_stderr: usize,
+    /// 出力を `shell-output` で逐次送る（`task_id` を付ける）
+    pub events: Option<(&'a AppHandle, Option<String>)>,
+    /// 中断されたらコマンドを止めてエラーにする
+    pub task: Option<&'a Task<'a>>,
+}
+
+impl Default for RunOptions<'_> {
+    fn default() -> Self {
+        Self {
+            input: None,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            max_stdout: MAX_OUTPUT,
+            max_stderr: MAX_OUTPUT,
+            events: None,
+            task: None,
+        }
+    }
+}
+
+/// `run` の結果（出力はバイト列のまま）
+pub struct RunOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub stdout_truncated: bool,
+    pub stderr_truncated: bool,
+    /// 終了の状態（止めた・確かめられなかった場合は `None`）
+    pub status: Option<ExitStatus>,
+    pub timed_out: bool,
+    pub duration: Duration,
+}
+
+/// コマンドを実行して出力を取り込む（終わりを待つ外部コマンドはすべてこれか `run_if_found` を使う。`name` はエラーに示す名前）
+///
+/// Unix ではコマンドを新しいプロセスグループで起動し、タイムアウトや中断のときはグループごと止める。
+/// それでもパイプが閉じない場合は、少し待ってからそれまでの出力で戻る。
+/// LSP のサーバーや録音・読み上げなど、止めるまで動き続けるプロセスはそれぞれのモジュールで管理する。
+pub fn run(mut command: Command, name: &str, options: RunOptions) -> Result<RunOutput, String> {
+    let started = Instant::now();
+    let child = spawn(&mut command, options.input.is_some())
+        .map_err(|e| tr!("Failed to run {name}: {e}"))?;
+    wait(child, options, started)
+}
+
+/// `run` と同じだが、コマンドが見つからなければ `None` を返す（候補のツールを順に試す場合用）
+pub fn run_if_found(
+    mut command: Command,
+    name: &str,
+    options: RunOptions,
+) -> Result<Option<RunOutput>, String> {
+    let started = Instant::now();
+    match spawn(&mut command, options.input.is_some()) {
+        Ok(child) => wait(child, options, started).map(Some),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(tr!("Failed to run {name}: {e}")),
+    }
+}
+
+/// 出力をパイプにつないでコマンドを起動する（Unix では新しいプロセスグループにする）
+fn spawn(command: &mut Command, input: bool) -> io::Result<Child> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    command
+        .stdin(if input { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+}
+
+/// 起動したコマンドに入力を渡し、終わるかタイムアウトか中断まで出力を読む
+fn wait(mut child: Child, options: RunOptions, started: Instant) -> Result<RunOutput, String> {
+    // 出力が大きい場合に詰まらないよう、入力は別スレッドで書き込む（終わりは待たない）
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), options.input) {
+        thread::spawn(move || {
+            let _ = stdin.write_all(&input);
+        });
+    }
+    let spawn_reader = |out: Box<dyn Read + Send>, limit: usize, stream: OutputStream| {
+        let output: Output = Arc::default();
+        let events = options
+            .events
+            .as_ref()
+            .map(|(app, task_id)| ((*app).clone(), task_id.clone(), stream));
+        let shared = output.clone();
+        let handle = thread::spawn(move || read_output(out, limit, &shared, events));
+        (handle, output)
+    };
+    let stdout = child
+        .stdout
+        .take()
+        .map(|out| spawn_reader(Box::new(out), options.max_stdout, OutputStream::Stdout));
+    let stderr = child
+        .stderr
+        .take()
+        .map(|err| spawn_reader(Box::new(err), options.max_stderr, OutputStream::Stderr));
+
+    let mut timed_out = false;
+    let mut cancelled = false;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) => {
+                cancelled = options.task.is_some_and(|task| task.check().is_err());
+                timed_out = started.elapsed() >= options.timeout;
+                if cancelled || timed_out {
+                    kill(&mut child);
+                    break None;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            Err(_) => break None,
+        }
+    };
+    let deadline = Instant::now() + READER_GRACE;
+    let (stdout, stdout_truncated) = take_output(stdout, deadline);
+    let (stderr, stderr_truncated) = take_output(stderr, deadline);
+    if cancelled {
+        return Err(cancelled_error());
+    }
+    Ok(RunOutput {
+        stdout,
+        stderr,
+        stdout_truncated,
+        stderr_truncated,
+        status,
+        timed_out,
+        duration: started.elapsed(),
+    })
+}
+
+/// コマンドを実行して出力を取り込む（`input` は標準入力に渡す。`name` はエラーに示す名前）
+///
+/// 出力は `shell-output` で逐次送る。`task` が中断されたらコマンドを止めてエラーにする。
+pub fn capture(
+    app: &AppHandle,
+    task: &Task,
+    task_id: Option<String>,
+    command: Command,
+    name: &str,
+    input: Option<String>,
+    timeout: Duration,
+) -> Result<ShellCapture, String> {
+    let output = run(
+        command,
+        name,
+        RunOptions {
+            input: input.map(String::into_bytes),
+            timeout,
+            events: Some((app, task_id)),
+            task: Some(task),
+            ..Default::default()
+        },
+    )?;
+    Ok(ShellCapture {
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        exit_code: output.status.and_then(|s| s.code()),
+        success: output.status.is_some_and(|s| s.success()),
+        timed_out: output.timed_out,
+        truncated: output.stdout_truncated || output.stderr_truncated,
+        duration_ms: output.duration.as_millis() as u64,
+    })
+}
+
+/// パスのフォルダ（ファイルならその親）でコマンドを実行するようにする（ファイルは `MDVIM_FILE` でも渡す）
+pub fn set_cwd(command: &mut Command, path: &Path) {
+    match if path.is_dir() {
+        Some(path)
+    } else {
+        path.parent()
+    } {
+        Some(dir) if dir.is_dir() => {
+            command.current_dir(dir);
+        }
+        _ => {}
+    }
+    if path.is_file() {
+        command.env("MDVIM_FILE", path);
+    }
+}
+
+/// シェルでコマンドを実行し、標準出力・標準エラー出力・終了コードを返す
+///
+/// `cwd`（ファイルならそのフォルダ）で実行し、そのワークスペースを信頼していなければエラーにする。
+/// 出力は `shell-output` で逐次送る（`task_id` を付ける）。`cancel_task` で中断できる。
+/// 終了コードが 0 でなくてもエラーにはしない。
+#[tauri::command(async)]
+pub fn run_shell_capture(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    cmd: String,
+    cwd: String,
+    timeout_secs: Option<u64>,
+    task_id: Option<String>,
+) -> Result<ShellCapture, String> {
+    let cwd = Path::new(&cwd);
+    state.trust.require(&app, cwd, "shell commands")?;
+    let cmd = cmd.trim();
+    if cmd.is_empty() {
+        return Err(tr!("No shell command"));
+    }
+    let task = Task::start(&app, &state, task_id.clone())?;
+    let mut command = hooks::shell(cmd);
+    set_cwd(&mut command, cwd);
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    capture(&app, &task, task_id, command, cmd, None, timeout)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeout_stops_the_whole_pipeline() {
+        let started = Instant::now();
+        let output = run(
+            hooks::shell("sleep 30 | cat"),
+            "sleep",
+            RunOptions {
+                timeout: Duration::from_millis(300),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(output.timed_out);
+        assert!(output.status.is_none());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn captures_output_and_input() {
+        let output = run(
+            hooks::shell("cat; echo err >&2"),
+            "cat",
+            RunOptions {
+                input: Some(b"hello".to_vec()),
+                max_stdout: 3,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(output.stdout, b"hel");
+        assert!(output.stdout_truncated);
+        assert_eq!(output.stderr, b"err\n");
+        assert!(output.status.is_some_and(|s| s.success()));
+    }
+
+    #[test]
+    fn missing_commands_are_skipped_only_when_asked() {
+        let missing = || Command::new("mdvim-no-such-command");
+        let skipped = run_if_found(missing(), "missing", RunOptions::default()).unwrap();
+        assert!(skipped.is_none());
+        assert!(run(missing(), "missing", RunOptions::default()).is_err());
+        let found = run_if_found(hooks::shell("true"), "true", RunOptions::default()).unwrap();
+        assert!(found.is_some_and(|o| o.status.is_some_and(|s| s.success())));
+    }
+}
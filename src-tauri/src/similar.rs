@@ -0,0 +1,196 @@
+// 内容の似たノートの検索（TF-IDF のコサイン類似度）
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+use crate::links;
+use crate::markdown;
+use crate::refactor::read;
+use crate::state::AppState;
+use crate::tasks::Task;
+use crate::text;
+use crate::vault::{self, path_string, Vault};
+
+/// 類似度の計算から除く英単語
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "in",
+    "is", "it", "its", "of", "on", "or", "that", "the", "this", "to", "was", "were", "will",
+    "with", "you", "not", "can", "we", "they", "he", "she", "do", "does", "if", "so", "than",
+];
+
+/// 結果に含める共通の語の数
+const SHARED_TERMS: usize = 5;
+
+/// 似たノート
+#[derive(Debug, Serialize)]
+pub struct SimilarNote {
+    pub path: String,
+    /// コサイン類似度（0〜1）
+    pub score: f32,
+    /// どちらかからもう一方へのリンクがある
+    pub linked: bool,
+    /// 類似度への寄与の大きい共通の語
+    pub shared_terms: Vec<String>,
+}
+
+/// 本文を語に分ける（英数字は単語、かな・漢字は 2 文字ずつ）
+pub fn terms(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut word = String::new();
+    let mut cjk: Vec<char> = Vec::new();
+    let flush_word = |word: &mut String, terms: &mut Vec<String>| {
+        if word.chars().count() >= 2 && !STOP_WORDS.contains(&word.as_str()) {
+            terms.push(std::mem::take(word));
+        }
+        word.clear();
+    };
+    let flush_cjk = |cjk: &mut Vec<char>, terms: &mut Vec<String>| {
+        if cjk.len() == 1 && text::is_kanji(cjk[0]) {
+            terms.push(cjk[0].to_string());
+        }
+        // ひらがなだけの組（助詞など）は除く
+        for pair in cjk.windows(2) {
+            if !(text::is_hiragana(pair[0]) && text::is_hiragana(pair[1])) {
+                terms.push(pair.iter().collect());
+            }
+        }
+        cjk.clear();
+    };
+    for c in text.chars() {
+        if text::is_cjk(c) {
+            flush_word(&mut word, &mut terms);
+            cjk.push(c);
+        } else if c.is_alphanumeric() || c == '_' {
+            flush_cjk(&mut cjk, &mut terms);
+            word.extend(c.to_lowercase());
+        } else {
+            flush_word(&mut word, &mut terms);
+            flush_cjk(&mut cjk, &mut terms);
+        }
+    }
+    flush_word(&mut word, &mut terms);
+    flush_cjk(&mut cjk, &mut terms);
+    terms
+}
+
+fn term_counts(content: &str) -> HashMap<String, f32> {
+    let mut counts = HashMap::new();
+    for block in markdown::prose_blocks(content) {
+        for term in terms(&block.text) {
+            *counts.entry(term).or_default() += 1.0;
+        }
+    }
+    counts
+}
+
+/// 正規化した TF-IDF のベクトル
+fn weights(counts: &HashMap<String, f32>, idf: &HashMap<String, f32>) -> HashMap<String, f32> {
+    let mut vector: HashMap<String, f32> = counts
+        .iter()
+        .map(|(term, &tf)| (term.clone(), (1.0 + tf.ln()) * idf[term]))
+        .collect();
+    let norm = vector.values().map(|w| w * w).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.values_mut().for_each(|w| *w /= norm);
+    }
+    vector
+}
+
+/// 文書からリンクしているノート
+fn linked_notes(vault: &Vault, doc: &Path, content: &str) -> HashSet<PathBuf> {
+    links::extract_links(content)
+        .iter()
+        .filter(|link| !link.target.is_empty())
+        .filter_map(|link| vault.resolve(doc, link))
+        .collect()
+}
+
+/// ワークスペース内で `path` と内容の似たノートを類似度の高い順に返す
+#[tauri::command(async)]
+pub fn find_similar_notes(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    limit: Option<usize>,
+    vault_root: Option<String>,
+    task_id: Option<String>,
+) -> Result<Vec<SimilarNote>, String> {
+    let task = Task::start(&app, &state, task_id)?;
+    let path = vault::normalize(Path::new(&path));
+    let root = vault_root
+        .map(PathBuf::from)
+        .unwrap_or_else(|| path.parent().map(Path::to_path_buf).unwrap_or_default());
+    let vault = Vault::scan(&root);
+
+    let mut docs = Vec::new();
+    let mut target = None;
+    let files: Vec<&PathBuf> = vault.markdown_files().collect();
+    for (i, doc) in files.iter().copied().enumerate() {
+        task.check()?;
+        task.progress(i + 1, files.len(), || path_string(doc));
+        let content = read(doc)?;
+        let counts = term_counts(&content);
+        if *doc == path {
+            target = Some((content, counts));
+        } else if !counts.is_empty() {
+            docs.push((doc, content, counts));
+        }
+    }
+    let (content, counts) = match target {
+        Some(target) => target,
+        None => {
+            let content = read(&path)?;
+            let counts = term_counts(&content);
+            (content, counts)
+        }
+    };
+
+    // 文書頻度（対象の文書も含める）
+    let total = docs.len() as f32 + 1.0;
+    let mut df: HashMap<String, f32> = HashMap::new();
+    for counts in docs.iter().map(|(_, _, c)| c).chain([&counts]) {
+        for term in counts.keys() {
+            *df.entry(term.clone()).or_default() += 1.0;
+        }
+    }
+    let idf: HashMap<String, f32> = df
+        .into_iter()
+        .map(|(term, df)| (term, ((total + 1.0) / (df + 1.0)).ln() + 1.0))
+        .collect();
+
+    let query = weights(&counts, &idf);
+    let links_from = linked_notes(&vault, &path, &content);
+    let mut results: Vec<SimilarNote> = docs
+        .iter()
+        .filter_map(|(doc, doc_content, doc_counts)| {
+            let vector = weights(doc_counts, &idf);
+            let mut shared: Vec<(&String, f32)> = query
+                .iter()
+                .filter_map(|(term, w)| vector.get(term).map(|v| (term, w * v)))
+                .collect();
+            let score: f32 = shared.iter().map(|(_, s)| s).sum();
+            if score <= 0.0 {
+                return None;
+            }
+            shared.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let linked =
+                links_from.contains(*doc) || linked_notes(&vault, doc, doc_content).contains(&path);
+            Some(SimilarNote {
+                path: path_string(doc),
+                score,
+                linked,
+                shared_terms: shared
+                    .into_iter()
+                    .take(SHARED_TERMS)
+                    .map(|(t, _)| t.clone())
+                    .collect(),
+            })
+        })
+        .collect();
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+    results.truncate(limit.unwrap_or(10));
+    Ok(results)
+}
@@ -0,0 +1,82 @@
+// 仕様書の例（CommonMark・GFM の spec.txt の形式）と描画結果の照合（テスト用）
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+const FENCE: &str = "````````````````````````````````";
+
+static CHECKBOX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<input[^>]*>\s*").unwrap());
+
+/// 仕様書の例（見出しの番号は出現順）
+pub struct Example {
+    pub number: usize,
+    pub markdown: String,
+    pub html: String,
+}
+
+/// `example` のコードブロックを読み出す（`→` はタブに戻す）
+pub fn examples(spec: &str) -> Vec<Example> {
+    let mut examples = Vec::new();
+    let mut lines = spec.lines();
+    while let Some(line) = lines.next() {
+        if line.trim_end() != format!("{FENCE} example") {
+            continue;
+        }
+        let mut markdown = String::new();
+        let mut html = String::new();
+        let mut in_html = false;
+        for line in lines.by_ref() {
+            if line.trim_end() == FENCE {
+                break;
+            }
+            if line == "." && !in_html {
+                in_html = true;
+                continue;
+            }
+            let target = if in_html { &mut html } else { &mut markdown };
+            target.push_str(&line.replace('→', "\t"));
+            target.push('\n');
+        }
+        examples.push(Example {
+            number: examples.len() + 1,
+            markdown,
+            html,
+        });
+    }
+    examples
+}
+
+/// 比較のための正規化（空要素の書き方・タグの間の改行・タスクのチェックボックスの属性をそろえる）
+pub fn normalize(html: &str) -> String {
+    let html = CHECKBOX.replace_all(html, |caps: &regex::Captures| {
+        if caps[0].contains("checked") {
+            "[x]"
+        } else {
+            "[ ]"
+        }
+    });
+    html.replace("<br>", "<br />")
+        .replace("<br/>", "<br />")
+        .replace("<hr>", "<hr />")
+        .replace("<hr/>", "<hr />")
+        .replace(">\n<", "><")
+}
+
+/// 一致しなかった例の説明
+pub fn failures(spec: &str, render: impl Fn(&str) -> String) -> Vec<String> {
+    let examples = examples(spec);
+    assert!(!examples.is_empty(), "no examples found");
+    examples
+        .iter()
+        .filter_map(|example| {
+            let actual = render(&example.markdown);
+            (normalize(&actual) != normalize(&example.html)).then(|| {
+                format!(
+                    "example {}\n--- markdown\n{}--- expected\n{}--- actual\n{}",
+                    example.number, example.markdown, example.html, actual
+                )
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,57 @@
+// アプリケーション全体で共有する状態
+
+use crate::ai::AiState;
+use crate::backup::BackupState;
+use crate::collab::CollabState;
+use crate::completion::CompletionState;
+use crate::diagram::DiagramState;
+use crate::export::WatchExportState;
+use crate::files::FileState;
+use crate::grammar::GrammarState;
+use crate::hooks::HookState;
+use crate::image_optimize::ImageOptimizeState;
+use crate::lsp::LspState;
+use crate::plugins::PluginState;
+use crate::preprocess::PreprocessState;
+use crate::preview::ParseCache;
+use crate::preview_server::PreviewServerState;
+use crate::recording::RecordingState;
+use crate::reminders::ReminderState;
+use crate::render_profile::ProfileState;
+use crate::render_queue::RenderQueueState;
+use crate::search_index::SearchIndexState;
+use crate::semantic::SemanticState;
+use crate::tasks::TaskState;
+use crate::trust::TrustState;
+use crate::tts::TtsState;
+use crate::vimrc::VimrcState;
+
+/// `tauri::Builder::manage` で登録する共有状態
+#[derive(Default)]
+pub struct AppState {
+    pub tts: TtsState,
+    pub backup: BackupState,
+    pub watch_export: WatchExportState,
+    pub preview_server: PreviewServerState,
+    pub collab: CollabState,
+    pub lsp: LspState,
+    pub plugins: PluginState,
+    pub hooks: HookState,
+    pub preprocess: PreprocessState,
+    pub semantic: SemanticState,
+    pub ai: AiState,
+    pub grammar: GrammarState,
+    pub completion: CompletionState,
+    pub files: FileState,
+    pub diagrams: DiagramState,
+    pub image_optimize: ImageOptimizeState,
+    pub recording: RecordingState,
+    pub profiles: ProfileState,
+    pub parse_cache: ParseCache,
+    pub render_queue: RenderQueueState,
+    pub tasks: TaskState,
+    pub search_index: SearchIndexState,
+    pub vimrc: VimrcState,
+    pub trust: TrustState,
+    pub reminders: ReminderState,
+}
@@ -0,0 +1,311 @@
+// 設定・索引・セッションのファイルを壊さずに書き換える（先書きの一時ファイル・直前の世代の控え・破損からの復旧）
+//
+// 書き込みの手順:
+// 1. 新しい内容を `<name>.tmp` に書いてディスクに反映する（先書き）
+// 2. 今のファイルを `<name>.bak` に移す（直前の世代の控え）
+// 3. `<name>.tmp` を `<name>` に移す（ここで確定）
+//
+// 読むときに残っている `<name>.tmp` は、読めれば書き込みの続きとして反映し、読めなければ捨てる。
+// 本体が壊れていれば `<name>.corrupt-<時刻>` に退避して控えから戻す。行った復旧は `storage_health` で確かめられる。
+
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::tr;
+use crate::vault::path_string;
+
+/// このセッションで行った復旧
+static RECOVERIES: Mutex<Vec<Recovery>> = Mutex::new(Vec::new());
+
+/// 復旧の内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryAction {
+    /// 確定前に中断した書き込みを反映した
+    ReplayedJournal,
+    /// 途中までしか書けていない一時ファイルを捨てた
+    DiscardedJournal,
+    /// 壊れたファイルを控えから戻した
+    RestoredBackup,
+    /// 壊れたファイルを退避し、既定値で始めた
+    Reset,
+}
+
+/// 行った復旧
+#[derive(Debug, Clone, Serialize)]
+pub struct Recovery {
+    pub path: String,
+    pub action: RecoveryAction,
+    /// 壊れていた理由
+    pub detail: Option<String>,
+    /// 壊れたファイルの退避先
+    pub quarantined: Option<String>,
+    /// UNIX ミリ秒
+    pub time: u64,
+}
+
+/// ファイルの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    Ok,
+    Missing,
+    Corrupt,
+}
+
+/// 確かめたファイル
+#[derive(Debug, Clone, Serialize)]
+pub struct FileHealth {
+    pub path: String,
+    pub status: FileStatus,
+    /// 直前の世代の控えがある
+    pub backup: bool,
+    /// 確定していない書き込みが残っている
+    pub pending_journal: bool,
+}
+
+/// `storage_health` の結果
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageHealth {
+    pub files: Vec<FileHealth>,
+    pub recoveries: Vec<Recovery>,
+    /// 退避した壊れたファイル
+    pub quarantined: Vec<String>,
+}
+
+/// `path` のファイル名に `suffix` を付けたパス
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn journal_path(path: &Path) -> PathBuf {
+    sibling(path, ".tmp")
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    sibling(path, ".bak")
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+fn record(path: &Path, action: RecoveryAction, detail: Option<String>, quarantined: Option<&Path>) {
+    RECOVERIES.lock().unwrap().push(Recovery {
+        path: path_string(path),
+        action,
+        detail,
+        quarantined: quarantined.map(path_string),
+        time: now_millis(),
+    });
+}
+
+/// フォルダへの名前の変更をディスクに反映する（Windows では不要）
+fn sync_dir(dir: &Path) {
+    #[cfg(unix)]
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
+
+/// 途中で止まっても元の内容か新しい内容のどちらかが残るように書き込む
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<(), String> {
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty());
+    if let Some(dir) = dir {
+        fs::create_dir_all(dir).map_err(|e| tr!("Failed to create {}: {e}", dir.display()))?;
+    }
+    let journal = journal_path(path);
+    let write = || -> std::io::Result<()> {
+        let mut file = File::create(&journal)?;
+        file.write_all(data)?;
+        file.sync_all()
+    };
+    write().map_err(|e| tr!("Failed to write {}: {e}", journal.display()))?;
+    if path.is_file() {
+        let backup = backup_path(path);
+        fs::rename(path, &backup).map_err(|e| tr!("Failed to back up {}: {e}", path.display()))?;
+    }
+    fs::rename(&journal, path).map_err(|e| tr!("Failed to write {}: {e}", path.display()))?;
+    if let Some(dir) = dir {
+        sync_dir(dir);
+    }
+    Ok(())
+}
+
+/// JSON として書き込む（索引など大きなものは `pretty` を `false` にする）
+pub fn write_json<T: Serialize + ?Sized>(
+    path: &Path,
+    value: &T,
+    pretty: bool,
+) -> Result<(), String> {
+    let json = if pretty {
+        serde_json::to_vec_pretty(value)
+    } else {
+        serde_json::to_vec(value)
+    }
+    .map_err(|e| tr!("Failed to write {}: {e}", path.display()))?;
+    write_atomic(path, &json)
+}
+
+fn parse<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+    serde_json::from_str(&text).map_err(|e| tr!("Failed to parse {}: {e}", path.display()))
+}
+
+/// 壊れたファイルを `<name>.corrupt-<時刻>` に移す
+fn quarantine(path: &Path) -> Option<PathBuf> {
+    let target = sibling(path, &format!(".corrupt-{}", now_millis() / 1000));
+    fs::rename(path, &target).ok().map(|_| target)
+}
+
+/// 書き込みの途中で止まって本体がなくなったファイルを戻す（確定前の一時ファイル、なければ控えから）
+///
+/// 利用者が編集する TOML などの設定ファイル向け。中身は確かめず、本体があれば何もしない。
+pub fn recover_missing(path: &Path) {
+    if path.exists() {
+        return;
+    }
+    let journal = journal_path(path);
+    if journal.is_file() && fs::rename(&journal, path).is_ok() {
+        record(path, RecoveryAction::ReplayedJournal, None, None);
+        return;
+    }
+    let backup = backup_path(path);
+    if backup.is_file() && fs::copy(&backup, path).is_ok() {
+        record(path, RecoveryAction::RestoredBackup, None, None);
+    }
+}
+
+/// JSON ファイルを読む（なければ `None`。中断した書き込みと壊れたファイルは復旧する）
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    // 確定前に中断した書き込み
+    let journal = journal_path(path);
+    if journal.is_file() {
+        match parse::<T>(&journal) {
+            Ok(value) => {
+                if path.is_file() {
+                    let _ = fs::rename(path, backup_path(path));
+                }
+                if fs::rename(&journal, path).is_ok() {
+                    record(path, RecoveryAction::ReplayedJournal, None, None);
+                    return Some(value);
+                }
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&journal);
+                record(path, RecoveryAction::DiscardedJournal, Some(e), None);
+            }
+        }
+    }
+
+    let backup = backup_path(path);
+    let error = match parse::<T>(path) {
+        Ok(value) => return Some(value),
+        // 本体も控えもなければまだ書いていない（本体だけがなければ書き込みの途中で止まったので控えから戻す）
+        Err(_) if !path.exists() && !backup.is_file() => return None,
+        Err(e) => e,
+    };
+    let quarantined = path.exists().then(|| quarantine(path)).flatten();
+    match parse::<T>(&backup) {
+        Ok(value) => {
+            let _ = fs::copy(&backup, path);
+            record(
+                path,
+                RecoveryAction::RestoredBackup,
+                Some(error),
+                quarantined.as_deref(),
+            );
+            Some(value)
+        }
+        Err(_) => {
+            record(
+                path,
+                RecoveryAction::Reset,
+                Some(error),
+                quarantined.as_deref(),
+            );
+            None
+        }
+    }
+}
+
+/// フォルダ内の JSON・TOML ファイルの状態と退避したファイル
+fn check_dir(dir: &Path, files: &mut Vec<FileHealth>, quarantined: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    paths.sort();
+    for path in paths {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if name.contains(".corrupt-") {
+            quarantined.push(path_string(&path));
+            continue;
+        }
+        // 本体のない一時ファイル・控えも本体の名前で報告する
+        let base = name
+            .strip_suffix(".tmp")
+            .or_else(|| name.strip_suffix(".bak"))
+            .unwrap_or(&name);
+        let toml = base.ends_with(".toml");
+        if !(base.ends_with(".json") || toml) || (base != name && dir.join(base).exists()) {
+            continue;
+        }
+        let path = dir.join(base);
+        if files.iter().any(|f| f.path == path_string(&path)) {
+            continue;
+        }
+        let status = match fs::read_to_string(&path) {
+            Err(_) => FileStatus::Missing,
+            Ok(text) if toml && toml::from_str::<toml::Table>(&text).is_ok() => FileStatus::Ok,
+            Ok(text) if !toml && serde_json::from_str::<Value>(&text).is_ok() => FileStatus::Ok,
+            Ok(_) => FileStatus::Corrupt,
+        };
+        files.push(FileHealth {
+            path: path_string(&path),
+            status,
+            backup: backup_path(&path).is_file(),
+            pending_journal: journal_path(&path).is_file(),
+        });
+    }
+}
+
+/// 設定・データフォルダ（と `vault_root` の索引）のファイルの状態と、このセッションで行った復旧
+#[tauri::command]
+pub fn storage_health(app: AppHandle, vault_root: Option<String>) -> StorageHealth {
+    let mut dirs = Vec::new();
+    dirs.extend(app.path().app_config_dir().ok());
+    dirs.extend(app.path().app_data_dir().ok());
+    if let Some(root) = vault_root {
+        dirs.push(Path::new(&root).join(".mdvim"));
+    }
+    let mut files = Vec::new();
+    let mut quarantined = Vec::new();
+    for dir in &dirs {
+        check_dir(dir, &mut files, &mut quarantined);
+    }
+    StorageHealth {
+        files,
+        recoveries: RECOVERIES.lock().unwrap().clone(),
+        quarantined,
+    }
+}
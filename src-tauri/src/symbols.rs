@@ -0,0 +1,151 @@
+// 文書のシンボル（パンくずリスト・シンボルへの移動用）
+
+use std::ops::Range;
+
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
+use serde::Serialize;
+
+use crate::lsp::LspRange;
+use crate::markdown;
+use crate::text::LineIndex;
+
+/// 文書のシンボル
+#[derive(Debug, Serialize)]
+pub struct DocumentSymbol {
+    pub name: String,
+    /// "heading" | "code_block" | "footnote" | "link_definition"
+    pub kind: String,
+    /// 見出しのレベル・コードの言語・リンク先など
+    pub detail: Option<String>,
+    /// シンボル全体（見出しは次の同じか上のレベルの見出しまで）
+    pub range: LspRange,
+    /// 名前の部分（移動先）
+    pub selection_range: LspRange,
+    pub children: Vec<DocumentSymbol>,
+}
+
+/// 組み立て前のシンボル（バイト範囲）
+struct Flat {
+    name: String,
+    kind: &'static str,
+    detail: Option<String>,
+    range: Range<usize>,
+    selection: Range<usize>,
+    /// 見出しのレベル（見出し以外は `None`）
+    level: Option<usize>,
+}
+
+fn flat_symbols(content: &str) -> Vec<Flat> {
+    let mut symbols = Vec::new();
+    let headings = markdown::headings(content);
+    for (i, heading) in headings.iter().enumerate() {
+        let end = headings[i + 1..]
+            .iter()
+            .find(|h| h.level <= heading.level)
+            .map_or(content.len(), |h| h.range.start);
+        symbols.push(Flat {
+            name: heading.text.clone(),
+            kind: "heading",
+            detail: Some(format!("H{}", heading.level)),
+            range: heading.range.start..end,
+            selection: heading.text_range.clone(),
+            level: Some(heading.level),
+        });
+    }
+
+    let mut parser = Parser::new_ext(content, markdown::parser_options()).into_offset_iter();
+    for (event, range) in parser.by_ref() {
+        let (name, kind, detail) = match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split_whitespace()
+                        .next()
+                        .map(str::to_string)
+                        .filter(|l| !l.is_empty()),
+                    CodeBlockKind::Indented => None,
+                };
+                let name = lang.clone().unwrap_or_else(|| "code".to_string());
+                (name, "code_block", lang)
+            }
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                (format!("[^{label}]"), "footnote", None)
+            }
+            _ => continue,
+        };
+        // 名前の部分は先頭の行
+        let line_end = content[range.clone()]
+            .find('\n')
+            .map_or(range.end, |i| range.start + i);
+        symbols.push(Flat {
+            name,
+            kind,
+            detail,
+            selection: range.start..line_end,
+            range,
+            level: None,
+        });
+    }
+    for (label, def) in parser.reference_definitions().iter() {
+        symbols.push(Flat {
+            name: format!("[{label}]"),
+            kind: "link_definition",
+            detail: Some(def.dest.to_string()),
+            range: def.span.clone(),
+            selection: def.span.clone(),
+            level: None,
+        });
+    }
+    // 文書順に並べ、同じ位置なら範囲の広いものを先にする
+    symbols.sort_by_key(|s| (s.range.start, std::cmp::Reverse(s.range.end)));
+    symbols
+}
+
+/// 見出しの入れ子に組み立てる（見出し以外はそれを含む最も深い見出しの子にする）
+fn build(content: &str, index: &LineIndex, symbols: Vec<Flat>) -> Vec<DocumentSymbol> {
+    fn attach(
+        stack: &mut Vec<(Option<usize>, DocumentSymbol)>,
+        roots: &mut Vec<DocumentSymbol>,
+        level: Option<usize>,
+    ) {
+        // 次のシンボルを入れられない見出しを閉じる
+        while let Some((Some(open), _)) = stack.last() {
+            if level.is_some_and(|l| l > *open) || level.is_none() {
+                break;
+            }
+            let (_, symbol) = stack.pop().unwrap();
+            match stack.last_mut() {
+                Some((_, parent)) => parent.children.push(symbol),
+                None => roots.push(symbol),
+            }
+        }
+    }
+
+    let mut roots = Vec::new();
+    let mut stack: Vec<(Option<usize>, DocumentSymbol)> = Vec::new();
+    for flat in symbols {
+        attach(&mut stack, &mut roots, flat.level);
+        let symbol = DocumentSymbol {
+            name: flat.name,
+            kind: flat.kind.to_string(),
+            detail: flat.detail,
+            range: LspRange::from_offsets(index, content, flat.range),
+            selection_range: LspRange::from_offsets(index, content, flat.selection),
+            children: Vec::new(),
+        };
+        match (flat.level, stack.last_mut()) {
+            (Some(level), _) => stack.push((Some(level), symbol)),
+            (None, Some((_, parent))) => parent.children.push(symbol),
+            (None, None) => roots.push(symbol),
+        }
+    }
+    attach(&mut stack, &mut roots, Some(0));
+    roots
+}
+
+/// 見出し・コードブロック・脚注・リンク定義の階層的なシンボル一覧
+#[tauri::command]
+pub fn get_document_symbols(content: String) -> Vec<DocumentSymbol> {
+    let index = LineIndex::new(&content);
+    build(&content, &index, flat_symbols(&content))
+}
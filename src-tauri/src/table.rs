@@ -0,0 +1,325 @@
+// CSV/TSV と Markdown の表の相互変換
+
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::tr;
+
+/// `csv_to_table` のオプション
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TableOptions {
+    /// 区切り文字（省略時は内容から推定）
+    pub delimiter: Option<char>,
+    /// 1 行目を見出しとして扱わない（見出しは `Column 1` `Column 2` … になる）
+    pub no_header: bool,
+}
+
+/// 表示幅（全角文字は 2）
+pub fn display_width(s: &str) -> usize {
+    s.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F
+            | 0x2E80..=0x303E
+            | 0x3041..=0x33FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xA000..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1F64F
+            | 0x1F900..=0x1F9FF
+            | 0x20000..=0x3FFFD => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// 1 行目の引用符の外にある文字の数から区切り文字を推定
+pub fn detect_delimiter(text: &str) -> char {
+    let mut counts = [('\t', 0usize), (',', 0), (';', 0)];
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '\n' if !quoted => break,
+            _ if !quoted => {
+                if let Some(entry) = counts.iter_mut().find(|(d, _)| *d == c) {
+                    entry.1 += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    counts
+        .iter()
+        .filter(|(_, n)| *n > 0)
+        .max_by_key(|(_, n)| *n)
+        .map_or(',', |(d, _)| *d)
+}
+
+/// CSV を解析（RFC 4180 形式の引用符、引用符内の改行、CRLF に対応）
+pub fn parse_csv(text: &str, delimiter: char) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    // 何も書かれていない行（`""` だけの行は空のセルが 1 つの行として残す）
+    let mut blank = true;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !matches!(c, '\n' | '\r') {
+            blank = false;
+        }
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            '\r' if chars.peek() == Some(&'\n') => {}
+            // 空行は除く
+            '\n' | '\r' if blank => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+                blank = true;
+            }
+            c if c == delimiter => row.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(tr!("Unterminated quoted field in CSV"));
+    }
+    if !blank {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// セルを Markdown の表に入れられる形にする
+pub fn escape_cell(cell: &str) -> String {
+    cell.trim()
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+fn is_number(cell: &str) -> bool {
+    let cell = cell.trim().trim_end_matches('%').replace(',', "");
+    let cell = cell.trim_start_matches(['¥', '$', '€', '£']);
+    !cell.is_empty() && cell.parse::<f64>().is_ok()
+}
+
+/// 行を列幅を揃えた Markdown の表にする（数値だけの列は右寄せ）
+pub fn format_table(mut rows: Vec<Vec<String>>, no_header: bool) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    for row in &mut rows {
+        row.resize(columns, String::new());
+    }
+    if no_header {
+        rows.insert(0, (1..=columns).map(|i| format!("Column {i}")).collect());
+    }
+    let right: Vec<bool> = (0..columns)
+        .map(|c| {
+            let mut body = rows[1..]
+                .iter()
+                .map(|r| &r[c])
+                .filter(|v| !v.trim().is_empty());
+            let first = body.next();
+            first.is_some_and(|v| is_number(v)) && body.all(|v| is_number(v))
+        })
+        .collect();
+    let widths: Vec<usize> = (0..columns)
+        .map(|c| {
+            rows.iter()
+                .map(|r| display_width(&r[c]))
+                .max()
+                .unwrap_or(0)
+                .max(3)
+        })
+        .collect();
+
+    let line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+    let mut out = Vec::with_capacity(rows.len() + 1);
+    for (i, row) in rows.iter().enumerate() {
+        let cells = row
+            .iter()
+            .enumerate()
+            .map(|(c, cell)| {
+                let pad = " ".repeat(widths[c] - display_width(cell));
+                if right[c] && i > 0 {
+                    format!("{pad}{cell}")
+                } else {
+                    format!("{cell}{pad}")
+                }
+            })
+            .collect();
+        out.push(line(cells));
+        if i == 0 {
+            let rule = (0..columns)
+                .map(|c| {
+                    if right[c] {
+                        format!("{}:", "-".repeat(widths[c] - 1))
+                    } else {
+                        "-".repeat(widths[c])
+                    }
+                })
+                .collect();
+            out.push(line(rule));
+        }
+    }
+    out.join("\n") + "\n"
+}
+
+/// CSV/TSV（ファイルのパスまたはテキスト）を Markdown の表に変換
+#[tauri::command]
+pub fn csv_to_table(input: String, options: Option<TableOptions>) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let path = Path::new(&input);
+    let is_path = !input.contains('\n') && path.is_file();
+    let read;
+    let text = if is_path {
+        read =
+            fs::read_to_string(path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+        &read
+    } else {
+        &input
+    };
+    let text = text.trim_start_matches('\u{feff}');
+    let tsv = is_path
+        && path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("tsv") || e.eq_ignore_ascii_case("tab"));
+    let delimiter = match options.delimiter {
+        Some(delimiter) => delimiter,
+        None if tsv => '\t',
+        None => detect_delimiter(text),
+    };
+
+    let rows: Vec<Vec<String>> = parse_csv(text, delimiter)?
+        .into_iter()
+        .map(|row| row.iter().map(|cell| escape_cell(cell)).collect())
+        .collect();
+    if rows.is_empty() {
+        return Err(tr!("No rows found"));
+    }
+    Ok(format_table(rows, options.no_header))
+}
+
+/// Markdown の表の行をセルに分ける（`\|` は区切りとして扱わない）
+fn split_row(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = match line.strip_suffix('|') {
+        Some(rest) if !rest.ends_with('\\') => rest,
+        _ => line,
+    };
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                chars.next();
+                cell.push('|');
+            }
+            '|' => cells.push(std::mem::take(&mut cell)),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell);
+    cells
+        .into_iter()
+        .map(|c| {
+            c.trim()
+                .replace("<br>", "\n")
+                .replace("<br/>", "\n")
+                .replace("<br />", "\n")
+        })
+        .collect()
+}
+
+fn is_rule_row(cells: &[String]) -> bool {
+    cells.iter().all(|c| {
+        let c = c.trim_matches(':');
+        !c.is_empty() && c.chars().all(|ch| ch == '-')
+    })
+}
+
+fn quote_field(field: &str, delimiter: char) -> String {
+    let needs_quotes = field.contains([delimiter, '"', '\n', '\r'])
+        || field.starts_with(' ')
+        || field.ends_with(' ');
+    if needs_quotes {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Markdown の表を CSV（`delimiter` が `\t` なら TSV）に変換
+#[tauri::command]
+pub fn table_to_csv(table: String, delimiter: Option<char>) -> Result<String, String> {
+    let delimiter = delimiter.unwrap_or(',');
+    let rows: Vec<Vec<String>> = table
+        .lines()
+        .filter(|line| line.trim_start().starts_with('|') || line.contains('|'))
+        .map(split_row)
+        .filter(|cells| !is_rule_row(cells))
+        .collect();
+    if rows.is_empty() {
+        return Err(tr!("No table found"));
+    }
+    let separator = delimiter.to_string();
+    let mut out = String::new();
+    for row in rows {
+        // 空のセルが 1 つだけの行は、空行として読み飛ばされないよう `""` にする
+        if let [field] = row.as_slice() {
+            if field.is_empty() {
+                out.push_str("\"\"\n");
+                continue;
+            }
+        }
+        let fields: Vec<String> = row.iter().map(|f| quote_field(f, delimiter)).collect();
+        out.push_str(&fields.join(&separator));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_single_column_rows_survive_a_round_trip() {
+        let table = "| Name |\n| ---- |\n| a    |\n|      |\n| b    |\n";
+        let csv = table_to_csv(table.to_string(), None).unwrap();
+        assert_eq!(csv, "Name\na\n\"\"\nb\n");
+        assert_eq!(
+            parse_csv(&csv, ',').unwrap(),
+            [vec!["Name"], vec!["a"], vec![""], vec!["b"]]
+        );
+        assert_eq!(csv_to_table(csv, None).unwrap(), table);
+        // 何も書かれていない行は除く
+        assert_eq!(
+            parse_csv("a\n\r\n\nb", ',').unwrap(),
+            [vec!["a"], vec!["b"]]
+        );
+    }
+}
@@ -0,0 +1,788 @@
+// 表の計算式（org-mode の `#+TBLFM` と同じ書き方）
+//
+// 表のすぐ下の `<!-- tblfm: @5$3=sum(@2..@4) :: $4=$2*$3 -->` と、セルの中の `<!-- =式 -->` を計算し、
+// 結果のセルを書き換える。行 `@` は見出しを 1 とし（区切りの行は数えない）、列 `$` は左から 1 始まり。
+//
+// 参照: `@2$3` `$3`（同じ行）`@2`（同じ列）`@<` `@>` `$<` `$>`（最初と最後）`@-1` `$+1`（相対）、範囲は `..`。
+// 式: `+ - * / ^` と括弧、関数 `sum` `mean`（`avg`）`min` `max` `count` `abs` `round(x, 桁)`。
+// `$3=...` は見出し以外のすべての行に、`@5$3=...` はそのセルに当てはめる（列の式を先に計算する）。
+// 式の後ろの `;%.2f` で小数点以下の桁数を決める。
+
+use std::ops::Range;
+
+use serde::Serialize;
+
+use crate::formatter::{self, TextEdit};
+use crate::markdown;
+use crate::table;
+use crate::text;
+use crate::tr;
+
+/// 計算できなかった式
+#[derive(Debug, Serialize)]
+pub struct FormulaError {
+    /// 式のある行（1 始まり）
+    pub line: usize,
+    pub formula: String,
+    pub message: String,
+}
+
+/// `evaluate_table_formulas` の結果
+#[derive(Debug, Serialize)]
+pub struct FormulaResult {
+    pub content: String,
+    pub changed: bool,
+    pub edits: Vec<TextEdit>,
+    /// 式のある表の数
+    pub tables: usize,
+    /// 書き換えたセルの数
+    pub cells: usize,
+    pub errors: Vec<FormulaError>,
+}
+
+struct Cell {
+    /// `|` の間の範囲
+    span: Range<usize>,
+    /// 見える値（セルの式の注釈を除く）
+    value: String,
+    /// セルの中の `<!-- =式 -->`
+    formula: Option<String>,
+}
+
+struct Table {
+    /// 区切りの行を除いた行（最初が見出し）
+    rows: Vec<Vec<Cell>>,
+    /// 行ごとの文書の行番号
+    lines: Vec<usize>,
+    /// 右寄せの列（区切りの行の `---:`）
+    right: Vec<bool>,
+    /// `tblfm` の式と行番号
+    formulas: Vec<(String, usize)>,
+}
+
+/// 行のセルの `|` の間の範囲（`\|` は区切りとして扱わない。`offset` は行の始まり）
+fn cell_spans(line: &str, offset: usize) -> Vec<Range<usize>> {
+    let mut pipes = Vec::new();
+    let mut backslashes = 0;
+    for (i, c) in line.char_indices() {
+        if c == '|' && backslashes % 2 == 0 {
+            pipes.push(i);
+        }
+        backslashes = if c == '\\' { backslashes + 1 } else { 0 };
+    }
+    // 先頭と末尾の `|` は省略できる
+    let start = line.len() - line.trim_start().len();
+    let end = line.trim_end().len();
+    let mut spans = Vec::new();
+    let mut cell_start = start;
+    for pipe in pipes {
+        if pipe != start {
+            spans.push(offset + cell_start..offset + pipe);
+        }
+        cell_start = pipe + 1;
+    }
+    if cell_start < end {
+        spans.push(offset + cell_start..offset + end);
+    }
+    spans
+}
+
+fn is_rule_row(cells: &[&str]) -> bool {
+    cells.iter().all(|c| {
+        let c = c.trim().trim_matches(':');
+        !c.is_empty() && c.chars().all(|ch| ch == '-')
+    })
+}
+
+/// セルの値と式の注釈
+fn parse_cell(text: &str) -> (String, Option<String>) {
+    let text = text.trim();
+    if let Some(start) = text.find("<!--") {
+        if let Some(len) = text[start..].find("-->") {
+            let comment = text[start + 4..start + len].trim();
+            if let Some(formula) = comment.strip_prefix('=') {
+                let value = format!("{}{}", &text[..start], &text[start + len + 3..]);
+                return (value.trim().to_string(), Some(formula.trim().to_string()));
+            }
+        }
+    }
+    (text.to_string(), None)
+}
+
+/// `<!-- tblfm: ... -->` の中身
+fn tblfm(line: &str) -> Option<&str> {
+    let inner = line
+        .trim()
+        .strip_prefix("<!--")?
+        .strip_suffix("-->")?
+        .trim();
+    let (name, formulas) = inner.split_once(':')?;
+    name.trim()
+        .eq_ignore_ascii_case("tblfm")
+        .then(|| formulas.trim())
+}
+
+/// 内容の表（コードブロックの中は除く）
+fn tables(content: &str) -> Vec<Table> {
+    let code = markdown::code_ranges(content);
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        lines.push((offset, text));
+        offset += line.len();
+    }
+    let mut tables = Vec::new();
+    let mut i = 0;
+    while i + 1 < lines.len() {
+        let (offset, line) = lines[i];
+        let is_row = |text: &str| text.contains('|') && !text.trim().is_empty();
+        let rule: Vec<&str> = lines[i + 1].1.trim().trim_matches('|').split('|').collect();
+        if !is_row(line) || !is_rule_row(&rule) || markdown::in_ranges(&code, offset) {
+            i += 1;
+            continue;
+        }
+        let mut table = Table {
+            rows: Vec::new(),
+            lines: Vec::new(),
+            formulas: Vec::new(),
+            right: rule
+                .iter()
+                .map(|c| {
+                    let c = c.trim();
+                    c.ends_with(':') && !c.starts_with(':')
+                })
+                .collect(),
+        };
+        let mut j = i;
+        while j < lines.len() && is_row(lines[j].1) && tblfm(lines[j].1).is_none() {
+            let (offset, line) = lines[j];
+            if j != i + 1 {
+                table.rows.push(
+                    cell_spans(line, offset)
+                        .into_iter()
+                        .map(|span| {
+                            let (value, formula) = parse_cell(&content[span.clone()]);
+                            Cell {
+                                span,
+                                value,
+                                formula,
+                            }
+                        })
+                        .collect(),
+                );
+                table.lines.push(j + 1);
+            }
+            j += 1;
+        }
+        // 表のすぐ下（空行を 1 行挟んでもよい）の式
+        let mut k = j;
+        if k < lines.len() && lines[k].1.trim().is_empty() {
+            k += 1;
+        }
+        while let Some(formulas) = lines.get(k).and_then(|(_, line)| tblfm(line)) {
+            table.formulas.extend(
+                formulas
+                    .split("::")
+                    .map(str::trim)
+                    .filter(|f| !f.is_empty())
+                    .map(|f| (f.to_string(), k + 1)),
+            );
+            k += 1;
+        }
+        tables.push(table);
+        i = j;
+    }
+    tables
+}
+
+/// 参照の行または列の指定
+#[derive(Clone, Copy)]
+enum Position {
+    Absolute(usize),
+    Relative(isize),
+    First,
+    Last,
+}
+
+impl Position {
+    fn resolve(self, current: usize, len: usize) -> Option<usize> {
+        let index = match self {
+            Position::Absolute(n) => n,
+            Position::Relative(d) => usize::try_from(current as isize + d).ok()?,
+            Position::First => 1,
+            Position::Last => len,
+        };
+        (1..=len).contains(&index).then_some(index)
+    }
+}
+
+/// 計算の値（範囲は数のリスト）
+enum Value {
+    Number(f64),
+    List(Vec<f64>),
+}
+
+impl Value {
+    fn number(self) -> Result<f64, String> {
+        match self {
+            Value::Number(n) => Ok(n),
+            Value::List(list) if list.len() == 1 => Ok(list[0]),
+            Value::List(_) => Err(tr!("A range cannot be used here")),
+        }
+    }
+}
+
+/// セルの数値（桁区切り・通貨記号・`%` は除く。空なら `None`）
+fn cell_number(cell: &str) -> Option<Result<f64, ()>> {
+    let cell = cell.trim();
+    if cell.is_empty() {
+        return None;
+    }
+    let cleaned = cell.trim_end_matches('%').replace(',', "");
+    let cleaned = cleaned.trim_start_matches(['¥', '$', '€', '£']);
+    Some(cleaned.parse().map_err(|_| ()))
+}
+
+/// 式を読みながら計算する
+struct Evaluator<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    values: &'a [Vec<String>],
+    row: usize,
+    column: usize,
+}
+
+impl Evaluator<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_spaces();
+        let eaten = self.peek() == Some(c);
+        if eaten {
+            self.pos += 1;
+        }
+        eaten
+    }
+
+    fn invalid(&self) -> String {
+        tr!("Invalid formula: {}", self.chars.iter().collect::<String>())
+    }
+
+    fn expression(&mut self) -> Result<Value, String> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value = Value::Number(value.number()? + self.term()?.number()?);
+            } else if self.eat('-') {
+                value = Value::Number(value.number()? - self.term()?.number()?);
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Value, String> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value = Value::Number(value.number()? * self.unary()?.number()?);
+            } else if self.eat('/') {
+                let divisor = self.unary()?.number()?;
+                if divisor == 0.0 {
+                    return Err(tr!("Division by zero"));
+                }
+                value = Value::Number(value.number()? / divisor);
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// 単項の `-` は `^` より弱く結び付く（`-2^2` は -4）
+    fn unary(&mut self) -> Result<Value, String> {
+        if self.eat('-') {
+            return Ok(Value::Number(-self.unary()?.number()?));
+        }
+        self.eat('+');
+        self.power()
+    }
+
+    /// `^` は右結合（指数には符号を付けてよい）
+    fn power(&mut self) -> Result<Value, String> {
+        let base = self.primary()?;
+        if self.eat('^') {
+            let exponent = self.unary()?.number()?;
+            return Ok(Value::Number(base.number()?.powf(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Value, String> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let value = self.expression()?;
+                if !self.eat(')') {
+                    return Err(self.invalid());
+                }
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    self.pos += 1;
+                }
+                let number: String = self.chars[start..self.pos].iter().collect();
+                number
+                    .parse()
+                    .map(Value::Number)
+                    .map_err(|_| self.invalid())
+            }
+            Some('@' | '$') => {
+                let from = self.reference()?;
+                if self.chars[self.pos..].starts_with(&['.', '.']) {
+                    self.pos += 2;
+                    let to = self.reference()?;
+                    return self.range(from, to);
+                }
+                let (row, column) = from;
+                match cell_number(&self.values[row - 1][column - 1]) {
+                    None => Ok(Value::Number(0.0)),
+                    Some(Ok(n)) => Ok(Value::Number(n)),
+                    Some(Err(())) => Err(tr!("Not a number: @{}${}", row, column)),
+                }
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_alphanumeric()) {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                if !self.eat('(') {
+                    return Err(self.invalid());
+                }
+                let mut args = Vec::new();
+                if !self.eat(')') {
+                    loop {
+                        args.push(self.expression()?);
+                        if self.eat(')') {
+                            break;
+                        }
+                        if !self.eat(',') && !self.eat(';') {
+                            return Err(self.invalid());
+                        }
+                    }
+                }
+                function(&name.to_lowercase(), args)
+            }
+            _ => Err(self.invalid()),
+        }
+    }
+
+    /// `@` と `$` の指定 1 つ
+    fn position(&mut self) -> Option<Position> {
+        match self.peek() {
+            Some('<') => {
+                self.pos += 1;
+                Some(Position::First)
+            }
+            Some('>') => {
+                self.pos += 1;
+                Some(Position::Last)
+            }
+            Some(sign @ ('+' | '-')) => {
+                self.pos += 1;
+                let n = self.digits()? as isize;
+                Some(Position::Relative(if sign == '-' { -n } else { n }))
+            }
+            _ => self.digits().map(Position::Absolute),
+        }
+    }
+
+    fn digits(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    /// 参照（省略した行・列は計算するセルと同じ）
+    fn reference(&mut self) -> Result<(usize, usize), String> {
+        let start = self.pos;
+        let mut row = Position::Relative(0);
+        let mut column = Position::Relative(0);
+        if self.peek() == Some('@') {
+            self.pos += 1;
+            row = self.position().ok_or_else(|| self.invalid())?;
+        }
+        if self.peek() == Some('$') {
+            self.pos += 1;
+            column = self.position().ok_or_else(|| self.invalid())?;
+        }
+        let columns = self.values.first().map_or(0, Vec::len);
+        match (
+            row.resolve(self.row, self.values.len()),
+            column.resolve(self.column, columns),
+        ) {
+            (Some(row), Some(column)) => Ok((row, column)),
+            _ => Err(tr!(
+                "Reference out of range: {}",
+                self.chars[start..self.pos].iter().collect::<String>()
+            )),
+        }
+    }
+
+    /// 範囲の数値（空のセルと数値でないセルは飛ばす）
+    fn range(&self, from: (usize, usize), to: (usize, usize)) -> Result<Value, String> {
+        let rows = from.0.min(to.0)..=from.0.max(to.0);
+        let columns = from.1.min(to.1)..=from.1.max(to.1);
+        Ok(Value::List(
+            rows.flat_map(|r| columns.clone().map(move |c| (r, c)))
+                .filter_map(|(r, c)| cell_number(&self.values[r - 1][c - 1])?.ok())
+                .collect(),
+        ))
+    }
+}
+
+fn function(name: &str, args: Vec<Value>) -> Result<Value, String> {
+    let all = || {
+        args.iter().flat_map(|v| match v {
+            Value::Number(n) => vec![*n],
+            Value::List(list) => list.clone(),
+        })
+    };
+    let number = match name {
+        "sum" | "vsum" => all().sum(),
+        "mean" | "avg" | "vmean" => {
+            let numbers: Vec<f64> = all().collect();
+            if numbers.is_empty() {
+                return Err(tr!("Division by zero"));
+            }
+            numbers.iter().sum::<f64>() / numbers.len() as f64
+        }
+        "min" | "vmin" => all()
+            .reduce(f64::min)
+            .ok_or_else(|| tr!("No numbers for {}", name))?,
+        "max" | "vmax" => all()
+            .reduce(f64::max)
+            .ok_or_else(|| tr!("No numbers for {}", name))?,
+        "count" | "vcount" => all().count() as f64,
+        "abs" | "round" => {
+            let mut args = args.into_iter();
+            let x = args
+                .next()
+                .ok_or_else(|| tr!("Invalid formula: {}", name))?
+                .number()?;
+            if name == "abs" {
+                x.abs()
+            } else {
+                let digits = args.next().map_or(Ok(0.0), Value::number)? as i32;
+                let scale = 10f64.powi(digits);
+                (x * scale).round() / scale
+            }
+        }
+        _ => return Err(tr!("Unknown function: {}", name)),
+    };
+    if number.is_finite() {
+        Ok(Value::Number(number))
+    } else {
+        Err(tr!("Numeric overflow: {}", name))
+    }
+}
+
+/// 結果の書式（`;%.2f` があればその桁数、なければ整数か最大 10 桁の小数）
+fn format_number(value: f64, decimals: Option<usize>) -> String {
+    match decimals {
+        Some(decimals) => format!("{value:.decimals$}"),
+        None if (value - value.round()).abs() < 1e-9 => format!("{}", value.round() as i64),
+        None => {
+            let text = format!("{value:.10}");
+            text.trim_end_matches('0').trim_end_matches('.').to_string()
+        }
+    }
+}
+
+/// 式を計算する（`;%.Nf` の書式を含めてよい）
+fn evaluate(
+    formula: &str,
+    values: &[Vec<String>],
+    row: usize,
+    column: usize,
+) -> Result<String, String> {
+    let (expression, decimals) = match formula.rsplit_once(';') {
+        Some((expression, format)) => {
+            let decimals = format
+                .trim()
+                .strip_prefix("%.")
+                .and_then(|f| f.strip_suffix('f'))
+                .and_then(|d| d.parse().ok())
+                .ok_or_else(|| tr!("Invalid formula: {}", formula))?;
+            (expression, Some(decimals))
+        }
+        None => (formula, None),
+    };
+    let mut evaluator = Evaluator {
+        chars: expression.chars().collect(),
+        pos: 0,
+        values,
+        row,
+        column,
+    };
+    let value = evaluator.expression()?;
+    evaluator.skip_spaces();
+    if evaluator.pos < evaluator.chars.len() {
+        return Err(tr!("Invalid formula: {}", formula));
+    }
+    let number = value.number()?;
+    if !number.is_finite() {
+        return Err(tr!("Numeric overflow: {}", formula));
+    }
+    Ok(format_number(number, decimals))
+}
+
+/// 式の書き換え先（`$3` は見出し以外の行すべて、`@5$3` はそのセル）
+fn targets(target: &str, rows: usize, columns: usize) -> Option<Vec<(usize, usize)>> {
+    let mut evaluator = Evaluator {
+        chars: target.trim().chars().collect(),
+        pos: 0,
+        values: &[],
+        row: 0,
+        column: 0,
+    };
+    let row = if evaluator.peek() == Some('@') {
+        evaluator.pos += 1;
+        Some(evaluator.position()?.resolve(0, rows)?)
+    } else {
+        None
+    };
+    if evaluator.peek() != Some('$') {
+        return None;
+    }
+    evaluator.pos += 1;
+    let column = evaluator.position()?.resolve(0, columns)?;
+    if evaluator.pos < evaluator.chars.len() {
+        return None;
+    }
+    Some(match row {
+        Some(row) => vec![(row, column)],
+        None => (2..=rows).map(|row| (row, column)).collect(),
+    })
+}
+
+/// 計算してセルに入れる式
+struct Assignment {
+    cells: Vec<(usize, usize)>,
+    expression: String,
+    line: usize,
+}
+
+/// 表の式を計算して書き換えるセルの一覧（セルの範囲と新しい中身）
+fn evaluate_table(
+    content: &str,
+    table: &Table,
+    errors: &mut Vec<FormulaError>,
+) -> Vec<(Range<usize>, String)> {
+    let rows = table.rows.len();
+    let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut values: Vec<Vec<String>> = table
+        .rows
+        .iter()
+        .map(|row| {
+            let mut values: Vec<String> = row.iter().map(|cell| cell.value.clone()).collect();
+            values.resize(columns, String::new());
+            values
+        })
+        .collect();
+    let original = values.clone();
+
+    // 列の式・セルの式（`tblfm`）・セルの中の式の順に計算する
+    let mut formulas = Vec::new();
+    let mut pending = Vec::new();
+    for (formula, line) in &table.formulas {
+        let Some((target, expression)) = formula.split_once('=') else {
+            errors.push(FormulaError {
+                line: *line,
+                formula: formula.clone(),
+                message: tr!("Invalid formula: {}", formula),
+            });
+            continue;
+        };
+        match targets(target, rows, columns) {
+            Some(cells) if !target.trim_start().starts_with('@') => formulas.push(Assignment {
+                cells,
+                expression: expression.trim().to_string(),
+                line: *line,
+            }),
+            Some(cells) => pending.push(Assignment {
+                cells,
+                expression: expression.trim().to_string(),
+                line: *line,
+            }),
+            None => errors.push(FormulaError {
+                line: *line,
+                formula: formula.clone(),
+                message: tr!("Reference out of range: {}", target.trim()),
+            }),
+        }
+    }
+    formulas.extend(pending);
+    for (r, row) in table.rows.iter().enumerate() {
+        for (c, cell) in row.iter().enumerate() {
+            if let Some(formula) = &cell.formula {
+                formulas.push(Assignment {
+                    cells: vec![(r + 1, c + 1)],
+                    expression: formula.clone(),
+                    line: table.lines[r],
+                });
+            }
+        }
+    }
+
+    for Assignment {
+        cells,
+        expression,
+        line,
+    } in &formulas
+    {
+        for &(row, column) in cells {
+            match evaluate(expression, &values, row, column) {
+                Ok(value) => values[row - 1][column - 1] = value,
+                Err(message) => {
+                    errors.push(FormulaError {
+                        line: *line,
+                        formula: expression.clone(),
+                        message,
+                    });
+                    break;
+                }
+            }
+        }
+    }
+
+    let mut edits = Vec::new();
+    for (r, row) in table.rows.iter().enumerate() {
+        for (c, cell) in row.iter().enumerate() {
+            if values[r][c] == original[r][c] {
+                continue;
+            }
+            let text = match &cell.formula {
+                Some(formula) => format!("{} <!-- ={formula} -->", values[r][c]),
+                None => values[r][c].clone(),
+            };
+            // 元のセルの幅に収まれば空白で埋めて揃えを保つ
+            let old = &content[cell.span.clone()];
+            let width = table::display_width(old).saturating_sub(2);
+            let pad = width.saturating_sub(table::display_width(&text));
+            let padded = if table.right.get(c).copied().unwrap_or(false) {
+                format!(" {}{text} ", " ".repeat(pad))
+            } else {
+                format!(" {text}{} ", " ".repeat(pad))
+            };
+            edits.push((cell.span.clone(), padded));
+        }
+    }
+    edits
+}
+
+/// 表の計算式を計算し、結果のセルを書き換えた内容を返す
+///
+/// 計算できなかった式は `errors` に入れ、そのセルは書き換えない。
+#[tauri::command]
+pub fn evaluate_table_formulas(content: String) -> FormulaResult {
+    let mut errors = Vec::new();
+    let mut edits = Vec::new();
+    let mut count = 0;
+    for table in tables(&content) {
+        if table.formulas.is_empty() && table.rows.iter().flatten().all(|c| c.formula.is_none()) {
+            continue;
+        }
+        count += 1;
+        edits.extend(evaluate_table(&content, &table, &mut errors));
+    }
+    let cells = edits.len();
+    let new_content = text::apply_edits(&content, edits);
+    FormulaResult {
+        changed: new_content != content,
+        edits: formatter::text_edits(&content, &new_content),
+        content: new_content,
+        tables: count,
+        cells,
+        errors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, evaluate_table_formulas};
+
+    fn grid(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(|c| c.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn unary_minus_binds_looser_than_power() {
+        let values = grid(&[&["a"]]);
+        let eval = |formula: &str| evaluate(formula, &values, 1, 1);
+        assert_eq!(eval("-2^2").unwrap(), "-4");
+        assert_eq!(eval("(-2)^2").unwrap(), "4");
+        assert_eq!(eval("2^-1").unwrap(), "0.5");
+        assert_eq!(eval("2^3^2").unwrap(), "512");
+        assert_eq!(eval("1 - -2 * 3").unwrap(), "7");
+    }
+
+    #[test]
+    fn references_and_ranges() {
+        let values = grid(&[
+            &["item", "price", "qty", "total"],
+            &["a", "1,200", "2", ""],
+            &["b", "300", "3", ""],
+            &["c", "50", "4", ""],
+        ]);
+        assert_eq!(evaluate("$2*$3", &values, 2, 4).unwrap(), "2400");
+        assert_eq!(evaluate("@-1$3", &values, 3, 4).unwrap(), "2");
+        assert_eq!(evaluate("sum(@2$2..@>$2)", &values, 1, 1).unwrap(), "1550");
+        assert_eq!(
+            evaluate("mean(@2$3..@4$3);%.1f", &values, 1, 1).unwrap(),
+            "3.0"
+        );
+        assert_eq!(evaluate("max(@2..@4)", &values, 1, 3).unwrap(), "4");
+        assert!(evaluate("@9$2", &values, 1, 1).is_err());
+        assert!(evaluate("$2..$3", &values, 2, 1).is_err());
+    }
+
+    #[test]
+    fn overflow_is_an_error() {
+        let values = grid(&[&[""]]);
+        assert!(evaluate("10^400", &values, 1, 1).is_err());
+        assert!(evaluate("10^300 * 10^300", &values, 1, 1).is_err());
+        assert!(evaluate("min(@1..@1)", &values, 1, 1).is_err());
+        assert!(evaluate("1/0", &values, 1, 1).is_err());
+    }
+
+    #[test]
+    fn writes_column_formulas() {
+        let content = "| a | b | c |\n|---|---|---|\n| 1 | 2 |   |\n| 3 | 4 |   |\n<!-- tblfm: $3=$1+$2 -->\n";
+        let result = evaluate_table_formulas(content.to_string());
+        assert!(result.errors.is_empty());
+        assert_eq!(result.cells, 2);
+        assert!(result.content.contains("| 1 | 2 | 3 |"));
+        assert!(result.content.contains("| 3 | 4 | 7 |"));
+    }
+}
@@ -0,0 +1,184 @@
+// 時間のかかる処理の進捗と中断（`job-progress` イベントと `cancel_task`）
+//
+// 書き出し・索引の作成などのコマンドは `task_id` を受け取り、進捗を送りながら別スレッドで実行する。
+// `jobs::start_job` から始めた処理は、番号を予約してから同じ仕組みで進捗を送る。
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::state::AppState;
+use crate::tr;
+
+/// 進捗を送る最短の間隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 中断されたときのエラー（表示の言語に訳す）
+pub fn cancelled_error() -> String {
+    tr!("Cancelled")
+}
+
+/// 実行中の処理
+#[derive(Default)]
+pub struct TaskState {
+    running: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    /// `start_job` が予約した番号と処理の種類（コマンドが始まると外す）
+    reserved: Mutex<HashMap<String, &'static str>>,
+    next_job: AtomicU64,
+}
+
+impl TaskState {
+    /// 処理の番号を予約する（コマンドが始まる前でも中断できる）
+    pub fn reserve(&self, kind: &'static str) -> String {
+        let id = format!("job-{}", self.next_job.fetch_add(1, Ordering::SeqCst) + 1);
+        let mut running = self.running.lock().unwrap();
+        running.insert(id.clone(), Arc::new(AtomicBool::new(false)));
+        self.reserved.lock().unwrap().insert(id.clone(), kind);
+        id
+    }
+
+    /// 中断が指示されているか
+    pub fn cancelled(&self, id: &str) -> bool {
+        self.running
+            .lock()
+            .unwrap()
+            .get(id)
+            .is_some_and(|cancel| cancel.load(Ordering::SeqCst))
+    }
+
+    /// 予約を外す（コマンドが始まらずに終わったとき）
+    pub fn release(&self, id: &str) {
+        let mut running = self.running.lock().unwrap();
+        if self.reserved.lock().unwrap().remove(id).is_some() {
+            running.remove(id);
+        }
+    }
+}
+
+/// `job-progress` イベント
+#[derive(Debug, Clone, Serialize)]
+pub struct JobProgress {
+    pub job_id: String,
+    /// `start_job` から始めた処理の種類
+    pub kind: Option<String>,
+    pub done: usize,
+    pub total: usize,
+    /// 処理中のファイルなど
+    pub message: Option<String>,
+}
+
+/// 実行中の処理（`task_id` がなければ進捗も中断もない。終わると登録を外す）
+pub struct Task<'a> {
+    app: &'a AppHandle,
+    state: &'a TaskState,
+    id: Option<String>,
+    kind: Option<&'static str>,
+    cancel: Arc<AtomicBool>,
+    last_progress: Cell<Option<Instant>>,
+}
+
+impl<'a> Task<'a> {
+    pub fn start(
+        app: &'a AppHandle,
+        state: &'a AppState,
+        id: Option<String>,
+    ) -> Result<Self, String> {
+        let mut cancel = Arc::new(AtomicBool::new(false));
+        let mut kind = None;
+        if let Some(id) = &id {
+            let mut running = state.tasks.running.lock().unwrap();
+            // 予約済みの番号なら、予約したときの中断の指示を引き継ぐ
+            kind = state.tasks.reserved.lock().unwrap().remove(id);
+            match running.get(id) {
+                Some(existing) if kind.is_some() => cancel = existing.clone(),
+                Some(_) => return Err(tr!("Task {id} is already running")),
+                None => {
+                    running.insert(id.clone(), cancel.clone());
+                }
+            }
+        }
+        Ok(Self {
+            app,
+            state: &state.tasks,
+            id,
+            kind,
+            cancel,
+            last_progress: Cell::new(None),
+        })
+    }
+
+    /// 中断されていればエラーにする（繰り返しの先頭で呼ぶ）
+    pub fn check(&self) -> Result<(), String> {
+        if self.cancel.load(Ordering::SeqCst) {
+            Err(cancelled_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// 進捗を送る（短い間隔で続いたものは間引く。最後の 1 件は必ず送る）
+    pub fn progress(&self, done: usize, total: usize, message: impl FnOnce() -> String) {
+        let Some(id) = &self.id else {
+            return;
+        };
+        let now = Instant::now();
+        let due = self
+            .last_progress
+            .get()
+            .is_none_or(|last| now.duration_since(last) >= PROGRESS_INTERVAL);
+        if !due && done < total {
+            return;
+        }
+        self.last_progress.set(Some(now));
+        let _ = self.app.emit(
+            "job-progress",
+            JobProgress {
+                job_id: id.clone(),
+                kind: self.kind.map(str::to_string),
+                done,
+                total,
+                message: Some(message()),
+            },
+        );
+    }
+}
+
+impl Drop for Task<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = &self.id {
+            self.state.running.lock().unwrap().remove(id);
+        }
+    }
+}
+
+/// 実行中の処理を中断する（見つかれば `true`。コマンドは "Cancelled" のエラーで終わる）
+#[tauri::command]
+pub fn cancel_task(state: State<'_, AppState>, task_id: String) -> bool {
+    match state.tasks.running.lock().unwrap().get(&task_id) {
+        Some(cancel) => {
+            cancel.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 実行中の処理の `task_id`
+#[tauri::command]
+pub fn list_tasks(state: State<'_, AppState>) -> Vec<String> {
+    let mut ids: Vec<String> = state
+        .tasks
+        .running
+        .lock()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect();
+    ids.sort();
+    ids
+}
@@ -0,0 +1,91 @@
+// エクスポート用の HTML テンプレート（`.mdvim/templates/*.html`）
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use serde::Serialize;
+
+use crate::tr;
+use crate::vault::{self, path_string};
+
+/// テンプレートを置くフォルダ（文書のフォルダから上へ探す）
+const TEMPLATE_DIR: &str = ".mdvim/templates";
+
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*([\w.-]+)\s*\}\}").unwrap());
+
+/// 使えるテンプレート
+#[derive(Debug, Serialize)]
+pub struct ExportTemplate {
+    pub name: String,
+    pub path: String,
+}
+
+/// テンプレートのフォルダ（文書に近い順）
+fn template_dirs(doc: &Path) -> Vec<PathBuf> {
+    let doc = vault::normalize(doc);
+    doc.ancestors()
+        .skip(1)
+        .map(|d| d.join(TEMPLATE_DIR))
+        .filter(|d| d.is_dir())
+        .collect()
+}
+
+/// テンプレートを読み込む（`name` はテンプレート名か HTML ファイルのパス）
+pub fn load(doc: &Path, name: &str) -> Result<String, String> {
+    let direct = Path::new(name);
+    let path = if direct.extension().is_some() && direct.is_file() {
+        Some(direct.to_path_buf())
+    } else {
+        template_dirs(doc)
+            .into_iter()
+            .map(|d| d.join(format!("{name}.html")))
+            .find(|p| p.is_file())
+    };
+    let path = path.ok_or_else(|| tr!("Template not found: {name}"))?;
+    fs::read_to_string(&path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))
+}
+
+/// `{{name}}` を値に置き換える（値のないものは空にする）
+pub fn fill(template: &str, values: &HashMap<String, String>) -> String {
+    PLACEHOLDER
+        .replace_all(template, |caps: &Captures| {
+            values.get(&caps[1]).cloned().unwrap_or_default()
+        })
+        .into_owned()
+}
+
+/// 文書から使えるエクスポート用テンプレート（同じ名前は近いフォルダのものを優先する）
+#[tauri::command]
+pub fn list_export_templates(path: String) -> Vec<ExportTemplate> {
+    let mut templates: Vec<ExportTemplate> = Vec::new();
+    for dir in template_dirs(Path::new(&path)) {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.extension()
+                    .is_some_and(|e| e.eq_ignore_ascii_case("html"))
+            })
+            .collect();
+        files.sort();
+        for file in files {
+            let name = file
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if !templates.iter().any(|t| t.name == name) {
+                templates.push(ExportTemplate {
+                    name,
+                    path: path_string(&file),
+                });
+            }
+        }
+    }
+    templates
+}
@@ -0,0 +1,119 @@
+// テキスト位置の共通ユーティリティ
+
+use std::ops::Range;
+
+/// バイトオフセットから行番号（1 始まり）を求めるための索引
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(content: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(content.match_indices('\n').map(|(i, _)| i + 1));
+        Self { line_starts }
+    }
+
+    /// バイトオフセットを含む行番号（1 始まり）
+    pub fn line_of(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(i) => i + 1,
+            Err(i) => i,
+        }
+    }
+
+    /// バイトオフセットのエディタ上の位置（行・UTF-16 の桁。どちらも 1 始まり）
+    pub fn position(&self, content: &str, offset: usize) -> (usize, usize) {
+        let line = self.line_of(offset);
+        let start = self.line_starts[line - 1];
+        let column = content[start..offset].encode_utf16().count() + 1;
+        (line, column)
+    }
+}
+
+/// エディタ上の位置（行・UTF-16 の桁。どちらも 1 始まり）をバイトオフセットにする（範囲外は行末・文末に寄せる）
+pub fn offset_at(content: &str, line: usize, column: usize) -> usize {
+    let start = match line.saturating_sub(1) {
+        0 => 0,
+        n => match content.match_indices('\n').nth(n - 1) {
+            Some((i, _)) => i + 1,
+            None => return content.len(),
+        },
+    };
+    let line_text = content[start..].split('\n').next().unwrap_or_default();
+    start + byte_offset(line_text, column.saturating_sub(1))
+}
+
+/// UTF-16 のオフセット（エディタの位置）をバイトオフセットにする
+pub fn byte_offset(s: &str, utf16: usize) -> usize {
+    let mut units = 0;
+    for (i, c) in s.char_indices() {
+        if units >= utf16 {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+    s.len()
+}
+
+/// ひらがな
+pub fn is_hiragana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{309F}')
+}
+
+/// カタカナ（長音符・半角カナを含む）
+pub fn is_katakana(c: char) -> bool {
+    matches!(c, '\u{30A0}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}')
+}
+
+/// 漢字（々・〆を含む）
+pub fn is_kanji(c: char) -> bool {
+    matches!(c,
+        '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}' | '々' | '〆'
+    )
+}
+
+/// 日本語の文とみなす文字（かな・漢字）
+pub fn is_cjk(c: char) -> bool {
+    is_hiragana(c) || is_katakana(c) || is_kanji(c)
+}
+
+/// 和文・欧文の句読点や括弧などの記号
+pub fn is_punctuation(c: char) -> bool {
+    if is_kanji(c) {
+        return false;
+    }
+    c.is_ascii_punctuation()
+        || matches!(c, '\u{3000}'..='\u{303F}' | '\u{FF01}'..='\u{FF0F}' | '\u{FF1A}'..='\u{FF20}'
+            | '\u{FF3B}'..='\u{FF40}' | '\u{FF5B}'..='\u{FF65}' | '・' | '…' | '‥' | '“' | '”' | '‘' | '’')
+}
+
+/// バイト範囲の置換をまとめて適用（範囲は互いに重ならないこと）
+pub fn apply_edits(content: &str, mut edits: Vec<(Range<usize>, String)>) -> String {
+    edits.sort_by_key(|(range, _)| range.start);
+    let mut out = String::with_capacity(content.len());
+    let mut last = 0;
+    for (range, replacement) in edits {
+        out.push_str(&content[last..range.start]);
+        out.push_str(&replacement);
+        last = range.end;
+    }
+    out.push_str(&content[last..]);
+    out
+}
+
+/// HTML の特殊文字をエスケープ
+pub fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
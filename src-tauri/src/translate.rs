@@ -0,0 +1,210 @@
+// Markdown 文書の翻訳（AST のテキストだけを翻訳し、記法はそのまま残す）
+
+use std::ops::Range;
+
+use pulldown_cmark::{Event, LinkType, Parser, Tag, TagEnd};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tauri_plugin_http::reqwest;
+
+use crate::keychain;
+use crate::markdown;
+use crate::semantic;
+use crate::text;
+use crate::tr;
+
+/// 1 回の API 呼び出しで送るテキストの数
+const BATCH_SIZE: usize = 50;
+
+/// 翻訳の呼び出し先
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TranslationProvider {
+    DeepL {
+        #[serde(default = "default_deepl_url")]
+        url: String,
+        /// キーチェーンに保存した API キーの名前
+        api_key_name: String,
+    },
+    Google {
+        #[serde(default = "default_google_url")]
+        url: String,
+        api_key_name: String,
+    },
+    /// ローカルのモデル（Ollama）
+    Ollama {
+        #[serde(default = "semantic::default_ollama_url")]
+        url: String,
+        model: String,
+    },
+}
+
+fn default_deepl_url() -> String {
+    "https://api-free.deepl.com/v2".to_string()
+}
+
+fn default_google_url() -> String {
+    "https://translation.googleapis.com/language/translate/v2".to_string()
+}
+
+/// 翻訳するテキストのバイト範囲（隣り合うテキストはまとめる）
+fn text_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut skip_depth = 0usize;
+    for (event, range) in Parser::new_ext(content, markdown::parser_options()).into_offset_iter() {
+        match event {
+            // ウィキリンクと自動リンクのテキストはリンク先を兼ねるので翻訳しない
+            Event::Start(
+                Tag::CodeBlock(_)
+                | Tag::MetadataBlock(_)
+                | Tag::Link {
+                    link_type: LinkType::WikiLink { .. } | LinkType::Autolink | LinkType::Email,
+                    ..
+                },
+            ) => skip_depth += 1,
+            Event::End(TagEnd::CodeBlock | TagEnd::MetadataBlock(_)) => {
+                skip_depth = skip_depth.saturating_sub(1)
+            }
+            Event::End(TagEnd::Link) if skip_depth > 0 => skip_depth -= 1,
+            Event::Text(_) if skip_depth == 0 => match ranges.last_mut() {
+                Some(last) if last.end == range.start => last.end = range.end,
+                _ => ranges.push(range),
+            },
+            _ => {}
+        }
+    }
+    ranges.retain(|r| content[r.clone()].chars().any(char::is_alphabetic));
+    ranges
+}
+
+/// 前後の空白を除いた範囲
+fn trimmed(content: &str, range: &Range<usize>) -> Range<usize> {
+    let s = &content[range.clone()];
+    let start = range.start + (s.len() - s.trim_start().len());
+    let end = range.end - (s.len() - s.trim_end().len());
+    start..end.max(start)
+}
+
+async fn post_json(url: &str, auth: Option<(&str, String)>, body: Value) -> Result<Value, String> {
+    let mut request = reqwest::Client::new()
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string());
+    if let Some((name, value)) = auth {
+        request = request.header(name, value);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| tr!("Failed to connect to {url}: {e}"))?;
+    let status = response.status();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| tr!("Failed to read response from {url}: {e}"))?;
+    if !status.is_success() {
+        return Err(tr!("Translation request failed ({status}): {text}"));
+    }
+    serde_json::from_str(&text).map_err(|e| tr!("Invalid response from {url}: {e}"))
+}
+
+fn api_key(name: &str) -> Result<String, String> {
+    keychain::get_secret(name)?.ok_or_else(|| tr!("API key \"{name}\" is not set"))
+}
+
+fn strings(values: Option<&Vec<Value>>, key: &str, expected: usize) -> Result<Vec<String>, String> {
+    let texts: Vec<String> = values
+        .map(|v| {
+            v.iter()
+                .map(|t| t[key].as_str().unwrap_or_default().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    if texts.len() != expected {
+        return Err(tr!("Translation server returned a wrong number of texts"));
+    }
+    Ok(texts)
+}
+
+/// テキストをまとめて翻訳（結果は `texts` と同じ順）
+async fn translate_batch(
+    provider: &TranslationProvider,
+    texts: &[&str],
+    from: Option<&str>,
+    to: &str,
+) -> Result<Vec<String>, String> {
+    match provider {
+        TranslationProvider::DeepL { url, api_key_name } => {
+            let mut body = json!({ "text": texts, "target_lang": to.to_uppercase() });
+            if let Some(from) = from {
+                body["source_lang"] = json!(from.to_uppercase());
+            }
+            let auth = format!("DeepL-Auth-Key {}", api_key(api_key_name)?);
+            let url = format!("{}/translate", url.trim_end_matches('/'));
+            let response = post_json(&url, Some(("Authorization", auth)), body).await?;
+            strings(response["translations"].as_array(), "text", texts.len())
+        }
+        TranslationProvider::Google { url, api_key_name } => {
+            let mut body = json!({ "q": texts, "target": to, "format": "text" });
+            if let Some(from) = from {
+                body["source"] = json!(from);
+            }
+            let key = api_key(api_key_name)?;
+            let response = post_json(url, Some(("X-Goog-Api-Key", key)), body).await?;
+            strings(
+                response["data"]["translations"].as_array(),
+                "translatedText",
+                texts.len(),
+            )
+        }
+        TranslationProvider::Ollama { url, model } => {
+            let url = format!("{}/api/generate", url.trim_end_matches('/'));
+            let source = from.map(|f| format!(" from {f}")).unwrap_or_default();
+            let mut translated = Vec::with_capacity(texts.len());
+            for text in texts {
+                let prompt = format!(
+                    "Translate the following text{source} into {to}. Keep any Markdown punctuation as is. Reply with the translation only.\n\n{text}"
+                );
+                let body = json!({ "model": model, "prompt": prompt, "stream": false });
+                let response = post_json(&url, None, body).await?;
+                let text = response["response"]
+                    .as_str()
+                    .ok_or_else(|| tr!("Invalid response from translation server"))?;
+                translated.push(text.trim().to_string());
+            }
+            Ok(translated)
+        }
+    }
+}
+
+/// Markdown の本文を翻訳する（コード・数式・URL・フロントマターなどは変えない）
+#[tauri::command]
+pub async fn translate_text(
+    text: String,
+    from: Option<String>,
+    to: String,
+    provider: TranslationProvider,
+) -> Result<String, String> {
+    if to.trim().is_empty() {
+        return Err(tr!("Target language is empty"));
+    }
+    let from = from.as_deref().map(str::trim).filter(|f| !f.is_empty());
+    let ranges: Vec<Range<usize>> = text_ranges(&text)
+        .iter()
+        .map(|r| trimmed(&text, r))
+        .collect();
+
+    let mut edits = Vec::with_capacity(ranges.len());
+    for batch in ranges.chunks(BATCH_SIZE) {
+        let sources: Vec<&str> = batch.iter().map(|r| &text[r.clone()]).collect();
+        let translated = translate_batch(&provider, &sources, from, to.trim()).await?;
+        // テキストのノードは改行を含まないので、訳文の改行は空白にする
+        edits.extend(
+            batch
+                .iter()
+                .cloned()
+                .zip(translated.into_iter().map(|t| t.replace(['\r', '\n'], " "))),
+        );
+    }
+    Ok(text::apply_edits(&text, edits))
+}
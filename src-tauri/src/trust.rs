@@ -0,0 +1,204 @@
+// ワークスペースの信頼（任意のコマンドを実行する機能を、信頼したフォルダでだけ使えるようにする）
+//
+// 信頼していないフォルダでは次を行わない:
+// - フック（`run_hooks`）と外部コマンドの前処理
+// - 外部コマンドによるフィルタ（`filter_through_command`）とシェルのコマンド（`run_shell_capture`）
+// - コードブロックの実行（`execute_code_block`）
+// - ワークスペースの外のファイルの `{{include}}`
+// - 図の外部レンダラー（Graphviz・PlantUML）と言語サーバー
+//
+// 判断はフォルダごとにアプリのデータフォルダの `trust.json` に保存し、最も近い親フォルダの判断に従う。
+// 判断がなければ信頼しない（`workspace-trust-required` イベントで確認を促す）。
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::app_data;
+use crate::state::AppState;
+use crate::tr;
+use crate::vault::{self, path_string};
+
+/// データフォルダ内のファイル
+const TRUST_FILE: &str = "trust.json";
+
+/// フォルダの信頼の判断
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TrustDecision {
+    pub trusted: bool,
+    /// 判断した日時（UNIX ミリ秒）
+    pub decided: u64,
+}
+
+/// 判断を保存したフォルダ
+#[derive(Debug, Clone, Serialize)]
+pub struct TrustedFolder {
+    pub folder: String,
+    pub trusted: bool,
+    pub decided: u64,
+}
+
+/// パスの信頼の状態
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceTrust {
+    /// 確認するときに示すフォルダ
+    pub folder: String,
+    pub trusted: bool,
+    /// 判断を下したフォルダ（まだ判断していなければ `None`）
+    pub decided_for: Option<String>,
+}
+
+/// `workspace-trust-required` イベント
+#[derive(Debug, Clone, Serialize)]
+pub struct TrustRequired {
+    pub folder: String,
+    /// 使えなかった機能
+    pub feature: String,
+}
+
+/// 信頼の判断
+#[derive(Default)]
+pub struct TrustState {
+    /// 保存先（起動時に決まる）
+    file: Mutex<Option<PathBuf>>,
+    folders: Mutex<BTreeMap<String, TrustDecision>>,
+}
+
+/// パスが属するフォルダ（フォルダならそれ自身）
+fn folder_of(path: &Path) -> PathBuf {
+    let path = vault::normalize(path);
+    if path.is_dir() {
+        path
+    } else {
+        path.parent().map(Path::to_path_buf).unwrap_or(path)
+    }
+}
+
+impl TrustState {
+    /// `path` を含み、判断を保存した最も近いフォルダ
+    fn decision(&self, path: &Path) -> Option<(PathBuf, bool)> {
+        let folders = self.folders.lock().unwrap();
+        let folder = folder_of(path);
+        folder.ancestors().find_map(|dir| {
+            folders
+                .get(&path_string(dir))
+                .map(|d| (dir.to_path_buf(), d.trusted))
+        })
+    }
+
+    /// `path`（ファイルまたはフォルダ）を含むワークスペースを信頼しているか
+    pub fn is_trusted(&self, path: &Path) -> bool {
+        self.decision(path).is_some_and(|(_, trusted)| trusted)
+    }
+
+    /// 信頼していなければ `workspace-trust-required` を送ってエラーにする
+    pub fn require(&self, app: &AppHandle, path: &Path, feature: &str) -> Result<(), String> {
+        if self.is_trusted(path) {
+            return Ok(());
+        }
+        let folder = path_string(&folder_of(path));
+        let _ = app.emit(
+            "workspace-trust-required",
+            TrustRequired {
+                folder: folder.clone(),
+                feature: feature.to_string(),
+            },
+        );
+        Err(tr!(
+            "Workspace is not trusted: {folder} ({feature} is disabled)"
+        ))
+    }
+
+    fn store(&self) -> Result<(), String> {
+        let file = self.file.lock().unwrap().clone();
+        match file {
+            Some(file) => app_data::write_json(&file, &*self.folders.lock().unwrap()),
+            None => Err(tr!("Trust settings are not loaded")),
+        }
+    }
+}
+
+/// 起動時に保存した判断を読み込む
+pub fn load_at_startup(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let Ok(file) = app_data::data_file(app, TRUST_FILE) else {
+        return;
+    };
+    if let Ok(folders) = app_data::read_json(&file) {
+        *state.trust.folders.lock().unwrap() = folders;
+    }
+    *state.trust.file.lock().unwrap() = Some(file);
+}
+
+/// ファイルまたはフォルダの信頼の状態
+#[tauri::command]
+pub fn get_workspace_trust(state: State<'_, AppState>, path: String) -> WorkspaceTrust {
+    let path = Path::new(&path);
+    let decision = state.trust.decision(path);
+    WorkspaceTrust {
+        folder: path_string(&folder_of(path)),
+        trusted: decision.as_ref().is_some_and(|(_, trusted)| *trusted),
+        decided_for: decision.map(|(dir, _)| path_string(&dir)),
+    }
+}
+
+/// フォルダを信頼する（`trusted` が `false` なら信頼しないことを記録する）
+#[tauri::command]
+pub fn set_workspace_trust(
+    state: State<'_, AppState>,
+    folder: String,
+    trusted: bool,
+) -> Result<(), String> {
+    let folder = folder_of(Path::new(&folder));
+    let decided = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+    state
+        .trust
+        .folders
+        .lock()
+        .unwrap()
+        .insert(path_string(&folder), TrustDecision { trusted, decided });
+    // 図や前処理の結果が変わるので描画結果を捨てる
+    state.parse_cache.clear();
+    state.trust.store()
+}
+
+/// フォルダの判断を取り消す
+#[tauri::command]
+pub fn forget_workspace_trust(state: State<'_, AppState>, folder: String) -> Result<(), String> {
+    let folder = path_string(&folder_of(Path::new(&folder)));
+    if state
+        .trust
+        .folders
+        .lock()
+        .unwrap()
+        .remove(&folder)
+        .is_none()
+    {
+        return Err(tr!("No trust decision for {folder}"));
+    }
+    state.parse_cache.clear();
+    state.trust.store()
+}
+
+/// 判断を保存したフォルダの一覧
+#[tauri::command]
+pub fn list_workspace_trust(state: State<'_, AppState>) -> Vec<TrustedFolder> {
+    state
+        .trust
+        .folders
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(folder, decision)| TrustedFolder {
+            folder: folder.clone(),
+            trusted: decision.trusted,
+            decided: decision.decided,
+        })
+        .collect()
+}
@@ -0,0 +1,189 @@
+// 読み上げ（プラットフォームの音声合成を利用）
+
+use std::io::Write;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::markdown;
+use crate::prose;
+use crate::state::AppState;
+use crate::tr;
+
+/// 読み上げ 1 回分のプロセスと中断フラグ
+#[derive(Default, Clone)]
+struct Session {
+    child: Arc<Mutex<Option<Child>>>,
+    cancel: Arc<AtomicBool>,
+}
+
+/// 読み上げ中のセッション
+#[derive(Default)]
+pub struct TtsState {
+    session: Mutex<Option<Session>>,
+}
+
+/// `tts-sentence` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeechPosition {
+    pub index: usize,
+    pub line: usize,
+    pub text: String,
+}
+
+/// `tts-finished` イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeechFinished {
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+/// 1 文を読み上げるプロセスを起動（テキストは標準入力で渡す）
+fn spawn_speaker(text: &str, voice: Option<&str>, rate: f64) -> std::io::Result<Child> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut c = Command::new("say");
+        if let Some(v) = voice {
+            c.args(["-v", v]);
+        }
+        c.args(["-r", &((175.0 * rate).round() as i64).to_string()]);
+        c.args(["-f", "-"]);
+        c
+    } else if cfg!(target_os = "windows") {
+        let rate = ((rate - 1.0) * 10.0).round().clamp(-10.0, 10.0) as i64;
+        let select = voice
+            .map(|v| format!("$s.SelectVoice('{}');", v.replace('\'', "''")))
+            .unwrap_or_default();
+        let script = format!(
+            "Add-Type -AssemblyName System.Speech; \
+             $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; {select} \
+             $s.Rate = {rate}; $s.Speak([Console]::In.ReadToEnd())"
+        );
+        let mut c = Command::new("powershell");
+        c.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+        c
+    } else {
+        let mut c = Command::new("espeak-ng");
+        if let Some(v) = voice {
+            c.args(["-v", v]);
+        }
+        c.args([
+            "-s",
+            &((175.0 * rate).round() as i64).to_string(),
+            "--stdin",
+        ]);
+        c
+    };
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // 書き込めなければ止めて回収する（ゾンビにしない）
+        if let Err(e) = stdin.write_all(text.as_bytes()) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e);
+        }
+    }
+    Ok(child)
+}
+
+fn stop(state: &TtsState) {
+    if let Some(session) = state.session.lock().unwrap().take() {
+        session.cancel.store(true, Ordering::SeqCst);
+        if let Some(mut child) = session.child.lock().unwrap().take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// 文書を 1 文ずつ読み上げ、読み上げ中の文を `tts-sentence` イベントで通知
+#[tauri::command]
+pub fn speak_text(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    content: String,
+    voice: Option<String>,
+    rate: Option<f64>,
+) {
+    stop(&state.tts);
+
+    let blocks = markdown::prose_blocks(&content);
+    let ja = prose::detect_lang(&blocks) == "ja";
+    let sentences: Vec<prose::Sentence> = blocks
+        .iter()
+        .flat_map(|b| prose::split_sentences(b, ja))
+        .collect();
+    let rate = rate.unwrap_or(1.0).clamp(0.25, 4.0);
+
+    let session = Session::default();
+    *state.tts.session.lock().unwrap() = Some(session.clone());
+
+    thread::spawn(move || {
+        let Session { child, cancel } = session;
+        let mut error = None;
+        for (index, sentence) in sentences.iter().enumerate() {
+            if cancel.load(Ordering::SeqCst) {
+                break;
+            }
+            let _ = app.emit(
+                "tts-sentence",
+                SpeechPosition {
+                    index,
+                    line: sentence.line,
+                    text: sentence.text.clone(),
+                },
+            );
+            match spawn_speaker(&sentence.text, voice.as_deref(), rate) {
+                Ok(mut process) => {
+                    // 起動中に停止要求が来ていれば自分で止める
+                    let mut slot = child.lock().unwrap();
+                    if cancel.load(Ordering::SeqCst) {
+                        let _ = process.kill();
+                        let _ = process.wait();
+                        break;
+                    }
+                    *slot = Some(process);
+                }
+                Err(e) => {
+                    error = Some(tr!("Failed to start speech synthesizer: {e}"));
+                    break;
+                }
+            }
+            // 停止要求で kill できるよう、ロックを保持せずに終了を待つ
+            loop {
+                let mut guard = child.lock().unwrap();
+                match guard.as_mut().map(|c| c.try_wait()) {
+                    Some(Ok(None)) => {}
+                    _ => {
+                        guard.take();
+                        break;
+                    }
+                }
+                drop(guard);
+                thread::sleep(Duration::from_millis(50));
+            }
+        }
+        let _ = app.emit(
+            "tts-finished",
+            SpeechFinished {
+                cancelled: cancel.load(Ordering::SeqCst),
+                error,
+            },
+        );
+    });
+}
+
+/// 読み上げを中断
+#[tauri::command]
+pub fn stop_speaking(state: State<'_, AppState>) {
+    stop(&state.tts);
+}
@@ -0,0 +1,126 @@
+// 取り消し履歴の保存（Vim の `undofile`。アプリのデータフォルダの `undo/` にファイルごとに置く）
+//
+// 履歴の中身はエディタが決める形式（操作の列やスナップショット）のまま保存する。
+// 保存したときのファイルのハッシュ値を記録し、外で書き換えられたファイルには履歴を返さない。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::app_data;
+use crate::integrity::{self, HashAlgorithm};
+use crate::md5;
+use crate::storage;
+use crate::tr;
+use crate::vault::{self, path_string};
+
+/// データフォルダ内のフォルダ
+const UNDO_DIR: &str = "undo";
+/// 1 ファイルの履歴の上限
+const MAX_FILE_BYTES: usize = 8 * 1024 * 1024;
+/// 履歴全体の上限（超えたら古いものから消す）
+const MAX_TOTAL_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct StoredHistory {
+    path: String,
+    /// 保存したときのファイルの SHA-256
+    content_hash: String,
+    /// 保存日時（UNIX ミリ秒）
+    saved: u64,
+    data: Value,
+}
+
+/// 読み込んだ履歴
+#[derive(Debug, Serialize)]
+pub struct UndoHistory {
+    pub data: Value,
+    pub saved: u64,
+}
+
+fn history_path(app: &AppHandle, path: &str) -> Result<PathBuf, String> {
+    let dir = app_data::data_file(app, UNDO_DIR)?;
+    Ok(dir.join(format!("{}.json", md5::hex_digest(path.as_bytes()))))
+}
+
+/// 履歴全体が上限を超えていれば古いものから消す（消した数を返す）
+fn prune(dir: &Path, max_bytes: u64) -> usize {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let modified = metadata.modified().ok()?;
+            Some((modified, metadata.len(), entry.path()))
+        })
+        .collect();
+    files.sort();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    let mut removed = 0;
+    for (_, len, path) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total -= len;
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// ファイルの取り消し履歴を保存する（ファイルを保存した直後に呼ぶ）
+#[tauri::command]
+pub fn save_undo_history(app: AppHandle, path: String, data: Value) -> Result<(), String> {
+    let path = path_string(&vault::normalize(Path::new(&path)));
+    let history = StoredHistory {
+        content_hash: integrity::file_hash(Path::new(&path), HashAlgorithm::Sha256)?,
+        saved: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64),
+        data,
+        path,
+    };
+    let json = serde_json::to_string(&history)
+        .map_err(|e| tr!("Failed to serialize undo history: {e}"))?;
+    if json.len() > MAX_FILE_BYTES {
+        return Err(tr!("Undo history for {} is too large", history.path));
+    }
+    let file = history_path(&app, &history.path)?;
+    if let Some(dir) = file.parent() {
+        storage::write_atomic(&file, json.as_bytes())?;
+        prune(dir, MAX_TOTAL_BYTES);
+    }
+    Ok(())
+}
+
+/// ファイルの取り消し履歴を読む（履歴がない、またはファイルが保存後に書き換えられていれば `None`）
+#[tauri::command]
+pub fn load_undo_history(app: AppHandle, path: String) -> Result<Option<UndoHistory>, String> {
+    let path = path_string(&vault::normalize(Path::new(&path)));
+    let file = history_path(&app, &path)?;
+    let Some(history) = storage::read_json::<StoredHistory>(&file) else {
+        return Ok(None);
+    };
+    let current = integrity::file_hash(Path::new(&path), HashAlgorithm::Sha256).ok();
+    if history.path != path || current.as_deref() != Some(history.content_hash.as_str()) {
+        return Ok(None);
+    }
+    Ok(Some(UndoHistory {
+        data: history.data,
+        saved: history.saved,
+    }))
+}
+
+/// 保存した取り消し履歴を上限まで減らす（`max_bytes` を省くと既定の上限。消した数を返す）
+#[tauri::command]
+pub fn prune_undo_history(app: AppHandle, max_bytes: Option<u64>) -> Result<usize, String> {
+    let dir = app_data::data_file(&app, UNDO_DIR)?;
+    Ok(prune(&dir, max_bytes.unwrap_or(MAX_TOTAL_BYTES)))
+}
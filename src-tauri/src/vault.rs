@@ -0,0 +1,286 @@
+// ワークスペース（ノートを置いたフォルダ）の走査とリンク解決
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::links::{self, LinkKind, LinkRef};
+use crate::obsidian;
+
+/// 走査しないディレクトリ名
+const IGNORED_DIRS: &[&str] = &["node_modules", "target"];
+
+/// Markdown ファイルか（拡張子で判定）
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("md") || e.eq_ignore_ascii_case("markdown"))
+}
+
+/// ルート以下のファイルを列挙（隠しディレクトリと `node_modules` などは除外、パス順）
+pub fn walk_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with('.') {
+                continue;
+            }
+            match entry.file_type() {
+                Ok(t) if t.is_dir() && !IGNORED_DIRS.contains(&name.as_ref()) => stack.push(path),
+                Ok(t) if t.is_file() => files.push(path),
+                _ => {}
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// `path` が `root` の中にあるか（シンボリックリンクを解決して確かめる。どちらかが存在しなければ `false`）
+pub fn is_within(root: &Path, path: &Path) -> bool {
+    match (fs::canonicalize(root), fs::canonicalize(path)) {
+        (Ok(root), Ok(path)) => path.starts_with(root),
+        _ => false,
+    }
+}
+
+/// `.` で始まるファイルやフォルダを含むか（`walk_files` が飛ばすもの）
+pub fn is_hidden(path: &Path) -> bool {
+    path.components().any(|component| match component {
+        Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        _ => false,
+    })
+}
+
+/// `.` と `..` を字句的に解決（存在しないパスにも使える）
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
+/// `/` 区切りのパス文字列
+pub fn to_slash(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `from_dir` から `to` への相対パス（`/` 区切り）
+pub fn relative_path(from_dir: &Path, to: &Path) -> String {
+    let (from_dir, to) = (normalize(from_dir), normalize(to));
+    let from: Vec<Component> = from_dir.components().collect();
+    let to_components: Vec<Component> = to.components().collect();
+    let common = from
+        .iter()
+        .zip(&to_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut parts: Vec<String> = vec!["..".to_string(); from.len() - common];
+    parts.extend(
+        to_components[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+    parts.join("/")
+}
+
+/// ファイル名の照合キー（大文字小文字を区別しない）
+fn name_key(name: &str) -> String {
+    name.to_lowercase()
+}
+
+/// ワークスペースのファイル一覧とウィキリンク解決用の索引
+pub struct Vault {
+    pub root: PathBuf,
+    pub files: Vec<PathBuf>,
+    by_name: HashMap<String, Vec<PathBuf>>,
+    /// フロントマターの別名（Obsidian の保管庫のみ）
+    aliases: HashMap<String, PathBuf>,
+}
+
+impl Vault {
+    pub fn scan(root: &Path) -> Self {
+        let root = normalize(root);
+        let files = walk_files(&root);
+        let mut by_name: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for file in &files {
+            if let Some(name) = file.file_name().and_then(|n| n.to_str()) {
+                by_name
+                    .entry(name_key(name))
+                    .or_default()
+                    .push(file.clone());
+            }
+        }
+        let mut aliases = HashMap::new();
+        if obsidian::is_vault(&root) {
+            for file in files.iter().filter(|p| is_markdown(p)) {
+                let Ok(content) = fs::read_to_string(file) else {
+                    continue;
+                };
+                for alias in obsidian::note_aliases(&content) {
+                    aliases
+                        .entry(name_key(&alias))
+                        .or_insert_with(|| file.clone());
+                }
+            }
+        }
+        Self {
+            root,
+            files,
+            by_name,
+            aliases,
+        }
+    }
+
+    /// Markdown ファイルのみ
+    pub fn markdown_files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.iter().filter(|p| is_markdown(p))
+    }
+
+    /// ウィキリンクのノート名からファイルを探す（同じフォルダ、パスの短いものを優先。なければ別名から）
+    fn resolve_wiki(&self, doc: &Path, target: &str) -> Option<PathBuf> {
+        let has_ext = Path::new(target).extension().is_some_and(|e| {
+            let e = e.to_string_lossy();
+            !e.is_empty() && !e.contains(' ')
+        });
+        let file = if has_ext {
+            target.to_string()
+        } else {
+            format!("{target}.md")
+        };
+        if target.contains('/') {
+            let from_root = normalize(&self.root.join(&file));
+            let from_doc = normalize(&doc.parent().unwrap_or(&self.root).join(&file));
+            return [from_root, from_doc].into_iter().find(|p| p.is_file());
+        }
+        let Some(candidates) = self.by_name.get(&name_key(&file)) else {
+            return self.aliases.get(&name_key(target)).cloned();
+        };
+        let doc_dir = doc.parent();
+        candidates
+            .iter()
+            .find(|p| p.parent() == doc_dir)
+            .or_else(|| candidates.iter().min_by_key(|p| p.components().count()))
+            .cloned()
+    }
+
+    /// リンク先のファイルパス（存在しない場合も相対リンクなら解決後のパスを返す）
+    pub fn resolve(&self, doc: &Path, link: &LinkRef) -> Option<PathBuf> {
+        if link.target.is_empty() {
+            // 同じ文書内のアンカー
+            return (link.anchor.is_some() || link.is_wiki()).then(|| doc.to_path_buf());
+        }
+        self.resolve_target(doc, &link.target, link.is_wiki())
+    }
+
+    /// `#` を除いたリンク先文字列からファイルパスを解決
+    pub fn resolve_target(&self, doc: &Path, target: &str, wiki: bool) -> Option<PathBuf> {
+        if target.is_empty() {
+            return Some(doc.to_path_buf());
+        }
+        if wiki {
+            return self.resolve_wiki(doc, target);
+        }
+        if links::is_external(target) {
+            return None;
+        }
+        let target = links::percent_decode(target);
+        let path = match target.strip_prefix('/') {
+            Some(rest) => self.root.join(rest),
+            None => doc.parent().unwrap_or(&self.root).join(target),
+        };
+        Some(normalize(&path))
+    }
+
+    /// `doc` 内のリンク `link` が `new_path` を指すように書き換えたリンク先文字列
+    pub fn link_text(&self, doc: &Path, link: &LinkRef, new_path: &Path) -> String {
+        if link.is_wiki() {
+            let keep_ext = Path::new(&link.target).extension().is_some() || !is_markdown(new_path);
+            let path = if link.target.contains('/') {
+                let relative = new_path.strip_prefix(&self.root).unwrap_or(new_path);
+                relative.to_path_buf()
+            } else {
+                PathBuf::from(new_path.file_name().unwrap_or_default())
+            };
+            let path = if keep_ext {
+                path
+            } else {
+                path.with_extension("")
+            };
+            return to_slash(&path);
+        }
+        let text = match link.target.strip_prefix('/') {
+            Some(_) => format!(
+                "/{}",
+                to_slash(new_path.strip_prefix(&self.root).unwrap_or(new_path))
+            ),
+            None => relative_path(doc.parent().unwrap_or(&self.root), new_path),
+        };
+        if link.kind == LinkKind::Html {
+            text
+        } else {
+            links::percent_encode(&text)
+        }
+    }
+}
+
+/// パスを文字列に変換（コマンドの戻り値用）
+pub fn path_string(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::symlink;
+
+    use std::path::Path;
+
+    use super::{is_hidden, is_within};
+
+    #[test]
+    fn symlinks_out_of_the_root_are_not_within_it() {
+        let base = std::env::temp_dir().join(format!("mdvim-vault-{}", std::process::id()));
+        let (root, outside) = (base.join("vault"), base.join("outside"));
+        fs::create_dir_all(root.join("notes")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(root.join("notes/a.md"), "a").unwrap();
+        fs::write(outside.join("secret.md"), "secret").unwrap();
+        symlink(outside.join("secret.md"), root.join("link.md")).unwrap();
+        symlink(&outside, root.join("dir")).unwrap();
+
+        assert!(is_within(&root, &root.join("notes/a.md")));
+        assert!(!is_within(&root, &root.join("link.md")));
+        assert!(!is_within(&root, &root.join("dir/secret.md")));
+        assert!(!is_within(&root, &root.join("missing.md")));
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn dot_components_are_hidden() {
+        assert!(is_hidden(Path::new(".git/config")));
+        assert!(is_hidden(Path::new("notes/.obsidian/app.json")));
+        assert!(is_hidden(Path::new(".env")));
+        assert!(!is_hidden(Path::new("notes/a.md")));
+        assert!(!is_hidden(Path::new("../notes/a.md")));
+    }
+}
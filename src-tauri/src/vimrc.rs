@@ -0,0 +1,329 @@
+// Vim の設定ファイル（`~/.mdvimrc`、なければ設定フォルダの `mdvim.toml` の `[vim]`）
+//
+// `.mdvimrc` で使えるもの:
+// - `set wrap` / `set nowrap` / `set tabstop=4` / `set wrap!`
+// - `let mapleader = ","`
+// - `map` `nmap` `nnoremap` `vnoremap` `xnoremap` `inoremap` `onoremap` `cnoremap` など
+// - `ab` `iab` `cab`（`abbreviate` `iabbrev` `cabbrev`）
+// - `"` で始まる行はコメント
+//
+// 起動時に読み込み、`reload_vim_config` で読み直す。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::app_data;
+use crate::state::AppState;
+use crate::tr;
+
+/// ホームフォルダの設定ファイル
+const VIMRC_FILE: &str = ".mdvimrc";
+/// 設定フォルダの設定ファイル
+const TOML_FILE: &str = "mdvim.toml";
+
+/// 短い名前の設定
+const OPTION_ALIASES: &[(&str, &str)] = &[
+    ("ts", "tabstop"),
+    ("sw", "shiftwidth"),
+    ("sts", "softtabstop"),
+    ("et", "expandtab"),
+    ("nu", "number"),
+    ("rnu", "relativenumber"),
+    ("ic", "ignorecase"),
+    ("scs", "smartcase"),
+    ("hls", "hlsearch"),
+    ("is", "incsearch"),
+    ("tw", "textwidth"),
+    ("so", "scrolloff"),
+    ("ai", "autoindent"),
+];
+
+/// 設定の値
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum OptionValue {
+    Bool(bool),
+    Number(i64),
+    Text(String),
+}
+
+/// キーの割り当て
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VimMapping {
+    /// "n" | "v" | "x" | "i" | "o" | "c"（空なら `map` と同じくノーマル・ビジュアル・オペレータ待ち）
+    #[serde(default)]
+    pub mode: String,
+    pub lhs: String,
+    pub rhs: String,
+    /// `noremap` 系でなければ `true`
+    #[serde(default)]
+    pub recursive: bool,
+}
+
+/// 短縮入力
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VimAbbreviation {
+    /// "i" | "c"（空なら両方）
+    #[serde(default)]
+    pub mode: String,
+    pub lhs: String,
+    pub rhs: String,
+}
+
+/// 読めなかった行
+#[derive(Debug, Clone, Serialize)]
+pub struct VimConfigWarning {
+    /// 行番号（1 始まり）
+    pub line: usize,
+    pub message: String,
+}
+
+/// Vim の設定
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct VimConfig {
+    /// 読み込んだファイル（なければ `None`）
+    #[serde(skip_deserializing)]
+    pub source: Option<String>,
+    pub leader: String,
+    pub options: BTreeMap<String, OptionValue>,
+    pub mappings: Vec<VimMapping>,
+    pub abbreviations: Vec<VimAbbreviation>,
+    #[serde(skip_deserializing)]
+    pub warnings: Vec<VimConfigWarning>,
+}
+
+impl Default for VimConfig {
+    fn default() -> Self {
+        Self {
+            source: None,
+            leader: "\\".to_string(),
+            options: BTreeMap::new(),
+            mappings: Vec::new(),
+            abbreviations: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// 読み込んだ設定
+#[derive(Default)]
+pub struct VimrcState {
+    config: Mutex<Option<VimConfig>>,
+}
+
+/// `mdvim.toml` のうち Vim の部分
+#[derive(Debug, Default, Deserialize)]
+struct TomlFile {
+    #[serde(default)]
+    vim: VimConfig,
+}
+
+fn option_name(name: &str) -> String {
+    OPTION_ALIASES
+        .iter()
+        .find(|(short, _)| *short == name)
+        .map_or(name, |(_, long)| long)
+        .to_string()
+}
+
+/// `set` の引数 1 つ
+fn apply_set(options: &mut BTreeMap<String, OptionValue>, arg: &str) -> Result<(), String> {
+    if let Some((name, value)) = arg.split_once(['=', ':']) {
+        let value = match value.parse::<i64>() {
+            Ok(n) => OptionValue::Number(n),
+            Err(_) => OptionValue::Text(value.to_string()),
+        };
+        options.insert(option_name(name), value);
+    } else if let Some(name) = arg.strip_suffix('!').or_else(|| arg.strip_prefix("inv")) {
+        let name = option_name(name);
+        let current = options.get(&name) == Some(&OptionValue::Bool(true));
+        options.insert(name, OptionValue::Bool(!current));
+    } else if let Some(name) = arg.strip_prefix("no").filter(|n| !n.is_empty()) {
+        options.insert(option_name(name), OptionValue::Bool(false));
+    } else if arg.chars().all(|c| c.is_ascii_alphanumeric()) && !arg.is_empty() {
+        options.insert(option_name(arg), OptionValue::Bool(true));
+    } else {
+        return Err(tr!("Invalid option: {arg}"));
+    }
+    Ok(())
+}
+
+/// `map` 系のコマンドのモードと再帰の有無
+fn map_command(command: &str) -> Option<(&'static str, bool)> {
+    let (mode, rest) = match command.chars().next()? {
+        'n' if command != "no" && !command.starts_with("nor") => ("n", &command[1..]),
+        'v' => ("v", &command[1..]),
+        'x' => ("x", &command[1..]),
+        'i' => ("i", &command[1..]),
+        'o' => ("o", &command[1..]),
+        'c' => ("c", &command[1..]),
+        _ => ("", command),
+    };
+    match rest {
+        "map" => Some((mode, true)),
+        "noremap" | "nore" | "no" => Some((mode, false)),
+        _ => None,
+    }
+}
+
+/// `ab` 系のコマンドのモード
+fn abbreviation_command(command: &str) -> Option<&'static str> {
+    let (mode, rest) = match command.chars().next()? {
+        'i' => ("i", &command[1..]),
+        'c' => ("c", &command[1..]),
+        _ => ("", command),
+    };
+    let rest = rest.strip_prefix("nore").unwrap_or(rest);
+    (rest.len() >= 2 && "abbreviate".starts_with(rest)).then_some(mode)
+}
+
+/// 引用符を外す（`"\<Space>"` は空白）
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let inner = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value);
+    match inner {
+        "\\<Space>" | "<Space>" => " ".to_string(),
+        "\\\\" => "\\".to_string(),
+        _ => inner.to_string(),
+    }
+}
+
+/// `.mdvimrc` を読む
+pub fn parse_vimrc(content: &str) -> VimConfig {
+    let mut config = VimConfig::default();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('"') {
+            continue;
+        }
+        let mut warn = |message: String| {
+            config.warnings.push(VimConfigWarning {
+                line: i + 1,
+                message,
+            })
+        };
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        if command == "set" || command == "se" {
+            for arg in rest.split_whitespace() {
+                if let Err(e) = apply_set(&mut config.options, arg) {
+                    warn(e);
+                }
+            }
+        } else if command == "let" {
+            match rest.split_once('=') {
+                Some((name, value)) if matches!(name.trim(), "mapleader" | "g:mapleader") => {
+                    config.leader = unquote(value)
+                }
+                _ => warn(tr!("Unsupported variable: {rest}")),
+            }
+        } else if let Some((mode, recursive)) = map_command(command) {
+            // `<silent>` などの引数は無視する
+            let mut parts = rest.split_whitespace().skip_while(|p| {
+                p.starts_with('<') && p.ends_with('>') && p.len() > 2 && {
+                    let arg = p[1..p.len() - 1].to_ascii_lowercase();
+                    matches!(
+                        arg.as_str(),
+                        "silent" | "buffer" | "nowait" | "expr" | "unique" | "special"
+                    )
+                }
+            });
+            match (parts.next(), parts.collect::<Vec<_>>().join(" ")) {
+                (Some(lhs), rhs) if !rhs.is_empty() => config.mappings.push(VimMapping {
+                    mode: mode.to_string(),
+                    lhs: lhs.to_string(),
+                    rhs,
+                    recursive,
+                }),
+                _ => warn(tr!("Incomplete mapping: {line}")),
+            }
+        } else if let Some(mode) = abbreviation_command(command) {
+            match rest.split_once(char::is_whitespace) {
+                Some((lhs, rhs)) => config.abbreviations.push(VimAbbreviation {
+                    mode: mode.to_string(),
+                    lhs: lhs.to_string(),
+                    rhs: rhs.trim().to_string(),
+                }),
+                None => warn(tr!("Incomplete abbreviation: {line}")),
+            }
+        } else {
+            warn(tr!("Unsupported command: {command}"));
+        }
+    }
+    config
+}
+
+/// 設定ファイルの候補（`.mdvimrc`、`mdvim.toml` の順）
+fn config_files(app: &AppHandle) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Ok(home) = app.path().home_dir() {
+        files.push(home.join(VIMRC_FILE));
+    }
+    if let Ok(toml) = app_data::config_file(app, TOML_FILE) {
+        files.push(toml);
+    }
+    files
+}
+
+/// 最初に見つかった設定ファイルを読む（なければ既定の設定）
+pub fn load(app: &AppHandle) -> Result<VimConfig, String> {
+    for path in config_files(app) {
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let mut config = if path.extension().is_some_and(|e| e == "toml") {
+            toml::from_str::<TomlFile>(&content)
+                .map_err(|e| tr!("Invalid {}: {e}", path.display()))?
+                .vim
+        } else {
+            parse_vimrc(&content)
+        };
+        config.options = config
+            .options
+            .into_iter()
+            .map(|(name, value)| (option_name(&name), value))
+            .collect();
+        config.source = Some(path.to_string_lossy().into_owned());
+        return Ok(config);
+    }
+    Ok(VimConfig::default())
+}
+
+/// 起動時に読み込む（読めなければ既定の設定にして `vim-config-error` を送る）
+pub fn load_at_startup(app: &AppHandle) {
+    let config = load(app).unwrap_or_else(|e| {
+        let _ = app.emit("vim-config-error", e);
+        VimConfig::default()
+    });
+    *app.state::<AppState>().vimrc.config.lock().unwrap() = Some(config);
+}
+
+/// Vim の設定（割り当て・設定・短縮入力）
+#[tauri::command]
+pub fn get_vim_config(app: AppHandle, state: State<'_, AppState>) -> Result<VimConfig, String> {
+    let mut config = state.vimrc.config.lock().unwrap();
+    if let Some(config) = config.as_ref() {
+        return Ok(config.clone());
+    }
+    let loaded = load(&app)?;
+    *config = Some(loaded.clone());
+    Ok(loaded)
+}
+
+/// 設定ファイルを読み直す
+#[tauri::command]
+pub fn reload_vim_config(app: AppHandle, state: State<'_, AppState>) -> Result<VimConfig, String> {
+    let loaded = load(&app)?;
+    *state.vimrc.config.lock().unwrap() = Some(loaded.clone());
+    Ok(loaded)
+}
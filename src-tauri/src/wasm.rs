@@ -0,0 +1,1823 @@
+// プラグイン用の WebAssembly インタプリタ
+//
+// MVP に符号拡張・飽和変換・バルクメモリ命令を加えたものに対応する（SIMD・スレッドは非対応）。
+// モジュールは信頼しない前提で、命令数・メモリ・呼び出しの深さを制限して実行する。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const PAGE_SIZE: usize = 65_536;
+const MAX_CALL_DEPTH: usize = 1_000;
+const MAX_STACK: usize = 1 << 20;
+const MAX_LOCALS: usize = 50_000;
+/// null の関数参照
+const NULL_REF: u64 = u64::MAX;
+/// i64 に変換できる値の下限の 1 つ手前（-2^63 の直前の f64）
+const I64_MIN_BOUND: f64 = -9_223_372_036_854_777_856.0;
+
+/// 実行時の制限
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// メモリの上限（64 KiB 単位のページ数）
+    pub max_pages: u32,
+    /// 1 回の呼び出しで実行できる命令数
+    pub fuel: u64,
+}
+
+/// インポートした関数の呼び出し先（値は 64 ビットに詰め、i32 はゼロ拡張する）
+pub trait Host {
+    fn call(
+        &mut self,
+        module: &str,
+        name: &str,
+        args: &[u64],
+        memory: &mut [u8],
+    ) -> Result<Vec<u64>, String>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct FuncType {
+    params: Vec<u8>,
+    results: Vec<u8>,
+}
+
+struct Import {
+    module: String,
+    name: String,
+}
+
+/// 定数式（初期化式）
+#[derive(Clone, Copy)]
+enum ConstExpr {
+    Value(u64),
+    Global(u32),
+}
+
+struct Element {
+    /// 能動セグメントのテーブル内の位置
+    offset: Option<ConstExpr>,
+    refs: Vec<u64>,
+}
+
+struct Data {
+    offset: Option<ConstExpr>,
+    bytes: Vec<u8>,
+}
+
+struct Func {
+    /// 引数以外のローカル変数の数
+    locals: usize,
+    code: Vec<Instr>,
+}
+
+/// 分岐先を解決済みの命令
+enum Instr {
+    Unreachable,
+    Nop,
+    /// `end` は対応する `end` の次の位置
+    Block {
+        end: usize,
+        params: usize,
+        results: usize,
+    },
+    Loop {
+        params: usize,
+    },
+    /// 条件が偽なら `else_` へ（`else` がなければ `end` 命令の位置）
+    If {
+        else_: usize,
+        end: usize,
+        params: usize,
+        results: usize,
+    },
+    /// `end` は対応する `end` 命令の位置
+    Else {
+        end: usize,
+    },
+    End,
+    Br(u32),
+    BrIf(u32),
+    BrTable(Box<[u32]>, u32),
+    Return,
+    Call(u32),
+    CallIndirect(u32),
+    Drop,
+    Select,
+    LocalGet(u32),
+    LocalSet(u32),
+    LocalTee(u32),
+    GlobalGet(u32),
+    GlobalSet(u32),
+    Load {
+        size: u8,
+        signed: bool,
+        wide: bool,
+        offset: u32,
+    },
+    Store {
+        size: u8,
+        offset: u32,
+    },
+    MemorySize,
+    MemoryGrow,
+    Const(u64),
+    /// 0x45..=0xC4 の数値命令
+    Numeric(u8),
+    /// 0xFC 0..=7 の飽和変換
+    TruncSat(u8),
+    MemoryInit(u32),
+    DataDrop(u32),
+    MemoryCopy,
+    MemoryFill,
+    RefNull,
+    RefIsNull,
+    RefFunc(u32),
+}
+
+/// 解析済みのモジュール
+pub struct Module {
+    types: Vec<FuncType>,
+    imports: Vec<Import>,
+    /// インポートを含む全関数の型
+    func_types: Vec<u32>,
+    funcs: Vec<Func>,
+    table: Option<u32>,
+    memory: Option<(u32, Option<u32>)>,
+    globals: Vec<ConstExpr>,
+    exports: HashMap<String, u32>,
+    start: Option<u32>,
+    elements: Vec<Element>,
+    data: Vec<Data>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn eof(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        let b = *self
+            .data
+            .get(self.pos)
+            .ok_or_else(|| "unexpected end of data".to_string())?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn peek(&self) -> Result<u8, String> {
+        self.data
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| "unexpected end of data".to_string())
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| "unexpected end of data".to_string())?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn leb(&mut self, bits: u32, signed: bool) -> Result<u64, String> {
+        let mut result = 0u64;
+        let mut shift = 0;
+        loop {
+            let b = self.byte()?;
+            if shift < 64 {
+                result |= u64::from(b & 0x7f) << shift;
+            }
+            shift += 7;
+            if b & 0x80 == 0 {
+                if signed && shift < 64 && b & 0x40 != 0 {
+                    result |= !0u64 << shift;
+                }
+                return Ok(result);
+            }
+            if shift >= bits.div_ceil(7) * 7 {
+                return Err("malformed integer".to_string());
+            }
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(self.leb(32, false)? as u32)
+    }
+
+    fn len(&mut self) -> Result<usize, String> {
+        let len = self.u32()? as usize;
+        // 要素は少なくとも 1 バイトあるので、残りより多ければ壊れている
+        if len > self.data.len() - self.pos {
+            return Err("invalid length".to_string());
+        }
+        Ok(len)
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let len = self.len()?;
+        let bytes = self.bytes(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| "invalid UTF-8 name".to_string())
+    }
+
+    fn limits(&mut self) -> Result<(u32, Option<u32>), String> {
+        match self.byte()? {
+            0x00 => Ok((self.u32()?, None)),
+            0x01 => Ok((self.u32()?, Some(self.u32()?))),
+            flags => Err(format!("unsupported limits flags 0x{flags:02x}")),
+        }
+    }
+}
+
+fn value_type(b: u8) -> Result<u8, String> {
+    match b {
+        0x7f | 0x7e | 0x7d | 0x7c | 0x70 | 0x6f => Ok(b),
+        0x7b => Err("SIMD is not supported".to_string()),
+        _ => Err(format!("invalid value type 0x{b:02x}")),
+    }
+}
+
+/// 定数命令の即値
+fn const_value(op: u8, r: &mut Reader) -> Result<u64, String> {
+    Ok(match op {
+        0x41 => r.leb(32, true)? as u32 as u64,
+        0x42 => r.leb(64, true)?,
+        0x43 => {
+            let b = r.bytes(4)?;
+            u64::from(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        }
+        _ => {
+            let b = r.bytes(8)?;
+            u64::from_le_bytes(b.try_into().unwrap_or_default())
+        }
+    })
+}
+
+fn const_expr(r: &mut Reader) -> Result<ConstExpr, String> {
+    let expr = match r.byte()? {
+        op @ 0x41..=0x44 => ConstExpr::Value(const_value(op, r)?),
+        0x23 => ConstExpr::Global(r.u32()?),
+        0xd0 => {
+            r.byte()?;
+            ConstExpr::Value(NULL_REF)
+        }
+        0xd2 => ConstExpr::Value(u64::from(r.u32()?)),
+        op => return Err(format!("unsupported constant expression 0x{op:02x}")),
+    };
+    match r.byte()? {
+        0x0b => Ok(expr),
+        _ => Err("unsupported constant expression".to_string()),
+    }
+}
+
+/// ブロック型を引数と戻り値の数にする
+fn block_type(r: &mut Reader, types: &[FuncType]) -> Result<(usize, usize), String> {
+    let b = r.peek()?;
+    if b == 0x40 {
+        r.byte()?;
+        return Ok((0, 0));
+    }
+    // 1 バイトの負数は値型、それ以外は型の番号
+    if b & 0xc0 == 0x40 {
+        value_type(r.byte()?)?;
+        return Ok((0, 1));
+    }
+    let index = r.leb(33, true)?;
+    let ty = types
+        .get(index as usize)
+        .ok_or_else(|| "invalid block type".to_string())?;
+    Ok((ty.params.len(), ty.results.len()))
+}
+
+/// 関数本体を命令列に変換し、分岐先を解決する
+fn compile(r: &mut Reader, types: &[FuncType]) -> Result<Vec<Instr>, String> {
+    let mut code = Vec::new();
+    // 閉じていないブロック（命令の位置と else の位置）
+    let mut open: Vec<(usize, Option<usize>)> = Vec::new();
+    loop {
+        let op = r.byte()?;
+        let instr = match op {
+            0x00 => Instr::Unreachable,
+            0x01 => Instr::Nop,
+            0x02..=0x04 => {
+                let (params, results) = block_type(r, types)?;
+                open.push((code.len(), None));
+                match op {
+                    0x02 => Instr::Block {
+                        end: 0,
+                        params,
+                        results,
+                    },
+                    0x03 => Instr::Loop { params },
+                    _ => Instr::If {
+                        else_: 0,
+                        end: 0,
+                        params,
+                        results,
+                    },
+                }
+            }
+            0x05 => {
+                let entry = open
+                    .last_mut()
+                    .filter(|(start, else_at)| {
+                        matches!(code[*start], Instr::If { .. }) && else_at.is_none()
+                    })
+                    .ok_or_else(|| "else outside of if".to_string())?;
+                entry.1 = Some(code.len());
+                Instr::Else { end: 0 }
+            }
+            0x0b => {
+                let end = code.len();
+                code.push(Instr::End);
+                let Some((start, else_at)) = open.pop() else {
+                    // 関数本体の終わり
+                    return Ok(code);
+                };
+                match &mut code[start] {
+                    Instr::Block { end: e, .. } => *e = end + 1,
+                    Instr::If { else_, end: e, .. } => {
+                        *e = end + 1;
+                        *else_ = else_at.map_or(end, |i| i + 1);
+                    }
+                    _ => {}
+                }
+                if let Some(i) = else_at {
+                    code[i] = Instr::Else { end };
+                }
+                continue;
+            }
+            0x0c => Instr::Br(r.u32()?),
+            0x0d => Instr::BrIf(r.u32()?),
+            0x0e => {
+                let count = r.len()?;
+                let targets = (0..count).map(|_| r.u32()).collect::<Result<Vec<_>, _>>()?;
+                Instr::BrTable(targets.into_boxed_slice(), r.u32()?)
+            }
+            0x0f => Instr::Return,
+            0x10 => Instr::Call(r.u32()?),
+            0x11 => {
+                let ty = r.u32()?;
+                if r.u32()? != 0 {
+                    return Err("multiple tables are not supported".to_string());
+                }
+                Instr::CallIndirect(ty)
+            }
+            0x1a => Instr::Drop,
+            0x1b => Instr::Select,
+            0x1c => {
+                for _ in 0..r.len()? {
+                    value_type(r.byte()?)?;
+                }
+                Instr::Select
+            }
+            0x20 => Instr::LocalGet(r.u32()?),
+            0x21 => Instr::LocalSet(r.u32()?),
+            0x22 => Instr::LocalTee(r.u32()?),
+            0x23 => Instr::GlobalGet(r.u32()?),
+            0x24 => Instr::GlobalSet(r.u32()?),
+            0x28..=0x3e => {
+                let align = r.u32()?;
+                if align & 0x40 != 0 && r.u32()? != 0 {
+                    return Err("multiple memories are not supported".to_string());
+                }
+                let offset = r.u32()?;
+                match op {
+                    0x28..=0x35 => {
+                        let (size, signed, wide) = match op {
+                            0x28 | 0x2a => (4, false, false),
+                            0x29 | 0x2b => (8, false, true),
+                            0x2c => (1, true, false),
+                            0x2d => (1, false, false),
+                            0x2e => (2, true, false),
+                            0x2f => (2, false, false),
+                            0x30 => (1, true, true),
+                            0x31 => (1, false, true),
+                            0x32 => (2, true, true),
+                            0x33 => (2, false, true),
+                            0x34 => (4, true, true),
+                            _ => (4, false, true),
+                        };
+                        Instr::Load {
+                            size,
+                            signed,
+                            wide,
+                            offset,
+                        }
+                    }
+                    _ => {
+                        let size = match op {
+                            0x36 | 0x38 | 0x3e => 4,
+                            0x37 | 0x39 => 8,
+                            0x3a | 0x3c => 1,
+                            _ => 2,
+                        };
+                        Instr::Store { size, offset }
+                    }
+                }
+            }
+            0x3f => {
+                r.byte()?;
+                Instr::MemorySize
+            }
+            0x40 => {
+                r.byte()?;
+                Instr::MemoryGrow
+            }
+            0x41..=0x44 => Instr::Const(const_value(op, r)?),
+            0x45..=0xc4 => Instr::Numeric(op),
+            0xd0 => {
+                r.byte()?;
+                Instr::RefNull
+            }
+            0xd1 => Instr::RefIsNull,
+            0xd2 => Instr::RefFunc(r.u32()?),
+            0xfc => match r.u32()? {
+                sub @ 0..=7 => Instr::TruncSat(sub as u8),
+                8 => {
+                    let segment = r.u32()?;
+                    r.byte()?;
+                    Instr::MemoryInit(segment)
+                }
+                9 => Instr::DataDrop(r.u32()?),
+                10 => {
+                    r.bytes(2)?;
+                    Instr::MemoryCopy
+                }
+                11 => {
+                    r.byte()?;
+                    Instr::MemoryFill
+                }
+                sub => return Err(format!("unsupported instruction 0xfc {sub}")),
+            },
+            _ => return Err(format!("unsupported instruction 0x{op:02x}")),
+        };
+        code.push(instr);
+    }
+}
+
+impl Module {
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        Self::parse_sections(bytes).map_err(|e| format!("Invalid WebAssembly module: {e}"))
+    }
+
+    fn parse_sections(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < 8 || &bytes[..4] != b"\0asm" {
+            return Err("missing magic number".to_string());
+        }
+        if bytes[4..8] != [1, 0, 0, 0] {
+            return Err("unsupported version".to_string());
+        }
+        let mut module = Module {
+            types: Vec::new(),
+            imports: Vec::new(),
+            func_types: Vec::new(),
+            funcs: Vec::new(),
+            table: None,
+            memory: None,
+            globals: Vec::new(),
+            exports: HashMap::new(),
+            start: None,
+            elements: Vec::new(),
+            data: Vec::new(),
+        };
+        let mut declared = Vec::new();
+        let mut r = Reader::new(&bytes[8..]);
+        while !r.eof() {
+            let id = r.byte()?;
+            let len = r.u32()? as usize;
+            let mut s = Reader::new(r.bytes(len)?);
+            match id {
+                0 | 12 => {}
+                1 => {
+                    for _ in 0..s.len()? {
+                        if s.byte()? != 0x60 {
+                            return Err("invalid function type".to_string());
+                        }
+                        let params = (0..s.len()?)
+                            .map(|_| value_type(s.byte()?))
+                            .collect::<Result<_, _>>()?;
+                        let results = (0..s.len()?)
+                            .map(|_| value_type(s.byte()?))
+                            .collect::<Result<_, _>>()?;
+                        module.types.push(FuncType { params, results });
+                    }
+                }
+                2 => {
+                    for _ in 0..s.len()? {
+                        let import = Import {
+                            module: s.name()?,
+                            name: s.name()?,
+                        };
+                        if s.byte()? != 0x00 {
+                            return Err(format!(
+                                "only function imports are supported ({}.{})",
+                                import.module, import.name
+                            ));
+                        }
+                        module.func_types.push(s.u32()?);
+                        module.imports.push(import);
+                    }
+                }
+                3 => {
+                    for _ in 0..s.len()? {
+                        declared.push(s.u32()?);
+                    }
+                }
+                4 => {
+                    for _ in 0..s.len()? {
+                        value_type(s.byte()?)?;
+                        let (min, _) = s.limits()?;
+                        if module.table.replace(min).is_some() {
+                            return Err("multiple tables are not supported".to_string());
+                        }
+                    }
+                }
+                5 => {
+                    for _ in 0..s.len()? {
+                        if module.memory.replace(s.limits()?).is_some() {
+                            return Err("multiple memories are not supported".to_string());
+                        }
+                    }
+                }
+                6 => {
+                    for _ in 0..s.len()? {
+                        value_type(s.byte()?)?;
+                        s.byte()?;
+                        module.globals.push(const_expr(&mut s)?);
+                    }
+                }
+                7 => {
+                    for _ in 0..s.len()? {
+                        let name = s.name()?;
+                        let kind = s.byte()?;
+                        let index = s.u32()?;
+                        if kind == 0x00 {
+                            module.exports.insert(name, index);
+                        }
+                    }
+                }
+                8 => module.start = Some(s.u32()?),
+                9 => {
+                    for _ in 0..s.len()? {
+                        module.elements.push(parse_element(&mut s)?);
+                    }
+                }
+                10 => {
+                    let count = s.len()?;
+                    if count != declared.len() {
+                        return Err("function and code counts differ".to_string());
+                    }
+                    for &ty in &declared {
+                        let size = s.u32()? as usize;
+                        let mut body = Reader::new(s.bytes(size)?);
+                        let mut locals = 0usize;
+                        for _ in 0..body.len()? {
+                            locals += body.u32()? as usize;
+                            value_type(body.byte()?)?;
+                            if locals > MAX_LOCALS {
+                                return Err("too many locals".to_string());
+                            }
+                        }
+                        let code = compile(&mut body, &module.types)?;
+                        module.func_types.push(ty);
+                        module.funcs.push(Func { locals, code });
+                    }
+                }
+                11 => {
+                    for _ in 0..s.len()? {
+                        let offset = match s.u32()? {
+                            0 => Some(const_expr(&mut s)?),
+                            1 => None,
+                            2 => {
+                                s.u32()?;
+                                Some(const_expr(&mut s)?)
+                            }
+                            mode => return Err(format!("invalid data segment mode {mode}")),
+                        };
+                        let len = s.u32()? as usize;
+                        let bytes = s.bytes(len)?.to_vec();
+                        module.data.push(Data { offset, bytes });
+                    }
+                }
+                _ => return Err(format!("unknown section {id}")),
+            }
+        }
+        if module.funcs.len() != declared.len() {
+            return Err("missing code section".to_string());
+        }
+        if let Some(ty) = module
+            .func_types
+            .iter()
+            .find(|&&ty| ty as usize >= module.types.len())
+        {
+            return Err(format!("invalid type index {ty}"));
+        }
+        Ok(module)
+    }
+
+    /// インポートしている関数（モジュール名と関数名）
+    pub fn imports(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.imports
+            .iter()
+            .map(|i| (i.module.as_str(), i.name.as_str()))
+    }
+
+    fn func_type(&self, func: u32) -> Result<&FuncType, String> {
+        self.func_types
+            .get(func as usize)
+            .and_then(|&ty| self.types.get(ty as usize))
+            .ok_or_else(|| format!("invalid function index {func}"))
+    }
+}
+
+fn parse_element(s: &mut Reader) -> Result<Element, String> {
+    let flags = s.u32()?;
+    if flags > 7 {
+        return Err(format!("invalid element segment flags {flags}"));
+    }
+    let active = flags & 0x01 == 0;
+    let offset = if active {
+        if flags & 0x02 != 0 && s.u32()? != 0 {
+            return Err("multiple tables are not supported".to_string());
+        }
+        Some(const_expr(s)?)
+    } else {
+        None
+    };
+    // 能動セグメントでテーブル番号を省略した形式には要素の種類がない
+    if flags & 0x03 != 0 {
+        s.byte()?;
+    }
+    let refs = (0..s.len()?)
+        .map(|_| {
+            if flags & 0x04 != 0 {
+                match const_expr(s)? {
+                    ConstExpr::Value(v) => Ok(v),
+                    ConstExpr::Global(_) => Err("unsupported element expression".to_string()),
+                }
+            } else {
+                Ok(u64::from(s.u32()?))
+            }
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(Element { offset, refs })
+}
+
+#[derive(Clone, Copy)]
+struct Label {
+    /// ラベルに入った時点のスタックの高さ
+    height: usize,
+    /// 分岐時に残す値の数
+    arity: usize,
+    /// 分岐先
+    cont: usize,
+    is_loop: bool,
+}
+
+struct Frame {
+    func: usize,
+    pc: usize,
+    locals_base: usize,
+    label_base: usize,
+    stack_base: usize,
+    results: usize,
+}
+
+#[derive(Default)]
+struct Machine {
+    stack: Vec<u64>,
+    locals: Vec<u64>,
+    labels: Vec<Label>,
+    frames: Vec<Frame>,
+}
+
+fn underflow() -> String {
+    "value stack underflow".to_string()
+}
+
+impl Machine {
+    fn pop(&mut self) -> Result<u64, String> {
+        self.stack.pop().ok_or_else(underflow)
+    }
+
+    fn pop_u32(&mut self) -> Result<u32, String> {
+        Ok(self.pop()? as u32)
+    }
+
+    fn height(&self, params: usize) -> Result<usize, String> {
+        self.stack.len().checked_sub(params).ok_or_else(underflow)
+    }
+
+    /// `depth` 番目のラベルへ分岐（関数の外側への分岐なら `None`）
+    fn branch(&mut self, depth: u32, label_base: usize) -> Result<Option<usize>, String> {
+        let depth = depth as usize;
+        if depth >= self.labels.len() - label_base {
+            return Ok(None);
+        }
+        let index = self.labels.len() - 1 - depth;
+        let label = self.labels[index];
+        let len = self.stack.len();
+        if len < label.height + label.arity {
+            return Err(underflow());
+        }
+        self.stack.drain(label.height..len - label.arity);
+        self.labels
+            .truncate(if label.is_loop { index + 1 } else { index });
+        Ok(Some(label.cont))
+    }
+
+    /// 現在の関数から戻る
+    fn leave(&mut self) -> Result<(), String> {
+        let frame = self.frames.pop().ok_or_else(underflow)?;
+        let len = self.stack.len();
+        if len < frame.stack_base + frame.results {
+            return Err(underflow());
+        }
+        self.stack.drain(frame.stack_base..len - frame.results);
+        self.locals.truncate(frame.locals_base);
+        self.labels.truncate(frame.label_base);
+        Ok(())
+    }
+}
+
+/// 実行位置（命令列、次の命令、ローカル変数とラベルの基準位置）
+fn position<'m>(module: &'m Module, m: &Machine) -> Option<(&'m [Instr], usize, usize, usize)> {
+    let frame = m.frames.last()?;
+    Some((
+        &module.funcs[frame.func].code,
+        frame.pc,
+        frame.locals_base,
+        frame.label_base,
+    ))
+}
+
+/// インスタンス化したモジュール（メモリやグローバル変数はインスタンスごとに独立）
+pub struct Instance {
+    module: Arc<Module>,
+    memory: Vec<u8>,
+    max_pages: u32,
+    globals: Vec<u64>,
+    table: Vec<u64>,
+    dropped: Vec<bool>,
+    limits: Limits,
+}
+
+impl Instance {
+    pub fn new(module: Module, limits: Limits, host: &mut dyn Host) -> Result<Self, String> {
+        let (min_pages, max_pages) = module.memory.unwrap_or((0, Some(0)));
+        let max_pages = max_pages.unwrap_or(u32::MAX).min(limits.max_pages);
+        if min_pages > max_pages {
+            return Err(format!(
+                "Module needs {min_pages} memory pages (limit {max_pages})"
+            ));
+        }
+        let mut instance = Instance {
+            memory: vec![0; min_pages as usize * PAGE_SIZE],
+            max_pages,
+            globals: Vec::with_capacity(module.globals.len()),
+            table: vec![NULL_REF; module.table.unwrap_or(0) as usize],
+            dropped: vec![false; module.data.len()],
+            limits,
+            module: Arc::new(module),
+        };
+        let module = instance.module.clone();
+
+        for init in &module.globals {
+            let value = instance.eval(*init)?;
+            instance.globals.push(value);
+        }
+        for element in &module.elements {
+            if let Some(offset) = element.offset {
+                let start = instance.eval(offset)? as u32 as usize;
+                let slots = instance
+                    .table
+                    .get_mut(start..start + element.refs.len())
+                    .ok_or_else(|| "Element segment out of bounds".to_string())?;
+                slots.copy_from_slice(&element.refs);
+            }
+        }
+        for (i, data) in module.data.iter().enumerate() {
+            if let Some(offset) = data.offset {
+                let start = instance.eval(offset)? as u32 as usize;
+                let target = instance
+                    .memory
+                    .get_mut(start..start + data.bytes.len())
+                    .ok_or_else(|| "Data segment out of bounds".to_string())?;
+                target.copy_from_slice(&data.bytes);
+                instance.dropped[i] = true;
+            }
+        }
+        if let Some(start) = module.start {
+            instance.invoke(start, &[], host)?;
+        }
+        Ok(instance)
+    }
+
+    fn eval(&self, expr: ConstExpr) -> Result<u64, String> {
+        match expr {
+            ConstExpr::Value(v) => Ok(v),
+            ConstExpr::Global(i) => self
+                .globals
+                .get(i as usize)
+                .copied()
+                .ok_or_else(|| format!("invalid global index {i}")),
+        }
+    }
+
+    pub fn has_export(&self, name: &str) -> bool {
+        self.module.exports.contains_key(name)
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut [u8] {
+        &mut self.memory
+    }
+
+    /// エクスポートした関数を呼ぶ
+    pub fn call(
+        &mut self,
+        name: &str,
+        args: &[u64],
+        host: &mut dyn Host,
+    ) -> Result<Vec<u64>, String> {
+        let func = *self
+            .module
+            .exports
+            .get(name)
+            .ok_or_else(|| format!("Function not exported: {name}"))?;
+        let expected = self.module.func_type(func)?.params.len();
+        if args.len() != expected {
+            return Err(format!(
+                "{name} takes {expected} arguments, got {}",
+                args.len()
+            ));
+        }
+        self.invoke(func, args, host)
+    }
+
+    fn address(&self, base: u32, offset: u32, len: usize) -> Result<usize, String> {
+        let start = u64::from(base) + u64::from(offset);
+        if start + len as u64 > self.memory.len() as u64 {
+            return Err("out of bounds memory access".to_string());
+        }
+        Ok(start as usize)
+    }
+
+    /// 関数に入る（インポートした関数はその場で呼ぶ）
+    fn enter(
+        &mut self,
+        module: &Module,
+        m: &mut Machine,
+        func: u32,
+        host: &mut dyn Host,
+    ) -> Result<(), String> {
+        let ty = module.func_type(func)?;
+        let args_start = m.height(ty.params.len())?;
+        if let Some(import) = module.imports.get(func as usize) {
+            let args: Vec<u64> = m.stack.drain(args_start..).collect();
+            let results = host.call(&import.module, &import.name, &args, &mut self.memory)?;
+            if results.len() != ty.results.len() {
+                return Err(format!(
+                    "{}.{} returned {} values",
+                    import.module,
+                    import.name,
+                    results.len()
+                ));
+            }
+            m.stack.extend(results);
+            return Ok(());
+        }
+        if m.frames.len() >= MAX_CALL_DEPTH {
+            return Err("call stack exhausted".to_string());
+        }
+        let index = func as usize - module.imports.len();
+        let locals_base = m.locals.len();
+        m.locals.extend(m.stack.drain(args_start..));
+        m.locals.resize(
+            locals_base + ty.params.len() + module.funcs[index].locals,
+            0,
+        );
+        m.frames.push(Frame {
+            func: index,
+            pc: 0,
+            locals_base,
+            label_base: m.labels.len(),
+            stack_base: m.stack.len(),
+            results: ty.results.len(),
+        });
+        Ok(())
+    }
+
+    fn invoke(&mut self, func: u32, args: &[u64], host: &mut dyn Host) -> Result<Vec<u64>, String> {
+        let module = self.module.clone();
+        let mut m = Machine {
+            stack: args.to_vec(),
+            ..Machine::default()
+        };
+        let mut fuel = self.limits.fuel;
+        self.enter(&module, &mut m, func, host)?;
+        let Some((mut code, mut pc, mut lbase, mut label_base)) = position(&module, &m) else {
+            return Ok(m.stack);
+        };
+        macro_rules! reload {
+            () => {
+                match position(&module, &m) {
+                    Some(p) => (code, pc, lbase, label_base) = p,
+                    None => return Ok(m.stack),
+                }
+            };
+        }
+        macro_rules! save_pc {
+            () => {
+                if let Some(frame) = m.frames.last_mut() {
+                    frame.pc = pc;
+                }
+            };
+        }
+
+        loop {
+            if fuel == 0 {
+                return Err("instruction limit exceeded".to_string());
+            }
+            fuel -= 1;
+            if m.stack.len() > MAX_STACK {
+                return Err("value stack exhausted".to_string());
+            }
+            let instr = code
+                .get(pc)
+                .ok_or_else(|| "fell off the end of a function".to_string())?;
+            pc += 1;
+            match instr {
+                Instr::Unreachable => return Err("unreachable executed".to_string()),
+                Instr::Nop => {}
+                Instr::Block {
+                    end,
+                    params,
+                    results,
+                } => {
+                    let height = m.height(*params)?;
+                    m.labels.push(Label {
+                        height,
+                        arity: *results,
+                        cont: *end,
+                        is_loop: false,
+                    });
+                }
+                Instr::Loop { params } => {
+                    let height = m.height(*params)?;
+                    m.labels.push(Label {
+                        height,
+                        arity: *params,
+                        cont: pc,
+                        is_loop: true,
+                    });
+                }
+                Instr::If {
+                    else_,
+                    end,
+                    params,
+                    results,
+                } => {
+                    let condition = m.pop_u32()?;
+                    let height = m.height(*params)?;
+                    m.labels.push(Label {
+                        height,
+                        arity: *results,
+                        cont: *end,
+                        is_loop: false,
+                    });
+                    if condition == 0 {
+                        pc = *else_;
+                    }
+                }
+                Instr::Else { end } => pc = *end,
+                Instr::End => {
+                    if m.labels.len() > label_base {
+                        m.labels.pop();
+                    } else {
+                        m.leave()?;
+                        reload!();
+                    }
+                }
+                Instr::Br(depth) => match m.branch(*depth, label_base)? {
+                    Some(target) => pc = target,
+                    None => {
+                        m.leave()?;
+                        reload!();
+                    }
+                },
+                Instr::BrIf(depth) => {
+                    if m.pop_u32()? != 0 {
+                        match m.branch(*depth, label_base)? {
+                            Some(target) => pc = target,
+                            None => {
+                                m.leave()?;
+                                reload!();
+                            }
+                        }
+                    }
+                }
+                Instr::BrTable(targets, default) => {
+                    let i = m.pop_u32()? as usize;
+                    let depth = targets.get(i).unwrap_or(default);
+                    match m.branch(*depth, label_base)? {
+                        Some(target) => pc = target,
+                        None => {
+                            m.leave()?;
+                            reload!();
+                        }
+                    }
+                }
+                Instr::Return => {
+                    m.leave()?;
+                    reload!();
+                }
+                Instr::Call(func) => {
+                    save_pc!();
+                    self.enter(&module, &mut m, *func, host)?;
+                    reload!();
+                }
+                Instr::CallIndirect(ty) => {
+                    let i = m.pop_u32()? as usize;
+                    let func = *self
+                        .table
+                        .get(i)
+                        .ok_or_else(|| "undefined element".to_string())?;
+                    if func == NULL_REF {
+                        return Err("uninitialized element".to_string());
+                    }
+                    let func = func as u32;
+                    if module.types.get(*ty as usize) != Some(module.func_type(func)?) {
+                        return Err("indirect call type mismatch".to_string());
+                    }
+                    save_pc!();
+                    self.enter(&module, &mut m, func, host)?;
+                    reload!();
+                }
+                Instr::Drop => {
+                    m.pop()?;
+                }
+                Instr::Select => {
+                    let condition = m.pop_u32()?;
+                    let b = m.pop()?;
+                    let a = m.pop()?;
+                    m.stack.push(if condition != 0 { a } else { b });
+                }
+                Instr::LocalGet(i) => {
+                    let value = *m.locals.get(lbase + *i as usize).ok_or_else(underflow)?;
+                    m.stack.push(value);
+                }
+                Instr::LocalSet(i) => {
+                    let value = m.pop()?;
+                    *m.locals
+                        .get_mut(lbase + *i as usize)
+                        .ok_or_else(underflow)? = value;
+                }
+                Instr::LocalTee(i) => {
+                    let value = *m.stack.last().ok_or_else(underflow)?;
+                    *m.locals
+                        .get_mut(lbase + *i as usize)
+                        .ok_or_else(underflow)? = value;
+                }
+                Instr::GlobalGet(i) => {
+                    let value = self.eval(ConstExpr::Global(*i))?;
+                    m.stack.push(value);
+                }
+                Instr::GlobalSet(i) => {
+                    let value = m.pop()?;
+                    *self
+                        .globals
+                        .get_mut(*i as usize)
+                        .ok_or_else(|| format!("invalid global index {i}"))? = value;
+                }
+                Instr::Load {
+                    size,
+                    signed,
+                    wide,
+                    offset,
+                } => {
+                    let base = m.pop_u32()?;
+                    let size = *size as usize;
+                    let start = self.address(base, *offset, size)?;
+                    let mut raw = [0u8; 8];
+                    raw[..size].copy_from_slice(&self.memory[start..start + size]);
+                    let mut value = u64::from_le_bytes(raw);
+                    if *signed {
+                        let shift = 64 - size * 8;
+                        value = ((value << shift) as i64 >> shift) as u64;
+                    }
+                    if !*wide {
+                        value = value as u32 as u64;
+                    }
+                    m.stack.push(value);
+                }
+                Instr::Store { size, offset } => {
+                    let value = m.pop()?;
+                    let base = m.pop_u32()?;
+                    let size = *size as usize;
+                    let start = self.address(base, *offset, size)?;
+                    self.memory[start..start + size].copy_from_slice(&value.to_le_bytes()[..size]);
+                }
+                Instr::MemorySize => m.stack.push((self.memory.len() / PAGE_SIZE) as u64),
+                Instr::MemoryGrow => {
+                    let delta = m.pop_u32()? as usize;
+                    let pages = self.memory.len() / PAGE_SIZE;
+                    if pages + delta > self.max_pages as usize {
+                        m.stack.push(u64::from(u32::MAX));
+                    } else {
+                        self.memory.resize((pages + delta) * PAGE_SIZE, 0);
+                        m.stack.push(pages as u64);
+                    }
+                }
+                Instr::Const(value) => m.stack.push(*value),
+                Instr::Numeric(op) => numeric(*op, &mut m.stack)?,
+                Instr::TruncSat(op) => {
+                    let a = m.pop()?;
+                    let f32 = || f32::from_bits(a as u32);
+                    let f64 = || f64::from_bits(a);
+                    m.stack.push(match op {
+                        0 => f32() as i32 as u32 as u64,
+                        1 => f32() as u32 as u64,
+                        2 => f64() as i32 as u32 as u64,
+                        3 => f64() as u32 as u64,
+                        4 => f32() as i64 as u64,
+                        5 => f32() as u64,
+                        6 => f64() as i64 as u64,
+                        _ => f64() as u64,
+                    });
+                }
+                Instr::MemoryInit(segment) => {
+                    let len = m.pop_u32()? as usize;
+                    let src = m.pop_u32()? as usize;
+                    let dst = m.pop_u32()?;
+                    let segment = *segment as usize;
+                    let data = module
+                        .data
+                        .get(segment)
+                        .ok_or_else(|| format!("invalid data segment {segment}"))?;
+                    let bytes: &[u8] = if self.dropped[segment] {
+                        &[]
+                    } else {
+                        &data.bytes
+                    };
+                    let source = bytes
+                        .get(src..src.saturating_add(len))
+                        .ok_or_else(|| "out of bounds memory access".to_string())?;
+                    let start = self.address(dst, 0, len)?;
+                    self.memory[start..start + len].copy_from_slice(source);
+                }
+                Instr::DataDrop(segment) => {
+                    if let Some(dropped) = self.dropped.get_mut(*segment as usize) {
+                        *dropped = true;
+                    }
+                }
+                Instr::MemoryCopy => {
+                    let len = m.pop_u32()? as usize;
+                    let src = m.pop_u32()?;
+                    let dst = m.pop_u32()?;
+                    let src = self.address(src, 0, len)?;
+                    let dst = self.address(dst, 0, len)?;
+                    self.memory.copy_within(src..src + len, dst);
+                }
+                Instr::MemoryFill => {
+                    let len = m.pop_u32()? as usize;
+                    let value = m.pop_u32()? as u8;
+                    let dst = m.pop_u32()?;
+                    let start = self.address(dst, 0, len)?;
+                    self.memory[start..start + len].fill(value);
+                }
+                Instr::RefNull => m.stack.push(NULL_REF),
+                Instr::RefIsNull => {
+                    let value = m.pop()?;
+                    m.stack.push(u64::from(value == NULL_REF));
+                }
+                Instr::RefFunc(func) => m.stack.push(u64::from(*func)),
+            }
+        }
+    }
+}
+
+/// NaN と ±0 を WebAssembly の規則どおりに扱う最小値
+fn fmin(x: f64, y: f64) -> f64 {
+    if x.is_nan() || y.is_nan() {
+        f64::NAN
+    } else if x == y {
+        if x.is_sign_negative() {
+            x
+        } else {
+            y
+        }
+    } else {
+        x.min(y)
+    }
+}
+
+fn fmax(x: f64, y: f64) -> f64 {
+    if x.is_nan() || y.is_nan() {
+        f64::NAN
+    } else if x == y {
+        if x.is_sign_positive() {
+            x
+        } else {
+            y
+        }
+    } else {
+        x.max(y)
+    }
+}
+
+/// 整数への変換（範囲外は `min` < x < `max` を満たさない値）
+fn trunc(x: f64, min: f64, max: f64) -> Result<f64, String> {
+    if x.is_nan() {
+        return Err("invalid conversion to integer".to_string());
+    }
+    if x <= min || x >= max {
+        return Err("integer overflow".to_string());
+    }
+    Ok(x.trunc())
+}
+
+fn numeric(op: u8, stack: &mut Vec<u64>) -> Result<(), String> {
+    let unary = matches!(
+        op,
+        0x45 | 0x50 | 0x67..=0x69 | 0x79..=0x7b | 0x8b..=0x91 | 0x99..=0x9f | 0xa7..=0xc4
+    );
+    let value = if unary {
+        let a = stack.pop().ok_or_else(underflow)?;
+        unary_op(op, a)?
+    } else {
+        let b = stack.pop().ok_or_else(underflow)?;
+        let a = stack.pop().ok_or_else(underflow)?;
+        binary_op(op, a, b)?
+    };
+    stack.push(value);
+    Ok(())
+}
+
+fn unary_op(op: u8, a: u64) -> Result<u64, String> {
+    let f32 = f32::from_bits(a as u32);
+    let f64 = f64::from_bits(a);
+    let value = match op {
+        0x45 => u64::from(a as u32 == 0),
+        0x50 => u64::from(a == 0),
+        0x67 => u64::from((a as u32).leading_zeros()),
+        0x68 => u64::from((a as u32).trailing_zeros()),
+        0x69 => u64::from((a as u32).count_ones()),
+        0x79 => u64::from(a.leading_zeros()),
+        0x7a => u64::from(a.trailing_zeros()),
+        0x7b => u64::from(a.count_ones()),
+        0x8b => a & 0x7fff_ffff,
+        0x8c => (a as u32 ^ 0x8000_0000) as u64,
+        0x8d..=0x91 => {
+            let r = match op {
+                0x8d => f32.ceil(),
+                0x8e => f32.floor(),
+                0x8f => f32.trunc(),
+                0x90 => f32.round_ties_even(),
+                _ => f32.sqrt(),
+            };
+            u64::from(r.to_bits())
+        }
+        0x99 => a & !(1 << 63),
+        0x9a => a ^ (1 << 63),
+        0x9b..=0x9f => {
+            let r = match op {
+                0x9b => f64.ceil(),
+                0x9c => f64.floor(),
+                0x9d => f64.trunc(),
+                0x9e => f64.round_ties_even(),
+                _ => f64.sqrt(),
+            };
+            r.to_bits()
+        }
+        0xa7 => a as u32 as u64,
+        0xa8 => trunc(f64::from(f32), -2_147_483_649.0, 2_147_483_648.0)? as i32 as u32 as u64,
+        0xa9 => trunc(f64::from(f32), -1.0, 4_294_967_296.0)? as u32 as u64,
+        0xaa => trunc(f64, -2_147_483_649.0, 2_147_483_648.0)? as i32 as u32 as u64,
+        0xab => trunc(f64, -1.0, 4_294_967_296.0)? as u32 as u64,
+        0xac => a as u32 as i32 as i64 as u64,
+        0xad => a as u32 as u64,
+        0xae => trunc(f64::from(f32), I64_MIN_BOUND, 9_223_372_036_854_775_808.0)? as i64 as u64,
+        0xaf => trunc(f64::from(f32), -1.0, 18_446_744_073_709_551_616.0)? as u64,
+        0xb0 => trunc(f64, I64_MIN_BOUND, 9_223_372_036_854_775_808.0)? as i64 as u64,
+        0xb1 => trunc(f64, -1.0, 18_446_744_073_709_551_616.0)? as u64,
+        0xb2 => u64::from((a as u32 as i32 as f32).to_bits()),
+        0xb3 => u64::from((a as u32 as f32).to_bits()),
+        0xb4 => u64::from((a as i64 as f32).to_bits()),
+        0xb5 => u64::from((a as f32).to_bits()),
+        0xb6 => u64::from((f64 as f32).to_bits()),
+        0xb7 => f64::from(a as u32 as i32).to_bits(),
+        0xb8 => f64::from(a as u32).to_bits(),
+        0xb9 => (a as i64 as f64).to_bits(),
+        0xba => (a as f64).to_bits(),
+        0xbb => f64::from(f32).to_bits(),
+        // reinterpret はビット列をそのまま使う
+        0xbc..=0xbf => a,
+        0xc0 => a as u8 as i8 as i32 as u32 as u64,
+        0xc1 => a as u16 as i16 as i32 as u32 as u64,
+        0xc2 => a as u8 as i8 as i64 as u64,
+        0xc3 => a as u16 as i16 as i64 as u64,
+        _ => a as u32 as i32 as i64 as u64,
+    };
+    Ok(value)
+}
+
+fn binary_op(op: u8, a: u64, b: u64) -> Result<u64, String> {
+    let value = match op {
+        0x46..=0x4f => {
+            let (x, y) = (a as u32, b as u32);
+            let (sx, sy) = (x as i32, y as i32);
+            u64::from(match op {
+                0x46 => x == y,
+                0x47 => x != y,
+                0x48 => sx < sy,
+                0x49 => x < y,
+                0x4a => sx > sy,
+                0x4b => x > y,
+                0x4c => sx <= sy,
+                0x4d => x <= y,
+                0x4e => sx >= sy,
+                _ => x >= y,
+            })
+        }
+        0x51..=0x5a => {
+            let (sx, sy) = (a as i64, b as i64);
+            u64::from(match op {
+                0x51 => a == b,
+                0x52 => a != b,
+                0x53 => sx < sy,
+                0x54 => a < b,
+                0x55 => sx > sy,
+                0x56 => a > b,
+                0x57 => sx <= sy,
+                0x58 => a <= b,
+                0x59 => sx >= sy,
+                _ => a >= b,
+            })
+        }
+        0x5b..=0x60 => {
+            let (x, y) = (f32::from_bits(a as u32), f32::from_bits(b as u32));
+            u64::from(match op {
+                0x5b => x == y,
+                0x5c => x != y,
+                0x5d => x < y,
+                0x5e => x > y,
+                0x5f => x <= y,
+                _ => x >= y,
+            })
+        }
+        0x61..=0x66 => {
+            let (x, y) = (f64::from_bits(a), f64::from_bits(b));
+            u64::from(match op {
+                0x61 => x == y,
+                0x62 => x != y,
+                0x63 => x < y,
+                0x64 => x > y,
+                0x65 => x <= y,
+                _ => x >= y,
+            })
+        }
+        0x6a..=0x78 => {
+            let (x, y) = (a as u32, b as u32);
+            let (sx, sy) = (x as i32, y as i32);
+            let r = match op {
+                0x6a => x.wrapping_add(y),
+                0x6b => x.wrapping_sub(y),
+                0x6c => x.wrapping_mul(y),
+                0x6d | 0x6f if y == 0 => return Err("integer divide by zero".to_string()),
+                0x6d if sx == i32::MIN && sy == -1 => return Err("integer overflow".to_string()),
+                0x6d => (sx / sy) as u32,
+                0x6e | 0x70 if y == 0 => return Err("integer divide by zero".to_string()),
+                0x6e => x / y,
+                0x6f => sx.wrapping_rem(sy) as u32,
+                0x70 => x % y,
+                0x71 => x & y,
+                0x72 => x | y,
+                0x73 => x ^ y,
+                0x74 => x.wrapping_shl(y),
+                0x75 => sx.wrapping_shr(y) as u32,
+                0x76 => x.wrapping_shr(y),
+                0x77 => x.rotate_left(y % 32),
+                _ => x.rotate_right(y % 32),
+            };
+            u64::from(r)
+        }
+        0x7c..=0x8a => {
+            let (sx, sy) = (a as i64, b as i64);
+            let shift = b as u32;
+            match op {
+                0x7c => a.wrapping_add(b),
+                0x7d => a.wrapping_sub(b),
+                0x7e => a.wrapping_mul(b),
+                0x7f | 0x81 if b == 0 => return Err("integer divide by zero".to_string()),
+                0x7f if sx == i64::MIN && sy == -1 => return Err("integer overflow".to_string()),
+                0x7f => (sx / sy) as u64,
+                0x80 | 0x82 if b == 0 => return Err("integer divide by zero".to_string()),
+                0x80 => a / b,
+                0x81 => sx.wrapping_rem(sy) as u64,
+                0x82 => a % b,
+                0x83 => a & b,
+                0x84 => a | b,
+                0x85 => a ^ b,
+                0x86 => a.wrapping_shl(shift),
+                0x87 => sx.wrapping_shr(shift) as u64,
+                0x88 => a.wrapping_shr(shift),
+                0x89 => a.rotate_left(shift % 64),
+                _ => a.rotate_right(shift % 64),
+            }
+        }
+        0x92..=0x98 => {
+            let (x, y) = (f32::from_bits(a as u32), f32::from_bits(b as u32));
+            let r = match op {
+                0x92 => x + y,
+                0x93 => x - y,
+                0x94 => x * y,
+                0x95 => x / y,
+                0x96 => fmin(f64::from(x), f64::from(y)) as f32,
+                0x97 => fmax(f64::from(x), f64::from(y)) as f32,
+                _ => return Ok((a & 0x7fff_ffff) | (b & 0x8000_0000)),
+            };
+            u64::from(r.to_bits())
+        }
+        _ => {
+            let (x, y) = (f64::from_bits(a), f64::from_bits(b));
+            let r = match op {
+                0xa0 => x + y,
+                0xa1 => x - y,
+                0xa2 => x * y,
+                0xa3 => x / y,
+                0xa4 => fmin(x, y),
+                0xa5 => fmax(x, y),
+                _ => return Ok((a & !(1 << 63)) | (b & (1 << 63))),
+            };
+            r.to_bits()
+        }
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Host, Instance, Limits, Module, PAGE_SIZE};
+
+    const I32: u8 = 0x7f;
+    const LIMITS: Limits = Limits {
+        max_pages: 16,
+        fuel: 1_000_000,
+    };
+
+    /// インポートを呼ばないモジュール用
+    struct NoHost;
+
+    impl Host for NoHost {
+        fn call(
+            &mut self,
+            module: &str,
+            name: &str,
+            _: &[u64],
+            _: &mut [u8],
+        ) -> Result<Vec<u64>, String> {
+            Err(format!("unexpected call to {module}.{name}"))
+        }
+    }
+
+    /// `env.double` を提供し、呼ばれた回数を数える
+    #[derive(Default)]
+    struct Doubler {
+        calls: usize,
+    }
+
+    impl Host for Doubler {
+        fn call(
+            &mut self,
+            _: &str,
+            _: &str,
+            args: &[u64],
+            _: &mut [u8],
+        ) -> Result<Vec<u64>, String> {
+            self.calls += 1;
+            Ok(vec![u64::from((args[0] as u32).wrapping_mul(2))])
+        }
+    }
+
+    fn leb(mut n: usize, out: &mut Vec<u8>) {
+        loop {
+            let byte = (n & 0x7f) as u8;
+            n >>= 7;
+            if n == 0 {
+                out.push(byte);
+                return;
+            }
+            out.push(byte | 0x80);
+        }
+    }
+
+    fn vector(items: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = Vec::new();
+        leb(items.len(), &mut out);
+        items.iter().for_each(|item| out.extend(item));
+        out
+    }
+
+    fn name(s: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        leb(s.len(), &mut out);
+        out.extend(s.as_bytes());
+        out
+    }
+
+    fn section(out: &mut Vec<u8>, id: u8, payload: Vec<u8>) {
+        out.push(id);
+        leb(payload.len(), out);
+        out.extend(payload);
+    }
+
+    /// 関数の型 `(引数, 戻り値)`
+    type Sig<'a> = (&'a [u8], &'a [u8]);
+
+    /// 各関数を `f0`, `f1`, … としてエクスポートするモジュール
+    ///
+    /// 関数の番号はインポートの後に続く。本体はローカル変数の宣言から `end` までを書く。
+    fn module(
+        memory: Option<(u32, u32)>,
+        imports: &[(&str, Sig)],
+        funcs: &[(Sig, &[u8])],
+    ) -> Vec<u8> {
+        let sigs = imports
+            .iter()
+            .map(|(_, sig)| sig)
+            .chain(funcs.iter().map(|(sig, _)| sig));
+        let types: Vec<Vec<u8>> = sigs
+            .map(|(params, results)| {
+                let mut ty = vec![0x60];
+                ty.extend(vector(&params.iter().map(|&p| vec![p]).collect::<Vec<_>>()));
+                ty.extend(vector(
+                    &results.iter().map(|&r| vec![r]).collect::<Vec<_>>(),
+                ));
+                ty
+            })
+            .collect();
+        let mut out = b"\0asm\x01\0\0\0".to_vec();
+        section(&mut out, 1, vector(&types));
+        if !imports.is_empty() {
+            let entries: Vec<Vec<u8>> = imports
+                .iter()
+                .enumerate()
+                .map(|(i, (import, _))| {
+                    let (module, field) = import.split_once('.').unwrap();
+                    let mut entry = name(module);
+                    entry.extend(name(field));
+                    entry.push(0x00);
+                    leb(i, &mut entry);
+                    entry
+                })
+                .collect();
+            section(&mut out, 2, vector(&entries));
+        }
+        let declared: Vec<Vec<u8>> = (0..funcs.len())
+            .map(|i| {
+                let mut ty = Vec::new();
+                leb(imports.len() + i, &mut ty);
+                ty
+            })
+            .collect();
+        section(&mut out, 3, vector(&declared));
+        if let Some((min, max)) = memory {
+            let mut limits = vec![0x01];
+            leb(min as usize, &mut limits);
+            leb(max as usize, &mut limits);
+            section(&mut out, 5, vector(&[limits]));
+        }
+        let exports: Vec<Vec<u8>> = (0..funcs.len())
+            .map(|i| {
+                let mut export = name(&format!("f{i}"));
+                export.push(0x00);
+                leb(imports.len() + i, &mut export);
+                export
+            })
+            .collect();
+        section(&mut out, 7, vector(&exports));
+        let bodies: Vec<Vec<u8>> = funcs
+            .iter()
+            .map(|(_, body)| {
+                let mut code = Vec::new();
+                leb(body.len(), &mut code);
+                code.extend(*body);
+                code
+            })
+            .collect();
+        section(&mut out, 10, vector(&bodies));
+        out
+    }
+
+    fn instance(memory: Option<(u32, u32)>, funcs: &[(Sig, &[u8])]) -> Instance {
+        let module = Module::parse(&module(memory, &[], funcs)).unwrap();
+        Instance::new(module, LIMITS, &mut NoHost).unwrap()
+    }
+
+    /// 1 から n までの和（ブロック・ループ・分岐）
+    const SUM: (Sig, &[u8]) = (
+        (&[I32], &[I32]),
+        &[
+            0x01, 0x01, I32, // ローカル変数 1 個
+            0x02, 0x40, 0x03, 0x40, // block loop
+            0x20, 0x00, 0x45, 0x0d, 0x01, // n == 0 なら抜ける
+            0x20, 0x01, 0x20, 0x00, 0x6a, 0x21, 0x01, // acc += n
+            0x20, 0x00, 0x41, 0x01, 0x6b, 0x21, 0x00, // n -= 1
+            0x0c, 0x00, 0x0b, 0x0b, // br 0 end end
+            0x20, 0x01, 0x0b,
+        ],
+    );
+
+    #[test]
+    fn rejects_malformed_modules() {
+        let error = |bytes: &[u8]| Module::parse(bytes).err().unwrap();
+        assert!(error(b"\0wasm\x01\0\0").contains("missing magic number"));
+        assert!(error(b"\0asm\x02\0\0\0").contains("unsupported version"));
+
+        let valid = module(None, &[], &[SUM]);
+        assert!(Module::parse(&valid).is_ok());
+        assert!(error(&valid[..valid.len() - 1]).contains("unexpected end of data"));
+        let mut unknown = valid.clone();
+        unknown.extend([13, 0]);
+        assert!(error(&unknown).contains("unknown section 13"));
+        // 関数宣言と本体の数が合わない
+        let mut missing = b"\0asm\x01\0\0\0".to_vec();
+        section(&mut missing, 1, vector(&[vec![0x60, 0, 0]]));
+        section(&mut missing, 3, vector(&[vec![0]]));
+        assert!(error(&missing).contains("missing code section"));
+        // 未対応の命令
+        let simd = module(None, &[], &[((&[], &[]), &[0x00, 0xfd, 0x0c, 0x0b])]);
+        assert!(error(&simd).contains("unsupported instruction 0xfd"));
+    }
+
+    #[test]
+    fn lists_imports_and_exports() {
+        let bytes = module(
+            None,
+            &[("env.double", (&[I32], &[I32]))],
+            &[((&[I32], &[I32]), &[0x00, 0x20, 0x00, 0x10, 0x00, 0x0b])],
+        );
+        let module = Module::parse(&bytes).unwrap();
+        assert_eq!(module.imports().collect::<Vec<_>>(), [("env", "double")]);
+
+        let mut host = Doubler::default();
+        let mut instance = Instance::new(module, LIMITS, &mut host).unwrap();
+        assert!(instance.has_export("f0"));
+        assert!(!instance.has_export("env.double"));
+        assert_eq!(instance.call("f0", &[21], &mut host).unwrap(), [42]);
+        assert_eq!(host.calls, 1);
+        assert!(instance
+            .call("f0", &[], &mut host)
+            .unwrap_err()
+            .contains("takes 1 arguments"));
+        assert!(instance
+            .call("f1", &[1], &mut host)
+            .unwrap_err()
+            .contains("not exported"));
+    }
+
+    #[test]
+    fn runs_control_flow() {
+        // if/else と再帰呼び出し（階乗）
+        let fact: &[u8] = &[
+            0x00, 0x20, 0x00, 0x45, 0x04, I32, 0x41, 0x01, 0x05, // n == 0 なら 1
+            0x20, 0x00, 0x20, 0x00, 0x41, 0x01, 0x6b, 0x10, 0x01, 0x6c, // n * f1(n - 1)
+            0x0b, 0x0b,
+        ];
+        let mut instance = instance(None, &[SUM, ((&[I32], &[I32]), fact)]);
+        assert_eq!(instance.call("f0", &[10], &mut NoHost).unwrap(), [55]);
+        assert_eq!(instance.call("f0", &[0], &mut NoHost).unwrap(), [0]);
+        assert_eq!(instance.call("f1", &[5], &mut NoHost).unwrap(), [120]);
+    }
+
+    #[test]
+    fn checks_memory_bounds() {
+        // (addr) に 7 を書く
+        let store: &[u8] = &[0x00, 0x20, 0x00, 0x41, 0x07, 0x36, 0x02, 0x00, 0x0b];
+        // (addr) から offset=4 で読む
+        let load: &[u8] = &[0x00, 0x20, 0x00, 0x28, 0x02, 0x04, 0x0b];
+        // 1 ページ増やし、以前のページ数（失敗時は -1）を返す
+        let grow: &[u8] = &[0x00, 0x41, 0x01, 0x40, 0x00, 0x0b];
+        let mut instance = instance(
+            Some((1, 4)),
+            &[
+                ((&[I32], &[]), store),
+                ((&[I32], &[I32]), load),
+                ((&[], &[I32]), grow),
+            ],
+        );
+        let last = (PAGE_SIZE - 4) as u64;
+        instance.call("f0", &[last], &mut NoHost).unwrap();
+        assert_eq!(instance.memory()[PAGE_SIZE - 4], 7);
+        assert!(instance
+            .call("f0", &[last + 1], &mut NoHost)
+            .unwrap_err()
+            .contains("out of bounds"));
+        assert_eq!(instance.call("f1", &[last - 4], &mut NoHost).unwrap(), [7]);
+        // アドレスとオフセットの和が 32 ビットを超える
+        assert!(instance
+            .call("f1", &[u64::from(u32::MAX)], &mut NoHost)
+            .unwrap_err()
+            .contains("out of bounds"));
+
+        assert_eq!(instance.call("f2", &[], &mut NoHost).unwrap(), [1]);
+        assert_eq!(instance.memory().len(), 2 * PAGE_SIZE);
+        instance.call("f0", &[last + 1], &mut NoHost).unwrap();
+
+        // 実行時の上限はモジュールの最大値より優先する
+        let bytes = module(Some((1, 4)), &[], &[((&[], &[I32]), grow)]);
+        let limits = Limits {
+            max_pages: 2,
+            ..LIMITS
+        };
+        let mut limited =
+            Instance::new(Module::parse(&bytes).unwrap(), limits, &mut NoHost).unwrap();
+        assert_eq!(limited.call("f0", &[], &mut NoHost).unwrap(), [1]);
+        assert_eq!(
+            limited.call("f0", &[], &mut NoHost).unwrap(),
+            [u64::from(u32::MAX)]
+        );
+        assert_eq!(limited.memory().len(), 2 * PAGE_SIZE);
+
+        let limits = Limits {
+            max_pages: 0,
+            ..LIMITS
+        };
+        let error = Instance::new(Module::parse(&bytes).unwrap(), limits, &mut NoHost)
+            .err()
+            .unwrap();
+        assert!(error.contains("needs 1 memory pages"));
+    }
+
+    #[test]
+    fn stops_when_fuel_runs_out() {
+        let spin: &[u8] = &[0x00, 0x03, 0x40, 0x0c, 0x00, 0x0b, 0x0b];
+        let bytes = module(None, &[], &[((&[], &[]), spin), SUM]);
+        let limits = Limits {
+            fuel: 10_000,
+            ..LIMITS
+        };
+        let mut instance =
+            Instance::new(Module::parse(&bytes).unwrap(), limits, &mut NoHost).unwrap();
+        assert!(instance
+            .call("f0", &[], &mut NoHost)
+            .unwrap_err()
+            .contains("instruction limit exceeded"));
+        // 命令数の上限は呼び出しごと
+        assert_eq!(instance.call("f1", &[100], &mut NoHost).unwrap(), [5050]);
+        assert!(instance
+            .call("f1", &[10_000], &mut NoHost)
+            .unwrap_err()
+            .contains("instruction limit exceeded"));
+    }
+
+    #[test]
+    fn reports_traps() {
+        let unreachable: &[u8] = &[0x00, 0x00, 0x0b];
+        let div: &[u8] = &[0x00, 0x20, 0x00, 0x20, 0x01, 0x6d, 0x0b];
+        let recurse: &[u8] = &[0x00, 0x10, 0x02, 0x0b];
+        let mut instance = instance(
+            None,
+            &[
+                ((&[], &[]), unreachable),
+                ((&[I32, I32], &[I32]), div),
+                ((&[], &[]), recurse),
+                SUM,
+            ],
+        );
+        let mut trap =
+            |name: &str, args: &[u64]| instance.call(name, args, &mut NoHost).unwrap_err();
+        assert!(trap("f0", &[]).contains("unreachable executed"));
+        assert!(trap("f1", &[1, 0]).contains("integer divide by zero"));
+        assert!(
+            trap("f1", &[u64::from(i32::MIN as u32), u64::from(u32::MAX)])
+                .contains("integer overflow")
+        );
+        assert!(trap("f2", &[]).contains("call stack exhausted"));
+
+        // トラップの後も呼び出せる
+        assert_eq!(
+            instance
+                .call("f1", &[u64::from(-9i32 as u32), 2], &mut NoHost)
+                .unwrap(),
+            [u64::from(-4i32 as u32)]
+        );
+        assert_eq!(instance.call("f3", &[3], &mut NoHost).unwrap(), [6]);
+    }
+}
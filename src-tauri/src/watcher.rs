@@ -0,0 +1,129 @@
+// フォルダ・ファイルの変更監視（更新時刻のポーリング）
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, SystemTime};
+
+use crate::vault;
+
+/// ファイルごとの更新時刻
+type Snapshot = HashMap<PathBuf, SystemTime>;
+
+/// 前回からの変更
+#[derive(Debug, Default)]
+pub struct Changes {
+    /// 追加・更新されたファイル
+    pub modified: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.modified.is_empty() && self.removed.is_empty()
+    }
+
+    /// 後から検出した変更をまとめる
+    fn merge(&mut self, other: Changes) {
+        for path in other.modified {
+            self.removed.retain(|p| *p != path);
+            if !self.modified.contains(&path) {
+                self.modified.push(path);
+            }
+        }
+        for path in other.removed {
+            self.modified.retain(|p| *p != path);
+            if !self.removed.contains(&path) {
+                self.removed.push(path);
+            }
+        }
+    }
+}
+
+/// 存在するファイルの更新時刻
+fn modified_times(paths: impl IntoIterator<Item = PathBuf>) -> Snapshot {
+    paths
+        .into_iter()
+        .filter_map(|p| {
+            let modified = fs::metadata(&p).and_then(|m| m.modified()).ok()?;
+            Some((p, modified))
+        })
+        .collect()
+}
+
+fn snapshot(root: &Path, exclude: Option<&Path>) -> Snapshot {
+    modified_times(
+        vault::walk_files(root)
+            .into_iter()
+            .filter(|p| exclude.is_none_or(|ex| !p.starts_with(ex))),
+    )
+}
+
+fn diff(old: &Snapshot, new: &Snapshot) -> Changes {
+    let mut modified: Vec<PathBuf> = new
+        .iter()
+        .filter(|(path, time)| old.get(*path) != Some(time))
+        .map(|(path, _)| path.clone())
+        .collect();
+    let mut removed: Vec<PathBuf> = old
+        .keys()
+        .filter(|path| !new.contains_key(*path))
+        .cloned()
+        .collect();
+    modified.sort();
+    removed.sort();
+    Changes { modified, removed }
+}
+
+/// `root` 以下を監視し、変更が落ち着いたところで `on_change` を呼ぶ
+///
+/// `stop` の送信側が破棄されると終了する。`exclude` 以下（出力先など）は監視しない。
+pub fn watch(
+    root: &Path,
+    exclude: Option<&Path>,
+    interval: Duration,
+    stop: &Receiver<()>,
+    on_change: impl FnMut(Changes),
+) {
+    poll(|| snapshot(root, exclude), interval, stop, on_change);
+}
+
+/// `files` が返すファイル（開いているファイルなど、監視中に増減するもの）を監視する
+///
+/// 一覧に加わったファイルは追加、外れたファイルは削除として通知する。
+pub fn watch_files(
+    mut files: impl FnMut() -> Vec<PathBuf>,
+    interval: Duration,
+    stop: &Receiver<()>,
+    on_change: impl FnMut(Changes),
+) {
+    poll(|| modified_times(files()), interval, stop, on_change);
+}
+
+fn poll(
+    mut snapshot: impl FnMut() -> Snapshot,
+    interval: Duration,
+    stop: &Receiver<()>,
+    mut on_change: impl FnMut(Changes),
+) {
+    let mut previous = snapshot();
+    let mut pending = Changes::default();
+    loop {
+        match stop.recv_timeout(interval) {
+            Err(RecvTimeoutError::Timeout) => {}
+            _ => return,
+        }
+        let current = snapshot();
+        let changes = diff(&previous, &current);
+        previous = current;
+        if changes.is_empty() {
+            // 保存が続いている間は待ち、静かになってから通知する
+            if !pending.is_empty() {
+                on_change(std::mem::take(&mut pending));
+            }
+        } else {
+            pending.merge(changes);
+        }
+    }
+}
@@ -0,0 +1,583 @@
+// Excel ブック（.xlsx）のシートを Markdown の表として読み込む
+//
+// calamine は依存に含まれていないため、ZIP の展開（`zip`）と quick-xml で必要な部分だけを読む。
+// 数式は計算せず保存されている結果の値を使う。.xls・.ods、結合セル、文字の書式には対応しない。
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::{Duration, NaiveDate};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::table;
+use crate::tr;
+use crate::zip;
+
+/// 日付として表示する組み込みの表示形式
+const DATE_FORMATS: &[u32] = &[14, 15, 16, 17, 18, 19, 20, 21, 22, 45, 46, 47];
+
+/// 属性の値（名前空間の接頭辞は無視する）
+fn attr(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+/// XML を読み、要素の開始・終了と文字列を順に `visit` に渡す
+enum Node<'a> {
+    Start(&'a BytesStart<'a>, bool),
+    End(&'a [u8]),
+    Text(String),
+}
+
+fn walk(xml: &[u8], mut visit: impl FnMut(Node)) -> Result<(), String> {
+    let xml = String::from_utf8_lossy(xml);
+    let mut reader = Reader::from_str(&xml);
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| tr!("Invalid spreadsheet XML: {e}"))?;
+        match event {
+            Event::Start(e) => visit(Node::Start(&e, false)),
+            Event::Empty(e) => {
+                visit(Node::Start(&e, true));
+                visit(Node::End(e.local_name().as_ref()));
+            }
+            Event::End(e) => visit(Node::End(e.local_name().as_ref())),
+            Event::Text(e) => {
+                if let Ok(text) = e.xml_content() {
+                    visit(Node::Text(text.into_owned()));
+                }
+            }
+            Event::CData(e) => {
+                if let Ok(text) = e.decode() {
+                    visit(Node::Text(text.into_owned()));
+                }
+            }
+            Event::GeneralRef(e) => {
+                let text = match e.resolve_char_ref() {
+                    Ok(Some(c)) => c.to_string(),
+                    _ => {
+                        let name = String::from_utf8_lossy(&e);
+                        quick_xml::escape::unescape(&format!("&{name};"))
+                            .map(|s| s.into_owned())
+                            .unwrap_or_default()
+                    }
+                };
+                visit(Node::Text(text));
+            }
+            Event::Eof => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+fn local(e: &BytesStart) -> Vec<u8> {
+    e.local_name().as_ref().to_vec()
+}
+
+/// 展開したブック
+struct Workbook {
+    files: HashMap<String, Vec<u8>>,
+    /// シート名とシートの XML のパス
+    sheets: Vec<(String, String)>,
+    date1904: bool,
+    shared_strings: Vec<String>,
+    /// セルの書式番号ごとの表示形式
+    formats: Vec<Format>,
+}
+
+/// 読み込み中のセル
+struct Cell {
+    /// 0 始まりの（行, 列）
+    position: Option<(usize, usize)>,
+    /// `t` 属性（`s` は共有文字列、`n` は数値など）
+    kind: String,
+    /// 書式番号
+    style: usize,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    General,
+    Date,
+    Percent(usize),
+}
+
+impl Workbook {
+    fn open(path: &Path) -> Result<Self, String> {
+        let data = fs::read(path).map_err(|e| tr!("Failed to read {}: {e}", path.display()))?;
+        Self::from_zip(&data)
+    }
+
+    fn from_zip(data: &[u8]) -> Result<Self, String> {
+        let files: HashMap<String, Vec<u8>> = zip::read_zip(data)
+            .map_err(|e| tr!("Not an Excel workbook: {e}"))?
+            .into_iter()
+            .map(|entry| (entry.name, entry.data))
+            .collect();
+        let workbook = files
+            .get("xl/workbook.xml")
+            .ok_or_else(|| tr!("Not an Excel workbook: xl/workbook.xml is missing"))?;
+
+        let mut targets = HashMap::new();
+        if let Some(rels) = files.get("xl/_rels/workbook.xml.rels") {
+            walk(rels, |node| {
+                if let Node::Start(e, _) = node {
+                    if let (Some(id), Some(target)) = (attr(e, b"Id"), attr(e, b"Target")) {
+                        let target = match target.strip_prefix('/') {
+                            Some(absolute) => absolute.to_string(),
+                            None => format!("xl/{target}"),
+                        };
+                        targets.insert(id, target);
+                    }
+                }
+            })?;
+        }
+        let mut sheets = Vec::new();
+        let mut date1904 = false;
+        walk(workbook, |node| {
+            if let Node::Start(e, _) = node {
+                match local(e).as_slice() {
+                    b"workbookPr" => {
+                        date1904 = attr(e, b"date1904").is_some_and(|v| v == "1" || v == "true")
+                    }
+                    b"sheet" => {
+                        let name = attr(e, b"name").unwrap_or_default();
+                        if let Some(target) = attr(e, b"id").and_then(|id| targets.get(&id)) {
+                            sheets.push((name, target.clone()));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        })?;
+
+        let mut book = Self {
+            shared_strings: Vec::new(),
+            formats: Vec::new(),
+            files,
+            sheets,
+            date1904,
+        };
+        book.load_shared_strings()?;
+        book.load_styles()?;
+        Ok(book)
+    }
+
+    /// 共有文字列（ふりがな `rPh` は除く）
+    fn load_shared_strings(&mut self) -> Result<(), String> {
+        let Some(xml) = self.files.get("xl/sharedStrings.xml") else {
+            return Ok(());
+        };
+        let mut strings = Vec::new();
+        let mut current = String::new();
+        let (mut in_text, mut in_phonetic) = (false, false);
+        walk(xml, |node| match node {
+            Node::Start(e, empty) => match local(e).as_slice() {
+                b"si" => current.clear(),
+                b"t" => in_text = !empty,
+                b"rPh" => in_phonetic = !empty,
+                _ => {}
+            },
+            Node::Text(text) if in_text && !in_phonetic => current.push_str(&text),
+            Node::End(b"t") => in_text = false,
+            Node::End(b"rPh") => in_phonetic = false,
+            Node::End(b"si") => strings.push(std::mem::take(&mut current)),
+            _ => {}
+        })?;
+        self.shared_strings = strings;
+        Ok(())
+    }
+
+    /// セルの書式から日付・パーセントの列を判別する
+    fn load_styles(&mut self) -> Result<(), String> {
+        let Some(xml) = self.files.get("xl/styles.xml") else {
+            return Ok(());
+        };
+        let mut custom: HashMap<u32, String> = HashMap::new();
+        let mut format_ids = Vec::new();
+        let mut in_cell_xfs = false;
+        walk(xml, |node| match node {
+            Node::Start(e, _) => match local(e).as_slice() {
+                b"numFmt" => {
+                    if let (Some(id), Some(code)) = (attr(e, b"numFmtId"), attr(e, b"formatCode")) {
+                        if let Ok(id) = id.parse() {
+                            custom.insert(id, code);
+                        }
+                    }
+                }
+                b"cellXfs" => in_cell_xfs = true,
+                b"xf" if in_cell_xfs => format_ids.push(
+                    attr(e, b"numFmtId")
+                        .and_then(|id| id.parse::<u32>().ok())
+                        .unwrap_or(0),
+                ),
+                _ => {}
+            },
+            Node::End(b"cellXfs") => in_cell_xfs = false,
+            _ => {}
+        })?;
+        self.formats = format_ids
+            .into_iter()
+            .map(|id| match custom.get(&id) {
+                Some(code) => classify_format(code),
+                None if DATE_FORMATS.contains(&id) => Format::Date,
+                None if id == 9 => Format::Percent(0),
+                None if id == 10 => Format::Percent(2),
+                None => Format::General,
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// シートを名前または 1 始まりの番号で探す（省略時は最初のシート）
+    fn sheet_path(&self, sheet: Option<&str>) -> Result<&str, String> {
+        let found = match sheet {
+            None => self.sheets.first(),
+            Some(name) => self.sheets.iter().find(|(n, _)| n == name).or_else(|| {
+                let index = name.parse::<usize>().ok()?;
+                self.sheets.get(index.checked_sub(1)?)
+            }),
+        };
+        found
+            .map(|(_, path)| path.as_str())
+            .ok_or_else(|| tr!("Sheet not found: {}", sheet.unwrap_or_default()))
+    }
+
+    /// シートのセルの表示値（行・列は 0 始まり）
+    fn cells(&self, sheet: Option<&str>) -> Result<HashMap<(usize, usize), String>, String> {
+        let path = self.sheet_path(sheet)?;
+        let xml = self
+            .files
+            .get(path)
+            .ok_or_else(|| tr!("Sheet data is missing: {path}"))?;
+        let mut cells = HashMap::new();
+        let mut cell: Option<Cell> = None;
+        let mut value = String::new();
+        let mut in_value = false;
+        // `r` 属性のないセルのための位置
+        let (mut row, mut column) = (0usize, 0usize);
+        walk(xml, |node| match node {
+            Node::Start(e, empty) => match local(e).as_slice() {
+                b"row" => {
+                    if let Some(r) = attr(e, b"r").and_then(|r| r.parse::<usize>().ok()) {
+                        row = r.saturating_sub(1);
+                    }
+                    column = 0;
+                }
+                b"c" => {
+                    let position = attr(e, b"r").and_then(|r| parse_cell(&r));
+                    let kind = attr(e, b"t").unwrap_or_else(|| "n".to_string());
+                    let style = attr(e, b"s").and_then(|s| s.parse().ok()).unwrap_or(0);
+                    value.clear();
+                    cell = Some(Cell {
+                        position,
+                        kind,
+                        style,
+                    });
+                }
+                b"v" | b"t" => in_value = !empty,
+                _ => {}
+            },
+            Node::Text(text) if in_value => value.push_str(&text),
+            Node::End(b"v") | Node::End(b"t") => in_value = false,
+            Node::End(b"c") => {
+                if let Some(Cell {
+                    position,
+                    kind,
+                    style,
+                }) = cell.take()
+                {
+                    let (r, c) = position.unwrap_or((row, column));
+                    column = c + 1;
+                    let text = self.display_value(&kind, &value, style);
+                    if !text.is_empty() {
+                        cells.insert((r, c), text);
+                    }
+                }
+            }
+            Node::End(b"row") => row += 1,
+            _ => {}
+        })?;
+        Ok(cells)
+    }
+
+    fn display_value(&self, kind: &str, value: &str, style: usize) -> String {
+        match kind {
+            "s" => value
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| self.shared_strings.get(i).cloned())
+                .unwrap_or_default(),
+            "b" if value.trim() == "1" => "TRUE".to_string(),
+            "b" => "FALSE".to_string(),
+            "n" => {
+                let Ok(number) = value.trim().parse::<f64>() else {
+                    return value.to_string();
+                };
+                match self.formats.get(style).copied().unwrap_or(Format::General) {
+                    Format::Date => format_date(number, self.date1904),
+                    Format::Percent(decimals) => format!("{:.*}%", decimals, number * 100.0),
+                    Format::General => format_number(number),
+                }
+            }
+            // str（数式の文字列結果）・inlineStr・e（エラー）
+            _ => value.to_string(),
+        }
+    }
+}
+
+/// ユーザー定義の表示形式が日付・パーセントか（引用符や `[...]` の中は見ない）
+fn classify_format(code: &str) -> Format {
+    let mut plain = String::new();
+    let mut chars = code.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                chars.by_ref().find(|&c| c == '"');
+            }
+            '[' => {
+                chars.by_ref().find(|&c| c == ']');
+            }
+            '\\' => {
+                chars.next();
+            }
+            _ => plain.push(c.to_ascii_lowercase()),
+        }
+    }
+    let plain = plain.split(';').next().unwrap_or_default();
+    if plain.contains(['y', 'd', 'h']) || (plain.contains('m') && !plain.contains('0')) {
+        Format::Date
+    } else if let Some(before) = plain.strip_suffix('%') {
+        Format::Percent(before.split_once('.').map_or(0, |(_, d)| d.len()))
+    } else {
+        Format::General
+    }
+}
+
+/// シリアル値を日付・時刻に変換
+fn format_date(serial: f64, date1904: bool) -> String {
+    let (base, days) = if date1904 {
+        (NaiveDate::from_ymd_opt(1904, 1, 1), serial)
+    } else if serial < 61.0 {
+        // 1900 年 2 月 29 日（実在しない日）より前
+        (NaiveDate::from_ymd_opt(1899, 12, 31), serial)
+    } else {
+        (NaiveDate::from_ymd_opt(1899, 12, 30), serial)
+    };
+    let Some(base) = base else {
+        return format_number(serial);
+    };
+    let seconds = (days.fract() * 86_400.0).round() as i64;
+    let datetime = base.and_hms_opt(0, 0, 0).unwrap()
+        + Duration::days(days.trunc() as i64)
+        + Duration::seconds(seconds);
+    match (days.trunc() == 0.0, seconds == 0) {
+        (true, _) if !date1904 => datetime.format("%H:%M:%S").to_string(),
+        (_, true) => datetime.format("%Y-%m-%d").to_string(),
+        _ => datetime.format("%Y-%m-%d %H:%M:%S").to_string(),
+    }
+}
+
+/// Excel と同じく有効数字 15 桁で表示
+fn format_number(number: f64) -> String {
+    if number == 0.0 || !number.is_finite() {
+        return number.to_string();
+    }
+    let magnitude = number.abs().log10().floor() as i32;
+    let decimals = (14 - magnitude).clamp(0, 15) as usize;
+    let text = format!("{number:.decimals$}");
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
+
+/// `B12` を 0 始まりの（行, 列）に変換
+fn parse_cell(reference: &str) -> Option<(usize, usize)> {
+    let reference = reference.replace('$', "");
+    let split = reference.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = reference.split_at(split);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let column = letters.chars().fold(0usize, |n, c| {
+        n * 26 + (c.to_ascii_uppercase() as usize - 'A' as usize + 1)
+    });
+    let row: usize = digits.parse().ok()?;
+    Some((row.checked_sub(1)?, column - 1))
+}
+
+/// ブックのシート名の一覧
+#[tauri::command]
+pub fn xlsx_sheets(path: String) -> Result<Vec<String>, String> {
+    let book = Workbook::open(Path::new(&path))?;
+    Ok(book.sheets.into_iter().map(|(name, _)| name).collect())
+}
+
+/// シート（名前または 1 始まりの番号）の範囲（`A1:D20` など。省略時は値のあるセル全体）を
+/// Markdown の表に変換（1 行目は見出し）
+#[tauri::command]
+pub fn import_xlsx_table(
+    path: String,
+    sheet: Option<String>,
+    range: Option<String>,
+) -> Result<String, String> {
+    let book = Workbook::open(Path::new(&path))?;
+    let cells = book.cells(sheet.as_deref())?;
+    let (start, end) = match range.as_deref().map(str::trim).filter(|r| !r.is_empty()) {
+        Some(range) => {
+            let (from, to) = range.split_once(':').unwrap_or((range, range));
+            match (parse_cell(from), parse_cell(to)) {
+                (Some(a), Some(b)) => ((a.0.min(b.0), a.1.min(b.1)), (a.0.max(b.0), a.1.max(b.1))),
+                _ => return Err(tr!("Invalid range: {range}")),
+            }
+        }
+        None => {
+            if cells.is_empty() {
+                return Err(tr!("The sheet is empty"));
+            }
+            let rows = cells.keys().map(|k| k.0);
+            let columns = cells.keys().map(|k| k.1);
+            (
+                (rows.clone().min().unwrap(), columns.clone().min().unwrap()),
+                (rows.max().unwrap(), columns.max().unwrap()),
+            )
+        }
+    };
+    let rows = (start.0..=end.0)
+        .map(|r| {
+            (start.1..=end.1)
+                .map(|c| {
+                    cells
+                        .get(&(r, c))
+                        .map(|v| table::escape_cell(v))
+                        .unwrap_or_default()
+                })
+                .collect()
+        })
+        .collect();
+    Ok(table::format_table(rows, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use super::{import_xlsx_table, Workbook};
+    use crate::zip::ZipWriter;
+
+    const WORKBOOK: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+<workbookPr/><sheets><sheet name="売上" sheetId="1" r:id="rId1"/><sheet name="Notes" sheetId="2" r:id="rId2"/></sheets>
+</workbook>"#;
+
+    const RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="/xl/worksheets/sheet2.xml"/>
+</Relationships>"#;
+
+    /// 3 つ目はふりがな付きのリッチテキスト
+    const SHARED_STRINGS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="3" uniqueCount="3">
+<si><t>Item</t></si><si><t>Price</t></si>
+<si><r><t>林</t></r><r><rPr><b/></rPr><t>檎</t></r><rPh sb="0" eb="2"><t>リンゴ</t></rPh></si>
+</sst>"#;
+
+    const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<numFmts count="1"><numFmt numFmtId="164" formatCode="0.0%"/></numFmts>
+<cellXfs count="3"><xf numFmtId="0"/><xf numFmtId="14"/><xf numFmtId="164"/></cellXfs>
+</styleSheet>"#;
+
+    const SHEET1: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>
+<row r="1"><c r="A1" t="s"><v>0</v></c><c r="B1" t="s"><v>1</v></c><c r="C1" t="inlineStr"><is><t>Date</t></is></c></row>
+<row r="2"><c r="A2" t="s"><v>2</v></c><c r="B2"><v>120</v></c><c r="C2" s="1"><v>45292</v></c></row>
+<row r="3"><c r="A3" t="inlineStr"><is><t>A &amp; B|C</t></is></c><c r="B3"><f>B2/240</f><v>0.5</v></c><c r="D3" s="2"><v>0.125</v></c></row>
+</sheetData></worksheet>"#;
+
+    /// `r` 属性のないセル
+    const SHEET2: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>
+<row><c t="inlineStr"><is><t>メモ</t></is></c><c t="b"><v>1</v></c></row>
+</sheetData></worksheet>"#;
+
+    fn workbook() -> Vec<u8> {
+        let mut writer = ZipWriter::new(Vec::new());
+        for (name, xml) in [
+            ("xl/workbook.xml", WORKBOOK),
+            ("xl/_rels/workbook.xml.rels", RELS),
+            ("xl/sharedStrings.xml", SHARED_STRINGS),
+            ("xl/styles.xml", STYLES),
+            ("xl/worksheets/sheet1.xml", SHEET1),
+            ("xl/worksheets/sheet2.xml", SHEET2),
+        ] {
+            writer.add(name, xml.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    fn cells(pairs: &[((usize, usize), &str)]) -> HashMap<(usize, usize), String> {
+        pairs.iter().map(|&(k, v)| (k, v.to_string())).collect()
+    }
+
+    #[test]
+    fn reads_shared_and_inline_strings() {
+        let book = Workbook::from_zip(&workbook()).unwrap();
+        let names: Vec<&str> = book.sheets.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["売上", "Notes"]);
+        let expected = cells(&[
+            ((0, 0), "Item"),
+            ((0, 1), "Price"),
+            ((0, 2), "Date"),
+            ((1, 0), "林檎"),
+            ((1, 1), "120"),
+            ((1, 2), "2024-01-01"),
+            ((2, 0), "A & B|C"),
+            ((2, 1), "0.5"),
+            ((2, 3), "12.5%"),
+        ]);
+        assert_eq!(book.cells(None).unwrap(), expected);
+        assert_eq!(book.cells(Some("売上")).unwrap(), expected);
+        let notes = cells(&[((0, 0), "メモ"), ((0, 1), "TRUE")]);
+        assert_eq!(book.cells(Some("Notes")).unwrap(), notes);
+        assert_eq!(book.cells(Some("2")).unwrap(), notes);
+        assert!(book.cells(Some("3")).is_err());
+    }
+
+    #[test]
+    fn rejects_non_workbooks() {
+        let mut writer = ZipWriter::new(Vec::new());
+        writer.add("word/document.xml", b"<document/>").unwrap();
+        let error = Workbook::from_zip(&writer.finish().unwrap()).err().unwrap();
+        assert!(error.contains("xl/workbook.xml is missing"));
+        assert!(Workbook::from_zip(b"not a zip").is_err());
+    }
+
+    #[test]
+    fn imports_range_as_table() {
+        let path = std::env::temp_dir().join(format!("mdvim-xlsx-{}.xlsx", std::process::id()));
+        fs::write(&path, workbook()).unwrap();
+        let table = import_xlsx_table(
+            path.to_string_lossy().into_owned(),
+            None,
+            Some("A1:B3".to_string()),
+        );
+        let _ = fs::remove_file(&path);
+        let table = table.unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("| Item") && lines[0].contains("Price"));
+        assert!(lines[2].contains("林檎") && lines[2].contains("120"));
+        assert!(lines[3].contains("A & B\\|C"));
+    }
+}
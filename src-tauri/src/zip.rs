@@ -0,0 +1,371 @@
+// ZIP アーカイブの読み書き（無圧縮と Deflate のみ、ZIP64 は非対応）
+//
+// zip クレートは依存に含まれていないため、flate2 と crc32fast で必要な分だけを実装している。
+// 暗号化・分割アーカイブ・ファイルの属性（実行権限など）には対応しない。
+
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use chrono::{Datelike, Local, Timelike};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+
+use crate::tr;
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+/// ファイル名が UTF-8 であることを示すフラグ
+const UTF8_FLAG: u16 = 1 << 11;
+const ENCRYPTED_FLAG: u16 = 1;
+/// 展開する 1 ファイルの大きさの上限
+const MAX_ENTRY_SIZE: usize = 512 << 20;
+/// 展開するファイルの合計の大きさの上限
+const MAX_TOTAL_SIZE: usize = 1 << 30;
+
+/// アーカイブ内のファイル
+pub struct Entry {
+    /// `/` 区切りのパス
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// 現在時刻を MS-DOS 形式（時刻, 日付）で
+fn dos_time() -> (u16, u16) {
+    let now = Local::now();
+    let time = (now.hour() << 11 | now.minute() << 5 | (now.second() / 2)) as u16;
+    let year = now.year().clamp(1980, 2107) as u32 - 1980;
+    let date = (year << 9 | now.month() << 5 | now.day()) as u16;
+    (time, date)
+}
+
+fn deflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .map_err(|e| tr!("Failed to compress data: {e}"))?;
+    encoder
+        .finish()
+        .map_err(|e| tr!("Failed to compress data: {e}"))
+}
+
+/// ZIP アーカイブの書き込み（圧縮して小さくならないものは無圧縮で格納）
+pub struct ZipWriter<W: Write> {
+    out: W,
+    offset: usize,
+    central: Vec<u8>,
+    count: usize,
+    time: u16,
+    date: u16,
+}
+
+impl<W: Write> ZipWriter<W> {
+    pub fn new(out: W) -> Self {
+        let (time, date) = dos_time();
+        Self {
+            out,
+            offset: 0,
+            central: Vec::new(),
+            count: 0,
+            time,
+            date,
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.out
+            .write_all(bytes)
+            .map_err(|e| tr!("Failed to write archive: {e}"))?;
+        self.offset += bytes.len();
+        Ok(())
+    }
+
+    /// ファイルを追加（`name` は `/` 区切り）
+    pub fn add(&mut self, name: &str, data: &[u8]) -> Result<(), String> {
+        if self.count == u16::MAX as usize {
+            return Err(tr!("Too many files for a zip archive"));
+        }
+        let crc = crc32fast::hash(data);
+        let compressed = deflate(data)?;
+        let (method, body) = if compressed.len() < data.len() {
+            (DEFLATED, compressed.as_slice())
+        } else {
+            (STORED, data)
+        };
+        if self.offset > u32::MAX as usize || data.len() > u32::MAX as usize {
+            return Err(tr!("Archive is too large"));
+        }
+        let name = name.as_bytes();
+
+        let mut fields = Vec::new();
+        put_u16(&mut fields, 20);
+        put_u16(&mut fields, UTF8_FLAG);
+        put_u16(&mut fields, method);
+        put_u16(&mut fields, self.time);
+        put_u16(&mut fields, self.date);
+        put_u32(&mut fields, crc);
+        put_u32(&mut fields, body.len() as u32);
+        put_u32(&mut fields, data.len() as u32);
+        put_u16(&mut fields, name.len() as u16);
+        put_u16(&mut fields, 0);
+
+        let central = &mut self.central;
+        put_u32(central, CENTRAL_HEADER);
+        put_u16(central, 20);
+        central.extend_from_slice(&fields);
+        put_u16(central, 0);
+        put_u16(central, 0);
+        put_u16(central, 0);
+        put_u32(central, 0);
+        put_u32(central, self.offset as u32);
+        central.extend_from_slice(name);
+        self.count += 1;
+
+        let mut header = Vec::with_capacity(4 + fields.len() + name.len());
+        put_u32(&mut header, LOCAL_HEADER);
+        header.extend_from_slice(&fields);
+        header.extend_from_slice(name);
+        self.write(&header)?;
+        self.write(body)
+    }
+
+    /// 中央ディレクトリを書き込んで完成させる
+    pub fn finish(mut self) -> Result<W, String> {
+        if self.offset > u32::MAX as usize {
+            return Err(tr!("Archive is too large"));
+        }
+        let central = std::mem::take(&mut self.central);
+        let mut end = Vec::new();
+        put_u32(&mut end, END_OF_CENTRAL_DIRECTORY);
+        put_u16(&mut end, 0);
+        put_u16(&mut end, 0);
+        put_u16(&mut end, self.count as u16);
+        put_u16(&mut end, self.count as u16);
+        put_u32(&mut end, central.len() as u32);
+        put_u32(&mut end, self.offset as u32);
+        put_u16(&mut end, 0);
+        self.write(&central)?;
+        self.write(&end)?;
+        self.out
+            .flush()
+            .map_err(|e| tr!("Failed to write archive: {e}"))?;
+        Ok(self.out)
+    }
+}
+
+/// ZIP アーカイブを展開（ディレクトリのエントリは除く）
+///
+/// 中央ディレクトリに書かれた大きさを超えて展開しない。大きさが合わないか上限を超えるエントリはエラーにする。
+pub fn read_zip(data: &[u8]) -> Result<Vec<Entry>, String> {
+    let invalid = || tr!("Invalid zip archive");
+    // 末尾のコメント（最大 64KiB）を飛ばして終端レコードを探す
+    let search_start = data.len().saturating_sub(22 + u16::MAX as usize);
+    let eocd = (search_start..data.len().saturating_sub(21))
+        .rev()
+        .find(|&i| get_u32(data, i) == Some(END_OF_CENTRAL_DIRECTORY))
+        .ok_or_else(invalid)?;
+    let count = get_u16(data, eocd + 10).ok_or_else(invalid)?;
+    let mut pos = get_u32(data, eocd + 16).ok_or_else(invalid)? as usize;
+
+    let mut entries = Vec::new();
+    let mut total = 0usize;
+    for _ in 0..count {
+        if get_u32(data, pos) != Some(CENTRAL_HEADER) {
+            return Err(invalid());
+        }
+        let field = |offset: usize| get_u16(data, pos + offset).ok_or_else(invalid);
+        let flags = field(8)?;
+        let method = field(10)?;
+        let crc = get_u32(data, pos + 16).ok_or_else(invalid)?;
+        let compressed_size = get_u32(data, pos + 20).ok_or_else(invalid)? as usize;
+        let size = get_u32(data, pos + 24).ok_or_else(invalid)? as usize;
+        let name_len = field(28)? as usize;
+        let extra_len = field(30)? as usize;
+        let comment_len = field(32)? as usize;
+        let local = get_u32(data, pos + 42).ok_or_else(invalid)? as usize;
+        let name = data
+            .get(pos + 46..pos + 46 + name_len)
+            .ok_or_else(invalid)?;
+        let name = String::from_utf8_lossy(name).replace('\\', "/");
+        pos += 46 + name_len + extra_len + comment_len;
+
+        if name.ends_with('/') {
+            continue;
+        }
+        if flags & ENCRYPTED_FLAG != 0 {
+            return Err(tr!("Encrypted entry is not supported: {name}"));
+        }
+        if get_u32(data, local) != Some(LOCAL_HEADER) {
+            return Err(invalid());
+        }
+        let local_name_len = get_u16(data, local + 26).ok_or_else(invalid)? as usize;
+        let local_extra_len = get_u16(data, local + 28).ok_or_else(invalid)? as usize;
+        let start = local + 30 + local_name_len + local_extra_len;
+        let body = data
+            .get(start..start + compressed_size)
+            .ok_or_else(invalid)?;
+
+        total = total.saturating_add(size);
+        if size > MAX_ENTRY_SIZE || total > MAX_TOTAL_SIZE {
+            return Err(tr!("Archive entry is too large: {}", name));
+        }
+        let content = match method {
+            STORED => body.to_vec(),
+            DEFLATED => {
+                let mut content = Vec::with_capacity(size.min(compressed_size * 4));
+                // 書かれた大きさより 1 バイトでも多く展開できれば壊れているか偽っている
+                DeflateDecoder::new(body)
+                    .take(size as u64 + 1)
+                    .read_to_end(&mut content)
+                    .map_err(|e| tr!("Failed to decompress {name}: {e}"))?;
+                content
+            }
+            _ => return Err(tr!("Unsupported compression method {method}: {name}")),
+        };
+        if content.len() != size {
+            return Err(tr!("Archive entry size mismatch: {}", name));
+        }
+        if crc32fast::hash(&content) != crc {
+            return Err(tr!("Checksum mismatch: {name}"));
+        }
+        entries.push(Entry {
+            name,
+            data: content,
+        });
+    }
+    Ok(entries)
+}
+
+/// アーカイブ内のパスが展開先の外を指していないか
+fn is_safe_entry(name: &str) -> bool {
+    !name.is_empty()
+        && Path::new(name)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// エントリを `dest_dir` に書き出す（`overwrite` が偽なら既存ファイルがあればエラー）
+pub fn extract(
+    entries: &[Entry],
+    dest_dir: &Path,
+    overwrite: bool,
+) -> Result<Vec<PathBuf>, String> {
+    if let Some(entry) = entries.iter().find(|e| !is_safe_entry(&e.name)) {
+        return Err(tr!("Unsafe path in archive: {}", entry.name));
+    }
+    if !overwrite {
+        if let Some(existing) = entries
+            .iter()
+            .map(|e| dest_dir.join(&e.name))
+            .find(|p| p.exists())
+        {
+            return Err(tr!("File already exists: {}", existing.display()));
+        }
+    }
+
+    let mut written = Vec::new();
+    for entry in entries {
+        let path = dest_dir.join(&entry.name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| tr!("Failed to create {}: {e}", parent.display()))?;
+        }
+        fs::write(&path, &entry.data)
+            .map_err(|e| tr!("Failed to write {}: {e}", path.display()))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn get_u16(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn get_u32(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract, get_u32, read_zip, Entry, ZipWriter, CENTRAL_HEADER};
+
+    fn archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Vec::new());
+        for (name, data) in files {
+            writer.add(name, data).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn round_trips_stored_and_deflated_entries() {
+        let long = "繰り返しの多い本文。".repeat(200);
+        let files: [(&str, &[u8]); 3] = [
+            ("a.txt", b"hello"),
+            ("日本語/メモ.md", long.as_bytes()),
+            ("empty", b""),
+        ];
+        let data = archive(&files);
+        // 圧縮した方が小さいものだけ Deflate にする
+        assert!(data.len() < long.len());
+        let entries = read_zip(&data).unwrap();
+        let read: Vec<(&str, &[u8])> = entries
+            .iter()
+            .map(|e| (e.name.as_str(), e.data.as_slice()))
+            .collect();
+        assert_eq!(read, files);
+    }
+
+    #[test]
+    fn rejects_corrupt_entries() {
+        // 無圧縮の本文はローカルヘッダー（30 バイト）とファイル名の後
+        let mut data = archive(&[("a.txt", b"hello")]);
+        data[30 + "a.txt".len()] ^= 0x01;
+        assert!(read_zip(&data)
+            .err()
+            .unwrap()
+            .contains("Checksum mismatch: a.txt"));
+
+        // 中央ディレクトリの CRC を書き換える
+        let text = "abc".repeat(100);
+        let mut data = archive(&[("b.txt", text.as_bytes())]);
+        let central = (0..data.len())
+            .find(|&i| get_u32(&data, i) == Some(CENTRAL_HEADER))
+            .unwrap();
+        data[central + 16] ^= 0xff;
+        assert!(read_zip(&data)
+            .err()
+            .unwrap()
+            .contains("Checksum mismatch: b.txt"));
+
+        let data = archive(&[("a.txt", b"hello")]);
+        assert!(read_zip(&data[..data.len() - 1]).is_err());
+        assert!(read_zip(b"PK").is_err());
+    }
+
+    #[test]
+    fn refuses_paths_outside_destination() {
+        let dest = std::env::temp_dir().join(format!("mdvim-zip-{}", std::process::id()));
+        for name in ["../escape.txt", "/abs.txt", "a/../../b.txt", ""] {
+            let entries = [Entry {
+                name: name.to_string(),
+                data: Vec::new(),
+            }];
+            let error = extract(&entries, &dest, true).err().unwrap();
+            assert!(error.contains("Unsafe path"), "{name}: {error}");
+        }
+        assert!(!dest.exists());
+    }
+}